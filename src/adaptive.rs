@@ -0,0 +1,150 @@
+use crate::ai::BlunderModel;
+
+/// Strength the adaptive AI starts at without a profile to go by,
+/// on a scale from 0 (weakest) to 1 (strongest).
+const INITIAL_LEVEL: f64 = 0.5;
+/// Search depths at the weakest and strongest level.
+const DEPTHS: (u32, u32) = (1, 6);
+/// Blunder model temperatures in centipawns at the weakest and strongest level.
+const TEMPERATURES: (f64, f64) = (250.0, 10.0);
+/// How far each move moves the level towards the player's accuracy.
+const ACCURACY_PULL: f64 = 0.3;
+/// AI advantage in centipawns that brings the level down by 0.1 after a move.
+const BALANCE_SCALE: f64 = 1000.0;
+/// Largest advantage counted when balancing, so a single blunder
+/// doesn't swing the level from one end to the other.
+const BALANCE_LIMIT: i32 = 300;
+
+/// Returns the chances in percent of winning from a position scored `cp`
+/// centipawns, counting draws as half a win.
+pub fn win_percent(cp: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * f64::from(cp)).exp()) - 1.0)
+}
+
+/// Returns the accuracy in percent of a move that took the position from
+/// `before` to `after` centipawns for the player who made it.
+///
+/// Moves that keep the winning chances score 100, and the accuracy drops
+/// quickly with the chances thrown away.
+///
+/// # Example
+///
+/// ```
+/// assert!(move_accuracy(50, 50) > 99.9);
+/// assert!(move_accuracy(50, -300) < 50.0);
+/// ```
+pub fn move_accuracy(before: i32, after: i32) -> f64 {
+    let lost = (win_percent(before) - win_percent(after)).max(0.0);
+    (103.1668 * (-0.04354 * lost).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+/// Strength of the adaptive AI, tuned after every move to the player's
+/// accuracy and to the score, so that the game stays close.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdaptiveSkill {
+    /// Current strength from 0 (weakest) to 1 (strongest).
+    level: f64,
+    /// Accuracy of each of the player's moves measured so far.
+    accuracies: Vec<f64>,
+    /// The AI's score after its last searched move.
+    last_score: Option<i32>,
+}
+
+impl AdaptiveSkill {
+    /// Creates an adaptive skill starting at the player's average
+    /// accuracy in earlier games, if known.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let skill = AdaptiveSkill::new(Some(80.0));
+    /// assert_eq!(skill.depth(), 5);
+    /// ```
+    pub fn new(accuracy: Option<f64>) -> Self {
+        Self {
+            level: accuracy.map_or(INITIAL_LEVEL, |accuracy| (accuracy / 100.0).clamp(0.0, 1.0)),
+            accuracies: Vec::new(),
+            last_score: None,
+        }
+    }
+
+    /// Returns the search depth at the current level.
+    pub fn depth(&self) -> u32 {
+        DEPTHS.0 + (self.level * f64::from(DEPTHS.1 - DEPTHS.0)).round() as u32
+    }
+
+    /// Returns how the AI picks among its best moves at the current level.
+    pub fn blunder_model(&self) -> BlunderModel {
+        BlunderModel {
+            candidates: 4,
+            temperature: TEMPERATURES.0 + self.level * (TEMPERATURES.1 - TEMPERATURES.0),
+        }
+    }
+
+    /// Returns the player's average accuracy in percent over the game,
+    /// `None` before any move was measured.
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.accuracies.is_empty() {
+            return None;
+        }
+        Some(self.accuracies.iter().sum::<f64>() / self.accuracies.len() as f64)
+    }
+
+    /// Takes in the AI's score of its latest searched move, from its
+    /// point of view, and adjusts the level.
+    ///
+    /// The change of score since the AI's previous move measures the
+    /// player's reply. The level then moves towards the player's accuracy,
+    /// and down while the AI is ahead or up while it's behind.
+    pub fn observe(&mut self, score: i32) {
+        if let Some(last) = self.last_score {
+            self.accuracies.push(move_accuracy(-last, -score));
+        }
+        self.last_score = Some(score);
+        if let Some(accuracy) = self.accuracy() {
+            self.level += ACCURACY_PULL * (accuracy / 100.0 - self.level);
+        }
+        let advantage = score.clamp(-BALANCE_LIMIT, BALANCE_LIMIT);
+        self.level = (self.level - f64::from(advantage) / BALANCE_SCALE).clamp(0.0, 1.0);
+    }
+
+    /// Forgets the AI's last score, e.g. after moves were taken back, so
+    /// the next one isn't compared with a position no longer on the board.
+    pub fn reset_score(&mut self) {
+        self.last_score = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_accuracy() {
+        assert_eq!(win_percent(0), 50.0);
+        assert!(win_percent(300) > 75.0);
+        assert!(move_accuracy(100, 100) > 99.9);
+        // Improving the position is as accurate as keeping it.
+        assert_eq!(move_accuracy(0, 200), move_accuracy(0, 0));
+        assert!(move_accuracy(0, -900) < 15.0);
+    }
+
+    #[test]
+    fn follows_the_player() {
+        let mut skill = AdaptiveSkill::new(None);
+        let start = skill.depth();
+        // The player keeps blundering and the AI pulls ahead.
+        for score in [0, 300, 600] {
+            skill.observe(score);
+        }
+        assert!(skill.accuracy().unwrap() < 50.0);
+        assert!(skill.depth() < start);
+        let weak = skill.blunder_model().temperature;
+
+        // The player now plays accurately and gets back into the game.
+        for score in [300, 0, -100, -100, -100] {
+            skill.observe(score);
+        }
+        assert!(skill.blunder_model().temperature < weak);
+    }
+}
@@ -0,0 +1,164 @@
+use chess::{Board, Color, Piece};
+
+/// Early result of an engine-vs-engine game given by [`Adjudication`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Adjudicated {
+    /// The given color is winning by far.
+    Win(Color),
+    Draw,
+}
+
+/// Rules ending engine-vs-engine games whose result is clear before
+/// they're over, to shorten matches.
+///
+/// Scores are the engines' own evaluations of the moves they play, so a
+/// rule only applies once both engines agree on the result.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Adjudication {
+    /// Centipawns a side has to be ahead by, by both engines' scores,
+    /// to be declared the winner; `None` to never adjudicate wins.
+    pub win_score: Option<i32>,
+    /// Number of moves each engine has to see the win for.
+    pub win_moves: u32,
+    /// Centipawns within which both engines' scores have to stay for a
+    /// draw; `None` to only adjudicate dead draws.
+    pub draw_score: Option<i32>,
+    /// Number of moves each engine has to see the draw for.
+    pub draw_moves: u32,
+}
+
+/// Returns whether neither side has enough material left to ever
+/// checkmate: bare kings, a single minor piece, or only bishops all on
+/// squares of the same color.
+pub fn insufficient_material(board: &Board) -> bool {
+    let heavy = board.pieces(Piece::Pawn) | board.pieces(Piece::Rook) | board.pieces(Piece::Queen);
+    if heavy.popcnt() > 0 {
+        return false;
+    }
+    let knights = board.pieces(Piece::Knight).popcnt();
+    let bishops = *board.pieces(Piece::Bishop);
+    let square_colors = |light: bool| {
+        bishops
+            .filter(|square| {
+                (square.get_rank().to_index() + square.get_file().to_index()) % 2
+                    == usize::from(light)
+            })
+            .count()
+    };
+    match (knights, bishops.popcnt()) {
+        (0, 0) | (1, 0) | (0, 1) => true,
+        (0, _) => square_colors(true) == 0 || square_colors(false) == 0,
+        _ => false,
+    }
+}
+
+impl Adjudication {
+    /// Changes the setting `key`: `win_score`, `win_moves`, `draw_score`
+    /// or `draw_moves`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming an unknown setting or invalid value.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid {} `{}`", key, value);
+        match key {
+            "win_score" => self.win_score = Some(value.parse().map_err(|_| invalid())?),
+            "win_moves" => self.win_moves = value.parse().map_err(|_| invalid())?,
+            "draw_score" => self.draw_score = Some(value.parse().map_err(|_| invalid())?),
+            "draw_moves" => self.draw_moves = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("unknown setting `{}`", key)),
+        }
+        Ok(())
+    }
+
+    /// Decides whether the game at `board` can be adjudicated.
+    ///
+    /// # Arguments
+    ///
+    /// * `board` - The current position.
+    /// * `scores` - The score of every move played so far, in centipawns
+    ///   from White's point of view, `None` for moves played without a
+    ///   search, such as book moves.
+    pub fn check(&self, board: &Board, scores: &[Option<i32>]) -> Option<Adjudicated> {
+        if insufficient_material(board) {
+            return Some(Adjudicated::Draw);
+        }
+        // Every score of the last `moves` moves of each side, if there
+        // are that many.
+        let last = |moves: u32| {
+            let plies = 2 * moves as usize;
+            (moves > 0 && scores.len() >= plies)
+                .then(|| {
+                    scores[scores.len() - plies..]
+                        .iter()
+                        .copied()
+                        .collect::<Option<Vec<_>>>()
+                })
+                .flatten()
+        };
+        if let (Some(threshold), Some(recent)) = (self.win_score, last(self.win_moves)) {
+            if recent.iter().all(|score| *score >= threshold) {
+                return Some(Adjudicated::Win(Color::White));
+            }
+            if recent.iter().all(|score| *score <= -threshold) {
+                return Some(Adjudicated::Win(Color::Black));
+            }
+        }
+        if let (Some(threshold), Some(recent)) = (self.draw_score, last(self.draw_moves)) {
+            if recent.iter().all(|score| score.abs() <= threshold) {
+                return Some(Adjudicated::Draw);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn finds_dead_draws() {
+        let board = |fen: &str| Board::from_str(fen).unwrap();
+        assert!(insufficient_material(&board(
+            "8/8/4k3/8/8/2K5/8/8 w - - 0 1"
+        )));
+        assert!(insufficient_material(&board(
+            "8/8/4k3/8/8/2K2N2/8/8 w - - 0 1"
+        )));
+        // Bishops on c1 and f8 are both on dark squares.
+        assert!(insufficient_material(&board(
+            "5b2/8/4k3/8/8/2K5/8/2B5 w - - 0 1"
+        )));
+        assert!(!insufficient_material(&board(
+            "4b3/8/4k3/8/8/2K5/8/2B5 w - - 0 1"
+        )));
+        assert!(!insufficient_material(&board(
+            "8/8/4k3/8/8/2K5/4P3/8 w - - 0 1"
+        )));
+        assert!(!insufficient_material(&Board::default()));
+    }
+
+    #[test]
+    fn adjudicates_agreed_scores() {
+        let board = Board::default();
+        let mut rules = Adjudication::default();
+        rules.set("win_score", "500").unwrap();
+        rules.set("win_moves", "2").unwrap();
+        rules.set("draw_score", "10").unwrap();
+        rules.set("draw_moves", "3").unwrap();
+        let winning = [Some(0), Some(-600), Some(-550), Some(-700), Some(-900)];
+        assert_eq!(
+            rules.check(&board, &winning),
+            Some(Adjudicated::Win(Color::Black))
+        );
+        assert_eq!(rules.check(&board, &winning[..4]), None);
+        let quiet = [Some(5); 6];
+        assert_eq!(rules.check(&board, &quiet), Some(Adjudicated::Draw));
+        // A book move in the window leaves the game to be played out.
+        let book = [None, Some(5), Some(5), Some(5), Some(5), Some(5)];
+        assert_eq!(rules.check(&board, &book), None);
+        assert!(rules.set("win_score", "lots").is_err());
+    }
+}
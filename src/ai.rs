@@ -1,69 +1,2131 @@
-use chess::{Board, BoardStatus, ChessMove, Color, MoveGen, Piece, ALL_SQUARES};
+use crate::incremental::IncrementalEval;
+use crate::info::{InfoSink, SearchInfo};
+use crate::mcts::Mcts;
+#[cfg(feature = "nnue")]
+use crate::nnue;
+use crate::pawns::{pawn_key, pawn_score, PawnTable};
+use crate::practical::Outlook;
+use crate::rng::random_range;
+use crate::strength::{StrengthLimit, MAX_ELO, MIN_ELO};
+use crate::timing::{Instability, TimeBudget};
+use crate::tree::SearchTree;
+use crate::tt::{Bound, Entry, SharedTable, TranspositionTable};
+use crate::variant::{hill_distance, Variant, VariantState};
+use chess::{
+    get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_rook_moves, BitBoard,
+    Board, ChessMove, Color, MoveGen, Piece, Square, EMPTY,
+};
+use log::{debug, trace};
+use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::MutexGuard;
+use std::time::{Duration, Instant};
 
+/// Score of a checkmate at the root. Mates further away score lower,
+/// so the search prefers the quickest mate (and the slowest loss).
+pub const MATE_SCORE: i32 = 100_000;
+/// Highest skill level, searching as deep as the hard difficulty.
+pub const MAX_SKILL: u32 = 20;
+/// Search depth at the highest skill level.
+pub const MAX_SKILL_DEPTH: u32 = 7;
+const INFINITY: i32 = MATE_SCORE + 1;
+
+/// Plies without a capture or pawn move after which the game is drawn.
+const FIFTY_MOVE_PLIES: u32 = 100;
+/// Futility margins in centipawns, indexed by remaining depth.
+const FUTILITY_MARGINS: [i32; 3] = [0, 200, 500];
+/// Least remaining depth at which a move is checked for being singular.
+const SINGULAR_MIN_DEPTH: u32 = 4;
+/// Centipawns per ply of depth every other move has to fall short of the
+/// best one's score by for it to count as singular.
+const SINGULAR_MARGIN: i32 = 2;
+/// Number of nodes searched between checks of the time limit.
+const TIME_CHECK_NODES: u64 = 1024;
+/// King of the Hill bonus in centipawns for each step the king is closer to the center.
+const HILL_STEP_BONUS: i32 = 50;
+/// Three-check bonus in centipawns for each check given.
+const CHECK_BONUS: i32 = 200;
+/// Mixed into the hash of a position once per check White has given,
+/// so Three-check positions differing only in checks don't collide.
+const WHITE_CHECK_KEY: u64 = 0x9e37_79b9_7f4a_7c15;
+/// Like [`WHITE_CHECK_KEY`], for the checks Black has given.
+const BLACK_CHECK_KEY: u64 = 0xc2b2_ae3d_27d4_eb4f;
+
+/// Tunable settings for [`search`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineOptions {
+    /// Skip a turn and prune if the position is still good enough for a cutoff.
+    pub null_move_pruning: bool,
+    /// Search late quiet moves at reduced depth, re-searching if they look promising.
+    pub late_move_reductions: bool,
+    /// Drop quiet moves near the horizon that cannot bring the score up to alpha.
+    pub futility_pruning: bool,
+    /// Search moves that give check one ply deeper.
+    pub check_extensions: bool,
+    /// Search the transposition table's best move one ply deeper when
+    /// every other move falls well short of it.
+    pub singular_extensions: bool,
+    /// Half-width in centipawns of the window searched around the previous
+    /// iteration's score, `None` to always search with a full window.
+    pub aspiration_window: Option<i32>,
+    /// Centipawns the engine gives up to avoid a draw. Positive values make it
+    /// play on in equal positions, negative values make it happy to draw.
+    pub contempt: i32,
+    /// Weights of the evaluation terms.
+    pub weights: EvalWeights,
+    /// Evaluate with the NNUE network, if one is loaded, instead of the
+    /// hand-written evaluation.
+    pub use_network: bool,
+    /// Hard limits on the nodes, time and memory of a search.
+    pub limits: SearchLimits,
+    /// Parameters of the null-move pruning and late move reductions.
+    pub params: SearchParams,
+    /// Transposition table the search keeps the positions it searched
+    /// in, shared with later searches, `None` to start every search cold.
+    pub table: Option<SharedTable>,
+    /// Skill level from 0 to [`MAX_SKILL`], setting how deep the engine
+    /// searches when no depth is given, see [`EngineOptions::skill_depth`].
+    pub skill: u32,
+    /// How many of the best moves are reported with their scores.
+    pub multi_pv: usize,
+    /// Time kept back from every move on a clock kept by someone else,
+    /// e.g. a GUI, for the delay until the move reaches it.
+    pub move_overhead: Duration,
+    /// The positions of the game before the one searched, so the search
+    /// sees draws by repetition and by the fifty-move rule.
+    pub history: PositionHistory,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self {
+            null_move_pruning: true,
+            late_move_reductions: true,
+            futility_pruning: true,
+            check_extensions: true,
+            singular_extensions: true,
+            aspiration_window: Some(150),
+            contempt: 0,
+            weights: EvalWeights::default(),
+            use_network: true,
+            limits: SearchLimits::default(),
+            params: SearchParams::default(),
+            table: None,
+            skill: MAX_SKILL,
+            multi_pv: 1,
+            move_overhead: Duration::from_millis(10),
+            history: PositionHistory::default(),
+        }
+    }
+}
+
+impl EngineOptions {
+    /// Returns the search depth at the skill level, from 1 at level 0
+    /// to [`MAX_SKILL_DEPTH`] at [`MAX_SKILL`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let options = EngineOptions { skill: 0, ..EngineOptions::default() };
+    /// assert_eq!(options.skill_depth(), 1);
+    /// ```
+    pub fn skill_depth(&self) -> u32 {
+        1 + self.skill.min(MAX_SKILL) * (MAX_SKILL_DEPTH - 1) / MAX_SKILL
+    }
+}
+
+/// Numeric parameters of the pruning and reductions of [`search`], as
+/// tuned by self-play, see [`Spsa`](crate::tune::Spsa).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchParams {
+    /// Extra depth reduction applied to the null-move search.
+    pub null_move_reduction: u32,
+    /// Number of moves searched at full depth before late move reductions apply.
+    pub lmr_full_depth_moves: usize,
+    /// Minimum remaining depth at which late move reductions apply, at
+    /// least 2.
+    pub lmr_min_depth: u32,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self {
+            null_move_reduction: 2,
+            lmr_full_depth_moves: 3,
+            lmr_min_depth: 3,
+        }
+    }
+}
+
+/// Weights of the terms of the evaluation. The default only counts
+/// material; the other terms shape the engine's playing style.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvalWeights {
+    /// Percentage of the material values counted.
+    pub material: i32,
+    /// Centipawns per legal move more than the opponent has.
+    pub mobility: i32,
+    /// Centipawns per piece in the center, counted twice in the four
+    /// central squares.
+    pub center: i32,
+    /// Centipawns per piece within two squares of the enemy king.
+    pub king_attack: i32,
+    /// Percentage of the piece-square table bonuses counted, see
+    /// [`piece_square_value`](crate::incremental::piece_square_value).
+    pub placement: i32,
+    /// Percentage of the pawn structure score counted, see
+    /// [`pawn_score`](crate::pawns::pawn_score).
+    pub pawn_structure: i32,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self {
+            material: 100,
+            mobility: 0,
+            center: 0,
+            king_attack: 0,
+            placement: 0,
+            pawn_structure: 0,
+        }
+    }
+}
+
+/// Hard limits on the resources a single search may use, so the engine
+/// can be embedded where every call has to finish within a budget.
+///
+/// When a limit trips, the search stops and returns the best move found
+/// so far: that of the deepest iteration completed, or of the moves the
+/// interrupted first iteration got through.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SearchLimits {
+    /// Most positions visited.
+    pub nodes: Option<u64>,
+    /// Longest time searched.
+    pub time: Option<Duration>,
+    /// Time a search on a clock aims for: no new iteration starts once
+    /// it's spent, stretched while the best move is unstable, see
+    /// [`TimeBudget`].
+    pub optimum_time: Option<Duration>,
+    /// Most memory in bytes held by the search: the evaluations along
+    /// the current line, the pawn and transposition tables and the
+    /// recorded search tree for alpha-beta, the tree grown by Monte Carlo
+    /// Tree Search.
+    pub memory: Option<usize>,
+}
+
+impl SearchLimits {
+    /// Returns the limits left once `nodes` positions were visited over
+    /// `elapsed`, `None` if the nodes or time ran out. Memory is given
+    /// back at the end of every search, so its limit stays the same.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let limits = SearchLimits { nodes: Some(1000), ..SearchLimits::default() };
+    /// assert_eq!(limits.remaining(400, Duration::ZERO).unwrap().nodes, Some(600));
+    /// assert_eq!(limits.remaining(1000, Duration::ZERO), None);
+    /// ```
+    pub fn remaining(&self, nodes: u64, elapsed: Duration) -> Option<Self> {
+        let nodes = match self.nodes {
+            Some(limit) if nodes >= limit => return None,
+            limit => limit.map(|limit| limit - nodes),
+        };
+        let time = match self.time {
+            Some(limit) if elapsed >= limit => return None,
+            limit => limit.map(|limit| limit - elapsed),
+        };
+        Some(Self {
+            nodes,
+            time,
+            optimum_time: self
+                .optimum_time
+                .map(|optimum| optimum.saturating_sub(elapsed)),
+            ..*self
+        })
+    }
+}
+
+/// The positions a game went through on its way to the one searched,
+/// see [`EngineOptions::history`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PositionHistory {
+    /// Hashes of the earlier positions, the latest last.
+    pub positions: Vec<u64>,
+    /// Plies since the last capture or pawn move, the count the
+    /// fifty-move rule goes by. Only the positions since then can repeat.
+    pub halfmove_clock: u32,
+}
+
+impl PositionHistory {
+    /// Adds `board` to the history as `mv` is played from it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut history = PositionHistory::default();
+    /// let mut board = Board::default();
+    /// for mv in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+    ///     let mv = ChessMove::from_str(mv)?;
+    ///     history.play(&board, mv);
+    ///     board = board.make_move_new(mv);
+    /// }
+    /// assert!(history.draws(&board));
+    /// ```
+    pub fn play(&mut self, board: &Board, mv: ChessMove) {
+        self.positions.push(board.get_hash());
+        self.halfmove_clock =
+            if board.piece_on(mv.get_source()) == Some(Piece::Pawn) || is_capture(board, mv) {
+                0
+            } else {
+                self.halfmove_clock + 1
+            };
+    }
+
+    /// Returns whether `board`, the position after the history, is
+    /// drawn: it occurred before, or the fifty-move rule applies and it
+    /// isn't checkmate. A single repetition counts, as a position that
+    /// came back once can come back again.
+    pub fn draws(&self, board: &Board) -> bool {
+        let hash = board.get_hash();
+        // Positions with the same side to move are two plies apart.
+        let repeats = self
+            .positions
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|position| *position == hash);
+        repeats
+            || (self.halfmove_clock >= FIFTY_MOVE_PLIES
+                && (*board.checkers() == EMPTY || MoveGen::new_legal(board).len() > 0))
+    }
+}
+
+/// Difficulty levels offered when playing against the AI.
+///
+/// The three weakest levels don't really play chess: they're meant for
+/// complete beginners and as fixed baselines to measure the engine
+/// against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    /// Level 0: plays a random legal move.
+    Random,
+    /// Level 0: takes the most valuable piece it can, otherwise plays
+    /// a random move.
+    Greedy,
+    /// Level 1: searches a single move ahead.
+    Beginner,
+    Easy,
+    Medium,
+    Hard,
+    /// Changes strength as the game goes to keep it close, see
+    /// [`AdaptiveSkill`](crate::adaptive::AdaptiveSkill).
+    Adaptive,
+    /// Plays at about the given Elo, see [`StrengthLimit`].
+    Elo(u32),
+}
+
+/// All difficulties, from the weakest to the strongest, followed by the
+/// adaptive level.
+pub const DIFFICULTIES: [Difficulty; 7] = [
+    Difficulty::Random,
+    Difficulty::Greedy,
+    Difficulty::Beginner,
+    Difficulty::Easy,
+    Difficulty::Medium,
+    Difficulty::Hard,
+    Difficulty::Adaptive,
+];
+
+impl Difficulty {
+    /// Returns the search depth used at this difficulty, 0 for the
+    /// levels that don't search.
+    pub fn depth(self) -> u32 {
+        match self {
+            Difficulty::Random | Difficulty::Greedy => 0,
+            Difficulty::Beginner => 1,
+            Difficulty::Easy => 3,
+            Difficulty::Medium | Difficulty::Adaptive => 4,
+            Difficulty::Hard => 7,
+            Difficulty::Elo(elo) => StrengthLimit::for_elo(elo).depth,
+        }
+    }
+
+    /// Returns how the AI picks its moves without searching at this
+    /// difficulty, `None` if it searches.
+    pub fn baseline(self) -> Option<Baseline> {
+        match self {
+            Difficulty::Random => Some(Baseline::Random),
+            Difficulty::Greedy => Some(Baseline::Greedy),
+            _ => None,
+        }
+    }
+
+    /// Returns how far the AI strays from its best move at this
+    /// difficulty, `None` if it always plays the move it finds best.
+    pub fn blunder_model(self) -> Option<BlunderModel> {
+        match self {
+            Difficulty::Easy => Some(BlunderModel {
+                candidates: 4,
+                temperature: 150.0,
+            }),
+            Difficulty::Medium | Difficulty::Adaptive => Some(BlunderModel {
+                candidates: 3,
+                temperature: 40.0,
+            }),
+            Difficulty::Elo(elo) => StrengthLimit::for_elo(elo).blunder_model,
+            _ => None,
+        }
+    }
+
+    /// Returns the most positions the AI searches for a move at this
+    /// difficulty, `None` if only the configured limits apply.
+    pub fn node_limit(self) -> Option<u64> {
+        match self {
+            Difficulty::Elo(elo) => Some(StrengthLimit::for_elo(elo).nodes),
+            _ => None,
+        }
+    }
+
+    /// Returns the engine options used at this difficulty.
+    ///
+    /// The easy engine settles for draws, while the hard one avoids them.
+    pub fn engine_options(self) -> EngineOptions {
+        let contempt = match self {
+            Difficulty::Random | Difficulty::Greedy | Difficulty::Beginner => 0,
+            Difficulty::Easy => -50,
+            Difficulty::Medium | Difficulty::Adaptive | Difficulty::Elo(_) => 0,
+            Difficulty::Hard => 25,
+        };
+        EngineOptions {
+            contempt,
+            ..EngineOptions::default()
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "random" | "level0" => Ok(Difficulty::Random),
+            "greedy" => Ok(Difficulty::Greedy),
+            "beginner" | "level1" => Ok(Difficulty::Beginner),
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            "adaptive" => Ok(Difficulty::Adaptive),
+            // Autosaves write the Elo as `Elo(1500)`.
+            s => s
+                .strip_prefix("elo(")
+                .and_then(|s| s.strip_suffix(')'))
+                .unwrap_or(s)
+                .parse()
+                .ok()
+                .filter(|elo| (MIN_ELO..=MAX_ELO).contains(elo))
+                .map(Difficulty::Elo)
+                .ok_or(()),
+        }
+    }
+}
+
+/// How the weakest levels pick a move without searching.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Baseline {
+    /// Any legal move, each as likely as the others.
+    Random,
+    /// The capture of the most valuable piece, or any move if nothing
+    /// can be taken.
+    Greedy,
+}
+
+/// Picks a move on `board` the way `baseline` does.
+///
+/// Ties between equally good moves are broken at random.
+///
+/// # Returns
+///
+/// The chosen move, `None` if the side to move has no legal moves.
+///
+/// # Example
+///
+/// ```
+/// let mv = baseline_move(&Board::default(), Baseline::Random);
+/// assert!(mv.is_some());
+/// ```
+pub fn baseline_move(board: &Board, baseline: Baseline) -> Option<ChessMove> {
+    let moves: Vec<ChessMove> = MoveGen::new_legal(board).collect();
+    let gain = |mv: &ChessMove| match baseline {
+        Baseline::Random => 0,
+        Baseline::Greedy => match board.piece_on(mv.get_dest()) {
+            Some(piece) => piece_value(piece),
+            // En passant is the only capture onto an empty square.
+            None if board.piece_on(mv.get_source()) == Some(Piece::Pawn)
+                && mv.get_source().get_file() != mv.get_dest().get_file() =>
+            {
+                piece_value(Piece::Pawn)
+            }
+            None => 0,
+        },
+    };
+    let best = moves.iter().map(gain).max()?;
+    let candidates: Vec<ChessMove> = moves.into_iter().filter(|mv| gain(mv) == best).collect();
+    Some(candidates[random_range(0..candidates.len())])
+}
+
+/// How the easier levels pick among their best moves, so that they
+/// misjudge close calls like a weaker player would instead of hanging
+/// pieces at random.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlunderModel {
+    /// Number of best moves considered.
+    pub candidates: usize,
+    /// Centipawns of score difference making a move e (about 2.7) times
+    /// less likely than a better one. Higher temperatures blunder more.
+    pub temperature: f64,
+}
+
+impl BlunderModel {
+    /// Picks one of the best scored moves, each with a weight of
+    /// `exp(-loss / temperature)`, where the loss is how much worse the
+    /// move scores than the best one.
+    ///
+    /// # Arguments
+    ///
+    /// * `scored` - Moves with their scores, best first, as returned by
+    ///   [`score_moves`].
+    ///
+    /// # Returns
+    ///
+    /// The chosen move and its score, `None` if there are no moves.
+    pub fn choose(&self, scored: &[(ChessMove, i32)]) -> Option<(ChessMove, i32)> {
+        let (_, best) = *scored.first()?;
+        let candidates = &scored[..scored.len().min(self.candidates.max(1))];
+        let weights: Vec<f64> = candidates
+            .iter()
+            .map(|(_, score)| {
+                let loss = f64::from(best.saturating_sub(*score));
+                (-loss / self.temperature.max(1.0)).exp()
+            })
+            .collect();
+        let mut pick = random_range(0.0..weights.iter().sum::<f64>());
+        for (candidate, weight) in candidates.iter().zip(weights) {
+            if pick < weight {
+                return Some(*candidate);
+            }
+            pick -= weight;
+        }
+        candidates.last().copied()
+    }
+}
+
+/// Scores every legal move on the board by searching the position after
+/// it to `depth - 1`.
+///
+/// The searches share the limits in `options`: once the nodes or time
+/// run out, the moves not scored yet are left out. The most promising
+/// moves are scored first, and at least one always is.
+///
+/// # Returns
+///
+/// The moves with their scores in centipawns from the point of view of
+/// the side to move, best first.
+///
+/// # Example
+///
+/// ```
+/// let scored = score_moves(&Board::default(), &VariantState::default(), 2, &EngineOptions::default());
+/// assert_eq!(scored.len(), 20);
+/// ```
+pub fn score_moves(
+    board: &Board,
+    state: &VariantState,
+    depth: u32,
+    options: &EngineOptions,
+) -> Vec<(ChessMove, i32)> {
+    let started = Instant::now();
+    let mut nodes = 0;
+    let mut scored = Vec::new();
+    for mv in ordered_moves(board) {
+        let limits = match options.limits.remaining(nodes, started.elapsed()) {
+            Some(limits) => limits,
+            None if scored.is_empty() => options.limits,
+            None => {
+                debug!("Search limits reached after scoring {:?}", scored);
+                break;
+            }
+        };
+        // The searches are run for the opponent, who sees draws the
+        // other way round.
+        let mut history = options.history.clone();
+        history.play(board, mv);
+        let new_board = board.make_move_new(mv);
+        let new_state = state.after_move(&new_board);
+        let score = if new_state.winner(&new_board) == Some(board.side_to_move()) {
+            MATE_SCORE - 1
+        } else if history.draws(&new_board) {
+            -options.contempt
+        } else {
+            let child_options = EngineOptions {
+                contempt: -options.contempt,
+                limits,
+                history,
+                ..options.clone()
+            };
+            let result = search_variant(
+                &new_board,
+                &new_state,
+                depth.saturating_sub(1),
+                &child_options,
+            );
+            nodes += result.nodes;
+            // Mates found by the opponent's search are a ply further
+            // from this position.
+            match -result.score {
+                score if score.abs() >= MATE_SCORE / 2 => score - score.signum(),
+                score => score,
+            }
+        };
+        scored.push((mv, score));
+    }
+    scored.sort_by_key(|(_, score)| Reverse(*score));
+    scored
+}
+
+/// Outcome of a [`search`] call.
+#[derive(Debug)]
+pub struct SearchResult {
+    /// Score in centipawns from the point of view of the side to move.
+    pub score: i32,
+    /// Best move found, `None` if the side to move has no legal moves.
+    pub best_move: Option<ChessMove>,
+    /// Number of positions visited during the search.
+    pub nodes: u64,
+}
+
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.best_move {
+            Some(mv) => write!(f, "{}", mv)?,
+            None => write!(f, "no move")?,
+        }
+        write!(f, " scoring {} after {} nodes", self.score, self.nodes)
+    }
+}
+
+/// Returns the material value of a piece in centipawns.
+pub fn piece_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 100,
+        Piece::Knight => 300,
+        Piece::Bishop => 300,
+        Piece::Rook => 500,
+        Piece::Queen => 900,
+        Piece::King => 2000,
+    }
+}
+
+/// Evaluates the board in centipawns from the point of view of `perspective`.
 pub fn evaluate(board: &Board, perspective: Color) -> i32 {
+    IncrementalEval::new(board).score(perspective, &EvalWeights::default())
+}
+
+/// Evaluates the board like [`evaluate`], with the material and the
+/// positional terms weighted by `weights`.
+pub fn evaluate_weighted(board: &Board, perspective: Color, weights: &EvalWeights) -> i32 {
+    IncrementalEval::new(board).score(perspective, weights)
+        + positional_terms(board, perspective, weights)
+        + pawn_term(pawn_score(board), perspective, weights)
+}
+
+/// Weighs the pawn structure score of a board, from White's point of
+/// view, for `perspective`.
+fn pawn_term(score: i32, perspective: Color, weights: &EvalWeights) -> i32 {
+    let score = score * weights.pawn_structure / 100;
+    match perspective {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+/// Returns the terms of the evaluation that depend on more than where
+/// each piece stands, weighted by `weights`: mobility, center control
+/// and pressure on the enemy king.
+fn positional_terms(board: &Board, perspective: Color, weights: &EvalWeights) -> i32 {
     let mut score = 0;
-    for square in ALL_SQUARES {
-        if let Some(piece) = board.piece_on(square) {
-            let piece_value = match piece {
-                Piece::Pawn => 1,
-                Piece::Knight => 3,
-                Piece::Bishop => 3,
-                Piece::Rook => 5,
-                Piece::Queen => 9,
-                Piece::King => 20,
-            };
-            if board.color_on(square) == Some(perspective) {
-                score += piece_value;
+    if weights.mobility != 0 {
+        let mobility = (
+            legal_move_count(board, perspective),
+            legal_move_count(board, !perspective),
+        );
+        if let (Some(ours), Some(theirs)) = mobility {
+            score += weights.mobility * (ours as i32 - theirs as i32);
+        }
+    }
+    if weights.center != 0 {
+        for square in *board.combined() & !board.pieces(Piece::King) {
+            let sign = if board.color_on(square) == Some(perspective) {
+                1
             } else {
-                score -= piece_value;
+                -1
+            };
+            let (file, rank) = (square.get_file().to_index(), square.get_rank().to_index());
+            if (2..6).contains(&file) && (2..6).contains(&rank) {
+                let central = (3..5).contains(&file) && (3..5).contains(&rank);
+                score += sign * weights.center * if central { 2 } else { 1 };
             }
         }
     }
+    if weights.king_attack != 0 {
+        let pressure =
+            king_attackers(board, !perspective) as i32 - king_attackers(board, perspective) as i32;
+        score += weights.king_attack * pressure;
+    }
     score
 }
 
-pub fn minimax(
+/// Returns the number of legal moves `color` has on `board`, as if it
+/// were its turn, `None` for the side not to move while the side to move
+/// is in check, as it can't be asked for its moves then.
+pub fn legal_move_count(board: &Board, color: Color) -> Option<usize> {
+    if board.side_to_move() == color {
+        return Some(MoveGen::new_legal(board).len());
+    }
+    board
+        .null_move()
+        .map(|passed| MoveGen::new_legal(&passed).len())
+}
+
+/// Returns the number of enemy pieces, kings aside, within two squares
+/// of the king of `color` on `board`.
+pub fn king_attackers(board: &Board, color: Color) -> u32 {
+    let king = board.king_square(color);
+    (*board.color_combined(!color) & !board.pieces(Piece::King))
+        .into_iter()
+        .filter(|square| {
+            let file = square.get_file().to_index();
+            let rank = square.get_rank().to_index();
+            file.abs_diff(king.get_file().to_index())
+                .max(rank.abs_diff(king.get_rank().to_index()))
+                <= 2
+        })
+        .count() as u32
+}
+
+/// Adds the variant-specific terms to the evaluation of the board,
+/// weighted by `weights`.
+///
+/// In King of the Hill, kings closer to the center are worth more; in
+/// Three-check, every check already given brings its side closer to a win.
+pub fn evaluate_variant(
     board: &Board,
-    depth: u32,
-    maximizing: bool,
+    state: &VariantState,
     perspective: Color,
-    mut alpha: i32,
-    mut beta: i32,
-) -> (i32, Option<ChessMove>) {
-    if depth == 0 || board.status() != BoardStatus::Ongoing {
-        return (evaluate(board, perspective), None);
+    weights: &EvalWeights,
+) -> i32 {
+    evaluate_weighted(board, perspective, weights) + variant_bonus(board, state, perspective)
+}
+
+/// Returns the variant-specific terms of the evaluation in centipawns
+/// from the point of view of `perspective`.
+fn variant_bonus(board: &Board, state: &VariantState, perspective: Color) -> i32 {
+    match state.variant {
+        Variant::Standard => 0,
+        Variant::KingOfTheHill => {
+            let ours = hill_distance(board, perspective) as i32;
+            let theirs = hill_distance(board, !perspective) as i32;
+            HILL_STEP_BONUS * (theirs - ours)
+        }
+        Variant::ThreeCheck => {
+            let ours = state.checks(perspective) as i32;
+            let theirs = state.checks(!perspective) as i32;
+            CHECK_BONUS * (ours - theirs)
+        }
+    }
+}
+
+/// A search algorithm the AI can play with, so different kinds of
+/// engines can be swapped in and compared.
+pub trait Engine {
+    /// Searches `board` for the best move, following the variant rules
+    /// and rule state in `state`.
+    fn search(&self, board: &Board, state: &VariantState) -> SearchResult;
+}
+
+/// The alpha-beta search of [`search_variant`] to a fixed depth.
+pub struct AlphaBeta {
+    pub depth: u32,
+    pub options: EngineOptions,
+}
+
+impl Engine for AlphaBeta {
+    fn search(&self, board: &Board, state: &VariantState) -> SearchResult {
+        search_variant(board, state, self.depth, &self.options)
     }
+}
+
+/// Number of Monte Carlo Tree Search iterations run for each ply of
+/// search depth the difficulty asks for.
+const MCTS_ITERATIONS_PER_PLY: u32 = 2000;
+
+/// The kinds of engines available, chosen with the `engine` setting.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EngineKind {
+    /// Alpha-beta search, see [`search`].
+    #[default]
+    AlphaBeta,
+    /// Monte Carlo Tree Search, see [`Mcts`].
+    Mcts,
+}
+
+impl FromStr for EngineKind {
+    type Err = ();
 
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "alphabeta" | "alpha-beta" => Ok(EngineKind::AlphaBeta),
+            "mcts" => Ok(EngineKind::Mcts),
+            _ => Err(()),
+        }
+    }
+}
+
+impl EngineKind {
+    /// Creates an engine of this kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - How deep the engine searches. Monte Carlo Tree Search
+    ///   runs a number of iterations proportional to it instead.
+    /// * `options` - Settings of the search and evaluation.
+    pub fn engine(self, depth: u32, options: EngineOptions) -> Box<dyn Engine> {
+        match self {
+            EngineKind::AlphaBeta => Box::new(AlphaBeta { depth, options }),
+            EngineKind::Mcts => Box::new(Mcts {
+                iterations: depth * MCTS_ITERATIONS_PER_PLY,
+                options,
+            }),
+        }
+    }
+}
+
+/// Searches the position to the given depth and returns the best move found.
+///
+/// The search is a negamax alpha-beta search with MVV-LVA move ordering,
+/// run with iterative deepening: every depth from 1 up to `depth` is searched
+/// in turn, trying the previous iteration's best move first. Once a score is
+/// known, the next iteration searches an aspiration window around it and
+/// widens the window if the result falls outside. Checks and singular
+/// moves are searched a ply deeper, as long as the line is under twice
+/// the iteration's depth. Null-move pruning, late move reductions,
+/// futility pruning, the extensions and the aspiration window can be
+/// configured through `options`, which also set the [`SearchLimits`] the
+/// search stops at.
+///
+/// # Example
+///
+/// ```
+/// let result = search(&Board::default(), 4, &EngineOptions::default());
+/// assert!(result.best_move.is_some());
+/// ```
+pub fn search(board: &Board, depth: u32, options: &EngineOptions) -> SearchResult {
+    search_variant(board, &VariantState::default(), depth, options)
+}
+
+/// Searches the position like [`search`], following the rules of a chess variant.
+///
+/// `state` holds the variant and the rule state at the root, such as the
+/// number of checks already given in a Three-check game.
+pub fn search_variant(
+    board: &Board,
+    state: &VariantState,
+    depth: u32,
+    options: &EngineOptions,
+) -> SearchResult {
+    run_search(board, state, depth, options, None, None).0
+}
+
+/// Searches the position like [`search`], reporting the progress after
+/// every iteration to `sink`.
+///
+/// # Example
+///
+/// ```
+/// let mut lines = Vec::new();
+/// search_reporting(&Board::default(), 3, &EngineOptions::default(), &mut lines);
+/// assert!(lines[0].starts_with("info depth 1"));
+/// ```
+pub fn search_reporting(
+    board: &Board,
+    depth: u32,
+    options: &EngineOptions,
+    sink: &mut dyn InfoSink,
+) -> SearchResult {
+    run_search(
+        board,
+        &VariantState::default(),
+        depth,
+        options,
+        None,
+        Some(sink),
+    )
+    .0
+}
+
+/// Searches the position like [`search_reporting`], recording the first
+/// `plies` plies of the search tree for debugging.
+///
+/// # Example
+///
+/// ```
+/// let (result, tree) =
+///     search_with_tree(&Board::default(), 3, &EngineOptions::default(), 2, &mut Vec::new());
+/// println!("{}", tree.to_dot());
+/// ```
+pub fn search_with_tree(
+    board: &Board,
+    depth: u32,
+    options: &EngineOptions,
+    plies: u32,
+    sink: &mut dyn InfoSink,
+) -> (SearchResult, SearchTree) {
+    let tree = SearchTree::new(plies);
+    let (result, tree) = run_search(
+        board,
+        &VariantState::default(),
+        depth,
+        options,
+        Some(tree),
+        Some(sink),
+    );
+    (result, tree.unwrap())
+}
+
+fn run_search(
+    board: &Board,
+    state: &VariantState,
+    depth: u32,
+    options: &EngineOptions,
+    tree: Option<SearchTree>,
+    mut sink: Option<&mut dyn InfoSink>,
+) -> (SearchResult, Option<SearchTree>) {
+    let mut searcher = Searcher {
+        options,
+        root_color: board.side_to_move(),
+        nodes: 0,
+        seldepth: 0,
+        root_depth: 0,
+        excluded: None,
+        started: Instant::now(),
+        stopped: false,
+        #[cfg(feature = "nnue")]
+        accumulators: nnue::network()
+            .filter(|_| options.use_network)
+            .map(|network| network.refresh(board))
+            .into_iter()
+            .collect(),
+        evals: vec![IncrementalEval::new(board)],
+        pawns: (options.weights.pawn_structure != 0).then(PawnTable::default),
+        tree,
+        table: options.table.as_ref().map(SharedTable::lock),
+        history: options.history.clone(),
+        clocks: Vec::new(),
+    };
+    let mut score = 0;
     let mut best_move = None;
-    let mut best_eval = if maximizing { i32::MIN } else { i32::MAX };
-
-    for m in MoveGen::new_legal(board) {
-        let new_board = board.make_move_new(m);
-        let (eval, _) = minimax(
-            &new_board,
-            depth - 1,
-            !maximizing,
-            !perspective,
-            alpha,
-            beta,
+    let budget = options.limits.optimum_time.map(|optimum| TimeBudget {
+        optimum,
+        maximum: options.limits.time.unwrap_or(Duration::MAX),
+    });
+    let mut instability = Instability::default();
+
+    for iteration in 1..=depth.max(1) {
+        if iteration > 1 && searcher.out_of_budget() {
+            break;
+        }
+        // Easily won positions are played faster.
+        let outlook = Outlook::from_score(score);
+        if iteration > 1
+            && budget.is_some_and(|budget| {
+                searcher.started.elapsed() >= budget.soft_limit(&instability, outlook)
+            })
+        {
+            debug!("Time budget spent after depth {}", iteration - 1);
+            break;
+        }
+        let mut delta = match options.aspiration_window {
+            Some(window) if iteration > 1 => window,
+            _ => INFINITY,
+        };
+        let mut alpha = (score - delta).max(-INFINITY);
+        let mut beta = (score + delta).min(INFINITY);
+
+        loop {
+            let (iteration_score, iteration_move) =
+                searcher.root(board, state, iteration, alpha, beta, best_move);
+            trace!(
+                "Depth {} in ({}, {}): {} scoring {}",
+                iteration,
+                alpha,
+                beta,
+                iteration_move.map_or("no move".to_string(), |mv| mv.to_string()),
+                iteration_score
+            );
+            if searcher.stopped {
+                // The interrupted iteration only counts if no earlier one
+                // found a move.
+                if best_move.is_none() {
+                    score = iteration_score;
+                    best_move = iteration_move;
+                }
+                break;
+            }
+            if iteration_score <= alpha && alpha > -INFINITY {
+                // Fail low: the best move is unreliable, re-search with a lower bound.
+                delta = delta.saturating_mul(2);
+                alpha = (iteration_score - delta).max(-INFINITY);
+            } else if iteration_score >= beta && beta < INFINITY {
+                // Fail high: re-search with a higher upper bound.
+                delta = delta.saturating_mul(2);
+                beta = (iteration_score + delta).min(INFINITY);
+            } else {
+                if iteration > 1 {
+                    instability.observe(iteration_move != best_move, iteration_score - score);
+                }
+                score = iteration_score;
+                best_move = iteration_move;
+                break;
+            }
+        }
+        if searcher.stopped {
+            debug!("Search limits reached at depth {}", iteration);
+            break;
+        }
+        // Positions without moves have no progress to show.
+        if let (Some(sink), Some(mv)) = (sink.as_deref_mut(), best_move) {
+            sink.report(&SearchInfo {
+                depth: iteration,
+                seldepth: searcher.seldepth.max(iteration),
+                score,
+                nodes: searcher.nodes,
+                time: searcher.started.elapsed(),
+                pv: searcher.principal_variation(board, state, mv, iteration),
+            });
+        }
+    }
+
+    let result = SearchResult {
+        score,
+        best_move,
+        nodes: searcher.nodes,
+    };
+    debug!("Searched {} to depth {}: {}", board, depth, result);
+    if let Some(pawns) = &searcher.pawns {
+        debug!("Pawn table: {} hits, {} misses", pawns.hits, pawns.misses);
+    }
+    (result, searcher.tree)
+}
+
+struct Searcher<'a> {
+    options: &'a EngineOptions,
+    root_color: Color,
+    nodes: u64,
+    /// Deepest ply reached so far.
+    seldepth: u32,
+    /// Depth of the iteration being searched, bounding the extensions.
+    root_depth: u32,
+    /// Move the next call of [`Searcher::negamax`] leaves out, to tell
+    /// whether it's singular.
+    excluded: Option<ChessMove>,
+    started: Instant,
+    /// Set once one of the search limits trips; the search then
+    /// unwinds without trusting the scores it returns.
+    stopped: bool,
+    /// Accumulators of the NNUE network for the positions along the
+    /// current line, empty if no network is loaded.
+    #[cfg(feature = "nnue")]
+    accumulators: Vec<nnue::Accumulator>,
+    /// Material and piece-square terms for the positions along the
+    /// current line.
+    evals: Vec<IncrementalEval>,
+    /// Pawn structure scores, kept if the weights count them.
+    pawns: Option<PawnTable>,
+    /// Records the top of the search tree, if asked for.
+    tree: Option<SearchTree>,
+    /// The transposition table of [`EngineOptions::table`], held for the
+    /// whole search.
+    table: Option<MutexGuard<'a, TranspositionTable>>,
+    /// The positions before the current one: the game's, then those
+    /// along the current line.
+    history: PositionHistory,
+    /// The halfmove clocks of the positions along the current line.
+    clocks: Vec<u32>,
+}
+
+impl Searcher<'_> {
+    /// Evaluates the board with the NNUE network if one is loaded, and
+    /// the hand-written evaluation otherwise.
+    #[cfg(feature = "nnue")]
+    fn evaluate(&mut self, board: &Board, state: &VariantState) -> i32 {
+        let perspective = board.side_to_move();
+        match (nnue::network(), self.accumulators.last()) {
+            (Some(network), Some(accumulator)) => {
+                network.evaluate(accumulator, perspective)
+                    + variant_bonus(board, state, perspective)
+            }
+            _ => self.evaluate_handwritten(board, state),
+        }
+    }
+
+    #[cfg(not(feature = "nnue"))]
+    fn evaluate(&mut self, board: &Board, state: &VariantState) -> i32 {
+        self.evaluate_handwritten(board, state)
+    }
+
+    /// Evaluates the board like [`evaluate_variant`], taking the material
+    /// and piece-square terms kept up to date by [`Searcher::make`] and
+    /// the pawn structure score from the pawn table.
+    fn evaluate_handwritten(&mut self, board: &Board, state: &VariantState) -> i32 {
+        let perspective = board.side_to_move();
+        let weights = &self.options.weights;
+        let eval = self.evals.last().unwrap();
+        debug_assert_eq!(
+            (
+                eval.score(perspective, &EvalWeights::default()),
+                eval.pawn_key()
+            ),
+            (evaluate(board, perspective), pawn_key(board)),
+            "incremental material out of step in {}",
+            board
+        );
+        let pawns = match &mut self.pawns {
+            Some(table) => pawn_term(table.score(board, eval.pawn_key()), perspective, weights),
+            None => 0,
+        };
+        eval.score(perspective, weights)
+            + positional_terms(board, perspective, weights)
+            + pawns
+            + variant_bonus(board, state, perspective)
+    }
+
+    /// Checks the search limits, stopping the search for good once one
+    /// of them trips. The clock is only read every [`TIME_CHECK_NODES`]
+    /// nodes.
+    fn out_of_budget(&mut self) -> bool {
+        let limits = &self.options.limits;
+        self.stopped = self.stopped
+            || limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || (self.nodes.is_multiple_of(TIME_CHECK_NODES)
+                && limits
+                    .time
+                    .is_some_and(|time| self.started.elapsed() >= time))
+            || limits.memory.is_some_and(|memory| self.memory() > memory);
+        self.stopped
+    }
+
+    /// Returns roughly how many bytes the search holds.
+    fn memory(&self) -> usize {
+        let line = self.evals.len() * size_of::<IncrementalEval>();
+        #[cfg(feature = "nnue")]
+        let line = line
+            + self
+                .accumulators
+                .iter()
+                .map(nnue::Accumulator::memory)
+                .sum::<usize>();
+        line + self.pawns.as_ref().map_or(0, PawnTable::memory)
+            + self.tree.as_ref().map_or(0, SearchTree::memory)
+            + self.table.as_deref().map_or(0, TranspositionTable::memory)
+    }
+
+    /// Notes that `mv` is being searched from `board` to `depth`, updating
+    /// the incremental evaluation, the NNUE accumulators, the position
+    /// history and the recorded search tree. Every call is followed by
+    /// [`Searcher::unmake`].
+    fn make(&mut self, board: &Board, mv: ChessMove, depth: u32) {
+        if let Some(tree) = &mut self.tree {
+            tree.enter(mv, depth);
+        }
+        self.clocks.push(self.history.halfmove_clock);
+        self.history.play(board, mv);
+        let eval = self.evals.last().unwrap().after_move(board, mv);
+        self.evals.push(eval);
+        self.push_accumulator(board, mv);
+    }
+
+    /// Notes that the search of the last move passed to [`Searcher::make`]
+    /// is over with `score` for the side that played it.
+    fn unmake(&mut self, score: i32) {
+        if let Some(tree) = &mut self.tree {
+            tree.leave(score);
+        }
+        self.history.positions.pop();
+        self.history.halfmove_clock = self.clocks.pop().unwrap();
+        self.evals.pop();
+        self.pop_accumulator();
+    }
+
+    #[cfg(feature = "nnue")]
+    fn push_accumulator(&mut self, board: &Board, mv: ChessMove) {
+        if let (Some(network), Some(accumulator)) = (nnue::network(), self.accumulators.last()) {
+            let next = network.update(accumulator, board, mv);
+            self.accumulators.push(next);
+        }
+    }
+
+    #[cfg(not(feature = "nnue"))]
+    fn push_accumulator(&mut self, _board: &Board, _mv: ChessMove) {}
+
+    #[cfg(feature = "nnue")]
+    fn pop_accumulator(&mut self) {
+        self.accumulators.pop();
+    }
+
+    #[cfg(not(feature = "nnue"))]
+    fn pop_accumulator(&mut self) {}
+
+    /// Searches the root position within the `(alpha, beta)` window,
+    /// trying `first_move` before the others.
+    fn root(
+        &mut self,
+        board: &Board,
+        state: &VariantState,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        first_move: Option<ChessMove>,
+    ) -> (i32, Option<ChessMove>) {
+        self.nodes += 1;
+        self.root_depth = depth;
+        if let Some(tree) = &mut self.tree {
+            tree.start(depth);
+        }
+        let mut moves = ordered_moves(board);
+        if moves.is_empty() {
+            let score = self.terminal_score(board, 0);
+            if let Some(tree) = &mut self.tree {
+                tree.finish(score);
+            }
+            return (score, None);
+        }
+        let key = table_key(board, state);
+        let first_move =
+            first_move.or_else(|| self.probe(key, 0).and_then(|entry| entry.best_move));
+        move_first(&mut moves, first_move);
+        let most_promising = moves[0];
+        let original_alpha = alpha;
+
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for mv in moves {
+            let new_board = board.make_move_new(mv);
+            let new_state = state.after_move(&new_board);
+            self.make(board, mv, depth - 1);
+            let score = -self.negamax(&new_board, &new_state, depth - 1, -beta, -alpha, 1, true);
+            self.unmake(score);
+            if self.stopped {
+                break;
+            }
+            if best_move.is_none() || score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        if best_move.is_none() {
+            // Stopped before a single move was searched to the end.
+            best_score = self.evaluate(board, state);
+            best_move = Some(most_promising);
+        }
+        self.store(
+            Entry {
+                key,
+                depth,
+                score: best_score,
+                bound: bound(best_score, original_alpha, beta),
+                best_move,
+            },
+            0,
         );
-        if maximizing {
-            if eval > best_eval {
-                best_eval = eval;
-                best_move = Some(m);
+        if let Some(tree) = &mut self.tree {
+            tree.finish(best_score);
+        }
+        (best_score, best_move)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        board: &Board,
+        state: &VariantState,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        ply: u32,
+        allow_null: bool,
+    ) -> i32 {
+        let excluded = self.excluded.take();
+        if self.out_of_budget() {
+            return 0;
+        }
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if state.winner(board).is_some() {
+            // Only the player who just moved can win by a variant rule.
+            return -MATE_SCORE + ply as i32;
+        }
+        if self.history.draws(board) {
+            return self.draw_score(board);
+        }
+        if MoveGen::new_legal(board).len() == 0 {
+            return self.terminal_score(board, ply);
+        }
+
+        let key = table_key(board, state);
+        // The entry's score counts the excluded move too.
+        let entry = self.probe(key, ply);
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth && excluded.is_none()) {
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if usable {
+                return entry.score;
             }
-            alpha = alpha.max(eval);
-        } else {
-            if eval < best_eval {
-                best_eval = eval;
-                best_move = Some(m);
+        }
+
+        let static_eval = self.evaluate(board, state);
+        if depth == 0 {
+            return static_eval;
+        }
+        let mut moves = ordered_moves(board);
+        move_first(&mut moves, entry.and_then(|entry| entry.best_move));
+        let original_alpha = alpha;
+        let in_check = *board.checkers() != EMPTY;
+
+        if self.options.null_move_pruning
+            && allow_null
+            && !in_check
+            && depth > self.options.params.null_move_reduction
+            && static_eval >= beta
+            && has_non_pawn_material(board, board.side_to_move())
+        {
+            if let Some(null_board) = board.null_move() {
+                let reduced = depth - 1 - self.options.params.null_move_reduction;
+                let score = -self.negamax(
+                    &null_board,
+                    state,
+                    reduced,
+                    -beta,
+                    -beta + 1,
+                    ply + 1,
+                    false,
+                );
+                // Verify the cutoff with a reduced search of the real position,
+                // so zugzwang positions are not pruned away.
+                if score >= beta
+                    && self.negamax(board, state, reduced, beta - 1, beta, ply, false) >= beta
+                {
+                    return beta;
+                }
+            }
+        }
+
+        let futile = self.options.futility_pruning
+            && !in_check
+            && (depth as usize) < FUTILITY_MARGINS.len()
+            && alpha.abs() < MATE_SCORE / 2
+            && static_eval + FUTILITY_MARGINS[depth as usize] <= alpha;
+
+        // Lines are only extended up to twice the iteration's depth, so
+        // a run of checks can't make the search explode.
+        let extend = ply < 2 * self.root_depth;
+        let singular = match entry {
+            Some(entry) if extend && excluded.is_none() => {
+                self.singular_move(board, state, depth, ply, entry)
+            }
+            _ => None,
+        };
+
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for (i, mv) in moves.into_iter().enumerate() {
+            if Some(mv) == excluded {
+                continue;
+            }
+            let new_board = board.make_move_new(mv);
+            let new_state = state.after_move(&new_board);
+            let quiet = !is_capture(board, mv) && mv.get_promotion().is_none();
+            let gives_check = *new_board.checkers() != EMPTY;
+
+            // Losing captures can't bring a futile position up to alpha either.
+            if futile && !gives_check && (quiet || see(board, mv) < 0) {
+                continue;
+            }
+
+            let extended =
+                extend && ((self.options.check_extensions && gives_check) || singular == Some(mv));
+            let new_depth = depth - 1 + u32::from(extended);
+            self.make(board, mv, new_depth);
+            let reduce = self.options.late_move_reductions
+                && i >= self.options.params.lmr_full_depth_moves
+                && depth >= self.options.params.lmr_min_depth.max(2)
+                && !in_check
+                && quiet
+                && !gives_check;
+            let score = if reduce {
+                let score = -self.negamax(
+                    &new_board,
+                    &new_state,
+                    depth - 2,
+                    -alpha - 1,
+                    -alpha,
+                    ply + 1,
+                    true,
+                );
+                if score > alpha {
+                    -self.negamax(
+                        &new_board,
+                        &new_state,
+                        new_depth,
+                        -beta,
+                        -alpha,
+                        ply + 1,
+                        true,
+                    )
+                } else {
+                    score
+                }
+            } else {
+                -self.negamax(
+                    &new_board,
+                    &new_state,
+                    new_depth,
+                    -beta,
+                    -alpha,
+                    ply + 1,
+                    true,
+                )
+            };
+            self.unmake(score);
+            if self.stopped {
+                break;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
             }
-            beta = beta.min(eval);
         }
-        if beta <= alpha {
+
+        if excluded.is_some() {
+            // No other move means the excluded one is singular.
+            return best_score.max(alpha);
+        }
+        // Every move was pruned as futile, so the static evaluation is the best estimate.
+        if best_score == -INFINITY {
+            return static_eval;
+        }
+        self.store(
+            Entry {
+                key,
+                depth,
+                score: best_score,
+                bound: bound(best_score, original_alpha, beta),
+                best_move,
+            },
+            ply,
+        );
+        best_score
+    }
+
+    /// Returns the best move of the transposition table's `entry` for
+    /// `board` if it's singular: a search of every other move, to half
+    /// the depth, falls short of its score by a margin growing with
+    /// `depth`.
+    fn singular_move(
+        &mut self,
+        board: &Board,
+        state: &VariantState,
+        depth: u32,
+        ply: u32,
+        entry: Entry,
+    ) -> Option<ChessMove> {
+        let mv = entry.best_move?;
+        if !self.options.singular_extensions
+            || depth < SINGULAR_MIN_DEPTH
+            || entry.depth + 3 < depth
+            || entry.bound == Bound::Upper
+            || entry.score.abs() >= MATE_SCORE / 2
+        {
+            return None;
+        }
+        let singular_beta = entry.score - SINGULAR_MARGIN * depth as i32;
+        self.excluded = Some(mv);
+        let score = self.negamax(
+            board,
+            state,
+            (depth - 1) / 2,
+            singular_beta - 1,
+            singular_beta,
+            ply,
+            false,
+        );
+        (score < singular_beta && !self.stopped).then_some(mv)
+    }
+
+    /// Returns the line the search expects from `board`: `best_move`,
+    /// then the best moves the transposition table keeps for the
+    /// positions after it, at most `depth` moves in all.
+    fn principal_variation(
+        &self,
+        board: &Board,
+        state: &VariantState,
+        best_move: ChessMove,
+        depth: u32,
+    ) -> Vec<ChessMove> {
+        let mut pv = vec![best_move];
+        let mut board = board.make_move_new(best_move);
+        let mut state = state.after_move(&board);
+        while (pv.len() as u32) < depth && state.winner(&board).is_none() {
+            let Some(mv) = self
+                .probe(table_key(&board, &state), 0)
+                .and_then(|entry| entry.best_move)
+                .filter(|mv| board.legal(*mv))
+            else {
+                break;
+            };
+            pv.push(mv);
+            board = board.make_move_new(mv);
+            state = state.after_move(&board);
+        }
+        pv
+    }
+
+    /// Looks up the position with hash `key`, reached at `ply`, in the
+    /// transposition table.
+    fn probe(&self, key: u64, ply: u32) -> Option<Entry> {
+        let mut entry = self.table.as_ref()?.probe(key)?;
+        // Mates are kept counted from the position, see `Searcher::store`.
+        if entry.score > MATE_SCORE / 2 {
+            entry.score -= ply as i32;
+        } else if entry.score < -MATE_SCORE / 2 {
+            entry.score += ply as i32;
+        }
+        Some(entry)
+    }
+
+    /// Keeps the result of searching a position reached at `ply` in the
+    /// transposition table, unless the search was stopped and the result
+    /// can't be trusted.
+    fn store(&mut self, mut entry: Entry, ply: u32) {
+        if self.stopped {
+            return;
+        }
+        let ply = ply as i32;
+        // Mate scores count plies from the root; the same position is
+        // as many plies from mate wherever it's reached.
+        if entry.score > MATE_SCORE / 2 {
+            entry.score += ply;
+        } else if entry.score < -MATE_SCORE / 2 {
+            entry.score -= ply;
+        }
+        if let Some(table) = &mut self.table {
+            table.store(entry);
+        }
+    }
+
+    /// Score of a position without legal moves: mated or stalemated.
+    fn terminal_score(&self, board: &Board, ply: u32) -> i32 {
+        if *board.checkers() != EMPTY {
+            -MATE_SCORE + ply as i32
+        } else {
+            self.draw_score(board)
+        }
+    }
+
+    /// Score of a drawn position from the side to move's point of view,
+    /// taking the engine's contempt into account.
+    fn draw_score(&self, board: &Board) -> i32 {
+        if board.side_to_move() == self.root_color {
+            -self.options.contempt
+        } else {
+            self.options.contempt
+        }
+    }
+}
+
+/// Computes the static exchange evaluation of `mv`: the material the
+/// side to move wins or loses in centipawns if both sides keep
+/// recapturing on the destination square with their least valuable
+/// piece, each free to stop when recapturing no longer pays.
+///
+/// Pieces attacking through the ones that capture before them (x-rays)
+/// are counted, but pins and checks are not. A quiet move scores how
+/// much the moved piece stands to lose on its new square.
+///
+/// # Example
+///
+/// ```
+/// // The queen takes a pawn defended by a pawn.
+/// let board = Board::from_str("4k3/2p5/3p4/8/8/8/3Q4/4K3 w - - 0 1").unwrap();
+/// assert_eq!(see(&board, ChessMove::from_str("d2d6").unwrap()), -800);
+/// ```
+pub fn see(board: &Board, mv: ChessMove) -> i32 {
+    let target = mv.get_dest();
+    let Some(mut attacker) = board.piece_on(mv.get_source()) else {
+        return 0;
+    };
+    let mut occupied = *board.combined() ^ BitBoard::from_square(mv.get_source());
+    // Gains after each capture, for at most the 32 pieces on the board.
+    let mut gains = [0; 32];
+    gains[0] = match board.piece_on(target) {
+        Some(victim) => piece_value(victim),
+        None if attacker == Piece::Pawn && mv.get_source().get_file() != target.get_file() => {
+            // En passant: the captured pawn is next to the capturing one.
+            let captured = Square::make_square(mv.get_source().get_rank(), target.get_file());
+            occupied ^= BitBoard::from_square(captured);
+            piece_value(Piece::Pawn)
+        }
+        None => 0,
+    };
+    if let Some(promotion) = mv.get_promotion() {
+        gains[0] += piece_value(promotion) - piece_value(Piece::Pawn);
+        attacker = promotion;
+    }
+
+    let mut side = !board.side_to_move();
+    let mut depth = 0;
+    loop {
+        depth += 1;
+        // What the last capture gains if the capturing piece is taken in turn.
+        gains[depth] = piece_value(attacker) - gains[depth - 1];
+        // Neither side can do better by recapturing from here on.
+        if (-gains[depth - 1]).max(gains[depth]) < 0 {
             break;
         }
+        let Some((square, piece)) = least_valuable_attacker(board, target, side, occupied) else {
+            break;
+        };
+        occupied ^= BitBoard::from_square(square);
+        attacker = piece;
+        side = !side;
+    }
+    // The last gain assumed a recapture that never happens, so it's skipped.
+    for depth in (1..depth).rev() {
+        gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+    }
+    gains[0]
+}
+
+/// Returns the square and kind of the least valuable piece of `side`
+/// attacking `target`, counting only the pieces in `occupied`.
+fn least_valuable_attacker(
+    board: &Board,
+    target: Square,
+    side: Color,
+    occupied: BitBoard,
+) -> Option<(Square, Piece)> {
+    let pieces = |piece: Piece| *board.pieces(piece) & board.color_combined(side) & occupied;
+    let diagonal = get_bishop_moves(target, occupied);
+    let straight = get_rook_moves(target, occupied);
+    [
+        (
+            Piece::Pawn,
+            get_pawn_attacks(target, !side, pieces(Piece::Pawn)),
+        ),
+        (
+            Piece::Knight,
+            get_knight_moves(target) & pieces(Piece::Knight),
+        ),
+        (Piece::Bishop, diagonal & pieces(Piece::Bishop)),
+        (Piece::Rook, straight & pieces(Piece::Rook)),
+        (Piece::Queen, (diagonal | straight) & pieces(Piece::Queen)),
+        (Piece::King, get_king_moves(target) & pieces(Piece::King)),
+    ]
+    .into_iter()
+    .find(|(_, attackers)| *attackers != EMPTY)
+    .map(|(piece, attackers)| (attackers.to_square(), piece))
+}
+
+/// Generates legal moves ordered by MVV-LVA, so captures of valuable
+/// pieces by cheap ones are searched first. Captures losing material
+/// by [`see`] come after the other captures, worst last, but still
+/// before the quiet moves, as the search has no quiescence stage to
+/// find the refutation of a quiet move that hangs a piece.
+fn ordered_moves(board: &Board) -> Vec<ChessMove> {
+    let mut moves: Vec<ChessMove> = MoveGen::new_legal(board).collect();
+    moves.sort_by_cached_key(|mv| Reverse(move_order_score(board, *mv)));
+    moves
+}
+
+/// Moves `first`, if it's one of `moves`, to the front, e.g. the best
+/// move of an earlier search of the position.
+fn move_first(moves: &mut Vec<ChessMove>, first: Option<ChessMove>) {
+    if let Some(position) = moves.iter().position(|mv| Some(*mv) == first) {
+        let mv = moves.remove(position);
+        moves.insert(0, mv);
+    }
+}
+
+/// Returns what a search within `(alpha, beta)` scoring `score` tells
+/// about the true score.
+fn bound(score: i32, alpha: i32, beta: i32) -> Bound {
+    if score <= alpha {
+        Bound::Upper
+    } else if score >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    }
+}
+
+/// Returns the key of the position in the transposition table: the hash
+/// of the board, mixed with the checks given so far.
+fn table_key(board: &Board, state: &VariantState) -> u64 {
+    board.get_hash()
+        ^ u64::from(state.checks(Color::White)).wrapping_mul(WHITE_CHECK_KEY)
+        ^ u64::from(state.checks(Color::Black)).wrapping_mul(BLACK_CHECK_KEY)
+}
+
+fn move_order_score(board: &Board, mv: ChessMove) -> i32 {
+    let promotion = mv.get_promotion().map_or(0, piece_value);
+    match board.piece_on(mv.get_dest()) {
+        Some(victim) => {
+            let exchange = see(board, mv);
+            if exchange < 0 {
+                return 10_000 + exchange;
+            }
+            let attacker = board.piece_on(mv.get_source()).map_or(0, piece_value);
+            20_000 + 10 * piece_value(victim) - attacker + promotion
+        }
+        None => promotion,
     }
-    (best_eval, best_move)
 }
 
+fn is_capture(board: &Board, mv: ChessMove) -> bool {
+    board.piece_on(mv.get_dest()).is_some()
+        || (board.piece_on(mv.get_source()) == Some(Piece::Pawn)
+            && mv.get_source().get_file() != mv.get_dest().get_file())
+}
+
+fn has_non_pawn_material(board: &Board, color: Color) -> bool {
+    let pawns_and_kings = board.pieces(Piece::Pawn) | board.pieces(Piece::King);
+    (board.color_combined(color) & !pawns_and_kings) != EMPTY
+}
+
+/// Positions used by the `bench` command and the search tests.
+pub const BENCH_POSITIONS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn no_pruning() -> EngineOptions {
+        EngineOptions {
+            null_move_pruning: false,
+            late_move_reductions: false,
+            futility_pruning: false,
+            check_extensions: false,
+            singular_extensions: false,
+            aspiration_window: None,
+            contempt: 0,
+            weights: EvalWeights::default(),
+            use_network: true,
+            limits: SearchLimits::default(),
+            params: SearchParams::default(),
+            table: None,
+            ..EngineOptions::default()
+        }
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        // Back rank mate with Re8#
+        let board = Board::from_str("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1").unwrap();
+        let result = search(&board, 3, &EngineOptions::default());
+        assert_eq!(result.best_move, Some(ChessMove::from_str("e1e8").unwrap()));
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn finds_mate_in_two() {
+        // Rook roller: 1. Rg7 Kb8 2. Rh8# (or 1. Rh7 and 2. Rg8#)
+        let board = Board::from_str("k7/8/8/8/8/8/6R1/4K2R w - - 0 1").unwrap();
+        let result = search(&board, 4, &EngineOptions::default());
+        assert_eq!(result.score, MATE_SCORE - 3);
+    }
+
+    #[test]
+    fn each_technique_keeps_winning_capture() {
+        let board =
+            Board::from_str("rnb1kbnr/pppp1ppp/8/4p3/4P2q/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let configurations = [
+            no_pruning(),
+            EngineOptions {
+                null_move_pruning: true,
+                ..no_pruning()
+            },
+            EngineOptions {
+                late_move_reductions: true,
+                ..no_pruning()
+            },
+            EngineOptions {
+                futility_pruning: true,
+                ..no_pruning()
+            },
+            EngineOptions {
+                check_extensions: true,
+                ..no_pruning()
+            },
+            EngineOptions {
+                singular_extensions: true,
+                table: Some(SharedTable::default()),
+                ..no_pruning()
+            },
+            EngineOptions {
+                aspiration_window: EngineOptions::default().aspiration_window,
+                ..no_pruning()
+            },
+            EngineOptions::default(),
+        ];
+        for options in configurations {
+            let result = search(&board, 4, &options);
+            assert_eq!(
+                result.best_move,
+                Some(ChessMove::from_str("f3h4").unwrap()),
+                "{:?}",
+                options
+            );
+        }
+    }
+
+    #[test]
+    fn no_legal_moves_returns_none() {
+        // Black is stalemated
+        let board = Board::from_str("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        let result = search(&board, 3, &EngineOptions::default());
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn pruning_reduces_node_count() {
+        for fen in BENCH_POSITIONS {
+            let board = Board::from_str(fen).unwrap();
+            let full = search(&board, 4, &no_pruning());
+            let pruned = search(&board, 4, &EngineOptions::default());
+            assert!(
+                pruned.nodes < full.nodes,
+                "{}: {} nodes with pruning, {} without",
+                fen,
+                pruned.nodes,
+                full.nodes
+            );
+        }
+    }
+
+    #[test]
+    fn aspiration_window_keeps_score() {
+        // Without pruning the search is exact, so narrowing the window and
+        // re-searching on failure must give the same score.
+        let options = EngineOptions {
+            aspiration_window: Some(250),
+            ..no_pruning()
+        };
+        for fen in BENCH_POSITIONS {
+            let board = Board::from_str(fen).unwrap();
+            let full = search(&board, 4, &no_pruning());
+            let windowed = search(&board, 4, &options);
+            assert_eq!(windowed.score, full.score, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn contempt_decides_whether_to_stalemate() {
+        // White is a pawn up, but Kc7 stalemates black.
+        let board = Board::from_str("k7/p7/P1K5/8/8/8/7P/8 w - - 0 1").unwrap();
+        let stalemate = Some(ChessMove::from_str("c6c7").unwrap());
+
+        let plays_on = search(&board, 3, &no_pruning());
+        assert_ne!(plays_on.best_move, stalemate);
+
+        let drawish = EngineOptions {
+            contempt: -200,
+            ..no_pruning()
+        };
+        let result = search(&board, 3, &drawish);
+        assert_eq!(result.best_move, stalemate);
+        assert_eq!(result.score, 200);
+    }
+
+    /// Returns `board` after playing `moves`, and the history of the
+    /// positions on the way.
+    fn play_moves(board: Board, moves: &[&str]) -> (Board, PositionHistory) {
+        let mut history = PositionHistory::default();
+        let board = moves.iter().fold(board, |board, mv| {
+            let mv = ChessMove::from_str(mv).unwrap();
+            assert!(board.legal(mv));
+            history.play(&board, mv);
+            board.make_move_new(mv)
+        });
+        (board, history)
+    }
+
+    #[test]
+    fn contempt_scores_repetitions() {
+        // White is a queen down, but Nf3 repeats the position after it.
+        let start = Board::from_str("7k/8/8/8/8/8/q7/6NK w - - 0 1").unwrap();
+        let (board, history) = play_moves(start, &["g1f3", "a2a3", "f3g1", "a3a2"]);
+        assert_eq!(board, start);
+        let repetition = ChessMove::from_str("g1f3").unwrap();
+
+        let options = EngineOptions {
+            history: history.clone(),
+            ..no_pruning()
+        };
+        let result = search(&board, 3, &options);
+        assert_eq!(result.best_move, Some(repetition));
+        assert_eq!(result.score, 0);
+
+        let eager = EngineOptions {
+            contempt: 100,
+            history,
+            ..no_pruning()
+        };
+        let result = search(&board, 3, &eager);
+        assert_eq!(result.best_move, Some(repetition));
+        assert_eq!(result.score, -100);
+        let scored = score_moves(&board, &VariantState::default(), 3, &eager);
+        assert_eq!(scored[0], (repetition, -100));
+
+        // Without the earlier positions nothing repeats.
+        assert!(search(&board, 3, &no_pruning()).score < -500);
+    }
+
+    #[test]
+    fn fifty_move_rule_draws_unless_mated() {
+        let losing = Board::from_str("7k/8/8/8/8/8/q7/6NK w - - 99 1").unwrap();
+        let options = EngineOptions {
+            history: PositionHistory {
+                positions: Vec::new(),
+                halfmove_clock: 99,
+            },
+            ..no_pruning()
+        };
+        assert_eq!(search(&losing, 3, &options).score, 0);
+
+        // Re8# comes first.
+        let mating = Board::from_str("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 99 1").unwrap();
+        let result = search(&mating, 3, &options);
+        assert_eq!(result.best_move, Some(ChessMove::from_str("e1e8").unwrap()));
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn scores_moves_best_first() {
+        let board = Board::from_str("k7/p7/P1K5/8/8/8/7P/8 w - - 0 1").unwrap();
+        let drawish = EngineOptions {
+            contempt: -200,
+            ..no_pruning()
+        };
+        let scored = score_moves(&board, &VariantState::default(), 3, &drawish);
+        assert_eq!(scored.len(), MoveGen::new_legal(&board).len());
+        assert!(scored.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert_eq!(scored[0], (ChessMove::from_str("c6c7").unwrap(), 200));
+    }
+
+    #[test]
+    fn stops_at_search_limits() {
+        let board = Board::default();
+        let limited = |limits| EngineOptions {
+            limits,
+            ..EngineOptions::default()
+        };
+        let nodes = limited(SearchLimits {
+            nodes: Some(5000),
+            ..SearchLimits::default()
+        });
+        let result = search(&board, 20, &nodes);
+        assert!(result.nodes <= 5000, "{}", result);
+        assert!(result.best_move.is_some());
+        let scored = score_moves(&board, &VariantState::default(), 20, &nodes);
+        assert!(!scored.is_empty() && scored.len() < 20);
+
+        let started = Instant::now();
+        let time = limited(SearchLimits {
+            time: Some(Duration::from_millis(50)),
+            ..SearchLimits::default()
+        });
+        assert!(search(&board, 20, &time).best_move.is_some());
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Too little memory to search a single move still gives one.
+        let memory = limited(SearchLimits {
+            memory: Some(0),
+            ..SearchLimits::default()
+        });
+        assert!(search(&board, 20, &memory).best_move.is_some());
+        // The transposition table counts toward the memory limit.
+        let table = TranspositionTable::new(1);
+        let memory = EngineOptions {
+            limits: SearchLimits {
+                memory: Some(table.memory()),
+                ..SearchLimits::default()
+            },
+            table: Some(SharedTable::new(table)),
+            ..EngineOptions::default()
+        };
+        let result = search(&board, 20, &memory);
+        assert!(result.best_move.is_some());
+        assert!(result.nodes < 100, "{}", result);
+        let mcts = Mcts {
+            iterations: 1_000_000,
+            options: nodes,
+        };
+        let result = mcts.search(&board, &VariantState::default());
+        assert!(result.best_move.is_some() && result.nodes < 6000);
+    }
+
+    #[test]
+    fn blunder_model_stays_among_candidates() {
+        let scored: Vec<(ChessMove, i32)> = MoveGen::new_legal(&Board::default())
+            .enumerate()
+            .map(|(i, mv)| (mv, -100 * i as i32))
+            .collect();
+        let model = BlunderModel {
+            candidates: 3,
+            temperature: 1000.0,
+        };
+        for _ in 0..50 {
+            let choice = model.choose(&scored).unwrap();
+            assert!(scored[..3].contains(&choice));
+        }
+        // A cold model all but always plays the best move.
+        let cold = BlunderModel {
+            temperature: 1.0,
+            ..model
+        };
+        assert_eq!(cold.choose(&scored), Some(scored[0]));
+        assert_eq!(cold.choose(&[]), None);
+    }
+
+    #[test]
+    fn static_exchange_evaluation() {
+        let see_uci = |fen: &str, uci: &str| {
+            see(
+                &Board::from_str(fen).unwrap(),
+                ChessMove::from_str(uci).unwrap(),
+            )
+        };
+        // An undefended pawn.
+        assert_eq!(see_uci("4k3/8/3p4/8/8/8/3R4/4K3 w - - 0 1", "d2d6"), 100);
+        // A pawn defended by a pawn, taken by a rook.
+        assert_eq!(see_uci("4k3/2p5/3p4/8/8/8/3R4/4K3 w - - 0 1", "d2d6"), -400);
+        // The rook on d1 backs up the one on d2 through it.
+        assert_eq!(
+            see_uci("3rk3/8/3n4/8/8/8/3R4/3RK3 w - - 0 1", "d2d6"),
+            300 - 500 + 500
+        );
+        // Knight for knight, the bishop recapturing.
+        assert_eq!(see_uci("4k3/8/2b5/3n4/8/4N3/8/4K3 w - - 0 1", "e3d5"), 0);
+        // En passant captures the pawn beside the capturing one.
+        assert_eq!(see_uci("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+        // A quiet move onto a square the opponent's pawn attacks.
+        assert_eq!(see_uci("4k3/8/2p5/8/8/8/8/3RK3 w - - 0 1", "d1d5"), -500);
+    }
+
+    #[test]
+    fn weighted_evaluation() {
+        let board = Board::from_str("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").unwrap();
+        let weighted = |weights: EvalWeights| evaluate_weighted(&board, Color::White, &weights);
+        let base = EvalWeights::default();
+        assert_eq!(weighted(base), evaluate(&board, Color::White));
+        assert_eq!(
+            weighted(EvalWeights {
+                material: 50,
+                ..base
+            }),
+            150
+        );
+        // The knight stands on one of the four central squares.
+        assert_eq!(weighted(EvalWeights { center: 10, ..base }), 320);
+        // 13 legal moves against the black king's 5.
+        assert_eq!(
+            weighted(EvalWeights {
+                mobility: 1,
+                ..base
+            }),
+            308
+        );
+        let near_king = Board::from_str("4k3/8/3N4/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let weights = EvalWeights {
+            king_attack: 15,
+            ..base
+        };
+        assert_eq!(evaluate_weighted(&near_king, Color::Black, &weights), -315);
+    }
+
+    #[test]
+    fn king_of_the_hill_walks_to_center() {
+        let board = Board::from_str("r3k3/8/8/8/8/3K4/8/7R w - - 0 1").unwrap();
+        let state = VariantState::new(Variant::KingOfTheHill);
+        let result = search_variant(&board, &state, 3, &EngineOptions::default());
+        let mv = result.best_move.unwrap();
+        assert!(crate::variant::HILL.contains(&mv.get_dest()));
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn three_check_gives_the_third_check() {
+        // White has given two checks already, so any check wins on the spot.
+        let board = Board::from_str("4k3/8/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let state = VariantState {
+            variant: Variant::ThreeCheck,
+            checks_given: [2, 0],
+        };
+        let result = search_variant(&board, &state, 3, &EngineOptions::default());
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn baseline_opponents() {
+        // The rook can take the queen, the knight a pawn.
+        let board = Board::from_str("4k3/8/8/3q4/8/2p5/N7/3RK3 w - - 0 1").unwrap();
+        for _ in 0..10 {
+            let mv = baseline_move(&board, Baseline::Greedy).unwrap();
+            assert_eq!(mv, ChessMove::from_str("d1d5").unwrap());
+            let mv = baseline_move(&board, Baseline::Random).unwrap();
+            assert!(MoveGen::new_legal(&board).any(|legal| legal == mv));
+        }
+        let mated = Board::from_str("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(baseline_move(&mated, Baseline::Random), None);
+        assert_eq!(Difficulty::from_str("level1"), Ok(Difficulty::Beginner));
+        assert_eq!(Difficulty::Greedy.baseline(), Some(Baseline::Greedy));
+        assert_eq!(Difficulty::Beginner.baseline(), None);
+        assert_eq!(Difficulty::from_str("1500"), Ok(Difficulty::Elo(1500)));
+        assert_eq!(Difficulty::from_str("Elo(800)"), Ok(Difficulty::Elo(800)));
+        assert_eq!(Difficulty::from_str("100"), Err(()));
+        assert_eq!(Difficulty::Elo(1500).depth(), 2);
+        assert!(Difficulty::Elo(1500).node_limit().is_some());
+    }
+}
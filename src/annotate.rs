@@ -0,0 +1,467 @@
+use crate::adaptive::win_percent;
+use crate::ai::{search, EngineOptions, SearchLimits, MATE_SCORE};
+use crate::archive::starting_board;
+use crate::explorer::Theory;
+use crate::motif::{motifs, Motif};
+use crate::notation::{parse_san, san_for_move};
+use crate::pgn::{write_movetext, write_tag, PgnGame};
+use chess::{BoardStatus, Color};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// Search depth of an analysis only limited by its time.
+const MAX_DEPTH: u32 = 64;
+/// Winning chances in percent a move throws away to be an inaccuracy,
+/// a mistake and a blunder.
+const JUDGMENT_THRESHOLDS: [f64; 3] = [10.0, 20.0, 30.0];
+/// Bars of an evaluation graph, from Black winning to White winning.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Widest evaluation graph, in characters.
+pub const GRAPH_WIDTH: usize = 60;
+
+/// How bad a move is, judged by the winning chances it throws away.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Judgment {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgment {
+    /// Judges a move that threw away `lost` percent of winning chances,
+    /// `None` if it's good enough.
+    fn from_loss(lost: f64) -> Option<Self> {
+        let [inaccuracy, mistake, blunder] = JUDGMENT_THRESHOLDS;
+        match lost {
+            lost if lost >= blunder => Some(Judgment::Blunder),
+            lost if lost >= mistake => Some(Judgment::Mistake),
+            lost if lost >= inaccuracy => Some(Judgment::Inaccuracy),
+            _ => None,
+        }
+    }
+
+    /// Returns the Numeric Annotation Glyph of the judgment: `$6` for
+    /// `?!`, `$2` for `?` and `$4` for `??`.
+    fn nag(self) -> &'static str {
+        match self {
+            Judgment::Inaccuracy => "$6",
+            Judgment::Mistake => "$2",
+            Judgment::Blunder => "$4",
+        }
+    }
+}
+
+impl fmt::Display for Judgment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Judgment::Inaccuracy => write!(f, "Inaccuracy"),
+            Judgment::Mistake => write!(f, "Mistake"),
+            Judgment::Blunder => write!(f, "Blunder"),
+        }
+    }
+}
+
+/// Formats a score in centipawns from White's side as a `[%eval]`
+/// command, see [`format_score`].
+fn eval_command(score: i32) -> String {
+    format!("[%eval {}]", format_score(score))
+}
+
+/// Formats a score in centipawns from White's side in pawns, or as the
+/// number of moves to mate after a `#`, negative when Black mates.
+pub fn format_score(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) => format!("#{}", moves),
+        None => format!("{:.2}", f64::from(score) / 100.0),
+    }
+}
+
+/// Returns the number of moves to mate a score in centipawns stands for,
+/// negative when the other side mates, `None` if it isn't a mate score.
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score.abs() >= MATE_SCORE - 1000 {
+        let plies = MATE_SCORE - score.abs();
+        Some(score.signum() * (plies + 1) / 2)
+    } else {
+        None
+    }
+}
+
+/// A game annotated by an [`Annotator`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotatedGame {
+    /// The game in PGN with the annotations.
+    pub pgn: String,
+    /// The score after every move in centipawns from White's side.
+    pub evals: Vec<i32>,
+    /// The move that left opening theory, e.g. `9... Nd4`, if any.
+    pub novelty: Option<String>,
+    /// The moves notable for their tactical motifs, in order.
+    pub tactics: Vec<Tactic>,
+}
+
+/// A move of an annotated game notable for its tactical motif, see
+/// [`motifs`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tactic {
+    /// The move, e.g. `12. Nc7+`: the one played or, if `missed`, the
+    /// one the engine preferred.
+    pub mv: String,
+    pub motif: Motif,
+    /// Whether the player missed the move, playing a worse one.
+    pub missed: bool,
+}
+
+impl fmt::Display for Tactic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.missed {
+            true => write!(f, "{} (missed {})", self.mv, self.motif),
+            false => write!(f, "{} ({})", self.mv, self.motif),
+        }
+    }
+}
+
+impl AnnotatedGame {
+    /// Draws the evaluation over the course of the game as a sparkline
+    /// at most `width` characters wide, higher bars for White's chances
+    /// to win. Longer games have every character stand for the average
+    /// of a few moves.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let graph = annotated.graph(GRAPH_WIDTH);
+    /// println!("{}", graph); // e.g. ▄▄▅▄▅▅▆▇██
+    /// ```
+    pub fn graph(&self, width: usize) -> String {
+        let moves_per_bar = self.evals.len().div_ceil(width.max(1)).max(1);
+        self.evals
+            .chunks(moves_per_bar)
+            .map(|evals| {
+                let chances =
+                    evals.iter().map(|eval| win_percent(*eval)).sum::<f64>() / evals.len() as f64;
+                BARS[((chances / 100.0 * BARS.len() as f64) as usize).min(BARS.len() - 1)]
+            })
+            .collect()
+    }
+}
+
+/// Writes the evaluations of annotated games as a JSON array with an
+/// object for each game holding its `white` and `black` players and its
+/// `evals`, see [`AnnotatedGame::evals`].
+pub fn evals_json(games: &[(&PgnGame, &AnnotatedGame)]) -> String {
+    let string = |value: Option<&str>| json_string(value.unwrap_or("?"));
+    let objects: Vec<String> = games
+        .iter()
+        .map(|(game, annotated)| {
+            let evals: Vec<String> = annotated.evals.iter().map(i32::to_string).collect();
+            format!(
+                "{{\"white\":{},\"black\":{},\"evals\":[{}]}}",
+                string(game.tag("White")),
+                string(game.tag("Black")),
+                evals.join(",")
+            )
+        })
+        .collect();
+    format!("[{}]\n", objects.join(","))
+}
+
+/// Quotes `value` as a JSON string, escaping the characters that need it.
+pub fn json_string(value: &str) -> String {
+    let mut json = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => json.extend(['\\', c]),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Runs the engine over games to annotate them: every move gets the
+/// evaluation of the position after it, and inaccuracies, mistakes and
+/// blunders are marked with a NAG and the move the engine preferred.
+/// The move leaving known theory is marked as a novelty, and good moves
+/// setting up a tactical motif are named by it, as are the motifs of the
+/// better moves missed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Annotator {
+    /// Time the engine searches each position for.
+    pub time_per_move: Duration,
+    /// Number of games annotated at once.
+    pub threads: usize,
+}
+
+impl Default for Annotator {
+    fn default() -> Self {
+        Self {
+            time_per_move: Duration::from_millis(500),
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+}
+
+impl Annotator {
+    /// Annotates `games`, spread over [`Annotator::threads`] threads,
+    /// looking for novelties against `theory`.
+    ///
+    /// # Returns
+    ///
+    /// Each annotated game in order, or an error describing why it
+    /// couldn't be annotated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let games = parse_pgn("1. e4 e5 *");
+    /// let annotated = Annotator::default().annotate(&games, &Theory::default());
+    /// println!("{}", annotated[0].as_ref().unwrap().pgn);
+    /// ```
+    pub fn annotate(
+        &self,
+        games: &[PgnGame],
+        theory: &Theory,
+    ) -> Vec<Result<AnnotatedGame, String>> {
+        let next = AtomicUsize::new(0);
+        let annotated = Mutex::new(vec![None; games.len()]);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.clamp(1, games.len().max(1)) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(game) = games.get(index) else {
+                        break;
+                    };
+                    let game = self.annotate_game(game, theory);
+                    annotated.lock().unwrap()[index] = Some(game);
+                });
+            }
+        });
+        annotated
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(Option::unwrap)
+            .collect()
+    }
+
+    /// Annotates a single game, replayed from its `FEN` tag if it has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an invalid FEN or an illegal move.
+    fn annotate_game(&self, game: &PgnGame, theory: &Theory) -> Result<AnnotatedGame, String> {
+        let start = starting_board(game).ok_or_else(|| "invalid FEN".to_string())?;
+        let mut boards = vec![start];
+        let mut moves = Vec::new();
+        for text in &game.moves {
+            let board = *boards.last().unwrap();
+            let mv = parse_san(&board, text).ok_or_else(|| format!("illegal move {}", text))?;
+            moves.push(mv);
+            boards.push(board.make_move_new(mv));
+        }
+        let options = EngineOptions {
+            limits: SearchLimits {
+                time: Some(self.time_per_move),
+                ..SearchLimits::default()
+            },
+            ..EngineOptions::default()
+        };
+        let results: Vec<_> = boards
+            .iter()
+            .map(|board| search(board, MAX_DEPTH, &options))
+            .collect();
+        let evals: Vec<i32> = boards[1..]
+            .iter()
+            .zip(&results[1..])
+            .map(|(board, result)| match board.side_to_move() {
+                Color::White => result.score,
+                Color::Black => -result.score,
+            })
+            .collect();
+        let novelty_ply = theory.novelty(&start, &moves);
+        let mut novelty = None;
+        let mut tactics = Vec::new();
+
+        let mut pgn = String::new();
+        for (name, value) in &game.tags {
+            pgn.push_str(&write_tag(name, value));
+        }
+        pgn.push_str(&write_tag("Annotator", "rChess"));
+        pgn.push('\n');
+        let mut tokens = Vec::new();
+        let mut number = 1;
+        for (i, mv) in moves.iter().enumerate() {
+            let (board, after) = (boards[i], boards[i + 1]);
+            match board.side_to_move() {
+                Color::White => tokens.push(format!("{}.", number)),
+                Color::Black if i == 0 => tokens.push(format!("{}...", number)),
+                Color::Black => (),
+            }
+            let san = san_for_move(&board, *mv);
+            tokens.push(san.clone());
+            let dots = match board.side_to_move() {
+                Color::White => ".",
+                Color::Black => "...",
+            };
+            let move_number = number;
+            let label = move |san: &str| format!("{}{} {}", move_number, dots, san);
+            let is_novelty = novelty_ply == Some(i);
+            if is_novelty {
+                // The NAG for a novelty, `N`.
+                tokens.push("$146".to_string());
+                novelty = Some(label(&san));
+            }
+            let played: Vec<Tactic> = motifs(&board, *mv)
+                .into_iter()
+                .map(|motif| Tactic {
+                    mv: label(&san),
+                    motif,
+                    missed: false,
+                })
+                .collect();
+            if board.side_to_move() == Color::Black {
+                number += 1;
+            }
+            if after.status() != BoardStatus::Ongoing {
+                if !played.is_empty() {
+                    let names: Vec<String> = played
+                        .iter()
+                        .map(|tactic| motif_name(tactic.motif))
+                        .collect();
+                    tokens.push(format!("{{{}.}}", names.join(". ")));
+                    tactics.extend(played);
+                }
+                continue;
+            }
+            // Scores from the point of view of the player who moved.
+            let before_score = results[i].score;
+            let after_score = -results[i + 1].score;
+            let mut comment = eval_command(evals[i]);
+            if is_novelty {
+                comment.push_str(" Novelty.");
+            }
+            let lost = win_percent(before_score) - win_percent(after_score);
+            match Judgment::from_loss(lost) {
+                Some(judgment) => {
+                    tokens.push(judgment.nag().to_string());
+                    comment.push_str(&format!(" {}.", judgment));
+                    if let Some(best) = results[i].best_move.filter(|best| best != mv) {
+                        let best_san = san_for_move(&board, best);
+                        comment.push_str(&format!(" {} was best.", best_san));
+                        for motif in motifs(&board, best) {
+                            comment.push_str(&format!(" Missed a {}.", motif));
+                            tactics.push(Tactic {
+                                mv: label(&best_san),
+                                motif,
+                                missed: true,
+                            });
+                        }
+                    }
+                }
+                None => {
+                    for tactic in played {
+                        comment.push_str(&format!(" {}.", motif_name(tactic.motif)));
+                        tactics.push(tactic);
+                    }
+                }
+            }
+            tokens.push(format!("{{{}}}", comment));
+        }
+        tokens.push(game.result().unwrap_or("*").to_string());
+        pgn.push_str(&write_movetext(&tokens));
+        Ok(AnnotatedGame {
+            pgn,
+            evals,
+            novelty,
+            tactics,
+        })
+    }
+}
+
+/// Returns the name of `motif` starting a sentence, e.g. `Fork`.
+fn motif_name(motif: Motif) -> String {
+    let name = motif.to_string();
+    let mut chars = name.chars();
+    chars.next().map_or(String::new(), |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explorer::PositionIndex;
+    use crate::pgn::parse_pgn;
+    use crate::repertoire::OpeningTree;
+
+    #[test]
+    fn marks_blunders() {
+        let annotator = Annotator {
+            time_per_move: Duration::from_millis(20),
+            threads: 2,
+        };
+        let games = parse_pgn(
+            "[White \"Anna\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n\n1. e4 Qxe4 *\n\n\
+             [FEN \"4k3/8/8/8/8/8/8/R3K3 w - - 0 1\"]\n\n1. Ra8+ Kd7 *\n\n\
+             [FEN \"r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1\"]\n\n1. Nc7+ Kd7 2. Nxa8 *\n",
+        );
+        let book = OpeningTree::from_pgn("1. e4 e5 2. Qh5 g6 *").unwrap();
+        let theory = Theory::new(book, PositionIndex::default());
+        let annotated = annotator.annotate(&games, &theory);
+        let pgn = &annotated[0].as_ref().unwrap().pgn;
+        assert!(pgn.starts_with("[White \"Anna\"]\n[Annotator \"rChess\"]\n\n1. e4 {[%eval "));
+        let movetext = pgn.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert!(movetext.contains("Nf6 $4 {[%eval #1] Blunder. "));
+        assert!(movetext.contains("} Nc6 $146 {[%eval "));
+        assert!(movetext.contains("] Novelty.} 3. Bc4"));
+        assert_eq!(
+            annotated[0].as_ref().unwrap().novelty.as_deref(),
+            Some("2... Nc6")
+        );
+        assert!(pgn.ends_with("4. Qxf7# 1-0\n"));
+        assert_eq!(annotated[1], Err("illegal move Qxe4".to_string()));
+        assert!(annotated[2]
+            .as_ref()
+            .unwrap()
+            .pgn
+            .contains("1. Ra8+ {[%eval "));
+        assert_eq!(annotated[0].as_ref().unwrap().evals.len(), 7);
+        let fork = annotated[3].as_ref().unwrap();
+        assert_eq!(fork.tactics[0].to_string(), "1. Nc7+ (fork)");
+        assert!(fork.pgn.contains("] Fork.} Kd7"));
+    }
+
+    #[test]
+    fn graphs_evaluations() {
+        let annotated = AnnotatedGame {
+            pgn: String::new(),
+            evals: vec![0, 0, 400, -MATE_SCORE + 1],
+            novelty: None,
+            tactics: Vec::new(),
+        };
+        assert_eq!(annotated.graph(GRAPH_WIDTH), "▅▅▇▁");
+        assert_eq!(annotated.graph(2), "▅▄");
+        let game = PgnGame {
+            tags: vec![("White".to_string(), "Anna \"A\" Nowak".to_string())],
+            ..PgnGame::default()
+        };
+        assert_eq!(
+            evals_json(&[(&game, &annotated)]),
+            "[{\"white\":\"Anna \\\"A\\\" Nowak\",\"black\":\"?\",\"evals\":[0,0,400,-99999]}]\n"
+        );
+    }
+
+    #[test]
+    fn judges_moves() {
+        assert_eq!(Judgment::from_loss(5.0), None);
+        assert_eq!(Judgment::from_loss(15.0), Some(Judgment::Inaccuracy));
+        assert_eq!(Judgment::from_loss(45.0), Some(Judgment::Blunder));
+        assert_eq!(eval_command(35), "[%eval 0.35]");
+        assert_eq!(eval_command(-MATE_SCORE + 3), "[%eval #-2]");
+    }
+}
@@ -0,0 +1,164 @@
+use chess::{between, Square};
+
+/// Squares marked and arrows drawn on the board, e.g. for teaching.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotations {
+    marks: Vec<Square>,
+    arrows: Vec<(Square, Square)>,
+}
+
+impl Annotations {
+    /// Marks `square`, or removes the mark if it's already marked.
+    pub fn toggle_mark(&mut self, square: Square) {
+        match self.marks.iter().position(|mark| *mark == square) {
+            Some(i) => {
+                self.marks.remove(i);
+            }
+            None => self.marks.push(square),
+        }
+    }
+
+    /// Draws an arrow from `from` to `to`, or removes it if it's already drawn.
+    pub fn toggle_arrow(&mut self, from: Square, to: Square) {
+        match self.arrows.iter().position(|arrow| *arrow == (from, to)) {
+            Some(i) => {
+                self.arrows.remove(i);
+            }
+            None => self.arrows.push((from, to)),
+        }
+    }
+
+    /// Removes all marks and arrows.
+    pub fn clear(&mut self) {
+        self.marks.clear();
+        self.arrows.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty() && self.arrows.is_empty()
+    }
+
+    pub fn marks(&self) -> &[Square] {
+        &self.marks
+    }
+
+    pub fn arrows(&self) -> &[(Square, Square)] {
+        &self.arrows
+    }
+
+    /// Returns whether `square` is marked.
+    pub fn is_marked(&self, square: Square) -> bool {
+        self.marks.contains(&square)
+    }
+
+    /// Returns whether an arrow starts, ends or passes through `square`.
+    ///
+    /// Arrows along a rank, file or diagonal pass through the squares
+    /// between their ends; other arrows (e.g. knight jumps) only cover
+    /// their ends.
+    pub fn on_arrow(&self, square: Square) -> bool {
+        self.arrows.iter().any(|&(from, to)| {
+            square == from || square == to || between(from, to).into_iter().any(|s| s == square)
+        })
+    }
+
+    /// Returns the annotations as PGN comment commands: `[%csl ...]` for
+    /// the marked squares and `[%cal ...]` for the arrows, all in green.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut annotations = Annotations::default();
+    /// annotations.toggle_mark(Square::D5);
+    /// annotations.toggle_arrow(Square::E2, Square::E4);
+    /// assert_eq!(annotations.pgn_commands(), "[%csl Gd5][%cal Ge2e4]");
+    /// ```
+    pub fn pgn_commands(&self) -> String {
+        let mut commands = String::new();
+        if !self.marks.is_empty() {
+            let marks: Vec<String> = self.marks.iter().map(|s| format!("G{}", s)).collect();
+            commands.push_str(&format!("[%csl {}]", marks.join(",")));
+        }
+        if !self.arrows.is_empty() {
+            let arrows: Vec<String> = self
+                .arrows
+                .iter()
+                .map(|(from, to)| format!("G{}{}", from, to))
+                .collect();
+            commands.push_str(&format!("[%cal {}]", arrows.join(",")));
+        }
+        commands
+    }
+}
+
+/// Glyphs of the first six Numeric Annotation Glyphs, `$1` to `$6`.
+const GLYPHS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+/// Reads a Numeric Annotation Glyph written as `$n` or as one of the
+/// move suffixes `!`, `?`, `!!`, `??`, `!?` and `?!`.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(parse_nag("!?"), Some(5));
+/// assert_eq!(parse_nag("$146"), Some(146));
+/// ```
+pub fn parse_nag(text: &str) -> Option<u8> {
+    match text.strip_prefix('$') {
+        Some(number) => number.parse().ok(),
+        None => GLYPHS
+            .iter()
+            .position(|glyph| *glyph == text)
+            .map(|index| index as u8 + 1),
+    }
+}
+
+/// Writes a Numeric Annotation Glyph as a move suffix like `!?` if it
+/// has one, or else as `$n`.
+pub fn nag_glyph(nag: u8) -> String {
+    match GLYPHS.get(usize::from(nag).wrapping_sub(1)) {
+        Some(glyph) => glyph.to_string(),
+        None => format!("${}", nag),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggles_and_clears() {
+        let mut annotations = Annotations::default();
+        annotations.toggle_mark(Square::D5);
+        annotations.toggle_mark(Square::E5);
+        annotations.toggle_mark(Square::D5);
+        assert_eq!(annotations.marks(), [Square::E5]);
+        annotations.toggle_arrow(Square::A1, Square::H8);
+        assert!(annotations.on_arrow(Square::D4));
+        assert!(!annotations.on_arrow(Square::D5));
+        annotations.clear();
+        assert!(annotations.is_empty());
+    }
+
+    #[test]
+    fn pgn_commands() {
+        let mut annotations = Annotations::default();
+        assert_eq!(annotations.pgn_commands(), "");
+        annotations.toggle_mark(Square::D5);
+        annotations.toggle_mark(Square::F7);
+        annotations.toggle_arrow(Square::G1, Square::F3);
+        assert_eq!(annotations.pgn_commands(), "[%csl Gd5,Gf7][%cal Gg1f3]");
+        assert!(!annotations.on_arrow(Square::G2));
+    }
+
+    #[test]
+    fn nags_and_glyphs() {
+        assert_eq!(parse_nag("!"), Some(1));
+        assert_eq!(parse_nag("?!"), Some(6));
+        assert_eq!(parse_nag("$19"), Some(19));
+        assert_eq!(parse_nag("!!!"), None);
+        assert_eq!(nag_glyph(4), "??");
+        assert_eq!(nag_glyph(0), "$0");
+        assert_eq!(nag_glyph(146), "$146");
+    }
+}
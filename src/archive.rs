@@ -0,0 +1,193 @@
+use crate::config::config_dir;
+use crate::notation::parse_san;
+use crate::pgn::PgnGame;
+use chess::Board;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the archive of played games inside the config directory.
+const ARCHIVE_FILE: &str = "games.pgn";
+
+/// Returns where finished games are archived.
+pub fn archive_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(ARCHIVE_FILE))
+}
+
+/// Returns the date `days` days after 1970-01-01 as a PGN date, e.g.
+/// `2024.05.17`.
+fn pgn_date(days: i64) -> String {
+    // Howard Hinnant's days-to-civil algorithm, counting in 400-year eras
+    // starting on 1 March.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Returns today's date (in UTC) as a PGN date.
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    pgn_date((seconds / 86_400) as i64)
+}
+
+/// Appends a game in PGN to the archive at `path`, creating the file
+/// and its directory if needed.
+///
+/// The game is written with a single append and flushed to disk before
+/// returning, so a crash can at worst cut off the game being written,
+/// never the ones archived before it.
+///
+/// # Errors
+///
+/// Returns an error if the archive can't be opened or written.
+pub fn append_game(path: &Path, pgn: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format!("{}\n\n", pgn.trim_end()).as_bytes())?;
+    file.sync_data()
+}
+
+/// A game of a PGN database in which a searched position occurs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionMatch {
+    /// Index of the game in the database.
+    pub game: usize,
+    /// Number of half-moves played before the position first occurs, 0
+    /// if the game starts from it.
+    pub ply: usize,
+}
+
+impl PositionMatch {
+    /// Describes when the position occurs in `game`, e.g. `after 2... Qh4#`.
+    pub fn when(&self, game: &PgnGame) -> String {
+        if self.ply == 0 {
+            return "at the start".to_string();
+        }
+        let number = self.ply.div_ceil(2);
+        let dots = if self.ply % 2 == 1 { "." } else { "..." };
+        format!("after {}{} {}", number, dots, game.moves[self.ply - 1])
+    }
+}
+
+/// Returns the position `game` starts from: the one in its `FEN` tag if
+/// it has one, `None` if that FEN is invalid.
+pub fn starting_board(game: &PgnGame) -> Option<Board> {
+    match game.tag("FEN") {
+        Some(fen) => Board::from_str(fen).ok(),
+        None => Some(Board::default()),
+    }
+}
+
+/// Finds the games in which `position` occurs, comparing Zobrist hashes,
+/// so the side to move, castling rights and en passant square have to
+/// match too.
+///
+/// Games are replayed from their `FEN` tag if they have one; a game with
+/// an invalid FEN or illegal move is only searched up to the error.
+///
+/// # Example
+///
+/// ```
+/// let games = parse_pgn(&fs::read_to_string("games.pgn")?);
+/// for found in find_position(&games, &Board::default()) {
+///     println!("game {} {}", found.game + 1, found.when(&games[found.game]));
+/// }
+/// ```
+pub fn find_position(games: &[PgnGame], position: &Board) -> Vec<PositionMatch> {
+    let hash = position.get_hash();
+    let mut matches = Vec::new();
+    for (index, game) in games.iter().enumerate() {
+        let Some(mut board) = starting_board(game) else {
+            continue;
+        };
+        let mut ply = 0;
+        loop {
+            if board.get_hash() == hash {
+                matches.push(PositionMatch { game: index, ply });
+                break;
+            }
+            let Some(mv) = game.moves.get(ply).and_then(|san| parse_san(&board, san)) else {
+                break;
+            };
+            board = board.make_move_new(mv);
+            ply += 1;
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::pgn::{parse_pgn, write_pgn};
+    use crate::testing::TempDir;
+
+    #[test]
+    fn converts_dates() {
+        assert_eq!(pgn_date(0), "1970.01.01");
+        assert_eq!(pgn_date(11_016), "2000.02.29");
+        assert_eq!(pgn_date(19_860), "2024.05.17");
+        assert_eq!(pgn_date(-1), "1969.12.31");
+    }
+
+    #[test]
+    fn appends_games() {
+        let dir = TempDir::new("archive");
+        // The archive's directory is created with the first game.
+        let path = dir.join("archive").join(ARCHIVE_FILE);
+        let mut game = Game::new_multi();
+        for mv in ["f3", "e5", "g4", "Qh4#"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        append_game(&path, &write_pgn(&game, &[("White", "Anna".to_string())])).unwrap();
+        append_game(&path, &write_pgn(&game, &[("White", "Bob".to_string())])).unwrap();
+
+        let games = parse_pgn(&fs::read_to_string(&path).unwrap());
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].tag("White"), Some("Bob"));
+        assert_eq!(games[1].result(), Some("0-1"));
+    }
+
+    #[test]
+    fn finds_positions() {
+        let games = parse_pgn(
+            "[White \"Anna\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0\n\n\
+             [White \"Bob\"]\n\n1. Nf3 Nc6 2. e4 e5 0-1\n\n\
+             [White \"Carl\"]\n\n1. d4 d5 *\n",
+        );
+        let position =
+            Board::from_str("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let matches = find_position(&games, &position);
+        assert_eq!(
+            matches,
+            [
+                PositionMatch { game: 0, ply: 4 },
+                PositionMatch { game: 1, ply: 4 }
+            ]
+        );
+        assert_eq!(matches[1].when(&games[1]), "after 2... e5");
+        let start = find_position(&games, &Board::default());
+        assert_eq!(start.len(), 3);
+        assert_eq!(start[2].when(&games[2]), "at the start");
+    }
+}
@@ -0,0 +1,343 @@
+use crate::adjudication::Adjudicated;
+use crate::config::parse_string;
+use crate::pgn::write_pgn;
+use crate::profile::Outcome;
+use crate::selfplay::{Ending, MatchConfig, MatchGame, PlayerConfig};
+use chess::Color;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// How the engines of an arena are paired.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// Every engine plays every other.
+    #[default]
+    RoundRobin,
+    /// The first engine plays every other, which don't play each other.
+    Gauntlet,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "round-robin" | "roundrobin" => Ok(Format::RoundRobin),
+            "gauntlet" => Ok(Format::Gauntlet),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Settings of a tournament between several engines, built-in ones set
+/// up differently or external UCI engines.
+///
+/// Arena files hold `key = value` settings like match files, see
+/// [`MatchConfig`], with the settings of each engine in a section named
+/// after it, in the order the engines are ranked before the first game:
+///
+/// ```toml
+/// format = "gauntlet"
+/// games = 4
+/// pgn = "gauntlet.pgn"
+///
+/// [adjudication]
+/// win_score = 800
+///
+/// [rchess]
+/// depth = 4
+///
+/// [mcts]
+/// engine = mcts
+///
+/// [stockfish]
+/// command = "stockfish"
+/// move_time = 100
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArenaConfig {
+    pub format: Format,
+    /// The engines with their names.
+    pub engines: Vec<(String, PlayerConfig)>,
+    /// Settings of the games each pair of engines plays: their number,
+    /// openings, move limit and adjudication. Its engines and SPRT
+    /// aren't used.
+    pub games: MatchConfig,
+    /// File all the games are saved to.
+    pub pgn: PathBuf,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            format: Format::default(),
+            engines: Vec::new(),
+            games: MatchConfig::default(),
+            pgn: PathBuf::from("arena.pgn"),
+        }
+    }
+}
+
+impl FromStr for ArenaConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = ArenaConfig::default();
+        let mut section = String::new();
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = format!("{}.", name.trim());
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+            let key = format!("{}{}", section, key.trim());
+            config
+                .set(&key, &parse_string(value.trim()))
+                .map_err(|e| format!("line {}: {}", number + 1, e))?;
+        }
+        Ok(config)
+    }
+}
+
+impl ArenaConfig {
+    /// Changes the setting `key`: `format`, `pgn`, a setting of the
+    /// games as in [`MatchConfig::set`], e.g. `games` or
+    /// `adjudication.win_score`, or an engine's setting prefixed with its
+    /// name, e.g. `stockfish.command`, which adds the engine if it's new.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming an unknown setting or invalid value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut config = ArenaConfig::default();
+    /// config.set("mcts.engine", "mcts")?;
+    /// ```
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key.split_once('.') {
+            Some(("adjudication", _)) => self.games.set(key, value),
+            Some((name, key)) => {
+                let index = match self.engines.iter().position(|(engine, _)| engine == name) {
+                    Some(index) => index,
+                    None => {
+                        self.engines
+                            .push((name.to_string(), PlayerConfig::default()));
+                        self.engines.len() - 1
+                    }
+                };
+                self.engines[index].1.set(key, value)
+            }
+            None => {
+                match key {
+                    "format" => {
+                        self.format = value
+                            .parse()
+                            .map_err(|_| format!("invalid {} `{}`", key, value))?
+                    }
+                    "pgn" => self.pgn = PathBuf::from(value),
+                    _ => self.games.set(key, value)?,
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the indexes of the pairs of engines playing each other,
+    /// the first of each playing White in their first game.
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let count = self.engines.len();
+        match self.format {
+            Format::RoundRobin => (0..count)
+                .flat_map(|a| (a + 1..count).map(move |b| (a, b)))
+                .collect(),
+            Format::Gauntlet => (1..count).map(|b| (0, b)).collect(),
+        }
+    }
+
+    /// Returns the match between engines `a` and `b`.
+    pub fn pairing(&self, a: usize, b: usize) -> MatchConfig {
+        MatchConfig {
+            a: self.engines[a].1.clone(),
+            b: self.engines[b].1.clone(),
+            sprt: None,
+            ..self.games.clone()
+        }
+    }
+}
+
+/// Points and games of every engine of an arena against every other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crosstable {
+    names: Vec<String>,
+    /// Points and games of each engine against each other, by index.
+    results: Vec<Vec<(f64, u32)>>,
+}
+
+impl Crosstable {
+    /// Creates the crosstable of the engines `names` before any game.
+    pub fn new(names: Vec<String>) -> Self {
+        let results = vec![vec![(0.0, 0); names.len()]; names.len()];
+        Self { names, results }
+    }
+
+    /// Records a game between engines `a` and `b` with `outcome` for `a`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut table = Crosstable::new(vec!["a".to_string(), "b".to_string()]);
+    /// table.record(0, 1, Outcome::Draw);
+    /// assert_eq!(table.points(1), 0.5);
+    /// ```
+    pub fn record(&mut self, a: usize, b: usize, outcome: Outcome) {
+        let score = outcome.score();
+        self.results[a][b].0 += score;
+        self.results[a][b].1 += 1;
+        self.results[b][a].0 += 1.0 - score;
+        self.results[b][a].1 += 1;
+    }
+
+    /// Returns the points engine `engine` scored.
+    pub fn points(&self, engine: usize) -> f64 {
+        self.results[engine].iter().map(|(points, _)| points).sum()
+    }
+
+    /// Returns the number of games engine `engine` played.
+    pub fn games(&self, engine: usize) -> u32 {
+        self.results[engine].iter().map(|(_, games)| games).sum()
+    }
+
+    /// Returns the engines' indexes from the most points to the fewest,
+    /// the ones listed first ahead on equal points.
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut ranking: Vec<_> = (0..self.names.len()).collect();
+        ranking.sort_by(|a, b| self.points(*b).total_cmp(&self.points(*a)));
+        ranking
+    }
+}
+
+impl fmt::Display for Crosstable {
+    /// Writes a row for every engine, in ranking order, with its points,
+    /// games and its points against the engine ranked in each column,
+    /// `-` if they didn't play.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ranking = self.ranking();
+        let width = self.names.iter().map(String::len).max().unwrap_or(0).max(6);
+        write!(
+            f,
+            "{:>2}  {:<width$}  {:>6}  {:>5}",
+            "#", "Engine", "Points", "Games"
+        )?;
+        for rank in 1..=ranking.len() {
+            write!(f, "  {:>5}", rank)?;
+        }
+        for (rank, engine) in ranking.iter().enumerate() {
+            write!(
+                f,
+                "\n{:>2}  {:<width$}  {:>6}  {:>5}",
+                rank + 1,
+                self.names[*engine],
+                self.points(*engine),
+                self.games(*engine)
+            )?;
+            for opponent in &ranking {
+                let cell = match self.results[*engine][*opponent] {
+                    _ if opponent == engine => "*".to_string(),
+                    (_, 0) => "-".to_string(),
+                    (points, games) => format!("{}/{}", points, games),
+                };
+                write!(f, "  {:>5}", cell)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Exports a game of an arena as PGN, with the names of the engines,
+/// its round and, for games not played to the end, its result and how
+/// it ended in a `Termination` tag.
+pub fn write_game(played: &MatchGame, white: &str, black: &str, round: &str) -> String {
+    let mut tags = vec![
+        ("Event", "rChess arena".to_string()),
+        ("Round", round.to_string()),
+        ("White", white.to_string()),
+        ("Black", black.to_string()),
+    ];
+    let ended = match played.ending {
+        Ending::Played => None,
+        Ending::MoveLimit => Some(("1/2-1/2", "move limit")),
+        Ending::Adjudicated(Adjudicated::Win(Color::White)) => Some(("1-0", "adjudication")),
+        Ending::Adjudicated(Adjudicated::Win(Color::Black)) => Some(("0-1", "adjudication")),
+        Ending::Adjudicated(Adjudicated::Draw) => Some(("1/2-1/2", "adjudication")),
+        Ending::Forfeit(Color::White) => Some(("0-1", "rules infraction")),
+        Ending::Forfeit(Color::Black) => Some(("1-0", "rules infraction")),
+    };
+    if let Some((result, termination)) = ended {
+        tags.push(("Result", result.to_string()));
+        tags.push(("Termination", termination.to_string()));
+    }
+    write_pgn(&played.game, &tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::EngineKind;
+
+    #[test]
+    fn reads_arena_files() {
+        let config = ArenaConfig::from_str(
+            "format = \"gauntlet\"\ngames = 4\n\n[adjudication]\nwin_score = 800\n\n\
+             [rchess]\ndepth = 3\n\n[mcts]\nengine = mcts\n\n[other]\ncommand = \"engine --uci\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.format, Format::Gauntlet);
+        assert_eq!(config.games.games, 4);
+        let names: Vec<_> = config
+            .engines
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["rchess", "mcts", "other"]);
+        assert_eq!(config.engines[0].1.depth, 3);
+        assert_eq!(config.pairings(), [(0, 1), (0, 2)]);
+        let pairing = config.pairing(1, 2);
+        assert_eq!(pairing.a.engine, EngineKind::Mcts);
+        assert_eq!(pairing.b.command.as_deref(), Some("engine --uci"));
+        assert_eq!(pairing.games, 4);
+
+        let mut config = config;
+        config.set("format", "round-robin").unwrap();
+        assert_eq!(config.pairings(), [(0, 1), (0, 2), (1, 2)]);
+        assert!(config.set("format", "swiss").is_err());
+        assert!(config.set("rchess.depth", "deep").is_err());
+    }
+
+    #[test]
+    fn keeps_a_crosstable() {
+        let names = ["a", "b", "c"].map(str::to_string).to_vec();
+        let mut table = Crosstable::new(names);
+        table.record(0, 1, Outcome::Loss);
+        table.record(1, 0, Outcome::Draw);
+        table.record(1, 2, Outcome::Win);
+        assert_eq!(table.points(1), 2.5);
+        assert_eq!(table.games(1), 3);
+        assert_eq!(table.ranking(), [1, 0, 2]);
+        assert_eq!(
+            table.to_string(),
+            " #  Engine  Points  Games      1      2      3\n \
+             1  b          2.5      3      *  1.5/2    1/1\n \
+             2  a          0.5      2  0.5/2      *      -\n \
+             3  c            0      1    0/1      -      *"
+        );
+    }
+}
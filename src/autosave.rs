@@ -0,0 +1,205 @@
+use crate::ai::Difficulty;
+use crate::config::config_dir;
+use crate::game::Game;
+use crate::personality::Personality;
+use crate::pgn::{parse_pgn, replay, write_pgn, GameTags};
+use chess::Color;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Name of the recovery file inside the config directory.
+const RECOVERY_FILE: &str = "recovery.pgn";
+
+/// Returns where the in-progress game is saved.
+pub fn recovery_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(RECOVERY_FILE))
+}
+
+/// Keeps a copy of the in-progress game on disk so it can be resumed
+/// if the program crashes or is closed mid-game.
+///
+/// The game is stored as PGN, with extra tags recording the game mode.
+pub struct Autosave {
+    path: PathBuf,
+    /// The human player's color and the AI difficulty in single-player games.
+    player: Option<(Color, Difficulty)>,
+    /// Name of the profile the single-player game is recorded in.
+    profile: Option<String>,
+}
+
+impl Autosave {
+    /// Creates an autosave writing to `path` for a game set up as described by `player`.
+    pub fn new(path: PathBuf, player: Option<(Color, Difficulty)>) -> Self {
+        Self {
+            path,
+            player,
+            profile: None,
+        }
+    }
+
+    /// Sets the name of the profile the game is recorded in.
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Swaps the human player's color in a single-player game, e.g. for
+    /// a rematch.
+    pub fn swap_colors(&mut self) {
+        if let Some((color, _)) = &mut self.player {
+            *color = !*color;
+        }
+    }
+
+    /// Returns the human player's color and the AI difficulty in single-player games.
+    pub fn player(&self) -> Option<(Color, Difficulty)> {
+        self.player
+    }
+
+    /// Returns the name of the profile the game is recorded in.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Writes the game to the recovery file.
+    ///
+    /// The game is written to a temporary file first and then moved
+    /// in place, so a crash while saving doesn't corrupt the last save.
+    pub fn save(&self, game: &Game) -> io::Result<()> {
+        let mut tags = Vec::new();
+        match self.player {
+            Some((color, difficulty)) => {
+                tags.push(("Mode", "single".to_string()));
+                tags.push(("PlayerColor", format!("{:?}", color)));
+                tags.push(("Difficulty", format!("{:?}", difficulty)));
+            }
+            None => tags.push(("Mode", "multi".to_string())),
+        }
+        if game.personality() != Personality::Balanced {
+            tags.push(("Personality", game.personality().to_string()));
+        }
+        if let Some(profile) = &self.profile {
+            tags.push(("Profile", profile.clone()));
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, write_pgn(game, &tags))?;
+        fs::rename(&temp, &self.path)
+    }
+
+    /// Removes the recovery file, e.g. once the game is over.
+    pub fn discard(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Restores the game saved in the recovery file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or doesn't hold
+    /// a game saved by [`Autosave::save`].
+    pub fn resume(path: &Path) -> io::Result<(Game, Self)> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let saved = parse_pgn(&fs::read_to_string(path)?)
+            .into_iter()
+            .next()
+            .ok_or_else(|| invalid("no game found"))?;
+
+        let player = match saved.tag("Mode") {
+            Some("multi") => None,
+            Some("single") => {
+                let color = match saved.tag("PlayerColor") {
+                    Some("White") => Color::White,
+                    Some("Black") => Color::Black,
+                    _ => return Err(invalid("invalid player color")),
+                };
+                let difficulty = saved
+                    .tag("Difficulty")
+                    .and_then(|d| Difficulty::from_str(d).ok())
+                    .ok_or_else(|| invalid("invalid difficulty"))?;
+                Some((color, difficulty))
+            }
+            _ => return Err(invalid("invalid game mode")),
+        };
+        let personality = match saved.tag("Personality") {
+            Some(name) => {
+                Personality::from_str(name).map_err(|_| invalid("invalid personality"))?
+            }
+            None => Personality::Balanced,
+        };
+        let game = match player {
+            Some((color, difficulty)) => {
+                Game::new_single(color, difficulty).with_personality(personality)
+            }
+            None => Game::new_multi(),
+        };
+        let game =
+            replay(&saved, game.with_tags(GameTags::read(&saved))).map_err(|e| invalid(&e))?;
+        let autosave = Self::new(path.to_path_buf(), player)
+            .with_profile(saved.tag("Profile").map(str::to_string));
+        Ok((game, autosave))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::variant::Variant;
+    use chess::Board;
+
+    #[test]
+    fn resumes_saved_game() {
+        let dir = TempDir::new("autosave");
+        let path = dir.join("single.pgn");
+        let autosave = Autosave::new(path.clone(), Some((Color::Black, Difficulty::Hard)))
+            .with_profile(Some("Anna".to_string()));
+        let mut game = Game::new_single(Color::Black, Difficulty::Hard)
+            .with_variant(Variant::ThreeCheck)
+            .with_personality(Personality::Drawish);
+        for mv in ["e4", "d5", "exd5", "Qxd5", "Nc3"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        autosave.save(&game).unwrap();
+
+        let (resumed, resumed_autosave) = Autosave::resume(&path).unwrap();
+        assert_eq!(resumed.board(), game.board());
+        assert_eq!(resumed.moves(), game.moves());
+        assert_eq!(resumed.variant(), Variant::ThreeCheck);
+        assert_eq!(resumed.personality(), Personality::Drawish);
+        assert_eq!(
+            resumed_autosave.player(),
+            Some((Color::Black, Difficulty::Hard))
+        );
+        assert_eq!(resumed_autosave.profile(), Some("Anna"));
+
+        autosave.discard().unwrap();
+        assert!(!path.exists());
+        assert!(Autosave::resume(&path).is_err());
+    }
+
+    #[test]
+    fn resumes_game_from_position() {
+        let dir = TempDir::new("autosave");
+        let path = dir.join("multi.pgn");
+        let board = Board::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let mut game = Game::new_multi().with_position(board);
+        game.make_move_from_str("e5d6", true).unwrap();
+        let autosave = Autosave::new(path.clone(), None);
+        autosave.save(&game).unwrap();
+
+        let (resumed, resumed_autosave) = Autosave::resume(&path).unwrap();
+        assert_eq!(resumed.starting_position(), &board);
+        assert_eq!(resumed.board(), game.board());
+        assert_eq!(resumed_autosave.player(), None);
+        assert_eq!(resumed_autosave.profile(), None);
+        autosave.discard().unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+use crate::ai::{search, EngineOptions};
+use crate::annotate::{format_score, json_string, mate_in};
+use crate::legality::validate_fen;
+use chess::{ChessMove, Color};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// How the evaluations of a batch of positions are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// A line per position with its FEN, score and best move.
+    Text,
+    /// An array with an object per position.
+    Json,
+    /// A header line, then a line of comma-separated values per position.
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The engine's verdict on one position of a batch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionEval {
+    pub fen: String,
+    /// The score in centipawns from White's point of view and the best
+    /// move, `None` if there's no legal move, or why the FEN couldn't be
+    /// evaluated.
+    pub result: Result<(i32, Option<ChessMove>), String>,
+}
+
+/// Runs the engine over many positions at once, for studies of large
+/// sets of positions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchEvaluator {
+    /// Depth every position is searched to.
+    pub depth: u32,
+    /// Number of positions searched at once.
+    pub threads: usize,
+}
+
+impl Default for BatchEvaluator {
+    fn default() -> Self {
+        Self {
+            depth: 5,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+}
+
+impl BatchEvaluator {
+    /// Evaluates the positions given by `fens`, spread over
+    /// [`BatchEvaluator::threads`] threads.
+    ///
+    /// # Returns
+    ///
+    /// The evaluation of each position in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let fens = ["rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string()];
+    /// let evals = BatchEvaluator::default().evaluate(&fens);
+    /// println!("{}", write_evals(&evals, OutputFormat::Json));
+    /// ```
+    pub fn evaluate(&self, fens: &[String]) -> Vec<PositionEval> {
+        let next = AtomicUsize::new(0);
+        let evals = Mutex::new(vec![None; fens.len()]);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.clamp(1, fens.len().max(1)) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(fen) = fens.get(index) else {
+                        break;
+                    };
+                    let eval = PositionEval {
+                        fen: fen.clone(),
+                        result: self.evaluate_fen(fen),
+                    };
+                    evals.lock().unwrap()[index] = Some(eval);
+                });
+            }
+        });
+        evals
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(Option::unwrap)
+            .collect()
+    }
+
+    /// Searches the position of `fen`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of what's wrong with an invalid FEN or an
+    /// impossible position.
+    fn evaluate_fen(&self, fen: &str) -> Result<(i32, Option<ChessMove>), String> {
+        let board = validate_fen(fen).map_err(|e| e.to_string())?;
+        let result = search(&board, self.depth, &EngineOptions::default());
+        let score = match board.side_to_move() {
+            Color::White => result.score,
+            Color::Black => -result.score,
+        };
+        Ok((score, result.best_move))
+    }
+}
+
+/// Writes `evals` in `format`. Scores are in pawns in the text format,
+/// and in centipawns with a separate number of moves to mate in the
+/// others, for other programs to read.
+pub fn write_evals(evals: &[PositionEval], format: OutputFormat) -> String {
+    let best = |mv: &Option<ChessMove>| mv.map_or(String::new(), |mv| mv.to_string());
+    match format {
+        OutputFormat::Text => evals
+            .iter()
+            .map(|eval| match &eval.result {
+                Ok((score, mv)) => {
+                    format!("{}: {} {}\n", eval.fen, format_score(*score), best(mv))
+                }
+                Err(e) => format!("{}: error: {}\n", eval.fen, e),
+            })
+            .collect(),
+        OutputFormat::Json => {
+            let objects: Vec<String> = evals
+                .iter()
+                .map(|eval| match &eval.result {
+                    Ok((score, mv)) => format!(
+                        "{{\"fen\":{},\"score\":{},\"mate\":{},\"best_move\":{}}}",
+                        json_string(&eval.fen),
+                        score,
+                        mate_in(*score).map_or("null".to_string(), |moves| moves.to_string()),
+                        mv.map_or("null".to_string(), |mv| json_string(&mv.to_string()))
+                    ),
+                    Err(e) => format!(
+                        "{{\"fen\":{},\"error\":{}}}",
+                        json_string(&eval.fen),
+                        json_string(e)
+                    ),
+                })
+                .collect();
+            format!("[{}]\n", objects.join(","))
+        }
+        OutputFormat::Csv => {
+            let mut csv = String::from("fen,score,mate,best_move,error\n");
+            for eval in evals {
+                match &eval.result {
+                    Ok((score, mv)) => csv.push_str(&format!(
+                        "{},{},{},{},\n",
+                        eval.fen,
+                        score,
+                        mate_in(*score).map_or(String::new(), |moves| moves.to_string()),
+                        best(mv)
+                    )),
+                    Err(e) => {
+                        csv.push_str(&format!("{},,,,\"{}\"\n", eval.fen, e.replace('"', "\"\"")))
+                    }
+                }
+            }
+            csv
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_positions_in_order() {
+        let fens = [
+            // White mates with Ra8.
+            "6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1",
+            "not a fen",
+            // Black is a queen up.
+            "3qk3/8/8/8/8/8/8/4K3 b - - 0 1",
+        ]
+        .map(String::from);
+        let evaluator = BatchEvaluator {
+            depth: 2,
+            threads: 2,
+        };
+        let evals = evaluator.evaluate(&fens);
+        assert_eq!(evals.len(), 3);
+        assert_eq!(
+            evals[0].result.as_ref().unwrap().1,
+            Some(ChessMove::from_str("a1a8").unwrap())
+        );
+        assert!(evals[1].result.is_err());
+        assert!(evals[2].result.as_ref().unwrap().0 < -500);
+
+        let csv = write_evals(&evals, OutputFormat::Csv);
+        assert!(csv.contains("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1,99999,1,a1a8,\n"));
+        let json = write_evals(&evals, OutputFormat::Json);
+        assert!(json.starts_with("[{\"fen\":\"6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1\",\"score\""));
+        assert!(json.contains("\"mate\":1,\"best_move\":\"a1a8\""));
+        assert!(json.contains("{\"fen\":\"not a fen\",\"error\":"));
+        assert!(write_evals(&evals, OutputFormat::Text).contains(": #1 a1a8\n"));
+        assert_eq!(OutputFormat::from_str("CSV"), Ok(OutputFormat::Csv));
+    }
+}
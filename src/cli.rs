@@ -1,92 +1,749 @@
-use crate::game::{Game, Status};
-use chess::{Color, Piece};
-use rand::random_bool;
-use std::io::{self, Write};
-
-pub fn intro() {
-    println!("WELCOME TO CHESS!!");
-    let valid_inputs = ["quit", "single", "multi"];
-    let input: String = loop {
-        print!("Select game mode (single or multi, quit to exit): ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            eprintln!("Error reading input, please try again.");
-            continue;
+use crate::adaptive::AdaptiveSkill;
+use crate::ai::{
+    search, search_reporting, Baseline, Difficulty, EngineOptions, EvalWeights, SearchLimits,
+    SearchParams, BENCH_POSITIONS, DIFFICULTIES,
+};
+use crate::annotate::{evals_json, format_score, Annotator, Tactic, GRAPH_WIDTH};
+use crate::annotation::{nag_glyph, parse_nag};
+use crate::archive::{self, append_game, archive_path, today};
+use crate::arena::{write_game, ArenaConfig, Crosstable};
+use crate::autosave::{recovery_path, Autosave};
+use crate::batch::{write_evals, BatchEvaluator, OutputFormat};
+use crate::book::{set_book, BookOptions, OpeningBook};
+use crate::clock::{Clock, TimeControl};
+use crate::command::{help, parse_command, spec, Command, Mode};
+use crate::config::{config_dir, Config, MAX_ZOOM};
+use crate::coords::{CoordsMode, CoordsTrainer, ROUND_TIME};
+use crate::correspondence::{list_games, CorrespondenceGame};
+use crate::document::DocumentFormat;
+use crate::drill::{Drill, DrillAnswer, Repertoire, BUILTIN_LINES};
+use crate::editor::{Editor, EDIT_COMMANDS};
+use crate::endgame::{Goal, Trainer, Verdict, EXERCISES};
+use crate::error::ChessError;
+use crate::events::{Event, EventLoop};
+use crate::explorer::{PositionIndex, Theory};
+use crate::game::{Game, GameEvent, Status};
+use crate::heatmap::Heatmap;
+use crate::i18n::{Language, Text};
+use crate::info::PrettyInfo;
+use crate::input::read_line;
+use crate::legality::{parse_fen, validate_fen};
+use crate::network::{
+    valid_room, Capabilities, Connection, GameSettings, Hello, Message, DEFAULT_PORT,
+};
+use crate::notation::{figurine_san, file_char, parse_square, san_for_move};
+use crate::notify::{Alert, Notifier};
+use crate::odds::Odds;
+use crate::paths::{PathPuzzle, PATH_PIECES};
+use crate::personality::Personality;
+use crate::pgn::{format_time, parse_pgn, result_token, write_pgn, GameTags, PgnGame};
+use crate::profile::{profiles_path, Outcome, ProfileStore};
+use crate::repertoire::OpeningTree;
+use crate::report::PositionReport;
+use crate::rng::random_bool;
+use crate::script::run_script;
+use crate::selfplay::{MatchConfig, PlayerConfig};
+use crate::session::{self, SessionGame};
+use crate::simul::{Simul, DEFAULT_BOARDS};
+use crate::speech::{describe_board, describe_moves, move_hook, notate_moves};
+use crate::sprt::SprtResult;
+use crate::standings::Standings;
+use crate::stats::DatabaseStats;
+use crate::strength::{performance, StrengthLimit, GREEDY_ELO, MAX_ELO, MIN_ELO};
+use crate::svg::SvgOptions;
+use crate::terminal::capabilities;
+use crate::tt::{SharedTable, TranspositionTable};
+use crate::tune::Spsa;
+use crate::variant::Variant;
+use chess::{Board, BoardBuilder, ChessMove, Color, Piece, Square};
+use log::{info, warn};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How games are shown to the player, set up at startup from the
+/// command line and the configuration and passed down to everything
+/// printing a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Display {
+    /// Whether the board is described in words, for screen readers,
+    /// instead of being drawn.
+    pub accessible: bool,
+    /// The language menus and announcements are written in.
+    pub language: Language,
+    /// How large the board is drawn, from 1, its normal size, to
+    /// [`MAX_ZOOM`], see [`large_diagram`].
+    pub zoom: u8,
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Self {
+            accessible: false,
+            language: Language::default(),
+            zoom: 1,
         }
-        let input = input.trim().to_lowercase();
-        if valid_inputs.contains(&input.as_str()) {
-            break input;
-        } else {
-            eprintln!("Illegal input, please try again.");
+    }
+}
+
+/// Returns the tags a new game starts with: `tags`, those set on the
+/// command line, with the event and site of the configuration if they
+/// aren't.
+fn game_tags(tags: &GameTags) -> GameTags {
+    let mut tags = tags.clone();
+    let config = Config::load();
+    tags.event = tags.event.or(config.event);
+    tags.site = tags.site.or(config.site);
+    tags
+}
+
+/// Returns the tags of a game against the AI, from `tags` like
+/// [`game_tags`], with the names of the player of `player_color`,
+/// `Player` if they have none, and of the AI.
+fn single_player_tags(
+    tags: &GameTags,
+    player_color: Color,
+    name: Option<&str>,
+    difficulty: Difficulty,
+) -> GameTags {
+    let mut tags = game_tags(tags);
+    tags.set_name(player_color, name.unwrap_or("Player"));
+    let ai = match difficulty {
+        Difficulty::Elo(elo) => format!("rChess AI ({} Elo)", elo),
+        difficulty => format!("rChess AI ({:?})", difficulty),
+    };
+    tags.set_name(!player_color, &ai);
+    tags
+}
+
+/// Loads the NNUE network at `path` for the engine to evaluate with,
+/// reporting a file that can't be used.
+#[cfg(feature = "nnue")]
+pub fn load_network(path: Option<&Path>) {
+    let Some(path) = path else {
+        return;
+    };
+    match crate::nnue::Network::load(path) {
+        Ok(network) => {
+            info!("Loaded NNUE network {}", path.display());
+            crate::nnue::set_network(network)
         }
+        Err(e) => eprintln!("Error loading {}: {}", path.display(), e),
+    }
+}
+
+#[cfg(not(feature = "nnue"))]
+pub fn load_network(path: Option<&Path>) {
+    if let Some(path) = path {
+        eprintln!(
+            "Ignoring {}: rChess was built without the nnue feature",
+            path.display()
+        );
+    }
+}
+
+/// Loads the opening book at `path` for the AI to play from, reporting
+/// a file that can't be used.
+pub fn load_book(path: Option<&Path>) {
+    let Some(path) = path else {
+        return;
+    };
+    match OpeningBook::load(path) {
+        Ok(book) => {
+            info!(
+                "Loaded opening book {} of {} moves",
+                path.display(),
+                book.len()
+            );
+            set_book(book)
+        }
+        Err(e) => eprintln!("Error loading {}: {}", path.display(), e),
+    }
+}
+
+pub fn intro(display: Display, tags: &GameTags) {
+    println!("{}", display.language.text(Text::Welcome));
+    let config = Config::load();
+    let recovery = if config.autosave {
+        recovery_path()
+    } else {
+        None
     };
+    if let Some(path) = recovery.as_deref().filter(|path| path.exists()) {
+        let input = prompt(
+            display.language.text(Text::ResumeGame),
+            &["yes", "no"],
+            display.language,
+        );
+        if input == "yes" {
+            match Autosave::resume(path) {
+                Ok((game, autosave)) => {
+                    match autosave.player() {
+                        Some((player_color, difficulty)) => {
+                            let profile = autosave.profile().map(str::to_string);
+                            play_single_player(
+                                game,
+                                player_color,
+                                difficulty,
+                                profile,
+                                Some(autosave),
+                                None,
+                                None,
+                                display,
+                            )
+                        }
+                        None => play_two_player(game, Some(autosave), None, display),
+                    }
+                    return;
+                }
+                Err(e) => eprintln!("Can't resume the game: {}", e),
+            }
+        }
+        if let Err(e) = fs::remove_file(path) {
+            eprintln!("Error removing {}: {}", path.display(), e);
+        }
+    }
+
+    let input = prompt(
+        display.language.text(Text::SelectMode),
+        &["quit", "single", "multi", "match"],
+        display.language,
+    );
     match input.as_str() {
         "quit" => (),
-        "single" => single_player(),
-        "multi" => two_player(),
+        "single" => single_player(recovery, None, display, tags),
+        "multi" => two_player(recovery, None, display, tags),
+        "match" => match_play(recovery, display, tags),
+        _ => unreachable!(),
+    }
+}
+
+/// Asks for the opponent and length of a best-of-N match and plays it.
+/// The length of a network match is set by its host.
+fn match_play(recovery: Option<PathBuf>, display: Display, tags: &GameTags) {
+    let opponent = prompt(
+        display.language.text(Text::SelectOpponent),
+        &["ai", "human", "network"],
+        display.language,
+    );
+    if opponent == "network"
+        && prompt(
+            display.language.text(Text::HostOrJoin),
+            &["host", "join"],
+            display.language,
+        ) == "join"
+    {
+        let address = read_line(display.language.text(Text::EnterAddress));
+        let room = read_line(display.language.text(Text::EnterRoom));
+        let args: Vec<String> = [address.trim(), room.trim()]
+            .into_iter()
+            .filter(|arg| !arg.is_empty())
+            .map(String::from)
+            .collect();
+        return join(&args, display, tags);
+    }
+    let games = loop {
+        match read_line(display.language.text(Text::NumberOfGames))
+            .trim()
+            .parse()
+        {
+            Ok(games) if games > 0 => break games,
+            _ => eprintln!("{}", display.language.text(Text::IllegalInput)),
+        }
+    };
+    match opponent.as_str() {
+        "ai" => single_player(recovery, Some(games), display, tags),
+        "human" => two_player(recovery, Some(games), display, tags),
+        "network" => host_game(
+            None,
+            Some(games),
+            None,
+            &GameSettings::default(),
+            &Capabilities::default(),
+            display,
+            tags,
+        ),
         _ => unreachable!(),
     }
 }
 
-fn two_player() {
-    let mut game = Game::new_multi();
+/// Asks the user to pick one of `valid_inputs`, repeating the question
+/// until a valid (case-insensitive) answer is given.
+fn prompt(message: &str, valid_inputs: &[&str], language: Language) -> String {
     loop {
-        display_board(&game);
-        print!("Enter move: ");
-        io::stdout().flush().unwrap();
+        let input = read_line(message).trim().to_lowercase();
+        if valid_inputs.contains(&input.as_str()) {
+            break input;
+        } else {
+            eprintln!("{}", language.text(Text::IllegalInput));
+        }
+    }
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        let input = input.trim();
+/// Asks the user which variant to play.
+fn select_variant(language: Language) -> Variant {
+    let input = prompt(
+        language.text(Text::SelectVariant),
+        &["standard", "koth", "threecheck"],
+        language,
+    );
+    Variant::from_str(&input).unwrap()
+}
 
-        match input {
-            "quit" => break,
-            "undo" => {
-                if let Err(e) = game.undo() {
-                    println!("{}", e);
+/// Returns a game listener printing checks, the end of the game and
+/// every move of `game` in SAN, e.g. `Played: Nxe5+`, naming the
+/// opponent, given by their color and name, if they don't play at this
+/// terminal; the opponent takes the other color when the players swap
+/// colors for a rematch. In accessible mode moves are described in words
+/// by the listener added with [`add_move_hook`] instead.
+fn announcer(
+    game: &Game,
+    opponent: Option<(Color, &'static str)>,
+    display: Display,
+) -> impl FnMut(&GameEvent) {
+    let variant = game.variant();
+    // Figurines need Unicode; letters are written in the player's language.
+    let unicode = capabilities().unicode;
+    let notation: fn(&Board, ChessMove) -> String =
+        if unicode { figurine_san } else { san_for_move };
+    let language = display.language;
+    let opponent = Arc::new(Mutex::new(opponent));
+    let echoed = Arc::clone(&opponent);
+    let mut echo = notate_moves(game, notation, move |color, san| {
+        if display.accessible {
+            return;
+        }
+        let san = if unicode {
+            san
+        } else {
+            language.localize_san(&san)
+        };
+        match *echoed.lock().unwrap() {
+            Some((opponent, name)) if color == opponent => {
+                println!("{}", language.fill(Text::OpponentPlayed, &[name, &san]))
+            }
+            _ => println!("{}", language.fill(Text::Played, &[&san])),
+        }
+    });
+    move |event| match *event {
+        GameEvent::MoveMade(..) | GameEvent::Reset => echo(event),
+        GameEvent::MoveUndone(mv) => {
+            echo(event);
+            println!("{}", language.fill(Text::TookBack, &[&mv.to_string()]))
+        }
+        GameEvent::ColorsSwapped => {
+            if let Some((color, _)) = opponent.lock().unwrap().as_mut() {
+                *color = !*color;
+            }
+        }
+        GameEvent::CheckGiven(_) => println!("{}", language.text(Text::Check)),
+        GameEvent::GameEnded(Status::Checkmate(color)) => {
+            println!(
+                "{}",
+                language.fill(Text::Wins, &[language.color_name(color)])
+            )
+        }
+        GameEvent::GameEnded(Status::Stalemate) => println!("{}", language.text(Text::Stalemate)),
+        GameEvent::GameEnded(Status::VariantWin(color)) => println!(
+            "{}",
+            language.fill(
+                Text::VariantWins,
+                &[language.color_name(color), &variant.to_string()]
+            )
+        ),
+        GameEvent::GameEnded(Status::Resignation(color)) => println!(
+            "{}",
+            language.fill(
+                Text::Resigns,
+                &[language.color_name(!color), language.color_name(color)]
+            )
+        ),
+        GameEvent::GameEnded(Status::Timeout(color)) => println!(
+            "{}",
+            language.fill(
+                Text::OutOfTime,
+                &[language.color_name(!color), language.color_name(color)]
+            )
+        ),
+        GameEvent::GameEnded(Status::TimeoutDraw(color)) => println!(
+            "{}",
+            language.fill(
+                Text::OutOfTimeDraw,
+                &[language.color_name(color), language.color_name(!color)]
+            )
+        ),
+        GameEvent::GameEnded(Status::DrawAgreed) => println!("{}", language.text(Text::DrawAgreed)),
+        _ => (),
+    }
+}
+
+/// Adds the listeners describing the moves of `game` in words: printing
+/// them in accessible mode and passing them to the announcement command
+/// set in the configuration, if any. Also adds the notifier set there,
+/// alerting the player to checks and the end of the game.
+fn add_move_hook(game: &mut Game, display: Display) {
+    if display.accessible {
+        let printer = describe_moves(game, |color, text| println!("{:?}: {}", color, text));
+        game.subscribe(printer);
+    }
+    let config = Config::load();
+    if let Some(command) = config.announce_command {
+        let hook = move_hook(command, game);
+        game.subscribe(hook);
+    }
+    if config.notify != Notifier::Off {
+        game.subscribe(config.notify.listener());
+    }
+}
+
+/// Saves the game if autosave is enabled, reporting any error.
+fn save_progress(autosave: &Option<Autosave>, game: &Game) {
+    if let Some(autosave) = autosave {
+        if let Err(e) = autosave.save(game) {
+            eprintln!("Error saving the game: {}", e);
+        }
+    }
+}
+
+/// Adds a finished game to the archive of played games, with `tags`
+/// naming the event and players, unless archiving is turned off.
+fn archive_game(game: &Game, tags: &[(&str, String)]) {
+    if game.status() == Status::Ongoing || !Config::load().archive {
+        return;
+    }
+    let Some(path) = archive_path() else {
+        return;
+    };
+    let mut tags = tags.to_vec();
+    tags.push(("Date", today()));
+    match append_game(&path, &write_pgn(game, &tags)) {
+        Ok(()) => info!("Game archived in {}", path.display()),
+        Err(e) => eprintln!("Error archiving the game: {}", e),
+    }
+}
+
+/// Reads the repertoire file set in the configuration, if any,
+/// reporting a file that can't be used.
+fn load_repertoire() -> Option<OpeningTree> {
+    let path = Config::load().repertoire_file?;
+    match OpeningTree::load(&path) {
+        Ok(tree) => Some(tree),
+        Err(e) => {
+            eprintln!("Error in the repertoire {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Presses `clock`, if the game has one, charging the mover at most
+/// `spent` if given, see [`Clock::press_spent`], and records the time
+/// they have left with the last move of `game` for its PGN.
+fn press_clock(game: &mut Game, clock: &mut Option<Clock>, spent: Option<Duration>) {
+    if let Some(clock) = clock {
+        let pressed = match spent {
+            Some(spent) => clock.press_spent(spent),
+            None => clock.press(),
+        };
+        if let Some(color) = pressed {
+            game.set_clock(clock.remaining(color));
+        }
+    }
+}
+
+/// Tells the player if the last move of `game` left their repertoire,
+/// and what the book move was.
+fn announce_deviation(repertoire: &Option<OpeningTree>, game: &Game) {
+    let Some(tree) = repertoire else {
+        return;
+    };
+    if let Some(deviation) = tree.deviation(game.starting_position(), game.moves()) {
+        if deviation.ply + 1 == game.moves().len() {
+            println!("{}", deviation.describe());
+        }
+    }
+}
+
+/// Prints the post-game report on the repertoire: where the game left
+/// it, if it did.
+fn repertoire_report(repertoire: &Option<OpeningTree>, game: &Game) {
+    let Some(tree) = repertoire else {
+        return;
+    };
+    match tree.deviation(game.starting_position(), game.moves()) {
+        Some(deviation) => println!("Repertoire: {}", deviation.describe()),
+        None => println!("Repertoire: the game didn't leave the repertoire"),
+    }
+}
+
+/// Records the result of a finished game of a series and prints the
+/// score. In match play the game is also kept in `rounds`, tagged with
+/// its round, for the PGN of the match.
+fn score_game(
+    standings: &mut Standings,
+    game: &Game,
+    rounds: &mut Vec<String>,
+    language: Language,
+) {
+    standings.record(game.status());
+    println!("{}", language.fill(Text::Score, &[&standings.to_string()]));
+    if standings.best_of().is_some() {
+        let mut tags = standings.round_tags();
+        tags.push(("Date", today()));
+        rounds.push(write_pgn(game, &tags));
+    }
+}
+
+/// Returns whether to play another game of a series. A match goes on
+/// until it's decided, when its winner is announced and its games can be
+/// saved; otherwise the players are asked for a rematch.
+fn play_on(standings: &Standings, rounds: &[String], language: Language) -> bool {
+    let Some(games) = standings.best_of() else {
+        return ask_rematch(language);
+    };
+    if !standings.is_decided() {
+        println!(
+            "{}",
+            language.fill(
+                Text::GameOf,
+                &[&(standings.games() + 1).to_string(), &games.to_string()]
+            )
+        );
+        return true;
+    }
+    match standings.leader() {
+        Some(winner) => println!(
+            "{}",
+            language.fill(Text::MatchWon, &[winner, &standings.to_string()])
+        ),
+        None => println!(
+            "{}",
+            language.fill(Text::MatchDrawn, &[&standings.to_string()])
+        ),
+    }
+    let input = read_line(language.text(Text::SaveMatch));
+    let path = input.trim();
+    if !path.is_empty() {
+        let pgn: String = rounds
+            .iter()
+            .map(|round| format!("{}\n\n", round.trim_end()))
+            .collect();
+        match fs::write(path, pgn) {
+            Ok(()) => println!(
+                "{}",
+                language.fill(Text::SavedGames, &[&rounds.len().to_string(), path])
+            ),
+            Err(e) => eprintln!("Error saving the match: {}", e),
+        }
+    }
+    false
+}
+
+/// Removes the saved copy of a game that has ended.
+fn discard_autosave(autosave: &Option<Autosave>) {
+    if let Some(autosave) = autosave {
+        if let Err(e) = autosave.discard() {
+            eprintln!("Error removing the saved game: {}", e);
+        }
+    }
+}
+
+fn two_player(recovery: Option<PathBuf>, best_of: Option<u32>, display: Display, tags: &GameTags) {
+    let mut tags = game_tags(tags);
+    for color in [Color::White, Color::Black] {
+        if tags.name(color).is_none() {
+            let input = read_line(
+                &display
+                    .language
+                    .fill(Text::EnterName, &[display.language.color_name(color)]),
+            );
+            if !input.trim().is_empty() {
+                tags.set_name(color, input.trim());
+            }
+        }
+    }
+    let game = Game::new_multi()
+        .with_tags(tags)
+        .with_variant(select_variant(display.language));
+    info!("Two-player {} game started", game.variant());
+    let autosave = recovery.map(|path| Autosave::new(path, None));
+    play_two_player(game, autosave, best_of, display);
+}
+
+/// Plays two-player games, keeping the score of the players across
+/// them: a match of `best_of` games, or as long as they want a rematch
+/// with colors swapped after each one.
+fn play_two_player(
+    mut game: Game,
+    autosave: Option<Autosave>,
+    best_of: Option<u32>,
+    mut display: Display,
+) {
+    let first = game.tags().white.clone().unwrap_or("Player 1".to_string());
+    let second = game.tags().black.clone().unwrap_or("Player 2".to_string());
+    let mut standings = Standings::new(&first, &second, Color::White).with_best_of(best_of);
+    let mut rounds = Vec::new();
+    game.subscribe(announcer(&game, None, display));
+    add_move_hook(&mut game, display);
+    loop {
+        play_two_player_game(&mut game, &autosave, &mut display);
+        if game.status() == Status::Ongoing {
+            break;
+        }
+        score_game(&mut standings, &game, &mut rounds, display.language);
+        if !play_on(&standings, &rounds, display.language) {
+            break;
+        }
+        game.rematch();
+        standings.swap_colors();
+        println!(
+            "{}",
+            display.language.fill(
+                Text::NowPlays,
+                &[&first, display.language.color_name(standings.color(0))]
+            )
+        );
+    }
+}
+
+fn play_two_player_game(game: &mut Game, autosave: &Option<Autosave>, display: &mut Display) {
+    let aliases = Config::load().aliases;
+    let repertoire = load_repertoire();
+    // Both players type at this terminal, so the input is all there is to
+    // wait for.
+    let mut events = EventLoop::new();
+    loop {
+        if let Some(requester) = game.takeback_request() {
+            let input = read_line(&display.language.fill(
+                Text::TakebackRequest,
+                &[
+                    display.language.color_name(requester),
+                    display.language.color_name(!requester),
+                ],
+            ));
+            let result = match input.trim() {
+                "accept" => game.accept_takeback(),
+                "decline" => game.decline_takeback(),
+                _ => {
+                    eprintln!("{}", display.language.text(Text::IllegalInput));
                     continue;
                 }
+            };
+            match result {
+                Ok(()) => save_progress(autosave, game),
+                Err(e) => println!("{}", e),
             }
-            "print" => print_move_history(&game),
-            _ => {
-                if let Err(e) = game.make_move_from_str(input, false) {
-                    println!("{}", e);
+            events.changed();
+            continue;
+        }
+        if let Some(offerer) = game.draw_offer() {
+            let input = read_line(&display.language.fill(
+                Text::DrawOffer,
+                &[
+                    display.language.color_name(offerer),
+                    display.language.color_name(!offerer),
+                ],
+            ));
+            let result = match input.trim() {
+                "accept" => game.accept_draw(),
+                "decline" => game.decline_draw(),
+                _ => {
+                    eprintln!("{}", display.language.text(Text::IllegalInput));
                     continue;
                 }
+            };
+            if let Err(e) = result {
+                println!("{}", e);
+            }
+            if game.status() != Status::Ongoing {
+                break;
             }
+            continue;
         }
 
-        match game.status() {
-            Status::Checkmate(color) => {
-                println!("Game Over: {:?} wins!", color);
-                break;
+        events.render(|| display_board(game, *display));
+        let input = match events.input(display.language.text(Text::EnterMove)) {
+            Event::Input(input) => input,
+            Event::Closed => break,
+            _ => continue,
+        };
+        events.changed();
+        let input = input.trim();
+
+        match parse_command(input, Mode::TwoPlayer, &aliases) {
+            Some((Command::Quit, _)) => break,
+            Some((Command::Undo, _)) => {
+                if let Err(e) = game.request_takeback() {
+                    println!("{}", e);
+                }
+                continue;
             }
-            Status::Stalemate => {
-                println!("Stalemate");
-                break;
+            Some((Command::Resign, _)) => match game.resign(game.turn()) {
+                Ok(()) => break,
+                Err(e) => {
+                    println!("{}", e);
+                    continue;
+                }
+            },
+            Some((Command::Draw, _)) => {
+                if let Err(e) = game.offer_draw(game.turn()) {
+                    println!("{}", e);
+                }
+                continue;
             }
-            Status::Ongoing => (),
+            Some((command, arguments)) => {
+                let orientation = game.turn();
+                let result = game_command(
+                    game,
+                    command,
+                    arguments,
+                    Mode::TwoPlayer,
+                    orientation,
+                    display,
+                );
+                if let Err(e) = result {
+                    println!("{}", e);
+                }
+                continue;
+            }
+            None => match play_input(game, input, display.language) {
+                Ok(true) => (),
+                Ok(false) => continue,
+                Err(e) => {
+                    println!("{}", e);
+                    continue;
+                }
+            },
+        }
+        announce_deviation(&repertoire, game);
+
+        if game.status() != Status::Ongoing {
+            break;
         }
+        save_progress(autosave, game);
+    }
+    if game.status() != Status::Ongoing {
+        repertoire_report(&repertoire, game);
+        discard_autosave(autosave);
+        session::record_game(SessionGame::new(game, None, None));
+        archive_game(game, &[("Event", "rChess two-player game".to_string())]);
     }
 }
 
-fn single_player() {
-    let input: String = loop {
-        print!("Select your color (white or black, random to choose randomly): ");
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            eprintln!("Error reading input, please try again.");
-            continue;
-        }
-        let input = input.trim().to_lowercase();
-        if input == "white" || input == "black" || input == "random" {
-            break input;
-        } else {
-            eprintln!("Illegal input, please try again.");
-        }
-    };
+fn single_player(
+    recovery: Option<PathBuf>,
+    best_of: Option<u32>,
+    display: Display,
+    tags: &GameTags,
+) {
+    let input = prompt(
+        display.language.text(Text::SelectColor),
+        &["white", "black", "random"],
+        display.language,
+    );
     let player_color = match input.as_str() {
         "white" => Color::White,
         "black" => Color::Black,
@@ -99,57 +756,827 @@ fn single_player() {
         }
         _ => unreachable!(),
     };
-    println!("You're playing as {:?}", player_color);
-    let mut game = Game::new_single(player_color, 7);
+    println!(
+        "{}",
+        display.language.fill(
+            Text::PlayingAs,
+            &[display.language.color_name(player_color)]
+        )
+    );
+    let name = tags
+        .name(player_color)
+        .map(str::to_string)
+        .or(Config::load().name);
+    let profile = ask_profile(name.as_deref(), display.language);
+    let difficulty = loop {
+        let input = read_line(&display.language.fill(
+            Text::SelectDifficulty,
+            &[&MIN_ELO.to_string(), &MAX_ELO.to_string()],
+        ));
+        match Difficulty::from_str(input.trim()) {
+            Ok(difficulty) => break difficulty,
+            Err(()) => eprintln!("{}", display.language.text(Text::IllegalInput)),
+        }
+    };
+    let input = prompt(
+        display.language.text(Text::SelectStyle),
+        &["balanced", "aggressive", "positional", "gambit", "drawish"],
+        display.language,
+    );
+    let personality = Personality::from_str(&input).unwrap();
+    let input = prompt(
+        display.language.text(Text::SelectOdds),
+        &["none", "pawn", "knight", "queen"],
+        display.language,
+    );
+    let odds = match input.as_str() {
+        "none" => Odds::None,
+        "pawn" => Odds::PawnAndMove,
+        "knight" => Odds::Knight,
+        "queen" => Odds::Queen,
+        _ => unreachable!(),
+    };
+    let giver = if odds == Odds::None {
+        !player_color
+    } else {
+        let input = prompt(
+            display.language.text(Text::OddsGiver),
+            &["me", "ai"],
+            display.language,
+        );
+        match input.as_str() {
+            "me" => player_color,
+            "ai" => !player_color,
+            _ => unreachable!(),
+        }
+    };
+    let config = Config::load();
+    let game = Game::new_single(player_color, difficulty)
+        .with_tags(single_player_tags(
+            tags,
+            player_color,
+            profile.as_deref(),
+            difficulty,
+        ))
+        .with_personality(personality)
+        .with_engine(config.engine)
+        .with_limits(config.limits)
+        .with_hash(config.hash_size)
+        .with_variant(select_variant(display.language))
+        .with_position(odds.starting_board(giver));
+    if let Err(e) = game.validate_position() {
+        println!("{}", ChessError::InvalidPosition(e));
+        return;
+    }
+    let time_controls = ask_time_controls(display.language);
+    info!(
+        "Single-player {} game started as {:?} against the {:?} {} AI",
+        game.variant(),
+        player_color,
+        difficulty,
+        personality
+    );
+    let autosave = recovery.map(|path| {
+        Autosave::new(path, Some((player_color, difficulty))).with_profile(profile.clone())
+    });
+    play_single_player(
+        game,
+        player_color,
+        difficulty,
+        profile,
+        autosave,
+        best_of,
+        time_controls,
+        display,
+    );
+}
+
+/// Asks for the player's time control and the AI's, which may differ to
+/// give one side time odds.
+///
+/// # Returns
+///
+/// The player's and the AI's time controls, `None` to play without a
+/// clock.
+fn ask_time_controls(language: Language) -> Option<(TimeControl, TimeControl)> {
+    let ask = |question: &str| loop {
+        let input = read_line(question);
+        if input.trim().is_empty() {
+            return None;
+        }
+        match TimeControl::from_str(&input) {
+            Ok(control) => return Some(control),
+            Err(e) => eprintln!("{}", e),
+        }
+    };
+    let player = ask(language.text(Text::TimeControl))?;
+    let ai = ask(language.text(Text::AiTimeControl)).unwrap_or(player);
+    Some((player, ai))
+}
+
+/// Asks for the player's name to track their rating in a profile,
+/// greeting returning players with their rating and a suggested difficulty.
+/// Leaving it empty takes `default`, the name set in the configuration
+/// or on the command line, if there's one.
+fn ask_profile(default: Option<&str>, language: Language) -> Option<String> {
+    let input = match default {
+        Some(default) => read_line(&language.fill(Text::ProfileNameDefault, &[default])),
+        None => read_line(language.text(Text::ProfileName)),
+    };
+    let name = match input.trim() {
+        "" => default.unwrap_or_default(),
+        name => name,
+    };
+    if name.is_empty() || name.contains('\t') {
+        return None;
+    }
+    let store = ProfileStore::load(&profiles_path()?).unwrap_or_else(|e| {
+        eprintln!("Error reading profiles: {}", e);
+        ProfileStore::default()
+    });
+    if let Some(profile) = store.get(name) {
+        println!(
+            "{}",
+            language.fill(
+                Text::WelcomeBack,
+                &[
+                    &profile.name,
+                    &format!("{:.0}", profile.rating),
+                    &format!("{:?}", profile.suggested_difficulty()),
+                ],
+            )
+        );
+    }
+    session::set_player(name);
+    Some(name.to_string())
+}
+
+/// Returns the average accuracy stored in the profile of the player
+/// called `name`, if any.
+fn profile_accuracy(name: &str) -> Option<f64> {
+    ProfileStore::load(&profiles_path()?)
+        .ok()?
+        .get(name)?
+        .accuracy()
+}
+
+/// Records the result of a finished game against the AI in the player's
+/// profile, along with their accuracy if it was measured.
+fn record_result(name: &str, difficulty: Difficulty, outcome: Outcome, accuracy: Option<f64>) {
+    let Some(path) = profiles_path() else {
+        return;
+    };
+    let mut store = match ProfileStore::load(&path) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error reading profiles: {}", e);
+            return;
+        }
+    };
+    let profile = store.get_or_create(name);
+    profile.add_result(difficulty, outcome);
+    if let Some(accuracy) = accuracy {
+        profile.add_accuracy(accuracy);
+    }
+    println!(
+        "{}'s rating: {:.0} (suggested difficulty: {:?})",
+        profile.name,
+        profile.rating,
+        profile.suggested_difficulty()
+    );
+    if let Err(e) = store.save(&path) {
+        eprintln!("Error saving profiles: {}", e);
+    }
+}
 
+/// Plays single-player games against the AI, keeping the score across
+/// them: a match of `best_of` games, or as long as the player wants a
+/// rematch with colors swapped after each one. With `time_controls`,
+/// the player's and the AI's, every game is played on a clock.
+#[allow(clippy::too_many_arguments)]
+fn play_single_player(
+    mut game: Game,
+    mut player_color: Color,
+    difficulty: Difficulty,
+    profile: Option<String>,
+    mut autosave: Option<Autosave>,
+    best_of: Option<u32>,
+    time_controls: Option<(TimeControl, TimeControl)>,
+    mut display: Display,
+) {
+    let name = profile.as_deref().unwrap_or("You");
+    let mut standings = Standings::new(name, "AI", player_color).with_best_of(best_of);
+    let mut rounds = Vec::new();
+    game.subscribe(announcer(&game, Some((!player_color, "AI")), display));
+    add_move_hook(&mut game, display);
     loop {
-        display_board(&game);
-        if game.turn() == player_color {
-            print!("Enter move: ");
-            io::stdout().flush().unwrap();
+        let clock = time_controls.map(|(player, ai)| match player_color {
+            Color::White => Clock::new(player, ai),
+            Color::Black => Clock::new(ai, player),
+        });
+        play_single_player_game(
+            &mut game,
+            player_color,
+            difficulty,
+            &profile,
+            &autosave,
+            clock,
+            &mut display,
+        );
+        if game.status() == Status::Ongoing {
+            break;
+        }
+        score_game(&mut standings, &game, &mut rounds, display.language);
+        if !play_on(&standings, &rounds, display.language) {
+            break;
+        }
+        game.rematch();
+        player_color = !player_color;
+        standings.swap_colors();
+        if let Some(autosave) = &mut autosave {
+            autosave.swap_colors();
+        }
+        println!(
+            "{}",
+            display.language.fill(
+                Text::PlayingAs,
+                &[display.language.color_name(player_color)]
+            )
+        );
+    }
+}
+
+/// Asks whether to play another game with colors swapped.
+fn ask_rematch(language: Language) -> bool {
+    let input = prompt(language.text(Text::Rematch), &["yes", "no"], language);
+    input == "yes"
+}
 
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).unwrap();
+fn play_single_player_game(
+    game: &mut Game,
+    player_color: Color,
+    difficulty: Difficulty,
+    profile: &Option<String>,
+    autosave: &Option<Autosave>,
+    mut clock: Option<Clock>,
+    display: &mut Display,
+) {
+    let config = Config::load();
+    let aliases = config.aliases;
+    let conduct = config.conduct;
+    let pacing = config.pacing;
+    let notify = config.notify;
+    let repertoire = load_repertoire();
+    // The AI's scores of its searched moves, and how many of them there
+    // were when it last offered a draw.
+    let mut ai_scores = Vec::new();
+    let mut offered_at = 0;
+    // A move entered while the AI was thinking, played as soon as it's
+    // the player's turn.
+    let mut premove: Option<String> = None;
+    // Measures the player's accuracy, and sets the AI's strength at the
+    // adaptive difficulty.
+    let mut skill = AdaptiveSkill::new(profile.as_deref().and_then(profile_accuracy));
+    let adaptive = difficulty == Difficulty::Adaptive;
+    if adaptive {
+        game.set_skill(skill.depth(), Some(skill.blunder_model()));
+    }
+    let limits = game.limits();
+    // The player's input, the AI's moves and the clock's ticks come in as
+    // events, so the AI thinks on its own thread while a clock runs down.
+    let mut events = EventLoop::new();
+    if let Some(clock) = &mut clock {
+        clock.start(game.turn());
+        events.start_ticker(CLOCK_TICK);
+    }
+    let mut thinking = false;
+    loop {
+        events.render(|| show_game(game, clock.as_ref(), *display));
+        if game.turn() == player_color {
+            let (input, is_premove) =
+                match turn_input(&mut premove, &mut events, None, display.language) {
+                    (Event::Input(line), is_premove) => (line, is_premove),
+                    (Event::Closed, _) => break,
+                    _ => {
+                        if let Some(flagged) = clock.as_ref().and_then(Clock::flagged) {
+                            println!();
+                            let _ = game.time_out(flagged);
+                            break;
+                        }
+                        continue;
+                    }
+                };
+            events.changed();
             let input = input.trim();
+            if let Some(flagged) = clock.as_ref().and_then(Clock::flagged) {
+                let _ = game.time_out(flagged);
+                break;
+            }
 
-            match input {
-                "quit" => break,
-                "undo" => {
+            match parse_command(input, Mode::SinglePlayer, &aliases) {
+                Some((Command::Quit, _)) => break,
+                Some((Command::Undo, _)) => {
                     if let Err(e) = game.undo() {
                         println!("{}", e);
                         continue;
                     }
+                    skill.reset_score();
                 }
-                "print" => print_move_history(&game),
-                _ => {
-                    if let Err(e) = game.make_move_from_str(input, false) {
+                Some((Command::Resign, _)) => match game.resign(player_color) {
+                    Ok(()) => break,
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                },
+                Some((Command::Draw, _)) => {
+                    if let Err(e) = game.offer_draw(player_color) {
                         println!("{}", e);
                         continue;
                     }
+                    if conduct.accepts_draw(ai_scores.last().copied()) {
+                        println!("{}", display.language.text(Text::AiAcceptsDraw));
+                        let _ = game.accept_draw();
+                        break;
+                    }
+                    println!("{}", display.language.text(Text::AiDeclinesDraw));
+                    let _ = game.decline_draw();
+                    continue;
+                }
+                Some((command, arguments)) => {
+                    let result = game_command(
+                        game,
+                        command,
+                        arguments,
+                        Mode::SinglePlayer,
+                        player_color,
+                        display,
+                    );
+                    if let Err(e) = result {
+                        println!("{}", e);
+                    }
+                    continue;
                 }
+                None => match play_input(game, input, display.language) {
+                    Ok(true) => {
+                        press_clock(game, &mut clock, None);
+                        announce_deviation(&repertoire, game)
+                    }
+                    Ok(false) => continue,
+                    Err(e) if is_premove => {
+                        println!(
+                            "{}",
+                            display
+                                .language
+                                .fill(Text::PremoveDiscarded, &[input, &e.to_string()])
+                        );
+                        continue;
+                    }
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                },
             }
         } else {
-            match game.get_ai_move() {
-                Ok(mv) => game.make_move(mv),
-                Err(e) => println!("{}", e),
+            if !thinking {
+                if game.moves().len() < 2 {
+                    println!("{}", display.language.text(Text::PremoveHint));
+                }
+                events.listen();
+                // On a clock, the AI thinks for a share of its own time.
+                let move_number = game.moves().len() as u32 / 2 + 1;
+                let budget = clock
+                    .as_ref()
+                    .map(|clock| clock.move_budget(!player_color, move_number));
+                if let Some(budget) = budget {
+                    game.set_limits(SearchLimits {
+                        time: Some(
+                            limits
+                                .time
+                                .map_or(budget.maximum, |time| time.min(budget.maximum)),
+                        ),
+                        optimum_time: Some(budget.optimum),
+                        ..limits
+                    });
+                }
+                let delay = pacing.delay(game.board(), game.moves().len());
+                let delay = budget.map_or(delay, |budget| delay.min(budget.optimum));
+                match game.ai_player() {
+                    Ok(ai) => events.think(ai, delay),
+                    Err(e) => {
+                        println!("{}", e);
+                        break;
+                    }
+                }
+                thinking = true;
             }
-        }
-        match game.status() {
-            Status::Checkmate(color) => {
-                println!("Game Over: {:?} wins!", color);
-                break;
+            let result = match events.next() {
+                Event::AiMove(result) => result,
+                // Taking the move back needn't wait for the AI's reply.
+                Event::Input(line)
+                    if matches!(
+                        parse_command(line.trim(), Mode::SinglePlayer, &aliases),
+                        Some((Command::Undo, _))
+                    ) =>
+                {
+                    if let Err(e) = game.undo() {
+                        println!("{}", e);
+                        continue;
+                    }
+                    events.discard_thinking();
+                    if let Some(clock) = &mut clock {
+                        clock.start(game.turn());
+                    }
+                    thinking = false;
+                    skill.reset_score();
+                    events.changed();
+                    continue;
+                }
+                Event::Input(line) => {
+                    premove = Some(line);
+                    continue;
+                }
+                Event::Tick => {
+                    if let Some(flagged) = clock.as_ref().and_then(Clock::flagged) {
+                        let _ = game.time_out(flagged);
+                        break;
+                    }
+                    continue;
+                }
+                Event::Closed => break,
+                // No connection is watched.
+                Event::Message(_) => unreachable!(),
+            };
+            thinking = false;
+            events.changed();
+            match result {
+                Ok((mv, score)) => {
+                    if let Some(flagged) = clock.as_ref().and_then(Clock::flagged) {
+                        let _ = game.time_out(flagged);
+                        break;
+                    }
+                    ai_scores.extend(score);
+                    if let Some(score) = score {
+                        skill.observe(score);
+                        if adaptive {
+                            game.set_skill(skill.depth(), Some(skill.blunder_model()));
+                        }
+                    }
+                    if conduct.resigns(&ai_scores) {
+                        let _ = game.resign(!player_color);
+                        break;
+                    }
+                    if let Err(e) = game.make_move(mv) {
+                        println!("{}", e);
+                        break;
+                    }
+                    press_clock(game, &mut clock, None);
+                    announce_deviation(&repertoire, game);
+                    if game.status() == Status::Ongoing {
+                        notify.alert(Alert::YourTurn);
+                    }
+                    if game.status() == Status::Ongoing
+                        && conduct.offers_draw(game.board(), &ai_scores[offered_at..])
+                    {
+                        offered_at = ai_scores.len();
+                        ai_offers_draw(game, *display);
+                    }
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    break;
+                }
             }
-            Status::Stalemate => {
-                println!("Stalemate");
-                break;
+        }
+        if game.status() != Status::Ongoing {
+            break;
+        }
+        save_progress(autosave, game);
+    }
+    let Some(outcome) = Outcome::from_status(game.status(), player_color) else {
+        return;
+    };
+    repertoire_report(&repertoire, game);
+    if let Some(accuracy) = skill.accuracy() {
+        println!(
+            "{}",
+            display
+                .language
+                .fill(Text::Accuracy, &[&format!("{:.1}", accuracy)])
+        );
+    }
+    discard_autosave(autosave);
+    session::record_game(SessionGame::new(game, Some(player_color), skill.accuracy()));
+    archive_game(game, &[("Event", "rChess single-player game".to_string())]);
+    if let Some(name) = &profile {
+        record_result(name, difficulty, outcome, skill.accuracy());
+    }
+}
+
+/// Draws the board of `game`, followed by `clock` if it's played on one.
+fn show_game(game: &Game, clock: Option<&Clock>, display: Display) {
+    display_board(game, display);
+    if let Some(clock) = clock {
+        println!("{}", clock.display());
+    }
+}
+
+/// Returns the player's input for their turn: the premove they entered
+/// while the opponent was thinking, if any, or else the line they enter
+/// now, with the messages of the opponent on `connection`, if given,
+/// and the other `events` coming in meanwhile.
+///
+/// # Returns
+///
+/// The [`Event::Input`] with the line, or another event that came first,
+/// and whether it's a premove.
+fn turn_input(
+    premove: &mut Option<String>,
+    events: &mut EventLoop,
+    connection: Option<&Connection>,
+    language: Language,
+) -> (Event, bool) {
+    if let Some(line) = premove.take() {
+        println!("{}", language.fill(Text::Premove, &[line.trim()]));
+        return (Event::Input(line), true);
+    }
+    let prompt = language.text(Text::EnterMove);
+    let event = match connection {
+        Some(connection) => events.input_or_message(prompt, connection),
+        None => events.input(prompt),
+    };
+    (event, false)
+}
+
+/// Asks the player whether they accept the AI's draw offer, ending the
+/// game if they do.
+fn ai_offers_draw(game: &mut Game, display: Display) {
+    let _ = game.offer_draw(!game.turn());
+    display_board(game, display);
+    let input = prompt(
+        display.language.text(Text::AiOffersDraw),
+        &["accept", "decline"],
+        display.language,
+    );
+    let _ = match input.as_str() {
+        "accept" => game.accept_draw(),
+        _ => game.decline_draw(),
+    };
+}
+
+/// How often the clock is checked for a fallen flag.
+const CLOCK_TICK: Duration = Duration::from_millis(100);
+
+/// Terminal style of marked squares: green background.
+const MARK_STYLE: &str = "\x1b[42m";
+/// Terminal style of squares an arrow covers: yellow background.
+const ARROW_STYLE: &str = "\x1b[43m";
+/// Terminal style of the selected piece's square: cyan background.
+const SELECTED_STYLE: &str = "\x1b[46m";
+/// Terminal style of the squares the selected piece can move to: blue background.
+const DESTINATION_STYLE: &str = "\x1b[44m";
+/// Terminal style of the squares the last move was played from and to:
+/// magenta background.
+const LAST_MOVE_STYLE: &str = "\x1b[45m";
+const RESET_STYLE: &str = "\x1b[0m";
+/// Terminal backgrounds of squares White controls in the heatmap, from
+/// one attacker more than Black to four or more: ever darker blues.
+const WHITE_CONTROL_STYLES: [&str; 4] = [
+    "\x1b[48;5;153m",
+    "\x1b[48;5;111m",
+    "\x1b[48;5;69m",
+    "\x1b[48;5;27m",
+];
+/// Terminal backgrounds of squares Black controls in the heatmap: ever
+/// darker reds.
+const BLACK_CONTROL_STYLES: [&str; 4] = [
+    "\x1b[48;5;217m",
+    "\x1b[48;5;210m",
+    "\x1b[48;5;203m",
+    "\x1b[48;5;160m",
+];
+/// Terminal background of squares both sides attack equally often in the
+/// heatmap: grey.
+const CONTESTED_STYLE: &str = "\x1b[48;5;250m";
+
+/// Plays a move typed in SAN or in two steps: typing the square of one
+/// of your pieces selects it, highlighting its legal moves on the board,
+/// and typing one of the highlighted squares then moves it there.
+///
+/// Returns whether a move was made.
+fn play_input(game: &mut Game, input: &str, language: Language) -> Result<bool, ChessError> {
+    if let Ok(square) = Square::from_str(input) {
+        if game.selected_destinations().contains(&square) {
+            game.move_selected(square)?;
+            return Ok(true);
+        }
+        if game.board().color_on(square) == Some(game.turn()) {
+            game.select(square)?;
+            return Ok(false);
+        }
+    }
+    // Piece letters may be typed in the player's language.
+    let input = language.read_move(input);
+    game.make_move_from_str(&input, false).map(|()| true)
+}
+
+/// Runs one of the commands shared by all kinds of games, listed in
+/// [`crate::command::COMMANDS`]. Images are drawn seen from the side
+/// given as an argument, `orientation` by default, in the colors set
+/// in the configuration, with the engine's best move and evaluation
+/// overlaid if `--best-move` and `--eval` are given.
+///
+/// # Errors
+///
+/// Returns a message explaining why the command failed, e.g. showing
+/// its usage if the arguments are invalid.
+fn game_command(
+    game: &mut Game,
+    command: Command,
+    arguments: &str,
+    mode: Mode,
+    orientation: Color,
+    display: &mut Display,
+) -> Result<(), String> {
+    let words: Vec<&str> = arguments.split_whitespace().collect();
+    let square = |name: &str| parse_square(name).map_err(|e| e.to_string());
+    match (command, &words[..]) {
+        (Command::Help, _) => print!("{}", help(mode, &Config::load().aliases)),
+        (Command::Print, _) => print_move_history(game),
+        (Command::Pgn, _) => println!("{}", write_pgn(game, &[])),
+        (Command::Fen, _) => println!("{}", game.fen()),
+        (Command::Mark, [name]) => game.annotations_mut().toggle_mark(square(name)?),
+        (Command::Arrow, [from, to]) => {
+            let (from, to) = (square(from)?, square(to)?);
+            game.annotations_mut().toggle_arrow(from, to)
+        }
+        (Command::Clear, _) => game.annotations_mut().clear(),
+        (Command::Comment, _) => game.set_comment(arguments.trim_matches('"')),
+        (Command::Nag, [glyph]) => {
+            let nag = parse_nag(glyph).ok_or_else(|| ChessError::InvalidInput.to_string())?;
+            game.toggle_nag(nag)
+        }
+        (Command::Export, [path, flags @ ..]) => {
+            let format = match flags {
+                [] => DocumentFormat::from_path(Path::new(path)),
+                ["--format", format] => format
+                    .parse()
+                    .map_err(|_| format!("Usage: {}", spec(command).usage()))?,
+                _ => return Err(format!("Usage: {}", spec(command).usage())),
+            };
+            fs::write(path, format.write(game))
+                .map_err(|e| format!("Error saving the game: {}", e))?;
+            println!("Saved the game to {}", path);
+        }
+        (Command::ExportImage | Command::ExportFrames, [path, flags @ ..]) => {
+            let mut options = SvgOptions {
+                theme: Config::load().image_theme,
+                orientation,
+                highlight_last_move: true,
+                ..SvgOptions::default()
+            };
+            for flag in flags {
+                match *flag {
+                    "white" => options.orientation = Color::White,
+                    "black" => options.orientation = Color::Black,
+                    "--best-move" => options.best_move_arrow = true,
+                    "--eval" => options.evaluation_badge = true,
+                    _ => return Err(format!("Usage: {}", spec(command).usage())),
+                }
+            }
+            export_images(game, command == Command::ExportFrames, path, &options)
+                .map_err(|e| format!("Error saving the image: {}", e))?
+        }
+        (Command::Explorer, paths) => print!("{}", load_index(paths)?.report(game.board())),
+        (Command::Explore, []) => explore(game, *display),
+        (Command::Heatmap, []) => print!("{}", heatmap_diagram(game, *display)),
+        (Command::Info, []) => print!("{}", PositionReport::new(game.board())),
+        (Command::Zoom, []) => display.zoom = display.zoom % MAX_ZOOM + 1,
+        (Command::Zoom, [level]) => match level.parse() {
+            Ok(level) if (1..=MAX_ZOOM).contains(&level) => display.zoom = level,
+            _ => return Err(format!("Usage: {}", spec(command).usage())),
+        },
+        _ => return Err(format!("Usage: {}", spec(command).usage())),
+    }
+    Ok(())
+}
+
+/// Opens a scratch board on the current position of `game` to try lines
+/// on against the engine, which answers every move of the side to move:
+/// `back` takes a move and its answer back, `reset` goes back to the
+/// game's position, `analyze [depth]` searches the position and `done`
+/// returns to the game, left as it was.
+fn explore(game: &Game, display: Display) {
+    let player = game.turn();
+    let start = game.moves().len();
+    let mut scratch = game.scratch(!player);
+    println!("Exploring on a scratch board, the game waits as it is");
+    loop {
+        display_board(&scratch, display);
+        let input = read_line("Explore (<move>, back, reset, analyze [depth] or done): ");
+        let input = input.trim();
+        let (command, argument) = input.split_once(' ').unwrap_or((input, ""));
+        match command {
+            "done" | "quit" => break,
+            "back" if scratch.moves().len() > start => {
+                let _ = scratch.undo();
+                if scratch.turn() != player && scratch.moves().len() > start {
+                    let _ = scratch.undo();
+                }
             }
-            Status::Ongoing => (),
+            "back" => println!("{}", ChessError::NothingToUndo),
+            "reset" => scratch = game.scratch(!player),
+            "analyze" => {
+                let depth = argument.parse().unwrap_or(6);
+                analyze(scratch.board(), depth, &EngineOptions::default());
+            }
+            _ => match play_input(&mut scratch, input, display.language) {
+                Ok(true) if scratch.status() == Status::Ongoing => {
+                    match scratch.get_scored_ai_move() {
+                        Ok((mv, score)) => {
+                            // Scores are shown from White's side.
+                            let sign = if scratch.turn() == Color::White {
+                                1
+                            } else {
+                                -1
+                            };
+                            let score = score.map(|s| format!(" ({})", format_score(sign * s)));
+                            println!(
+                                "The engine answers {}{}",
+                                display.language.local_san(scratch.board(), mv),
+                                score.unwrap_or_default()
+                            );
+                            let _ = scratch.make_move(mv);
+                        }
+                        Err(e) => println!("{}", e),
+                    }
+                }
+                Ok(_) => (),
+                Err(e) => println!("{}", e),
+            },
         }
+        if scratch.status() != Status::Ongoing {
+            println!(
+                "Game over on the scratch board: {}",
+                result_token(scratch.status())
+            );
+        }
+    }
+    println!("Back to the game");
+}
+
+/// Indexes the positions of the games in the PGN files at `paths`, or
+/// in the archive of played games if none are given.
+///
+/// # Errors
+///
+/// Returns a message if a file can't be read.
+fn load_index(paths: &[&str]) -> Result<PositionIndex, String> {
+    let paths: Vec<PathBuf> = if paths.is_empty() {
+        // The archive only exists once a game has been finished.
+        archive_path()
+            .filter(|path| path.exists())
+            .into_iter()
+            .collect()
+    } else {
+        paths.iter().map(PathBuf::from).collect()
+    };
+    let mut games = Vec::new();
+    for path in paths {
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+        games.extend(parse_pgn(&text));
     }
+    Ok(PositionIndex::new(&games))
 }
 
-/// Converts a chess piece and color into a Unicode character for display.
+/// Saves the board at `path` as an SVG image drawn with `options`, or,
+/// if `frames` is set, every position of the game as numbered SVG
+/// frames in the directory `path`, e.g. to be made into an animation.
+fn export_images(game: &Game, frames: bool, path: &str, options: &SvgOptions) -> io::Result<()> {
+    if !frames {
+        fs::write(path, game.render_svg(options))?;
+        println!("Saved the board to {}", path);
+        return Ok(());
+    }
+    let dir = Path::new(path);
+    let frames = game.render_svg_frames(options);
+    fs::create_dir_all(dir)?;
+    for (number, frame) in frames.iter().enumerate() {
+        fs::write(dir.join(format!("frame-{:03}.svg", number)), frame)?;
+    }
+    println!("Saved {} frames to {}", frames.len(), path);
+    Ok(())
+}
+
+/// Converts a chess piece and color into a Unicode character for display,
+/// or, if the terminal can't show it, a letter as in FEN: uppercase for
+/// White, lowercase for Black.
 fn piece_symbol(piece: Piece, color: Color) -> char {
+    if !capabilities().unicode {
+        return piece.to_string(color).chars().next().unwrap();
+    }
     match (piece, color) {
         (Piece::Pawn, Color::White) => '♙',
         (Piece::Pawn, Color::Black) => '♟',
@@ -176,9 +1603,32 @@ fn piece_symbol(piece: Piece, color: Color) -> char {
 ///
 /// ```
 /// let game = Game::new_multi();
-/// display_board(&game);
+/// display_board(&game, Display::default());
 /// ```
-fn display_board(game: &Game) {
+fn display_board(game: &Game, display: Display) {
+    if display.accessible {
+        println!("{}", describe_position(game));
+        return;
+    }
+    let mut board_str = board_diagram(game, true, display.zoom);
+    board_str.push_str(&board_notes(game));
+    println!("{}", board_str);
+}
+
+/// Draws the board of `game` from the side to move, with the ranks and
+/// files labeled if `labels` is set. Highlighted, see [`highlight`], are:
+///
+/// - the selected piece and the squares it can move to,
+/// - the marked squares and the squares under arrows,
+/// - the squares the last move was played from and to, so the
+///   opponent's reply is easy to spot.
+///
+/// Zoomed in, with `zoom` above 1, the board is drawn larger by
+/// [`large_diagram`].
+fn board_diagram(game: &Game, labels: bool, zoom: u8) -> String {
+    if zoom > 1 {
+        return large_diagram(game, labels, zoom);
+    }
     let mut board_str = String::new();
 
     let board = game.board();
@@ -190,7 +1640,9 @@ fn display_board(game: &Game) {
     }; //a hack to make up for the lack of 8..0 in rust
 
     for rank in &rank_range {
-        board_str.push_str(&format!("{}  ", rank + 1));
+        if labels {
+            board_str.push_str(&format!("{}  ", rank + 1));
+        }
         for file in &file_range {
             let square = chess::Square::make_square(
                 chess::Rank::from_index(*rank),
@@ -203,32 +1655,2751 @@ fn display_board(game: &Game) {
                 (Some(p), Some(c)) => piece_symbol(p, c),
                 _ => '.',
             };
-            board_str.push(symbol);
-            board_str.push(' ');
+            match highlight(game, square) {
+                Some((style, _)) if capabilities().ansi => {
+                    board_str.push_str(&format!("{}{}{} ", style, symbol, RESET_STYLE))
+                }
+                Some((_, marker)) => {
+                    board_str.push(symbol);
+                    board_str.push(marker);
+                }
+                None => {
+                    board_str.push(symbol);
+                    board_str.push(' ');
+                }
+            }
         }
         board_str.push('\n');
     }
-    board_str.push_str(match turn {
+    if labels {
+        board_str.push_str(match turn {
+            Color::White => "   a b c d e f g h\n",
+            Color::Black => "   h g f e d c b a\n",
+        });
+    }
+    board_str
+}
+
+/// Returns how `square` is highlighted on the board of `game`, if it
+/// is: the terminal style of its background and, for terminals without
+/// colors, the character drawn after its piece instead.
+fn highlight(game: &Game, square: Square) -> Option<(&'static str, char)> {
+    let annotations = game.annotations();
+    let last_move = game.moves().last();
+    if game.selection() == Some(square) {
+        Some((SELECTED_STYLE, '<'))
+    } else if game.selected_destinations().contains(&square) {
+        Some((DESTINATION_STYLE, '*'))
+    } else if annotations.is_marked(square) {
+        Some((MARK_STYLE, '+'))
+    } else if annotations.on_arrow(square) {
+        Some((ARROW_STYLE, '~'))
+    } else if last_move.is_some_and(|mv| [mv.get_source(), mv.get_dest()].contains(&square)) {
+        Some((LAST_MOVE_STYLE, '\''))
+    } else {
+        None
+    }
+}
+
+/// Draws the board of `game` like [`board_diagram`], but with every
+/// square boxed in a grid, `2 * zoom - 1` characters wide and
+/// `2 * zoom - 3` lines tall, and dark squares shaded with `:`, for big
+/// screens and streams.
+///
+/// # Example
+///
+/// ```
+/// let game = Game::new_multi();
+/// print!("{}", large_diagram(&game, true, 2));
+/// ```
+fn large_diagram(game: &Game, labels: bool, zoom: u8) -> String {
+    let width = usize::from(2 * zoom - 1);
+    let height = usize::from(2 * zoom - 3);
+    let board = game.board();
+    let (ranks, files): (Vec<usize>, Vec<usize>) = match game.turn() {
+        Color::White => ((0..8).rev().collect(), (0..8).collect()),
+        Color::Black => ((0..8).collect(), (0..8).rev().collect()),
+    };
+    let margin = if labels { "   " } else { "" };
+    let border = format!(
+        "{}+{}\n",
+        margin,
+        format!("{}+", "-".repeat(width)).repeat(8)
+    );
+
+    let mut board_str = String::new();
+    for rank in &ranks {
+        board_str.push_str(&border);
+        for line in 0..height {
+            let middle = line == height / 2;
+            if labels {
+                if middle {
+                    board_str.push_str(&format!("{}  ", rank + 1));
+                } else {
+                    board_str.push_str(margin);
+                }
+            }
+            board_str.push('|');
+            for file in &files {
+                let square = Square::make_square(
+                    chess::Rank::from_index(*rank),
+                    chess::File::from_index(*file),
+                );
+                let shade = if (rank + file) % 2 == 0 { ':' } else { ' ' };
+                let mut cell: Vec<char> = vec![shade; width];
+                if middle {
+                    if let (Some(piece), Some(color)) =
+                        (board.piece_on(square), board.color_on(square))
+                    {
+                        cell[width / 2] = piece_symbol(piece, color);
+                    }
+                }
+                let cell: String = match highlight(game, square) {
+                    Some((style, _)) if capabilities().ansi => {
+                        format!("{}{}{}", style, String::from_iter(cell), RESET_STYLE)
+                    }
+                    Some((_, marker)) if middle => {
+                        cell[width - 1] = marker;
+                        cell.into_iter().collect()
+                    }
+                    _ => cell.into_iter().collect(),
+                };
+                board_str.push_str(&cell);
+                board_str.push('|');
+            }
+            board_str.push('\n');
+        }
+    }
+    board_str.push_str(&border);
+    if labels {
+        let names: Vec<String> = files
+            .iter()
+            .map(|file| format!("{:^width$}", file_char(chess::File::from_index(*file))))
+            .collect();
+        board_str.push_str(&format!("{} {}\n", margin, names.join(" ").trim_end()));
+    }
+    board_str
+}
+
+/// Draws the board of `game` from the side to move, each square colored
+/// by which side attacks it more often and by how much, see [`Heatmap`],
+/// with the number of attackers of each side listed beside every rank.
+/// In the accessible display mode the squares each side controls are
+/// listed instead.
+fn heatmap_diagram(game: &Game, display: Display) -> String {
+    let heatmap = Heatmap::new(game.board());
+    if display.accessible {
+        let mut description = String::new();
+        for color in [Color::White, Color::Black] {
+            let squares: Vec<String> = heatmap
+                .controlled(color)
+                .iter()
+                .map(|square| format!("{} ({})", square, heatmap.balance(*square).abs()))
+                .collect();
+            description.push_str(&format!("{:?} controls {}.\n", color, squares.join(", ")));
+        }
+        return description;
+    }
+
+    let board = game.board();
+    let (rank_range, file_range): (Vec<usize>, Vec<usize>) = match game.turn() {
+        Color::White => ((0..8).rev().collect(), (0..8).collect()),
+        Color::Black => ((0..8).collect(), (0..8).rev().collect()),
+    };
+    let mut board_str = String::new();
+    for rank in &rank_range {
+        board_str.push_str(&format!("{}  ", rank + 1));
+        let mut counts = Vec::new();
+        for file in &file_range {
+            let square = chess::Square::make_square(
+                chess::Rank::from_index(*rank),
+                chess::File::from_index(*file),
+            );
+            let symbol = match (board.piece_on(square), board.color_on(square)) {
+                (Some(p), Some(c)) => piece_symbol(p, c),
+                _ => '.',
+            };
+            let balance = heatmap.balance(square);
+            let shade = (balance.unsigned_abs() as usize).clamp(1, 4) - 1;
+            let style = match balance {
+                _ if !capabilities().ansi => "",
+                0 if heatmap.attackers(Color::White, square) == 0 => "",
+                0 => CONTESTED_STYLE,
+                1.. => WHITE_CONTROL_STYLES[shade],
+                _ => BLACK_CONTROL_STYLES[shade],
+            };
+            if style.is_empty() {
+                board_str.push_str(&format!("{} ", symbol));
+            } else {
+                board_str.push_str(&format!("{}{} {}", style, symbol, RESET_STYLE));
+            }
+            counts.push(format!(
+                "{}/{}",
+                heatmap.attackers(Color::White, square),
+                heatmap.attackers(Color::Black, square)
+            ));
+        }
+        board_str.push_str(&format!("  {}\n", counts.join(" ")));
+    }
+    board_str.push_str(match game.turn() {
         Color::White => "   a b c d e f g h\n",
         Color::Black => "   h g f e d c b a\n",
     });
-    println!("{}", board_str);
+    if capabilities().ansi {
+        board_str.push_str("Blue: White controls the square, red: Black does, grey: contested.\n");
+    }
+    board_str.push_str("Attackers beside each rank as White/Black.\n");
+    board_str
+}
+
+/// Describes the position in words for the accessible display mode,
+/// listing the pieces rank by rank.
+fn describe_position(game: &Game) -> String {
+    let mut description = format!("{:?} to move.\n", game.turn());
+    description.push_str(&describe_board(game.board()));
+    if let Some(square) = game.selection() {
+        let destinations: Vec<String> = game
+            .selected_destinations()
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        description.push_str(&format!(
+            "Selected {}, it can move to {}\n",
+            square,
+            destinations.join(", ")
+        ));
+    }
+    description.push_str(&board_notes(game));
+    description
+}
+
+/// Lists the board annotations and variant state shown below the board.
+fn board_notes(game: &Game) -> String {
+    let mut board_str = String::new();
+    let annotations = game.annotations();
+    if !annotations.marks().is_empty() {
+        let marks: Vec<String> = annotations.marks().iter().map(|s| s.to_string()).collect();
+        board_str.push_str(&format!("Marked: {}\n", marks.join(", ")));
+    }
+    if !annotations.arrows().is_empty() {
+        let arrows: Vec<String> = annotations
+            .arrows()
+            .iter()
+            .map(|(from, to)| format!("{}->{}", from, to))
+            .collect();
+        board_str.push_str(&format!("Arrows: {}\n", arrows.join(", ")));
+    }
+    if game.in_check() {
+        let checkers: Vec<String> = game.checkers().iter().map(Square::to_string).collect();
+        board_str.push_str(&format!("Check from {}\n", checkers.join(", ")));
+    }
+    if game.variant() == Variant::ThreeCheck {
+        let state = game.variant_state();
+        board_str.push_str(&format!(
+            "Checks given: White {}, Black {}\n",
+            state.checks(Color::White),
+            state.checks(Color::Black)
+        ));
+    }
+    board_str
 }
 
 /// Prints the history of moves played so far.
 ///
 /// Moves are displayed in pairs using UCI notation, along
-/// with their number - first the white move, then black.
+/// with their number and the time spent on each of them -
+/// first the white move, then black.
 /// If black hasn't made their move in the last turn,
-/// only white move is printed.
+/// only white move is printed. If black moved first
+/// (e.g. in a pawn-and-move odds game), the first
+/// white move is shown as `...`.
 fn print_move_history(game: &Game) {
     println!("Move history:");
-    for (i, chunk) in game.moves().chunks(2).enumerate() {
+    if !game.move_tree().comment.is_empty() {
+        println!("{{{}}}", game.move_tree().comment);
+    }
+    let timed: Vec<String> = game
+        .move_tree()
+        .line(game.current_path())
+        .into_iter()
+        .zip(game.moves().iter().zip(game.move_times()))
+        .map(|(node, (mv, time))| {
+            let glyphs: String = node.nags.iter().map(|nag| nag_glyph(*nag)).collect();
+            let mut entry = format!("{}{} ({:.1}s)", mv, glyphs, time.as_secs_f64());
+            if !node.comment.is_empty() {
+                entry.push_str(&format!(" {{{}}}", node.comment));
+            }
+            entry
+        })
+        .collect();
+    let mut moves = &timed[..];
+    let mut number = 1;
+    if game.starting_position().side_to_move() == Color::Black {
+        if let Some((first, rest)) = moves.split_first() {
+            println!("1. ... {}", first);
+            moves = rest;
+            number = 2;
+        }
+    }
+    for (i, chunk) in moves.chunks(2).enumerate() {
         match chunk {
-            [w, b] => println!("{}. {} {}", i + 1, w, b),
-            [w] => println!("{}. {}", i + 1, w),
+            [w, b] => println!("{}. {} {}", i + number, w, b),
+            [w] => println!("{}. {}", i + number, w),
             _ => unreachable!(),
         }
     }
     println!();
 }
+
+/// Measures the Elo the AI plays at held back to each calibrated level
+/// of [`StrengthLimit`], printing its performance next to the target.
+///
+/// Each level plays `games` games, 10 by default, against the one below
+/// it, the weakest against the greedy baseline, rated [`GREEDY_ELO`].
+pub fn calibrate(games: Option<u32>) {
+    let games = games.unwrap_or(10).max(1);
+    let mut opponent = PlayerConfig {
+        baseline: Some(Baseline::Greedy),
+        ..PlayerConfig::default()
+    };
+    let mut opponent_elo = GREEDY_ELO;
+    for elo in StrengthLimit::calibrated_elos() {
+        let player = PlayerConfig {
+            elo: Some(elo),
+            ..PlayerConfig::default()
+        };
+        let config = MatchConfig {
+            a: player.clone(),
+            b: opponent,
+            games,
+            ..MatchConfig::default()
+        };
+        let points: f64 = (1..=games)
+            .map(|number| config.play_game(number % 2 == 1, None).outcome.score())
+            .sum();
+        let rating = performance(opponent_elo, points / f64::from(games));
+        println!(
+            "Elo {}: {}/{} against an opponent rated {:.0}, performance {:.0}",
+            elo, points, games, opponent_elo, rating
+        );
+        opponent = player;
+        opponent_elo = rating;
+    }
+}
+
+/// Runs the search over a fixed set of positions and prints node counts
+/// and timings for every search technique, alone and combined.
+///
+/// Used to measure how much each search technique saves
+/// at a given depth. Defaults to depth 5.
+pub fn bench(depth: Option<u32>) {
+    let depth = depth.unwrap_or(5);
+    let none = EngineOptions {
+        null_move_pruning: false,
+        late_move_reductions: false,
+        futility_pruning: false,
+        check_extensions: false,
+        singular_extensions: false,
+        aspiration_window: None,
+        contempt: 0,
+        weights: EvalWeights::default(),
+        use_network: true,
+        limits: SearchLimits::default(),
+        params: SearchParams::default(),
+        table: None,
+        ..EngineOptions::default()
+    };
+    let configurations = [
+        ("none", none.clone()),
+        (
+            "null move",
+            EngineOptions {
+                null_move_pruning: true,
+                ..none.clone()
+            },
+        ),
+        (
+            "lmr",
+            EngineOptions {
+                late_move_reductions: true,
+                ..none.clone()
+            },
+        ),
+        (
+            "futility",
+            EngineOptions {
+                futility_pruning: true,
+                ..none.clone()
+            },
+        ),
+        (
+            "check ext",
+            EngineOptions {
+                check_extensions: true,
+                ..none.clone()
+            },
+        ),
+        (
+            "singular ext",
+            EngineOptions {
+                singular_extensions: true,
+                table: Some(SharedTable::default()),
+                ..none.clone()
+            },
+        ),
+        (
+            "aspiration",
+            EngineOptions {
+                aspiration_window: EngineOptions::default().aspiration_window,
+                ..none.clone()
+            },
+        ),
+        (
+            "hash table",
+            EngineOptions {
+                table: Some(SharedTable::default()),
+                ..none.clone()
+            },
+        ),
+        (
+            "all",
+            EngineOptions {
+                table: Some(SharedTable::default()),
+                ..EngineOptions::default()
+            },
+        ),
+    ];
+
+    println!("Benchmark at depth {}", depth);
+    for (name, options) in &configurations {
+        println!("{}:", name);
+        let start = Instant::now();
+        let mut nodes = 0;
+        for (i, fen) in BENCH_POSITIONS.iter().enumerate() {
+            let board = Board::from_str(fen).unwrap();
+            let result = search(&board, depth, options);
+            match result.best_move {
+                Some(mv) => println!("  position {}: {} ({})", i + 1, mv, result.score),
+                None => println!("  position {}: none ({})", i + 1, result.score),
+            }
+            nodes += result.nodes;
+        }
+        let elapsed = start.elapsed();
+        let nps = nodes as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        println!(
+            "  total: {} nodes, {} ms, {:.0} nps",
+            nodes,
+            elapsed.as_millis(),
+            nps
+        );
+    }
+}
+
+/// Number of openings listed by the `stats` command.
+const TOP_OPENINGS: usize = 10;
+
+/// Reads a PGN database and prints aggregate statistics: results by
+/// color, the most common openings by ECO code, the average game length
+/// and the score of every player.
+pub fn stats(path: Option<&str>) {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: rChess stats <file.pgn>");
+            return;
+        }
+    };
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path, e);
+            return;
+        }
+    };
+    let stats = DatabaseStats::from_games(&parse_pgn(&text));
+
+    println!("Games: {}", stats.games);
+    if stats.games == 0 {
+        return;
+    }
+    let percent = |count: u32| 100.0 * count as f64 / stats.games as f64;
+    println!(
+        "White wins: {} ({:.1}%)",
+        stats.white_wins,
+        percent(stats.white_wins)
+    );
+    println!(
+        "Black wins: {} ({:.1}%)",
+        stats.black_wins,
+        percent(stats.black_wins)
+    );
+    println!("Draws: {} ({:.1}%)", stats.draws, percent(stats.draws));
+    if stats.unfinished > 0 {
+        println!("Unfinished: {}", stats.unfinished);
+    }
+    println!("Average length: {:.1} moves", stats.average_length);
+
+    if !stats.openings.is_empty() {
+        println!();
+        println!("Most common openings:");
+        for (eco, count) in stats.openings.iter().take(TOP_OPENINGS) {
+            println!("  {:<4} {:>6} ({:.1}%)", eco, count, percent(*count));
+        }
+    }
+    if !stats.players.is_empty() {
+        println!();
+        println!("Players:");
+        for player in &stats.players {
+            println!(
+                "  {:<24} {:>6}/{:<4} ({:.1}%)",
+                player.name,
+                player.points,
+                player.games,
+                100.0 * player.points / player.games as f64
+            );
+        }
+    }
+}
+
+/// Searches the archive of played games and the PGN files in `args`,
+/// after the position's FEN, for games reaching the position, printing
+/// each game's players and result and when the position occurs.
+pub fn find_position(args: &[String]) {
+    let Some(position) = args.first() else {
+        eprintln!("Usage: rChess find-position <fen> [file.pgn ...]");
+        return;
+    };
+    let board = match validate_fen(position) {
+        Ok(board) => board,
+        Err(e) => {
+            eprintln!("Invalid FEN {}: {}", position, e);
+            return;
+        }
+    };
+    // The archive only exists once a game has been finished.
+    let mut paths: Vec<PathBuf> = archive_path()
+        .filter(|path| path.exists())
+        .into_iter()
+        .collect();
+    paths.extend(args[1..].iter().map(PathBuf::from));
+
+    let mut found = 0;
+    for path in &paths {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let games = parse_pgn(&text);
+        for found_at in archive::find_position(&games, &board) {
+            let game = &games[found_at.game];
+            println!(
+                "{} game {}: {} - {}, {}, {}",
+                path.display(),
+                found_at.game + 1,
+                game.tag("White").unwrap_or("?"),
+                game.tag("Black").unwrap_or("?"),
+                game.result().unwrap_or("*"),
+                found_at.when(game)
+            );
+            found += 1;
+        }
+    }
+    println!("{} game(s) found", found);
+}
+
+/// Makes an opening book from the games of a PGN file, which the AI
+/// plays from once it's set as `book_file` in the configuration.
+///
+/// `args` are the PGN file and the book file, followed by `--plies n`,
+/// how many plies of each game go into the book, and `--min-games n`,
+/// how many games a move has to be played in to be kept.
+pub fn make_book(args: &[String]) {
+    const USAGE: &str =
+        "Usage: rChess make-book <games.pgn> <book.bin> [--plies n] [--min-games n]";
+    let [input, output, settings @ ..] = args else {
+        eprintln!("{}", USAGE);
+        return;
+    };
+    let mut options = BookOptions::default();
+    let mut settings = settings.iter();
+    while let Some(arg) = settings.next() {
+        let valid = match (arg.as_str(), settings.next()) {
+            ("--plies", Some(value)) => value.parse().map(|plies| options.plies = plies).is_ok(),
+            ("--min-games", Some(value)) => {
+                value.parse().map(|count| options.min_games = count).is_ok()
+            }
+            _ => false,
+        };
+        if !valid {
+            eprintln!("{}", USAGE);
+            return;
+        }
+    }
+    let text = match fs::read_to_string(input) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", input, e);
+            return;
+        }
+    };
+    let games = parse_pgn(&text);
+    let book = OpeningBook::from_games(&games, options);
+    match book.save(Path::new(output)) {
+        Ok(()) => println!(
+            "Book of {} moves from {} games saved to {}",
+            book.len(),
+            games.len(),
+            output
+        ),
+        Err(e) => eprintln!("Error writing {}: {}", output, e),
+    }
+}
+
+/// Plays an engine-vs-engine match between two engines configured
+/// independently, printing each game's result and the final score.
+///
+/// `args` are an optional match file, see [`MatchConfig`], followed by
+/// settings overriding it, e.g. `--games 10 --a.depth 3 --b.engine mcts`.
+/// With a SPRT, its log-likelihood ratio is printed after every game
+/// and the match stops once the test is decided. With an opening suite,
+/// A's score from each opening is printed at the end.
+pub fn play_match(args: &[String]) {
+    let mut config = MatchConfig::default();
+    let mut settings = args.iter();
+    if let Some(path) = args.first().filter(|arg| !arg.starts_with("--")) {
+        let parsed = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| MatchConfig::from_str(&text));
+        match parsed {
+            Ok(parsed) => config = parsed,
+            Err(e) => {
+                eprintln!("Error in {}: {}", path, e);
+                return;
+            }
+        }
+        settings.next();
+    }
+    while let Some(arg) = settings.next() {
+        let setting = arg
+            .strip_prefix("--")
+            .zip(settings.next())
+            .ok_or_else(|| format!("expected `--<setting> <value>`, got `{}`", arg))
+            .and_then(|(key, value)| config.set(key, value));
+        if let Err(e) = setting {
+            eprintln!("{}", e);
+            eprintln!("Usage: rChess match [match.toml] [--<setting> <value> ...]");
+            return;
+        }
+    }
+
+    info!("Match of {} games started: {:?}", config.games, config);
+    let (mut wins, mut losses, mut draws) = (0, 0, 0);
+    // A's points and number of games from each opening.
+    let mut by_opening = vec![(0.0, 0); config.openings.len()];
+    for number in 1..=config.games {
+        let a_white = number % 2 == 1;
+        let index = config.opening_index(number);
+        let opening = index.map(|index| &config.openings[index]);
+        let played = config.play_game(a_white, opening);
+        match played.outcome {
+            Outcome::Win => wins += 1,
+            Outcome::Loss => losses += 1,
+            Outcome::Draw => draws += 1,
+        }
+        if let Some(index) = index {
+            by_opening[index].0 += played.outcome.score();
+            by_opening[index].1 += 1;
+        }
+        let (white, black) = if a_white { ("A", "B") } else { ("B", "A") };
+        let from = opening.map_or(String::new(), |opening| format!(" ({})", opening.name));
+        println!(
+            "Game {}{}: {} - {} {} in {} moves",
+            number,
+            from,
+            white,
+            black,
+            played.result(),
+            played.game.moves().len().div_ceil(2)
+        );
+        if let Some(sprt) = config.sprt {
+            let (lower, upper) = sprt.bounds();
+            println!(
+                "LLR {:.2} ({:.2}, {:.2}) [{}, {}]",
+                sprt.llr(wins, draws, losses),
+                lower,
+                upper,
+                sprt.elo0,
+                sprt.elo1
+            );
+            match sprt.result(wins, draws, losses) {
+                SprtResult::Continue => (),
+                SprtResult::AcceptH0 => {
+                    println!(
+                        "H0 accepted: A is closer to {} than {} Elo stronger than B",
+                        sprt.elo0, sprt.elo1
+                    );
+                    break;
+                }
+                SprtResult::AcceptH1 => {
+                    println!(
+                        "H1 accepted: A is closer to {} than {} Elo stronger than B",
+                        sprt.elo1, sprt.elo0
+                    );
+                    break;
+                }
+            }
+        }
+    }
+    if config
+        .sprt
+        .is_some_and(|sprt| sprt.result(wins, draws, losses) == SprtResult::Continue)
+    {
+        println!("SPRT undecided after {} games", wins + draws + losses);
+    }
+    if by_opening.iter().any(|(_, games)| *games > 0) {
+        println!("Score of A by opening:");
+        for (opening, (points, games)) in config.openings.iter().zip(&by_opening) {
+            if *games > 0 {
+                println!("  {:<40} {}/{}", opening.name, points, games);
+            }
+        }
+    }
+    let points = |wins: u32| wins as f64 + draws as f64 / 2.0;
+    println!(
+        "Score: A {} - B {} (+{} -{} ={})",
+        points(wins),
+        points(losses),
+        wins,
+        losses,
+        draws
+    );
+}
+
+/// Plays a tournament between several engines, printing each game's
+/// result and the crosstable at the end, and saves all the games as
+/// PGN.
+///
+/// `args` are an optional arena file, see [`ArenaConfig`], followed by
+/// settings overriding it, e.g. `--format gauntlet --sf.command stockfish`.
+/// Each pair of engines plays as for [`play_match`], without a SPRT.
+pub fn arena(args: &[String]) {
+    const USAGE: &str = "Usage: rChess arena [arena.toml] [--<setting> <value> ...]";
+    let mut config = ArenaConfig::default();
+    let mut settings = args.iter();
+    if let Some(path) = args.first().filter(|arg| !arg.starts_with("--")) {
+        let parsed = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ArenaConfig::from_str(&text));
+        match parsed {
+            Ok(parsed) => config = parsed,
+            Err(e) => {
+                eprintln!("Error in {}: {}", path, e);
+                return;
+            }
+        }
+        settings.next();
+    }
+    while let Some(arg) = settings.next() {
+        let setting = arg
+            .strip_prefix("--")
+            .zip(settings.next())
+            .ok_or_else(|| format!("expected `--<setting> <value>`, got `{}`", arg))
+            .and_then(|(key, value)| config.set(key, value));
+        if let Err(e) = setting {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            return;
+        }
+    }
+    if config.engines.len() < 2 {
+        eprintln!("An arena needs at least two engines");
+        eprintln!("{}", USAGE);
+        return;
+    }
+
+    info!("Arena started: {:?}", config);
+    let names: Vec<_> = config
+        .engines
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut table = Crosstable::new(names.clone());
+    let mut pgn = String::new();
+    let mut number = 0;
+    for (round, (a, b)) in config.pairings().into_iter().enumerate() {
+        let pairing = config.pairing(a, b);
+        for game in 1..=pairing.games {
+            number += 1;
+            let a_white = game % 2 == 1;
+            let opening = pairing
+                .opening_index(game)
+                .map(|index| &pairing.openings[index]);
+            let played = pairing.play_game(a_white, opening);
+            table.record(a, b, played.outcome);
+            let (white, black) = if a_white { (a, b) } else { (b, a) };
+            println!(
+                "Game {}: {} - {} {} in {} moves",
+                number,
+                names[white],
+                names[black],
+                played.result(),
+                played.game.moves().len().div_ceil(2)
+            );
+            let round = format!("{}.{}", round + 1, game);
+            pgn.push_str(&write_game(&played, &names[white], &names[black], &round));
+            pgn.push('\n');
+        }
+    }
+    println!("{}", table);
+    match fs::write(&config.pgn, pgn) {
+        Ok(()) => println!("Games saved to {}", config.pgn.display()),
+        Err(e) => eprintln!("Error saving {}: {}", config.pgn.display(), e),
+    }
+}
+
+/// Plays the moves and commands of a script non-interactively and
+/// prints the final position in FEN and the game status.
+///
+/// The script is read from `path`, or from standard input if no path
+/// or `-` is given. See [`run_script`] for the script format. Exits
+/// with a non-zero code if the script can't be read or fails.
+pub fn script(path: Option<&str>) {
+    let mut text = String::new();
+    let read = match path {
+        None | Some("-") => io::stdin().read_to_string(&mut text).map(|_| ()),
+        Some(path) => fs::read_to_string(path).map(|contents| text = contents),
+    };
+    if let Err(e) = read {
+        eprintln!("Error reading script: {}", e);
+        process::exit(1);
+    }
+
+    match run_script(&text) {
+        Ok(game) => {
+            println!("FEN: {}", game.fen());
+            let state = game.state();
+            match state.status {
+                Status::Ongoing => println!(
+                    "Status: ongoing, {:?} to move{}",
+                    game.turn(),
+                    if state.is_check { " in check" } else { "" }
+                ),
+                Status::Checkmate(color) => println!("Status: checkmate, {:?} wins", color),
+                Status::Stalemate => println!("Status: stalemate"),
+                Status::VariantWin(color) => {
+                    println!("Status: {:?} wins ({})", color, game.variant())
+                }
+                Status::Resignation(color) => println!("Status: {:?} wins by resignation", color),
+                Status::Timeout(color) => println!("Status: {:?} wins on time", color),
+                Status::TimeoutDraw(color) => {
+                    println!("Status: draw, {:?} ran out of time", color)
+                }
+                Status::DrawAgreed => println!("Status: draw agreed"),
+            }
+        }
+        Err(e) => {
+            eprintln!("Error in script, {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Manages player profiles: `list` shows every player's rating,
+/// `show <name>` a player's results by difficulty and `delete <name>`
+/// removes a profile.
+pub fn profile(args: &[String]) {
+    let Some(path) = profiles_path() else {
+        eprintln!("No config directory found for storing profiles");
+        return;
+    };
+    let mut store = match ProfileStore::load(&path) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error reading profiles: {}", e);
+            return;
+        }
+    };
+    let name = args.get(1..).unwrap_or_default().join(" ");
+    match args.first().map(String::as_str) {
+        None | Some("list") => {
+            if store.profiles().is_empty() {
+                println!("No profiles yet");
+            }
+            for profile in store.profiles() {
+                println!(
+                    "{:<24} {:>6.0} ({} games)",
+                    profile.name,
+                    profile.rating,
+                    profile.games()
+                );
+            }
+        }
+        Some("show") => match store.get(&name) {
+            Some(profile) => {
+                println!("{}: rating {:.0}", profile.name, profile.rating);
+                for difficulty in DIFFICULTIES {
+                    let record = profile.record(difficulty);
+                    println!(
+                        "  vs {:<8} +{} ={} -{}",
+                        format!("{:?}", difficulty),
+                        record.wins,
+                        record.draws,
+                        record.losses
+                    );
+                }
+                if let Some(accuracy) = profile.accuracy() {
+                    println!("Average accuracy: {:.1}%", accuracy);
+                }
+                println!(
+                    "Sessions: {}, time played: {}",
+                    profile.sessions(),
+                    format_time(profile.time_played())
+                );
+                println!(
+                    "Best coordinate scores: {} naming squares, {} telling colors",
+                    profile.best_coords(CoordsMode::Name),
+                    profile.best_coords(CoordsMode::Color)
+                );
+                println!("Suggested difficulty: {:?}", profile.suggested_difficulty());
+            }
+            None => eprintln!("No profile named {}", name),
+        },
+        Some("delete") => {
+            if !store.remove(&name) {
+                eprintln!("No profile named {}", name);
+            } else if let Err(e) = store.save(&path) {
+                eprintln!("Error saving profiles: {}", e);
+            } else {
+                println!("Deleted profile {}", name);
+            }
+        }
+        Some(_) => eprintln!("Usage: rChess profile [list | show <name> | delete <name>]"),
+    }
+}
+
+/// Practices an opening line from memory: the program plays the
+/// opponent's moves of the line and corrects the player's moves that
+/// leave it.
+///
+/// Lines are read from the PGN file at `path`, or taken from the
+/// built-in repertoire if no file is given.
+pub fn drill(path: Option<&str>, display: Display) {
+    let repertoire = match path {
+        Some(path) => match fs::read_to_string(path) {
+            Ok(text) => Repertoire::from_pgn(&text),
+            Err(e) => Err(format!("Error reading {}: {}", path, e)),
+        },
+        None => Ok(Repertoire::builtin()),
+    };
+    let repertoire = match repertoire {
+        Ok(repertoire) if !repertoire.lines.is_empty() => repertoire,
+        Ok(_) => {
+            eprintln!("No lines to practice");
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    for (i, line) in repertoire.lines.iter().enumerate() {
+        println!("{:>3}. {}", i + 1, line.name);
+    }
+    let numbers: Vec<String> = (1..=repertoire.lines.len())
+        .map(|n| n.to_string())
+        .collect();
+    let numbers: Vec<&str> = numbers.iter().map(String::as_str).collect();
+    let input = prompt("Select a line to practice: ", &numbers, display.language);
+    let line = &repertoire.lines[input.parse::<usize>().unwrap() - 1];
+    let input = prompt(
+        "Select your color (white or black): ",
+        &["white", "black"],
+        display.language,
+    );
+    let color = if input == "white" {
+        Color::White
+    } else {
+        Color::Black
+    };
+
+    println!(
+        "Play the moves of {} (hint to show the next move, quit to exit)",
+        line.name
+    );
+    let mut drill = Drill::new(line, color);
+    while !drill.finished() {
+        if let Some(mv) = drill.play_opponent() {
+            println!("Opponent plays {}", mv);
+            continue;
+        }
+        display_board(drill.game(), display);
+        let input = read_line("Enter move: ");
+        match input.trim() {
+            "quit" => return,
+            "hint" => println!("The line continues with {}", drill.hint().unwrap()),
+            input => match drill.play(input) {
+                Ok(DrillAnswer::Correct) => println!("Correct!"),
+                Ok(DrillAnswer::Wrong(expected)) => {
+                    println!(
+                        "That leaves the line, which continues with {}. Try again.",
+                        expected
+                    )
+                }
+                Err(e) => println!("{}", e),
+            },
+        }
+    }
+    println!(
+        "Line completed with {} mistake{}!",
+        drill.mistakes(),
+        if drill.mistakes() == 1 { "" } else { "s" }
+    );
+}
+
+/// Trains a built-in endgame position against the engine defending at
+/// full strength, checking that the player reaches the exercise's goal
+/// within its move budget.
+pub fn endgame(display: Display) {
+    for (i, exercise) in EXERCISES.iter().enumerate() {
+        println!("{:>3}. {}", i + 1, exercise.name);
+    }
+    let numbers: Vec<String> = (1..=EXERCISES.len()).map(|n| n.to_string()).collect();
+    let numbers: Vec<&str> = numbers.iter().map(String::as_str).collect();
+    let input = prompt("Select an exercise: ", &numbers, display.language);
+    let exercise = EXERCISES[input.parse::<usize>().unwrap() - 1];
+
+    let mut trainer = Trainer::new(exercise);
+    let engine = !trainer.player();
+    let listener = announcer(trainer.game(), Some((engine, "AI")), display);
+    trainer.game_mut().subscribe(listener);
+    add_move_hook(trainer.game_mut(), display);
+    match exercise.goal {
+        Goal::Win => println!(
+            "You play {:?}: checkmate within {} moves!",
+            trainer.player(),
+            exercise.moves
+        ),
+        Goal::Draw => println!(
+            "You play {:?}: hold out for {} moves!",
+            trainer.player(),
+            exercise.moves
+        ),
+    }
+    let verdict = loop {
+        if let Some(verdict) = trainer.verdict() {
+            break verdict;
+        }
+        if trainer.game().turn() != trainer.player() {
+            if let Err(e) = trainer.reply() {
+                println!("{}", e);
+            }
+            continue;
+        }
+        display_board(trainer.game(), display);
+        let input = read_line(&format!("Enter move ({} left): ", trainer.moves_left()));
+        match input.trim() {
+            "quit" => return,
+            input => {
+                if let Err(e) = trainer.play(input) {
+                    println!("{}", e);
+                }
+            }
+        }
+    };
+    match verdict {
+        Verdict::Passed => println!("Exercise passed!"),
+        Verdict::Failed => println!("Exercise failed, try again!"),
+    }
+}
+
+/// Trains board coordinates for a round of [`ROUND_TIME`]: naming
+/// squares highlighted on the board, or telling whether named squares
+/// are light or dark, given by `mode`. The best score of the player
+/// is kept in their profile.
+pub fn coords(mode: Option<&str>) {
+    let input = match mode {
+        Some(mode @ ("name" | "color")) => mode.to_string(),
+        _ => prompt(
+            "Name highlighted squares or tell square colors? (name or color): ",
+            &["name", "color"],
+            Language::English,
+        ),
+    };
+    let mode = match input.as_str() {
+        "name" => CoordsMode::Name,
+        _ => CoordsMode::Color,
+    };
+    let mut position = Board::default();
+    if mode == CoordsMode::Name {
+        let input = prompt(
+            "See the board as (white or black): ",
+            &["white", "black"],
+            Language::English,
+        );
+        if input == "black" {
+            position = position.null_move().unwrap();
+        }
+    }
+    let profile = Config::load()
+        .name
+        .or_else(|| ask_profile(None, Language::English));
+    read_line(&format!(
+        "You have {} seconds, press Enter to start",
+        ROUND_TIME.as_secs()
+    ));
+    let mut trainer = CoordsTrainer::new(mode);
+    while !trainer.is_over() {
+        if trainer.mode() == CoordsMode::Name {
+            let mut board = Game::new_multi().with_position(position);
+            board.annotations_mut().toggle_mark(trainer.square());
+            println!("{}", board_diagram(&board, false, 1));
+        }
+        let question = trainer.question();
+        let solution = trainer.solution();
+        let input = read_line(&question);
+        if input.trim() == "quit" {
+            return;
+        }
+        if trainer.is_over() {
+            println!("Time's up!");
+        } else if trainer.answer(&input) {
+            println!("Right! ({}s left)", trainer.time_left().as_secs());
+        } else {
+            println!("Wrong, it's {}", solution);
+        }
+    }
+    println!(
+        "Score: {} right, {} wrong",
+        trainer.score(),
+        trainer.mistakes()
+    );
+    let Some(name) = profile else {
+        return;
+    };
+    let Some(path) = profiles_path() else {
+        return;
+    };
+    let mut store = match ProfileStore::load(&path) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error reading profiles: {}", e);
+            return;
+        }
+    };
+    let profile = store.get_or_create(&name);
+    if profile.add_coords_score(mode, trainer.score()) {
+        println!("New best score!");
+    } else {
+        println!("Best score: {}", profile.best_coords(mode));
+    }
+    if let Err(e) = store.save(&path) {
+        eprintln!("Error saving profiles: {}", e);
+    }
+}
+
+/// Plays piece-path puzzles: taking a `piece`, e.g. `knight`, to a
+/// target square without stopping on squares enemy pieces stand on or
+/// attack, in as few moves as possible.
+pub fn paths(piece: Option<&str>) {
+    let names: Vec<String> = PATH_PIECES
+        .iter()
+        .map(|piece| format!("{:?}", piece).to_lowercase())
+        .collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let input = match piece {
+        Some(piece) if names.contains(&piece) => piece.to_string(),
+        _ => prompt(
+            &format!("Select a piece ({}): ", names.join(", ")),
+            &names,
+            Language::English,
+        ),
+    };
+    let piece = PATH_PIECES[names.iter().position(|name| *name == input).unwrap()];
+    loop {
+        let mut puzzle = PathPuzzle::new(piece);
+        println!(
+            "Take the {} from {} to {}, never stopping on a square marked x or taken by an enemy",
+            format!("{:?}", puzzle.piece()).to_lowercase(),
+            puzzle.current(),
+            puzzle.target()
+        );
+        while !puzzle.is_solved() {
+            println!("{}", puzzle.diagram());
+            let input = read_line(&format!("Move {} (restart or quit): ", puzzle.steps() + 1));
+            match input.trim() {
+                "quit" => return,
+                "restart" => puzzle.restart(),
+                input => {
+                    if let Err(e) = puzzle.step(input) {
+                        println!("{}", e);
+                    }
+                }
+            }
+        }
+        if puzzle.steps() == puzzle.best() {
+            println!("Solved in {} moves, the shortest path!", puzzle.steps());
+        } else {
+            println!(
+                "Solved in {} moves, it can be done in {}",
+                puzzle.steps(),
+                puzzle.best()
+            );
+        }
+        if prompt(
+            "Another puzzle? (yes or no): ",
+            &["yes", "no"],
+            Language::English,
+        ) == "no"
+        {
+            return;
+        }
+    }
+}
+
+/// Plays a simul: you against the AI on `boards` boards at once, going
+/// from board to board after each of your moves. `board <number>` goes
+/// to another board and `board` lists them all.
+pub fn simul(boards: Option<usize>, mut display: Display) {
+    let boards = boards
+        .filter(|&boards| boards > 0)
+        .unwrap_or(DEFAULT_BOARDS);
+    let input = prompt(
+        "Select difficulty (random, greedy, beginner, easy, medium or hard): ",
+        &[
+            "random", "level0", "greedy", "beginner", "level1", "easy", "medium", "hard",
+        ],
+        display.language,
+    );
+    let difficulty = Difficulty::from_str(&input).unwrap();
+    let config = Config::load();
+    let mut simul = Simul::new(boards, |color| {
+        // The boards share the memory of a single game's table.
+        let mut game = Game::new_single(color, difficulty)
+            .with_engine(config.engine)
+            .with_limits(config.limits)
+            .with_hash((config.hash_size / boards).max(1));
+        game.subscribe(announcer(&game, Some((!color, "AI")), display));
+        add_move_hook(&mut game, display);
+        game
+    });
+    info!("Simul on {} boards against the {:?} AI", boards, difficulty);
+    println!(
+        "Simul on {} boards: you play White on odd boards and Black on even ones",
+        boards
+    );
+    loop {
+        let board = simul.current();
+        let player = simul.player(board);
+        if simul.game().status() != Status::Ongoing {
+            if !simul.next_board() {
+                break;
+            }
+            continue;
+        }
+        if simul.game().turn() != player {
+            if let Err(e) = simul.reply() {
+                println!("{}", e);
+                break;
+            }
+            continue;
+        }
+        println!("{}", simul.describe(board));
+        display_board(simul.game(), display);
+        let input = read_line(display.language.text(Text::EnterMove));
+        let input = input.trim();
+
+        match parse_command(input, Mode::Simul, &config.aliases) {
+            Some((Command::Quit, _)) => break,
+            Some((Command::Board, "")) => {
+                for board in 0..simul.boards() {
+                    println!("{}", simul.describe(board));
+                }
+            }
+            Some((Command::Board, number)) => {
+                let result = number
+                    .parse()
+                    .map_err(|_| ChessError::InvalidInput)
+                    .and_then(|number| simul.switch(number));
+                if let Err(e) = result {
+                    println!("{}", e);
+                }
+            }
+            Some((Command::Resign, _)) => {
+                if let Err(e) = simul.game_mut().resign(player) {
+                    println!("{}", e);
+                }
+            }
+            Some((command, arguments)) => {
+                let result = game_command(
+                    simul.game_mut(),
+                    command,
+                    arguments,
+                    Mode::Simul,
+                    player,
+                    &mut display,
+                );
+                if let Err(e) = result {
+                    println!("{}", e);
+                }
+            }
+            None => match play_input(simul.game_mut(), input, display.language) {
+                Ok(true) => {
+                    if simul.game().status() == Status::Ongoing {
+                        if let Err(e) = simul.reply() {
+                            println!("{}", e);
+                        }
+                    }
+                    simul.next_board();
+                }
+                Ok(false) => (),
+                Err(e) => println!("{}", e),
+            },
+        }
+    }
+    if simul.is_over() {
+        println!("The simul is over!");
+    }
+    let ai = format!("rChess AI ({:?})", difficulty);
+    for (board, game) in simul.games().iter().enumerate() {
+        println!("{}", simul.describe(board));
+        let (white, black) = match simul.player(board) {
+            Color::White => ("Player".to_string(), ai.clone()),
+            Color::Black => (ai.clone(), "Player".to_string()),
+        };
+        if simul.outcome(board).is_some() {
+            session::record_game(SessionGame::new(game, Some(simul.player(board)), None));
+        }
+        archive_game(
+            game,
+            &[
+                ("Event", "rChess simul".to_string()),
+                ("Board", (board + 1).to_string()),
+                ("White", white),
+                ("Black", black),
+            ],
+        );
+    }
+    println!(
+        "You scored {} out of {} points",
+        simul.score(),
+        simul.boards()
+    );
+}
+
+/// Downloads the game at `url` from Lichess or Chess.com and opens it
+/// for review.
+#[cfg(feature = "import")]
+pub fn import(url: Option<&str>, display: Display) {
+    let Some(url) = url else {
+        eprintln!("Usage: rChess import <url>");
+        return;
+    };
+    let saved = match crate::import::import(url) {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("Error importing {}: {}", url, e);
+            return;
+        }
+    };
+    let game = match crate::pgn::replay(&saved, Game::new_multi()) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("Error reading the game: {}", e);
+            return;
+        }
+    };
+    info!("Imported {} with {} moves", url, game.moves().len());
+    println!(
+        "{} - {}, {}",
+        saved.tag("White").unwrap_or("?"),
+        saved.tag("Black").unwrap_or("?"),
+        saved.result().unwrap_or("*")
+    );
+    analysis_board(game, &saved, display);
+}
+
+#[cfg(not(feature = "import"))]
+pub fn import(_url: Option<&str>, _display: Display) {
+    eprintln!("rChess was built without the import feature");
+}
+
+/// Opens the first game of the PGN file at `path` on an analysis board.
+pub fn review(path: Option<&str>, display: Display) {
+    let Some(path) = path else {
+        eprintln!("Usage: rChess review <file.pgn>");
+        return;
+    };
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path, e);
+            return;
+        }
+    };
+    let Some(saved) = parse_pgn(&text).into_iter().next() else {
+        eprintln!("No game found in {}", path);
+        return;
+    };
+    match crate::pgn::replay(&saved, Game::new_multi()) {
+        Ok(game) => analysis_board(game, &saved, display),
+        Err(e) => eprintln!("Error reading the game: {}", e),
+    }
+}
+
+/// Opens `game` on an analysis board, starting from its first move:
+/// `next [n]` (or just Enter) plays the line's next move, or the `n`th
+/// move played from the position, `back` goes one move back, `start` and
+/// `end` jump to either end of the line, and a move entered plays it,
+/// starting a variation if it wasn't played there yet. `comment "text"`
+/// and `nag <glyph>` annotate the move shown, `analyze [depth]` searches
+/// the position and `save <file>` writes the game with its variations
+/// and annotations as PGN, with the tags of `saved`.
+fn analysis_board(mut game: Game, saved: &PgnGame, mut display: Display) {
+    let _ = game.branch(0);
+    loop {
+        display_board(&game, display);
+        let moves = game.moves();
+        if let Some(&last) = moves.last() {
+            let before = moves[..moves.len() - 1]
+                .iter()
+                .fold(*game.starting_position(), |board, mv| {
+                    board.make_move_new(*mv)
+                });
+            let dots = match before.side_to_move() {
+                Color::White => ".",
+                Color::Black => "...",
+            };
+            let glyphs: String = game.nags().iter().map(|nag| nag_glyph(*nag)).collect();
+            println!(
+                "After {}{} {}{}",
+                moves.len().div_ceil(2),
+                dots,
+                display.language.local_san(&before, last),
+                glyphs
+            );
+        }
+        if !game.comment().is_empty() {
+            println!("{{{}}}", game.comment());
+        }
+        let variations = game.variations();
+        if variations.len() > 1 {
+            let choices: Vec<String> = variations
+                .iter()
+                .enumerate()
+                .map(|(i, mv)| {
+                    format!(
+                        "{}) {}",
+                        i + 1,
+                        display.language.local_san(game.board(), *mv)
+                    )
+                })
+                .collect();
+            println!("Moves played here: {}", choices.join(", "));
+        }
+        let continuation = game.move_tree().node(game.current_path()).main_line();
+        let input = read_line(&format!(
+            "Move {} of {} (next [n], back, start, end, <move>, comment \"text\", nag <glyph>, analyze [depth], save <file> or quit): ",
+            moves.len(),
+            moves.len() + continuation.len()
+        ));
+        let input = input.trim();
+        let (command, argument) = input.split_once(' ').unwrap_or((input, ""));
+        match command {
+            "" | "next" => {
+                let n = argument.parse().unwrap_or(1_usize);
+                match n.checked_sub(1).and_then(|i| variations.get(i)) {
+                    Some(&mv) => {
+                        let _ = game.make_move(mv);
+                    }
+                    None if variations.is_empty() => println!("No more moves in this line"),
+                    None => println!("{}", ChessError::InvalidInput),
+                }
+            }
+            "back" => {
+                let _ = game.branch(moves.len().saturating_sub(1));
+            }
+            "start" => {
+                let _ = game.branch(0);
+            }
+            "end" => {
+                for mv in continuation {
+                    let _ = game.make_move(mv);
+                }
+            }
+            "comment" | "nag" => {
+                let command = match command {
+                    "comment" => Command::Comment,
+                    _ => Command::Nag,
+                };
+                if let Err(e) = game_command(
+                    &mut game,
+                    command,
+                    argument,
+                    Mode::TwoPlayer,
+                    Color::White,
+                    &mut display,
+                ) {
+                    println!("{}", e);
+                }
+            }
+            "analyze" => {
+                let depth = argument.parse().unwrap_or(6);
+                analyze(game.board(), depth, &EngineOptions::default());
+            }
+            "save" if !argument.is_empty() => {
+                let tags: Vec<(&str, String)> = saved
+                    .tags
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.clone()))
+                    .collect();
+                match fs::write(argument, write_pgn(&game, &tags)) {
+                    Ok(()) => println!("Saved the game to {}", argument),
+                    Err(e) => println!("Error saving the game: {}", e),
+                }
+            }
+            "quit" => return,
+            _ => {
+                if let Err(e) = play_input(&mut game, input, display.language) {
+                    println!("{}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Annotates every game of a PGN file with the engine's evaluations and
+/// judgments of the moves, writing them to another file and showing a
+/// graph of each game's evaluation. `--time <ms>` sets how long each
+/// position is searched, `--threads <n>` how many games are annotated at
+/// once and `--json <file>` where to write the evaluations as JSON.
+pub fn annotate(args: &[String]) {
+    const USAGE: &str =
+        "Usage: rChess annotate <in.pgn> <out.pgn> [--time <ms>] [--threads <n>] [--json <file>]";
+    let [input, output, settings @ ..] = args else {
+        eprintln!("{}", USAGE);
+        return;
+    };
+    let mut annotator = Annotator::default();
+    let mut json = None;
+    for pair in settings.chunks(2) {
+        match pair {
+            [key, value] if key == "--time" && value.parse::<u64>().is_ok() => {
+                annotator.time_per_move = Duration::from_millis(value.parse().unwrap())
+            }
+            [key, value] if key == "--threads" && value.parse::<usize>().is_ok_and(|n| n > 0) => {
+                annotator.threads = value.parse().unwrap()
+            }
+            [key, value] if key == "--json" => json = Some(value),
+            _ => {
+                eprintln!("{}", USAGE);
+                return;
+            }
+        }
+    }
+    let games = match fs::read_to_string(input) {
+        Ok(text) => parse_pgn(&text),
+        Err(e) => {
+            eprintln!("Error reading {}: {}", input, e);
+            return;
+        }
+    };
+    println!(
+        "Annotating {} games on {} threads, {} ms per move",
+        games.len(),
+        annotator.threads.min(games.len()),
+        annotator.time_per_move.as_millis()
+    );
+    let results = annotator.annotate(&games, &theory(&games));
+    let mut pgn = String::new();
+    let mut annotated = Vec::new();
+    for (number, (game, result)) in games.iter().zip(&results).enumerate() {
+        match result {
+            Ok(result) => {
+                if !pgn.is_empty() {
+                    pgn.push('\n');
+                }
+                pgn.push_str(&result.pgn);
+                println!(
+                    "Game {} ({} - {}): {}",
+                    number + 1,
+                    game.tag("White").unwrap_or("?"),
+                    game.tag("Black").unwrap_or("?"),
+                    result.graph(GRAPH_WIDTH)
+                );
+                if let Some(novelty) = &result.novelty {
+                    println!("  Novelty: {}", novelty);
+                }
+                if !result.tactics.is_empty() {
+                    let tactics: Vec<String> =
+                        result.tactics.iter().map(Tactic::to_string).collect();
+                    println!("  Tactics: {}", tactics.join(", "));
+                }
+                annotated.push((game, result));
+            }
+            Err(e) => eprintln!("Skipping game {}: {}", number + 1, e),
+        }
+    }
+    match fs::write(output, pgn) {
+        Ok(()) => println!("Annotated games saved to {}", output),
+        Err(e) => eprintln!("Error saving {}: {}", output, e),
+    }
+    if let Some(path) = json {
+        match fs::write(path, evals_json(&annotated)) {
+            Ok(()) => println!("Evaluations saved to {}", path),
+            Err(e) => eprintln!("Error saving {}: {}", path, e),
+        }
+    }
+}
+
+/// Tunes search parameters by self-play, see [`Spsa`], printing their
+/// values after every iteration and the tuned ones at the end.
+///
+/// `args` are an optional match file setting up the games, see
+/// [`MatchConfig`], followed by settings overriding it, as for
+/// [`play_match`], and the tuner's own: `--iterations <n>`,
+/// `--pairs <n>` of games per iteration, `--rate <r>` and one
+/// `--param name=value,min,max,step` for each parameter to tune, the
+/// null-move reduction, late move reductions and aspiration window by
+/// default.
+pub fn tune_search(args: &[String]) {
+    const USAGE: &str = "Usage: rChess tune-search [match.toml] [--iterations <n>] [--pairs <n>] \
+                         [--rate <r>] [--param name=value,min,max,step ...] [--<setting> <value> ...]";
+    let mut config = MatchConfig::default();
+    let mut spsa = Spsa::default();
+    let mut params = Vec::new();
+    let mut settings = args.iter();
+    if let Some(path) = args.first().filter(|arg| !arg.starts_with("--")) {
+        let parsed = fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| MatchConfig::from_str(&text));
+        match parsed {
+            Ok(parsed) => config = parsed,
+            Err(e) => {
+                eprintln!("Error in {}: {}", path, e);
+                return;
+            }
+        }
+        settings.next();
+    }
+    while let Some(arg) = settings.next() {
+        let setting = arg
+            .strip_prefix("--")
+            .zip(settings.next())
+            .ok_or_else(|| format!("expected `--<setting> <value>`, got `{}`", arg))
+            .and_then(|(key, value)| {
+                let invalid = || format!("invalid {} `{}`", key, value);
+                match key {
+                    "iterations" => spsa.iterations = value.parse().map_err(|_| invalid())?,
+                    "pairs" => spsa.pairs = value.parse().map_err(|_| invalid())?,
+                    "rate" => spsa.rate = value.parse().map_err(|_| invalid())?,
+                    "param" => params.push(value.parse()?),
+                    _ => config.set(key, value)?,
+                }
+                Ok(())
+            });
+        if let Err(e) = setting {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            return;
+        }
+    }
+    if !params.is_empty() {
+        spsa.params = params;
+    }
+
+    info!("Tuning started: {:?} {:?}", spsa, config);
+    let values = |spsa: &Spsa| -> Vec<String> {
+        spsa.params
+            .iter()
+            .map(|param| format!("{} {:.2}", param.name, param.value))
+            .collect()
+    };
+    println!("Start: {}", values(&spsa).join(", "));
+    for k in 1..=spsa.iterations {
+        let mut error = None;
+        let tuner = spsa.clone();
+        let result = spsa.step(k, |plus, minus| {
+            tuner.play(&config, plus, minus, k).unwrap_or_else(|e| {
+                error = Some(e);
+                0
+            })
+        });
+        if let Some(e) = error {
+            eprintln!("{}", e);
+            return;
+        }
+        println!(
+            "Iteration {} ({:+}): {}",
+            k,
+            result,
+            values(&spsa).join(", ")
+        );
+    }
+    println!("Tuned values:");
+    for param in &spsa.params {
+        println!("{} = {}", param.name, param.value.round());
+    }
+}
+
+/// Evaluates every position of a file with a FEN on each line, skipping
+/// blank lines and `#` comments, printing the score and best move of
+/// each or writing them to `--output <file>`. `--depth <n>` sets how
+/// deep each position is searched, `--threads <n>` how many positions
+/// are searched at once and `--format text|json|csv` how the results are
+/// written.
+pub fn evaluate_positions(args: &[String]) {
+    const USAGE: &str = "Usage: rChess eval --file <positions.fen> [--depth <n>] [--threads <n>] \
+                         [--format text|json|csv] [--output <file>]";
+    let mut evaluator = BatchEvaluator::default();
+    let mut file = None;
+    let mut format = OutputFormat::Text;
+    let mut output = None;
+    for pair in args.chunks(2) {
+        match pair {
+            [key, value] if key == "--file" => file = Some(value),
+            [key, value] if key == "--depth" && value.parse::<u32>().is_ok_and(|n| n > 0) => {
+                evaluator.depth = value.parse().unwrap()
+            }
+            [key, value] if key == "--threads" && value.parse::<usize>().is_ok_and(|n| n > 0) => {
+                evaluator.threads = value.parse().unwrap()
+            }
+            [key, value] if key == "--format" && value.parse::<OutputFormat>().is_ok() => {
+                format = value.parse().unwrap()
+            }
+            [key, value] if key == "--output" => output = Some(value),
+            _ => {
+                eprintln!("{}", USAGE);
+                return;
+            }
+        }
+    }
+    let Some(file) = file else {
+        eprintln!("{}", USAGE);
+        return;
+    };
+    let fens: Vec<String> = match fs::read_to_string(file) {
+        Ok(text) => text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect(),
+        Err(e) => {
+            eprintln!("Error reading {}: {}", file, e);
+            return;
+        }
+    };
+    // Progress goes to stderr, so the results can be piped.
+    eprintln!(
+        "Evaluating {} positions on {} threads to depth {}",
+        fens.len(),
+        evaluator.threads.min(fens.len()),
+        evaluator.depth
+    );
+    let results = write_evals(&evaluator.evaluate(&fens), format);
+    match output {
+        Some(path) => match fs::write(path, results) {
+            Ok(()) => println!("Evaluations saved to {}", path),
+            Err(e) => eprintln!("Error saving {}: {}", path, e),
+        },
+        None => print!("{}", results),
+    }
+}
+
+/// Returns the opening theory games are checked against for novelties:
+/// the built-in opening lines and the archive of played games, leaving
+/// out `games` themselves, which may have been taken from it.
+fn theory(games: &[PgnGame]) -> Theory {
+    let book = OpeningTree::from_pgn(BUILTIN_LINES).unwrap();
+    let archived = archive_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|text| parse_pgn(&text))
+        .unwrap_or_default();
+    let known: Vec<PgnGame> = archived
+        .into_iter()
+        .filter(|game| !games.contains(game))
+        .collect();
+    Theory::new(book, PositionIndex::new(&known))
+}
+
+/// Lets the user set up a position, starting from the FEN in `args` or
+/// the starting position, and then play or analyze it.
+pub fn edit(args: &[String], display: Display, tags: &GameTags) {
+    let setup = match args.join(" ").as_str() {
+        "" => Board::default().into(),
+        fen => match parse_fen(fen) {
+            Ok(setup) => setup,
+            Err(e) => {
+                eprintln!("Invalid FEN {}: {}", fen, e);
+                return;
+            }
+        },
+    };
+    let mut editor = Editor::new(setup);
+    loop {
+        display_setup(editor.setup(), display);
+        let input = read_line("Edit (help for commands): ");
+        let input = input.trim();
+        let (command, argument) = input.split_once(' ').unwrap_or((input, ""));
+        match command {
+            "help" => {
+                for (usage, description) in EDIT_COMMANDS {
+                    println!("  {:<24} {}", usage, description);
+                }
+                println!("  {:<24} Plays the position against a friend", "play");
+                println!("  {:<24} Plays the position against the AI", "ai");
+                println!(
+                    "  {:<24} Searches the position (default depth 6)",
+                    "analyze [depth]"
+                );
+                println!("  {:<24} Leaves the editor", "quit");
+            }
+            "quit" => return,
+            "play" | "ai" | "analyze" => {
+                let board = match editor.position() {
+                    Ok(board) => board,
+                    Err(e) => {
+                        println!("{}", ChessError::InvalidPosition(e));
+                        continue;
+                    }
+                };
+                match command {
+                    "play" => {
+                        info!("Two-player game started from {}", board);
+                        let game = Game::new_multi()
+                            .with_tags(game_tags(tags))
+                            .with_position(board);
+                        return play_two_player(game, None, None, display);
+                    }
+                    "ai" => {
+                        let input = prompt(
+                            "Select your color (white or black): ",
+                            &["white", "black"],
+                            display.language,
+                        );
+                        let player_color = if input == "white" {
+                            Color::White
+                        } else {
+                            Color::Black
+                        };
+                        let input = prompt(
+                            "Select difficulty (beginner, easy, medium, hard or adaptive): ",
+                            &["beginner", "easy", "medium", "hard", "adaptive"],
+                            display.language,
+                        );
+                        let difficulty = Difficulty::from_str(&input).unwrap();
+                        let config = Config::load();
+                        let game = Game::new_single(player_color, difficulty)
+                            .with_tags(single_player_tags(tags, player_color, None, difficulty))
+                            .with_engine(config.engine)
+                            .with_limits(config.limits)
+                            .with_hash(config.hash_size)
+                            .with_position(board);
+                        info!("Single-player game started from {}", board);
+                        return play_single_player(
+                            game,
+                            player_color,
+                            difficulty,
+                            None,
+                            None,
+                            None,
+                            None,
+                            display,
+                        );
+                    }
+                    _ => {
+                        let depth = argument.parse().unwrap_or(6);
+                        analyze(&board, depth, &EngineOptions::default());
+                    }
+                }
+            }
+            _ => {
+                if let Err(e) = editor.apply(input) {
+                    println!("{}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Displays a position being set up in the editor, White at the bottom,
+/// followed by its FEN.
+fn display_setup(setup: &BoardBuilder, display: Display) {
+    if !display.accessible {
+        let mut board_str = String::new();
+        for rank in (0..8).rev() {
+            board_str.push_str(&format!("{}  ", rank + 1));
+            for file in 0..8 {
+                let square = chess::Square::make_square(
+                    chess::Rank::from_index(rank),
+                    chess::File::from_index(file),
+                );
+                board_str.push(match setup[square] {
+                    Some((piece, color)) => piece_symbol(piece, color),
+                    None => '.',
+                });
+                board_str.push(' ');
+            }
+            board_str.push('\n');
+        }
+        board_str.push_str("   a b c d e f g h");
+        println!("{}", board_str);
+    }
+    println!("FEN: {}", setup);
+}
+
+/// Hosts a network game set up from the command line, the host playing
+/// White unless set otherwise:
+/// `rChess host [port] [--room <code>] [--variant <variant>] [--time <minutes+increment>] [--color white|black|random] [--takebacks yes|no]`.
+/// With a room code, a single word, only an opponent giving it can join.
+pub fn host(args: &[String], display: Display, tags: &GameTags) {
+    const USAGE: &str = "Usage: rChess host [port] [--room <code>] [--variant <variant>] \
+                         [--time <minutes+increment>] [--color white|black|random] \
+                         [--takebacks yes|no]";
+    let (port, flags) = match args.first().map(|port| port.parse()) {
+        Some(Ok(port)) => (Some(port), &args[1..]),
+        _ => (None, args),
+    };
+    let mut settings = GameSettings::default();
+    let mut capabilities = Capabilities::default();
+    let mut room = None;
+    for pair in flags.chunks(2) {
+        match pair {
+            [key, value] if key == "--room" && valid_room(value) => room = Some(value.as_str()),
+            [key, value] if key == "--variant" && value.parse::<Variant>().is_ok() => {
+                settings.variant = value.parse().unwrap()
+            }
+            [key, value] if key == "--time" && value.parse::<TimeControl>().is_ok() => {
+                settings.time_control = Some(value.parse().unwrap())
+            }
+            [key, value] if key == "--color" && value == "white" => {
+                settings.guest_color = Color::Black
+            }
+            [key, value] if key == "--color" && value == "black" => {
+                settings.guest_color = Color::White
+            }
+            [key, value] if key == "--color" && value == "random" => {
+                settings.guest_color = if random_bool(0.5) {
+                    Color::White
+                } else {
+                    Color::Black
+                }
+            }
+            [key, value] if key == "--takebacks" && (value == "yes" || value == "no") => {
+                capabilities.takebacks = value == "yes"
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return;
+            }
+        }
+    }
+    host_game(port, None, room, &settings, &capabilities, display, tags);
+}
+
+/// Hosts a network game on `port` set up with `settings`, or a match of
+/// `best_of` games, letting in only an opponent giving the room code
+/// `room`, if one is set. The game uses what both the opponent and the
+/// host, allowing `capabilities`, can do.
+fn host_game(
+    port: Option<u16>,
+    best_of: Option<u32>,
+    room: Option<&str>,
+    settings: &GameSettings,
+    capabilities: &Capabilities,
+    display: Display,
+    tags: &GameTags,
+) {
+    let port = port.unwrap_or(DEFAULT_PORT);
+    match room {
+        Some(code) => println!(
+            "Waiting for an opponent on port {} with room code {}...",
+            port, code
+        ),
+        None => println!("Waiting for an opponent on port {}...", port),
+    }
+    match Connection::host(port, room, settings, capabilities) {
+        Ok(connection) => play_network(
+            connection,
+            !settings.guest_color,
+            best_of,
+            settings,
+            None,
+            display,
+            tags,
+        ),
+        Err(e) => eprintln!("Error hosting the game: {}", e),
+    }
+}
+
+/// Joins a network game or match hosted at an address, giving the room
+/// code if the game has one, and playing it as the host set it up:
+/// `rChess join <host:port> [room code] [--session <token>]`. With the
+/// session token printed when joining, a game the player lost the
+/// connection to is taken up again where it was.
+pub fn join(args: &[String], display: Display, tags: &GameTags) {
+    const USAGE: &str = "Usage: rChess join <host:port> [room code] [--session <token>]";
+    let Some(address) = args.first() else {
+        eprintln!("{}", USAGE);
+        return;
+    };
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, DEFAULT_PORT)
+    };
+    let mut hello = Hello::default();
+    let mut flags = args[1..].iter();
+    while let Some(arg) = flags.next() {
+        match arg.as_str() {
+            "--session" => hello.session = flags.next().cloned(),
+            room if hello.room.is_none() && !room.starts_with("--") && valid_room(room) => {
+                hello.room = Some(room.to_string())
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return;
+            }
+        }
+    }
+    let resuming = hello.session.is_some();
+    let (connection, settings) = match Connection::join(&address, hello) {
+        Ok(joined) => joined,
+        Err(e) => {
+            eprintln!("Error joining the game: {}", e);
+            return;
+        }
+    };
+    println!(
+        "If the connection drops, rejoin with: rChess join {} --session {}",
+        address,
+        connection.session()
+    );
+    // The host sends the game played so far to a player coming back.
+    let resumed = match resuming.then(|| connection.receive()) {
+        Some(Some(Message::Sync(fen, moves))) => Some((fen, moves)),
+        Some(_) => {
+            eprintln!("Error joining the game: the host didn't send the game");
+            return;
+        }
+        None => None,
+    };
+    let resumed = resumed
+        .as_ref()
+        .map(|(fen, moves)| (fen.as_str(), moves.as_slice()));
+    play_network(
+        connection,
+        settings.guest_color,
+        None,
+        &settings,
+        resumed,
+        display,
+        tags,
+    );
+}
+
+/// Waits up to `grace` for the opponent to come back after the
+/// connection dropped, the host then sending the guest `game` to catch
+/// up with. If the opponent doesn't come back, they're taken to have
+/// abandoned the game and lose it.
+///
+/// # Returns
+///
+/// Whether the game goes on.
+fn reconnect(
+    connection: &mut Connection,
+    grace: Duration,
+    game: &mut Game,
+    local: Color,
+    settings: &GameSettings,
+) -> bool {
+    println!(
+        "Lost connection to your opponent, waiting {} seconds for them to come back...",
+        grace.as_secs()
+    );
+    let settings = GameSettings {
+        guest_color: !local,
+        ..settings.clone()
+    };
+    let resumed = connection.reconnect(grace, &settings).and_then(|()| {
+        if connection.is_host() {
+            connection.send(&Message::Sync(game.fen(), game.moves().clone()))?;
+        }
+        Ok(())
+    });
+    match resumed {
+        Ok(()) => {
+            println!("Reconnected to your opponent");
+            true
+        }
+        Err(e) => {
+            warn!("Reconnecting failed: {}", e);
+            println!("Your opponent abandoned the game");
+            let _ = game.resign(!local);
+            false
+        }
+    }
+}
+
+/// Brings `game` in line with the `moves` the host has, taking back
+/// those the host never got and playing those missed, and checks the
+/// position reached is the host's `fen`.
+fn resync(game: &mut Game, fen: &str, moves: &[ChessMove]) {
+    while !moves.starts_with(game.moves()) {
+        if game.undo().is_err() {
+            break;
+        }
+    }
+    let played = game.moves().len();
+    for &mv in moves.iter().skip(played) {
+        if let Err(e) = game.make_move(mv) {
+            warn!("Replaying {} failed: {}", mv, e);
+            break;
+        }
+    }
+    if game.fen() != fen {
+        warn!("Out of sync: {} against the host's {}", game.fen(), fen);
+        eprintln!("The game is out of sync with your opponent's");
+    }
+}
+
+/// Prints a chat message from the opponent, returning `false` if the
+/// message ends the game instead.
+fn show_message(message: Option<Message>) -> bool {
+    match message {
+        Some(Message::Chat(text)) => {
+            println!("Opponent says: {}", text);
+            true
+        }
+        Some(Message::Reject(reason)) => {
+            println!("Your opponent turned you away: {}", reason);
+            false
+        }
+        Some(Message::Quit) | None => {
+            println!("Your opponent left the game");
+            false
+        }
+        Some(_) => true,
+    }
+}
+
+/// Asks the local player for a rematch and waits for the opponent's
+/// answer, returning whether both want one. Declining leaves the game.
+fn agree_rematch(connection: &mut Connection, language: Language) -> bool {
+    let message = if ask_rematch(language) {
+        Message::Rematch
+    } else {
+        Message::Quit
+    };
+    if let Err(e) = connection.send(&message) {
+        eprintln!("Lost connection to your opponent: {}", e);
+        return false;
+    }
+    if message == Message::Quit {
+        return false;
+    }
+    println!("Waiting for your opponent's answer...");
+    loop {
+        match connection.receive() {
+            Some(Message::Rematch) => return true,
+            message => {
+                if !show_message(message) {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Asks the opponent to take back the local player's last move and the
+/// opponent's reply to it, and waits for the answer, if the game allows
+/// takebacks.
+fn ask_takeback(connection: &mut Connection, game: &mut Game) {
+    if !connection.capabilities().takebacks {
+        println!("Takebacks aren't allowed in this game");
+        return;
+    }
+    if game.moves().len() < 2 {
+        println!("{}", ChessError::NothingToUndo);
+        return;
+    }
+    if let Err(e) = connection.send(&Message::Takeback) {
+        eprintln!("Lost connection to your opponent: {}", e);
+        return;
+    }
+    println!("Waiting for your opponent to answer...");
+    loop {
+        match connection.receive() {
+            Some(Message::TakebackAnswer(true)) => {
+                let _ = game.undo();
+                let _ = game.undo();
+                break;
+            }
+            Some(Message::TakebackAnswer(false)) => {
+                println!("Your opponent declined the takeback");
+                break;
+            }
+            message => {
+                if !show_message(message) {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// How long past the guest's time the host waits for its move before
+/// calling its flag, for the move to arrive.
+const LAG_ALLOWANCE: Duration = Duration::from_secs(2);
+
+/// Sends the guest the time both sides have left on the host's clock,
+/// which a timed network game goes by.
+fn send_clock(connection: &mut Connection, clock: Option<&Clock>) {
+    let Some(clock) = clock.filter(|_| connection.is_host()) else {
+        return;
+    };
+    let message = Message::Clock(clock.remaining(Color::White), clock.remaining(Color::Black));
+    if let Err(e) = connection.send(&message) {
+        warn!("Sending {} failed: {}", message, e);
+    }
+}
+
+/// Plays games against an opponent over the network, the local player
+/// playing `local` in the first one, with colors swapped for every
+/// rematch both agree to. `say <text>` sends a chat message.
+///
+/// The host of a match of `best_of` games tells the opponent its length
+/// before the first game, and the games follow each other until it's
+/// decided. Games are played in the variant and on the time control of
+/// `settings`. The host keeps the clock: every move is charged the time
+/// its player took over it, leaving out the time it took to arrive, the
+/// guest is sent both sides' time after every move, and the host calls
+/// the guest's flag if no move comes in time.
+fn play_network(
+    mut connection: Connection,
+    mut local: Color,
+    best_of: Option<u32>,
+    settings: &GameSettings,
+    resumed: Option<(&str, &[ChessMove])>,
+    mut display: Display,
+    tags: &GameTags,
+) {
+    println!("Connected! You're playing as {:?}", local);
+    if settings.variant != Variant::Standard {
+        println!("Variant: {}", settings.variant);
+    }
+    if connection.capabilities().takebacks {
+        println!("Takebacks are allowed: undo asks to take back your last move");
+    }
+    let new_clock = || {
+        settings.time_control.map(|control| {
+            println!("Time control: {}", control);
+            let mut clock = Clock::new(control, control);
+            clock.start(Color::White);
+            clock
+        })
+    };
+    let mut clock = new_clock();
+    let mut tags = game_tags(tags);
+    if let (None, Some(name)) = (tags.name(local), Config::load().name) {
+        tags.set_name(local, &name);
+    }
+    let mut game = Game::new_multi()
+        .with_tags(tags)
+        .with_variant(settings.variant);
+    game.subscribe(announcer(&game, Some((!local, "Opponent")), display));
+    add_move_hook(&mut game, display);
+    if let Some((fen, moves)) = resumed {
+        resync(&mut game, fen, moves);
+        if let Some(clock) = &mut clock {
+            clock.start(game.turn());
+        }
+    }
+    let mut standings = Standings::new("You", "Opponent", local).with_best_of(best_of);
+    if let Some(games) = best_of {
+        if let Err(e) = connection.send(&Message::Match(games)) {
+            eprintln!("Lost connection to your opponent: {}", e);
+            return;
+        }
+        println!("Best of {} match", games);
+    }
+    let mut rounds = Vec::new();
+    let config = Config::load();
+    let aliases = config.aliases;
+    let mut premove = None;
+    let mut connected = true;
+    // The local player's input, the opponent's messages and the clock's
+    // ticks come in as events, so chat and the clock go on while either
+    // side thinks.
+    let mut events = EventLoop::new();
+    if clock.is_some() {
+        events.start_ticker(CLOCK_TICK);
+    }
+    // Whether the opponent's move is being waited for, and until when the
+    // host waits before calling their flag.
+    let mut waiting = false;
+    let mut deadline = None;
+    loop {
+        let (event, is_premove) = if game.turn() == local {
+            waiting = false;
+            events.render(|| show_game(&game, clock.as_ref(), display));
+            turn_input(
+                &mut premove,
+                &mut events,
+                Some(&connection),
+                display.language,
+            )
+        } else {
+            if !waiting {
+                println!("Waiting for your opponent's move... (you can enter your next move now)");
+                events.listen();
+                waiting = true;
+                deadline = clock
+                    .as_ref()
+                    .filter(|_| connection.is_host())
+                    .map(|clock| Instant::now() + clock.remaining(!local) + LAG_ALLOWANCE);
+            }
+            let event = match events.next_or_message(&connection) {
+                Event::Tick if deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                    Event::Message(Some(Message::Flag(!local)))
+                }
+                event => event,
+            };
+            (event, false)
+        };
+        let local_flagged = clock.as_ref().and_then(Clock::flagged) == Some(local);
+        match event {
+            Event::Message(received) => match received {
+                Some(Message::Match(games))
+                    if standings.games() == 0 && game.moves().is_empty() =>
+                {
+                    standings = standings.with_best_of(Some(games));
+                    println!("Your opponent started a best of {} match", games);
+                    continue;
+                }
+                Some(Message::Move(mv, spent))
+                    if game.turn() != local && game.board().legal(mv) =>
+                {
+                    if let Err(e) = game.make_move(mv) {
+                        eprintln!("Your opponent sent a move after the end of the game: {}", e);
+                        break;
+                    }
+                    press_clock(&mut game, &mut clock, spent);
+                    send_clock(&mut connection, clock.as_ref());
+                    events.changed();
+                    if game.status() == Status::Ongoing {
+                        config.notify.alert(Alert::YourTurn);
+                    }
+                }
+                Some(Message::Flag(color)) if !connection.is_host() || color != local => {
+                    let _ = game.time_out(color);
+                    if connection.is_host() {
+                        let _ = connection.send(&Message::Flag(color));
+                    }
+                }
+                Some(Message::Clock(white, black)) => {
+                    if let Some(clock) = &mut clock {
+                        clock.set_remaining(white, black);
+                    }
+                    continue;
+                }
+                Some(Message::Takeback) => {
+                    let accepted = connection.capabilities().takebacks
+                        && game.turn() != local
+                        && game.moves().len() >= 2
+                        && prompt(
+                            "Your opponent asks to take back their last move (accept or decline): ",
+                            &["accept", "decline"],
+                            display.language,
+                        ) == "accept";
+                    if accepted {
+                        let _ = game.undo();
+                        let _ = game.undo();
+                        events.changed();
+                    }
+                    let _ = connection.send(&Message::TakebackAnswer(accepted));
+                    continue;
+                }
+                Some(Message::Sync(fen, moves)) => {
+                    resync(&mut game, &fen, &moves);
+                    if let Some(clock) = &mut clock {
+                        clock.start(game.turn());
+                    }
+                    events.changed();
+                    waiting = false;
+                    continue;
+                }
+                None => {
+                    if let Some(clock) = &mut clock {
+                        clock.stop();
+                    }
+                    let grace = config.reconnect_grace;
+                    connected = reconnect(&mut connection, grace, &mut game, local, settings);
+                    if let Some(clock) = &mut clock {
+                        clock.start(game.turn());
+                    }
+                    send_clock(&mut connection, clock.as_ref());
+                    events.changed();
+                    waiting = false;
+                }
+                Some(Message::Move(mv, _)) => {
+                    warn!("Illegal move {} received in {}", mv, game.board());
+                    eprintln!("Your opponent sent an illegal move: {}", mv);
+                    break;
+                }
+                message => {
+                    if !show_message(message) {
+                        break;
+                    }
+                    continue;
+                }
+            },
+            Event::Input(line) if game.turn() != local => {
+                premove = Some(line);
+                continue;
+            }
+            Event::Tick if game.turn() != local || !local_flagged => continue,
+            // No AI plays in a network game.
+            Event::AiMove(_) => unreachable!(),
+            event @ (Event::Input(_) | Event::Tick | Event::Closed) => {
+                // A tick gets here once the local player's flag fell,
+                // which is all there's left to tell the opponent. At the
+                // end of input the player leaves.
+                let input = match event {
+                    Event::Input(line) => line,
+                    Event::Closed => "quit".to_string(),
+                    _ => {
+                        println!();
+                        String::new()
+                    }
+                };
+                events.changed();
+                let input = input.trim();
+                let message = match parse_command(input, Mode::Network, &aliases) {
+                    _ if local_flagged => {
+                        let _ = game.time_out(local);
+                        Message::Flag(local)
+                    }
+                    Some((Command::Quit, _)) => Message::Quit,
+                    Some((Command::Say, _)) if !connection.capabilities().chat => {
+                        println!("Your opponent's rChess doesn't show chat messages");
+                        continue;
+                    }
+                    Some((Command::Say, text)) if !text.is_empty() => {
+                        Message::Chat(text.to_string())
+                    }
+                    Some((Command::Undo, _)) => {
+                        ask_takeback(&mut connection, &mut game);
+                        continue;
+                    }
+                    Some((command, arguments)) => {
+                        let result = game_command(
+                            &mut game,
+                            command,
+                            arguments,
+                            Mode::Network,
+                            local,
+                            &mut display,
+                        );
+                        if let Err(e) = result {
+                            println!("{}", e);
+                        }
+                        continue;
+                    }
+                    None => match play_input(&mut game, input, display.language) {
+                        Ok(true) => {
+                            let spent = clock.as_ref().map(Clock::elapsed);
+                            press_clock(&mut game, &mut clock, None);
+                            Message::Move(*game.moves().last().unwrap(), spent)
+                        }
+                        Ok(false) => continue,
+                        Err(e) if is_premove => {
+                            println!(
+                                "{}",
+                                display
+                                    .language
+                                    .fill(Text::PremoveDiscarded, &[input, &e.to_string()])
+                            );
+                            continue;
+                        }
+                        Err(e) => {
+                            println!("{}", e);
+                            continue;
+                        }
+                    },
+                };
+                if let Err(e) = connection.send(&message) {
+                    warn!("Sending {} failed: {}", message, e);
+                    if message == Message::Quit {
+                        break;
+                    }
+                    // The host sends the game once back, so a move the
+                    // opponent never got is taken back to be played again.
+                    let grace = config.reconnect_grace;
+                    connected = reconnect(&mut connection, grace, &mut game, local, settings);
+                } else if let Message::Move(..) = message {
+                    send_clock(&mut connection, clock.as_ref());
+                }
+                if message == Message::Quit {
+                    break;
+                }
+            }
+        }
+        if game.status() != Status::Ongoing {
+            session::record_game(SessionGame::new(&game, Some(local), None));
+            archive_game(&game, &[("Event", "rChess network game".to_string())]);
+            score_game(&mut standings, &game, &mut rounds, display.language);
+            let another = connected
+                && if standings.best_of().is_some() {
+                    play_on(&standings, &rounds, display.language)
+                } else {
+                    agree_rematch(&mut connection, display.language)
+                };
+            if !another {
+                break;
+            }
+            game.rematch();
+            clock = new_clock();
+            local = !local;
+            waiting = false;
+            events.changed();
+            standings.swap_colors();
+            println!("Rematch! You're playing as {:?}", local);
+        }
+    }
+}
+
+/// Plays correspondence games, kept in the directory set in the
+/// configuration and told apart by the player `name` set there:
+///
+/// * `resume` - lists your games
+/// * `resume new <id> <opponent>` - starts a game with you playing White
+/// * `resume <id>` - shows a game and asks for your move if it's your turn
+pub fn resume(args: &[String], display: Display) {
+    let config = Config::load();
+    let Some(name) = config.name.as_deref() else {
+        eprintln!("Set your name in config.toml (name = \"...\") to play correspondence games");
+        return;
+    };
+    let Some(dir) = config.correspondence_dir() else {
+        eprintln!("Error: could not find the correspondence directory");
+        return;
+    };
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => match list_games(&dir) {
+            Ok(games) => {
+                let mine: Vec<_> = games
+                    .iter()
+                    .filter(|g| g.color_of(name).is_some())
+                    .collect();
+                if mine.is_empty() {
+                    println!("No correspondence games in {}", dir.display());
+                }
+                if mine.iter().any(|game| game.awaits(name)) {
+                    config.notify.alert(Alert::YourTurn);
+                }
+                for game in mine {
+                    let color = game.color_of(name).unwrap();
+                    let state = match game.game().status() {
+                        Status::Ongoing if game.awaits(name) => "your move".to_string(),
+                        Status::Ongoing => "waiting".to_string(),
+                        status => format!("finished, {}", result_token(status)),
+                    };
+                    println!(
+                        "{:<16} vs {:<16} {:?}, move {}: {}",
+                        game.id(),
+                        game.player(!color),
+                        color,
+                        game.game().moves().len() / 2 + 1,
+                        state
+                    );
+                }
+            }
+            Err(e) => eprintln!("Error reading {}: {}", dir.display(), e),
+        },
+        ["new", id, opponent] => {
+            if CorrespondenceGame::load(&dir, id).is_ok() {
+                eprintln!("A game called {} already exists", id);
+                return;
+            }
+            match CorrespondenceGame::new(id, name, opponent).save(&dir) {
+                Ok(()) => println!("Started {} against {}, you play White", id, opponent),
+                Err(e) => eprintln!("Error saving the game: {}", e),
+            }
+        }
+        [id] => match CorrespondenceGame::load(&dir, id) {
+            Ok(game) => play_correspondence(game, name, &dir, display),
+            Err(e) => eprintln!("Error loading {}: {}", id, e),
+        },
+        _ => eprintln!("Usage: rChess resume [<id> | new <id> <opponent>]"),
+    }
+}
+
+/// Shows a correspondence game and lets the player called `name` make
+/// one move if it's their turn, saving it to `dir` for the opponent.
+/// Searches `board` to `depth`, printing the score and the expected
+/// line after every iteration.
+fn analyze(board: &Board, depth: u32, options: &EngineOptions) {
+    let mut info = PrettyInfo { board: *board };
+    if search_reporting(board, depth, options, &mut info)
+        .best_move
+        .is_none()
+    {
+        println!("No legal moves");
+    }
+}
+
+/// Search depth of `analyze` in a correspondence game unless given.
+const CORRESPONDENCE_ANALYSIS_DEPTH: u32 = 6;
+
+/// Returns the file the engine's analysis of the correspondence game
+/// `id` is kept in between sessions, if `correspondence_hash` is set.
+/// It's kept in [`config_dir`], away from the shared games the opponent
+/// can read.
+fn correspondence_hash_path(config: &Config, id: &str) -> Option<PathBuf> {
+    if !config.correspondence_hash {
+        return None;
+    }
+    config_dir().map(|dir| dir.join("hash").join(format!("{}.hash", id)))
+}
+
+fn play_correspondence(mut game: CorrespondenceGame, name: &str, dir: &Path, display: Display) {
+    display_board(game.game(), display);
+    print_move_history(game.game());
+    let status = game.game().status();
+    if status != Status::Ongoing {
+        println!("The game is over: {}", result_token(status));
+        return;
+    }
+    if !game.awaits(name) {
+        println!("Waiting for {} to move", game.player(game.game().turn()));
+        return;
+    }
+    let listener = announcer(game.game(), None, display);
+    game.game_mut().subscribe(listener);
+    add_move_hook(game.game_mut(), display);
+    let config = Config::load();
+    let hash_path = correspondence_hash_path(&config, game.id());
+    // Set up on the first analysis, then kept for the session.
+    let mut analysis: Option<EngineOptions> = None;
+    let moved = loop {
+        let input = read_line("Enter your move, analyze [depth] or quit to decide later: ");
+        let input = input.trim();
+        if input == "quit" {
+            break false;
+        }
+        let mut words = input.split_whitespace();
+        if words.next() == Some("analyze") {
+            let depth = words
+                .next()
+                .and_then(|depth| depth.parse().ok())
+                .unwrap_or(CORRESPONDENCE_ANALYSIS_DEPTH);
+            let options = analysis.get_or_insert_with(|| {
+                let table = hash_path
+                    .as_deref()
+                    .and_then(|path| TranspositionTable::load(path, config.hash_size).ok())
+                    .unwrap_or_else(|| TranspositionTable::new(config.hash_size));
+                EngineOptions {
+                    table: Some(SharedTable::new(table)),
+                    ..EngineOptions::default()
+                }
+            });
+            analyze(game.game().board(), depth, options);
+            continue;
+        }
+        match play_input(game.game_mut(), input, display.language) {
+            Ok(true) => break true,
+            Ok(false) => display_board(game.game(), display),
+            Err(e) => println!("{}", e),
+        }
+    };
+    if let (Some(table), Some(path)) = (
+        analysis.and_then(|options| options.table),
+        hash_path.as_deref(),
+    ) {
+        let saved =
+            fs::create_dir_all(path.parent().unwrap()).and_then(|()| table.lock().save(path));
+        if let Err(e) = saved {
+            eprintln!("Error saving the analysis: {}", e);
+        }
+    }
+    if !moved {
+        return;
+    }
+    match game.save(dir) {
+        Ok(()) => println!("Move sent to {}", game.player(game.game().turn())),
+        Err(e) => eprintln!("Error saving the game: {}", e),
+    }
+    if game.game().status() != Status::Ongoing {
+        session::record_game(SessionGame::new(game.game(), game.color_of(name), None));
+    }
+    archive_game(game.game(), &game.tags());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_games_with_the_tags_given() {
+        let tags = GameTags {
+            round: Some("3".to_string()),
+            white: Some("Ann".to_string()),
+            ..GameTags::default()
+        };
+        assert_eq!(game_tags(&tags).round.as_deref(), Some("3"));
+        let against_ai = single_player_tags(&tags, Color::Black, Some("Bea"), Difficulty::Easy);
+        assert_eq!(against_ai.round.as_deref(), Some("3"));
+        assert_eq!(against_ai.black.as_deref(), Some("Bea"));
+        assert_eq!(against_ai.white.as_deref(), Some("rChess AI (Easy)"));
+        assert_eq!(game_tags(&GameTags::default()).round, None);
+    }
+
+    #[test]
+    fn zooms_only_the_display_given() {
+        let mut game = Game::new_multi();
+        let mut display = Display::default();
+        let mut zoom = |arguments: &str| {
+            let result = game_command(
+                &mut game,
+                Command::Zoom,
+                arguments,
+                Mode::TwoPlayer,
+                Color::White,
+                &mut display,
+            );
+            result.map(|()| display.zoom)
+        };
+        assert_eq!(zoom(""), Ok(2));
+        assert_eq!(zoom("3"), Ok(3));
+        assert_eq!(zoom(""), Ok(1));
+        assert!(zoom("4").is_err());
+        assert_eq!(Display::default().zoom, 1);
+    }
+
+    #[test]
+    fn highlights_the_last_move() {
+        let mut game = Game::new_multi();
+        assert_eq!(highlight(&game, Square::E2), None);
+        game.make_move_from_str("e4", false).unwrap();
+        for square in [Square::E2, Square::E4] {
+            assert_eq!(highlight(&game, square), Some((LAST_MOVE_STYLE, '\'')));
+        }
+        assert_eq!(highlight(&game, Square::D2), None);
+        let diagram = board_diagram(&game, true, 1);
+        assert_eq!(diagram.matches(LAST_MOVE_STYLE).count(), 2);
+        let pawn = piece_symbol(Piece::Pawn, Color::White);
+        assert!(diagram.contains(&format!("{LAST_MOVE_STYLE}{pawn}{RESET_STYLE}")));
+        assert!(diagram.contains(&format!("{LAST_MOVE_STYLE}.{RESET_STYLE}")));
+
+        // The selected piece's destinations take over the highlight.
+        game.make_move_from_str("d5", false).unwrap();
+        game.select(Square::E4).unwrap();
+        assert_eq!(highlight(&game, Square::D5), Some((DESTINATION_STYLE, '*')));
+        assert_eq!(highlight(&game, Square::D7), Some((LAST_MOVE_STYLE, '\'')));
+    }
+}
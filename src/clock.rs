@@ -0,0 +1,240 @@
+use crate::timing::TimeBudget;
+use chess::Color;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// The time a player gets for the game: a base time and an increment
+/// added after each of their moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// Reads a time control written as minutes and an optional increment
+    /// in seconds, e.g. `10+5`, `3` or `0.5+1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time control {}, e.g. 10+5", s.trim());
+        let (minutes, increment) = s.trim().split_once('+').unwrap_or((s.trim(), "0"));
+        let minutes: f64 = minutes.trim().parse().map_err(|_| invalid())?;
+        let increment: f64 = increment.trim().parse().map_err(|_| invalid())?;
+        if !(minutes > 0.0 && minutes.is_finite() && increment >= 0.0 && increment.is_finite()) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            base: Duration::from_secs_f64(minutes * 60.0),
+            increment: Duration::from_secs_f64(increment),
+        })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}+{}",
+            self.base.as_secs_f64() / 60.0,
+            self.increment.as_secs_f64()
+        )
+    }
+}
+
+/// A chess clock, with each side on its own time control, so one player
+/// can be given time odds.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    controls: [TimeControl; 2],
+    /// Time left for White and Black when their clock was last stopped.
+    remaining: [Duration; 2],
+    /// The side whose clock is running, and since when.
+    running: Option<(Color, Instant)>,
+}
+
+impl Clock {
+    /// Creates a stopped clock with `white` and `black` on their own time
+    /// controls.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let human = TimeControl::from_str("10")?;
+    /// let ai = TimeControl::from_str("1")?;
+    /// let clock = Clock::new(human, ai);
+    /// assert_eq!(clock.remaining(Color::Black), Duration::from_secs(60));
+    /// ```
+    pub fn new(white: TimeControl, black: TimeControl) -> Self {
+        Self {
+            controls: [white, black],
+            remaining: [white.base, black.base],
+            running: None,
+        }
+    }
+
+    /// Returns the time control of `color`.
+    pub fn control(&self, color: Color) -> TimeControl {
+        self.controls[color.to_index()]
+    }
+
+    /// Returns the time `color` has left, counting down while their
+    /// clock runs.
+    pub fn remaining(&self, color: Color) -> Duration {
+        let stopped = self.remaining[color.to_index()];
+        match self.running {
+            Some((running, since)) if running == color => stopped.saturating_sub(since.elapsed()),
+            _ => stopped,
+        }
+    }
+
+    /// Starts the clock of `color`, stopping the other one.
+    pub fn start(&mut self, color: Color) {
+        self.stop();
+        self.running = Some((color, Instant::now()));
+    }
+
+    /// Returns how long the side whose clock is running has been on it.
+    pub fn elapsed(&self) -> Duration {
+        self.running
+            .map_or(Duration::ZERO, |(_, since)| since.elapsed())
+    }
+
+    /// Sets the time White and Black have left, e.g. to the clock kept
+    /// by the host of a network game. A running clock runs on from now.
+    pub fn set_remaining(&mut self, white: Duration, black: Duration) {
+        self.remaining = [white, black];
+        if let Some((color, _)) = self.running {
+            self.running = Some((color, Instant::now()));
+        }
+    }
+
+    /// Stops the running clock, if any.
+    pub fn stop(&mut self) {
+        if let Some((color, _)) = self.running {
+            self.remaining[color.to_index()] = self.remaining(color);
+            self.running = None;
+        }
+    }
+
+    /// Ends the move of the side whose clock is running: adds their
+    /// increment, unless their time ran out, and starts the opponent's
+    /// clock.
+    ///
+    /// # Returns
+    ///
+    /// The side that moved, if a clock was running.
+    pub fn press(&mut self) -> Option<Color> {
+        self.press_spent(Duration::MAX)
+    }
+
+    /// Ends the move of the side whose clock is running like
+    /// [`Clock::press`], but charges them `spent`, the time they say they
+    /// took over it, if it's less than the time measured: a move sent
+    /// over the network isn't charged the time it took to arrive.
+    pub fn press_spent(&mut self, spent: Duration) -> Option<Color> {
+        let (color, since) = self.running?;
+        self.running = None;
+        let remaining = &mut self.remaining[color.to_index()];
+        *remaining = remaining.saturating_sub(spent.min(since.elapsed()));
+        if !self.remaining[color.to_index()].is_zero() {
+            self.remaining[color.to_index()] += self.control(color).increment;
+        }
+        self.start(!color);
+        Some(color)
+    }
+
+    /// Returns the side whose time ran out, if any.
+    pub fn flagged(&self) -> Option<Color> {
+        [Color::White, Color::Black]
+            .into_iter()
+            .find(|color| self.remaining(*color).is_zero())
+    }
+
+    /// Allots time to move `move_number` of `color` from their own clock,
+    /// so an AI given time odds budgets from its smaller one.
+    pub fn move_budget(&self, color: Color, move_number: u32) -> TimeBudget {
+        TimeBudget::new(
+            self.remaining(color),
+            self.control(color).increment,
+            move_number,
+            None,
+        )
+    }
+
+    /// Shows both sides' remaining time, e.g. `White 9:58 | Black 0:59.4`,
+    /// with tenths of a second below ten seconds.
+    pub fn display(&self) -> String {
+        let format = |color: Color| {
+            let time = self.remaining(color);
+            let seconds = time.as_secs();
+            if seconds < 10 {
+                format!("{:?} 0:0{:.1}", color, time.as_secs_f64())
+            } else {
+                format!("{:?} {}:{:02}", color, seconds / 60, seconds % 60)
+            }
+        };
+        format!("{} | {}", format(Color::White), format(Color::Black))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_time_controls() {
+        let control = TimeControl::from_str("10+5").unwrap();
+        assert_eq!(control.base, Duration::from_secs(600));
+        assert_eq!(control.increment, Duration::from_secs(5));
+        assert_eq!(control.to_string(), "10+5");
+        assert_eq!(
+            TimeControl::from_str(" 0.5 ").unwrap().base,
+            Duration::from_secs(30)
+        );
+        assert!(TimeControl::from_str("0+5").is_err());
+        assert!(TimeControl::from_str("ten").is_err());
+    }
+
+    #[test]
+    fn runs_each_side_on_its_own_time() {
+        let human = TimeControl::from_str("10").unwrap();
+        let ai = TimeControl::from_str("1+2").unwrap();
+        let mut clock = Clock::new(human, ai);
+        assert_eq!(clock.press(), None);
+        clock.start(Color::Black);
+        assert_eq!(clock.press(), Some(Color::Black));
+        // Black got its increment; White's clock runs now.
+        assert!(clock.remaining(Color::Black) > Duration::from_secs(61));
+        assert!(clock.remaining(Color::White) <= Duration::from_secs(600));
+        clock.stop();
+        assert_eq!(clock.flagged(), None);
+        // The AI's budget comes from its own clock, not the human's.
+        assert!(clock.move_budget(Color::Black, 1).optimum < Duration::from_secs(4));
+        assert!(clock.move_budget(Color::White, 1).optimum >= Duration::from_secs(14));
+        assert!(clock.display().starts_with("White 9:59 | Black 1:0"));
+
+        let mut clock = Clock::new(TimeControl::from_str("0.001").unwrap(), ai);
+        clock.start(Color::White);
+        std::thread::sleep(Duration::from_millis(80));
+        assert_eq!(clock.flagged(), Some(Color::White));
+        clock.press();
+        // A move sent over the network is charged the time its player
+        // took, not the time it took to arrive.
+        let mut lagged = Clock::new(human, ai);
+        lagged.start(Color::White);
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(lagged.elapsed() >= Duration::from_millis(30));
+        lagged.press_spent(Duration::from_millis(10));
+        assert_eq!(
+            lagged.remaining(Color::White),
+            Duration::from_millis(599_990)
+        );
+        lagged.set_remaining(Duration::from_secs(5), Duration::from_secs(6));
+        assert!(lagged.remaining(Color::Black) <= Duration::from_secs(6));
+        // No increment once the flag has fallen.
+        assert!(clock.remaining(Color::White).is_zero());
+        assert_eq!(clock.move_budget(Color::White, 1).maximum, Duration::ZERO);
+    }
+}
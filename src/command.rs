@@ -0,0 +1,465 @@
+/// A command that can be typed instead of a move during a game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Help,
+    Quit,
+    Undo,
+    Resign,
+    Draw,
+    Print,
+    Pgn,
+    Fen,
+    Mark,
+    Arrow,
+    Clear,
+    Comment,
+    Nag,
+    Export,
+    ExportImage,
+    ExportFrames,
+    Say,
+    Board,
+    Explorer,
+    Heatmap,
+    Info,
+    Zoom,
+    Explore,
+}
+
+/// The kind of game a command is typed in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    TwoPlayer,
+    SinglePlayer,
+    Network,
+    /// One player against the AI on several boards, see
+    /// [`Simul`](crate::simul::Simul).
+    Simul,
+}
+
+/// Description of a command, used both to recognize it and to list it
+/// in the help.
+pub struct CommandSpec {
+    pub command: Command,
+    pub name: &'static str,
+    /// Other names the command can be typed as.
+    pub aliases: &'static [&'static str],
+    /// The command's arguments, as shown in the help.
+    pub arguments: &'static str,
+    /// What the command does, unless it works differently in a mode, see
+    /// [`CommandSpec::describe`].
+    pub description: &'static str,
+    /// The kinds of games the command is available in.
+    pub modes: &'static [Mode],
+}
+
+const ALL_MODES: &[Mode] = &[
+    Mode::TwoPlayer,
+    Mode::SinglePlayer,
+    Mode::Network,
+    Mode::Simul,
+];
+
+/// All commands available during games.
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        command: Command::Help,
+        name: "help",
+        aliases: &["?"],
+        arguments: "",
+        description: "Lists the available commands",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Quit,
+        name: "quit",
+        aliases: &["exit", "q"],
+        arguments: "",
+        description: "Leaves the game",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Undo,
+        name: "undo",
+        aliases: &["takeback", "u"],
+        arguments: "",
+        description: "Takes back the last move, if your opponent agrees",
+        modes: &[Mode::TwoPlayer, Mode::SinglePlayer, Mode::Network],
+    },
+    CommandSpec {
+        command: Command::Resign,
+        name: "resign",
+        aliases: &[],
+        arguments: "",
+        description: "Gives up the game",
+        modes: &[Mode::TwoPlayer, Mode::SinglePlayer, Mode::Simul],
+    },
+    CommandSpec {
+        command: Command::Draw,
+        name: "draw",
+        aliases: &["offer-draw"],
+        arguments: "",
+        description: "Offers your opponent a draw",
+        modes: &[Mode::TwoPlayer, Mode::SinglePlayer],
+    },
+    CommandSpec {
+        command: Command::Print,
+        name: "print",
+        aliases: &["history", "p"],
+        arguments: "",
+        description: "Shows the moves played so far",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Pgn,
+        name: "pgn",
+        aliases: &[],
+        arguments: "",
+        description: "Shows the game in PGN",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Fen,
+        name: "fen",
+        aliases: &[],
+        arguments: "",
+        description: "Shows the current position in FEN",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Mark,
+        name: "mark",
+        aliases: &[],
+        arguments: "<square>",
+        description: "Marks a square on the board, or removes the mark",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Arrow,
+        name: "arrow",
+        aliases: &[],
+        arguments: "<from> <to>",
+        description: "Draws an arrow on the board, or removes it",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Clear,
+        name: "clear",
+        aliases: &[],
+        arguments: "",
+        description: "Removes all marks and arrows",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Comment,
+        name: "comment",
+        aliases: &[],
+        arguments: "[\"text\"]",
+        description: "Comments on the last move, or removes the comment",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Nag,
+        name: "nag",
+        aliases: &[],
+        arguments: "<!|?|!!|??|!?|?!|$n>",
+        description: "Annotates the last move with a glyph, or removes it",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Export,
+        name: "export",
+        aliases: &[],
+        arguments: "<file> [--format pgn|markdown|latex]",
+        description:
+            "Saves the game as PGN, a Markdown table or LaTeX (xskak), by its extension by default",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::ExportImage,
+        name: "export-image",
+        aliases: &[],
+        arguments: "<file> [white|black] [--best-move] [--eval]",
+        description: "Saves the board as an SVG image",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::ExportFrames,
+        name: "export-frames",
+        aliases: &[],
+        arguments: "<dir> [white|black] [--best-move] [--eval]",
+        description: "Saves every position of the game as SVG frames",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Explorer,
+        name: "explorer",
+        aliases: &[],
+        arguments: "[file.pgn ...]",
+        description: "Shows the moves played here in your archived games or the given ones",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Heatmap,
+        name: "heatmap",
+        aliases: &["control"],
+        arguments: "",
+        description: "Colors the board by how many pieces of each side attack every square",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Info,
+        name: "info",
+        aliases: &[],
+        arguments: "",
+        description: "Shows legal moves, material, pawn structure and king safety of both sides",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Zoom,
+        name: "zoom",
+        aliases: &[],
+        arguments: "[1|2|3]",
+        description: "Draws the board larger, or cycles through the sizes",
+        modes: ALL_MODES,
+    },
+    CommandSpec {
+        command: Command::Explore,
+        name: "explore",
+        aliases: &[],
+        arguments: "",
+        description: "Tries lines against the engine on a scratch board, leaving the game as it is",
+        modes: &[Mode::TwoPlayer, Mode::SinglePlayer],
+    },
+    CommandSpec {
+        command: Command::Say,
+        name: "say",
+        aliases: &[],
+        arguments: "<text>",
+        description: "Sends a chat message to your opponent",
+        modes: &[Mode::Network],
+    },
+    CommandSpec {
+        command: Command::Board,
+        name: "board",
+        aliases: &[],
+        arguments: "[number]",
+        description: "Goes to another board, or lists the boards",
+        modes: &[Mode::Simul],
+    },
+];
+
+impl CommandSpec {
+    /// Returns how the command is typed, e.g. `mark <square>`.
+    pub fn usage(&self) -> String {
+        if self.arguments.is_empty() {
+            self.name.to_string()
+        } else {
+            format!("{} {}", self.name, self.arguments)
+        }
+    }
+
+    /// Returns what the command does in `mode`: a takeback is answered by
+    /// the other player at the board, made at once against the AI and
+    /// asked for over the network.
+    pub fn describe(&self, mode: Mode) -> &'static str {
+        match (self.command, mode) {
+            (Command::Undo, Mode::SinglePlayer) => "Takes back the last move",
+            (Command::Undo, Mode::Network) => {
+                "Asks your opponent to take back your last move and their reply"
+            }
+            _ => self.description,
+        }
+    }
+}
+
+/// Returns the description of `command` in [`COMMANDS`].
+pub fn spec(command: Command) -> &'static CommandSpec {
+    COMMANDS
+        .iter()
+        .find(|spec| spec.command == command)
+        .unwrap()
+}
+
+/// Finds the command called `name` by its name or one of its built-in aliases.
+pub fn find(name: &str) -> Option<Command> {
+    COMMANDS
+        .iter()
+        .find(|spec| spec.name == name || spec.aliases.contains(&name))
+        .map(|spec| spec.command)
+}
+
+/// Returns whether `text` could be meant as a move, e.g. `e4`, `Nxf3`,
+/// `O-O` or `e7e8q`, or a square selecting a piece, so it can't be
+/// used as a command alias.
+pub fn looks_like_move(text: &str) -> bool {
+    let castling = text
+        .trim_end_matches(['+', '#'])
+        .replace('0', "O")
+        .to_uppercase();
+    let bytes = text.as_bytes();
+    castling == "O-O"
+        || castling == "O-O-O"
+        || bytes
+            .windows(2)
+            .any(|pair| (b'a'..=b'h').contains(&pair[0]) && (b'1'..=b'8').contains(&pair[1]))
+}
+
+/// Splits `input` into a command available in `mode` and its arguments.
+///
+/// Commands are recognized by their names, built-in aliases and the
+/// user-defined `aliases`, given with the command they stand for.
+///
+/// # Returns
+///
+/// * `Some((Command, &str))` if `input` starts with the name or an
+///   alias of a command
+/// * `None` otherwise, e.g. if `input` is a move
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(
+///     parse_command("mark e4", Mode::TwoPlayer, &[]),
+///     Some((Command::Mark, "e4"))
+/// );
+/// assert_eq!(parse_command("Nf3", Mode::TwoPlayer, &[]), None);
+/// ```
+pub fn parse_command<'a>(
+    input: &'a str,
+    mode: Mode,
+    aliases: &[(String, Command)],
+) -> Option<(Command, &'a str)> {
+    let input = input.trim();
+    let (name, arguments) = input.split_once(' ').unwrap_or((input, ""));
+    let command = aliases
+        .iter()
+        .find(|(alias, _)| alias == name)
+        .map(|(_, command)| *command)
+        .or_else(|| find(name))?;
+    spec(command)
+        .modes
+        .contains(&mode)
+        .then_some((command, arguments.trim()))
+}
+
+/// Lists the commands available in `mode` with their descriptions,
+/// including the user-defined `aliases`.
+pub fn help(mode: Mode, aliases: &[(String, Command)]) -> String {
+    let mut help = String::from(
+        "Type a move (e.g. e4, Nf3, Ng1-f3, P-K4 or e7e8q) or a square to select a piece, or one of:\n",
+    );
+    for spec in COMMANDS.iter().filter(|spec| spec.modes.contains(&mode)) {
+        let mut line = format!("  {:<36}{}", spec.usage(), spec.describe(mode));
+        let names: Vec<&str> = spec
+            .aliases
+            .iter()
+            .copied()
+            .chain(
+                aliases
+                    .iter()
+                    .filter(|(_, command)| *command == spec.command)
+                    .map(|(alias, _)| alias.as_str()),
+            )
+            .collect();
+        if !names.is_empty() {
+            line.push_str(&format!(" (also {})", names.join(", ")));
+        }
+        help.push_str(&line);
+        help.push('\n');
+    }
+    help
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_aliases() {
+        assert_eq!(
+            parse_command("quit", Mode::SinglePlayer, &[]),
+            Some((Command::Quit, ""))
+        );
+        assert_eq!(
+            parse_command("takeback", Mode::TwoPlayer, &[]),
+            Some((Command::Undo, ""))
+        );
+        assert_eq!(
+            parse_command("arrow e2  e4 ", Mode::Network, &[]),
+            Some((Command::Arrow, "e2  e4"))
+        );
+        assert_eq!(
+            parse_command("say good luck!", Mode::Network, &[]),
+            Some((Command::Say, "good luck!"))
+        );
+        assert_eq!(parse_command("say hi", Mode::TwoPlayer, &[]), None);
+        assert_eq!(
+            parse_command("undo", Mode::Network, &[]),
+            Some((Command::Undo, ""))
+        );
+        // No engine help against a human opponent over the network.
+        assert_eq!(parse_command("explore", Mode::Network, &[]), None);
+        assert_eq!(
+            parse_command("offer-draw", Mode::SinglePlayer, &[]),
+            Some((Command::Draw, ""))
+        );
+        assert_eq!(parse_command("e4", Mode::TwoPlayer, &[]), None);
+        assert_eq!(
+            parse_command("board 2", Mode::Simul, &[]),
+            Some((Command::Board, "2"))
+        );
+        assert_eq!(parse_command("board 2", Mode::SinglePlayer, &[]), None);
+        assert_eq!(parse_command("undo", Mode::Simul, &[]), None);
+        // A lone file letter stays free for the user, e.g. as an alias.
+        assert_eq!(parse_command("h", Mode::TwoPlayer, &[]), None);
+    }
+
+    #[test]
+    fn user_aliases() {
+        let aliases = [
+            ("bye".to_string(), Command::Quit),
+            ("chat".to_string(), Command::Say),
+        ];
+        assert_eq!(
+            parse_command("bye", Mode::TwoPlayer, &aliases),
+            Some((Command::Quit, ""))
+        );
+        assert_eq!(
+            parse_command("chat gg", Mode::Network, &aliases),
+            Some((Command::Say, "gg"))
+        );
+        assert_eq!(parse_command("chat gg", Mode::TwoPlayer, &aliases), None);
+    }
+
+    #[test]
+    fn recognizes_moves() {
+        for text in ["e4", "Nf3", "exd5", "Qh4#", "O-O", "0-0-0+", "e7e8q", "a1"] {
+            assert!(looks_like_move(text), "{}", text);
+        }
+        for text in ["u", "back", "x", "z9"] {
+            assert!(!looks_like_move(text), "{}", text);
+        }
+        for spec in COMMANDS {
+            assert!(!looks_like_move(spec.name));
+            assert!(!spec.aliases.iter().any(|alias| looks_like_move(alias)));
+        }
+    }
+
+    #[test]
+    fn help_lists_available_commands() {
+        let help = help(Mode::SinglePlayer, &[("back".to_string(), Command::Undo)]);
+        assert!(help.contains("mark <square>"));
+        assert!(help.contains("(also takeback, u, back)"));
+        assert!(help.contains("Takes back the last move (also"));
+        assert!(!help.contains("if your opponent agrees"));
+        let network = super::help(Mode::Network, &[]);
+        assert!(network.contains("Asks your opponent to take back your last move"));
+        let two_player = super::help(Mode::TwoPlayer, &[]);
+        assert!(two_player.contains("Takes back the last move, if your opponent agrees"));
+        assert!(!help.contains("say"));
+        for entry in COMMANDS {
+            assert_eq!(spec(entry.command).name, entry.name);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use crate::ai::piece_value;
+use chess::{Board, Color, Piece};
+
+/// Material per side, in centipawns and without pawns, up to which a
+/// position counts as an endgame: e.g. a queen and a minor piece.
+const ENDGAME_MATERIAL: i32 = 1200;
+
+/// When the AI gives up or settles for a draw, judged from the scores
+/// of its searches so that single-player games end like games between
+/// people instead of being played out to mate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conduct {
+    /// Centipawns the AI has to be behind by to resign, `None` to never
+    /// resign.
+    pub resign_score: Option<i32>,
+    /// Number of its moves in a row the AI has to be that far behind for.
+    pub resign_moves: u32,
+    /// Centipawns from equality within which the AI offers and accepts
+    /// draws, `None` to never do either.
+    pub draw_score: Option<i32>,
+    /// Number of its moves in a row the position has to stay equal for
+    /// before the AI offers a draw.
+    pub draw_moves: u32,
+}
+
+impl Default for Conduct {
+    fn default() -> Self {
+        Self {
+            resign_score: Some(900),
+            resign_moves: 3,
+            draw_score: Some(20),
+            draw_moves: 5,
+        }
+    }
+}
+
+/// Returns whether both sides are down to endgame material.
+fn is_endgame(board: &Board) -> bool {
+    [Color::White, Color::Black].into_iter().all(|color| {
+        let material: i32 = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen]
+            .into_iter()
+            .map(|piece| {
+                let count = (board.pieces(piece) & board.color_combined(color)).popcnt();
+                count as i32 * piece_value(piece)
+            })
+            .sum();
+        material <= ENDGAME_MATERIAL
+    })
+}
+
+impl Conduct {
+    /// Returns the last `moves` of `scores`, if there are that many.
+    fn last(scores: &[i32], moves: u32) -> Option<&[i32]> {
+        let moves = moves.max(1) as usize;
+        scores.get(scores.len().checked_sub(moves)?..)
+    }
+
+    /// Returns whether the AI resigns, given the `scores` of its
+    /// searches so far, from its point of view and most recent last.
+    pub fn resigns(&self, scores: &[i32]) -> bool {
+        match (self.resign_score, Self::last(scores, self.resign_moves)) {
+            (Some(threshold), Some(recent)) => recent.iter().all(|score| *score <= -threshold),
+            _ => false,
+        }
+    }
+
+    /// Returns whether the AI offers a draw in the position at `board`,
+    /// given the `scores` of its searches since its last offer: only in
+    /// endgames that have stayed dead equal for a while.
+    pub fn offers_draw(&self, board: &Board, scores: &[i32]) -> bool {
+        match (self.draw_score, Self::last(scores, self.draw_moves)) {
+            (Some(threshold), Some(recent)) => {
+                is_endgame(board) && recent.iter().all(|score| score.abs() <= threshold)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether the AI accepts a draw offered by its opponent,
+    /// given its latest score: it does unless it thinks it's better.
+    pub fn accepts_draw(&self, score: Option<i32>) -> bool {
+        match (self.draw_score, score) {
+            (Some(threshold), Some(score)) => score <= threshold,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn resigns_lost_positions() {
+        let conduct = Conduct::default();
+        assert!(!conduct.resigns(&[-1000, -1000]));
+        assert!(conduct.resigns(&[0, -950, -1200, -900]));
+        assert!(!conduct.resigns(&[-950, -1200, -300]));
+        let never = Conduct {
+            resign_score: None,
+            ..Conduct::default()
+        };
+        assert!(!never.resigns(&[-5000; 10]));
+    }
+
+    #[test]
+    fn offers_and_accepts_draws() {
+        let conduct = Conduct::default();
+        let rook_ending = Board::from_str("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1").unwrap();
+        assert!(conduct.offers_draw(&rook_ending, &[0, 10, -5, 0, 20]));
+        assert!(!conduct.offers_draw(&rook_ending, &[0, 10, -5, 0, 60]));
+        assert!(!conduct.offers_draw(&rook_ending, &[0, 0]));
+        assert!(!conduct.offers_draw(&Board::default(), &[0; 5]));
+        assert!(conduct.accepts_draw(Some(-300)));
+        assert!(!conduct.accepts_draw(Some(150)));
+        assert!(!conduct.accepts_draw(None));
+    }
+}
@@ -0,0 +1,434 @@
+use crate::ai::{EngineKind, SearchLimits};
+use crate::command::{self, looks_like_move, Command};
+use crate::conduct::Conduct;
+use crate::i18n::Language;
+use crate::notify::Notifier;
+use crate::pacing::Pacing;
+use crate::server::{DEFAULT_SERVER_GAMES, DEFAULT_SERVER_HASH_MB};
+use crate::svg::Theme;
+use crate::tt::DEFAULT_HASH_MB;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Name of the configuration file inside [`config_dir`].
+const CONFIG_FILE: &str = "config.toml";
+/// Largest size the board can be drawn in, see [`Config::zoom`].
+pub const MAX_ZOOM: u8 = 3;
+
+/// User settings read from the configuration file.
+///
+/// The file holds one `key = value` setting per line; lines starting
+/// with `#` are comments. Settings missing from the file keep their
+/// default values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// Whether the in-progress game is saved after every move so it
+    /// can be resumed after a crash.
+    pub autosave: bool,
+    /// Whether finished games are added to `games.pgn` in [`config_dir`].
+    pub archive: bool,
+    /// The player's name, used to tell their side in correspondence
+    /// games and offered when asking for it in other games.
+    pub name: Option<String>,
+    /// Event games are exported with, instead of rChess's own.
+    pub event: Option<String>,
+    /// Site games are exported with, e.g. the club they're played at.
+    pub site: Option<String>,
+    /// Directory correspondence games are shared through, e.g. a synced
+    /// or network folder; `correspondence` in [`config_dir`] by default.
+    pub correspondence_dir: Option<PathBuf>,
+    /// Colors of exported board images: `brown`, `blue`, `green` or `gray`.
+    pub image_theme: Theme,
+    /// Command every move is passed to in words, e.g. `espeak` to have
+    /// moves read out loud.
+    pub announce_command: Option<String>,
+    /// Whether the board is described in words for screen readers
+    /// instead of drawn, as with `--accessible`.
+    pub accessible: bool,
+    /// Language of prompts and piece letters: `en`, `de` or `pl`, or
+    /// `auto`, the default, for the language of the locale.
+    pub language: Option<Language>,
+    /// Whether pieces are drawn as Unicode figurines rather than ASCII
+    /// letters; `auto`, the default, asks the terminal.
+    pub unicode: Option<bool>,
+    /// Whether the board is colored with ANSI escape codes; `auto`, the
+    /// default, asks the terminal.
+    pub color: Option<bool>,
+    /// How large the board is drawn, from 1, its normal size, to
+    /// [`MAX_ZOOM`], with every square boxed in a grid.
+    pub zoom: u8,
+    /// Extra names for in-game commands, set with `alias.<name> = <command>`.
+    pub aliases: Vec<(String, Command)>,
+    /// Engine the AI plays with: `alphabeta` or `mcts`.
+    pub engine: EngineKind,
+    /// Hard limits on every search the AI runs for a move, set with
+    /// `max_nodes`, `max_time` in milliseconds and `max_memory` in
+    /// megabytes.
+    pub limits: SearchLimits,
+    /// Size in megabytes of the transposition table the AI keeps its
+    /// searches in over a game, at most half of `max_memory` so the
+    /// table leaves the search room within it.
+    pub hash_size: usize,
+    /// Whether the table of the engine analyzing a correspondence game
+    /// is saved between sessions, in `hash` in [`config_dir`].
+    pub correspondence_hash: bool,
+    /// NNUE network file the engine evaluates positions with, if rChess
+    /// is built with the `nnue` feature.
+    pub nnue_file: Option<PathBuf>,
+    /// Opening book the AI plays from before its built-in one, a
+    /// Polyglot book such as those made with `rChess make-book`.
+    pub book_file: Option<PathBuf>,
+    /// PGN file of the player's opening repertoire, with variations;
+    /// games leaving it are pointed out.
+    pub repertoire_file: Option<PathBuf>,
+    /// When the AI resigns and offers or accepts draws, set with
+    /// `resign_score`, `resign_moves`, `draw_score` and `draw_moves`;
+    /// a score of `off` turns resigning or draws off.
+    pub conduct: Conduct,
+    /// How long the AI takes over its moves, set with `think_time`: the
+    /// average time in milliseconds, or `instant` to reply at once.
+    pub pacing: Pacing,
+    /// How the player is alerted to their turn, checks and the end of
+    /// the game: `off`, `bell` or a command, see [`Notifier`].
+    pub notify: Notifier,
+    /// How long a network game waits for an opponent who lost the
+    /// connection to come back, set with `reconnect_grace` in seconds,
+    /// before they're taken to have abandoned it.
+    pub reconnect_grace: Duration,
+    /// Most games `rChess serve` hosts at once, set with `server_games`;
+    /// new games are refused while it's full.
+    pub server_games: usize,
+    /// Size in megabytes of the transposition table of each game
+    /// `rChess serve` hosts, set with `server_hash_size`; small, as
+    /// every game has its own.
+    pub server_hash_size: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            autosave: true,
+            archive: true,
+            name: None,
+            event: None,
+            site: None,
+            correspondence_dir: None,
+            image_theme: Theme::default(),
+            announce_command: None,
+            accessible: false,
+            language: None,
+            unicode: None,
+            color: None,
+            zoom: 1,
+            aliases: Vec::new(),
+            engine: EngineKind::default(),
+            limits: SearchLimits::default(),
+            hash_size: DEFAULT_HASH_MB,
+            correspondence_hash: false,
+            nnue_file: None,
+            book_file: None,
+            repertoire_file: None,
+            conduct: Conduct::default(),
+            pacing: Pacing::default(),
+            notify: Notifier::default(),
+            reconnect_grace: Duration::from_secs(60),
+            server_games: DEFAULT_SERVER_GAMES,
+            server_hash_size: DEFAULT_SERVER_HASH_MB,
+        }
+    }
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = Config::default();
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+            let value = value.trim();
+            match key.trim() {
+                "autosave" => config.autosave = parse_bool(value, number)?,
+                "archive" => config.archive = parse_bool(value, number)?,
+                "accessible" => config.accessible = parse_bool(value, number)?,
+                "language" => {
+                    config.language = match parse_string(value).as_str() {
+                        "auto" => None,
+                        language => Some(Language::from_str(language).map_err(|_| {
+                            format!("line {}: unknown language `{}`", number + 1, value)
+                        })?),
+                    }
+                }
+                "unicode" => config.unicode = parse_auto_bool(value, number)?,
+                "color" => config.color = parse_auto_bool(value, number)?,
+                "zoom" => {
+                    config.zoom = parse_number(value, number)?;
+                    if !(1..=MAX_ZOOM).contains(&config.zoom) {
+                        return Err(format!(
+                            "line {}: zoom goes from 1 to {}, got `{}`",
+                            number + 1,
+                            MAX_ZOOM,
+                            value
+                        ));
+                    }
+                }
+                "announce_command" => config.announce_command = Some(parse_string(value)),
+                "notify" => config.notify = Notifier::parse(&parse_string(value)),
+                "name" => config.name = Some(parse_string(value)),
+                "event" => config.event = Some(parse_string(value)),
+                "site" => config.site = Some(parse_string(value)),
+                "image_theme" => {
+                    config.image_theme = Theme::from_str(&parse_string(value))
+                        .map_err(|_| format!("line {}: unknown theme `{}`", number + 1, value))?
+                }
+                "engine" => {
+                    config.engine = EngineKind::from_str(&parse_string(value))
+                        .map_err(|_| format!("line {}: unknown engine `{}`", number + 1, value))?
+                }
+                "max_nodes" => config.limits.nodes = Some(parse_number(value, number)?),
+                "max_time" => {
+                    let millis = parse_number(value, number)?;
+                    config.limits.time = Some(Duration::from_millis(millis));
+                }
+                "max_memory" => {
+                    let megabytes: usize = parse_number(value, number)?;
+                    config.limits.memory = Some(megabytes << 20);
+                }
+                "hash_size" => config.hash_size = parse_number(value, number)?,
+                "correspondence_hash" => config.correspondence_hash = parse_bool(value, number)?,
+                "nnue_file" => config.nnue_file = Some(PathBuf::from(parse_string(value))),
+                "book_file" => config.book_file = Some(PathBuf::from(parse_string(value))),
+                "repertoire_file" => {
+                    config.repertoire_file = Some(PathBuf::from(parse_string(value)))
+                }
+                "resign_score" => config.conduct.resign_score = parse_threshold(value, number)?,
+                "resign_moves" => config.conduct.resign_moves = parse_number(value, number)?,
+                "draw_score" => config.conduct.draw_score = parse_threshold(value, number)?,
+                "draw_moves" => config.conduct.draw_moves = parse_number(value, number)?,
+                "think_time" => {
+                    config.pacing.think_time = match value {
+                        "instant" => None,
+                        _ => Some(Duration::from_millis(parse_number(value, number)?)),
+                    }
+                }
+                "reconnect_grace" => {
+                    config.reconnect_grace = Duration::from_secs(parse_number(value, number)?)
+                }
+                "server_games" => config.server_games = parse_number(value, number)?,
+                "server_hash_size" => config.server_hash_size = parse_number(value, number)?,
+                "correspondence_dir" => {
+                    config.correspondence_dir = Some(PathBuf::from(parse_string(value)))
+                }
+                key if key.starts_with("alias.") => {
+                    let alias = parse_alias(&key["alias.".len()..], value, number)?;
+                    config.aliases.retain(|(name, _)| *name != alias.0);
+                    config.aliases.push(alias);
+                }
+                key => return Err(format!("line {}: unknown setting `{}`", number + 1, key)),
+            }
+        }
+        if let Some(memory) = config.limits.memory {
+            config.hash_size = config.hash_size.min((memory >> 20) / 2);
+        }
+        Ok(config)
+    }
+}
+
+impl Config {
+    /// Reads the configuration file, falling back to the defaults
+    /// if there is none. Errors in the file are reported and the
+    /// defaults used instead.
+    pub fn load() -> Self {
+        let Some(path) = config_dir().map(|dir| dir.join(CONFIG_FILE)) else {
+            return Config::default();
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Config::default();
+        };
+        text.parse().unwrap_or_else(|e| {
+            eprintln!("Error in {}: {}", path.display(), e);
+            Config::default()
+        })
+    }
+
+    /// Returns the directory correspondence games are kept in.
+    pub fn correspondence_dir(&self) -> Option<PathBuf> {
+        self.correspondence_dir
+            .clone()
+            .or_else(|| config_dir().map(|dir| dir.join("correspondence")))
+    }
+}
+
+/// Reads a string value, which may be written in double quotes.
+pub fn parse_string(value: &str) -> String {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// Reads the alias `name` for the command named `value`, making sure
+/// the alias can't be mistaken for a move or another command.
+fn parse_alias(name: &str, value: &str, number: usize) -> Result<(String, Command), String> {
+    let command = command::find(&parse_string(value))
+        .ok_or_else(|| format!("line {}: unknown command `{}`", number + 1, value))?;
+    if name.is_empty() || looks_like_move(name) || command::find(name).is_some() {
+        return Err(format!(
+            "line {}: `{}` can't be an alias, it could be read as a move or command",
+            number + 1,
+            name
+        ));
+    }
+    Ok((name.to_string(), command))
+}
+
+fn parse_bool(value: &str, number: usize) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!(
+            "line {}: expected `true` or `false`, got `{}`",
+            number + 1,
+            value
+        )),
+    }
+}
+
+/// Reads `true` or `false`, or `auto` for neither.
+fn parse_auto_bool(value: &str, number: usize) -> Result<Option<bool>, String> {
+    match value {
+        "auto" => Ok(None),
+        _ => parse_bool(value, number).map(Some),
+    }
+}
+
+/// Reads a number on line `number` (counted from 0).
+fn parse_number<T: FromStr>(value: &str, number: usize) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("line {}: expected a number, got `{}`", number + 1, value))
+}
+
+/// Reads a score in centipawns, or `off` for none.
+fn parse_threshold(value: &str, number: usize) -> Result<Option<i32>, String> {
+    match value {
+        "off" => Ok(None),
+        _ => parse_number(value, number).map(Some),
+    }
+}
+
+/// Returns the directory rChess keeps its configuration and data in:
+/// `$XDG_CONFIG_HOME/rchess`, or `~/.config/rchess` if that's unset.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?)
+            .join(".config"),
+    };
+    Some(base.join("rchess"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_settings() {
+        let config = Config::from_str("# my settings\n\nautosave = false\n").unwrap();
+        assert!(!config.autosave);
+        assert!(config.archive);
+        assert!(!Config::from_str("archive = false").unwrap().archive);
+        assert!(Config::from_str("accessible = true").unwrap().accessible);
+        let config = Config::from_str("language = de").unwrap();
+        assert_eq!(config.language, Some(Language::German));
+        let config = Config::from_str(
+            "unicode = false
+color = auto",
+        )
+        .unwrap();
+        assert_eq!((config.unicode, config.color), (Some(false), None));
+        assert_eq!(Config::from_str("zoom = 3").unwrap().zoom, 3);
+        let config = Config::from_str("alias.back = takeback\nalias.bye = quit").unwrap();
+        assert_eq!(
+            config.aliases,
+            [
+                ("back".to_string(), Command::Undo),
+                ("bye".to_string(), Command::Quit)
+            ]
+        );
+        let config = Config::from_str("name = \"Anna\"\ncorrespondence_dir = /mnt/games").unwrap();
+        assert_eq!(config.name.as_deref(), Some("Anna"));
+        assert_eq!(
+            config.correspondence_dir(),
+            Some(PathBuf::from("/mnt/games"))
+        );
+        let config = Config::from_str("event = \"Club championship\"\nsite = Gdynia").unwrap();
+        assert_eq!(config.event.as_deref(), Some("Club championship"));
+        assert_eq!(config.site.as_deref(), Some("Gdynia"));
+        let config = Config::from_str("announce_command = \"espeak -s 150\"").unwrap();
+        assert_eq!(config.announce_command.as_deref(), Some("espeak -s 150"));
+        let config = Config::from_str("notify = \"paplay ding.ogg\"").unwrap();
+        assert_eq!(
+            config.notify,
+            Notifier::Command("paplay ding.ogg".to_string())
+        );
+        let config = Config::from_str("engine = mcts").unwrap();
+        assert_eq!(config.engine, EngineKind::Mcts);
+        let config =
+            Config::from_str("max_nodes = 50000\nmax_time = 200\nmax_memory = 16").unwrap();
+        assert_eq!(config.limits.nodes, Some(50_000));
+        assert_eq!(config.limits.time, Some(Duration::from_millis(200)));
+        assert_eq!(config.limits.memory, Some(16 << 20));
+        assert_eq!(config.hash_size, 8);
+        let config = Config::from_str("hash_size = 64\ncorrespondence_hash = true").unwrap();
+        assert_eq!(config.hash_size, 64);
+        assert!(config.correspondence_hash);
+        let config = Config::from_str("server_games = 8\nserver_hash_size = 2").unwrap();
+        assert_eq!(config.server_games, 8);
+        assert_eq!(config.server_hash_size, 2);
+        let config = Config::from_str("nnue_file = nets/small.nnue").unwrap();
+        assert_eq!(config.nnue_file, Some(PathBuf::from("nets/small.nnue")));
+        let config = Config::from_str("book_file = books/club.bin").unwrap();
+        assert_eq!(config.book_file, Some(PathBuf::from("books/club.bin")));
+        let config = Config::from_str("repertoire_file = \"~/chess/white.pgn\"").unwrap();
+        assert_eq!(
+            config.repertoire_file,
+            Some(PathBuf::from("~/chess/white.pgn"))
+        );
+        let config = Config::from_str("resign_score = 600\ndraw_score = off").unwrap();
+        assert_eq!(config.conduct.resign_score, Some(600));
+        assert_eq!(config.conduct.draw_score, None);
+        assert_eq!(config.conduct.draw_moves, Conduct::default().draw_moves);
+        let config = Config::from_str("think_time = instant").unwrap();
+        assert_eq!(config.pacing.think_time, None);
+        let config = Config::from_str("think_time = 800").unwrap();
+        assert_eq!(config.pacing.think_time, Some(Duration::from_millis(800)));
+        assert_eq!(Config::from_str("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(Config::from_str("autosave = maybe").is_err());
+        assert!(Config::from_str("autosave").is_err());
+        assert!(Config::from_str("colour = blue").is_err());
+        assert!(Config::from_str("image_theme = purple").is_err());
+        assert!(Config::from_str("engine = deep-blue").is_err());
+        assert!(Config::from_str("language = klingon").is_err());
+        assert!(Config::from_str("zoom = 4").is_err());
+        assert!(Config::from_str("resign_moves = many").is_err());
+        assert!(Config::from_str("max_time = soon").is_err());
+        assert!(Config::from_str("think_time = slow").is_err());
+        assert!(Config::from_str("alias.e4 = undo").is_err());
+        assert!(Config::from_str("alias.q = undo").is_err());
+        assert!(Config::from_str("alias.back = fly").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use crate::rng::random_range;
+use chess::{Square, ALL_SQUARES};
+use std::time::{Duration, Instant};
+
+/// How long a round of coordinate training lasts.
+pub const ROUND_TIME: Duration = Duration::from_secs(30);
+
+/// What the player is asked about the squares.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoordsMode {
+    /// A square is highlighted on the board and the player names it.
+    Name,
+    /// A square is named and the player tells whether it's light or dark.
+    Color,
+}
+
+/// Coordinate training: random squares are shown one after another and
+/// the player answers as many as they can before the round is over.
+pub struct CoordsTrainer {
+    mode: CoordsMode,
+    /// The square asked about.
+    square: Square,
+    started: Instant,
+    score: u32,
+    mistakes: u32,
+}
+
+impl CoordsTrainer {
+    /// Starts a round in `mode`, the clock running from now.
+    pub fn new(mode: CoordsMode) -> Self {
+        Self {
+            mode,
+            square: random_square(),
+            started: Instant::now(),
+            score: 0,
+            mistakes: 0,
+        }
+    }
+
+    pub fn mode(&self) -> CoordsMode {
+        self.mode
+    }
+
+    /// Returns the square asked about.
+    pub fn square(&self) -> Square {
+        self.square
+    }
+
+    /// Returns the question about the current square.
+    pub fn question(&self) -> String {
+        match self.mode {
+            CoordsMode::Name => "Name the highlighted square: ".to_string(),
+            CoordsMode::Color => format!("Is {} light or dark? ", self.square),
+        }
+    }
+
+    /// Returns the right answer to the question.
+    pub fn solution(&self) -> String {
+        match self.mode {
+            CoordsMode::Name => self.square.to_string(),
+            CoordsMode::Color if is_light(self.square) => "light".to_string(),
+            CoordsMode::Color => "dark".to_string(),
+        }
+    }
+
+    /// Checks the player's answer, counts it and moves on to another
+    /// square. In [`CoordsMode::Color`] the first letter of the color
+    /// is enough.
+    ///
+    /// # Returns
+    ///
+    /// Whether the answer was right.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut trainer = CoordsTrainer::new(CoordsMode::Name);
+    /// let answer = trainer.square().to_string();
+    /// assert!(trainer.answer(&answer));
+    /// ```
+    pub fn answer(&mut self, input: &str) -> bool {
+        let input = input.trim().to_lowercase();
+        let solution = self.solution();
+        let right = match self.mode {
+            CoordsMode::Name => input == solution,
+            CoordsMode::Color => !input.is_empty() && solution.starts_with(&input),
+        };
+        if right {
+            self.score += 1;
+        } else {
+            self.mistakes += 1;
+        }
+        self.square = random_square();
+        right
+    }
+
+    /// Returns the time left in the round.
+    pub fn time_left(&self) -> Duration {
+        ROUND_TIME.saturating_sub(self.started.elapsed())
+    }
+
+    /// Returns whether the round is over.
+    pub fn is_over(&self) -> bool {
+        self.time_left().is_zero()
+    }
+
+    /// Returns the number of right answers.
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn mistakes(&self) -> u32 {
+        self.mistakes
+    }
+}
+
+fn random_square() -> Square {
+    ALL_SQUARES[random_range(0..ALL_SQUARES.len())]
+}
+
+/// Returns whether `square` is a light square, like h1 and a8.
+fn is_light(square: Square) -> bool {
+    (square.get_file().to_index() + square.get_rank().to_index()) % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_square_colors() {
+        assert!(!is_light(Square::A1));
+        assert!(is_light(Square::H1));
+        assert!(is_light(Square::D1));
+        assert!(!is_light(Square::D4));
+
+        let mut trainer = CoordsTrainer::new(CoordsMode::Color);
+        assert!(trainer.question().contains(&trainer.square().to_string()));
+        let answer = trainer.solution()[..1].to_uppercase();
+        assert!(trainer.answer(&answer));
+        assert!(!trainer.answer(""));
+        assert_eq!((trainer.score(), trainer.mistakes()), (1, 1));
+    }
+
+    #[test]
+    fn checks_square_names() {
+        let mut trainer = CoordsTrainer::new(CoordsMode::Name);
+        let square = trainer.square().to_string();
+        assert!(trainer.answer(&format!(" {} ", square.to_uppercase())));
+        let wrong = if trainer.square() == Square::A1 {
+            "h8"
+        } else {
+            "a1"
+        };
+        assert!(!trainer.answer(wrong));
+        assert!(!trainer.is_over());
+        assert!(trainer.time_left() <= ROUND_TIME);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::game::{Game, Status};
+use crate::pgn::{parse_pgn, replay, write_pgn};
+use chess::Color;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A game played by correspondence, each player making their move
+/// whenever they like.
+///
+/// Games are kept as `<id>.pgn` files in a directory shared by both
+/// players, e.g. a synced or network folder, with the players' names
+/// in the `White` and `Black` tags.
+pub struct CorrespondenceGame {
+    id: String,
+    white: String,
+    black: String,
+    game: Game,
+}
+
+impl CorrespondenceGame {
+    /// Starts a new game called `id` between the players named `white` and `black`.
+    pub fn new(id: &str, white: &str, black: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            white: white.to_string(),
+            black: black.to_string(),
+            game: Game::new_multi(),
+        }
+    }
+
+    /// Returns the name the game is stored under.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the game played so far.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Returns the game, e.g. to make a move in it.
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    /// Returns the name of the player of `color`.
+    pub fn player(&self, color: Color) -> &str {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    /// Returns the color the player called `name` plays, if they play in this game.
+    pub fn color_of(&self, name: &str) -> Option<Color> {
+        if self.white == name {
+            Some(Color::White)
+        } else if self.black == name {
+            Some(Color::Black)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the player called `name` has to make the next move.
+    pub fn awaits(&self, name: &str) -> bool {
+        self.game.status() == Status::Ongoing && self.color_of(name) == Some(self.game.turn())
+    }
+
+    /// Returns the path of the game called `id` in `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `id` isn't a valid game name: only letters,
+    /// digits, `-` and `_` are allowed, so games can't be stored
+    /// outside `dir`.
+    fn path(dir: &Path, id: &str) -> io::Result<PathBuf> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid game name `{}`", id),
+            ));
+        }
+        Ok(dir.join(id).with_extension("pgn"))
+    }
+
+    /// Reads the game called `id` from `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the game can't be read or isn't a valid game.
+    pub fn load(dir: &Path, id: &str) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let saved = parse_pgn(&fs::read_to_string(Self::path(dir, id)?)?)
+            .into_iter()
+            .next()
+            .ok_or_else(|| invalid("no game found"))?;
+        let game = replay(&saved, Game::new_multi()).map_err(|e| invalid(&e))?;
+        Ok(Self {
+            id: id.to_string(),
+            white: saved.tag("White").unwrap_or("?").to_string(),
+            black: saved.tag("Black").unwrap_or("?").to_string(),
+            game,
+        })
+    }
+
+    /// Returns the PGN tags naming the game and its players.
+    pub fn tags(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Event", "rChess correspondence game".to_string()),
+            ("Site", self.id.clone()),
+            ("White", self.white.clone()),
+            ("Black", self.black.clone()),
+        ]
+    }
+
+    /// Writes the game to `dir`, replacing the previous version.
+    ///
+    /// The game is written to a temporary file first and then moved in
+    /// place, so the opponent never reads a half-written game.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let path = Self::path(dir, &self.id)?;
+        fs::create_dir_all(dir)?;
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, write_pgn(&self.game, &self.tags()))?;
+        fs::rename(&temp, &path)
+    }
+}
+
+/// Reads all correspondence games in `dir`, sorted by name.
+///
+/// Files that aren't valid games are skipped. A missing directory
+/// holds no games.
+pub fn list_games(dir: &Path) -> io::Result<Vec<CorrespondenceGame>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut games = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "pgn") {
+            let id = path.file_stem().and_then(|stem| stem.to_str());
+            if let Some(Ok(game)) = id.map(|id| CorrespondenceGame::load(dir, id)) {
+                games.push(game);
+            }
+        }
+    }
+    games.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn players_take_turns_through_shared_dir() {
+        let temp = TempDir::new("correspondence");
+        let dir = temp.join("games");
+        let mut game = CorrespondenceGame::new("club-1", "Anna", "Bob");
+        assert!(game.awaits("Anna"));
+        game.game_mut().make_move_from_str("e4", false).unwrap();
+        game.save(&dir).unwrap();
+
+        let mut game = CorrespondenceGame::load(&dir, "club-1").unwrap();
+        assert_eq!(game.color_of("Bob"), Some(Color::Black));
+        assert_eq!(game.player(Color::White), "Anna");
+        assert!(game.awaits("Bob"));
+        assert!(!game.awaits("Anna"));
+        assert!(!game.awaits("Carl"));
+        game.game_mut().make_move_from_str("c5", false).unwrap();
+        game.save(&dir).unwrap();
+
+        let games = list_games(&dir).unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].id(), "club-1");
+        assert_eq!(games[0].game().moves().len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(list_games(&dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        let temp = TempDir::new("correspondence-names");
+        let dir = temp.join("games");
+        assert!(CorrespondenceGame::new("../escape", "Anna", "Bob")
+            .save(&dir)
+            .is_err());
+        assert!(CorrespondenceGame::load(&dir, "").is_err());
+    }
+}
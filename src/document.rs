@@ -0,0 +1,355 @@
+use crate::annotation::nag_glyph;
+use crate::game::Game;
+use crate::notation::san_for_move;
+use crate::pgn::{result_token, write_pgn};
+use chess::{Board, Color, Rank, Square, ALL_FILES};
+use std::path::Path;
+use std::str::FromStr;
+
+/// A format a game can be exported in with the `export` command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DocumentFormat {
+    Pgn,
+    /// A Markdown table of the moves, with text diagrams.
+    Markdown,
+    /// A LaTeX document typeset with the `xskak` package.
+    Latex,
+}
+
+impl FromStr for DocumentFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pgn" => Ok(DocumentFormat::Pgn),
+            "markdown" | "md" => Ok(DocumentFormat::Markdown),
+            "latex" | "tex" | "skak" | "xskak" => Ok(DocumentFormat::Latex),
+            _ => Err(()),
+        }
+    }
+}
+
+impl DocumentFormat {
+    /// Returns the format a file is written in by its extension, PGN
+    /// unless it's `.md` or `.tex`.
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse().ok())
+            .unwrap_or(DocumentFormat::Pgn)
+    }
+
+    /// Writes `game` in this format.
+    pub fn write(self, game: &Game) -> String {
+        match self {
+            DocumentFormat::Pgn => write_pgn(game, &[]),
+            DocumentFormat::Markdown => write_markdown(game),
+            DocumentFormat::Latex => write_latex(game),
+        }
+    }
+}
+
+/// A move of the exported line.
+struct Entry {
+    number: u32,
+    color: Color,
+    /// The move in SAN followed by its glyphs, e.g. `Nf3!?`.
+    san: String,
+    comment: String,
+    /// Whether the move was commented on or judged with a glyph, so
+    /// the position after it is shown.
+    key: bool,
+    /// The position after the move.
+    board: Board,
+}
+
+/// Returns the moves of the current line of `game`.
+fn entries(game: &Game) -> Vec<Entry> {
+    let mut board = *game.starting_position();
+    let mut number = 1;
+    let nodes = game.move_tree().line(game.current_path());
+    nodes
+        .into_iter()
+        .zip(game.moves())
+        .map(|(node, mv)| {
+            let color = board.side_to_move();
+            let glyphs: String = node.nags.iter().map(|nag| nag_glyph(*nag)).collect();
+            let entry = Entry {
+                number,
+                color,
+                san: format!("{}{}", san_for_move(&board, *mv), glyphs),
+                comment: node.comment.clone(),
+                key: !node.comment.is_empty() || !node.nags.is_empty(),
+                board: board.make_move_new(*mv),
+            };
+            board = entry.board;
+            if color == Color::Black {
+                number += 1;
+            }
+            entry
+        })
+        .collect()
+}
+
+/// Returns the line under the title, e.g. `Club night, Gdynia, round 3`,
+/// with the event, site and round of `game` that are set.
+fn subtitle(game: &Game) -> String {
+    let tags = game.tags();
+    let mut parts: Vec<String> = [&tags.event, &tags.site]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect();
+    if let Some(round) = &tags.round {
+        parts.push(format!("round {}", round));
+    }
+    parts.join(", ")
+}
+
+/// Splits `entries` after every key moment and the last move.
+fn sections(entries: &[Entry]) -> Vec<&[Entry]> {
+    entries
+        .split_inclusive(|entry| entry.key)
+        .filter(|section| !section.is_empty())
+        .collect()
+}
+
+/// Writes the board as text, rank 8 on top, with pieces as in FEN and
+/// empty squares as dots.
+fn text_diagram(board: &Board) -> String {
+    let mut diagram = String::new();
+    for rank in (0..8).rev() {
+        diagram.push_str(&format!("{} ", rank + 1));
+        for file in ALL_FILES {
+            let square = Square::make_square(Rank::from_index(rank), file);
+            let symbol = match (board.piece_on(square), board.color_on(square)) {
+                (Some(piece), Some(color)) => piece.to_string(color),
+                _ => ".".to_string(),
+            };
+            diagram.push_str(&format!(" {}", symbol));
+        }
+        diagram.push('\n');
+    }
+    diagram.push_str("   a b c d e f g h\n");
+    diagram
+}
+
+/// Writes a row of a Markdown table, leaving empty cells blank.
+fn table_row(cells: &[String]) -> String {
+    let mut row = String::from("|");
+    for cell in cells {
+        match cell.as_str() {
+            "" => row.push_str(" |"),
+            cell => row.push_str(&format!(" {} |", cell)),
+        }
+    }
+    row.push('\n');
+    row
+}
+
+/// Exports a game as Markdown: a title naming the players, a table of
+/// the moves, and a diagram after every commented or judged move, with
+/// the comment, and of the final position.
+///
+/// # Example
+///
+/// ```
+/// let mut game = Game::new_multi();
+/// game.make_move_from_str("e4", false)?;
+/// assert!(write_markdown(&game).contains("| 1 | e4 | |"));
+/// ```
+pub fn write_markdown(game: &Game) -> String {
+    let tags = game.tags();
+    let mut markdown = format!(
+        "# {} – {}\n\n",
+        tags.white.as_deref().unwrap_or("?"),
+        tags.black.as_deref().unwrap_or("?")
+    );
+    let subtitle = subtitle(game);
+    if !subtitle.is_empty() {
+        markdown.push_str(&format!("{}\n\n", subtitle));
+    }
+    if !game.move_tree().comment.is_empty() {
+        markdown.push_str(&format!("{}\n\n", game.move_tree().comment));
+    }
+    let entries = entries(game);
+    for section in sections(&entries) {
+        markdown.push_str("| Move | White | Black |\n|---:|---|---|\n");
+        for row in section.chunk_by(|a, b| a.number == b.number) {
+            let cell = |color| {
+                row.iter()
+                    .find(|entry| entry.color == color)
+                    .map_or(String::new(), |entry| entry.san.clone())
+            };
+            let white = match cell(Color::White) {
+                white if white.is_empty() && row[0].color == Color::Black => "…".to_string(),
+                white => white,
+            };
+            markdown.push_str(&table_row(&[
+                row[0].number.to_string(),
+                white,
+                cell(Color::Black),
+            ]));
+        }
+        let last = section.last().unwrap();
+        markdown.push('\n');
+        if last.key {
+            let dots = if last.color == Color::White {
+                "."
+            } else {
+                "..."
+            };
+            markdown.push_str(&format!("**{}{} {}**", last.number, dots, last.san));
+            if !last.comment.is_empty() {
+                markdown.push_str(&format!(" {}", last.comment));
+            }
+            markdown.push_str(&format!(
+                "\n\n```text\n{}```\n\n",
+                text_diagram(&last.board)
+            ));
+        }
+    }
+    if entries.last().is_none_or(|last| !last.key) {
+        markdown.push_str(&format!(
+            "Final position:\n\n```text\n{}```\n\n",
+            text_diagram(game.board())
+        ));
+    }
+    markdown.push_str(&format!("Result: {}\n", result_token(game.status())));
+    markdown
+}
+
+/// Escapes the characters LaTeX treats specially in `text`.
+fn escape_latex(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".to_string(),
+            '~' => "\\textasciitilde{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{}", c),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Exports a game as a LaTeX document typeset with the `xskak` package:
+/// the moves in `\mainline`s, broken after every commented or judged
+/// move by its comment and a `\chessboard` diagram, and a diagram of the
+/// final position.
+///
+/// # Example
+///
+/// ```
+/// let mut game = Game::new_multi();
+/// game.make_move_from_str("e4", false)?;
+/// assert!(write_latex(&game).contains("\\mainline{1. e4}"));
+/// ```
+pub fn write_latex(game: &Game) -> String {
+    let tags = game.tags();
+    let mut latex =
+        String::from("\\documentclass{article}\n\\usepackage{xskak}\n\\begin{document}\n\n");
+    latex.push_str(&format!(
+        "\\section*{{{} -- {}}}\n",
+        escape_latex(tags.white.as_deref().unwrap_or("?")),
+        escape_latex(tags.black.as_deref().unwrap_or("?"))
+    ));
+    let subtitle = subtitle(game);
+    if !subtitle.is_empty() {
+        latex.push_str(&format!("{}\n", escape_latex(&subtitle)));
+    }
+    if !game.move_tree().comment.is_empty() {
+        latex.push_str(&format!("\n{}\n", escape_latex(&game.move_tree().comment)));
+    }
+    if *game.starting_position() == Board::default() {
+        latex.push_str("\n\\newchessgame\n");
+    } else {
+        latex.push_str(&format!(
+            "\n\\newchessgame[setfen={}]\n",
+            game.starting_position()
+        ));
+    }
+    let entries = entries(game);
+    for section in sections(&entries) {
+        let moves: Vec<String> = section
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| match entry.color {
+                Color::White => format!("{}. {}", entry.number, entry.san),
+                Color::Black if index == 0 => format!("{}... {}", entry.number, entry.san),
+                Color::Black => entry.san.clone(),
+            })
+            .collect();
+        latex.push_str(&format!("\\mainline{{{}}}\n", moves.join(" ")));
+        let last = section.last().unwrap();
+        if last.key {
+            if !last.comment.is_empty() {
+                latex.push_str(&format!("\n{}\n", escape_latex(&last.comment)));
+            }
+            latex.push_str("\n\\chessboard\n\n");
+        }
+    }
+    if entries.last().is_none_or(|last| !last.key) {
+        latex.push_str("\n\\chessboard\n\n");
+    }
+    latex.push_str(&format!(
+        "Result: {}\n\n\\end{{document}}\n",
+        result_token(game.status())
+    ));
+    latex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::GameTags;
+
+    fn annotated_game() -> Game {
+        let mut tags = GameTags::default();
+        tags.set("white", "Anna").unwrap();
+        tags.set("event", "Club night").unwrap();
+        let mut game = Game::new_multi().with_tags(tags);
+        for mv in ["e4", "e5", "Nf3"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        game.toggle_nag(5);
+        game.set_comment("100% Anna's");
+        for mv in ["Nc6", "Bb5"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn writes_markdown() {
+        let markdown = write_markdown(&annotated_game());
+        assert!(markdown.starts_with("# Anna – ?\n\nClub night\n\n"));
+        assert!(markdown.contains("| 2 | Nf3!? | |\n\n**2. Nf3!?** 100% Anna's\n"));
+        assert!(markdown.contains("| 2 | … | Nc6 |\n| 3 | Bb5 | |\n"));
+        assert!(markdown.contains("8  r . b q k b n r\n"));
+        assert!(markdown.contains("Final position:"));
+        assert!(markdown.ends_with("Result: *\n"));
+        assert_eq!(
+            DocumentFormat::from_path(Path::new("game.md")),
+            DocumentFormat::Markdown
+        );
+        assert_eq!(
+            DocumentFormat::from_path(Path::new("game")),
+            DocumentFormat::Pgn
+        );
+    }
+
+    #[test]
+    fn writes_latex() {
+        let latex = write_latex(&annotated_game());
+        assert!(latex.contains("\\usepackage{xskak}"));
+        assert!(latex.contains("\\mainline{1. e4 e5 2. Nf3!?}\n\n100\\% Anna's\n\n\\chessboard\n"));
+        assert!(latex.contains("\\mainline{2... Nc6 3. Bb5}\n\n\\chessboard\n"));
+        assert!(latex.ends_with("\\end{document}\n"));
+        let board = Board::from_str("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap();
+        let mut game = Game::new_multi().with_position(board);
+        game.make_move_from_str("Kd7", false).unwrap();
+        let latex = write_latex(&game);
+        assert!(latex.contains("\\newchessgame[setfen=4k3/8/8/8/8/8/4P3/4K3 b - - 0 1]"));
+        assert!(latex.contains("\\mainline{1... Kd7}"));
+    }
+}
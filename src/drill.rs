@@ -0,0 +1,283 @@
+use crate::error::ChessError;
+use crate::game::Game;
+use crate::legality::validate_fen;
+use crate::notation::{parse_san, san_for_move};
+use crate::pgn::{parse_pgn, PgnGame};
+use chess::{Board, ChessMove, Color};
+
+/// Opening lines offered when no repertoire file is given.
+pub const BUILTIN_LINES: &str = r#"
+[Opening "Ruy Lopez, Morphy Defense"]
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O *
+
+[Opening "Italian Game, Giuoco Piano"]
+1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. c3 Nf6 5. d4 exd4 6. cxd4 Bb4+ 7. Bd2 Bxd2+ *
+
+[Opening "King's Gambit Accepted, Kieseritzky Gambit"]
+1. e4 e5 2. f4 exf4 3. Nf3 g5 4. h4 g4 5. Ne5 Nf6 *
+
+[Opening "Sicilian Defense, Najdorf Variation"]
+1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 Nf6 5. Nc3 a6 *
+
+[Opening "French Defense, Classical Variation"]
+1. e4 e6 2. d4 d5 3. Nc3 Nf6 4. Bg5 Be7 5. e5 Nfd7 *
+
+[Opening "Caro-Kann Defense, Classical Variation"]
+1. e4 c6 2. d4 d5 3. Nc3 dxe4 4. Nxe4 Bf5 5. Ng3 Bg6 *
+
+[Opening "Queen's Gambit Declined, Orthodox Defense"]
+1. d4 d5 2. c4 e6 3. Nc3 Nf6 4. Bg5 Be7 5. e3 O-O 6. Nf3 Nbd7 *
+
+[Opening "King's Indian Defense, Classical Variation"]
+1. d4 Nf6 2. c4 g6 3. Nc3 Bg7 4. e4 d6 5. Nf3 O-O 6. Be2 e5 *
+"#;
+
+/// A named sequence of moves to learn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub name: String,
+    pub start: Board,
+    pub moves: Vec<ChessMove>,
+}
+
+impl Line {
+    /// Reads a line from the main line of a PGN game.
+    ///
+    /// The name is taken from the `Opening` tag, falling back to `Event`.
+    fn from_game(game: &PgnGame, number: usize) -> Result<Self, String> {
+        let name = game
+            .tag("Opening")
+            .or_else(|| game.tag("Event"))
+            .filter(|name| !name.is_empty() && *name != "?")
+            .map_or_else(|| format!("Line {}", number), str::to_string);
+        let start = match game.tag("FEN") {
+            Some(fen) => validate_fen(fen).map_err(|e| format!("{}: invalid FEN: {}", name, e))?,
+            None => Board::default(),
+        };
+        let mut board = start;
+        let mut moves = Vec::new();
+        for text in &game.moves {
+            let mv = parse_san(&board, text)
+                .ok_or_else(|| format!("{}: illegal move {}", name, text))?;
+            board = board.make_move_new(mv);
+            moves.push(mv);
+        }
+        Ok(Self { name, start, moves })
+    }
+}
+
+/// A set of opening lines to practice.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repertoire {
+    pub lines: Vec<Line>,
+}
+
+impl Repertoire {
+    /// Builds a repertoire from the main lines of a PGN database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the line with an illegal move or FEN.
+    pub fn from_pgn(text: &str) -> Result<Self, String> {
+        let lines = parse_pgn(text)
+            .iter()
+            .enumerate()
+            .map(|(i, game)| Line::from_game(game, i + 1))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { lines })
+    }
+
+    /// Builds a repertoire of start positions, without moves, from an
+    /// EPD file: one position per line, its four FEN fields followed by
+    /// operations such as `id "Name";`, which names the position.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the line with an invalid position.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let suite = Repertoire::from_epd("4k3/8/8/8/8/8/4P3/4K3 w - - id \"KP vs K\";")?;
+    /// assert_eq!(suite.lines[0].name, "KP vs K");
+    /// ```
+    pub fn from_epd(text: &str) -> Result<Self, String> {
+        let mut lines = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.splitn(5, ' ').collect();
+            let fen = format!("{} 0 1", fields[..fields.len().min(4)].join(" "));
+            let start = validate_fen(&fen)
+                .map_err(|e| format!("line {}: invalid position: {}", number + 1, e))?;
+            let name = fields
+                .get(4)
+                .and_then(|operations| {
+                    operations
+                        .split(';')
+                        .find_map(|op| op.trim().strip_prefix("id "))
+                })
+                .map(|name| name.trim().trim_matches('"').to_string())
+                .unwrap_or_else(|| format!("Position {}", lines.len() + 1));
+            lines.push(Line {
+                name,
+                start,
+                moves: Vec::new(),
+            });
+        }
+        Ok(Self { lines })
+    }
+
+    /// Returns the built-in repertoire of common openings.
+    pub fn builtin() -> Self {
+        Self::from_pgn(BUILTIN_LINES).unwrap()
+    }
+}
+
+/// Answer to a move played in a drill.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrillAnswer {
+    /// The move follows the line.
+    Correct,
+    /// The move leaves the line, which continues with the given move in SAN.
+    Wrong(String),
+}
+
+/// Practice session of one line, playing the moves of one side from memory.
+pub struct Drill<'a> {
+    line: &'a Line,
+    color: Color,
+    game: Game,
+    mistakes: u32,
+}
+
+impl<'a> Drill<'a> {
+    /// Starts practicing `line` with the player playing `color`.
+    pub fn new(line: &'a Line, color: Color) -> Self {
+        Self {
+            line,
+            color,
+            game: Game::new_multi().with_position(line.start),
+            mistakes: 0,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Returns the number of moves played that left the line.
+    pub fn mistakes(&self) -> u32 {
+        self.mistakes
+    }
+
+    /// Returns whether every move of the line has been played.
+    pub fn finished(&self) -> bool {
+        self.game.moves().len() >= self.line.moves.len()
+    }
+
+    /// Returns the next move of the line.
+    fn expected(&self) -> Option<ChessMove> {
+        self.line.moves.get(self.game.moves().len()).copied()
+    }
+
+    /// Returns the next move of the line in SAN, as a hint.
+    pub fn hint(&self) -> Option<String> {
+        self.expected()
+            .map(|mv| san_for_move(self.game.board(), mv))
+    }
+
+    /// Plays the opponent's next move of the line, returning it in SAN,
+    /// or `None` if it's the player's turn or the line is over.
+    pub fn play_opponent(&mut self) -> Option<String> {
+        if self.game.turn() == self.color {
+            return None;
+        }
+        let mv = self.expected()?;
+        let text = san_for_move(self.game.board(), mv);
+        self.game.make_move(mv).ok()?;
+        Some(text)
+    }
+
+    /// Checks the player's move against the line, playing it if it's correct.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input isn't a legal move.
+    pub fn play(&mut self, input: &str) -> Result<DrillAnswer, ChessError> {
+        let mv = self.game.parse_move(input, false)?;
+        match self.expected() {
+            Some(expected) if expected != mv => {
+                self.mistakes += 1;
+                Ok(DrillAnswer::Wrong(san_for_move(
+                    self.game.board(),
+                    expected,
+                )))
+            }
+            _ => {
+                self.game.make_move(mv)?;
+                Ok(DrillAnswer::Correct)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_lines_are_legal() {
+        let repertoire = Repertoire::builtin();
+        assert_eq!(repertoire.lines[0].name, "Ruy Lopez, Morphy Defense");
+        assert!(repertoire.lines.iter().all(|line| line.moves.len() >= 10));
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let repertoire =
+            Repertoire::from_pgn("1. e4 e5 *\n\n[Event \"Gambit\"]\n1. e4 d5 2. exd5 *");
+        assert_eq!(repertoire.unwrap().lines[1].name, "Gambit");
+        assert_eq!(
+            Repertoire::from_pgn("1. e4 e5 2. Ke3 *"),
+            Err("Line 1: illegal move Ke3".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_epd_suites() {
+        let suite = Repertoire::from_epd(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 id \"King's Pawn\";\n\
+             # Endgames\n\
+             4k3/8/8/8/8/8/4P3/4K3 w - - bm Kd2;\n",
+        )
+        .unwrap();
+        assert_eq!(suite.lines.len(), 2);
+        assert_eq!(suite.lines[0].name, "King's Pawn");
+        assert_eq!(suite.lines[0].start.side_to_move(), Color::Black);
+        assert_eq!(suite.lines[1].name, "Position 2");
+        assert!(suite.lines[1].moves.is_empty());
+        assert_eq!(
+            Repertoire::from_epd("not a position"),
+            Err("line 1: invalid position: the FEN can't be read".to_string())
+        );
+    }
+
+    #[test]
+    fn drill_corrects_deviations() {
+        let repertoire = Repertoire::from_pgn("1. e4 c5 2. Nf3 d6 *").unwrap();
+        let mut drill = Drill::new(&repertoire.lines[0], Color::Black);
+        assert_eq!(drill.play_opponent(), Some("e4".to_string()));
+        assert_eq!(drill.play_opponent(), None);
+        assert_eq!(drill.play("e5"), Ok(DrillAnswer::Wrong("c5".to_string())));
+        assert_eq!(drill.hint(), Some("c5".to_string()));
+        assert!(drill.play("Ke3").is_err());
+        assert_eq!(drill.play("c5"), Ok(DrillAnswer::Correct));
+        assert_eq!(drill.play_opponent(), Some("Nf3".to_string()));
+        assert_eq!(drill.play("d6"), Ok(DrillAnswer::Correct));
+        assert!(drill.finished());
+        assert_eq!(drill.mistakes(), 1);
+    }
+}
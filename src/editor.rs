@@ -0,0 +1,258 @@
+use crate::legality::{parse_fen, validate_position, PositionError};
+use chess::{Board, BoardBuilder, CastleRights, Color, Piece, Square, ALL_COLORS};
+use std::fmt;
+use std::str::FromStr;
+
+/// Commands understood by the [`Editor`], as listed in the editor's help.
+pub const EDIT_COMMANDS: &[(&str, &str)] = &[
+    (
+        "put <piece> <square>",
+        "Places a piece, e.g. put K e1 or put n f6",
+    ),
+    ("remove <square>", "Empties a square"),
+    ("clear", "Removes every piece"),
+    ("reset", "Sets up the starting position"),
+    ("turn <white|black>", "Sets the side to move"),
+    ("castle <KQkq|->", "Sets the castling rights"),
+    ("ep <square|->", "Sets the en passant target square"),
+    ("fen <FEN>", "Loads a position"),
+];
+
+/// Errors from editing a position.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditError {
+    /// The input is not an editor command.
+    UnknownCommand(String),
+    /// The command's arguments are missing or can't be read; holds its usage.
+    Usage(&'static str),
+    /// The FEN can't be read.
+    InvalidFen,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::UnknownCommand(command) => write!(f, "Unknown command: {}", command),
+            EditError::Usage(usage) => write!(f, "Usage: {}", usage),
+            EditError::InvalidFen => write!(f, "Invalid FEN!"),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// A position being set up piece by piece.
+pub struct Editor {
+    setup: BoardBuilder,
+}
+
+impl Editor {
+    /// Creates an editor holding `setup`, which doesn't need to be playable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let editor = Editor::new(Board::default().into());
+    /// assert_eq!(editor.position(), Ok(Board::default()));
+    /// ```
+    pub fn new(setup: BoardBuilder) -> Self {
+        Self { setup }
+    }
+
+    /// Returns the position as set up so far, which may not be playable.
+    pub fn setup(&self) -> &BoardBuilder {
+        &self.setup
+    }
+
+    /// Carries out one of the [`EDIT_COMMANDS`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command is unknown or its arguments are
+    /// invalid, leaving the position unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut editor = Editor::new(Board::default().into());
+    /// editor.apply("remove d1").unwrap();
+    /// editor.apply("put Q h5").unwrap();
+    /// ```
+    pub fn apply(&mut self, input: &str) -> Result<(), EditError> {
+        let input = input.trim();
+        let (command, argument) = input.split_once(' ').unwrap_or((input, ""));
+        let argument = argument.trim();
+        match command {
+            "put" => {
+                let usage = EditError::Usage("put <piece> <square>");
+                let (piece, square) = argument.split_once(' ').ok_or(usage.clone())?;
+                let (piece, color) = parse_piece(piece).ok_or(usage.clone())?;
+                let square = Square::from_str(square.trim()).map_err(|_| usage)?;
+                self.setup.piece(square, piece, color);
+            }
+            "remove" => {
+                let square =
+                    Square::from_str(argument).map_err(|_| EditError::Usage("remove <square>"))?;
+                self.setup.clear_square(square);
+            }
+            "clear" => {
+                let turn = self.setup.get_side_to_move();
+                self.setup = BoardBuilder::new();
+                self.setup.side_to_move(turn);
+            }
+            "reset" => self.setup = Board::default().into(),
+            "turn" => {
+                let color = match argument {
+                    "white" | "w" => Color::White,
+                    "black" | "b" => Color::Black,
+                    _ => return Err(EditError::Usage("turn <white|black>")),
+                };
+                self.setup.side_to_move(color);
+            }
+            "castle" => {
+                let rights = parse_castling(argument).ok_or(EditError::Usage("castle <KQkq|->"))?;
+                for (color, rights) in ALL_COLORS.iter().zip(rights) {
+                    self.setup.castle_rights(*color, rights);
+                }
+            }
+            "ep" => {
+                let file = match argument {
+                    "-" => None,
+                    square => Some(
+                        Square::from_str(square)
+                            .map_err(|_| EditError::Usage("ep <square|->"))?
+                            .get_file(),
+                    ),
+                };
+                self.setup.en_passant(file);
+            }
+            "fen" => self.setup = parse_fen(argument).map_err(|_| EditError::InvalidFen)?,
+            _ => return Err(EditError::UnknownCommand(command.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Checks that the position can be played and returns it, see
+    /// [`validate_position`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the first problem found with the position.
+    pub fn position(&self) -> Result<Board, PositionError> {
+        validate_position(&self.setup)
+    }
+}
+
+/// Reads a piece in FEN notation: upper case for White, lower case for Black.
+fn parse_piece(input: &str) -> Option<(Piece, Color)> {
+    let mut chars = input.chars();
+    let letter = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    let piece = match letter.to_ascii_lowercase() {
+        'p' => Piece::Pawn,
+        'n' => Piece::Knight,
+        'b' => Piece::Bishop,
+        'r' => Piece::Rook,
+        'q' => Piece::Queen,
+        'k' => Piece::King,
+        _ => return None,
+    };
+    let color = if letter.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Some((piece, color))
+}
+
+/// Reads castling rights in FEN notation into the rights of White and Black.
+fn parse_castling(input: &str) -> Option<[CastleRights; 2]> {
+    let mut sides = [[false; 2]; 2];
+    if input != "-" {
+        for letter in input.chars() {
+            let (color, side) = match letter {
+                'K' => (0, 0),
+                'Q' => (0, 1),
+                'k' => (1, 0),
+                'q' => (1, 1),
+                _ => return None,
+            };
+            sides[color][side] = true;
+        }
+    }
+    Some(sides.map(|side| match side {
+        [true, true] => CastleRights::Both,
+        [true, false] => CastleRights::KingSide,
+        [false, true] => CastleRights::QueenSide,
+        [false, false] => CastleRights::NoRights,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(commands: &[&str]) -> Editor {
+        let mut editor = Editor::new(Board::default().into());
+        for command in commands {
+            editor.apply(command).unwrap();
+        }
+        editor
+    }
+
+    #[test]
+    fn sets_up_positions() {
+        let board = editor(&[
+            "clear",
+            "put K g1",
+            "put k g8",
+            "put R a1",
+            "put p h7",
+            "turn black",
+        ])
+        .position()
+        .unwrap();
+        assert_eq!(
+            board,
+            Board::from_str("6k1/7p/8/8/8/8/8/R5K1 b - - 0 1").unwrap()
+        );
+
+        let board = editor(&["remove g1", "remove b1", "castle Qk", "ep -"])
+            .position()
+            .unwrap();
+        assert_eq!(board.castle_rights(Color::White), CastleRights::QueenSide);
+        assert_eq!(board.castle_rights(Color::Black), CastleRights::KingSide);
+
+        let mut editor = editor(&[]);
+        assert_eq!(
+            editor.apply("put X e4"),
+            Err(EditError::Usage("put <piece> <square>"))
+        );
+        assert_eq!(
+            editor.apply("jump e4"),
+            Err(EditError::UnknownCommand("jump".to_string()))
+        );
+        assert_eq!(
+            editor.apply("fen 8/8/8 w - - 0 1"),
+            Err(EditError::InvalidFen)
+        );
+    }
+
+    #[test]
+    fn validates_positions() {
+        let invalid = |commands: &[&str]| editor(commands).position().unwrap_err();
+        assert_eq!(
+            invalid(&["remove e8"]),
+            PositionError::MissingKing(Color::Black)
+        );
+        assert_eq!(
+            invalid(&["clear", "put K e1", "put k e8", "put R e4"]),
+            PositionError::WaitingSideInCheck(Color::Black)
+        );
+        assert!(editor(&["clear", "put K e1", "put k e8", "put r e4"])
+            .position()
+            .is_ok());
+    }
+}
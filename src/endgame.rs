@@ -0,0 +1,241 @@
+use crate::adjudication::insufficient_material;
+use crate::ai::Difficulty;
+use crate::error::ChessError;
+use crate::game::{Game, Status};
+use chess::{Board, Color};
+use std::str::FromStr;
+
+/// What the player has to achieve in an [`Exercise`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Goal {
+    /// Checkmate the engine within the move budget.
+    Win,
+    /// Draw, or don't get checkmated before the move budget runs out.
+    /// Winning meets it too.
+    Draw,
+}
+
+/// A training position with a goal and a move budget.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Exercise {
+    pub name: &'static str,
+    /// Starting position; the player plays the side to move.
+    pub fen: &'static str,
+    pub goal: Goal,
+    /// Number of moves the player has to reach the goal, or hold out for.
+    pub moves: u32,
+}
+
+/// Built-in endgame exercises.
+pub const EXERCISES: [Exercise; 4] = [
+    Exercise {
+        name: "Queen vs king: checkmate",
+        fen: "8/8/8/4k3/8/8/8/4K2Q w - - 0 1",
+        goal: Goal::Win,
+        moves: 10,
+    },
+    Exercise {
+        name: "Rook vs king: checkmate",
+        fen: "8/8/8/4k3/8/8/8/4K2R w - - 0 1",
+        goal: Goal::Win,
+        moves: 20,
+    },
+    Exercise {
+        name: "Lucena position: build a bridge and win",
+        fen: "1K1k4/1P6/8/8/8/8/r7/2R5 w - - 0 1",
+        goal: Goal::Win,
+        moves: 30,
+    },
+    Exercise {
+        name: "Philidor position: hold the draw",
+        fen: "3k4/7R/r7/3PK3/8/8/8/8 b - - 0 1",
+        goal: Goal::Draw,
+        moves: 20,
+    },
+];
+
+/// Outcome of an exercise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    Passed,
+    Failed,
+}
+
+/// An exercise in progress, with the engine defending at full strength.
+pub struct Trainer {
+    exercise: Exercise,
+    player: Color,
+    game: Game,
+}
+
+impl Trainer {
+    pub fn new(exercise: Exercise) -> Self {
+        let board = Board::from_str(exercise.fen).unwrap();
+        let player = board.side_to_move();
+        Self {
+            exercise,
+            player,
+            game: Game::new_single(player, Difficulty::Hard).with_position(board),
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    /// Returns the color the player trains with.
+    pub fn player(&self) -> Color {
+        self.player
+    }
+
+    /// Returns how many moves the player has left.
+    pub fn moves_left(&self) -> u32 {
+        let played = self.game.moves().len().div_ceil(2) as u32;
+        self.exercise.moves.saturating_sub(played)
+    }
+
+    /// Plays the player's move.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the move is invalid or illegal.
+    pub fn play(&mut self, input: &str) -> Result<(), ChessError> {
+        self.game.make_move_from_str(input, false)
+    }
+
+    /// Plays the engine's reply.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it's the player's turn or the game is over.
+    pub fn reply(&mut self) -> Result<(), ChessError> {
+        let mv = self.game.get_ai_move()?;
+        self.game.make_move(mv)
+    }
+
+    /// Returns the verdict once the exercise is over, `None` while it goes on.
+    ///
+    /// The exercise is over when the game ends, when it's drawn by
+    /// threefold repetition, the fifty-move rule or insufficient material,
+    /// or, on the player's turn, when the move budget is spent. A win
+    /// meets either goal.
+    pub fn verdict(&self) -> Option<Verdict> {
+        let reached = match self.game.status() {
+            Status::Checkmate(winner)
+            | Status::VariantWin(winner)
+            | Status::Resignation(winner)
+            | Status::Timeout(winner) => Some(winner == self.player),
+            Status::Stalemate | Status::DrawAgreed | Status::TimeoutDraw(_) => {
+                Some(self.exercise.goal == Goal::Draw)
+            }
+            Status::Ongoing if self.drawn() => Some(self.exercise.goal == Goal::Draw),
+            Status::Ongoing if self.game.turn() == self.player && self.moves_left() == 0 => {
+                Some(self.exercise.goal == Goal::Draw)
+            }
+            Status::Ongoing => None,
+        };
+        reached.map(|passed| {
+            if passed {
+                Verdict::Passed
+            } else {
+                Verdict::Failed
+            }
+        })
+    }
+
+    /// Returns whether the game is drawn by rule though it could go on.
+    fn drawn(&self) -> bool {
+        self.game.repetitions() >= 3
+            || self.game.halfmove_clock() >= 100
+            || insufficient_material(self.game.board())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exercises_are_valid() {
+        for exercise in EXERCISES {
+            let trainer = Trainer::new(exercise);
+            assert_eq!(trainer.game().status(), Status::Ongoing);
+            assert_eq!(trainer.moves_left(), exercise.moves);
+        }
+    }
+
+    #[test]
+    fn checkmate_within_budget_passes() {
+        let mut trainer = Trainer::new(Exercise {
+            name: "Mate in two",
+            fen: "k7/8/1K6/8/8/8/8/7R w - - 0 1",
+            goal: Goal::Win,
+            moves: 2,
+        });
+        trainer.play("Kb7").unwrap_err();
+        trainer.play("Rh7").unwrap();
+        assert_eq!(trainer.verdict(), None);
+        trainer.reply().unwrap();
+        assert_eq!(trainer.moves_left(), 1);
+        // The only reply is Kb8, allowing Rh8#
+        trainer.play("Rh8").unwrap();
+        assert_eq!(trainer.verdict(), Some(Verdict::Passed));
+    }
+
+    #[test]
+    fn budget_decides_the_result() {
+        let exercise = Exercise {
+            name: "Hold out",
+            fen: "k7/8/1K6/8/8/8/8/7R b - - 0 1",
+            goal: Goal::Draw,
+            moves: 1,
+        };
+        let mut trainer = Trainer::new(exercise);
+        trainer.play("Kb8").unwrap();
+        trainer.reply().unwrap();
+        assert_eq!(trainer.game().status(), Status::Checkmate(Color::White));
+        assert_eq!(trainer.verdict(), Some(Verdict::Failed));
+
+        let mut trainer = Trainer::new(Exercise {
+            goal: Goal::Win,
+            fen: "k7/8/1K6/8/8/8/8/7R w - - 0 1",
+            ..exercise
+        });
+        trainer.play("Rh2").unwrap();
+        trainer.reply().unwrap();
+        assert_eq!(trainer.verdict(), Some(Verdict::Failed));
+    }
+
+    #[test]
+    fn draws_and_wins_meet_a_draw_goal() {
+        let exercise = Exercise {
+            name: "Take the rook",
+            fen: "8/8/8/4k3/8/8/1r6/K7 w - - 0 1",
+            goal: Goal::Draw,
+            moves: 5,
+        };
+        let mut trainer = Trainer::new(exercise);
+        trainer.play("Kxb2").unwrap();
+        assert_eq!(trainer.verdict(), Some(Verdict::Passed));
+
+        let mut trainer = Trainer::new(Exercise {
+            goal: Goal::Win,
+            ..exercise
+        });
+        trainer.play("Kxb2").unwrap();
+        assert_eq!(trainer.verdict(), Some(Verdict::Failed));
+
+        let mut trainer = Trainer::new(Exercise {
+            fen: "k7/8/1K6/8/8/8/8/7R w - - 0 1",
+            ..exercise
+        });
+        trainer.play("Rh7").unwrap();
+        trainer.reply().unwrap();
+        trainer.play("Rh8").unwrap();
+        assert_eq!(trainer.verdict(), Some(Verdict::Passed));
+    }
+}
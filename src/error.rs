@@ -0,0 +1,74 @@
+use crate::legality::{IllegalMoveReason, PositionError};
+use chess::Square;
+use std::fmt;
+
+/// Errors returned by the [`Game`](crate::game::Game) API.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChessError {
+    /// The input is not a move in the expected UCI format.
+    InvalidFormat,
+    /// The input can't be read as a move.
+    InvalidInput,
+    /// The input names a move that is not legal in the current position.
+    IllegalMove(IllegalMoveReason),
+    /// The position can't come up in a game.
+    InvalidPosition(PositionError),
+    /// There are no moves to undo.
+    NothingToUndo,
+    /// A takeback has already been requested and is waiting for an answer.
+    TakebackPending,
+    /// There is no takeback request to answer.
+    NoTakebackRequested,
+    /// A draw has already been offered and is waiting for an answer.
+    DrawOfferPending,
+    /// There is no draw offer to answer.
+    NoDrawOffered,
+    /// The game is already over.
+    GameOver,
+    /// The AI was asked to move in a two-player game.
+    AiUnavailable,
+    /// The AI was asked to move on the player's turn.
+    NotAiTurn,
+    /// The AI has no legal moves to choose from.
+    NoLegalMoves,
+    /// The selected piece has no legal moves.
+    NoMovesFrom(Square),
+    /// A destination was given without selecting a piece first.
+    NothingSelected,
+    /// A simul has no board with the given number.
+    NoSuchBoard(usize),
+    /// A path puzzle's piece can't stop on the square, as an enemy
+    /// piece stands on or attacks it.
+    UnsafeSquare(Square),
+    /// The current line of the game has fewer moves than the given ply.
+    NoSuchPly(usize),
+}
+
+impl fmt::Display for ChessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChessError::InvalidFormat => write!(f, "Invalid input format!"),
+            ChessError::InvalidInput => write!(f, "Invalid input!"),
+            ChessError::IllegalMove(reason) => write!(f, "Illegal move: {}!", reason),
+            ChessError::InvalidPosition(reason) => write!(f, "Invalid position: {}!", reason),
+            ChessError::NothingToUndo => write!(f, "No moves to undo!"),
+            ChessError::TakebackPending => write!(f, "A takeback has already been requested!"),
+            ChessError::NoTakebackRequested => write!(f, "No takeback has been requested!"),
+            ChessError::DrawOfferPending => write!(f, "A draw has already been offered!"),
+            ChessError::NoDrawOffered => write!(f, "No draw has been offered!"),
+            ChessError::GameOver => write!(f, "The game is over!"),
+            ChessError::AiUnavailable => write!(f, "AI can only be used in single player mode"),
+            ChessError::NotAiTurn => write!(f, "It's not the AI's turn"),
+            ChessError::NoLegalMoves => write!(f, "No legal moves for AI available"),
+            ChessError::NoMovesFrom(square) => {
+                write!(f, "The piece on {} has no legal moves!", square)
+            }
+            ChessError::NothingSelected => write!(f, "No piece selected!"),
+            ChessError::NoSuchBoard(number) => write!(f, "There's no board {}!", number),
+            ChessError::UnsafeSquare(square) => write!(f, "{} is not safe!", square),
+            ChessError::NoSuchPly(ply) => write!(f, "The line has no move {}!", ply),
+        }
+    }
+}
+
+impl std::error::Error for ChessError {}
@@ -0,0 +1,359 @@
+use crate::error::ChessError;
+use crate::game::AiPlayer;
+use crate::input::{read_ahead, read_line, take_line};
+use crate::network::{Connection, Message};
+use crate::rng::{fork_seed, set_seed};
+use chess::ChessMove;
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a line typed in the background is looked for while waiting
+/// for other events.
+const INPUT_POLL: Duration = Duration::from_millis(20);
+
+/// Something a game loop reacts to.
+#[derive(Debug)]
+pub enum Event {
+    /// The user entered a line.
+    Input(String),
+    /// The AI chose its move, with its score, see
+    /// [`Game::get_scored_ai_move`](crate::game::Game::get_scored_ai_move).
+    AiMove(Result<(ChessMove, Option<i32>), ChessError>),
+    /// The clock ticked, see [`EventLoop::start_ticker`].
+    Tick,
+    /// The opponent in a network game sent a message, or disconnected
+    /// if `None`, see [`EventLoop::next_or_message`].
+    Message(Option<Message>),
+    /// The input ended, e.g. the user pressed Ctrl-D, while other events
+    /// were waited for too, see [`take_line`].
+    Closed,
+}
+
+/// What the threads of an event loop send it: an event, or the move of
+/// the AI's search with the given number, see [`EventLoop::think`].
+enum Sent {
+    Event(Event),
+    Thought(u64, Result<(ChessMove, Option<i32>), ChessError>),
+}
+
+/// Gathers the events of a game as they happen: the user's input, read
+/// on its own thread, the AI's move, searched on another, the messages
+/// of a network opponent and the ticks of a clock. A game loop reacts to
+/// whichever comes first, so it stays responsive while the AI thinks,
+/// and a flag falls while the user is still typing.
+///
+/// The loop also keeps track of whether the game changed since it was
+/// last drawn, so it's drawn once per change, see [`EventLoop::render`].
+pub struct EventLoop {
+    sender: Sender<Sent>,
+    receiver: Receiver<Sent>,
+    /// Whether the input is read from the user, rather than given to
+    /// [`EventLoop::with_input`].
+    stdin: bool,
+    /// The number of the AI's latest search; the moves of earlier ones
+    /// are dropped, see [`EventLoop::discard_thinking`].
+    thought: u64,
+    /// Whether a ticker was started.
+    ticking: bool,
+    /// Whether a line is being read in the background.
+    reading: bool,
+    /// Whether the prompt for the line being read was shown.
+    prompted: bool,
+    /// Whether the game changed since it was last drawn.
+    stale: bool,
+}
+
+impl EventLoop {
+    /// Creates an event loop with no event sources running.
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            stdin: true,
+            thought: 0,
+            ticking: false,
+            reading: false,
+            prompted: false,
+            stale: true,
+        }
+    }
+
+    /// Creates an event loop whose input is the lines sent on `lines`,
+    /// rather than those the user types. An [`Event::Closed`] follows
+    /// the last one, once every sender is dropped. The lines come in as
+    /// they're sent, whether or not the loop [listens](EventLoop::listen).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let (typed, lines) = mpsc::channel();
+    /// let mut events = EventLoop::with_input(lines);
+    /// typed.send("e4".to_string())?;
+    /// drop(typed);
+    /// assert!(matches!(events.next(), Event::Input(line) if line == "e4"));
+    /// assert!(matches!(events.next(), Event::Closed));
+    /// ```
+    #[cfg(test)]
+    pub fn with_input(lines: Receiver<String>) -> Self {
+        let mut events = Self::new();
+        let sender = events.sender.clone();
+        thread::spawn(move || {
+            for line in lines {
+                if sender.send(Sent::Event(Event::Input(line))).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(Sent::Event(Event::Closed));
+        });
+        events.stdin = false;
+        events
+    }
+
+    /// Marks the game as changed, so the next [`EventLoop::render`]
+    /// draws it again, followed by the prompt.
+    pub fn changed(&mut self) {
+        self.stale = true;
+        self.prompted = false;
+    }
+
+    /// Draws the game with `draw` if it changed since it was last drawn.
+    pub fn render(&mut self, draw: impl FnOnce()) {
+        if self.stale {
+            draw();
+            self.stale = false;
+        }
+    }
+
+    /// Starts sending an [`Event::Tick`] every `interval`, until the
+    /// event loop is dropped.
+    pub fn start_ticker(&mut self, interval: Duration) {
+        let sender = self.sender.clone();
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if sender.send(Sent::Event(Event::Tick)).is_err() {
+                break;
+            }
+        });
+        self.ticking = true;
+    }
+
+    /// Starts `ai` thinking on its own thread. Its move is sent as an
+    /// [`Event::AiMove`] once it has taken at least `min_time`, so it
+    /// isn't played instantly. A search started earlier is discarded,
+    /// see [`EventLoop::discard_thinking`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// events.think(game.ai_player()?, Duration::from_secs(1));
+    /// if let Event::AiMove(Ok((mv, _))) = events.next() {
+    ///     game.make_move(mv)?;
+    /// }
+    /// ```
+    pub fn think(&mut self, ai: AiPlayer, min_time: Duration) {
+        self.thought += 1;
+        let thought = self.thought;
+        let sender = self.sender.clone();
+        let seed = fork_seed();
+        thread::spawn(move || {
+            if let Some(seed) = seed {
+                set_seed(seed);
+            }
+            let started = Instant::now();
+            let result = ai.choose();
+            thread::sleep(min_time.saturating_sub(started.elapsed()));
+            let _ = sender.send(Sent::Thought(thought, result));
+        });
+    }
+
+    /// Drops the move of the AI's search in progress, once it's found,
+    /// e.g. after the position it searched was undone. The search itself
+    /// runs to its end on its own thread.
+    pub fn discard_thinking(&mut self) {
+        self.thought += 1;
+    }
+
+    /// Starts reading a line in the background, sent as an
+    /// [`Event::Input`] once entered, e.g. a premove while the AI thinks.
+    /// Nothing is read twice: a line already being read, or taken by a
+    /// question asked meanwhile, see [`read_line`], is waited for.
+    pub fn listen(&mut self) {
+        if self.stdin {
+            read_ahead();
+        }
+        self.reading = true;
+    }
+
+    /// Shows `prompt`, unless it's shown already, and waits for the
+    /// user's input.
+    ///
+    /// Without a ticker nothing else happens meanwhile, so the line is
+    /// read with line editing, see [`read_line`]; otherwise it's read in
+    /// the background, and ticks come in while the user types.
+    ///
+    /// # Returns
+    ///
+    /// The [`Event::Input`] with the line, or another event that came
+    /// first.
+    pub fn input(&mut self, prompt: &str) -> Event {
+        if !self.ticking && self.stdin {
+            self.reading = false;
+            return Event::Input(read_line(prompt));
+        }
+        self.show_prompt(prompt);
+        self.next()
+    }
+
+    /// Shows `prompt`, unless it's shown already, and waits for the
+    /// user's input or a message from the opponent on `connection`. The
+    /// line is read in the background, so messages come in while the
+    /// user types.
+    ///
+    /// # Returns
+    ///
+    /// The [`Event::Input`] with the line, or another event that came
+    /// first.
+    pub fn input_or_message(&mut self, prompt: &str, connection: &Connection) -> Event {
+        self.show_prompt(prompt);
+        self.next_or_message(connection)
+    }
+
+    /// Waits for the next event.
+    pub fn next(&mut self) -> Event {
+        self.wait(None)
+    }
+
+    /// Waits for the next event, or a message from the opponent on
+    /// `connection`.
+    pub fn next_or_message(&mut self, connection: &Connection) -> Event {
+        self.wait(Some(connection))
+    }
+
+    /// Shows `prompt`, unless it's shown already, and reads a line in the
+    /// background.
+    fn show_prompt(&mut self, prompt: &str) {
+        if !self.prompted {
+            print!("{}", prompt);
+            let _ = io::stdout().flush();
+            self.prompted = true;
+        }
+        self.listen();
+    }
+
+    fn wait(&mut self, connection: Option<&Connection>) -> Event {
+        loop {
+            if self.reading && self.stdin {
+                if let Some(line) = take_line() {
+                    self.reading = false;
+                    self.prompted = false;
+                    return line.map_or(Event::Closed, Event::Input);
+                }
+            }
+            match connection.map(Connection::try_receive) {
+                Some(Ok(message)) => return Event::Message(Some(message)),
+                Some(Err(TryRecvError::Disconnected)) => return Event::Message(None),
+                Some(Err(TryRecvError::Empty)) | None => (),
+            }
+            match self.receiver.recv_timeout(INPUT_POLL) {
+                Ok(Sent::Event(event)) => {
+                    if let Event::Input(_) = event {
+                        self.reading = false;
+                        self.prompted = false;
+                    }
+                    return event;
+                }
+                Ok(Sent::Thought(thought, result)) if thought == self.thought => {
+                    return Event::AiMove(result)
+                }
+                // A search discarded since.
+                Ok(Sent::Thought(..)) => (),
+                Err(RecvTimeoutError::Timeout) => (),
+                // The loop holds a sender itself.
+                Err(RecvTimeoutError::Disconnected) => unreachable!(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::game::Game;
+    use chess::Color;
+
+    #[test]
+    fn ticks_while_the_ai_thinks() {
+        let game = Game::new_single(Color::Black, Difficulty::Easy);
+        let mut events = EventLoop::new();
+        events.start_ticker(Duration::from_millis(5));
+        events.think(game.ai_player().unwrap(), Duration::from_millis(60));
+        let mut ticks = 0;
+        let mv = loop {
+            match events.next() {
+                Event::Tick => ticks += 1,
+                Event::AiMove(result) => break result.unwrap().0,
+                event => panic!("unexpected {:?}", event),
+            }
+        };
+        assert!(game.board().legal(mv));
+        assert!(ticks > 1);
+    }
+
+    #[test]
+    fn delivers_input_and_ai_moves_in_order() {
+        let game = Game::new_single(Color::Black, Difficulty::Easy);
+        let (typed, lines) = mpsc::channel();
+        let mut events = EventLoop::with_input(lines);
+        typed.send("e4".to_string()).unwrap();
+        typed.send("Nf3".to_string()).unwrap();
+        events.think(game.ai_player().unwrap(), Duration::from_millis(50));
+        assert!(matches!(events.next(), Event::Input(line) if line == "e4"));
+        assert!(matches!(events.next(), Event::Input(line) if line == "Nf3"));
+        assert!(matches!(events.next(), Event::AiMove(Ok(_))));
+        typed.send("quit".to_string()).unwrap();
+        assert!(matches!(events.input("> "), Event::Input(line) if line == "quit"));
+    }
+
+    #[test]
+    fn drops_moves_of_discarded_searches() {
+        let mut game = Game::new_single(Color::White, Difficulty::Easy);
+        let (_typed, lines) = mpsc::channel();
+        let mut events = EventLoop::with_input(lines);
+        // The player's move is taken back while the AI thinks about it.
+        game.make_move_from_str("e4", false).unwrap();
+        events.think(game.ai_player().unwrap(), Duration::ZERO);
+        game.undo().unwrap();
+        events.discard_thinking();
+        // A new game starts while the AI thinks, and it thinks afresh.
+        game.make_move_from_str("d4", false).unwrap();
+        events.think(game.ai_player().unwrap(), Duration::ZERO);
+        game.reset();
+        game.make_move_from_str("c4", false).unwrap();
+        let started = Instant::now();
+        events.think(game.ai_player().unwrap(), Duration::from_millis(100));
+        let Event::AiMove(Ok((mv, _))) = events.next() else {
+            panic!("expected the AI's move");
+        };
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert!(game.board().legal(mv));
+        events.start_ticker(Duration::from_millis(5));
+        // Nothing else comes in but ticks.
+        for _ in 0..20 {
+            assert!(matches!(events.next(), Event::Tick));
+        }
+    }
+
+    #[test]
+    fn ends_waiting_when_the_input_closes() {
+        let (typed, lines) = mpsc::channel();
+        let mut events = EventLoop::with_input(lines);
+        typed.send("resign".to_string()).unwrap();
+        drop(typed);
+        assert!(matches!(events.input("> "), Event::Input(line) if line == "resign"));
+        assert!(matches!(events.input("> "), Event::Closed));
+    }
+}
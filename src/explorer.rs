@@ -0,0 +1,261 @@
+use crate::archive::starting_board;
+use crate::notation::{parse_san, san_for_move};
+use crate::pgn::PgnGame;
+use crate::repertoire::OpeningTree;
+use chess::{Board, ChessMove};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Results of the games in which a move was played.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MoveStats {
+    pub games: u32,
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+}
+
+impl MoveStats {
+    fn add(&mut self, result: Option<&str>) {
+        self.games += 1;
+        match result {
+            Some("1-0") => self.white_wins += 1,
+            Some("1/2-1/2") => self.draws += 1,
+            Some("0-1") => self.black_wins += 1,
+            _ => (),
+        }
+    }
+
+    /// Returns the percentages of White wins, draws and Black wins
+    /// among the games. Unfinished games count towards none of them.
+    pub fn percentages(&self) -> [f64; 3] {
+        [self.white_wins, self.draws, self.black_wins]
+            .map(|count| 100.0 * f64::from(count) / f64::from(self.games.max(1)))
+    }
+}
+
+/// The moves played from each position of a PGN database, looked up by
+/// the position's Zobrist hash, so games transposing into a position
+/// are counted together.
+#[derive(Clone, Debug, Default)]
+pub struct PositionIndex {
+    positions: HashMap<u64, Vec<(ChessMove, MoveStats)>>,
+}
+
+impl PositionIndex {
+    /// Indexes the positions of all `games`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let index = PositionIndex::new(&parse_pgn("1. e4 e5 1-0\n\n1. d4 d5 0-1\n"));
+    /// assert_eq!(index.moves(&Board::default()).len(), 2);
+    /// ```
+    pub fn new(games: &[PgnGame]) -> Self {
+        let mut index = Self::default();
+        for game in games {
+            index.add(game);
+        }
+        index
+    }
+
+    /// Adds the moves of `game`, replayed from its `FEN` tag if it has
+    /// one, up to its first illegal move if any. A move played more than
+    /// once from the same position in a game, e.g. when repeating moves,
+    /// is counted once.
+    fn add(&mut self, game: &PgnGame) {
+        let Some(mut board) = starting_board(game) else {
+            return;
+        };
+        let result = game.result();
+        let mut seen = HashSet::new();
+        for text in &game.moves {
+            let Some(mv) = parse_san(&board, text) else {
+                break;
+            };
+            let hash = board.get_hash();
+            if seen.insert((hash, mv)) {
+                let moves = self.positions.entry(hash).or_default();
+                match moves.iter_mut().find(|(played, _)| *played == mv) {
+                    Some((_, stats)) => stats.add(result),
+                    None => {
+                        let mut stats = MoveStats::default();
+                        stats.add(result);
+                        moves.push((mv, stats));
+                    }
+                }
+            }
+            board = board.make_move_new(mv);
+        }
+    }
+
+    /// Returns the moves played from `position` with their results,
+    /// most played first.
+    pub fn moves(&self, position: &Board) -> Vec<(ChessMove, MoveStats)> {
+        let mut moves = self
+            .positions
+            .get(&position.get_hash())
+            .cloned()
+            .unwrap_or_default();
+        moves.sort_by_key(|(_, stats)| Reverse(stats.games));
+        moves
+    }
+
+    /// Lists the moves played from `position` in a table, with their
+    /// number of games and the percentages of each result.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let index = PositionIndex::new(&parse_pgn("1. e4 e5 1-0\n"));
+    /// print!("{}", index.report(&Board::default()));
+    /// ```
+    pub fn report(&self, position: &Board) -> String {
+        let moves = self.moves(position);
+        if moves.is_empty() {
+            return "No games reach this position\n".to_string();
+        }
+        let mut report = format!(
+            "{:<8}{:>6}{:>8}{:>8}{:>8}\n",
+            "Move", "Games", "White", "Draw", "Black"
+        );
+        for (mv, stats) in moves {
+            let [white, draw, black] = stats.percentages();
+            report.push_str(&format!(
+                "{:<8}{:>6}{:>7.0}%{:>7.0}%{:>7.0}%\n",
+                san_for_move(position, mv),
+                stats.games,
+                white,
+                draw,
+                black
+            ));
+        }
+        report
+    }
+}
+
+/// Known opening theory: the lines of an opening book and the moves
+/// played in a database of games.
+#[derive(Clone, Debug, Default)]
+pub struct Theory {
+    book: OpeningTree,
+    index: PositionIndex,
+}
+
+impl Theory {
+    pub fn new(book: OpeningTree, index: PositionIndex) -> Self {
+        Self { book, index }
+    }
+
+    /// Returns the moves known from `position`, in the book or the
+    /// database.
+    fn known_moves(&self, position: &Board) -> Vec<ChessMove> {
+        let mut moves = self.book.book_moves(position).to_vec();
+        moves.extend(self.index.moves(position).into_iter().map(|(mv, _)| mv));
+        moves
+    }
+
+    /// Finds the novelty among `moves`, played from `start`: the first
+    /// move that leaves theory, played in a known position none of the
+    /// known moves of which was chosen.
+    ///
+    /// # Returns
+    ///
+    /// The number of half-moves played before the novelty, `None` if the
+    /// game stays in theory, reaches the end of it or doesn't start in
+    /// it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let theory = Theory::new(OpeningTree::default(), PositionIndex::new(&games));
+    /// if let Some(ply) = theory.novelty(&Board::default(), game.moves()) {
+    ///     println!("Novelty on move {}", ply / 2 + 1);
+    /// }
+    /// ```
+    pub fn novelty(&self, start: &Board, moves: &[ChessMove]) -> Option<usize> {
+        let mut board = *start;
+        for (ply, mv) in moves.iter().enumerate() {
+            let known = self.known_moves(&board);
+            if known.is_empty() {
+                return None;
+            }
+            if !known.contains(mv) {
+                return Some(ply);
+            }
+            board = board.make_move_new(*mv);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::parse_pgn;
+    use std::str::FromStr;
+
+    #[test]
+    fn counts_moves_and_results() {
+        let index = PositionIndex::new(&parse_pgn(
+            "1. e4 e5 2. Nf3 Nc6 1-0\n\n\
+             1. Nf3 Nc6 2. e4 e5 3. Bb5 1/2-1/2\n\n\
+             1. e4 c5 0-1\n\n\
+             1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 *\n",
+        ));
+        let start = index.moves(&Board::default());
+        assert_eq!(start.len(), 2);
+        assert_eq!(start[0].0, ChessMove::from_str("e2e4").unwrap());
+        assert_eq!(
+            start[0].1,
+            MoveStats {
+                games: 2,
+                white_wins: 1,
+                draws: 0,
+                black_wins: 1
+            }
+        );
+        // The last game plays Nf3 from the starting position twice.
+        assert_eq!(start[1].1.games, 2);
+
+        // Both of the first two games reach the position after 2. Nf3 Nc6.
+        let transposed =
+            Board::from_str("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let report = index.report(&transposed);
+        assert_eq!(
+            report.lines().nth(1),
+            Some("Bb5          1      0%    100%      0%")
+        );
+        assert_eq!(
+            index.report(&Board::from_str("8/8/8/4k3/8/8/8/4K3 w - - 0 1").unwrap()),
+            "No games reach this position\n"
+        );
+    }
+
+    #[test]
+    fn finds_novelties() {
+        let book = OpeningTree::from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 *").unwrap();
+        let index = PositionIndex::new(&parse_pgn("1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. c3 *\n"));
+        let theory = Theory::new(book, index);
+        let replay = |moves: &[&str]| {
+            let mut board = Board::default();
+            let mut played = Vec::new();
+            for text in moves {
+                let mv = parse_san(&board, text).unwrap();
+                board = board.make_move_new(mv);
+                played.push(mv);
+            }
+            played
+        };
+        let start = Board::default();
+        // The archive knows 3. Bc4 Bc5, the book doesn't.
+        let moves = replay(&["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5", "d3"]);
+        assert_eq!(theory.novelty(&start, &moves), Some(6));
+        // Out of theory after 3. Bb5, but the game didn't leave it.
+        let moves = replay(&["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert_eq!(theory.novelty(&start, &moves), None);
+        assert_eq!(theory.novelty(&start, &replay(&["d4"])), Some(0));
+        assert_eq!(Theory::default().novelty(&start, &moves), None);
+    }
+}
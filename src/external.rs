@@ -0,0 +1,214 @@
+use crate::ai::MATE_SCORE;
+use crate::game::Game;
+use chess::ChessMove;
+use log::debug;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// An engine running as a separate program, spoken to over UCI.
+///
+/// The engine is started by [`ExternalEngine::start`] and told to quit
+/// when dropped.
+pub struct ExternalEngine {
+    process: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl ExternalEngine {
+    /// Starts the engine and waits until it's ready to search.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The engine's program followed by its arguments,
+    ///   separated by spaces, e.g. `stockfish` or `rChess uci`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program can't be started or quits before
+    /// answering `uci` and `isready`.
+    pub fn start(command: &str) -> io::Result<Self> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no engine command"))?;
+        let mut process = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let input = process.stdin.take().expect("stdin is piped");
+        let output = BufReader::new(process.stdout.take().expect("stdout is piped"));
+        let mut engine = Self {
+            process,
+            input,
+            output,
+        };
+        engine.send("uci")?;
+        engine.wait_for("uciok")?;
+        engine.send("ucinewgame")?;
+        engine.send("isready")?;
+        engine.wait_for("readyok")?;
+        Ok(engine)
+    }
+
+    /// Sends the engine a command.
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        debug!("To engine: {}", command);
+        writeln!(self.input, "{}", command)?;
+        self.input.flush()
+    }
+
+    /// Reads the engine's output up to a line starting with `word`,
+    /// returning that line and the ones before it.
+    fn wait_for(&mut self, word: &str) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.output.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("the engine quit before `{}`", word),
+                ));
+            }
+            let line = line.trim().to_string();
+            debug!("From engine: {}", line);
+            let found = line.split_whitespace().next() == Some(word);
+            lines.push(line);
+            if found {
+                return Ok(lines);
+            }
+        }
+    }
+
+    /// Searches the current position of `game` for the side to move.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - How deep the engine searches without a time per move.
+    /// * `move_time` - How long the engine searches.
+    ///
+    /// # Returns
+    ///
+    /// The move with the last score the engine reported, in centipawns
+    /// for the side to move, or `None` if the engine found no move.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine quits or plays an illegal move.
+    pub fn search(
+        &mut self,
+        game: &Game,
+        depth: u32,
+        move_time: Option<Duration>,
+    ) -> io::Result<Option<(ChessMove, Option<i32>)>> {
+        let mut position = format!("position fen {}", game.starting_position());
+        if !game.moves().is_empty() {
+            position.push_str(" moves");
+            for mv in game.moves() {
+                position.push_str(&format!(" {}", mv));
+            }
+        }
+        self.send(&position)?;
+        match move_time {
+            Some(time) => self.send(&format!("go movetime {}", time.as_millis()))?,
+            None => self.send(&format!("go depth {}", depth))?,
+        }
+        let lines = self.wait_for("bestmove")?;
+        let score = lines.iter().rev().find_map(|line| read_score(line));
+        let best = lines.last().and_then(|line| line.split_whitespace().nth(1));
+        match best {
+            None | Some("(none)") | Some("0000") => Ok(None),
+            Some(text) => ChessMove::from_str(text)
+                .ok()
+                .filter(|mv| game.board().legal(*mv))
+                .map(|mv| Some((mv, score)))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("the engine played the illegal move `{}`", text),
+                    )
+                }),
+        }
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// Reads the score of a UCI `info` line in centipawns, with mates as
+/// [`MATE_SCORE`] less the number of moves to them.
+fn read_score(line: &str) -> Option<i32> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("info") {
+        return None;
+    }
+    // Whatever follows `string` is free text.
+    if words.find(|word| *word == "score" || *word == "string")? == "string" {
+        return None;
+    }
+    let kind = words.next()?;
+    let value: i32 = words.next()?.parse().ok()?;
+    match kind {
+        "cp" => Some(value),
+        "mate" => Some(value.signum() * (MATE_SCORE - value.abs())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::testing::TempDir;
+
+    #[test]
+    fn reads_scores() {
+        assert_eq!(
+            read_score("info depth 5 score cp -31 nodes 900 pv e2e4"),
+            Some(-31)
+        );
+        assert_eq!(
+            read_score("info depth 9 score mate 3 pv h5f7"),
+            Some(MATE_SCORE - 3)
+        );
+        assert_eq!(read_score("info string score cp 5"), None);
+        assert_eq!(read_score("bestmove e2e4"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn plays_over_uci() {
+        let dir = TempDir::new("engine");
+        let path = dir.join("engine.sh");
+        std::fs::write(
+            &path,
+            "while read command rest; do case $command in\n\
+             uci) echo id name Script; echo uciok;;\n\
+             isready) echo readyok;;\n\
+             go) echo info depth 1 score cp 12; echo bestmove e7e5;;\n\
+             quit) exit;;\n\
+             esac; done\n",
+        )
+        .unwrap();
+        let mut engine = ExternalEngine::start(&format!("sh {}", path.display())).unwrap();
+        let mut game = Game::new_multi();
+        game.make_move_from_str("e4", false).unwrap();
+        let (mv, score) = engine.search(&game, 1, None).unwrap().unwrap();
+        assert_eq!(mv.to_string(), "e7e5");
+        assert_eq!(score, Some(12));
+        game.make_move(mv).unwrap();
+        // The script doesn't look at the position, so its reply is illegal now.
+        assert!(engine.search(&game, 1, None).is_err());
+        drop(engine);
+        assert!(ExternalEngine::start("/nonexistent/engine").is_err());
+    }
+}
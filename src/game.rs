@@ -1,15 +1,75 @@
-use crate::ai::minimax;
-use chess::{Board, BoardStatus, ChessMove, Color};
+use crate::ai::{
+    baseline_move, score_moves, Baseline, BlunderModel, Difficulty, EngineKind, EngineOptions,
+    PositionHistory, SearchLimits,
+};
+use crate::annotation::Annotations;
+use crate::book::book_move;
+use crate::error::ChessError;
+use crate::legality::{
+    explain_illegal_move, explain_illegal_san, validate_position, IllegalMoveReason, PositionError,
+};
+use crate::notation::{fen_with_counters, parse_descriptive, parse_long_algebraic};
+use crate::personality::Personality;
+use crate::pgn::GameTags;
+use crate::practical::{practical_move, Outlook};
+use crate::svg::{render_board, SvgOptions};
+use crate::tt::{SharedTable, TranspositionTable};
+use crate::variant::{Variant, VariantState};
+use crate::variation::MoveTree;
+use chess::{Board, BoardStatus, ChessMove, Color, MoveGen, Piece, Rank, Square, EMPTY};
+use log::{debug, info};
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 /// Represents the status of the game.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Status {
     Ongoing,
     Checkmate(Color),
     Stalemate,
+    /// The given color won by the extra win condition of the game's variant.
+    VariantWin(Color),
+    /// The given color won because the other side resigned.
+    Resignation(Color),
+    /// The given color won because the other side ran out of time.
+    Timeout(Color),
+    /// The given color ran out of time, but the other side has no
+    /// material left to checkmate with, a draw.
+    TimeoutDraw(Color),
+    /// The players agreed to a draw.
+    DrawAgreed,
 }
 
+/// The status of the game along with whether the side to move is in
+/// check, so front-ends can announce both without looking at the board.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameState {
+    pub status: Status,
+    /// Whether the side to move is in check, also when it's mated.
+    pub is_check: bool,
+}
+
+/// A change in the game state reported to listeners added with [`Game::subscribe`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    /// The given color played a move.
+    MoveMade(Color, ChessMove),
+    /// The given move was taken back.
+    MoveUndone(ChessMove),
+    /// The given color gave check.
+    CheckGiven(Color),
+    /// The game ended with the given status.
+    GameEnded(Status),
+    /// A new game started from the position the last one started from,
+    /// see [`Game::reset`].
+    Reset,
+    /// The players swapped colors for a rematch, see [`Game::rematch`].
+    ColorsSwapped,
+}
+
+/// A callback added with [`Game::subscribe`].
+type Listener = Box<dyn FnMut(&GameEvent) + Send>;
+
 /// Represents the game mode.
 pub enum GameMode {
     TwoPlayer,
@@ -18,14 +78,41 @@ pub enum GameMode {
 
 /// Represents a chess game state.
 ///
-/// Holds the current board state, current turn and move/board history.
+/// Holds the current board state, current turn and move/board history,
+/// along with the time spent on each move.
 pub struct Game {
     board: Board,
     turn: Color,
     game_mode: GameMode,
+    variant: Variant,
     recursion_depth: Option<u32>,
+    /// Most positions the AI searches for a move at its difficulty, on
+    /// top of the limits set with [`Game::with_limits`].
+    node_limit: Option<u64>,
+    baseline: Option<Baseline>,
+    blunder_model: Option<BlunderModel>,
+    engine_options: EngineOptions,
+    engine: EngineKind,
+    personality: Personality,
     history: Vec<(Board, Color)>,
     moves: Vec<ChessMove>,
+    move_times: Vec<Duration>,
+    move_started: Instant,
+    /// Every move played, with the variations branched off with
+    /// [`Game::branch`].
+    tree: MoveTree,
+    /// The child of the tree taken at each ply of the current line.
+    path: Vec<usize>,
+    takeback_request: Option<Color>,
+    draw_offer: Option<Color>,
+    /// How the game ended if the players ended it, by resigning or
+    /// agreeing to a draw.
+    ended: Option<Status>,
+    listeners: Vec<Listener>,
+    annotations: Annotations,
+    selection: Option<Square>,
+    /// The event and players' names the game is exported with.
+    tags: GameTags,
 }
 
 impl Game {
@@ -42,9 +129,27 @@ impl Game {
             board: Board::default(),
             turn: Color::White,
             game_mode: GameMode::TwoPlayer,
+            variant: Variant::Standard,
             recursion_depth: None,
+            node_limit: None,
+            baseline: None,
+            blunder_model: None,
+            engine_options: EngineOptions::default(),
+            engine: EngineKind::default(),
+            personality: Personality::default(),
             history: Vec::new(),
             moves: Vec::new(),
+            move_times: Vec::new(),
+            move_started: Instant::now(),
+            tree: MoveTree::default(),
+            path: Vec::new(),
+            takeback_request: None,
+            draw_offer: None,
+            ended: None,
+            listeners: Vec::new(),
+            annotations: Annotations::default(),
+            selection: None,
+            tags: GameTags::default(),
         }
     }
 
@@ -53,29 +158,191 @@ impl Game {
     /// # Arguments
     ///
     /// * player_color - a chess::Color instance representing the player color
-    /// * recursion_depth - An u32 representing the recursion depth for AI
+    /// * difficulty - a Difficulty setting the AI's search depth and engine options
     ///
     /// # Example
     ///
     /// ```
-    /// let game = Game::new_single(Color::White, 5);
+    /// let game = Game::new_single(Color::White, Difficulty::Medium);
     /// game.display_board();
     /// ```
-    pub fn new_single(player_color: Color, recursion_depth: u32) -> Self {
+    pub fn new_single(player_color: Color, difficulty: Difficulty) -> Self {
         Self {
             board: Board::default(),
             turn: Color::White,
             game_mode: GameMode::SinglePlayer(player_color),
-            recursion_depth: Some(recursion_depth),
+            variant: Variant::Standard,
+            recursion_depth: Some(difficulty.depth()),
+            node_limit: difficulty.node_limit(),
+            baseline: difficulty.baseline(),
+            blunder_model: difficulty.blunder_model(),
+            // The table is kept across the AI's moves, so each search
+            // starts from what the previous ones found.
+            engine_options: EngineOptions {
+                table: Some(SharedTable::default()),
+                ..difficulty.engine_options()
+            },
+            engine: EngineKind::default(),
+            personality: Personality::default(),
             history: Vec::new(),
             moves: Vec::new(),
+            move_times: Vec::new(),
+            move_started: Instant::now(),
+            tree: MoveTree::default(),
+            path: Vec::new(),
+            takeback_request: None,
+            draw_offer: None,
+            ended: None,
+            listeners: Vec::new(),
+            annotations: Annotations::default(),
+            selection: None,
+            tags: GameTags::default(),
+        }
+    }
+
+    /// Sets the kind of engine the AI searches with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let game = Game::new_single(Color::White, Difficulty::Medium).with_engine(EngineKind::Mcts);
+    /// ```
+    pub fn with_engine(mut self, engine: EngineKind) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Sets hard limits on the nodes, time and memory of every search the
+    /// AI runs for a move.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let limits = SearchLimits { nodes: Some(100_000), ..SearchLimits::default() };
+    /// let game = Game::new_single(Color::White, Difficulty::Hard).with_limits(limits);
+    /// ```
+    pub fn with_limits(mut self, limits: SearchLimits) -> Self {
+        self.engine_options.limits = limits;
+        self
+    }
+
+    /// Sets the size in megabytes of the transposition table the AI's
+    /// searches share over the game.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let game = Game::new_single(Color::White, Difficulty::Hard).with_hash(64);
+    /// ```
+    pub fn with_hash(mut self, megabytes: usize) -> Self {
+        if self.engine_options.table.is_some() {
+            self.engine_options.table = Some(SharedTable::new(TranspositionTable::new(megabytes)));
         }
+        self
+    }
+
+    /// Changes the limits of the AI's searches during the game, see
+    /// [`Game::with_limits`].
+    pub fn set_limits(&mut self, limits: SearchLimits) {
+        self.engine_options.limits = limits;
+    }
+
+    /// Returns the limits of the AI's searches.
+    pub fn limits(&self) -> SearchLimits {
+        self.engine_options.limits
+    }
+
+    /// Sets the event and players' names the game is exported with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut tags = GameTags::default();
+    /// tags.set("white", "Anna")?;
+    /// let game = Game::new_multi().with_tags(tags);
+    /// ```
+    pub fn with_tags(mut self, tags: GameTags) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Sets the playing style of the AI, adjusting its evaluation, setting
+    /// its contempt in place of the difficulty's and letting it follow the
+    /// openings it likes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let game = Game::new_single(Color::White, Difficulty::Medium)
+    ///     .with_personality(Personality::Aggressive);
+    /// ```
+    pub fn with_personality(mut self, personality: Personality) -> Self {
+        self.personality = personality;
+        self.engine_options.weights = personality.weights();
+        self.engine_options.contempt = personality.contempt();
+        self
+    }
+
+    /// Sets the variant the game is played with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let game = Game::new_multi().with_variant(Variant::ThreeCheck);
+    /// assert_eq!(game.variant(), Variant::ThreeCheck);
+    /// ```
+    pub fn with_variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Sets the position the game starts from, e.g. an odds setup.
+    ///
+    /// The side to move is taken from the board.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let board = Odds::PawnAndMove.starting_board(Color::White);
+    /// let game = Game::new_multi().with_position(board);
+    /// assert_eq!(game.turn(), Color::Black);
+    /// ```
+    pub fn with_position(mut self, board: Board) -> Self {
+        self.board = board;
+        self.turn = board.side_to_move();
+        self.history.clear();
+        self.moves.clear();
+        self.move_times.clear();
+        self.move_started = Instant::now();
+        self.tree = MoveTree::default();
+        self.path.clear();
+        self
+    }
+
+    /// Checks that the current position could come up in a game, e.g.
+    /// after setting it up with [`Game::with_position`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the first problem found with the position, see
+    /// [`validate_position`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let board = Odds::Queen.starting_board(Color::White);
+    /// assert!(Game::new_multi().with_position(board).validate_position().is_ok());
+    /// ```
+    pub fn validate_position(&self) -> Result<(), PositionError> {
+        validate_position(&self.board.into()).map(|_| ())
     }
 
     /// Attempts to generate a ChessMove from the given inputstring.
     ///
     /// Depending on the `uci` flag, the function expects the input either
-    /// in UCI format or in Standard Algebraic Notation (SAN).
+    /// in UCI format or in Standard Algebraic Notation (SAN). Without it,
+    /// long algebraic (`Ng1-f3`) and descriptive (`P-K4`) notation are
+    /// read too.
     ///
     /// # Arguments
     ///
@@ -85,7 +352,8 @@ impl Game {
     /// # Returns
     ///
     /// * `Ok(ChessMove)` - If the input is valid and the move is legal
-    /// * `Err(String)` - If the input is invalid or the move is illegal
+    /// * `Err(ChessError)` - If the input is invalid or the move is illegal,
+    ///   with an explanation of why the move can't be played
     ///
     /// # Examples
     ///
@@ -94,44 +362,200 @@ impl Game {
     /// assert!(game.parse_move("e2e4", true).is_ok());
     /// assert!(game.parse_move("e4", false).is_ok());
     /// ```
-    fn parse_move(&self, input: &str, uci: bool) -> Result<ChessMove, String> {
+    pub fn parse_move(&self, input: &str, uci: bool) -> Result<ChessMove, ChessError> {
         if uci {
             match ChessMove::from_str(input) {
                 Ok(mv) => {
                     if self.board.legal(mv) {
                         Ok(mv)
                     } else {
-                        Err("Illegal move!".into())
+                        Err(ChessError::IllegalMove(explain_illegal_move(
+                            &self.board,
+                            mv,
+                        )))
                     }
                 }
-                Err(_) => Err("Invalid input format!".into()),
+                Err(_) => Err(ChessError::InvalidFormat),
             }
         } else {
-            match ChessMove::from_san(&self.board, input) {
-                Ok(mv) => Ok(mv),
-                Err(_) => Err("Invalid input!".into()),
+            if let Ok(mv) = ChessMove::from_san(&self.board, input) {
+                return Ok(mv);
+            }
+            // Players used to older books may write both squares or
+            // use descriptive notation.
+            if let Some(mv) = parse_long_algebraic(&self.board, input) {
+                if !self.board.legal(mv) {
+                    let reason = explain_illegal_move(&self.board, mv);
+                    return Err(ChessError::IllegalMove(reason));
+                }
+                return Ok(mv);
+            }
+            if let Some(mv) = parse_descriptive(&self.board, input) {
+                return Ok(mv);
+            }
+            match explain_illegal_san(&self.board, input) {
+                Some(reason) => Err(ChessError::IllegalMove(reason)),
+                None => Err(ChessError::InvalidInput),
+            }
+        }
+    }
+
+    /// Selects the piece on `square` to move it with [`Game::move_selected`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no piece of the side to move on
+    /// `square` or it has no legal moves.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut game = Game::new_multi();
+    /// game.select(Square::G1).unwrap();
+    /// assert_eq!(game.selected_destinations(), [Square::F3, Square::H3]);
+    /// ```
+    pub fn select(&mut self, square: Square) -> Result<(), ChessError> {
+        if self.status() != Status::Ongoing {
+            return Err(ChessError::GameOver);
+        }
+        match self.board.color_on(square) {
+            None => Err(ChessError::IllegalMove(IllegalMoveReason::NoPieceOnSquare(
+                square,
+            ))),
+            Some(color) if color != self.turn => Err(ChessError::IllegalMove(
+                IllegalMoveReason::OpponentsPiece(square, color),
+            )),
+            Some(_) => {
+                self.selection = Some(square);
+                if self.selected_destinations().is_empty() {
+                    self.selection = None;
+                    return Err(ChessError::NoMovesFrom(square));
+                }
+                Ok(())
             }
         }
     }
 
+    /// Returns the square of the selected piece
+    pub fn selection(&self) -> Option<Square> {
+        self.selection
+    }
+
+    /// Returns the squares the selected piece can legally move to.
+    pub fn selected_destinations(&self) -> Vec<Square> {
+        let Some(selection) = self.selection else {
+            return Vec::new();
+        };
+        let mut destinations: Vec<Square> = MoveGen::new_legal(&self.board)
+            .filter(|mv| mv.get_source() == selection)
+            .map(|mv| mv.get_dest())
+            .collect();
+        destinations.dedup();
+        destinations
+    }
+
+    /// Moves the selected piece to `dest`, promoting pawns to a queen.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no piece is selected, the move is illegal,
+    /// explaining why, or the game is over.
+    pub fn move_selected(&mut self, dest: Square) -> Result<(), ChessError> {
+        let source = self.selection.ok_or(ChessError::NothingSelected)?;
+        let promotion = (self.board.piece_on(source) == Some(Piece::Pawn)
+            && (dest.get_rank() == Rank::First || dest.get_rank() == Rank::Eighth))
+            .then_some(Piece::Queen);
+        let mv = ChessMove::new(source, dest, promotion);
+        if !self.board.legal(mv) {
+            return Err(ChessError::IllegalMove(explain_illegal_move(
+                &self.board,
+                mv,
+            )));
+        }
+        self.make_move(mv)
+    }
+
     /// Makes a move on the board.
     ///
     /// # Arguments
     ///
     /// * `mv` - a ChessMove instance
     ///
+    /// Making a move declines any pending takeback request or draw
+    /// offer. The time
+    /// since the previous move is recorded as the time spent on this one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChessError::GameOver`] once the game has ended; start
+    /// a new one with [`Game::reset`].
+    ///
     /// # Examples
     ///
     /// ```
     /// let mut game = Game::new_multi();
     /// let mv = parse_move("e2e4", true).unwrap();
-    /// game.make_move(mv);
+    /// game.make_move(mv).unwrap();
     /// ```
-    pub fn make_move(&mut self, mv: ChessMove) {
+    pub fn make_move(&mut self, mv: ChessMove) -> Result<(), ChessError> {
+        if self.status() != Status::Ongoing {
+            return Err(ChessError::GameOver);
+        }
+        self.takeback_request = None;
+        self.draw_offer = None;
+        self.selection = None;
         self.history.push((self.board, self.turn));
         self.board = self.board.make_move_new(mv);
         self.turn = !self.turn;
         self.moves.push(mv);
+        self.move_times.push(self.move_started.elapsed());
+        self.move_started = Instant::now();
+        let index = self
+            .tree
+            .play(&self.path, mv, *self.move_times.last().unwrap());
+        self.path.push(index);
+
+        self.emit(GameEvent::MoveMade(!self.turn, mv));
+        if self.in_check() {
+            self.emit(GameEvent::CheckGiven(!self.turn));
+        }
+        let status = self.status();
+        if status != Status::Ongoing {
+            self.emit(GameEvent::GameEnded(status));
+        }
+        Ok(())
+    }
+
+    /// Adds a listener called on every [`GameEvent`], so front-ends
+    /// can react to moves, checks and the end of the game without
+    /// polling the game state. Listeners must be `Send`, so a game can
+    /// be handed to another thread, as the game server does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut game = Game::new_multi();
+    /// game.subscribe(|event| {
+    ///     if let GameEvent::CheckGiven(color) = event {
+    ///         println!("{:?} gives check!", color);
+    ///     }
+    /// });
+    /// ```
+    pub fn subscribe(&mut self, listener: impl FnMut(&GameEvent) + Send + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        match event {
+            GameEvent::MoveMade(color, mv) => debug!("{:?} played {}", color, mv),
+            GameEvent::MoveUndone(mv) => debug!("{} taken back", mv),
+            GameEvent::CheckGiven(color) => debug!("{:?} gave check", color),
+            GameEvent::GameEnded(status) => info!("Game ended: {:?}", status),
+            GameEvent::Reset | GameEvent::ColorsSwapped => (),
+        }
+        for listener in &mut self.listeners {
+            listener(&event);
+        }
     }
 
     /// Attempts to make a move from the given inputstring.
@@ -147,8 +571,9 @@ impl Game {
     /// # Errors
     ///
     /// Returns an error if:
+    /// - the game is over,
     /// - the input format is invalid,
-    /// - the move is illegal.
+    /// - the move is illegal, explaining why.
     ///
     /// # Examples
     ///
@@ -158,32 +583,368 @@ impl Game {
     /// // This should succeed (for UCI input)
     /// assert!(game.make_move_from_str("e2e4", true).is_ok());
     /// ```
-    pub fn make_move_from_str(&mut self, input: &str, uci: bool) -> Result<(), String> {
+    pub fn make_move_from_str(&mut self, input: &str, uci: bool) -> Result<(), ChessError> {
+        if self.status() != Status::Ongoing {
+            return Err(ChessError::GameOver);
+        }
         match self.parse_move(input, uci) {
-            Ok(mv) => {
-                self.make_move(mv);
-                Ok(())
-            }
+            Ok(mv) => self.make_move(mv),
             Err(e) => Err(e),
         }
     }
 
+    /// Starts a new game from the position this one started from,
+    /// keeping its mode, variant, AI settings and listeners, which are
+    /// told with [`GameEvent::Reset`]. The moves, annotations, pending
+    /// requests and the result are cleared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut game = Game::new_multi();
+    /// game.resign(Color::White).unwrap();
+    /// game.reset();
+    /// assert_eq!(game.status(), Status::Ongoing);
+    /// ```
+    pub fn reset(&mut self) {
+        let start = *self.starting_position();
+        self.board = start;
+        self.turn = start.side_to_move();
+        self.history.clear();
+        self.moves.clear();
+        self.move_times.clear();
+        self.move_started = Instant::now();
+        self.tree = MoveTree::default();
+        self.path.clear();
+        self.takeback_request = None;
+        self.draw_offer = None;
+        self.ended = None;
+        self.annotations = Annotations::default();
+        self.selection = None;
+        if let Some(table) = &self.engine_options.table {
+            table.lock().clear();
+        }
+        info!("Game reset to {}", start);
+        self.emit(GameEvent::Reset);
+    }
+
+    /// Starts a new game like [`Game::reset`], with the players swapping
+    /// colors: in a single-player game, the player takes the AI's color,
+    /// and the players' names swap sides.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut game = Game::new_single(Color::White, Difficulty::Easy);
+    /// game.resign(Color::White).unwrap();
+    /// game.rematch();
+    /// assert!(game.get_ai_move().is_ok());
+    /// ```
+    pub fn rematch(&mut self) {
+        self.reset();
+        self.tags.swap_players();
+        if let GameMode::SinglePlayer(color) = self.game_mode {
+            self.game_mode = GameMode::SinglePlayer(!color);
+        }
+        self.emit(GameEvent::ColorsSwapped);
+    }
+
+    /// Copies the game up to the current position onto a scratch board to
+    /// try lines on while the game goes on, the AI playing `ai_color` at
+    /// full strength. Listeners, annotations and offers aren't copied, so
+    /// nothing done on the scratch board reaches the game.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut scratch = game.scratch(!game.turn());
+    /// scratch.make_move_from_str("Qxf7", false)?;
+    /// let reply = scratch.get_ai_move()?;
+    /// ```
+    pub fn scratch(&self, ai_color: Color) -> Game {
+        let mut scratch = Game::new_single(!ai_color, Difficulty::Hard)
+            .with_variant(self.variant)
+            .with_engine(self.engine);
+        scratch.board = self.board;
+        scratch.turn = self.turn;
+        scratch.history = self.history.clone();
+        scratch.moves = self.moves.clone();
+        scratch.move_times = self.move_times.clone();
+        scratch.tree = self.tree.clone();
+        scratch.path = self.path.clone();
+        scratch
+    }
+
     /// Undoes the last move, reverting the board to its previous state.
     ///
     /// Pops the last state from the undo history stack and restores
-    /// both the board and the turn. If no moves have been made,
-    /// returns an error.
-    pub fn undo(&mut self) -> Result<(), String> {
-        if let Some((prev_board, prev_turn)) = self.history.pop() {
-            self.board = prev_board;
-            self.turn = prev_turn;
-            self.moves.pop();
-            Ok(())
+    /// both the board and the turn. The player to move starts
+    /// thinking afresh. The move is removed from the game, with any
+    /// variations after it. If no moves have been made, returns an error.
+    pub fn undo(&mut self) -> Result<(), ChessError> {
+        if self.history.is_empty() {
+            return Err(ChessError::NothingToUndo);
+        }
+        self.tree.remove(&self.path);
+        self.step_back();
+        Ok(())
+    }
+
+    /// Goes back to the position after `ply` moves of the current line,
+    /// to play another move there. Unlike [`Game::undo`], the moves gone
+    /// back over are kept as the line's continuation, and following
+    /// moves played differently start a variation, see
+    /// [`Game::variations`]. Any result of the game is cleared.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the current line has fewer than `ply` moves.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut game = Game::new_multi();
+    /// game.make_move_from_str("e4", false)?;
+    /// game.make_move_from_str("e5", false)?;
+    /// game.branch(1)?;
+    /// game.make_move_from_str("c5", false)?;
+    /// assert_eq!(game.move_tree().main_line().len(), 2);
+    /// ```
+    pub fn branch(&mut self, ply: usize) -> Result<(), ChessError> {
+        if ply > self.moves.len() {
+            return Err(ChessError::NoSuchPly(ply));
+        }
+        while self.moves.len() > ply {
+            self.step_back();
+        }
+        self.takeback_request = None;
+        self.draw_offer = None;
+        self.ended = None;
+        Ok(())
+    }
+
+    /// Returns the moves played from the current position in any line of
+    /// the game: the continuation of the line first, then its variations.
+    pub fn variations(&self) -> Vec<ChessMove> {
+        self.tree
+            .node(&self.path)
+            .children
+            .iter()
+            .filter_map(|child| child.mv)
+            .collect()
+    }
+
+    /// Returns every move of the game as a tree of variations.
+    pub fn move_tree(&self) -> &MoveTree {
+        &self.tree
+    }
+
+    /// Returns the child of the move tree taken at each ply of the
+    /// current line.
+    pub fn current_path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Returns the comment on the last move of the current line, or on
+    /// the game before any move.
+    pub fn comment(&self) -> &str {
+        &self.tree.node(&self.path).comment
+    }
+
+    /// Comments on the last move of the current line, or on the game
+    /// before any move; an empty comment removes it. Comments are
+    /// exported to PGN, see [`write_pgn`](crate::pgn::write_pgn).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// game.make_move_from_str("e4", false)?;
+    /// game.set_comment("Best by test");
+    /// assert_eq!(game.comment(), "Best by test");
+    /// ```
+    pub fn set_comment(&mut self, comment: &str) {
+        self.tree.node_mut(&self.path).comment = comment.trim().to_string();
+    }
+
+    /// Returns the Numeric Annotation Glyphs of the last move of the
+    /// current line.
+    pub fn nags(&self) -> &[u8] {
+        &self.tree.node(&self.path).nags
+    }
+
+    /// Adds the Numeric Annotation Glyph `nag` to the last move of the
+    /// current line, or removes it if it's there already.
+    pub fn toggle_nag(&mut self, nag: u8) {
+        let nags = &mut self.tree.node_mut(&self.path).nags;
+        match nags.iter().position(|n| *n == nag) {
+            Some(index) => {
+                nags.remove(index);
+            }
+            None => nags.push(nag),
+        }
+    }
+
+    /// Sets the Numeric Annotation Glyphs of the last move of the current
+    /// line, replacing any it had.
+    pub fn set_nags(&mut self, nags: &[u8]) {
+        self.tree.node_mut(&self.path).nags = nags.to_vec();
+    }
+
+    /// Takes back the last move of the current line, leaving the move
+    /// tree as it is.
+    fn step_back(&mut self) {
+        let Some((prev_board, prev_turn)) = self.history.pop() else {
+            return;
+        };
+        self.board = prev_board;
+        self.turn = prev_turn;
+        let mv = self.moves.pop().unwrap();
+        self.move_times.pop();
+        self.path.pop();
+        self.selection = None;
+        self.move_started = Instant::now();
+        self.emit(GameEvent::MoveUndone(mv));
+    }
+
+    /// Requests a takeback of the last move on behalf of the player who made it.
+    ///
+    /// The move stays on the board until the opponent answers with
+    /// [`Game::accept_takeback`] or [`Game::decline_takeback`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no moves to take back
+    /// or a takeback has already been requested.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut game = Game::new_multi();
+    /// game.make_move_from_str("e4", false).unwrap();
+    /// game.request_takeback().unwrap();
+    /// assert_eq!(game.takeback_request(), Some(Color::White));
+    /// ```
+    pub fn request_takeback(&mut self) -> Result<(), ChessError> {
+        if self.history.is_empty() {
+            return Err(ChessError::NothingToUndo);
+        }
+        if self.takeback_request.is_some() {
+            return Err(ChessError::TakebackPending);
+        }
+        self.takeback_request = Some(!self.turn);
+        Ok(())
+    }
+
+    /// Accepts the pending takeback request, undoing the last move.
+    pub fn accept_takeback(&mut self) -> Result<(), ChessError> {
+        if self.takeback_request.take().is_none() {
+            return Err(ChessError::NoTakebackRequested);
+        }
+        self.undo()
+    }
+
+    /// Declines the pending takeback request, leaving the board as it is.
+    pub fn decline_takeback(&mut self) -> Result<(), ChessError> {
+        match self.takeback_request.take() {
+            Some(_) => Ok(()),
+            None => Err(ChessError::NoTakebackRequested),
+        }
+    }
+
+    /// Returns the color of the player waiting for an answer to a takeback request
+    pub fn takeback_request(&self) -> Option<Color> {
+        self.takeback_request
+    }
+
+    /// Ends the game with `color` resigning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the game is already over.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut game = Game::new_multi();
+    /// game.resign(Color::White).unwrap();
+    /// assert_eq!(game.status(), Status::Resignation(Color::Black));
+    /// ```
+    pub fn resign(&mut self, color: Color) -> Result<(), ChessError> {
+        self.end(Status::Resignation(!color))
+    }
+
+    /// Ends the game with `color` running out of time: a loss, or a draw
+    /// if the opponent has only their king left, or a king and a single
+    /// bishop or knight, which can't checkmate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the game is already over.
+    pub fn time_out(&mut self, color: Color) -> Result<(), ChessError> {
+        let opponent = self.board.color_combined(!color);
+        let heavy = self.board.pieces(Piece::Pawn)
+            | self.board.pieces(Piece::Rook)
+            | self.board.pieces(Piece::Queen);
+        let minors = self.board.pieces(Piece::Knight) | self.board.pieces(Piece::Bishop);
+        if (opponent & heavy).popcnt() == 0 && (opponent & minors).popcnt() <= 1 {
+            self.end(Status::TimeoutDraw(color))
         } else {
-            Err("No moves to undo!".into())
+            self.end(Status::Timeout(!color))
+        }
+    }
+
+    /// Offers a draw on behalf of `color`.
+    ///
+    /// The game goes on until the opponent answers with
+    /// [`Game::accept_draw`] or [`Game::decline_draw`]; making a move
+    /// declines the offer too.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the game is over or a draw has already been
+    /// offered.
+    pub fn offer_draw(&mut self, color: Color) -> Result<(), ChessError> {
+        if self.status() != Status::Ongoing {
+            return Err(ChessError::GameOver);
+        }
+        if self.draw_offer.is_some() {
+            return Err(ChessError::DrawOfferPending);
+        }
+        self.draw_offer = Some(color);
+        Ok(())
+    }
+
+    /// Accepts the pending draw offer, ending the game.
+    pub fn accept_draw(&mut self) -> Result<(), ChessError> {
+        if self.draw_offer.take().is_none() {
+            return Err(ChessError::NoDrawOffered);
+        }
+        self.end(Status::DrawAgreed)
+    }
+
+    /// Declines the pending draw offer.
+    pub fn decline_draw(&mut self) -> Result<(), ChessError> {
+        match self.draw_offer.take() {
+            Some(_) => Ok(()),
+            None => Err(ChessError::NoDrawOffered),
         }
     }
 
+    /// Returns the color of the player waiting for an answer to a draw offer.
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    /// Ends an ongoing game by the players' decision with `status`.
+    fn end(&mut self, status: Status) -> Result<(), ChessError> {
+        if self.status() != Status::Ongoing {
+            return Err(ChessError::GameOver);
+        }
+        self.ended = Some(status);
+        self.emit(GameEvent::GameEnded(status));
+        Ok(())
+    }
+
     /// Returns the status of the game.
     /// Checks the board state and maps the chess crate's `BoardStatus`
     /// to the custom [`Status`] enum.
@@ -193,6 +954,10 @@ impl Game {
     /// - [`Status::Ongoing`] if the game is still in progress
     /// - [`Status::Stalemate`] if there are no legal moves for the current player but the player is not in check
     /// - [`Status::Checkmate`] if the current player is in check and there are no legal moves available. Also returns the winner of the game.
+    /// - [`Status::VariantWin`] if the last move met the variant's extra win condition, such as a king reaching the center in King of the Hill. Also returns the winner of the game.
+    /// - [`Status::Resignation`] or [`Status::DrawAgreed`] if a player resigned or the players agreed to a draw.
+    /// - [`Status::Timeout`] if a player ran out of time. Also returns the winner of the game.
+    /// - [`Status::TimeoutDraw`] if a player ran out of time with the opponent unable to checkmate. Also returns the player out of time.
     ///
     /// # Example
     ///
@@ -201,6 +966,12 @@ impl Game {
     /// assert_eq!(game.status(), Status::Ongoing);
     /// ```
     pub fn status(&self) -> Status {
+        if let Some(status) = self.ended {
+            return status;
+        }
+        if let Some(winner) = self.variant_state().winner(&self.board) {
+            return Status::VariantWin(winner);
+        }
         match self.board.status() {
             BoardStatus::Ongoing => Status::Ongoing,
             BoardStatus::Checkmate => Status::Checkmate(!self.turn),
@@ -208,6 +979,78 @@ impl Game {
         }
     }
 
+    /// Returns the status of the game along with whether the side to
+    /// move is in check.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut game = Game::new_multi();
+    /// game.make_move(ChessMove::from_str("e2e4").unwrap()).unwrap();
+    /// assert_eq!(game.state(), GameState { status: Status::Ongoing, is_check: false });
+    /// ```
+    pub fn state(&self) -> GameState {
+        GameState {
+            status: self.status(),
+            is_check: self.in_check(),
+        }
+    }
+
+    /// Returns whether the side to move is in check.
+    pub fn in_check(&self) -> bool {
+        *self.board.checkers() != EMPTY
+    }
+
+    /// Returns the squares of the pieces giving check to the side to
+    /// move, empty if it isn't in check.
+    pub fn checkers(&self) -> Vec<Square> {
+        self.board.checkers().into_iter().collect()
+    }
+
+    /// Returns the FEN of the current position with its move counters,
+    /// counting moves from 1 at the starting position.
+    pub fn fen(&self) -> String {
+        let plies = self.moves.len() + self.starting_position().side_to_move().to_index();
+        fen_with_counters(&self.board, self.halfmove_clock(), plies as u32 / 2 + 1)
+    }
+
+    /// Returns the number of plies since the last capture or pawn move,
+    /// the count the fifty-move rule goes by.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.history
+            .iter()
+            .zip(&self.moves)
+            .rev()
+            .take_while(|((board, _), mv)| {
+                board.piece_on(mv.get_source()) != Some(Piece::Pawn)
+                    && board.piece_on(mv.get_dest()).is_none()
+            })
+            .count() as u32
+    }
+
+    /// Returns how many times the current position has occurred in the
+    /// game, counting this time.
+    pub fn repetitions(&self) -> usize {
+        1 + self
+            .history
+            .iter()
+            .filter(|(board, _)| *board == self.board)
+            .count()
+    }
+
+    /// Returns the positions the game went through, for the AI's search
+    /// to see draws by repetition and the fifty-move rule.
+    pub fn position_history(&self) -> PositionHistory {
+        PositionHistory {
+            positions: self
+                .history
+                .iter()
+                .map(|(board, _)| board.get_hash())
+                .collect(),
+            halfmove_clock: self.halfmove_clock(),
+        }
+    }
+
     /// Returns the current turn
     pub fn turn(&self) -> Color {
         self.turn
@@ -218,50 +1061,281 @@ impl Game {
         &self.board
     }
 
+    /// Returns the position the game started from
+    pub fn starting_position(&self) -> &Board {
+        self.history.first().map_or(&self.board, |(board, _)| board)
+    }
+
+    /// Returns the variant the game is played with
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Returns the variant rule state, recomputed from the board history.
+    pub fn variant_state(&self) -> VariantState {
+        self.history
+            .iter()
+            .skip(1)
+            .map(|(board, _)| board)
+            .chain(std::iter::once(&self.board))
+            .fold(VariantState::new(self.variant), |state, board| {
+                state.after_move(board)
+            })
+    }
+
     /// Returns the move history of the game
     pub fn moves(&self) -> &Vec<ChessMove> {
         &self.moves
     }
 
+    /// Returns the squares marked and arrows drawn on the board
+    pub fn annotations(&self) -> &Annotations {
+        &self.annotations
+    }
+
+    /// Returns the board annotations for changing them.
+    ///
+    /// Annotations stay on the board until they are removed.
+    pub fn annotations_mut(&mut self) -> &mut Annotations {
+        &mut self.annotations
+    }
+
+    /// Returns the event and players' names the game is exported with.
+    pub fn tags(&self) -> &GameTags {
+        &self.tags
+    }
+
+    /// Returns the playing style of the AI
+    pub fn personality(&self) -> Personality {
+        self.personality
+    }
+
+    /// Records `remaining` as the time left on the clock of the side that
+    /// made the last move.
+    pub fn set_clock(&mut self, remaining: Duration) {
+        if !self.path.is_empty() {
+            self.tree.node_mut(&self.path).clock = Some(remaining);
+        }
+    }
+
+    /// Returns the wall-clock time spent on each move of the move history
+    pub fn move_times(&self) -> &[Duration] {
+        &self.move_times
+    }
+
+    /// Draws the current position as an SVG image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let game = Game::new_multi();
+    /// fs::write("board.svg", game.render_svg(&SvgOptions::default()))?;
+    /// ```
+    pub fn render_svg(&self, options: &SvgOptions) -> String {
+        render_board(&self.board, self.moves.last().copied(), options)
+    }
+
+    /// Draws every position of the game, from the starting position to
+    /// the current one, as SVG images.
+    pub fn render_svg_frames(&self, options: &SvgOptions) -> Vec<String> {
+        let mut board = *self.starting_position();
+        let mut frames = vec![render_board(&board, None, options)];
+        for mv in &self.moves {
+            board = board.make_move_new(*mv);
+            frames.push(render_board(&board, Some(*mv), options));
+        }
+        frames
+    }
+
     /// Gets the best move generated by AI.
     ///
     /// # Returns
     ///
     /// * `Ok(ChessMove)` if there is a legal move
-    /// * `Err()` if there are no legal moves
+    /// * `Err()` if the game is over or it's the player's turn
     ///
     /// # Example
     ///
     /// ```
-    /// let mut game = Game::new_single(Color:Black, 3);
+    /// let mut game = Game::new_single(Color::Black, Difficulty::Easy);
     /// match game.get_ai_move() {
-    ///     Ok(mv) => game.make_move(mv),
+    ///     Ok(mv) => game.make_move(mv).unwrap(),
     ///     Err(e) => println!("{}", e)
     /// }
     /// ```
-    pub fn get_ai_move(&self) -> Result<ChessMove, String> {
-        let ai_color = match self.game_mode {
-            GameMode::SinglePlayer(player_color) => !player_color,
-            GameMode::TwoPlayer => return Err("AI can only be used in single player mode".into()),
+    pub fn get_ai_move(&self) -> Result<ChessMove, ChessError> {
+        self.get_scored_ai_move().map(|(mv, _)| mv)
+    }
+
+    /// Changes how strong the AI plays from its next move on.
+    ///
+    /// # Arguments
+    ///
+    /// * `depth` - How deep the AI searches.
+    /// * `blunder_model` - How it picks among its best moves, `None` to
+    ///   always play the best one.
+    pub fn set_skill(&mut self, depth: u32, blunder_model: Option<BlunderModel>) {
+        self.recursion_depth = Some(depth);
+        self.blunder_model = blunder_model;
+    }
+
+    /// Gets the best move generated by AI like [`Game::get_ai_move`],
+    /// along with the AI's score of the position in centipawns from its
+    /// point of view, `None` if the move was picked without a search.
+    pub fn get_scored_ai_move(&self) -> Result<(ChessMove, Option<i32>), ChessError> {
+        self.ai_player()?.choose()
+    }
+
+    /// Takes what the AI needs to choose its move in the current
+    /// position, so it can think on another thread while the game goes on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the game is over, or it isn't the AI's turn.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let ai = game.ai_player()?;
+    /// let thinking = thread::spawn(move || ai.choose());
+    /// ```
+    pub fn ai_player(&self) -> Result<AiPlayer, ChessError> {
+        if self.status() != Status::Ongoing {
+            return Err(ChessError::GameOver);
+        }
+        match self.game_mode {
+            GameMode::SinglePlayer(player_color) if player_color == self.turn => {
+                return Err(ChessError::NotAiTurn)
+            }
+            GameMode::SinglePlayer(_) => (),
+            GameMode::TwoPlayer => return Err(ChessError::AiUnavailable),
+        }
+        let mut engine_options = self.engine_options.clone();
+        if let Some(limit) = self.node_limit {
+            let nodes = engine_options
+                .limits
+                .nodes
+                .map_or(limit, |nodes| nodes.min(limit));
+            engine_options.limits.nodes = Some(nodes);
+        }
+        engine_options.history = PositionHistory {
+            positions: self
+                .history
+                .iter()
+                .map(|(board, _)| board.get_hash())
+                .collect(),
+            halfmove_clock: self.halfmove_clock(),
         };
-        let (_eval, best_move) = minimax(
+        Ok(AiPlayer {
+            board: self.board,
+            state: self.variant_state(),
+            starting_position: *self.starting_position(),
+            moves: self.moves.clone(),
+            depth: self.recursion_depth.unwrap(),
+            baseline: self.baseline,
+            blunder_model: self.blunder_model,
+            engine: self.engine,
+            engine_options,
+            personality: self.personality,
+        })
+    }
+}
+
+/// The AI of a single-player [`Game`] about to choose a move, see
+/// [`Game::ai_player`].
+#[derive(Clone, Debug)]
+pub struct AiPlayer {
+    board: Board,
+    state: VariantState,
+    starting_position: Board,
+    moves: Vec<ChessMove>,
+    depth: u32,
+    baseline: Option<Baseline>,
+    blunder_model: Option<BlunderModel>,
+    engine: EngineKind,
+    engine_options: EngineOptions,
+    personality: Personality,
+}
+
+impl AiPlayer {
+    /// Chooses the AI's move, see [`Game::get_scored_ai_move`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no legal move.
+    pub fn choose(&self) -> Result<(ChessMove, Option<i32>), ChessError> {
+        if let Some(baseline) = self.baseline {
+            let mv = baseline_move(&self.board, baseline).ok_or(ChessError::NoLegalMoves)?;
+            debug!("{:?} baseline chose {}", baseline, mv);
+            return Ok((mv, None));
+        }
+        if let Some(mv) = book_move(&self.board) {
+            debug!("Played book move {}", mv);
+            return Ok((mv, None));
+        }
+        if let Some(mv) = self
+            .personality
+            .book_move(&self.starting_position, &self.moves)
+        {
+            debug!("{} personality played book move {}", self.personality, mv);
+            return Ok((mv, None));
+        }
+        if let (Some(model), EngineKind::AlphaBeta) = (self.blunder_model, self.engine) {
+            let scored = score_moves(&self.board, &self.state, self.depth, &self.engine_options);
+            let (mv, score) = model.choose(&scored).ok_or(ChessError::NoLegalMoves)?;
+            debug!(
+                "{:?} chose {} scoring {} out of {:?}",
+                model, mv, score, scored
+            );
+            return Ok((mv, Some(score)));
+        }
+        let started = Instant::now();
+        let result = self
+            .engine
+            .engine(self.depth, self.engine_options.clone())
+            .search(&self.board, &self.state);
+        debug!("{:?} engine chose {}", self.engine, result);
+        let best_move = result.best_move.ok_or(ChessError::NoLegalMoves)?;
+        if self.engine == EngineKind::AlphaBeta
+            && Outlook::from_score(result.score) != Outlook::Balanced
+        {
+            if let Some(mv) = self.practical_move(result.nodes, started.elapsed()) {
+                return Ok(mv);
+            }
+        }
+        Ok((best_move, Some(result.score)))
+    }
+
+    /// Looks again at the moves in a won or lost position, a little less
+    /// deep than the search that found it so, for a more practical one,
+    /// see [`practical_move`]. The search already visited `nodes`
+    /// positions over `elapsed`, counting towards its limits.
+    ///
+    /// # Returns
+    ///
+    /// The move with its score, `None` to play the search's best move or
+    /// if the limits ran out.
+    fn practical_move(&self, nodes: u64, elapsed: Duration) -> Option<(ChessMove, Option<i32>)> {
+        let options = EngineOptions {
+            limits: self.engine_options.limits.remaining(nodes, elapsed)?,
+            ..self.engine_options.clone()
+        };
+        let scored = score_moves(
             &self.board,
-            self.recursion_depth.unwrap(),
-            true,
-            ai_color,
-            i32::MIN,
-            i32::MAX,
+            &self.state,
+            self.depth.saturating_sub(1).max(1),
+            &options,
         );
-        match best_move {
-            Some(m) => Ok(m),
-            None => Err("No legal moves for AI available".into()),
-        }
+        let (mv, score) = practical_move(&self.board, &scored)?;
+        debug!("Practical play chose {} scoring {}", mv, score);
+        Some((mv, Some(score)))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::odds::Odds;
 
     #[test]
     fn parse_move_valid_uci() {
@@ -281,6 +1355,17 @@ mod tests {
         assert!(game.parse_move("e2e5", true).is_err());
     }
 
+    #[test]
+    fn illegal_move_explained() {
+        let mut game = Game::new_multi();
+        assert_eq!(
+            game.make_move_from_str("Bc4", false),
+            Err(ChessError::IllegalMove(IllegalMoveReason::PathBlocked(
+                chess::Square::E2
+            )))
+        );
+    }
+
     #[test]
     fn input_format_invalid() {
         let game = Game::new_multi();
@@ -316,6 +1401,43 @@ mod tests {
         assert_eq!(game.status(), Status::Checkmate(chess::Color::White));
     }
 
+    #[test]
+    fn counts_repetitions_and_quiet_plies() {
+        let mut game = Game::new_multi();
+        game.make_move_from_str("e4", false).unwrap();
+        assert_eq!(game.halfmove_clock(), 0);
+        for mv in ["Nf6", "Nf3", "Ng8", "Ng1", "Nf6", "Nf3", "Ng8", "Ng1"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        assert_eq!(game.halfmove_clock(), 8);
+        assert_eq!(game.repetitions(), 3);
+        let history = game.position_history();
+        assert_eq!(history.halfmove_clock, 8);
+        assert!(history.draws(game.board()));
+        game.undo().unwrap();
+        assert_eq!(game.repetitions(), 2);
+    }
+
+    #[test]
+    fn reports_check_and_checkers() {
+        let mut game = Game::new_multi();
+        game.make_move_from_str("e4", false).unwrap();
+        game.make_move_from_str("f6", false).unwrap();
+        assert!(!game.in_check());
+        assert!(game.checkers().is_empty());
+        game.make_move_from_str("Qh5", false).unwrap();
+        assert!(game.in_check());
+        assert_eq!(game.checkers(), [Square::H5]);
+        assert!(game.fen().ends_with(" b KQkq - 1 2"));
+        assert_eq!(
+            game.state(),
+            GameState {
+                status: Status::Ongoing,
+                is_check: true
+            }
+        );
+    }
+
     #[test]
     fn is_status_stalemate() {
         let mut game = Game::new_multi();
@@ -342,6 +1464,109 @@ mod tests {
         assert_eq!(game.status(), Status::Stalemate);
     }
 
+    #[test]
+    fn timeouts_against_bare_minor_pieces_are_draws() {
+        let mut game = Game::new_multi();
+        game.time_out(Color::White).unwrap();
+        assert_eq!(game.status(), Status::Timeout(Color::Black));
+
+        let board = Board::from_str("4k3/8/8/8/8/2n5/PPP5/4K3 w - - 0 1").unwrap();
+        let mut game = Game::new_multi().with_position(board);
+        game.time_out(Color::White).unwrap();
+        assert_eq!(game.status(), Status::TimeoutDraw(Color::White));
+        let mut game = Game::new_multi().with_position(board);
+        game.time_out(Color::Black).unwrap();
+        assert_eq!(game.status(), Status::Timeout(Color::White));
+    }
+
+    #[test]
+    fn personality_sets_the_contempt() {
+        let game = Game::new_single(Color::White, Difficulty::Hard)
+            .with_personality(Personality::Aggressive)
+            .with_personality(Personality::Aggressive);
+        assert_eq!(
+            game.engine_options.contempt,
+            Personality::Aggressive.contempt()
+        );
+    }
+
+    #[test]
+    fn resigns_and_agrees_draws() {
+        let mut game = Game::new_multi();
+        game.offer_draw(Color::White).unwrap();
+        assert_eq!(
+            game.offer_draw(Color::White),
+            Err(ChessError::DrawOfferPending)
+        );
+        game.decline_draw().unwrap();
+        assert_eq!(game.accept_draw(), Err(ChessError::NoDrawOffered));
+        game.offer_draw(Color::Black).unwrap();
+        game.make_move_from_str("e4", false).unwrap();
+        assert_eq!(game.draw_offer(), None);
+        game.offer_draw(Color::Black).unwrap();
+        game.accept_draw().unwrap();
+        assert_eq!(game.status(), Status::DrawAgreed);
+        assert_eq!(game.resign(Color::White), Err(ChessError::GameOver));
+
+        let mut game = Game::new_multi();
+        game.resign(Color::White).unwrap();
+        assert_eq!(game.status(), Status::Resignation(Color::Black));
+    }
+
+    #[test]
+    fn refuses_moves_after_the_end() {
+        let board = Odds::Knight.starting_board(Color::White);
+        let mut game = Game::new_single(Color::Black, Difficulty::Beginner).with_position(board);
+        game.resign(Color::Black).unwrap();
+        assert_eq!(
+            game.make_move_from_str("e4", false),
+            Err(ChessError::GameOver)
+        );
+        assert_eq!(game.select(Square::E2), Err(ChessError::GameOver));
+        assert_eq!(game.get_ai_move(), Err(ChessError::GameOver));
+
+        game.reset();
+        assert_eq!(game.status(), Status::Ongoing);
+        assert_eq!(*game.board(), board);
+        assert!(game.get_ai_move().is_ok());
+        game.rematch();
+        assert_eq!(game.get_ai_move(), Err(ChessError::NotAiTurn));
+
+        let mut game = Game::new_multi();
+        for mv in ["f3", "e5", "g4", "Qh4"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        let mv = ChessMove::from_str("e2e4").unwrap();
+        assert_eq!(game.make_move(mv), Err(ChessError::GameOver));
+        assert_eq!(game.moves().len(), 4);
+    }
+
+    #[test]
+    fn is_status_king_of_the_hill_win() {
+        let mut game = Game::new_multi().with_variant(Variant::KingOfTheHill);
+        for mv in ["e4", "e5", "Ke2", "d5", "Ke3", "dxe4"] {
+            game.make_move_from_str(mv, false).unwrap();
+            assert_eq!(game.status(), Status::Ongoing);
+        }
+        game.make_move_from_str("Kxe4", false).unwrap();
+        assert_eq!(game.status(), Status::VariantWin(Color::White));
+    }
+
+    #[test]
+    fn is_status_three_check_win() {
+        let mut game = Game::new_multi().with_variant(Variant::ThreeCheck);
+        for mv in ["e4", "d5", "Bb5+", "c6", "Bxc6+", "Nxc6", "Qh5"] {
+            game.make_move_from_str(mv, false).unwrap();
+            assert_eq!(game.status(), Status::Ongoing);
+        }
+        assert_eq!(game.variant_state().checks(Color::White), 2);
+        game.make_move_from_str("Qa5", false).unwrap();
+        game.make_move_from_str("Qxf7+", false).unwrap();
+        assert_eq!(game.status(), Status::VariantWin(Color::White));
+        game.undo().unwrap();
+        assert_eq!(game.status(), Status::Ongoing);
+    }
+
     #[test]
     fn en_passant_move() {
         let mut game = Game::new_multi();
@@ -410,6 +1635,181 @@ mod tests {
         );
     }
 
+    #[test]
+    fn accepted_takeback_restores_state() {
+        let mut game = Game::new_multi();
+        game.make_move_from_str("e4", false).unwrap();
+        let previous_board = *game.board();
+        game.make_move_from_str("e5", false).unwrap();
+        game.request_takeback().unwrap();
+        assert_eq!(game.takeback_request(), Some(Color::Black));
+        assert_eq!(game.request_takeback(), Err(ChessError::TakebackPending));
+        game.accept_takeback().unwrap();
+        assert!(game.board() == &previous_board && game.turn() == Color::Black);
+        assert_eq!(game.takeback_request(), None);
+    }
+
+    #[test]
+    fn declined_takeback_keeps_state() {
+        let mut game = Game::new_multi();
+        assert_eq!(game.request_takeback(), Err(ChessError::NothingToUndo));
+        game.make_move_from_str("e4", false).unwrap();
+        let board = *game.board();
+        game.request_takeback().unwrap();
+        game.decline_takeback().unwrap();
+        assert!(game.board() == &board && game.turn() == Color::Black);
+        assert_eq!(game.accept_takeback(), Err(ChessError::NoTakebackRequested));
+    }
+
+    #[test]
+    fn moving_declines_takeback() {
+        let mut game = Game::new_multi();
+        game.make_move_from_str("e4", false).unwrap();
+        game.request_takeback().unwrap();
+        game.make_move_from_str("e5", false).unwrap();
+        assert_eq!(game.takeback_request(), None);
+    }
+
+    #[test]
+    fn branching_keeps_variations() {
+        let mut game = Game::new_multi();
+        for mv in ["e4", "e5", "Nf3"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        assert_eq!(game.branch(4), Err(ChessError::NoSuchPly(4)));
+        game.branch(1).unwrap();
+        assert_eq!(game.moves().len(), 1);
+        game.make_move_from_str("c5", false).unwrap();
+        game.branch(1).unwrap();
+        let e5 = ChessMove::from_str("e7e5").unwrap();
+        let c5 = ChessMove::from_str("c7c5").unwrap();
+        assert_eq!(game.variations(), [e5, c5]);
+        // Playing the move again follows the line it starts.
+        game.make_move(e5).unwrap();
+        assert_eq!(game.variations().len(), 1);
+        assert_eq!(game.move_tree().main_line().len(), 3);
+        // A takeback removes the move with what followed it.
+        game.undo().unwrap();
+        assert_eq!(game.variations(), [c5]);
+    }
+
+    #[test]
+    fn move_times_follow_history() {
+        let mut game = Game::new_multi();
+        std::thread::sleep(Duration::from_millis(20));
+        game.make_move_from_str("e4", false).unwrap();
+        game.make_move_from_str("e5", false).unwrap();
+        assert_eq!(game.move_times().len(), 2);
+        assert!(game.move_times()[0] >= Duration::from_millis(20));
+        assert!(game.move_times()[1] < game.move_times()[0]);
+        game.undo().unwrap();
+        assert_eq!(game.move_times().len(), 1);
+    }
+
+    #[test]
+    fn svg_frames_cover_every_position() {
+        let mut game = Game::new_multi();
+        game.make_move_from_str("e4", false).unwrap();
+        game.make_move_from_str("e5", false).unwrap();
+        let options = SvgOptions::default();
+        let frames = game.render_svg_frames(&options);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], Game::new_multi().render_svg(&options));
+        assert_eq!(frames[2], game.render_svg(&options));
+    }
+
+    #[test]
+    fn listeners_receive_events() {
+        use std::sync::{Arc, Mutex};
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut game = Game::new_multi();
+        let received = Arc::clone(&events);
+        game.subscribe(move |event| received.lock().unwrap().push(*event));
+
+        // Fool's mate
+        for mv in ["f3", "e5", "g4"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        game.undo().unwrap();
+        game.make_move_from_str("g4", false).unwrap();
+        game.make_move_from_str("Qh4", false).unwrap();
+
+        let g2g4 = ChessMove::from_str("g2g4").unwrap();
+        let d8h4 = ChessMove::from_str("d8h4").unwrap();
+        assert_eq!(
+            events.lock().unwrap()[2..],
+            [
+                GameEvent::MoveMade(Color::White, g2g4),
+                GameEvent::MoveUndone(g2g4),
+                GameEvent::MoveMade(Color::White, g2g4),
+                GameEvent::MoveMade(Color::Black, d8h4),
+                GameEvent::CheckGiven(Color::Black),
+                GameEvent::GameEnded(Status::Checkmate(Color::Black)),
+            ]
+        );
+
+        // Listeners stay for the next game.
+        events.lock().unwrap().clear();
+        game.rematch();
+        game.make_move_from_str("e4", false).unwrap();
+        let e2e4 = ChessMove::from_str("e2e4").unwrap();
+        assert_eq!(
+            events.lock().unwrap()[..],
+            [
+                GameEvent::Reset,
+                GameEvent::ColorsSwapped,
+                GameEvent::MoveMade(Color::White, e2e4),
+            ]
+        );
+    }
+
+    #[test]
+    fn two_step_move() {
+        let mut game = Game::new_multi();
+        assert_eq!(
+            game.select(Square::E7),
+            Err(ChessError::IllegalMove(IllegalMoveReason::OpponentsPiece(
+                Square::E7,
+                Color::Black
+            )))
+        );
+        assert_eq!(
+            game.select(Square::A1),
+            Err(ChessError::NoMovesFrom(Square::A1))
+        );
+        assert_eq!(
+            game.move_selected(Square::E4),
+            Err(ChessError::NothingSelected)
+        );
+        game.select(Square::E2).unwrap();
+        assert_eq!(game.selected_destinations(), [Square::E3, Square::E4]);
+        assert!(game.move_selected(Square::E5).is_err());
+        game.move_selected(Square::E4).unwrap();
+        assert_eq!(game.selection(), None);
+        assert_eq!(game.moves(), &[ChessMove::from_str("e2e4").unwrap()]);
+    }
+
+    #[test]
+    fn two_step_promotion() {
+        let board = Board::from_str("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut game = Game::new_multi().with_position(board);
+        game.select(Square::B7).unwrap();
+        game.move_selected(Square::B8).unwrap();
+        assert_eq!(game.board().piece_on(Square::B8), Some(Piece::Queen));
+    }
+
+    #[test]
+    fn game_from_odds_position() {
+        let board = Odds::PawnAndMove.starting_board(Color::White);
+        let mut game = Game::new_multi().with_position(board);
+        assert_eq!(game.turn(), Color::Black);
+        game.make_move_from_str("e5", false).unwrap();
+        game.make_move_from_str("e4", false).unwrap();
+        assert_eq!(game.starting_position(), &board);
+        assert_eq!(game.turn(), Color::Black);
+    }
+
     #[test]
     fn undo_restores_state() {
         let mut game = Game::new_multi();
@@ -420,4 +1820,18 @@ mod tests {
         game.undo().unwrap();
         assert!(game.board() == &previous_board && game.turn() == previous_turn);
     }
+
+    #[test]
+    fn scratch_board_leaves_the_game_alone() {
+        let mut game = Game::new_multi();
+        game.make_move_from_str("e4", false).unwrap();
+        let mut scratch = game.scratch(Color::White);
+        assert_eq!(scratch.moves(), game.moves());
+        scratch.make_move_from_str("e5", false).unwrap();
+        assert!(scratch.get_ai_move().is_ok());
+        scratch.undo().unwrap();
+        scratch.undo().unwrap();
+        assert_eq!(game.moves().len(), 1);
+        assert_eq!(game.turn(), Color::Black);
+    }
 }
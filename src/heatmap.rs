@@ -0,0 +1,86 @@
+use crate::motif::attacks;
+use chess::{Board, Color, Square, ALL_SQUARES};
+
+/// How many pieces of each side attack every square of a board: for a
+/// square holding a piece, how often it's attacked by one side and
+/// defended by the other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heatmap {
+    /// The number of attackers of each square, indexed by color and
+    /// square.
+    counts: [[u8; 64]; 2],
+}
+
+impl Heatmap {
+    /// Counts the attackers of every square on `board`, from the squares
+    /// each piece attacks as the move generator sees them. Pieces in the
+    /// way stop sliding pieces, batteries only count their front piece.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let heatmap = Heatmap::new(&Board::default());
+    /// // The e and g pawns and the knight.
+    /// assert_eq!(heatmap.attackers(Color::White, Square::F3), 3);
+    /// ```
+    pub fn new(board: &Board) -> Self {
+        let mut counts = [[0; 64]; 2];
+        let occupied = *board.combined();
+        for from in occupied {
+            let (Some(piece), Some(color)) = (board.piece_on(from), board.color_on(from)) else {
+                continue;
+            };
+            for square in attacks(piece, color, from, occupied) {
+                counts[color.to_index()][square.to_index()] += 1;
+            }
+        }
+        Self { counts }
+    }
+
+    /// Returns how many pieces of `color` attack `square`.
+    pub fn attackers(&self, color: Color, square: Square) -> u8 {
+        self.counts[color.to_index()][square.to_index()]
+    }
+
+    /// Returns how many more pieces of White than of Black attack
+    /// `square`, negative if Black controls it.
+    pub fn balance(&self, square: Square) -> i32 {
+        i32::from(self.attackers(Color::White, square))
+            - i32::from(self.attackers(Color::Black, square))
+    }
+
+    /// Returns the squares `color` attacks more often than its opponent.
+    pub fn controlled(&self, color: Color) -> Vec<Square> {
+        ALL_SQUARES
+            .into_iter()
+            .filter(|square| match color {
+                Color::White => self.balance(*square) > 0,
+                Color::Black => self.balance(*square) < 0,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn counts_attackers_and_defenders() {
+        let heatmap = Heatmap::new(&Board::default());
+        assert_eq!(heatmap.attackers(Color::White, Square::F3), 3);
+        assert_eq!(heatmap.attackers(Color::Black, Square::F3), 0);
+        // The queen defends the king's pawn, blocked pieces don't.
+        assert_eq!(heatmap.attackers(Color::White, Square::D2), 4);
+        assert_eq!(heatmap.attackers(Color::White, Square::D4), 0);
+        assert_eq!(heatmap.controlled(Color::Black).len(), 22);
+
+        // The rook behind the queen doesn't count.
+        let board = Board::from_str("4k3/8/8/3p4/8/8/3Q4/3RK3 w - - 0 1").unwrap();
+        let heatmap = Heatmap::new(&board);
+        assert_eq!(heatmap.attackers(Color::White, Square::D5), 1);
+        assert_eq!(heatmap.attackers(Color::Black, Square::D5), 0);
+        assert_eq!(heatmap.balance(Square::E4), -1);
+    }
+}
@@ -0,0 +1,428 @@
+use crate::notation::san_for_move;
+use chess::{Board, ChessMove, Color, Piece};
+use std::env;
+use std::str::FromStr;
+
+/// A language rChess talks to the player in.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    Polish,
+}
+
+impl FromStr for Language {
+    type Err = ();
+
+    /// Reads a language by its ISO 639-1 code or its name, in English or
+    /// in itself, e.g. `de`, `german` or `deutsch`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "en" | "english" => Ok(Language::English),
+            "de" | "german" | "deutsch" => Ok(Language::German),
+            "pl" | "polish" | "polski" => Ok(Language::Polish),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Language {
+    /// Reads the language of a POSIX locale, e.g. `de_DE.UTF-8`, falling
+    /// back to English for languages rChess doesn't speak.
+    pub fn from_locale(locale: &str) -> Self {
+        let code = locale
+            .split(['_', '.', '@', '-'])
+            .next()
+            .unwrap_or_default();
+        code.parse().unwrap_or_default()
+    }
+
+    /// Returns the language of the user's locale, from `LC_ALL`,
+    /// `LC_MESSAGES` or `LANG`, whichever is set first.
+    pub fn detect() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|locale| !locale.is_empty())
+            .map(|locale| Self::from_locale(&locale))
+            .unwrap_or_default()
+    }
+
+    /// Returns the letter `piece` is written with in SAN, e.g. `S` for
+    /// the knight (Springer) in German. Pawns have none, so their
+    /// English letter is returned.
+    pub fn piece_letter(self, piece: Piece) -> char {
+        let letters = match self {
+            Language::English => ['P', 'N', 'B', 'R', 'Q', 'K'],
+            Language::German => ['P', 'S', 'L', 'T', 'D', 'K'],
+            Language::Polish => ['P', 'S', 'G', 'W', 'H', 'K'],
+        };
+        letters[piece.to_index()]
+    }
+
+    /// Rewrites the piece letters of a move in English SAN in this
+    /// language, e.g. `Nxe5+` as `Sxe5+` in German.
+    pub fn localize_san(self, san: &str) -> String {
+        san.chars()
+            .map(|c| match piece_from_letter(Language::English, c) {
+                Some(piece) => self.piece_letter(piece),
+                None => c,
+            })
+            .collect()
+    }
+
+    /// Rewrites the piece letters of a move typed in this language in
+    /// English, so it can be read as SAN, e.g. `Dd1-h5` as `Qd1-h5` in
+    /// German. Letters the language doesn't use are kept, so English
+    /// moves are read as well.
+    pub fn read_move(self, text: &str) -> String {
+        text.chars()
+            .map(|c| match piece_from_letter(self, c) {
+                Some(piece) if piece != Piece::Pawn => Language::English.piece_letter(piece),
+                _ => c,
+            })
+            .collect()
+    }
+
+    /// Returns `message` in this language with each `{}` replaced by the
+    /// next of `arguments`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// println!("{}", Language::German.fill(Text::OpponentPlayed, &["KI", "Sf3"]));
+    /// ```
+    pub fn fill(self, message: Text, arguments: &[&str]) -> String {
+        let mut parts = self.text(message).split("{}");
+        let mut filled = parts.next().unwrap_or_default().to_string();
+        for (part, argument) in parts.zip(arguments.iter().chain(std::iter::repeat(&""))) {
+            filled.push_str(argument);
+            filled.push_str(part);
+        }
+        filled
+    }
+
+    /// Returns the name of `color` in this language.
+    pub fn color_name(self, color: Color) -> &'static str {
+        match color {
+            Color::White => self.text(Text::White),
+            Color::Black => self.text(Text::Black),
+        }
+    }
+
+    /// Writes `mv` in SAN with the piece letters of this language.
+    pub fn local_san(self, board: &Board, mv: ChessMove) -> String {
+        self.localize_san(&san_for_move(board, mv))
+    }
+
+    /// Returns `message` in this language.
+    pub fn text(self, message: Text) -> &'static str {
+        use Text::*;
+        match self {
+            Language::English => match message {
+                Welcome => "WELCOME TO CHESS!!",
+                IllegalInput => "Illegal input, please try again.",
+                EnterMove => "Enter move (help for commands): ",
+                Played => "Played: {}",
+                OpponentPlayed => "{} played {}",
+                TookBack => "Took back {}",
+                Check => "Check!",
+                Wins => "Game Over: {} wins!",
+                VariantWins => "Game Over: {} wins ({})!",
+                Resigns => "Game Over: {} resigns, {} wins!",
+                OutOfTime => "Game Over: {} ran out of time, {} wins!",
+                OutOfTimeDraw => {
+                    "Game Over: {} ran out of time, but {} can't checkmate: draw"
+                }
+                DrawAgreed => "Game Over: draw agreed",
+                Stalemate => "Stalemate",
+                White => "White",
+                Black => "Black",
+                ResumeGame => "An unfinished game was found. Resume it? (yes or no): ",
+                SelectMode => "Select game mode (single, multi or match, quit to exit): ",
+                SelectOpponent => "Select the opponent (ai, human or network): ",
+                HostOrJoin => "Host or join? (host or join): ",
+                EnterAddress => "Enter the host's address (host:port): ",
+                EnterRoom => "Enter the room code (leave empty if there's none): ",
+                NumberOfGames => "Number of games (best of): ",
+                SelectVariant => "Select variant (standard, koth or threecheck): ",
+                SelectColor => "Select your color (white or black, random to choose randomly): ",
+                PlayingAs => "You're playing as {}",
+                EnterName => "Enter {}'s name (leave empty to skip): ",
+                SelectDifficulty => "Select difficulty (random, greedy, beginner, easy, medium, hard, adaptive or an Elo from {} to {}): ",
+                SelectStyle => "Select the AI's style (balanced, aggressive, positional, gambit or drawish): ",
+                SelectOdds => "Select odds (none, pawn, knight or queen): ",
+                OddsGiver => "Who gives the odds? (me or ai): ",
+                TimeControl => "Enter your time control in minutes+increment, e.g. 10+5 (leave empty to play without a clock): ",
+                AiTimeControl => "Enter the AI's time control, e.g. 1 for time odds (leave empty for the same): ",
+                ProfileName => "Enter your name to track your rating (leave empty to skip): ",
+                ProfileNameDefault => "Enter your name to track your rating (leave empty for {}): ",
+                WelcomeBack => "Welcome back, {}! Your rating is {}, suggested difficulty: {}",
+                TakebackRequest => "{} requests a takeback, {} to answer (accept or decline): ",
+                DrawOffer => "{} offers a draw, {} to answer (accept or decline): ",
+                AiOffersDraw => "The AI offers a draw (accept or decline): ",
+                AiAcceptsDraw => "The AI accepts the draw",
+                AiDeclinesDraw => "The AI declines the draw",
+                PremoveHint => "You can enter your next move while the AI is thinking",
+                Premove => "Premove: {}",
+                PremoveDiscarded => "Premove {} discarded: {}",
+                Score => "Score: {}",
+                GameOf => "Game {} of {}",
+                MatchWon => "{} wins the match: {}",
+                MatchDrawn => "The match is drawn: {}",
+                SaveMatch => "Save the games of the match to a PGN file (leave empty to skip): ",
+                SavedGames => "Saved {} games to {}",
+                NowPlays => "{} now plays {}",
+                Rematch => "Play a rematch with colors swapped? (yes or no): ",
+                Accuracy => "Your accuracy: {}%",
+            },
+            Language::German => match message {
+                Welcome => "WILLKOMMEN ZUM SCHACH!!",
+                IllegalInput => "Ungültige Eingabe, bitte noch einmal.",
+                EnterMove => "Zug eingeben (help für Befehle): ",
+                Played => "Gespielt: {}",
+                OpponentPlayed => "{} spielt {}",
+                TookBack => "{} zurückgenommen",
+                Check => "Schach!",
+                Wins => "Partie beendet: {} gewinnt!",
+                VariantWins => "Partie beendet: {} gewinnt ({})!",
+                Resigns => "Partie beendet: {} gibt auf, {} gewinnt!",
+                OutOfTime => "Partie beendet: {} hat die Zeit überschritten, {} gewinnt!",
+                OutOfTimeDraw => {
+                    "Partie beendet: {} hat die Zeit überschritten, aber {} kann nicht mattsetzen: Remis"
+                }
+                DrawAgreed => "Partie beendet: Remis vereinbart",
+                Stalemate => "Patt",
+                White => "Weiß",
+                Black => "Schwarz",
+                ResumeGame => "Eine unvollendete Partie wurde gefunden. Fortsetzen? (yes oder no): ",
+                SelectMode => "Spielmodus wählen (single, multi oder match, quit zum Beenden): ",
+                SelectOpponent => "Gegner wählen (ai, human oder network): ",
+                HostOrJoin => "Partie eröffnen oder beitreten? (host oder join): ",
+                EnterAddress => "Adresse des Gastgebers eingeben (host:port): ",
+                EnterRoom => "Raumcode eingeben (leer lassen, wenn es keinen gibt): ",
+                NumberOfGames => "Anzahl der Partien (best of): ",
+                SelectVariant => "Variante wählen (standard, koth oder threecheck): ",
+                SelectColor => "Farbe wählen (white oder black, random für eine zufällige): ",
+                PlayingAs => "Du spielst {}",
+                EnterName => "Namen für {} eingeben (leer lassen zum Überspringen): ",
+                SelectDifficulty => "Schwierigkeit wählen (random, greedy, beginner, easy, medium, hard, adaptive oder eine Elo von {} bis {}): ",
+                SelectStyle => "Spielstil der KI wählen (balanced, aggressive, positional, gambit oder drawish): ",
+                SelectOdds => "Vorgabe wählen (none, pawn, knight oder queen): ",
+                OddsGiver => "Wer gibt die Vorgabe? (me oder ai): ",
+                TimeControl => "Bedenkzeit in Minuten+Inkrement eingeben, z. B. 10+5 (leer lassen für eine Partie ohne Uhr): ",
+                AiTimeControl => "Bedenkzeit der KI eingeben, z. B. 1 für eine Zeitvorgabe (leer lassen für dieselbe): ",
+                ProfileName => "Namen eingeben, um deine Wertung zu verfolgen (leer lassen zum Überspringen): ",
+                ProfileNameDefault => "Namen eingeben, um deine Wertung zu verfolgen (leer lassen für {}): ",
+                WelcomeBack => "Willkommen zurück, {}! Deine Wertung ist {}, empfohlene Schwierigkeit: {}",
+                TakebackRequest => "{} möchte einen Zug zurücknehmen, {} antwortet (accept oder decline): ",
+                DrawOffer => "{} bietet Remis an, {} antwortet (accept oder decline): ",
+                AiOffersDraw => "Die KI bietet Remis an (accept oder decline): ",
+                AiAcceptsDraw => "Die KI nimmt das Remis an",
+                AiDeclinesDraw => "Die KI lehnt das Remis ab",
+                PremoveHint => "Du kannst deinen nächsten Zug eingeben, während die KI nachdenkt",
+                Premove => "Vorauszug: {}",
+                PremoveDiscarded => "Vorauszug {} verworfen: {}",
+                Score => "Stand: {}",
+                GameOf => "Partie {} von {}",
+                MatchWon => "{} gewinnt den Wettkampf: {}",
+                MatchDrawn => "Der Wettkampf endet unentschieden: {}",
+                SaveMatch => "Partien des Wettkampfs als PGN-Datei speichern (leer lassen zum Überspringen): ",
+                SavedGames => "{} Partien in {} gespeichert",
+                NowPlays => "{} spielt jetzt {}",
+                Rematch => "Revanche mit getauschten Farben? (yes oder no): ",
+                Accuracy => "Deine Genauigkeit: {}%",
+            },
+            Language::Polish => match message {
+                Welcome => "WITAJ W SZACHACH!!",
+                IllegalInput => "Niepoprawne dane, spróbuj ponownie.",
+                EnterMove => "Podaj ruch (help – lista poleceń): ",
+                Played => "Zagrano: {}",
+                OpponentPlayed => "{} gra {}",
+                TookBack => "Cofnięto {}",
+                Check => "Szach!",
+                Wins => "Koniec gry: wygrywają {}!",
+                VariantWins => "Koniec gry: wygrywają {} ({})!",
+                Resigns => "Koniec gry: {} poddają się, wygrywają {}!",
+                OutOfTime => "Koniec gry: {} przekroczyły czas, wygrywają {}!",
+                OutOfTimeDraw => {
+                    "Koniec gry: {} przekroczyły czas, ale {} nie mogą dać mata: remis"
+                }
+                DrawAgreed => "Koniec gry: remis za zgodą",
+                Stalemate => "Pat",
+                White => "białe",
+                Black => "czarne",
+                ResumeGame => "Znaleziono niedokończoną partię. Wznowić ją? (yes lub no): ",
+                SelectMode => "Wybierz tryb gry (single, multi lub match, quit, aby wyjść): ",
+                SelectOpponent => "Wybierz przeciwnika (ai, human lub network): ",
+                HostOrJoin => "Utworzyć grę czy dołączyć? (host lub join): ",
+                EnterAddress => "Podaj adres gospodarza (host:port): ",
+                EnterRoom => "Podaj kod pokoju (zostaw puste, jeśli go nie ma): ",
+                NumberOfGames => "Liczba partii (best of): ",
+                SelectVariant => "Wybierz wariant (standard, koth lub threecheck): ",
+                SelectColor => "Wybierz kolor (white lub black, random, aby wylosować): ",
+                PlayingAs => "Twój kolor: {}",
+                EnterName => "Podaj imię gracza – {} (zostaw puste, aby pominąć): ",
+                SelectDifficulty => "Wybierz poziom trudności (random, greedy, beginner, easy, medium, hard, adaptive lub ranking Elo od {} do {}): ",
+                SelectStyle => "Wybierz styl gry AI (balanced, aggressive, positional, gambit lub drawish): ",
+                SelectOdds => "Wybierz fory (none, pawn, knight lub queen): ",
+                OddsGiver => "Kto daje fory? (me lub ai): ",
+                TimeControl => "Podaj swoje tempo gry w minutach+dodatku, np. 10+5 (zostaw puste, aby grać bez zegara): ",
+                AiTimeControl => "Podaj tempo gry AI, np. 1 dla forów czasowych (zostaw puste, aby było takie samo): ",
+                ProfileName => "Podaj imię, aby śledzić swój ranking (zostaw puste, aby pominąć): ",
+                ProfileNameDefault => "Podaj imię, aby śledzić swój ranking (zostaw puste dla: {}): ",
+                WelcomeBack => "Witaj ponownie, {}! Twój ranking to {}, sugerowany poziom trudności: {}",
+                TakebackRequest => "{} proszą o cofnięcie ruchu, odpowiadają {} (accept lub decline): ",
+                DrawOffer => "{} proponują remis, odpowiadają {} (accept lub decline): ",
+                AiOffersDraw => "AI proponuje remis (accept lub decline): ",
+                AiAcceptsDraw => "AI przyjmuje remis",
+                AiDeclinesDraw => "AI odrzuca remis",
+                PremoveHint => "Możesz podać następny ruch, gdy AI myśli",
+                Premove => "Ruch z wyprzedzeniem: {}",
+                PremoveDiscarded => "Odrzucono ruch z wyprzedzeniem {}: {}",
+                Score => "Wynik: {}",
+                GameOf => "Partia {} z {}",
+                MatchWon => "{} wygrywa mecz: {}",
+                MatchDrawn => "Mecz zakończył się remisem: {}",
+                SaveMatch => "Zapisz partie meczu do pliku PGN (zostaw puste, aby pominąć): ",
+                SavedGames => "Zapisano partie ({}) w pliku {}",
+                NowPlays => "{} gra teraz – {}",
+                Rematch => "Rewanż z zamienionymi kolorami? (yes lub no): ",
+                Accuracy => "Twoja dokładność: {}%",
+            },
+        }
+    }
+}
+
+/// Returns the piece written with `letter` in SAN in `language`.
+fn piece_from_letter(language: Language, letter: char) -> Option<Piece> {
+    chess::ALL_PIECES
+        .into_iter()
+        .find(|piece| language.piece_letter(*piece) == letter)
+}
+
+/// A user-facing message, see [`Language::text`]. Messages with `{}`
+/// take arguments, filled in by [`Language::fill`].
+///
+/// These cover playing games at the terminal: the menus setting a game
+/// up, prompts and announcements during it and the score of a series.
+/// Error messages, the in-game help, connecting to network games and
+/// the tools and trainers started as subcommands, e.g. `bench` or
+/// `coords`, are only in English. Answers to prompts, like `yes` or `accept`, are typed in
+/// English in every language.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Text {
+    Welcome,
+    IllegalInput,
+    EnterMove,
+    /// The move just played, e.g. `Played: Nf3`.
+    Played,
+    /// The opponent's name and move.
+    OpponentPlayed,
+    /// The move taken back.
+    TookBack,
+    Check,
+    /// The winner.
+    Wins,
+    /// The winner and the variant they won by.
+    VariantWins,
+    /// The side resigning and the winner.
+    Resigns,
+    /// The side out of time and the winner.
+    OutOfTime,
+    /// The side out of time and the side that can't checkmate.
+    OutOfTimeDraw,
+    DrawAgreed,
+    Stalemate,
+    White,
+    Black,
+    ResumeGame,
+    SelectMode,
+    SelectOpponent,
+    HostOrJoin,
+    EnterAddress,
+    EnterRoom,
+    NumberOfGames,
+    SelectVariant,
+    SelectColor,
+    /// The player's color.
+    PlayingAs,
+    /// The color of the player asked for.
+    EnterName,
+    /// The lowest and the highest Elo.
+    SelectDifficulty,
+    SelectStyle,
+    SelectOdds,
+    OddsGiver,
+    TimeControl,
+    AiTimeControl,
+    ProfileName,
+    /// The name taken if none is entered.
+    ProfileNameDefault,
+    /// The player's name, rating and suggested difficulty.
+    WelcomeBack,
+    /// The side asking and the side answering.
+    TakebackRequest,
+    /// The side offering and the side answering.
+    DrawOffer,
+    AiOffersDraw,
+    AiAcceptsDraw,
+    AiDeclinesDraw,
+    PremoveHint,
+    /// The premove played.
+    Premove,
+    /// The premove and why it can't be played.
+    PremoveDiscarded,
+    /// The standings.
+    Score,
+    /// The number of the next game and of all games.
+    GameOf,
+    /// The winner and the standings.
+    MatchWon,
+    /// The standings.
+    MatchDrawn,
+    SaveMatch,
+    /// The number of games and the file.
+    SavedGames,
+    /// The player and their new color.
+    NowPlays,
+    Rematch,
+    /// The player's accuracy in percent.
+    Accuracy,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_piece_letters() {
+        assert_eq!(Language::German.localize_san("Nxe5+"), "Sxe5+");
+        assert_eq!(Language::Polish.localize_san("exd8=Q#"), "exd8=H#");
+        assert_eq!(Language::German.localize_san("O-O"), "O-O");
+        assert_eq!(Language::German.read_move("Dd1-h5"), "Qd1-h5");
+        assert_eq!(Language::Polish.read_move("Gb5"), "Bb5");
+        // English letters the language doesn't use still work.
+        assert_eq!(Language::German.read_move("Nf3"), "Nf3");
+        assert_eq!(Language::from_locale("de_DE.UTF-8"), Language::German);
+        assert_eq!(Language::from_locale("pl"), Language::Polish);
+        assert_eq!(Language::from_locale("C"), Language::English);
+        assert_eq!("polski".parse(), Ok(Language::Polish));
+        assert_eq!(
+            Language::English.fill(Text::Resigns, &["Black", "White"]),
+            "Game Over: Black resigns, White wins!"
+        );
+        let polish = Language::Polish;
+        assert_eq!(
+            polish.fill(
+                Text::TakebackRequest,
+                &[
+                    polish.color_name(Color::White),
+                    polish.color_name(Color::Black)
+                ]
+            ),
+            "białe proszą o cofnięcie ruchu, odpowiadają czarne (accept lub decline): "
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use crate::legality::validate_fen;
+use crate::notation::san_for_move;
+use crate::pgn::{parse_pgn, PgnGame};
+use chess::{Board, ChessMove, Piece, ALL_SQUARES};
+use std::process::Command;
+
+/// Characters of Chess.com's compact move encoding: the first 64 stand
+/// for the squares from a1 to h8, the next ones for promotions.
+const TCN: &[u8] =
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!?{~}(^)[_]@#$,./&-*++=";
+/// Promotion pieces in the order Chess.com encodes them.
+const TCN_PROMOTIONS: [Piece; 4] = [Piece::Queen, Piece::Knight, Piece::Rook, Piece::Bishop];
+
+/// A game on a chess server that can be imported.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    /// A Lichess game, by its 8-character ID.
+    Lichess(String),
+    /// A Chess.com game, by its number and whether it's a daily game.
+    ChessCom { id: u64, daily: bool },
+}
+
+impl Source {
+    /// Reads a game's URL, e.g. `https://lichess.org/q7ZvsdUF/black` or
+    /// `https://www.chess.com/game/live/123456789`, or a bare Lichess
+    /// game ID.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// assert_eq!(
+    ///     Source::parse("lichess.org/q7ZvsdUF"),
+    ///     Some(Source::Lichess("q7ZvsdUF".to_string()))
+    /// );
+    /// ```
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let address = input
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("www.");
+        let address = address.split(['?', '#']).next()?;
+        let lichess_id = |id: &str| {
+            (matches!(id.len(), 8 | 12) && id.chars().all(|c| c.is_ascii_alphanumeric()))
+                .then(|| Source::Lichess(id[..8].to_string()))
+        };
+        if let Some(path) = address.strip_prefix("lichess.org/") {
+            return lichess_id(path.split('/').next()?);
+        }
+        if let Some(path) = address.strip_prefix("chess.com/") {
+            let segments: Vec<&str> = path.split('/').collect();
+            return Some(Source::ChessCom {
+                id: segments.last()?.parse().ok()?,
+                daily: segments.contains(&"daily"),
+            });
+        }
+        lichess_id(input)
+    }
+
+    /// Returns the address the game is downloaded from: Lichess' game
+    /// export API, or for Chess.com the data its game viewer loads, as
+    /// its public API has no way to look up a single game.
+    fn download_url(&self) -> String {
+        match self {
+            Source::Lichess(id) => format!("https://lichess.org/game/export/{}", id),
+            Source::ChessCom { id, daily } => format!(
+                "https://www.chess.com/callback/{}/game/{}",
+                if *daily { "daily" } else { "live" },
+                id
+            ),
+        }
+    }
+}
+
+/// Downloads the game at `url`, see [`Source::parse`].
+///
+/// # Errors
+///
+/// Returns an error describing an unrecognized URL, a failed download
+/// or a game that can't be read.
+pub fn import(url: &str) -> Result<PgnGame, String> {
+    let source =
+        Source::parse(url).ok_or_else(|| format!("not a Lichess or Chess.com game: {}", url))?;
+    let text = download(&source.download_url())?;
+    match source {
+        Source::Lichess(_) => parse_pgn(&text)
+            .into_iter()
+            .next()
+            .ok_or_else(|| "no game found".to_string()),
+        Source::ChessCom { .. } => chess_com_game(&text),
+    }
+}
+
+/// Downloads `url` with `curl`.
+fn download(url: &str) -> Result<String, String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", url])
+        .output()
+        .map_err(|e| format!("error running curl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "downloading {} failed: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|_| format!("{} is not text", url))
+}
+
+/// Reads the game data loaded by Chess.com's game viewer: the PGN tags
+/// under `pgnHeaders` and the moves in its compact encoding under
+/// `moveList`.
+fn chess_com_game(json: &str) -> Result<PgnGame, String> {
+    let invalid = || "unexpected game data".to_string();
+    let headers = json
+        .find("\"pgnHeaders\":{")
+        .map(|start| &json[start + "\"pgnHeaders\":{".len()..])
+        .ok_or_else(invalid)?;
+    let tags = json_pairs(headers).ok_or_else(invalid)?;
+    let mut game = PgnGame {
+        tags,
+        ..PgnGame::default()
+    };
+    let mut board = match game.tag("FEN") {
+        Some(fen) => validate_fen(fen).map_err(|e| format!("invalid FEN {}: {}", fen, e))?,
+        None => Board::default(),
+    };
+    let moves = json_string(json, "moveList").ok_or_else(invalid)?;
+    for mv in decode_moves(&moves).ok_or_else(invalid)? {
+        if !board.legal(mv) {
+            return Err(format!("illegal move {}", mv));
+        }
+        game.moves.push(san_for_move(&board, mv));
+        board = board.make_move_new(mv);
+    }
+    game.result = game.tag("Result").map(str::to_string);
+    Ok(game)
+}
+
+/// Decodes moves in Chess.com's compact encoding, two characters each:
+/// the source square and either the destination or, for promotions,
+/// the piece and the direction the pawn moves in.
+///
+/// # Example
+///
+/// ```
+/// let moves = decode_moves("mC0K").unwrap();
+/// assert_eq!(moves[0], ChessMove::from_str("e2e4").unwrap());
+/// ```
+fn decode_moves(moves: &str) -> Option<Vec<ChessMove>> {
+    let indexes: Vec<usize> = moves
+        .bytes()
+        .map(|c| TCN.iter().position(|t| *t == c))
+        .collect::<Option<_>>()?;
+    indexes
+        .chunks(2)
+        .map(|pair| {
+            let [source, dest] = *pair else {
+                return None;
+            };
+            if dest < 64 {
+                return Some(ChessMove::new(
+                    *ALL_SQUARES.get(source)?,
+                    ALL_SQUARES[dest],
+                    None,
+                ));
+            }
+            let promotion = *TCN_PROMOTIONS.get((dest - 64) / 3)?;
+            // Left, straight ahead or right, as seen from White's side.
+            let file_step = (dest - 64) % 3;
+            let ahead = if source < 16 {
+                source.checked_sub(8)?
+            } else {
+                source + 8
+            };
+            let dest = (ahead + file_step).checked_sub(1)?;
+            Some(ChessMove::new(
+                *ALL_SQUARES.get(source)?,
+                *ALL_SQUARES.get(dest)?,
+                Some(promotion),
+            ))
+        })
+        .collect()
+}
+
+/// Returns the value of the first string field called `key` in `json`.
+fn json_string(json: &str, key: &str) -> Option<String> {
+    let pattern = format!("\"{}\":\"", key);
+    let start = json.find(&pattern)? + pattern.len();
+    read_string(&json[start..]).map(|(value, _)| value)
+}
+
+/// Reads a JSON string up to its closing quote from `text`, which starts
+/// right after the opening one.
+///
+/// # Returns
+///
+/// The string and the text after it.
+fn read_string(text: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &text[i + 1..])),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                    value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                escaped => value.push(escaped),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Reads the fields of a flat JSON object from `text`, which starts
+/// right after its opening brace, as pairs of names and values. Values
+/// other than strings are kept as written, e.g. `1500`.
+fn json_pairs(text: &str) -> Option<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    let mut rest = text.trim_start();
+    loop {
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+        if rest.starts_with('}') {
+            return Some(pairs);
+        }
+        let (name, after) = read_string(rest.strip_prefix('"')?)?;
+        rest = after.trim_start().strip_prefix(':')?.trim_start();
+        let value = match rest.strip_prefix('"') {
+            Some(string) => {
+                let (value, after) = read_string(string)?;
+                rest = after;
+                value
+            }
+            None => {
+                let end = rest.find([',', '}'])?;
+                let value = rest[..end].trim().to_string();
+                rest = &rest[end..];
+                value
+            }
+        };
+        pairs.push((name, value));
+        rest = rest.trim_start();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn reads_game_urls() {
+        let lichess = Some(Source::Lichess("q7ZvsdUF".to_string()));
+        assert_eq!(Source::parse("https://lichess.org/q7ZvsdUF"), lichess);
+        assert_eq!(Source::parse("lichess.org/q7ZvsdUF/black#32"), lichess);
+        assert_eq!(Source::parse("https://lichess.org/q7ZvsdUFx2Lm"), lichess);
+        assert_eq!(Source::parse("q7ZvsdUF"), lichess);
+        assert_eq!(
+            Source::parse("https://www.chess.com/game/live/123456789"),
+            Some(Source::ChessCom {
+                id: 123456789,
+                daily: false
+            })
+        );
+        assert_eq!(
+            Source::parse("https://www.chess.com/game/daily/4242?move=3"),
+            Some(Source::ChessCom {
+                id: 4242,
+                daily: true
+            })
+        );
+        assert_eq!(Source::parse("https://example.com/q7ZvsdUF"), None);
+        assert_eq!(
+            Source::Lichess("q7ZvsdUF".to_string()).download_url(),
+            "https://lichess.org/game/export/q7ZvsdUF"
+        );
+    }
+
+    #[test]
+    fn reads_chess_com_games() {
+        let json = r#"{"game":{"id":1,"moveList":"mC0Kgv5Q","pgnHeaders":{
+            "Event":"Live Chess", "White":"Anna \"A\" Nowak", "Black":"Bob",
+            "WhiteElo":1500, "Result":"1-0"}}}"#;
+        let game = chess_com_game(json).unwrap();
+        assert_eq!(game.moves, ["e4", "e5", "Nf3", "Nc6"]);
+        assert_eq!(game.tag("White"), Some("Anna \"A\" Nowak"));
+        assert_eq!(game.tag("WhiteElo"), Some("1500"));
+        assert_eq!(game.result(), Some("1-0"));
+
+        // b7-b8 promoting to a queen, and g2xh1 to a knight.
+        assert_eq!(
+            decode_moves("X~").unwrap(),
+            [ChessMove::from_str("b7b8q").unwrap()]
+        );
+        assert_eq!(
+            decode_moves("o)").unwrap(),
+            [ChessMove::from_str("g2h1n").unwrap()]
+        );
+        assert_eq!(decode_moves("m"), None);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::ai::{piece_value, EvalWeights};
+use crate::pawns::pawn_square_key;
+use chess::{Board, ChessMove, Color, File, Piece, Square, ALL_COLORS, ALL_PIECES};
+
+/// Piece-square tables in centipawns, indexed by piece and then by
+/// square from White's point of view, a1 first. The king's table is
+/// for the middlegame: it stays behind its pawns.
+#[rustfmt::skip]
+const PIECE_SQUARE_TABLES: [[i32; 64]; 6] = [
+    // Pawn
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+          5,  10,  10, -20, -20,  10,  10,   5,
+          5,  -5, -10,   0,   0, -10,  -5,   5,
+          0,   0,   0,  20,  20,   0,   0,   0,
+          5,   5,  10,  25,  25,  10,   5,   5,
+         10,  10,  20,  30,  30,  20,  10,  10,
+         50,  50,  50,  50,  50,  50,  50,  50,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // Knight
+    [
+        -50, -40, -30, -30, -30, -30, -40, -50,
+        -40, -20,   0,   5,   5,   0, -20, -40,
+        -30,   5,  10,  15,  15,  10,   5, -30,
+        -30,   0,  15,  20,  20,  15,   0, -30,
+        -30,   5,  15,  20,  20,  15,   5, -30,
+        -30,   0,  10,  15,  15,  10,   0, -30,
+        -40, -20,   0,   0,   0,   0, -20, -40,
+        -50, -40, -30, -30, -30, -30, -40, -50,
+    ],
+    // Bishop
+    [
+        -20, -10, -10, -10, -10, -10, -10, -20,
+        -10,   5,   0,   0,   0,   0,   5, -10,
+        -10,  10,  10,  10,  10,  10,  10, -10,
+        -10,   0,  10,  10,  10,  10,   0, -10,
+        -10,   5,   5,  10,  10,   5,   5, -10,
+        -10,   0,   5,  10,  10,   5,   0, -10,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -20, -10, -10, -10, -10, -10, -10, -20,
+    ],
+    // Rook
+    [
+          0,   0,   0,   5,   5,   0,   0,   0,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+          5,  10,  10,  10,  10,  10,  10,   5,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // Queen
+    [
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+        -10,   0,   5,   0,   0,   0,   0, -10,
+        -10,   5,   5,   5,   5,   5,   0, -10,
+          0,   0,   5,   5,   5,   5,   0,  -5,
+         -5,   0,   5,   5,   5,   5,   0,  -5,
+        -10,   0,   5,   5,   5,   5,   0, -10,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+    ],
+    // King
+    [
+         20,  30,  10,   0,   0,  10,  30,  20,
+         20,  20,   0,   0,   0,   0,  20,  20,
+        -10, -20, -20, -20, -20, -20, -20, -10,
+        -20, -30, -30, -40, -40, -30, -30, -20,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+    ],
+];
+
+/// Returns the piece-square table bonus of `piece` of `color` on `square`.
+pub fn piece_square_value(piece: Piece, color: Color, square: Square) -> i32 {
+    let index = match color {
+        Color::White => square.to_index(),
+        // Black's tables are White's, flipped vertically.
+        Color::Black => square.to_index() ^ 56,
+    };
+    PIECE_SQUARE_TABLES[piece.to_index()][index]
+}
+
+/// The material and piece-square terms of the evaluation, kept up to
+/// date move by move instead of being added up over the whole board at
+/// every position the search evaluates.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IncrementalEval {
+    /// Material of each side in centipawns, indexed by color.
+    material: [i32; 2],
+    /// Piece-square table bonuses of each side, indexed by color.
+    placement: [i32; 2],
+    /// Pawn key of the position, see [`pawn_key`](crate::pawns::pawn_key).
+    pawn_key: u64,
+}
+
+impl IncrementalEval {
+    /// Adds up the terms over the whole board.
+    pub fn new(board: &Board) -> Self {
+        let mut eval = Self::default();
+        for color in ALL_COLORS {
+            for piece in ALL_PIECES {
+                for square in *board.pieces(piece) & board.color_combined(color) {
+                    eval.add(piece, color, square);
+                }
+            }
+        }
+        eval
+    }
+
+    /// Returns the terms after `mv` is played on `board`.
+    ///
+    /// Only the squares the move changes are looked at: the moving piece's
+    /// source and destination, a captured piece (en passant included), a
+    /// promotion and the rook's move when castling.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let board = Board::default();
+    /// let mv = ChessMove::from_str("e2e4").unwrap();
+    /// let eval = IncrementalEval::new(&board).after_move(&board, mv);
+    /// assert_eq!(eval, IncrementalEval::new(&board.make_move_new(mv)));
+    /// ```
+    pub fn after_move(&self, board: &Board, mv: ChessMove) -> Self {
+        let mut next = *self;
+        let (source, dest) = (mv.get_source(), mv.get_dest());
+        let mover = board.side_to_move();
+        let Some(piece) = board.piece_on(source) else {
+            return next;
+        };
+        if let Some(victim) = board.piece_on(dest) {
+            next.remove(victim, !mover, dest);
+        } else if piece == Piece::Pawn && source.get_file() != dest.get_file() {
+            // En passant: the captured pawn is next to the capturing one.
+            next.remove(
+                Piece::Pawn,
+                !mover,
+                Square::make_square(source.get_rank(), dest.get_file()),
+            );
+        }
+        next.remove(piece, mover, source);
+        next.add(mv.get_promotion().unwrap_or(piece), mover, dest);
+        if piece == Piece::King
+            && source
+                .get_file()
+                .to_index()
+                .abs_diff(dest.get_file().to_index())
+                == 2
+        {
+            let (from, to) = if dest.get_file() == File::G {
+                (File::H, File::F)
+            } else {
+                (File::A, File::D)
+            };
+            let rank = source.get_rank();
+            next.remove(Piece::Rook, mover, Square::make_square(rank, from));
+            next.add(Piece::Rook, mover, Square::make_square(rank, to));
+        }
+        next
+    }
+
+    /// Returns the material and piece-square terms in centipawns from the
+    /// point of view of `perspective`, each weighted by `weights`.
+    pub fn score(&self, perspective: Color, weights: &EvalWeights) -> i32 {
+        let (us, them) = (perspective.to_index(), (!perspective).to_index());
+        (self.material[us] - self.material[them]) * weights.material / 100
+            + (self.placement[us] - self.placement[them]) * weights.placement / 100
+    }
+
+    /// Returns the pawn key of the position, kept up to date like the
+    /// other terms.
+    pub fn pawn_key(&self) -> u64 {
+        self.pawn_key
+    }
+
+    fn add(&mut self, piece: Piece, color: Color, square: Square) {
+        self.material[color.to_index()] += piece_value(piece);
+        self.placement[color.to_index()] += piece_square_value(piece, color, square);
+        if piece == Piece::Pawn {
+            self.pawn_key ^= pawn_square_key(color, square);
+        }
+    }
+
+    fn remove(&mut self, piece: Piece, color: Color, square: Square) {
+        self.material[color.to_index()] -= piece_value(piece);
+        self.placement[color.to_index()] -= piece_square_value(piece, color, square);
+        if piece == Piece::Pawn {
+            self.pawn_key ^= pawn_square_key(color, square);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn follows_moves() {
+        // Castling, en passant and a promotion with capture.
+        let games = [
+            (
+                "r3k2r/pppq1ppp/2n2n2/3pp3/4P3/2NP1N2/PPPQ1PPP/R3K2R w KQkq - 0 1",
+                &["e1g1", "e8c8", "f3e5", "c6e5"][..],
+            ),
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                &["e5d6", "e8f7", "d6d7", "f7e7", "d7d8q", "e7d8"][..],
+            ),
+            ("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1", &["a7b8q"][..]),
+        ];
+        for (fen, moves) in games {
+            let mut board = Board::from_str(fen).unwrap();
+            let mut eval = IncrementalEval::new(&board);
+            for uci in moves {
+                let mv = ChessMove::from_str(uci).unwrap();
+                assert!(board.legal(mv), "{} in {}", uci, board);
+                eval = eval.after_move(&board, mv);
+                board = board.make_move_new(mv);
+                assert_eq!(eval, IncrementalEval::new(&board), "{} in {}", uci, fen);
+            }
+        }
+    }
+
+    #[test]
+    fn tables_are_symmetric() {
+        let board = Board::default();
+        let eval = IncrementalEval::new(&board);
+        let weights = EvalWeights {
+            placement: 100,
+            ..EvalWeights::default()
+        };
+        assert_eq!(eval.score(Color::White, &weights), 0);
+        assert_eq!(
+            piece_square_value(Piece::Knight, Color::White, Square::F3),
+            piece_square_value(Piece::Knight, Color::Black, Square::F6)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::annotate::{format_score, mate_in};
+use crate::notation::san_for_move;
+use chess::{Board, ChessMove, Color};
+use std::fmt;
+use std::time::Duration;
+
+/// Progress of a search after an iteration of its iterative deepening,
+/// shown as a UCI `info` line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchInfo {
+    /// Depth of the iteration.
+    pub depth: u32,
+    /// Deepest ply the search reached so far.
+    pub seldepth: u32,
+    /// Score in centipawns from the point of view of the side to move.
+    pub score: i32,
+    /// Positions visited so far.
+    pub nodes: u64,
+    /// Time searched so far.
+    pub time: Duration,
+    /// The line the search expects, starting with the best move.
+    pub pv: Vec<ChessMove>,
+}
+
+impl SearchInfo {
+    /// Returns the positions visited per second.
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.time.as_secs_f64().max(f64::EPSILON)) as u64
+    }
+}
+
+/// Writes the score as UCI does: `mate <moves>`, negative when the side
+/// to move is mated, or `cp <centipawns>`.
+pub fn uci_score(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
+    }
+}
+
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "info depth {} seldepth {} score {} nodes {} nps {} time {}",
+            self.depth,
+            self.seldepth,
+            uci_score(self.score),
+            self.nodes,
+            self.nps(),
+            self.time.as_millis()
+        )?;
+        if !self.pv.is_empty() {
+            write!(f, " pv")?;
+            for mv in &self.pv {
+                write!(f, " {}", mv)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a search reports its progress, so every front-end shows it
+/// from the same [`SearchInfo`].
+pub trait InfoSink {
+    /// Takes the progress after an iteration of the search.
+    fn report(&mut self, info: &SearchInfo);
+}
+
+/// Collects the progress as UCI `info` lines, answered verbatim in UCI
+/// mode.
+impl InfoSink for Vec<String> {
+    fn report(&mut self, info: &SearchInfo) {
+        self.push(info.to_string());
+    }
+}
+
+/// Prints the progress of a search of `board` for people: the score
+/// from White's side and the line in SAN.
+pub struct PrettyInfo {
+    pub board: Board,
+}
+
+impl PrettyInfo {
+    /// Formats `info` as a line of a table, e.g.
+    /// `  5/5    0.35     12345 nodes    800 kn/s  e4 e5 Nf3`.
+    pub fn format(&self, info: &SearchInfo) -> String {
+        let score = match self.board.side_to_move() {
+            Color::White => info.score,
+            Color::Black => -info.score,
+        };
+        let mut board = self.board;
+        let line: Vec<String> = info
+            .pv
+            .iter()
+            .map(|mv| {
+                let san = san_for_move(&board, *mv);
+                board = board.make_move_new(*mv);
+                san
+            })
+            .collect();
+        format!(
+            "{:>3}/{:<3}{:>7}{:>10} nodes{:>7} kn/s  {}",
+            info.depth,
+            info.seldepth,
+            format_score(score),
+            info.nodes,
+            info.nps() / 1000,
+            line.join(" ")
+        )
+    }
+}
+
+impl InfoSink for PrettyInfo {
+    fn report(&mut self, info: &SearchInfo) {
+        println!("{}", self.format(info));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn formats_uci_and_pretty_lines() {
+        let info = SearchInfo {
+            depth: 2,
+            seldepth: 3,
+            score: -35,
+            nodes: 5000,
+            time: Duration::from_millis(250),
+            pv: vec![
+                ChessMove::from_str("e7e5").unwrap(),
+                ChessMove::from_str("g1f3").unwrap(),
+            ],
+        };
+        assert_eq!(
+            info.to_string(),
+            "info depth 2 seldepth 3 score cp -35 nodes 5000 nps 20000 time 250 pv e7e5 g1f3"
+        );
+        let board =
+            Board::from_str("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let pretty = PrettyInfo { board }.format(&info);
+        assert!(pretty.contains("0.35"));
+        assert!(pretty.ends_with("e5 Nf3"));
+        assert_eq!(uci_score(99_999), "mate 1");
+    }
+}
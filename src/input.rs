@@ -0,0 +1,142 @@
+use crate::session;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::cell::RefCell;
+use std::io::{self, IsTerminal, Write};
+use std::process;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// A line read in the background: `None` at the end of input.
+type LineAhead = Receiver<io::Result<Option<String>>>;
+
+thread_local! {
+    /// Line editor shared by all prompts, so they share one history.
+    /// Input that isn't typed in a terminal, e.g. piped in, is read as is.
+    static EDITOR: RefCell<Option<DefaultEditor>> = RefCell::new(
+        io::stdin().is_terminal().then(|| DefaultEditor::new().ok()).flatten()
+    );
+    /// The line being read ahead by [`read_ahead`], until it's taken.
+    static AHEAD: RefCell<Option<LineAhead>> = const { RefCell::new(None) };
+}
+
+/// Shows `prompt` and reads a line typed by the user.
+///
+/// In a terminal the line can be edited before pressing Enter, and
+/// earlier lines recalled with the up and down arrows. Ctrl-C and the
+/// end of input (Ctrl-D) end the program; games in progress are
+/// autosaved after every move, so they can be resumed later.
+///
+/// # Returns
+///
+/// The line without its trailing newline.
+///
+/// # Example
+///
+/// ```
+/// let input = read_line("Enter move: ");
+/// ```
+pub fn read_line(prompt: &str) -> String {
+    if let Some(ahead) = AHEAD.take() {
+        // The user may already be typing the line read ahead.
+        print!("{}", prompt);
+        let _ = io::stdout().flush();
+        let line = ahead.recv().unwrap_or(Ok(None));
+        return finish_read(line_ahead(line));
+    }
+    let result = EDITOR.with_borrow_mut(|editor| match editor {
+        Some(editor) => {
+            let line = editor.readline(prompt)?;
+            if !line.trim().is_empty() {
+                let _ = editor.add_history_entry(line.as_str());
+            }
+            Ok(line)
+        }
+        None => {
+            print!("{}", prompt);
+            io::stdout().flush()?;
+            let mut line = String::new();
+            match io::stdin().read_line(&mut line)? {
+                0 => Err(ReadlineError::Eof),
+                _ => Ok(line.trim_end_matches(['\r', '\n']).to_string()),
+            }
+        }
+    });
+    finish_read(result)
+}
+
+/// Starts reading a line in the background, so the user can type ahead
+/// while the program is busy, e.g. a premove while the opponent thinks.
+/// Does nothing if a line is being read ahead already.
+///
+/// The line is read without line editing, which would garble what's
+/// printed in the meantime. It's given by [`take_line`] once entered,
+/// or else by the next [`read_line`], so no input goes astray.
+pub fn read_ahead() {
+    AHEAD.with_borrow_mut(|ahead| {
+        if ahead.is_none() {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                let mut line = String::new();
+                let result = io::stdin().read_line(&mut line).map(|read| {
+                    (read > 0).then(|| line.trim_end_matches(['\r', '\n']).to_string())
+                });
+                let _ = sender.send(result);
+            });
+            *ahead = Some(receiver);
+        }
+    });
+}
+
+/// Returns the line read ahead by [`read_ahead`], if the user has
+/// entered it by now, or `Some(None)` at the end of input. Unlike
+/// [`read_line`], the end of input doesn't end the program, so a game
+/// waiting for other events meanwhile can end first.
+pub fn take_line() -> Option<Option<String>> {
+    let ahead = AHEAD.take()?;
+    let line = match ahead.try_recv() {
+        Ok(line) => line_ahead(line),
+        Err(TryRecvError::Empty) => {
+            AHEAD.set(Some(ahead));
+            return None;
+        }
+        Err(TryRecvError::Disconnected) => Err(ReadlineError::Eof),
+    };
+    match line {
+        Err(ReadlineError::Eof) => {
+            println!();
+            Some(None)
+        }
+        line => Some(Some(finish_read(line))),
+    }
+}
+
+/// Turns a line read ahead into the result of reading it with the line
+/// editor, adding it to the editor's history.
+fn line_ahead(line: io::Result<Option<String>>) -> Result<String, ReadlineError> {
+    let line = line?.ok_or(ReadlineError::Eof)?;
+    EDITOR.with_borrow_mut(|editor| {
+        if let Some(editor) = editor.as_mut().filter(|_| !line.trim().is_empty()) {
+            let _ = editor.add_history_entry(line.as_str());
+        }
+    });
+    Ok(line)
+}
+
+/// Returns the line read, or ends the program at Ctrl-C, the end of
+/// input or an error, see [`read_line`].
+fn finish_read(result: Result<String, ReadlineError>) -> String {
+    match result {
+        Ok(line) => line,
+        Err(ReadlineError::Interrupted) => process::exit(130),
+        Err(ReadlineError::Eof) => {
+            println!();
+            session::finish();
+            process::exit(0)
+        }
+        Err(e) => {
+            eprintln!("Error reading input: {}", e);
+            process::exit(1)
+        }
+    }
+}
@@ -0,0 +1,867 @@
+use chess::{
+    between, get_bishop_moves, get_bishop_rays, get_king_moves, get_knight_moves, get_pawn_attacks,
+    get_rook_moves, get_rook_rays, BitBoard, Board, BoardBuilder, CastleRights, ChessMove, Color,
+    File, Piece, Rank, Square, ALL_COLORS, ALL_PIECES, ALL_SQUARES, EMPTY,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// Explains why a move was rejected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IllegalMoveReason {
+    /// There is no piece on the source square.
+    NoPieceOnSquare(Square),
+    /// The piece on the source square belongs to the side not on move.
+    OpponentsPiece(Square, Color),
+    /// The piece cannot reach the destination from its square, even on an empty board.
+    CannotMoveThatWay(Piece),
+    /// Another piece stands on the path between the source and destination.
+    PathBlocked(Square),
+    /// The destination is occupied by one of the mover's own pieces.
+    OwnPieceOnTarget(Square),
+    /// A pawn can only move diagonally when capturing.
+    NothingToCapture(Square),
+    /// A capture was written without `x`.
+    CaptureNotMarked(Square),
+    /// A pawn cannot move forward onto an occupied square.
+    PawnBlocked(Square),
+    /// A pawn reaching the last rank must promote.
+    MissingPromotion,
+    /// Only pawns reaching the last rank can promote, and only to a knight, bishop, rook or queen.
+    InvalidPromotion,
+    /// The side to move is in check and the move does not get out of it.
+    DoesNotResolveCheck,
+    /// The move would expose the mover's own king to an attack.
+    LeavesKingInCheck,
+    /// The king or rook has already moved, so castling on that side is no longer allowed.
+    CastlingRightsLost,
+    /// A piece stands between the king and the rook.
+    CastlingPathBlocked(Square),
+    /// Castling is not allowed while in check.
+    CastlingOutOfCheck,
+    /// The king would pass through or land on an attacked square.
+    CastlingThroughCheck(Square),
+    /// None of the mover's pieces of the given kind can move to the square.
+    NoPieceCanReach(Piece, Square),
+    /// Several pieces can make the move, so the source square must be specified.
+    Ambiguous,
+}
+
+impl fmt::Display for IllegalMoveReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IllegalMoveReason::NoPieceOnSquare(square) => {
+                write!(f, "there is no piece on {}", square)
+            }
+            IllegalMoveReason::OpponentsPiece(square, turn) => {
+                write!(
+                    f,
+                    "the piece on {} is not yours, it's {:?}'s turn",
+                    square, turn
+                )
+            }
+            IllegalMoveReason::CannotMoveThatWay(piece) => {
+                write!(f, "a {} can't move that way", piece_name(*piece))
+            }
+            IllegalMoveReason::PathBlocked(square) => {
+                write!(f, "the path is blocked on {}", square)
+            }
+            IllegalMoveReason::OwnPieceOnTarget(square) => {
+                write!(f, "{} is occupied by your own piece", square)
+            }
+            IllegalMoveReason::NothingToCapture(square) => {
+                write!(f, "there is nothing to capture on {}", square)
+            }
+            IllegalMoveReason::CaptureNotMarked(square) => {
+                write!(f, "capturing on {} must be written with an 'x'", square)
+            }
+            IllegalMoveReason::PawnBlocked(square) => {
+                write!(f, "the pawn is blocked on {}", square)
+            }
+            IllegalMoveReason::MissingPromotion => {
+                write!(f, "a pawn reaching the last rank must promote")
+            }
+            IllegalMoveReason::InvalidPromotion => write!(f, "that promotion is not allowed"),
+            IllegalMoveReason::DoesNotResolveCheck => {
+                write!(f, "your king is in check and that move doesn't stop it")
+            }
+            IllegalMoveReason::LeavesKingInCheck => {
+                write!(f, "that would leave your king in check")
+            }
+            IllegalMoveReason::CastlingRightsLost => {
+                write!(f, "castling on that side is no longer allowed")
+            }
+            IllegalMoveReason::CastlingPathBlocked(square) => {
+                write!(f, "can't castle, {} is occupied", square)
+            }
+            IllegalMoveReason::CastlingOutOfCheck => write!(f, "can't castle while in check"),
+            IllegalMoveReason::CastlingThroughCheck(square) => {
+                write!(f, "can't castle, {} is attacked", square)
+            }
+            IllegalMoveReason::NoPieceCanReach(piece, square) => {
+                write!(
+                    f,
+                    "none of your {}s can move to {}",
+                    piece_name(*piece),
+                    square
+                )
+            }
+            IllegalMoveReason::Ambiguous => {
+                write!(
+                    f,
+                    "more than one piece can make that move, specify which one"
+                )
+            }
+        }
+    }
+}
+
+fn piece_name(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn => "pawn",
+        Piece::Knight => "knight",
+        Piece::Bishop => "bishop",
+        Piece::Rook => "rook",
+        Piece::Queen => "queen",
+        Piece::King => "king",
+    }
+}
+
+/// Explains why `mv` is illegal on `board`.
+///
+/// The move is assumed to be illegal; checks run from the most basic
+/// (is there a piece to move at all) to the most subtle (does the move
+/// expose the king), and the first one that fails is reported.
+///
+/// # Example
+///
+/// ```
+/// let mv = ChessMove::from_str("e2e5").unwrap();
+/// assert_eq!(
+///     explain_illegal_move(&Board::default(), mv),
+///     IllegalMoveReason::CannotMoveThatWay(Piece::Pawn)
+/// );
+/// ```
+pub fn explain_illegal_move(board: &Board, mv: ChessMove) -> IllegalMoveReason {
+    let (source, dest) = (mv.get_source(), mv.get_dest());
+    let us = board.side_to_move();
+    let piece = match board.piece_on(source) {
+        Some(piece) => piece,
+        None => return IllegalMoveReason::NoPieceOnSquare(source),
+    };
+    if board.color_on(source) != Some(us) {
+        return IllegalMoveReason::OpponentsPiece(source, us);
+    }
+    if piece == Piece::King && is_castling_attempt(board, mv) {
+        return explain_castling(board, mv);
+    }
+    if board.color_on(dest) == Some(us) {
+        return IllegalMoveReason::OwnPieceOnTarget(dest);
+    }
+    if let Some(reason) = explain_movement(board, piece, source, dest) {
+        return reason;
+    }
+
+    let last_rank = match us {
+        Color::White => Rank::Eighth,
+        Color::Black => Rank::First,
+    };
+    match mv.get_promotion() {
+        None if piece == Piece::Pawn && dest.get_rank() == last_rank => {
+            return IllegalMoveReason::MissingPromotion
+        }
+        Some(Piece::Pawn | Piece::King) => return IllegalMoveReason::InvalidPromotion,
+        Some(_) if piece != Piece::Pawn || dest.get_rank() != last_rank => {
+            return IllegalMoveReason::InvalidPromotion
+        }
+        _ => (),
+    }
+
+    if *board.checkers() != EMPTY {
+        IllegalMoveReason::DoesNotResolveCheck
+    } else {
+        IllegalMoveReason::LeavesKingInCheck
+    }
+}
+
+/// Explains why the SAN move `input` can't be played on `board`.
+///
+/// Returns `None` if the input can't be read as a SAN move at all.
+pub fn explain_illegal_san(board: &Board, input: &str) -> Option<IllegalMoveReason> {
+    let text = input.trim_end_matches(['+', '#', '!', '?']);
+    let us = board.side_to_move();
+
+    if matches!(text, "O-O" | "O-O-O" | "0-0" | "0-0-0") {
+        let rank = us.to_my_backrank();
+        let dest_file = if text.len() == 3 { File::G } else { File::C };
+        let mv = ChessMove::new(
+            Square::make_square(rank, File::E),
+            Square::make_square(rank, dest_file),
+            None,
+        );
+        return Some(explain_illegal_move(board, mv));
+    }
+
+    let san = SanMove::parse(text)?;
+    let mut candidates = *board.pieces(san.piece) & *board.color_combined(us);
+    if let Some(file) = san.source_file {
+        candidates &= chess::get_file(file);
+    } else if san.piece == Piece::Pawn && !san.takes {
+        candidates &= chess::get_file(san.dest.get_file());
+    }
+    if let Some(rank) = san.source_rank {
+        candidates &= chess::get_rank(rank);
+    }
+
+    let moves: Vec<ChessMove> = candidates
+        .map(|source| ChessMove::new(source, san.dest, san.promotion))
+        .collect();
+    let legal: Vec<&ChessMove> = moves.iter().filter(|mv| board.legal(**mv)).collect();
+    match legal.len() {
+        0 => (),
+        1 => {
+            let occupied = board.piece_on(san.dest).is_some();
+            return if san.takes && !occupied {
+                Some(IllegalMoveReason::NothingToCapture(san.dest))
+            } else if !san.takes && occupied {
+                Some(IllegalMoveReason::CaptureNotMarked(san.dest))
+            } else {
+                None
+            };
+        }
+        _ => return Some(IllegalMoveReason::Ambiguous),
+    }
+
+    // Report the problem of a piece that could at least move that way,
+    // falling back to a general message if none of them could.
+    moves
+        .iter()
+        .map(|mv| explain_illegal_move(board, *mv))
+        .find(|reason| !matches!(reason, IllegalMoveReason::CannotMoveThatWay(_)))
+        .or(Some(IllegalMoveReason::NoPieceCanReach(
+            san.piece, san.dest,
+        )))
+}
+
+/// The parts of a (non-castling) SAN move needed to find candidate moves.
+struct SanMove {
+    piece: Piece,
+    source_file: Option<File>,
+    source_rank: Option<Rank>,
+    takes: bool,
+    dest: Square,
+    promotion: Option<Piece>,
+}
+
+impl SanMove {
+    fn parse(text: &str) -> Option<Self> {
+        let mut chars: Vec<char> = text.chars().collect();
+        let piece = match chars.first()? {
+            'N' => Some(Piece::Knight),
+            'B' => Some(Piece::Bishop),
+            'R' => Some(Piece::Rook),
+            'Q' => Some(Piece::Queen),
+            'K' => Some(Piece::King),
+            _ => None,
+        };
+        if piece.is_some() {
+            chars.remove(0);
+        }
+
+        let promotion = match chars.last()? {
+            'N' => Some(Piece::Knight),
+            'B' => Some(Piece::Bishop),
+            'R' => Some(Piece::Rook),
+            'Q' => Some(Piece::Queen),
+            _ => None,
+        };
+        if promotion.is_some() {
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        if chars.len() < 2 {
+            return None;
+        }
+        let dest: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let dest = Square::from_str(&dest).ok()?;
+
+        let takes = chars.last() == Some(&'x');
+        if takes {
+            chars.pop();
+        }
+        let mut source_file = None;
+        let mut source_rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' if source_file.is_none() => {
+                    source_file = Some(File::from_index(c as usize - 'a' as usize))
+                }
+                '1'..='8' if source_rank.is_none() => {
+                    source_rank = Some(Rank::from_index(c as usize - '1' as usize))
+                }
+                _ => return None,
+            }
+        }
+
+        Some(Self {
+            piece: piece.unwrap_or(Piece::Pawn),
+            source_file,
+            source_rank,
+            takes,
+            dest,
+            promotion,
+        })
+    }
+}
+
+fn is_castling_attempt(board: &Board, mv: ChessMove) -> bool {
+    let home = Square::make_square(board.side_to_move().to_my_backrank(), File::E);
+    mv.get_source() == home
+        && mv.get_dest().get_rank() == home.get_rank()
+        && matches!(mv.get_dest().get_file(), File::C | File::G)
+}
+
+fn explain_castling(board: &Board, mv: ChessMove) -> IllegalMoveReason {
+    let rank = mv.get_source().get_rank();
+    let kingside = mv.get_dest().get_file() == File::G;
+    let rights = board.my_castle_rights();
+    let (allowed, rook_file, king_path) = if kingside {
+        (rights.has_kingside(), File::H, [File::F, File::G])
+    } else {
+        (rights.has_queenside(), File::A, [File::D, File::C])
+    };
+    if !allowed {
+        return IllegalMoveReason::CastlingRightsLost;
+    }
+
+    let rook_square = Square::make_square(rank, rook_file);
+    if let Some(blocker) = nearest(
+        between(mv.get_source(), rook_square) & board.combined(),
+        mv.get_source(),
+    ) {
+        return IllegalMoveReason::CastlingPathBlocked(blocker);
+    }
+    if *board.checkers() != EMPTY {
+        return IllegalMoveReason::CastlingOutOfCheck;
+    }
+    for file in king_path {
+        let square = Square::make_square(rank, file);
+        if is_attacked(board, square, !board.side_to_move()) {
+            return IllegalMoveReason::CastlingThroughCheck(square);
+        }
+    }
+    IllegalMoveReason::LeavesKingInCheck
+}
+
+/// Checks whether `piece` could move from `source` to `dest` given the
+/// pieces currently on the board, ignoring checks.
+fn explain_movement(
+    board: &Board,
+    piece: Piece,
+    source: Square,
+    dest: Square,
+) -> Option<IllegalMoveReason> {
+    let reachable = match piece {
+        Piece::Pawn => return explain_pawn_movement(board, source, dest),
+        Piece::Knight => get_knight_moves(source),
+        Piece::King => get_king_moves(source),
+        Piece::Bishop => get_bishop_rays(source),
+        Piece::Rook => get_rook_rays(source),
+        Piece::Queen => get_bishop_rays(source) | get_rook_rays(source),
+    };
+    if reachable & BitBoard::from_square(dest) == EMPTY {
+        return Some(IllegalMoveReason::CannotMoveThatWay(piece));
+    }
+    nearest(between(source, dest) & board.combined(), source).map(IllegalMoveReason::PathBlocked)
+}
+
+fn explain_pawn_movement(board: &Board, source: Square, dest: Square) -> Option<IllegalMoveReason> {
+    let us = board.side_to_move();
+    let forward = |square: Square| match us {
+        Color::White => square.up(),
+        Color::Black => square.down(),
+    };
+    let start_rank = match us {
+        Color::White => Rank::Second,
+        Color::Black => Rank::Seventh,
+    };
+
+    if get_pawn_attacks(source, us, !EMPTY) & BitBoard::from_square(dest) != EMPTY {
+        let en_passant_target = board.en_passant().and_then(forward);
+        return if board.piece_on(dest).is_none() && Some(dest) != en_passant_target {
+            Some(IllegalMoveReason::NothingToCapture(dest))
+        } else {
+            None
+        };
+    }
+
+    let one_step = forward(source)?;
+    let two_steps = forward(one_step).filter(|_| source.get_rank() == start_rank);
+    if dest == one_step || Some(dest) == two_steps {
+        if board.piece_on(one_step).is_some() {
+            return Some(IllegalMoveReason::PawnBlocked(one_step));
+        }
+        if board.piece_on(dest).is_some() {
+            return Some(IllegalMoveReason::PawnBlocked(dest));
+        }
+        return None;
+    }
+    Some(IllegalMoveReason::CannotMoveThatWay(Piece::Pawn))
+}
+
+/// Returns whether `square` is attacked by any piece of `attacker`.
+pub fn is_attacked(board: &Board, square: Square, attacker: Color) -> bool {
+    let theirs = board.color_combined(attacker);
+    attacked_by(square, *board.combined(), attacker, |piece| {
+        *board.pieces(piece) & theirs
+    })
+}
+
+/// Returns whether `square` is attacked by any piece of `attacker`, given
+/// the occupied squares and the squares of each kind of `attacker`'s pieces.
+fn attacked_by(
+    square: Square,
+    occupied: BitBoard,
+    attacker: Color,
+    pieces: impl Fn(Piece) -> BitBoard,
+) -> bool {
+    let diagonal = pieces(Piece::Bishop) | pieces(Piece::Queen);
+    let straight = pieces(Piece::Rook) | pieces(Piece::Queen);
+
+    (get_knight_moves(square) & pieces(Piece::Knight)) != EMPTY
+        || (get_king_moves(square) & pieces(Piece::King)) != EMPTY
+        || get_pawn_attacks(square, !attacker, pieces(Piece::Pawn)) != EMPTY
+        || (get_bishop_moves(square, occupied) & diagonal) != EMPTY
+        || (get_rook_moves(square, occupied) & straight) != EMPTY
+}
+
+/// Explains why a position can't be played.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PositionError {
+    /// The FEN can't be read.
+    InvalidFen,
+    /// The side has no king.
+    MissingKing(Color),
+    /// The side has more than one king.
+    ExtraKing(Color),
+    /// The side has more than eight pawns.
+    TooManyPawns(Color),
+    /// The side has more than sixteen pieces.
+    TooManyPieces(Color),
+    /// The side has more promoted pieces than pawns it could have promoted.
+    TooManyPromotions(Color),
+    /// A pawn stands on the first or last rank.
+    PawnOnBackRank(Square),
+    /// Both kings are in check.
+    BothInCheck,
+    /// The side that just moved is in check.
+    WaitingSideInCheck(Color),
+    /// The side may castle but its king has left its starting square.
+    CastlingKingMoved(Color),
+    /// The side may castle with a rook that is not on its starting square.
+    CastlingRookMissing(Color, Square),
+    /// No pawn can just have made the double step over the en passant square.
+    InvalidEnPassant(Square),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::InvalidFen => write!(f, "the FEN can't be read"),
+            PositionError::MissingKing(color) => write!(f, "{:?} has no king", color),
+            PositionError::ExtraKing(color) => write!(f, "{:?} has more than one king", color),
+            PositionError::TooManyPawns(color) => {
+                write!(f, "{:?} has more than eight pawns", color)
+            }
+            PositionError::TooManyPieces(color) => {
+                write!(f, "{:?} has more than sixteen pieces", color)
+            }
+            PositionError::TooManyPromotions(color) => {
+                write!(f, "{:?} has more promoted pieces than missing pawns", color)
+            }
+            PositionError::PawnOnBackRank(square) => {
+                write!(f, "the pawn on {} can't stand on the back rank", square)
+            }
+            PositionError::BothInCheck => write!(f, "both kings are in check"),
+            PositionError::WaitingSideInCheck(color) => {
+                write!(f, "{:?} is in check but it's not their move", color)
+            }
+            PositionError::CastlingKingMoved(color) => write!(
+                f,
+                "{:?} can't castle with the king off its starting square",
+                color
+            ),
+            PositionError::CastlingRookMissing(color, square) => {
+                write!(f, "{:?} can't castle without a rook on {}", color, square)
+            }
+            PositionError::InvalidEnPassant(square) => {
+                write!(f, "no pawn can just have passed {}", square)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+/// Reads a FEN without checking that the position can be played.
+///
+/// # Errors
+///
+/// Returns [`PositionError::InvalidFen`] if the FEN can't be read.
+pub fn parse_fen(fen: &str) -> Result<BoardBuilder, PositionError> {
+    let fen = fen.trim();
+    // The chess crate panics on FENs with missing ranks.
+    if fen.split(' ').next().unwrap_or("").split('/').count() != 8 {
+        return Err(PositionError::InvalidFen);
+    }
+    BoardBuilder::from_str(fen).map_err(|_| PositionError::InvalidFen)
+}
+
+/// Reads a FEN and checks that the position can be played, see
+/// [`validate_position`].
+///
+/// # Errors
+///
+/// Returns the first problem found with the FEN or the position.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(
+///     validate_fen("4k3/8/8/8/8/8/8/8 w - - 0 1"),
+///     Err(PositionError::MissingKing(Color::White))
+/// );
+/// ```
+pub fn validate_fen(fen: &str) -> Result<Board, PositionError> {
+    validate_position(&parse_fen(fen)?)
+}
+
+/// Checks that a position can be played and returns it.
+///
+/// The checks run from the piece counts through pawns on the back ranks
+/// and kings in check to the castling rights and en passant square, and
+/// the first one that fails is reported.
+///
+/// # Errors
+///
+/// Returns the first problem found with the position.
+pub fn validate_position(setup: &BoardBuilder) -> Result<Board, PositionError> {
+    let squares_of = |piece: Piece, color: Color| {
+        ALL_SQUARES
+            .into_iter()
+            .filter(move |square| setup[*square] == Some((piece, color)))
+    };
+    let count = |piece: Piece, color: Color| squares_of(piece, color).count();
+    for color in ALL_COLORS {
+        match count(Piece::King, color) {
+            0 => return Err(PositionError::MissingKing(color)),
+            1 => (),
+            _ => return Err(PositionError::ExtraKing(color)),
+        }
+        let pawns = count(Piece::Pawn, color);
+        if pawns > 8 {
+            return Err(PositionError::TooManyPawns(color));
+        }
+        if ALL_PIECES
+            .iter()
+            .map(|piece| count(*piece, color))
+            .sum::<usize>()
+            > 16
+        {
+            return Err(PositionError::TooManyPieces(color));
+        }
+        let promoted: usize = [
+            (Piece::Queen, 1),
+            (Piece::Rook, 2),
+            (Piece::Bishop, 2),
+            (Piece::Knight, 2),
+        ]
+        .iter()
+        .map(|(piece, initial)| count(*piece, color).saturating_sub(*initial))
+        .sum();
+        if promoted > 8 - pawns {
+            return Err(PositionError::TooManyPromotions(color));
+        }
+    }
+    for color in ALL_COLORS {
+        if let Some(square) = squares_of(Piece::Pawn, color)
+            .find(|square| matches!(square.get_rank(), Rank::First | Rank::Eighth))
+        {
+            return Err(PositionError::PawnOnBackRank(square));
+        }
+    }
+
+    let occupied = ALL_SQUARES
+        .into_iter()
+        .filter(|square| setup[*square].is_some())
+        .fold(EMPTY, |bits, square| bits | BitBoard::from_square(square));
+    let in_check = |color: Color| {
+        let king = squares_of(Piece::King, color).next().unwrap();
+        attacked_by(king, occupied, !color, |piece| {
+            squares_of(piece, !color)
+                .fold(EMPTY, |bits, square| bits | BitBoard::from_square(square))
+        })
+    };
+    let waiting = !setup.get_side_to_move();
+    match (in_check(waiting), in_check(!waiting)) {
+        (true, true) => return Err(PositionError::BothInCheck),
+        (true, false) => return Err(PositionError::WaitingSideInCheck(waiting)),
+        _ => (),
+    }
+
+    for color in ALL_COLORS {
+        let rights = setup.get_castle_rights(color);
+        if rights == CastleRights::NoRights {
+            continue;
+        }
+        let back_rank = color.to_my_backrank();
+        if setup[Square::make_square(back_rank, File::E)] != Some((Piece::King, color)) {
+            return Err(PositionError::CastlingKingMoved(color));
+        }
+        let rook_files = match rights {
+            CastleRights::KingSide => &[File::H][..],
+            CastleRights::QueenSide => &[File::A],
+            _ => &[File::H, File::A],
+        };
+        for file in rook_files {
+            let square = Square::make_square(back_rank, *file);
+            if setup[square] != Some((Piece::Rook, color)) {
+                return Err(PositionError::CastlingRookMissing(color, square));
+            }
+        }
+    }
+
+    if let Some(pawn) = setup.get_en_passant() {
+        // The pawn that just moved stands on `pawn`, having passed the
+        // target square from the square behind it.
+        let (target, origin) = match waiting {
+            Color::White => (pawn.down(), pawn.down().and_then(|square| square.down())),
+            Color::Black => (pawn.up(), pawn.up().and_then(|square| square.up())),
+        };
+        let target = target.unwrap_or(pawn);
+        let passed = origin.is_some_and(|origin| {
+            setup[pawn] == Some((Piece::Pawn, waiting))
+                && setup[target].is_none()
+                && setup[origin].is_none()
+        });
+        if !passed {
+            return Err(PositionError::InvalidEnPassant(target));
+        }
+    }
+
+    Ok(Board::try_from(setup).expect("validated positions are sane"))
+}
+
+/// Returns the square of `squares` closest to `origin`.
+fn nearest(squares: BitBoard, origin: Square) -> Option<Square> {
+    squares.min_by_key(|square| distance(*square, origin))
+}
+
+fn distance(a: Square, b: Square) -> usize {
+    let files = a.get_file().to_index().abs_diff(b.get_file().to_index());
+    let ranks = a.get_rank().to_index().abs_diff(b.get_rank().to_index());
+    files.max(ranks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn explain_uci(fen: &str, uci: &str) -> IllegalMoveReason {
+        let board = Board::from_str(fen).unwrap();
+        explain_illegal_move(&board, ChessMove::from_str(uci).unwrap())
+    }
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn piece_cannot_move_that_way() {
+        assert_eq!(
+            explain_uci(START, "e2e5"),
+            IllegalMoveReason::CannotMoveThatWay(Piece::Pawn)
+        );
+        assert_eq!(
+            explain_uci(START, "b1b3"),
+            IllegalMoveReason::CannotMoveThatWay(Piece::Knight)
+        );
+    }
+
+    #[test]
+    fn path_blocked() {
+        assert_eq!(
+            explain_uci(START, "f1c4"),
+            IllegalMoveReason::PathBlocked(Square::E2)
+        );
+        assert_eq!(
+            explain_uci(START, "a1a4"),
+            IllegalMoveReason::PathBlocked(Square::A2)
+        );
+    }
+
+    #[test]
+    fn wrong_piece_or_square() {
+        assert_eq!(
+            explain_uci(START, "e7e5"),
+            IllegalMoveReason::OpponentsPiece(Square::E7, Color::White)
+        );
+        assert_eq!(
+            explain_uci(START, "e3e4"),
+            IllegalMoveReason::NoPieceOnSquare(Square::E3)
+        );
+        assert_eq!(
+            explain_uci(START, "d1d2"),
+            IllegalMoveReason::OwnPieceOnTarget(Square::D2)
+        );
+    }
+
+    #[test]
+    fn pawn_rules() {
+        assert_eq!(
+            explain_uci(START, "e2d3"),
+            IllegalMoveReason::NothingToCapture(Square::D3)
+        );
+        let blocked = "4k3/8/8/8/8/4p3/4P3/4K3 w - - 0 1";
+        assert_eq!(
+            explain_uci(blocked, "e2e3"),
+            IllegalMoveReason::PawnBlocked(Square::E3)
+        );
+        let promoting = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(
+            explain_uci(promoting, "a7a8"),
+            IllegalMoveReason::MissingPromotion
+        );
+        assert_eq!(
+            explain_uci(START, "e2e4q"),
+            IllegalMoveReason::InvalidPromotion
+        );
+    }
+
+    #[test]
+    fn king_safety() {
+        // The e-file knight is pinned by the rook on e8.
+        let pinned = "4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1";
+        assert_eq!(
+            explain_uci(pinned, "e2c3"),
+            IllegalMoveReason::LeavesKingInCheck
+        );
+        let walks_into_attack = "3r2k1/8/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(
+            explain_uci(walks_into_attack, "e1d1"),
+            IllegalMoveReason::LeavesKingInCheck
+        );
+        let in_check = "4r1k1/8/8/8/8/8/1N6/4K3 w - - 0 1";
+        assert_eq!(
+            explain_uci(in_check, "b2a4"),
+            IllegalMoveReason::DoesNotResolveCheck
+        );
+    }
+
+    #[test]
+    fn castling_rules() {
+        assert_eq!(
+            explain_uci(START, "e1g1"),
+            IllegalMoveReason::CastlingPathBlocked(Square::F1)
+        );
+        let no_rights = "4k3/8/8/8/8/8/8/R3K2R w Q - 0 1";
+        assert_eq!(
+            explain_uci(no_rights, "e1g1"),
+            IllegalMoveReason::CastlingRightsLost
+        );
+        let through_check = "4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1";
+        assert_eq!(
+            explain_uci(through_check, "e1g1"),
+            IllegalMoveReason::CastlingThroughCheck(Square::F1)
+        );
+        let in_check = "4r1k1/8/8/8/8/8/8/R3K2R w KQ - 0 1";
+        assert_eq!(
+            explain_uci(in_check, "e1c1"),
+            IllegalMoveReason::CastlingOutOfCheck
+        );
+    }
+
+    #[test]
+    fn san_explanations() {
+        let board = Board::default();
+        assert_eq!(
+            explain_illegal_san(&board, "Nc4"),
+            Some(IllegalMoveReason::NoPieceCanReach(
+                Piece::Knight,
+                Square::C4
+            ))
+        );
+        assert_eq!(
+            explain_illegal_san(&board, "Bc4"),
+            Some(IllegalMoveReason::PathBlocked(Square::E2))
+        );
+        assert_eq!(
+            explain_illegal_san(&board, "Nxf3"),
+            Some(IllegalMoveReason::NothingToCapture(Square::F3))
+        );
+        assert_eq!(
+            explain_illegal_san(&board, "O-O"),
+            Some(IllegalMoveReason::CastlingPathBlocked(Square::F1))
+        );
+        assert_eq!(explain_illegal_san(&board, "hello"), None);
+
+        let two_knights = Board::from_str("4k3/8/8/8/8/8/8/1N3NK1 w - - 0 1").unwrap();
+        assert_eq!(
+            explain_illegal_san(&two_knights, "Nd2"),
+            Some(IllegalMoveReason::Ambiguous)
+        );
+    }
+
+    #[test]
+    fn validates_positions() {
+        let invalid = |fen: &str| validate_fen(fen).unwrap_err();
+        assert_eq!(validate_fen(START), Ok(Board::default()));
+        assert_eq!(invalid("8/8/8 w - - 0 1"), PositionError::InvalidFen);
+        assert_eq!(
+            invalid("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w kq - 0 1"),
+            PositionError::MissingKing(Color::White)
+        );
+        assert_eq!(
+            invalid("4k3/8/8/8/8/3K4/8/4K3 w - - 0 1"),
+            PositionError::ExtraKing(Color::White)
+        );
+        assert_eq!(
+            invalid("4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1"),
+            PositionError::TooManyPawns(Color::Black)
+        );
+        assert_eq!(
+            invalid("4k3/8/8/8/8/QQ6/PPPPPPPP/4K3 w - - 0 1"),
+            PositionError::TooManyPromotions(Color::White)
+        );
+        assert_eq!(
+            invalid("4k3/8/8/8/8/8/8/P3K3 w - - 0 1"),
+            PositionError::PawnOnBackRank(Square::A1)
+        );
+        assert_eq!(
+            invalid("4k3/8/8/8/4R3/8/8/4K3 w - - 0 1"),
+            PositionError::WaitingSideInCheck(Color::Black)
+        );
+        assert_eq!(
+            invalid("4k3/8/8/8/4R3/8/8/r3K3 w - - 0 1"),
+            PositionError::BothInCheck
+        );
+        assert_eq!(
+            invalid("4k3/8/8/8/8/8/8/R3K3 w K - 0 1"),
+            PositionError::CastlingRookMissing(Color::White, Square::H1)
+        );
+        assert_eq!(
+            invalid("r3k2r/8/8/8/8/8/8/4K3 w KQkq - 0 1"),
+            PositionError::CastlingRookMissing(Color::White, Square::H1)
+        );
+        assert_eq!(
+            invalid("r4k1r/8/8/8/8/8/8/4K3 w kq - 0 1"),
+            PositionError::CastlingKingMoved(Color::Black)
+        );
+        assert_eq!(
+            invalid("4k3/8/8/8/8/8/8/4K3 w - e6 0 1"),
+            PositionError::InvalidEnPassant(Square::E6)
+        );
+        assert!(validate_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").is_ok());
+    }
+}
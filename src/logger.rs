@@ -0,0 +1,99 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Writes log records, one per line, to a file or standard error.
+struct Logger {
+    level: LevelFilter,
+    /// The log file, `None` to write to standard error.
+    file: Option<Mutex<File>>,
+}
+
+/// Formats a log line: the time in seconds since the Unix epoch, the
+/// level, the module that logged it and the message.
+fn format_line(seconds: f64, record: &Record) -> String {
+    format!(
+        "{:.3} {:<5} {}: {}",
+        seconds,
+        record.level(),
+        record.target(),
+        record.args()
+    )
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |time| time.as_secs_f64());
+        let line = format_line(seconds, record);
+        match &self.file {
+            Some(file) => {
+                let _ = writeln!(file.lock().unwrap(), "{}", line);
+            }
+            None => eprintln!("{}", line),
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Starts logging records up to `level`, appending them to `file` or
+/// writing them to standard error if there's no file.
+///
+/// # Errors
+///
+/// Returns an error if the log file can't be opened.
+///
+/// # Example
+///
+/// ```
+/// init(LevelFilter::Debug, Some(Path::new("rchess.log")))?;
+/// log::info!("logging started");
+/// ```
+pub fn init(level: LevelFilter, file: Option<&Path>) -> io::Result<()> {
+    let file = match file {
+        Some(path) => Some(Mutex::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        )),
+        None => None,
+    };
+    // Logging is only set up once, at startup.
+    if log::set_boxed_logger(Box::new(Logger { level, file })).is_ok() {
+        log::set_max_level(level);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn formats_lines() {
+        let line = format_line(
+            12.5,
+            &Record::builder()
+                .args(format_args!("searched {} nodes", 42))
+                .level(Level::Debug)
+                .target("rChess::ai")
+                .build(),
+        );
+        assert_eq!(line, "12.500 DEBUG rChess::ai: searched 42 nodes");
+    }
+}
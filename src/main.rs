@@ -1,7 +1,166 @@
-mod game;
-mod cli;
+mod adaptive;
+mod adjudication;
 mod ai;
+mod annotate;
+mod annotation;
+mod archive;
+mod arena;
+mod autosave;
+mod batch;
+mod book;
+mod cli;
+mod clock;
+mod command;
+mod conduct;
+mod config;
+mod coords;
+mod correspondence;
+mod document;
+mod drill;
+mod editor;
+mod endgame;
+mod error;
+mod events;
+mod explorer;
+mod external;
+mod game;
+mod heatmap;
+mod i18n;
+#[cfg(feature = "import")]
+mod import;
+mod incremental;
+mod info;
+mod input;
+mod legality;
+mod logger;
+mod mcts;
+mod motif;
+mod network;
+#[cfg(feature = "nnue")]
+mod nnue;
+mod notation;
+mod notify;
+mod odds;
+mod pacing;
+mod paths;
+mod pawns;
+mod personality;
+mod pgn;
+mod practical;
+mod profile;
+mod repertoire;
+mod report;
+mod rng;
+mod script;
+mod selfplay;
+mod server;
+mod session;
+mod simul;
+mod speech;
+mod sprt;
+mod standings;
+mod stats;
+mod strength;
+mod svg;
+mod terminal;
+#[cfg(test)]
+mod testing;
+mod timing;
+mod tree;
+mod tt;
+mod tune;
+mod uci;
+mod variant;
+mod variation;
+
+/// Exits with a usage error for the command line option `option`.
+fn usage_error(option: &str, expected: &str) -> ! {
+    eprintln!("{} needs {}", option, expected);
+    std::process::exit(2)
+}
 
 fn main() {
-    cli::intro();
+    let mut accessible = false;
+    let mut log_level = None;
+    let mut log_file = None;
+    let mut tags = pgn::GameTags::default();
+    let mut args = Vec::new();
+    let mut arguments = std::env::args().skip(1);
+    while let Some(arg) = arguments.next() {
+        match arg.as_str() {
+            "--accessible" => accessible = true,
+            "--seed" => match arguments.next().and_then(|seed| seed.parse().ok()) {
+                Some(seed) => rng::set_seed(seed),
+                None => usage_error("--seed", "a number"),
+            },
+            "--log-level" => match arguments.next().and_then(|level| level.parse().ok()) {
+                Some(level) => log_level = Some(level),
+                None => usage_error("--log-level", "error, warn, info, debug or trace"),
+            },
+            "--log-file" => match arguments.next() {
+                Some(path) => log_file = Some(std::path::PathBuf::from(path)),
+                None => usage_error("--log-file", "a file name"),
+            },
+            "--white" | "--black" | "--event" | "--site" | "--round" => match arguments.next() {
+                Some(value) => tags.set(&arg[2..], &value).unwrap(),
+                None => usage_error(&arg, "a value"),
+            },
+            _ => args.push(arg),
+        }
+    }
+    if log_level.is_some() || log_file.is_some() {
+        let level = log_level.unwrap_or(log::LevelFilter::Info);
+        if let Err(e) = logger::init(level, log_file.as_deref()) {
+            eprintln!("Error opening the log file: {}", e);
+        }
+    }
+    log::info!(
+        "rChess {} started with {:?}",
+        env!("CARGO_PKG_VERSION"),
+        args
+    );
+    if let Some(seed) = rng::seed() {
+        log::info!("Random seed {}", seed);
+    }
+    let config = config::Config::load();
+    let display = cli::Display {
+        accessible: accessible || config.accessible,
+        language: config.language.unwrap_or_else(i18n::Language::detect),
+        zoom: config.zoom,
+    };
+    terminal::set_capabilities(terminal::Capabilities {
+        unicode: config.unicode.unwrap_or_else(terminal::detect_unicode),
+        ansi: config.color.unwrap_or_else(terminal::detect_ansi),
+    });
+    cli::load_network(config.nnue_file.as_deref());
+    cli::load_book(config.book_file.as_deref());
+    match args.first().map(String::as_str) {
+        Some("annotate") => cli::annotate(&args[1..]),
+        Some("arena") => cli::arena(&args[1..]),
+        Some("bench") => cli::bench(args.get(1).and_then(|depth| depth.parse().ok())),
+        Some("calibrate") => cli::calibrate(args.get(1).and_then(|games| games.parse().ok())),
+        Some("find-position") => cli::find_position(&args[1..]),
+        Some("stats") => cli::stats(args.get(1).map(String::as_str)),
+        Some("uci") => uci::run(),
+        Some("coords") => cli::coords(args.get(1).map(String::as_str)),
+        Some("edit") => cli::edit(&args[1..], display, &tags),
+        Some("eval") => cli::evaluate_positions(&args[1..]),
+        Some("drill") => cli::drill(args.get(1).map(String::as_str), display),
+        Some("endgame") => cli::endgame(display),
+        Some("host") => cli::host(&args[1..], display, &tags),
+        Some("import") => cli::import(args.get(1).map(String::as_str), display),
+        Some("join") => cli::join(&args[1..], display, &tags),
+        Some("make-book") => cli::make_book(&args[1..]),
+        Some("match") => cli::play_match(&args[1..]),
+        Some("paths") => cli::paths(args.get(1).map(String::as_str)),
+        Some("profile") => cli::profile(&args[1..]),
+        Some("review") => cli::review(args.get(1).map(String::as_str), display),
+        Some("resume") => cli::resume(&args[1..], display),
+        Some("serve") => server::run(&args[1..]),
+        Some("script") => cli::script(args.get(1).map(String::as_str)),
+        Some("tune-search") => cli::tune_search(&args[1..]),
+        Some("simul") => cli::simul(args.get(1).and_then(|boards| boards.parse().ok()), display),
+        _ => cli::intro(display, &tags),
+    }
+    session::finish();
 }
@@ -0,0 +1,254 @@
+use crate::ai::{evaluate_variant, Engine, EngineOptions, SearchResult, MATE_SCORE};
+use crate::rng::random_range;
+use crate::variant::VariantState;
+use chess::{Board, BoardStatus, ChessMove, MoveGen};
+use log::debug;
+use std::time::Instant;
+
+/// Exploration constant of the UCT formula: higher values try less
+/// visited moves more often, lower ones focus on the best moves so far.
+const EXPLORATION: f64 = 1.4;
+/// Random moves played out from a new node before the position is evaluated.
+const ROLLOUT_PLIES: u32 = 8;
+/// Centipawns of evaluation that make a win about 73% likely in a rollout.
+const SCORE_SCALE: f64 = 400.0;
+
+/// A node of the search tree: a position reached by `mv` from its parent.
+struct Node {
+    mv: Option<ChessMove>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Legal moves that don't have a child yet.
+    untried: Vec<ChessMove>,
+    visits: u32,
+    /// Sum of the results for the player who played `mv`, from 0 for a
+    /// loss to 1 for a win.
+    wins: f64,
+}
+
+impl Node {
+    fn new(
+        mv: Option<ChessMove>,
+        parent: Option<usize>,
+        board: &Board,
+        state: &VariantState,
+    ) -> Self {
+        let untried = if state.winner(board).is_some() {
+            Vec::new()
+        } else {
+            MoveGen::new_legal(board).collect()
+        };
+        Self {
+            mv,
+            parent,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+
+    /// Returns roughly how many bytes the node takes in the tree,
+    /// counting its index among its parent's children.
+    fn memory(&self) -> usize {
+        size_of::<Self>() + self.untried.capacity() * size_of::<ChessMove>() + size_of::<usize>()
+    }
+
+    /// Returns the UCT score the node is selected by, given how often
+    /// its parent was visited.
+    fn uct(&self, parent_visits: u32) -> f64 {
+        let visits = self.visits as f64;
+        self.wins / visits + EXPLORATION * ((parent_visits as f64).ln() / visits).sqrt()
+    }
+}
+
+/// Monte Carlo Tree Search engine.
+///
+/// Every iteration walks down the tree picking moves by UCT, adds one
+/// untried move, plays a few random moves from there and scores the
+/// resulting position with the evaluation, turned into the chance of
+/// winning. The move visited most often at the root is played.
+pub struct Mcts {
+    /// Number of iterations to run, fewer if the search limits in
+    /// `options` trip first.
+    pub iterations: u32,
+    /// The evaluation weights are used to score rollouts; the
+    /// alpha-beta pruning settings don't apply.
+    pub options: EngineOptions,
+}
+
+impl Mcts {
+    /// Returns the result of a short random playout from `board` for
+    /// the side to move, from 0 for a loss to 1 for a win, and the
+    /// number of moves played.
+    fn rollout(&self, board: &Board, state: &VariantState) -> (f64, u32) {
+        let mut board = *board;
+        let mut state = *state;
+        for ply in 0..=ROLLOUT_PLIES {
+            // The result so far, from the point of view of the side to
+            // move at the start of the rollout.
+            let flip = |result: f64| if ply % 2 == 0 { result } else { 1.0 - result };
+            if let Some(winner) = state.winner(&board) {
+                let result = if winner == board.side_to_move() {
+                    1.0
+                } else {
+                    0.0
+                };
+                return (flip(result), ply);
+            }
+            match board.status() {
+                BoardStatus::Checkmate => return (flip(0.0), ply),
+                BoardStatus::Stalemate => return (0.5, ply),
+                BoardStatus::Ongoing => (),
+            }
+            if ply == ROLLOUT_PLIES {
+                let score =
+                    evaluate_variant(&board, &state, board.side_to_move(), &self.options.weights);
+                return (flip(win_chance(score)), ply);
+            }
+            let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+            board = board.make_move_new(moves[random_range(0..moves.len())]);
+            state = state.after_move(&board);
+        }
+        unreachable!()
+    }
+}
+
+/// Turns a score in centipawns into the chance of winning, from 0 to 1.
+fn win_chance(score: i32) -> f64 {
+    1.0 / (1.0 + (-score as f64 / SCORE_SCALE).exp())
+}
+
+/// Turns the chance of winning back into a score in centipawns.
+fn chance_score(chance: f64) -> i32 {
+    let chance = chance.clamp(1e-6, 1.0 - 1e-6);
+    let score = -SCORE_SCALE * (1.0 / chance - 1.0).ln();
+    (score as i32).clamp(-MATE_SCORE, MATE_SCORE)
+}
+
+impl Engine for Mcts {
+    fn search(&self, board: &Board, state: &VariantState) -> SearchResult {
+        let mut tree = vec![Node::new(None, None, board, state)];
+        let mut nodes = 0;
+        let mut memory = tree[0].memory();
+        let started = Instant::now();
+        let limits = &self.options.limits;
+
+        let mut iterations = 0;
+        while iterations < self.iterations.max(1) {
+            if iterations > 0
+                && (limits.nodes.is_some_and(|limit| nodes >= limit)
+                    || limits.time.is_some_and(|limit| started.elapsed() >= limit)
+                    || limits.memory.is_some_and(|limit| memory > limit))
+            {
+                debug!("MCTS search limits reached");
+                break;
+            }
+            iterations += 1;
+            let mut node = 0;
+            let mut position = *board;
+            let mut position_state = *state;
+
+            // Selection: follow the best UCT scores down to a node with
+            // untried moves or no moves at all.
+            while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+                let parent_visits = tree[node].visits;
+                node = *tree[node]
+                    .children
+                    .iter()
+                    .max_by(|a, b| {
+                        tree[**a]
+                            .uct(parent_visits)
+                            .total_cmp(&tree[**b].uct(parent_visits))
+                    })
+                    .unwrap();
+                position = position.make_move_new(tree[node].mv.unwrap());
+                position_state = position_state.after_move(&position);
+            }
+
+            // Expansion: add one of the untried moves.
+            if !tree[node].untried.is_empty() {
+                let untried = &mut tree[node].untried;
+                let mv = untried.swap_remove(random_range(0..untried.len()));
+                position = position.make_move_new(mv);
+                position_state = position_state.after_move(&position);
+                let child = Node::new(Some(mv), Some(node), &position, &position_state);
+                memory += child.memory();
+                tree.push(child);
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+
+            // Simulation, then backpropagation of the result, which is
+            // for the side to move at `node` and alternates going up.
+            let (mut result, visited) = self.rollout(&position, &position_state);
+            nodes += visited as u64 + 1;
+            let mut current = Some(node);
+            while let Some(index) = current {
+                result = 1.0 - result;
+                tree[index].visits += 1;
+                tree[index].wins += result;
+                current = tree[index].parent;
+            }
+        }
+
+        debug!(
+            "MCTS ran {} iterations from {}, growing {} nodes",
+            iterations,
+            board,
+            tree.len()
+        );
+        let best = tree[0]
+            .children
+            .iter()
+            .max_by_key(|child| tree[**child].visits)
+            .map(|child| &tree[*child]);
+        SearchResult {
+            score: match best {
+                Some(child) => chance_score(child.wins / child.visits as f64),
+                None => 0,
+            },
+            best_move: best.and_then(|child| child.mv),
+            nodes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn mcts(iterations: u32) -> Mcts {
+        Mcts {
+            iterations,
+            options: EngineOptions::default(),
+        }
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let board = Board::from_str("6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1").unwrap();
+        let result = mcts(2000).search(&board, &VariantState::default());
+        assert_eq!(result.best_move, Some(ChessMove::from_str("e1e8").unwrap()));
+        assert!(result.score > 0);
+    }
+
+    #[test]
+    fn takes_hanging_queen() {
+        let board = Board::from_str("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+        let result = mcts(2000).search(&board, &VariantState::default());
+        assert_eq!(result.best_move, Some(ChessMove::from_str("d1d5").unwrap()));
+        assert!(result.nodes > 0);
+        assert_eq!(chance_score(0.5), 0);
+        assert!(win_chance(300) > 0.5);
+    }
+
+    #[test]
+    fn no_moves_no_result() {
+        let board = Board::from_str("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        let result = mcts(10).search(&board, &VariantState::default());
+        assert_eq!(result.best_move, None);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::ai::piece_value;
+use chess::{
+    between, get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_rook_moves,
+    line, BitBoard, Board, BoardStatus, ChessMove, Color, Piece, Rank, Square, EMPTY,
+};
+use std::fmt;
+
+/// A tactical pattern a move sets up on the board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Motif {
+    /// The moved piece attacks two enemy pieces at once.
+    Fork,
+    /// The moved piece attacks an enemy piece that can't move away
+    /// without exposing a more valuable one behind it.
+    Pin,
+    /// The moved piece attacks a valuable enemy piece that has to move
+    /// away, exposing a lesser one behind it.
+    Skewer,
+    /// The move uncovers an attack by a piece behind it.
+    DiscoveredAttack,
+    /// A rook or queen mates the king on its back rank.
+    BackRankMate,
+}
+
+impl fmt::Display for Motif {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Motif::Fork => write!(f, "fork"),
+            Motif::Pin => write!(f, "pin"),
+            Motif::Skewer => write!(f, "skewer"),
+            Motif::DiscoveredAttack => write!(f, "discovered attack"),
+            Motif::BackRankMate => write!(f, "back-rank mate"),
+        }
+    }
+}
+
+/// Finds the tactical motifs `mv`, a legal move on `board`, sets up.
+///
+/// Only attacks worth something count: on the king, on a piece more
+/// valuable than the attacker, or on an undefended one. Pawns are never
+/// targets.
+///
+/// # Example
+///
+/// ```
+/// // The knight checks the king and attacks the rook.
+/// let board = Board::from_str("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1").unwrap();
+/// let mv = ChessMove::from_str("b5c7").unwrap();
+/// assert_eq!(motifs(&board, mv), [Motif::Fork]);
+/// ```
+pub fn motifs(board: &Board, mv: ChessMove) -> Vec<Motif> {
+    let us = board.side_to_move();
+    let after = board.make_move_new(mv);
+    let dest = mv.get_dest();
+    let Some(piece) = after.piece_on(dest) else {
+        return Vec::new();
+    };
+    let occupied = *after.combined();
+    let theirs = *after.color_combined(!us);
+    let mut found = Vec::new();
+
+    // Whether attacking `target` with a piece worth `attacker` wins something.
+    let worth_attacking = |target: Square, attacker: Piece| match after.piece_on(target) {
+        Some(Piece::Pawn) | None => false,
+        Some(Piece::King) => true,
+        Some(victim) => {
+            piece_value(victim) > piece_value(attacker) || !attacked(&after, target, !us)
+        }
+    };
+
+    let safe = !attacked(&after, dest, !us) || attacked(&after, dest, us);
+    let targets = attacks(piece, us, dest, occupied) & theirs;
+    if safe
+        && targets
+            .into_iter()
+            .filter(|target| worth_attacking(*target, piece))
+            .count()
+            >= 2
+    {
+        found.push(Motif::Fork);
+    }
+
+    if matches!(piece, Piece::Bishop | Piece::Rook | Piece::Queen) {
+        for front in targets {
+            let rest = occupied ^ BitBoard::from_square(front);
+            let behind = attacks(piece, us, dest, rest)
+                & theirs
+                & line(dest, front)
+                & !attacks(piece, us, dest, occupied);
+            let (Some(front_piece), Some(back)) = (
+                after.piece_on(front),
+                behind
+                    .into_iter()
+                    .next()
+                    .and_then(|back| after.piece_on(back)),
+            ) else {
+                continue;
+            };
+            if front_piece == Piece::Pawn || back == Piece::Pawn {
+                continue;
+            }
+            let motif = if piece_value(front_piece) < piece_value(back) {
+                Motif::Pin
+            } else {
+                Motif::Skewer
+            };
+            let attacker = piece_value(piece);
+            let wins = match motif {
+                Motif::Pin => back == Piece::King || piece_value(back) > attacker,
+                _ => front_piece == Piece::King || piece_value(front_piece) > attacker,
+            };
+            if wins && !found.contains(&motif) {
+                found.push(motif);
+            }
+        }
+    }
+
+    let source = mv.get_source();
+    let sliders =
+        (*after.pieces(Piece::Bishop) | *after.pieces(Piece::Rook) | *after.pieces(Piece::Queen))
+            & after.color_combined(us);
+    let discovered = sliders
+        .into_iter()
+        .filter(|slider| *slider != dest)
+        .any(|slider| {
+            let slider_piece = after.piece_on(slider).unwrap();
+            (attacks(slider_piece, us, slider, occupied) & theirs)
+                .into_iter()
+                .any(|target| {
+                    between(slider, target) & BitBoard::from_square(source) != EMPTY
+                        && worth_attacking(target, slider_piece)
+                })
+        });
+    if discovered {
+        found.push(Motif::DiscoveredAttack);
+    }
+
+    if after.status() == BoardStatus::Checkmate {
+        let back_rank = match !us {
+            Color::White => Rank::First,
+            Color::Black => Rank::Eighth,
+        };
+        let on_back_rank = |square: Square| square.get_rank() == back_rank;
+        let heavy_check = after.checkers().into_iter().any(|checker| {
+            on_back_rank(checker)
+                && matches!(after.piece_on(checker), Some(Piece::Rook | Piece::Queen))
+        });
+        if heavy_check && on_back_rank(after.king_square(!us)) {
+            found.push(Motif::BackRankMate);
+        }
+    }
+    found
+}
+
+/// Returns the squares `piece` of `color` on `from` attacks, with
+/// pieces on `occupied` standing in the way of sliding pieces.
+pub fn attacks(piece: Piece, color: Color, from: Square, occupied: BitBoard) -> BitBoard {
+    match piece {
+        Piece::Pawn => get_pawn_attacks(from, color, !EMPTY),
+        Piece::Knight => get_knight_moves(from),
+        Piece::Bishop => get_bishop_moves(from, occupied),
+        Piece::Rook => get_rook_moves(from, occupied),
+        Piece::Queen => get_bishop_moves(from, occupied) | get_rook_moves(from, occupied),
+        Piece::King => get_king_moves(from),
+    }
+}
+
+/// Returns whether a piece of `color` attacks `square` on `board`.
+fn attacked(board: &Board, square: Square, color: Color) -> bool {
+    let occupied = *board.combined();
+    let pieces = |piece: Piece| *board.pieces(piece) & board.color_combined(color);
+    let diagonal = pieces(Piece::Bishop) | pieces(Piece::Queen);
+    let straight = pieces(Piece::Rook) | pieces(Piece::Queen);
+    (get_knight_moves(square) & pieces(Piece::Knight)) != EMPTY
+        || (get_king_moves(square) & pieces(Piece::King)) != EMPTY
+        || get_pawn_attacks(square, !color, pieces(Piece::Pawn)) != EMPTY
+        || (get_bishop_moves(square, occupied) & diagonal) != EMPTY
+        || (get_rook_moves(square, occupied) & straight) != EMPTY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn motifs_of(fen: &str, uci: &str) -> Vec<Motif> {
+        let board = Board::from_str(fen).unwrap();
+        motifs(&board, ChessMove::from_str(uci).unwrap())
+    }
+
+    #[test]
+    fn finds_forks_pins_and_skewers() {
+        assert_eq!(
+            motifs_of("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1", "b5c7"),
+            [Motif::Fork]
+        );
+        // A knight the pawn takes for free forks nothing.
+        assert_eq!(motifs_of("8/1p2k3/8/q7/1N6/8/8/4K3 w - - 0 1", "b4c6"), []);
+        assert_eq!(
+            motifs_of("8/4k3/8/q7/1N6/8/8/4K3 w - - 0 1", "b4c6"),
+            [Motif::Fork]
+        );
+        assert_eq!(
+            motifs_of("4k3/8/2n5/8/8/8/8/4KB2 w - - 0 1", "f1b5"),
+            [Motif::Pin]
+        );
+        assert_eq!(
+            motifs_of("8/8/8/8/q3k3/8/8/4K2R w - - 0 1", "h1h4"),
+            [Motif::Skewer]
+        );
+        assert_eq!(
+            motifs_of("1q6/8/8/4k3/8/8/8/4K1B1 w - - 0 1", "g1h2"),
+            [Motif::Skewer]
+        );
+    }
+
+    #[test]
+    fn finds_discovered_attacks_and_back_rank_mates() {
+        assert_eq!(
+            motifs_of("3q2k1/8/8/8/8/3N4/8/3RK3 w - - 0 1", "d3f4"),
+            [Motif::DiscoveredAttack]
+        );
+        assert_eq!(
+            motifs_of("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"),
+            [Motif::BackRankMate]
+        );
+    }
+}
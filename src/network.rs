@@ -0,0 +1,966 @@
+use crate::clock::TimeControl;
+use crate::rng::secret;
+use crate::variant::{Variant, VARIANTS};
+use chess::{ChessMove, Color};
+use log::{debug, info};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Port network games are hosted on unless another one is given.
+pub const DEFAULT_PORT: u16 = 7878;
+/// Newest version of the network protocol spoken. Host and guest speak
+/// the older of their newest versions. Clients from before versions
+/// were sent speak version 1.
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Oldest version of the network protocol still spoken.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// How long the host waits for a guest to introduce itself, and the
+/// guest for the host's answer.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often the guest tries to connect again after losing the
+/// connection, and the host looks for it.
+const RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What a client can do in a network game, sent in the handshake so
+/// the game only uses what both sides have.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    /// Variants the client plays.
+    pub variants: Vec<Variant>,
+    /// Whether the client asks for and answers takebacks.
+    pub takebacks: bool,
+    /// Whether the client shows chat messages.
+    pub chat: bool,
+}
+
+impl Default for Capabilities {
+    /// Everything this client can do.
+    fn default() -> Self {
+        Self {
+            variants: VARIANTS.to_vec(),
+            takebacks: true,
+            chat: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// What clients from before capabilities were sent can do: play
+    /// standard chess and chat.
+    fn legacy() -> Self {
+        Self {
+            variants: vec![Variant::Standard],
+            takebacks: false,
+            chat: true,
+        }
+    }
+
+    /// Returns what both this client and one that can do `other` can do.
+    pub fn common(&self, other: &Capabilities) -> Self {
+        Self {
+            variants: (self.variants.iter())
+                .filter(|variant| other.variants.contains(variant))
+                .copied()
+                .collect(),
+            takebacks: self.takebacks && other.takebacks,
+            chat: self.chat && other.chat,
+        }
+    }
+
+    /// Reads the capability written as `key`=`value`, skipping other
+    /// keys and unknown variants for compatibility with newer clients.
+    fn read(&mut self, key: &str, value: &str) {
+        match key {
+            "variants" => self.variants = value.split(',').filter_map(|v| v.parse().ok()).collect(),
+            "takebacks" => self.takebacks = value == "yes",
+            "chat" => self.chat = value == "yes",
+            _ => (),
+        }
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let variants: Vec<_> = self.variants.iter().map(|v| variant_word(*v)).collect();
+        let yes_no = |yes| if yes { "yes" } else { "no" };
+        write!(
+            f,
+            "variants={} takebacks={} chat={}",
+            variants.join(","),
+            yes_no(self.takebacks),
+            yes_no(self.chat)
+        )
+    }
+}
+
+/// How a guest introduces itself to the host, see [`Message::Hello`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hello {
+    /// The room code the guest was given, if any.
+    pub room: Option<String>,
+    /// The session token of a game the guest lost the connection to and
+    /// comes back to.
+    pub session: Option<String>,
+    /// The protocol version the guest speaks.
+    pub version: u32,
+    /// What the guest can do.
+    pub capabilities: Capabilities,
+}
+
+impl Default for Hello {
+    /// A hello from this client, with no room code or session token.
+    fn default() -> Self {
+        Self {
+            room: None,
+            session: None,
+            version: PROTOCOL_VERSION,
+            capabilities: Capabilities::default(),
+        }
+    }
+}
+
+impl FromStr for Hello {
+    type Err = ();
+
+    /// Reads `key=value` pairs, e.g. `room=k7x2 version=2`, skipping
+    /// unknown keys. A hello without a version is from a version 1
+    /// client.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hello = Hello {
+            version: 1,
+            capabilities: Capabilities::legacy(),
+            ..Hello::default()
+        };
+        for (key, value) in s.split_whitespace().filter_map(|pair| pair.split_once('=')) {
+            match key {
+                "room" => hello.room = Some(value.to_string()),
+                "session" => hello.session = Some(value.to_string()),
+                "version" => hello.version = value.parse().map_err(|_| ())?,
+                key => hello.capabilities.read(key, value),
+            }
+        }
+        Ok(hello)
+    }
+}
+
+impl fmt::Display for Hello {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "hello version={} {}", self.version, self.capabilities)?;
+        if let Some(room) = &self.room {
+            write!(f, " room={}", room)?;
+        }
+        if let Some(session) = &self.session {
+            write!(f, " session={}", session)?;
+        }
+        Ok(())
+    }
+}
+
+/// How the host lets the guest in, see [`Message::Welcome`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Welcome {
+    /// How the game is set up.
+    pub settings: GameSettings,
+    /// The token to come back to the game with if the connection drops.
+    pub session: String,
+    /// The protocol version the host speaks.
+    pub version: u32,
+    /// What both host and guest can do, and the game may use.
+    pub capabilities: Capabilities,
+}
+
+impl FromStr for Welcome {
+    type Err = ();
+
+    /// Reads the settings and `key=value` pairs of [`Welcome`]'s other
+    /// fields, skipping unknown keys.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut session = None;
+        let mut version = 1;
+        let mut capabilities = Capabilities::legacy();
+        for (key, value) in s.split_whitespace().filter_map(|pair| pair.split_once('=')) {
+            match key {
+                "session" => session = Some(value.to_string()),
+                "version" => version = value.parse().map_err(|_| ())?,
+                key => capabilities.read(key, value),
+            }
+        }
+        Ok(Welcome {
+            settings: s.parse()?,
+            session: session.ok_or(())?,
+            version,
+            capabilities,
+        })
+    }
+}
+
+impl fmt::Display for Welcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "welcome {} session={} version={} {}",
+            self.settings, self.session, self.version, self.capabilities
+        )
+    }
+}
+
+/// How the host set up a network game, sent to the guest once it's let
+/// in, see [`Connection::host`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSettings {
+    pub variant: Variant,
+    /// Time control of both players, `None` for an untimed game.
+    pub time_control: Option<TimeControl>,
+    /// Color the guest plays in the first game.
+    pub guest_color: Color,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            variant: Variant::Standard,
+            time_control: None,
+            guest_color: Color::Black,
+        }
+    }
+}
+
+impl FromStr for GameSettings {
+    type Err = ();
+
+    /// Reads settings written as `key=value` pairs, e.g.
+    /// `variant=koth color=white time=5+3`. Unknown keys are skipped for
+    /// compatibility with newer hosts.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut settings = GameSettings::default();
+        for (key, value) in s.split_whitespace().filter_map(|pair| pair.split_once('=')) {
+            match key {
+                "variant" => settings.variant = value.parse()?,
+                "time" => settings.time_control = Some(value.parse().map_err(|_| ())?),
+                "color" => settings.guest_color = parse_color(value)?,
+                _ => (),
+            }
+        }
+        Ok(settings)
+    }
+}
+
+impl fmt::Display for GameSettings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let variant = variant_word(self.variant);
+        let color = color_word(self.guest_color);
+        write!(f, "variant={} color={}", variant, color)?;
+        if let Some(time_control) = self.time_control {
+            write!(f, " time={}", time_control)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `variant` as it's sent, e.g. `kingofthehill`.
+fn variant_word(variant: Variant) -> String {
+    variant.to_string().to_lowercase().replace(' ', "")
+}
+
+/// Writes `color` as it's sent, `white` or `black`.
+fn color_word(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+/// Reads a color written with [`color_word`].
+fn parse_color(word: &str) -> Result<Color, ()> {
+    match word {
+        "white" => Ok(Color::White),
+        "black" => Ok(Color::Black),
+        _ => Err(()),
+    }
+}
+
+/// A message of the network protocol.
+///
+/// Messages are sent as lines of text: `move <uci> [<milliseconds>]`,
+/// `clock <milliseconds> <milliseconds>`, `say <text>`, `match <games>`,
+/// `takeback [yes|no]`, `rematch`, `flag <color>` or `quit` during a game,
+/// `hello <key>=<value>...` ([`Hello`]), `welcome <key>=<value>...`
+/// ([`Welcome`]) or `reject <reason>` to start it, and `sync <fen>
+/// moves <uci>...` after reconnecting.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// The guest introduces itself.
+    Hello(Hello),
+    /// The host lets the guest in.
+    Welcome(Welcome),
+    /// The host turns the guest away for the given reason.
+    Reject(String),
+    /// The sender played the given move, taking the given time over it
+    /// in a timed game, which is what their clock is charged rather than
+    /// the time the move took to arrive.
+    Move(ChessMove, Option<Duration>),
+    /// The host, keeping the clock of a timed game, sends the time White
+    /// and Black have left after every move.
+    Clock(Duration, Duration),
+    /// A chat message.
+    Chat(String),
+    /// The host starts a match over the given number of games, sent
+    /// before the first one.
+    Match(u32),
+    /// The sender wants to play again with colors swapped, once the
+    /// game is over.
+    Rematch,
+    /// The sender asks to take back their last move, with the
+    /// opponent's reply to it, if the game allows takebacks.
+    Takeback,
+    /// The answer to a takeback request: whether it's accepted.
+    TakebackAnswer(bool),
+    /// The given side ran out of time: sent by the host, going by its
+    /// clock, or by the guest about itself.
+    Flag(Color),
+    /// The host sends the game after the guest reconnected: the position
+    /// in FEN and every move played, so the guest can take back the
+    /// moves the host never got and play those it missed.
+    Sync(String, Vec<ChessMove>),
+    /// The sender left the game.
+    Quit,
+}
+
+impl FromStr for Message {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim_end().split_once(' ').unwrap_or((s.trim_end(), "")) {
+            ("hello", hello) => hello.parse().map(Message::Hello),
+            ("welcome", welcome) => welcome.parse().map(Message::Welcome),
+            ("sync", game) => {
+                let (fen, moves) = game.split_once(" moves").ok_or(())?;
+                let moves = moves
+                    .split_whitespace()
+                    .map(ChessMove::from_str)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| ())?;
+                Ok(Message::Sync(fen.to_string(), moves))
+            }
+            ("reject", reason) => Ok(Message::Reject(reason.to_string())),
+            ("takeback", "") => Ok(Message::Takeback),
+            ("takeback", "yes") => Ok(Message::TakebackAnswer(true)),
+            ("takeback", "no") => Ok(Message::TakebackAnswer(false)),
+            ("flag", color) => parse_color(color).map(Message::Flag),
+            ("move", mv) => {
+                let (mv, spent) = mv.split_once(' ').unwrap_or((mv, ""));
+                let spent = match spent {
+                    "" => None,
+                    millis => Some(Duration::from_millis(millis.parse().map_err(|_| ())?)),
+                };
+                let mv = ChessMove::from_str(mv).map_err(|_| ())?;
+                Ok(Message::Move(mv, spent))
+            }
+            ("clock", times) => {
+                let (white, black) = times.split_once(' ').ok_or(())?;
+                let millis = |time: &str| time.parse().map(Duration::from_millis).map_err(|_| ());
+                Ok(Message::Clock(millis(white)?, millis(black)?))
+            }
+            ("say", text) => Ok(Message::Chat(text.to_string())),
+            ("match", games) => games.parse().map(Message::Match).map_err(|_| ()),
+            ("rematch", "") => Ok(Message::Rematch),
+            ("quit", "") => Ok(Message::Quit),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Hello(hello) => write!(f, "{}", hello),
+            Message::Welcome(welcome) => write!(f, "{}", welcome),
+            Message::Sync(fen, moves) => {
+                write!(f, "sync {} moves", fen)?;
+                for mv in moves {
+                    write!(f, " {}", mv)?;
+                }
+                Ok(())
+            }
+            Message::Reject(reason) => write!(f, "reject {}", reason.replace(['\r', '\n'], " ")),
+            Message::Takeback => write!(f, "takeback"),
+            Message::TakebackAnswer(true) => write!(f, "takeback yes"),
+            Message::TakebackAnswer(false) => write!(f, "takeback no"),
+            Message::Flag(color) => write!(f, "flag {}", color_word(*color)),
+            Message::Move(mv, None) => write!(f, "move {}", mv),
+            Message::Move(mv, Some(spent)) => write!(f, "move {} {}", mv, spent.as_millis()),
+            Message::Clock(white, black) => {
+                write!(f, "clock {} {}", white.as_millis(), black.as_millis())
+            }
+            Message::Chat(text) => write!(f, "say {}", text.replace(['\r', '\n'], " ")),
+            Message::Match(games) => write!(f, "match {}", games),
+            Message::Rematch => write!(f, "rematch"),
+            Message::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+/// Which side of the game the local player is on, to know how to get
+/// the opponent back if the connection drops.
+enum Role {
+    /// Hosting the game, waiting for the guest on the listener again.
+    Host(TcpListener),
+    /// Joined the game at the address, connecting to it again.
+    Guest(String),
+}
+
+/// A connection to the opponent in a network game.
+///
+/// Incoming messages are read on a background thread, so they can be
+/// checked for without blocking while the local player is thinking.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Receiver<Message>,
+    role: Role,
+    /// Token the guest gives to come back to the game after losing the
+    /// connection, see [`Connection::reconnect`].
+    session: String,
+    /// Protocol version both sides speak.
+    version: u32,
+    /// What both sides can do.
+    capabilities: Capabilities,
+}
+
+impl Connection {
+    /// Waits for an opponent to connect on `port` and lets them in with
+    /// `settings`, turning away those who don't give the room code
+    /// `room`, if one is set, or can't play the game. The game uses what
+    /// both the host, able to do `capabilities`, and the guest can do.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidInput` error if `room` isn't a valid room code,
+    /// see [`valid_room`].
+    pub fn host(
+        port: u16,
+        room: Option<&str>,
+        settings: &GameSettings,
+        capabilities: &Capabilities,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        Self::accept_guest(listener, room, settings, capabilities)
+    }
+
+    /// Lets in the first guest connecting to `listener` that may join,
+    /// see [`Connection::host`].
+    fn accept_guest(
+        listener: TcpListener,
+        room: Option<&str>,
+        settings: &GameSettings,
+        capabilities: &Capabilities,
+    ) -> io::Result<Self> {
+        if room.is_some_and(|room| !valid_room(room)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "room codes can't be empty or contain spaces",
+            ));
+        }
+        let session = format!("{:032x}", secret());
+        loop {
+            let (stream, address) = listener.accept()?;
+            let (mut stream, incoming) = open(stream)?;
+            match incoming.recv_timeout(HANDSHAKE_TIMEOUT) {
+                Ok(Message::Hello(hello)) => match admit(&hello, room, settings, capabilities) {
+                    Ok((version, capabilities)) => {
+                        let welcome = Welcome {
+                            settings: settings.clone(),
+                            session: session.clone(),
+                            version,
+                            capabilities: capabilities.clone(),
+                        };
+                        write_message(&mut stream, &Message::Welcome(welcome))?;
+                        return Ok(Self {
+                            stream,
+                            incoming,
+                            role: Role::Host(listener),
+                            session,
+                            version,
+                            capabilities,
+                        });
+                    }
+                    Err(reason) => {
+                        info!("Turned away {}: {}", address, reason);
+                        let _ = write_message(&mut stream, &Message::Reject(reason));
+                    }
+                },
+                _ => info!("Turned away {}: no hello", address),
+            }
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Connects to an opponent hosting a game at `address` (`host:port`)
+    /// with `hello`, giving the room code if the game has one, or the
+    /// session token of a game the player lost the connection to.
+    ///
+    /// # Returns
+    ///
+    /// The connection and how the host set up the game.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host can't be reached, turns the player
+    /// away or doesn't answer.
+    pub fn join(address: &str, hello: Hello) -> io::Result<(Self, GameSettings)> {
+        let (mut stream, incoming) = open(TcpStream::connect(address)?)?;
+        write_message(&mut stream, &Message::Hello(hello))?;
+        let welcome = welcome(&incoming)?;
+        let connection = Self {
+            stream,
+            incoming,
+            role: Role::Guest(address.to_string()),
+            session: welcome.session,
+            version: welcome.version,
+            capabilities: welcome.capabilities,
+        };
+        Ok((connection, welcome.settings))
+    }
+
+    /// Returns what both sides can do, and the game may use.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Returns the token the guest can come back to the game with, e.g.
+    /// from a new session: `rChess join <address> --session <token>`.
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Returns whether the local player hosts the game, and so sends it
+    /// to the guest after reconnecting, see [`Message::Sync`].
+    pub fn is_host(&self) -> bool {
+        matches!(self.role, Role::Host(_))
+    }
+
+    /// Gets the opponent back after the connection dropped, trying for
+    /// up to `grace`: the host waits for the guest to come back with the
+    /// session token, turning away anyone else, and lets it in with
+    /// `settings`; the guest connects to the host again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the opponent isn't back within `grace`.
+    pub fn reconnect(&mut self, grace: Duration, settings: &GameSettings) -> io::Result<()> {
+        let deadline = Instant::now() + grace;
+        let gone = || io::Error::new(io::ErrorKind::TimedOut, "the opponent didn't come back");
+        let (stream, incoming) = match &self.role {
+            Role::Host(listener) => {
+                listener.set_nonblocking(true)?;
+                let accepted = loop {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            stream.set_nonblocking(false)?;
+                            let (mut stream, incoming) = open(stream)?;
+                            match incoming.recv_timeout(HANDSHAKE_TIMEOUT) {
+                                Ok(Message::Hello(hello))
+                                    if hello.session.as_deref() == Some(&self.session) =>
+                                {
+                                    let welcome = Welcome {
+                                        settings: settings.clone(),
+                                        session: self.session.clone(),
+                                        version: self.version,
+                                        capabilities: self.capabilities.clone(),
+                                    };
+                                    write_message(&mut stream, &Message::Welcome(welcome))?;
+                                    break Ok((stream, incoming));
+                                }
+                                _ => {
+                                    let reject = Message::Reject("game in progress".to_string());
+                                    let _ = write_message(&mut stream, &reject);
+                                    let _ = stream.shutdown(Shutdown::Both);
+                                }
+                            }
+                        }
+                        Err(e) if e.kind() != io::ErrorKind::WouldBlock => break Err(e),
+                        Err(_) if Instant::now() >= deadline => break Err(gone()),
+                        Err(_) => thread::sleep(RETRY_INTERVAL),
+                    }
+                };
+                listener.set_nonblocking(false)?;
+                accepted?
+            }
+            Role::Guest(address) => loop {
+                let hello = Hello {
+                    session: Some(self.session.clone()),
+                    ..Hello::default()
+                };
+                let attempt = TcpStream::connect(address).and_then(open).and_then(
+                    |(mut stream, incoming)| {
+                        write_message(&mut stream, &Message::Hello(hello))?;
+                        welcome(&incoming)?;
+                        Ok((stream, incoming))
+                    },
+                );
+                match attempt {
+                    Ok(connection) => break connection,
+                    Err(e) if e.kind() == io::ErrorKind::PermissionDenied => return Err(e),
+                    Err(_) if Instant::now() >= deadline => return Err(gone()),
+                    Err(_) => thread::sleep(RETRY_INTERVAL),
+                }
+            },
+        };
+        info!("Reconnected to the opponent");
+        let _ = self.stream.shutdown(Shutdown::Both);
+        self.stream = stream;
+        self.incoming = incoming;
+        Ok(())
+    }
+
+    /// Sends a message to the opponent.
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        write_message(&mut self.stream, message)
+    }
+
+    /// Waits for the next message, returning `None` once the opponent disconnected.
+    pub fn receive(&self) -> Option<Message> {
+        self.incoming.recv().ok()
+    }
+
+    /// Returns the next message if one has already arrived.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TryRecvError::Empty`] if none has yet, and
+    /// [`TryRecvError::Disconnected`] once the opponent disconnected.
+    pub fn try_receive(&self) -> Result<Message, TryRecvError> {
+        self.incoming.try_recv()
+    }
+}
+
+/// Reads the messages arriving on `stream` on a background thread.
+///
+/// # Returns
+///
+/// The stream, to send messages on, and the messages received.
+fn open(stream: TcpStream) -> io::Result<(TcpStream, Receiver<Message>)> {
+    if let Ok(address) = stream.peer_addr() {
+        info!("Connected to {}", address);
+    }
+    let reader = BufReader::new(stream.try_clone()?);
+    let (sender, incoming) = mpsc::channel();
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            // Unknown messages are skipped for compatibility with newer clients.
+            debug!("Received {}", line);
+            if let Ok(message) = line.parse() {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    Ok((stream, incoming))
+}
+
+fn write_message(stream: &mut TcpStream, message: &Message) -> io::Result<()> {
+    debug!("Sending {}", message);
+    writeln!(stream, "{}", message)?;
+    stream.flush()
+}
+
+/// Returns whether `room` can be a room code: a non-empty word, as it's
+/// sent as `room=<code>` in a line of space-separated pairs.
+pub fn valid_room(room: &str) -> bool {
+    !room.is_empty() && !room.contains(char::is_whitespace)
+}
+
+/// Checks the guest introducing itself with `hello` may join the game
+/// set up with `settings` and the room code `room`, if one is set.
+///
+/// # Returns
+///
+/// The protocol version both speak, the older of the host's and the
+/// guest's, and what both the host, able to do `capabilities`, and the
+/// guest can do in it.
+///
+/// # Errors
+///
+/// Returns why the guest is turned away.
+fn admit(
+    hello: &Hello,
+    room: Option<&str>,
+    settings: &GameSettings,
+    capabilities: &Capabilities,
+) -> Result<(u32, Capabilities), String> {
+    if room.is_some_and(|room| hello.room.as_deref() != Some(room)) {
+        return Err("wrong room code".to_string());
+    }
+    if hello.version < MIN_PROTOCOL_VERSION {
+        return Err(format!(
+            "the host speaks protocol versions {} to {} and you {}, update rChess",
+            MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, hello.version
+        ));
+    }
+    let version = hello.version.min(PROTOCOL_VERSION);
+    let mut common = capabilities.common(&hello.capabilities);
+    if version == 1 {
+        common = common.common(&Capabilities::legacy());
+    }
+    if !common.variants.contains(&settings.variant) {
+        return Err(format!("your rChess doesn't play {}", settings.variant));
+    }
+    Ok((version, common))
+}
+
+/// Waits for the host to let the guest in.
+///
+/// # Errors
+///
+/// Returns an error if the host turns the guest away, picks a protocol
+/// version the guest doesn't speak or doesn't answer.
+fn welcome(incoming: &Receiver<Message>) -> io::Result<Welcome> {
+    match incoming.recv_timeout(HANDSHAKE_TIMEOUT) {
+        Ok(Message::Welcome(welcome))
+            if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&welcome.version) =>
+        {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "the host speaks protocol version {} and you {} to {}, update rChess",
+                    welcome.version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+                ),
+            ))
+        }
+        Ok(Message::Welcome(welcome)) => Ok(welcome),
+        Ok(Message::Reject(reason)) => Err(io::Error::new(io::ErrorKind::PermissionDenied, reason)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the host didn't answer",
+        )),
+    }
+}
+
+impl Drop for Connection {
+    /// Closes the connection, also ending the background reader.
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess::Board;
+
+    fn connect(stream: TcpStream, role: Role) -> Connection {
+        let (stream, incoming) = open(stream).unwrap();
+        Connection {
+            stream,
+            incoming,
+            role,
+            session: String::new(),
+            version: PROTOCOL_VERSION,
+            capabilities: Capabilities::default(),
+        }
+    }
+
+    #[test]
+    fn message_format() {
+        let mv = ChessMove::from_str("e7e8q").unwrap();
+        for message in [
+            Message::Move(mv, None),
+            Message::Move(mv, Some(Duration::from_millis(1500))),
+            Message::Clock(Duration::from_secs(300), Duration::from_millis(299_900)),
+            Message::Chat("good luck!".to_string()),
+            Message::Match(5),
+            Message::Rematch,
+            Message::Flag(Color::Black),
+            Message::Takeback,
+            Message::TakebackAnswer(false),
+            Message::Quit,
+            Message::Hello(Hello::default()),
+            Message::Hello(Hello {
+                room: Some("k7x2".to_string()),
+                session: Some("00c0ffee".to_string()),
+                ..Hello::default()
+            }),
+            Message::Sync(
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".to_string(),
+                vec![ChessMove::from_str("e2e4").unwrap()],
+            ),
+            Message::Sync(Board::default().to_string(), Vec::new()),
+            Message::Reject("wrong room code".to_string()),
+            Message::Welcome(Welcome {
+                settings: GameSettings {
+                    variant: Variant::KingOfTheHill,
+                    time_control: Some("5+3".parse().unwrap()),
+                    guest_color: Color::White,
+                },
+                session: "00c0ffee".to_string(),
+                version: PROTOCOL_VERSION,
+                capabilities: Capabilities {
+                    variants: vec![Variant::Standard, Variant::KingOfTheHill],
+                    takebacks: false,
+                    chat: true,
+                },
+            }),
+        ] {
+            assert_eq!(message.to_string().parse(), Ok(message));
+        }
+        assert_eq!(
+            Message::Chat("two\nlines".to_string()).to_string(),
+            "say two lines"
+        );
+        assert!(Message::from_str("move e9e4").is_err());
+        assert!(Message::from_str("resign").is_err());
+        assert!(Message::from_str("match three").is_err());
+    }
+
+    #[test]
+    fn exchanges_messages() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let stream = TcpStream::connect(&address).unwrap();
+        let mut guest = connect(stream, Role::Guest(address));
+        let stream = listener.accept().unwrap().0;
+        let mut host = connect(stream, Role::Host(listener));
+
+        let mv = Message::Move(ChessMove::from_str("e2e4").unwrap(), None);
+        host.send(&mv).unwrap();
+        host.send(&Message::Chat("hello".to_string())).unwrap();
+        assert_eq!(guest.receive(), Some(mv));
+        assert_eq!(guest.receive(), Some(Message::Chat("hello".to_string())));
+        guest.send(&Message::Quit).unwrap();
+        assert_eq!(host.receive(), Some(Message::Quit));
+
+        drop(guest);
+        assert_eq!(host.receive(), None);
+        assert_eq!(host.try_receive(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn rejects_room_codes_with_spaces() {
+        assert!(valid_room("k7x2"));
+        assert!(!valid_room("k7 x2"));
+        assert!(!valid_room(""));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let hosted = Connection::accept_guest(
+            listener,
+            Some("k7 x2"),
+            &GameSettings::default(),
+            &Capabilities::default(),
+        );
+        assert_eq!(
+            hosted.err().map(|e| e.kind()),
+            Some(io::ErrorKind::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn lets_in_guests_with_the_room_code() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let settings = GameSettings {
+            guest_color: Color::White,
+            ..GameSettings::default()
+        };
+        let host = thread::spawn({
+            let settings = settings.clone();
+            move || {
+                let capabilities = Capabilities::default();
+                Connection::accept_guest(listener, Some("k7x2"), &settings, &capabilities)
+            }
+        });
+        let hello = |room: &str| Hello {
+            room: Some(room.to_string()),
+            ..Hello::default()
+        };
+        let turned_away = Connection::join(&address, hello("0000")).err();
+        assert_eq!(
+            turned_away.map(|e| e.kind()),
+            Some(io::ErrorKind::PermissionDenied)
+        );
+        let (mut guest, joined) = Connection::join(&address, hello("k7x2")).unwrap();
+        assert_eq!(joined, settings);
+        let mut host = host.join().unwrap().unwrap();
+        assert_eq!(guest.session(), host.session());
+
+        // The guest loses the connection and comes back with the session token.
+        guest.stream.shutdown(Shutdown::Both).unwrap();
+        assert_eq!(host.receive(), None);
+        let grace = Duration::from_secs(5);
+        let host = thread::spawn(move || {
+            host.reconnect(grace, &settings).unwrap();
+            host.send(&Message::Rematch).unwrap();
+            host
+        });
+        guest.reconnect(grace, &GameSettings::default()).unwrap();
+        assert_eq!(guest.receive(), Some(Message::Rematch));
+        assert!(host.join().unwrap().is_host());
+    }
+
+    #[test]
+    fn negotiates_versions_and_capabilities() {
+        let settings = GameSettings {
+            variant: Variant::ThreeCheck,
+            ..GameSettings::default()
+        };
+        let host = Capabilities {
+            takebacks: false,
+            ..Capabilities::default()
+        };
+        let hello = Hello::default();
+        let (version, common) = admit(&hello, None, &settings, &host).unwrap();
+        assert_eq!(version, PROTOCOL_VERSION);
+        assert_eq!(common.variants, VARIANTS);
+        assert!(!common.takebacks && common.chat);
+
+        // Clients from before versions were sent only play standard chess.
+        let standard = GameSettings::default();
+        let legacy: Hello = "room=k7x2".parse().unwrap();
+        assert_eq!(legacy.version, 1);
+        assert!(admit(&legacy, Some("k7x2"), &settings, &host)
+            .unwrap_err()
+            .contains("doesn't play"));
+        let (version, common) = admit(&legacy, Some("k7x2"), &standard, &host).unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(common, Capabilities::legacy().common(&host));
+        let newer: Hello = "version=3 variants=standard,crazyhouse chat=no"
+            .parse()
+            .unwrap();
+        assert_eq!(newer.capabilities.variants, [Variant::Standard]);
+        assert!(admit(&newer, None, &settings, &host).is_err());
+        let (version, common) = admit(&newer, None, &standard, &host).unwrap();
+        assert_eq!(version, PROTOCOL_VERSION);
+        assert!(!common.chat);
+        let ancient: Hello = "version=0".parse().unwrap();
+        assert!(admit(&ancient, None, &standard, &host)
+            .unwrap_err()
+            .contains("update rChess"));
+    }
+
+    #[test]
+    fn plays_version_1_guests_without_takebacks() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let host = thread::spawn(move || {
+            let settings = GameSettings::default();
+            Connection::accept_guest(listener, None, &settings, &Capabilities::default())
+        });
+        // A version 1 client says hello without a version or capabilities.
+        let mut guest = TcpStream::connect(address).unwrap();
+        writeln!(guest, "hello").unwrap();
+        let mut line = String::new();
+        BufReader::new(&guest).read_line(&mut line).unwrap();
+        let Ok(Message::Welcome(welcome)) = line.trim_end().parse() else {
+            panic!("expected a welcome, got {:?}", line);
+        };
+        assert_eq!(welcome.version, 1);
+        assert_eq!(welcome.capabilities, Capabilities::legacy());
+        let host = host.join().unwrap().unwrap();
+        assert_eq!(host.version, 1);
+        assert!(!host.capabilities().takebacks);
+    }
+}
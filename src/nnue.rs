@@ -0,0 +1,283 @@
+use chess::{Board, ChessMove, Color, File, Piece, Square, ALL_SQUARES};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Bytes every network file starts with.
+const MAGIC: &[u8; 4] = b"RCNN";
+/// Number of input features: one per piece type and color on each square.
+const FEATURES: usize = 768;
+/// Scale of the hidden layer: activations are clipped to `0..=QA`.
+const QA: i32 = 255;
+/// Scale of the output weights.
+const QB: i32 = 64;
+/// Centipawns the network's output is multiplied by.
+const SCALE: i32 = 400;
+
+/// The network loaded with [`set_network`], used by the search in place
+/// of the hand-written evaluation.
+static NETWORK: OnceLock<Network> = OnceLock::new();
+
+/// Sets the network the engine evaluates positions with. Only the first
+/// network set is used.
+pub fn set_network(network: Network) {
+    let _ = NETWORK.set(network);
+}
+
+/// Returns the network set with [`set_network`], if any.
+pub fn network() -> Option<&'static Network> {
+    NETWORK.get()
+}
+
+/// A small efficiently updatable neural network evaluating positions.
+///
+/// The 768 inputs say which piece of which color is on each square,
+/// seen from each side; they feed a hidden layer of any size, computed
+/// once for each side, whose clipped activations are combined into the
+/// score, side to move first.
+#[derive(Debug, PartialEq)]
+pub struct Network {
+    hidden: usize,
+    /// Hidden layer weights, `hidden` for each feature in turn.
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    /// Output weights for the side to move's activations, then the other side's.
+    output_weights: Vec<i16>,
+    output_bias: i16,
+}
+
+/// The hidden layer of a [`Network`] before clipping, for each side's
+/// point of view, indexed by color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accumulator {
+    values: [Vec<i16>; 2],
+}
+
+impl Accumulator {
+    /// Returns how many bytes the accumulator takes.
+    pub fn memory(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .values
+                .iter()
+                .map(|values| values.capacity() * size_of::<i16>())
+                .sum::<usize>()
+    }
+}
+
+/// Returns the input feature of a `piece` of `color` on `square` from
+/// the point of view of `perspective`, with the board flipped for Black.
+fn feature(perspective: Color, color: Color, piece: Piece, square: Square) -> usize {
+    let (side, square) = match perspective {
+        Color::White => (color.to_index(), square.to_index()),
+        Color::Black => ((!color).to_index(), square.to_index() ^ 56),
+    };
+    (side * 6 + piece.to_index()) * 64 + square
+}
+
+impl Network {
+    /// Reads a network file.
+    ///
+    /// The file holds [`MAGIC`], the size of the hidden layer as a
+    /// little-endian `u32`, then little-endian `i16` values: the hidden
+    /// layer weights (feature by feature) and biases, the output weights
+    /// and the output bias.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't a network.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("not an rChess network file"))?;
+        if rest.len() < 4 {
+            return Err(invalid("network file is truncated"));
+        }
+        let hidden = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+        let values: Vec<i16> = rest[4..]
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        if hidden == 0 || rest.len() % 2 != 0 || values.len() != (FEATURES + 3) * hidden + 1 {
+            return Err(invalid("network file has the wrong size"));
+        }
+        let (feature_weights, rest) = values.split_at(FEATURES * hidden);
+        let (feature_biases, rest) = rest.split_at(hidden);
+        let (output_weights, rest) = rest.split_at(2 * hidden);
+        Ok(Self {
+            hidden,
+            feature_weights: feature_weights.to_vec(),
+            feature_biases: feature_biases.to_vec(),
+            output_weights: output_weights.to_vec(),
+            output_bias: rest[0],
+        })
+    }
+
+    /// Returns the hidden layer weights of `feature`.
+    fn weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+
+    /// Computes the accumulator of `board` from scratch.
+    pub fn refresh(&self, board: &Board) -> Accumulator {
+        let mut accumulator = Accumulator {
+            values: [self.feature_biases.clone(), self.feature_biases.clone()],
+        };
+        for square in ALL_SQUARES {
+            if let (Some(piece), Some(color)) = (board.piece_on(square), board.color_on(square)) {
+                self.change(&mut accumulator, color, piece, square, true);
+            }
+        }
+        accumulator
+    }
+
+    /// Adds or removes a piece in both halves of `accumulator`.
+    fn change(
+        &self,
+        accumulator: &mut Accumulator,
+        color: Color,
+        piece: Piece,
+        square: Square,
+        add: bool,
+    ) {
+        for perspective in [Color::White, Color::Black] {
+            let weights = self.weights(feature(perspective, color, piece, square));
+            let values = &mut accumulator.values[perspective.to_index()];
+            for (value, weight) in values.iter_mut().zip(weights) {
+                *value = if add {
+                    value.wrapping_add(*weight)
+                } else {
+                    value.wrapping_sub(*weight)
+                };
+            }
+        }
+    }
+
+    /// Returns the accumulator after `mv` is played on `board`, whose
+    /// accumulator is `accumulator`, changing only the pieces the move
+    /// moves, captures or promotes instead of starting over.
+    pub fn update(&self, accumulator: &Accumulator, board: &Board, mv: ChessMove) -> Accumulator {
+        let mut next = accumulator.clone();
+        let (from, to) = (mv.get_source(), mv.get_dest());
+        let (Some(piece), Some(color)) = (board.piece_on(from), board.color_on(from)) else {
+            return self.refresh(&board.make_move_new(mv));
+        };
+        self.change(&mut next, color, piece, from, false);
+        self.change(
+            &mut next,
+            color,
+            mv.get_promotion().unwrap_or(piece),
+            to,
+            true,
+        );
+        if let Some(captured) = board.piece_on(to) {
+            self.change(&mut next, !color, captured, to, false);
+        } else if piece == Piece::Pawn && from.get_file() != to.get_file() {
+            // En passant: the captured pawn stands beside the moving one.
+            let square = Square::make_square(from.get_rank(), to.get_file());
+            self.change(&mut next, !color, Piece::Pawn, square, false);
+        }
+        if piece == Piece::King
+            && from
+                .get_file()
+                .to_index()
+                .abs_diff(to.get_file().to_index())
+                == 2
+        {
+            let (rook_from, rook_to) = match to.get_file() {
+                File::G => (File::H, File::F),
+                _ => (File::A, File::D),
+            };
+            let rank = from.get_rank();
+            self.change(
+                &mut next,
+                color,
+                Piece::Rook,
+                Square::make_square(rank, rook_from),
+                false,
+            );
+            self.change(
+                &mut next,
+                color,
+                Piece::Rook,
+                Square::make_square(rank, rook_to),
+                true,
+            );
+        }
+        next
+    }
+
+    /// Evaluates the position of `accumulator` in centipawns from the
+    /// point of view of `side_to_move`.
+    pub fn evaluate(&self, accumulator: &Accumulator, side_to_move: Color) -> i32 {
+        let ours = &accumulator.values[side_to_move.to_index()];
+        let theirs = &accumulator.values[(!side_to_move).to_index()];
+        let (our_weights, their_weights) = self.output_weights.split_at(self.hidden);
+        let clipped = |value: i16| (value as i32).clamp(0, QA);
+        let sum: i32 = ours
+            .iter()
+            .zip(our_weights)
+            .chain(theirs.iter().zip(their_weights))
+            .map(|(value, weight)| clipped(*value) * *weight as i32)
+            .sum();
+        (sum + self.output_bias as i32) * SCALE / (QA * QB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_san;
+    use std::str::FromStr;
+
+    /// A network with made-up weights, encoded as a network file.
+    fn test_network_bytes(hidden: usize) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((hidden as u32).to_le_bytes());
+        let count = (FEATURES + 3) * hidden + 1;
+        for i in 0..count {
+            let value = ((i * 37) % 61) as i16 - 30;
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_network_files() {
+        let network = Network::from_bytes(&test_network_bytes(4)).unwrap();
+        assert_eq!(network.hidden, 4);
+        assert_eq!(network.output_weights.len(), 8);
+        let mut truncated = test_network_bytes(4);
+        truncated.pop();
+        assert!(Network::from_bytes(&truncated).is_err());
+        assert!(Network::from_bytes(b"PNG").is_err());
+    }
+
+    #[test]
+    fn incremental_updates_match_refresh() {
+        let network = Network::from_bytes(&test_network_bytes(8)).unwrap();
+        // Castling, en passant, captures and a promotion.
+        let board = Board::from_str("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let accumulator = network.refresh(&board);
+        for san in ["O-O", "O-O-O", "exd6", "bxa8=Q", "Rxa8", "b8=N"] {
+            let mv = parse_san(&board, san).unwrap();
+            let after = board.make_move_new(mv);
+            assert_eq!(
+                network.update(&accumulator, &board, mv),
+                network.refresh(&after),
+                "{}",
+                san
+            );
+        }
+        assert_eq!(
+            network.evaluate(&accumulator, Color::White),
+            network.evaluate(&network.refresh(&board), Color::White)
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use chess::{
+    Board, BoardStatus, ChessMove, Color, File, MoveGen, Piece, Rank, Square, ALL_FILES, EMPTY,
+};
+use std::fmt;
+use std::str::FromStr;
+
+/// Text that couldn't be read as a file, rank, square or move, with
+/// a hint at what was expected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NotationError {
+    File(String),
+    Rank(String),
+    Square(String),
+    /// A move that can't be read or isn't legal, in the notation named.
+    Move {
+        text: String,
+        notation: &'static str,
+    },
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::File(text) => {
+                write!(f, "`{}` isn't a file, files go from a to h", text)
+            }
+            NotationError::Rank(text) => {
+                write!(f, "`{}` isn't a rank, ranks go from 1 to 8", text)
+            }
+            NotationError::Square(text) => write!(
+                f,
+                "`{}` isn't a square, write a file and a rank, e.g. e4",
+                text
+            ),
+            NotationError::Move { text, notation } => {
+                write!(f, "`{}` isn't a legal move in {}", text, notation)
+            }
+        }
+    }
+}
+
+/// Reads a file, `a` to `h` in either case.
+pub fn parse_file(text: &str) -> Result<File, NotationError> {
+    match text.as_bytes() {
+        [letter @ b'a'..=b'h'] | [letter @ b'A'..=b'H'] => Ok(File::from_index(usize::from(
+            letter.to_ascii_lowercase() - b'a',
+        ))),
+        _ => Err(NotationError::File(text.to_string())),
+    }
+}
+
+/// Reads a rank, `1` to `8`.
+pub fn parse_rank(text: &str) -> Result<Rank, NotationError> {
+    match text.as_bytes() {
+        [digit @ b'1'..=b'8'] => Ok(Rank::from_index(usize::from(digit - b'1'))),
+        _ => Err(NotationError::Rank(text.to_string())),
+    }
+}
+
+/// Reads a square such as `e4`, telling whether its file or its rank
+/// is wrong.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(parse_square("E4"), Ok(Square::E4));
+/// assert_eq!(parse_square("e9"), Err(NotationError::Rank("9".to_string())));
+/// ```
+pub fn parse_square(text: &str) -> Result<Square, NotationError> {
+    let text = text.trim();
+    if text.len() != 2 || !text.is_ascii() {
+        return Err(NotationError::Square(text.to_string()));
+    }
+    let file = parse_file(&text[..1])?;
+    let rank = parse_rank(&text[1..])?;
+    Ok(Square::make_square(rank, file))
+}
+
+/// Reads a legal move in UCI notation, e.g. `e7e8q`.
+pub fn parse_uci(board: &Board, text: &str) -> Result<ChessMove, NotationError> {
+    ChessMove::from_str(text)
+        .ok()
+        .filter(|mv| board.legal(*mv))
+        .ok_or_else(|| NotationError::Move {
+            text: text.to_string(),
+            notation: "UCI",
+        })
+}
+
+/// Returns the Standard Algebraic Notation of a legal move, including
+/// disambiguation and the `+`/`#` suffix.
+///
+/// # Example
+///
+/// ```
+/// let mv = ChessMove::from_str("g1f3").unwrap();
+/// assert_eq!(san_for_move(&Board::default(), mv), "Nf3");
+/// ```
+pub fn san_for_move(board: &Board, mv: ChessMove) -> String {
+    let source = mv.get_source();
+    let dest = mv.get_dest();
+    let piece = board.piece_on(source).unwrap();
+    let capture = board.piece_on(dest).is_some()
+        || (piece == Piece::Pawn && source.get_file() != dest.get_file());
+
+    let mut san = String::new();
+    if piece == Piece::King && source.get_file() == File::E && dest.get_file() == File::G {
+        san.push_str("O-O");
+    } else if piece == Piece::King && source.get_file() == File::E && dest.get_file() == File::C {
+        san.push_str("O-O-O");
+    } else if piece == Piece::Pawn {
+        if capture {
+            san.push(file_char(source.get_file()));
+            san.push('x');
+        }
+        san.push_str(&dest.to_string());
+        if let Some(promotion) = mv.get_promotion() {
+            san.push('=');
+            san.push(piece_char(promotion));
+        }
+    } else {
+        san.push(piece_char(piece));
+        let rivals: Vec<_> = MoveGen::new_legal(board)
+            .filter(|other| {
+                other.get_dest() == dest
+                    && other.get_source() != source
+                    && board.piece_on(other.get_source()) == Some(piece)
+            })
+            .map(|other| other.get_source())
+            .collect();
+        if !rivals.is_empty() {
+            let source_name = source.to_string();
+            if rivals.iter().all(|r| r.get_file() != source.get_file()) {
+                san.push_str(&source_name[..1]);
+            } else if rivals.iter().all(|r| r.get_rank() != source.get_rank()) {
+                san.push_str(&source_name[1..]);
+            } else {
+                san.push_str(&source_name);
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&dest.to_string());
+    }
+
+    let after = board.make_move_new(mv);
+    if *after.checkers() != EMPTY {
+        san.push(if after.status() == BoardStatus::Checkmate {
+            '#'
+        } else {
+            '+'
+        });
+    }
+    san
+}
+
+/// Finds the legal move written as `text` in SAN, as produced by [`san_for_move`].
+///
+/// Unlike `ChessMove::from_san`, this accepts en passant captures
+/// without an `e.p.` suffix and ignores check marks and annotations.
+pub fn parse_san(board: &Board, text: &str) -> Option<ChessMove> {
+    let strip = |san: &str| san.trim_end_matches(['+', '#', '!', '?']).to_string();
+    let text = strip(text);
+    MoveGen::new_legal(board).find(|mv| strip(&san_for_move(board, *mv)) == text)
+}
+
+/// Returns the letter of `file`, e.g. `a`.
+pub fn file_char(file: File) -> char {
+    (b'a' + file.to_index() as u8) as char
+}
+
+fn piece_char(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Rook => 'R',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+/// Returns the piece written as `letter` in SAN, the inverse of
+/// [`piece_char`].
+fn piece_from_char(letter: char) -> Option<Piece> {
+    match letter {
+        'P' => Some(Piece::Pawn),
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+/// Reads the promotion piece written after a move, e.g. `=Q`, `/Q`,
+/// `(Q)` or `q`, `None` for an empty suffix and `Some(None)` for one
+/// that isn't a piece a pawn can promote to.
+fn parse_promotion(suffix: &str) -> Option<Option<Piece>> {
+    let letter = suffix
+        .trim_start_matches(['=', '/', '('])
+        .trim_end_matches(')')
+        .to_ascii_uppercase();
+    match letter.as_str() {
+        "" => None,
+        "N" | "KT" => Some(Some(Piece::Knight)),
+        "B" | "R" | "Q" => Some(letter.chars().next().and_then(piece_from_char)),
+        _ => Some(None),
+    }
+}
+
+/// Reads a move in long algebraic notation, naming both squares, e.g.
+/// `Ng1-f3`, `e2-e4`, `Rd1xd7+` or `e7-e8=Q`. The move is returned
+/// whether or not it's legal, so why it isn't can be explained, but the
+/// piece named has to stand on the first square if any piece does.
+///
+/// # Example
+///
+/// ```
+/// let mv = parse_long_algebraic(&Board::default(), "Ng1-f3");
+/// assert_eq!(mv, Some(ChessMove::new(Square::G1, Square::F3, None)));
+/// ```
+pub fn parse_long_algebraic(board: &Board, text: &str) -> Option<ChessMove> {
+    let text = text.trim().trim_end_matches(['+', '#', '!', '?']);
+    if !text.is_ascii() {
+        return None;
+    }
+    let (piece, rest) = match text.chars().next().and_then(piece_from_char) {
+        Some(piece) => (piece, &text[1..]),
+        None => (Piece::Pawn, text),
+    };
+    let source = parse_square(rest.get(..2)?).ok()?;
+    let rest = rest[2..].trim_start_matches(['-', 'x', ':']);
+    let dest = parse_square(rest.get(..2)?).ok()?;
+    let promotion = match parse_promotion(&rest[2..]) {
+        None => None,
+        Some(Some(piece)) => Some(piece),
+        Some(None) => return None,
+    };
+    if board.piece_on(source).is_some_and(|on| on != piece) {
+        return None;
+    }
+    Some(ChessMove::new(source, dest, promotion))
+}
+
+/// Returns the files a descriptive notation file name stands for, e.g.
+/// `QB` for the c file or `N` for the b and g files.
+fn descriptive_files(name: &str) -> Option<Vec<File>> {
+    let files = |indices: &[usize]| Some(indices.iter().map(|i| File::from_index(*i)).collect());
+    match name {
+        "QR" => files(&[0]),
+        "QN" | "QKT" => files(&[1]),
+        "QB" => files(&[2]),
+        "Q" => files(&[3]),
+        "K" => files(&[4]),
+        "KB" => files(&[5]),
+        "KN" | "KKT" => files(&[6]),
+        "KR" => files(&[7]),
+        "R" => files(&[0, 7]),
+        "N" | "KT" => files(&[1, 6]),
+        "B" => files(&[2, 5]),
+        _ => None,
+    }
+}
+
+/// Reads a piece in descriptive notation, e.g. `N`, `Kt`, `KB` (the
+/// king's bishop) or `QRP` (the pawn on the queen's rook file), as the
+/// piece and the files it may stand on.
+fn descriptive_piece(name: &str) -> Option<(Piece, Vec<File>)> {
+    let (piece, qualifier) = if let Some(qualifier) = name.strip_suffix("KT") {
+        (Piece::Knight, qualifier)
+    } else {
+        let piece = piece_from_char(name.chars().last()?)?;
+        (piece, &name[..name.len() - 1])
+    };
+    let all = || ALL_FILES.to_vec();
+    let files = match (piece, qualifier) {
+        (_, "") => all(),
+        // A pawn is named after its file.
+        (Piece::Pawn, file) => descriptive_files(file)?,
+        // Other pieces after the side they started on.
+        (_, "Q") => all()[..4].to_vec(),
+        (_, "K") => all()[4..].to_vec(),
+        _ => return None,
+    };
+    Some((piece, files))
+}
+
+/// Reads a move in the descriptive notation of older books, e.g. `P-K4`,
+/// `N-KB3`, `Kt-QB3`, `BxN`, `PxP e.p.` or `P-K8=Q`. Files are named
+/// after the pieces starting on them, `QR` to `KR`, and ranks counted
+/// from the mover's side.
+///
+/// Short forms such as `N-B3` or `PxP` may fit several moves, so the
+/// move is only returned if exactly one legal move fits.
+///
+/// # Example
+///
+/// ```
+/// let mv = parse_descriptive(&Board::default(), "P-K4");
+/// assert_eq!(mv, Some(ChessMove::new(Square::E2, Square::E4, None)));
+/// ```
+pub fn parse_descriptive(board: &Board, text: &str) -> Option<ChessMove> {
+    let mut text = text.to_ascii_uppercase().replace([' ', '.'], "");
+    while let Some(stripped) = ["CH", "MATE", "EP", "+", "#", "!", "?"]
+        .iter()
+        .find_map(|suffix| text.strip_suffix(suffix))
+    {
+        text = stripped.to_string();
+    }
+    let (capture, (piece, target)) = match (text.split_once('-'), text.split_once('X')) {
+        (Some(parts), None) => (false, parts),
+        (None, Some(parts)) => (true, parts),
+        _ => return None,
+    };
+    let (piece, files) = descriptive_piece(piece)?;
+    let (target, promotion) = match target.find(['=', '/', '(']) {
+        Some(at) => (&target[..at], parse_promotion(&target[at..])?),
+        None => (target, None),
+    };
+    let us = board.side_to_move();
+    let fits: Box<dyn Fn(ChessMove) -> bool> = if capture {
+        let (victim, victim_files) = descriptive_piece(target)?;
+        Box::new(move |mv: ChessMove| {
+            let dest = mv.get_dest();
+            let on_dest = board.piece_on(dest).or_else(|| {
+                // Only an en passant capture lands on an empty square.
+                (piece == Piece::Pawn && mv.get_source().get_file() != dest.get_file())
+                    .then_some(Piece::Pawn)
+            });
+            on_dest == Some(victim) && victim_files.contains(&dest.get_file())
+        })
+    } else {
+        let rank = target
+            .chars()
+            .last()?
+            .to_digit(10)
+            .filter(|r| (1..=8).contains(r))? as usize;
+        let rank = Rank::from_index(match us {
+            Color::White => rank - 1,
+            Color::Black => 8 - rank,
+        });
+        let dest_files = descriptive_files(&target[..target.len() - 1])?;
+        Box::new(move |mv: ChessMove| {
+            let dest = mv.get_dest();
+            board.piece_on(dest).is_none()
+                && dest.get_rank() == rank
+                && dest_files.contains(&dest.get_file())
+        })
+    };
+    let mut candidates = MoveGen::new_legal(board).filter(|mv| {
+        board.piece_on(mv.get_source()) == Some(piece)
+            && files.contains(&mv.get_source().get_file())
+            && mv.get_promotion() == promotion
+            && fits(*mv)
+    });
+    match (candidates.next(), candidates.next()) {
+        (Some(mv), None) => Some(mv),
+        _ => None,
+    }
+}
+
+/// Returns the SAN of a legal move in figurine notation, with symbols
+/// for the pieces instead of letters, e.g. `♘f3`.
+pub fn figurine_san(board: &Board, mv: ChessMove) -> String {
+    san_for_move(board, mv)
+        .chars()
+        .map(|c| match c {
+            'K' => '♔',
+            'Q' => '♕',
+            'R' => '♖',
+            'B' => '♗',
+            'N' => '♘',
+            c => c,
+        })
+        .collect()
+}
+
+/// Writes the FEN of `board` with the move counters, which the board
+/// doesn't keep and writes as `0 1`.
+///
+/// # Example
+///
+/// ```
+/// let fen = fen_with_counters(&Board::default(), 0, 1);
+/// assert_eq!(fen, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+/// ```
+pub fn fen_with_counters(board: &Board, halfmove_clock: u32, fullmove_number: u32) -> String {
+    let fen = board.to_string();
+    let position = fen.strip_suffix(" 0 1").unwrap_or(&fen);
+    format!("{} {} {}", position, halfmove_clock, fullmove_number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san_of(fen: &str, uci: &str) -> String {
+        let board = Board::from_str(fen).unwrap();
+        san_for_move(&board, ChessMove::from_str(uci).unwrap())
+    }
+
+    #[test]
+    fn parse_san_accepts_en_passant() {
+        let board = Board::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(
+            parse_san(&board, "exd6"),
+            Some(ChessMove::from_str("e5d6").unwrap())
+        );
+        assert_eq!(
+            parse_san(&board, "Kd2+"),
+            Some(ChessMove::from_str("e1d2").unwrap())
+        );
+        assert_eq!(parse_san(&board, "Ke3"), None);
+    }
+
+    #[test]
+    fn san_notation() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san_of(start, "e2e4"), "e4");
+        assert_eq!(san_of(start, "g1f3"), "Nf3");
+        assert_eq!(san_of("r3k3/8/8/8/8/8/8/R3K2R w KQq - 0 1", "e1g1"), "O-O");
+        assert_eq!(
+            san_of("r3k3/8/8/8/8/8/8/R3K2R b KQq - 0 1", "e8c8"),
+            "O-O-O"
+        );
+        assert_eq!(san_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+        assert_eq!(san_of("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q+");
+    }
+
+    #[test]
+    fn san_disambiguation_and_mate() {
+        let rooks = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1";
+        assert_eq!(san_of(rooks, "a1d1"), "Rad1");
+        let knights = "4k3/8/8/1N6/8/1N6/8/4K3 w - - 0 1";
+        assert_eq!(san_of(knights, "b5d4"), "N5d4");
+        let queens = "1k6/8/8/8/Q6Q/8/8/Q3K3 w - - 0 1";
+        assert_eq!(san_of(queens, "a4d4"), "Qa4d4");
+        assert_eq!(
+            san_of(
+                "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2",
+                "d8h4"
+            ),
+            "Qh4#"
+        );
+    }
+
+    #[test]
+    fn reads_long_algebraic_and_descriptive_notation() {
+        let mv = |uci: &str| Some(ChessMove::from_str(uci).unwrap());
+        let start = Board::default();
+        assert_eq!(parse_long_algebraic(&start, "Ng1-f3"), mv("g1f3"));
+        assert_eq!(parse_long_algebraic(&start, "e2-e4"), mv("e2e4"));
+        // Illegal moves are read, to be explained; wrong pieces aren't.
+        assert_eq!(parse_long_algebraic(&start, "Bf1xa6"), mv("f1a6"));
+        assert_eq!(parse_long_algebraic(&start, "Bg1-f3"), None);
+        let promotion = Board::from_str("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(parse_long_algebraic(&promotion, "b7-b8=N+"), mv("b7b8n"));
+
+        assert_eq!(parse_descriptive(&start, "P-K4"), mv("e2e4"));
+        assert_eq!(parse_descriptive(&start, "Kt-KB3"), mv("g1f3"));
+        assert_eq!(parse_descriptive(&start, "N-B3"), None);
+        let board = start.make_move_new(ChessMove::from_str("e2e4").unwrap());
+        assert_eq!(parse_descriptive(&board, "p-qb4"), mv("c7c5"));
+        let board = board.make_move_new(ChessMove::from_str("d7d5").unwrap());
+        assert_eq!(parse_descriptive(&board, "PxP"), mv("e4d5"));
+        assert_eq!(parse_descriptive(&board, "BxN ch"), None);
+        let en_passant = Board::from_str("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(parse_descriptive(&en_passant, "PxP e.p."), mv("e5d6"));
+    }
+
+    #[test]
+    fn parses_squares_and_uci_with_friendly_errors() {
+        assert_eq!(parse_square(" h1 "), Ok(Square::H1));
+        assert_eq!(
+            parse_square("i4").unwrap_err().to_string(),
+            "`i` isn't a file, files go from a to h"
+        );
+        assert_eq!(
+            parse_square("e44"),
+            Err(NotationError::Square("e44".to_string()))
+        );
+        let board = Board::default();
+        assert_eq!(
+            parse_uci(&board, "g1f3"),
+            Ok(ChessMove::from_str("g1f3").unwrap())
+        );
+        assert_eq!(
+            parse_uci(&board, "e2e5").unwrap_err().to_string(),
+            "`e2e5` isn't a legal move in UCI"
+        );
+        assert_eq!(
+            figurine_san(&board, ChessMove::from_str("g1f3").unwrap()),
+            "♘f3"
+        );
+        assert_eq!(
+            fen_with_counters(&board, 3, 12),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3 12"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::game::GameEvent;
+use crate::speech::run_hook;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Something the player is alerted to, e.g. while looking away from the
+/// board waiting for a network opponent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alert {
+    /// It's the player's turn after the opponent moved.
+    YourTurn,
+    /// A move gave check.
+    Check,
+    /// The game ended.
+    GameOver,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Alert::YourTurn => write!(f, "Your turn"),
+            Alert::Check => write!(f, "Check"),
+            Alert::GameOver => write!(f, "Game over"),
+        }
+    }
+}
+
+/// How the player is alerted, set with the `notify` setting: `off`,
+/// `bell` to ring the terminal bell, or a command that gets the alert
+/// in words on its standard input, e.g. `notify-send rChess`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Notifier {
+    #[default]
+    Off,
+    Bell,
+    Command(String),
+}
+
+impl Notifier {
+    /// Reads the value of the `notify` setting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// assert_eq!(Notifier::parse("bell"), Notifier::Bell);
+    /// assert_eq!(
+    ///     Notifier::parse("paplay ding.ogg"),
+    ///     Notifier::Command("paplay ding.ogg".to_string())
+    /// );
+    /// ```
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "off" | "" => Notifier::Off,
+            "bell" => Notifier::Bell,
+            command => Notifier::Command(command.to_string()),
+        }
+    }
+
+    /// Alerts the player to `alert`.
+    pub fn alert(&self, alert: Alert) {
+        match self {
+            Notifier::Off => (),
+            Notifier::Bell => {
+                print!("\x07");
+                let _ = io::stdout().flush();
+            }
+            Notifier::Command(command) => run_hook(command, alert.to_string()),
+        }
+    }
+
+    /// Returns a game listener alerting the player to checks and the end
+    /// of the game.
+    pub fn listener(self) -> impl FnMut(&GameEvent) {
+        move |event| match event {
+            GameEvent::CheckGiven(_) => self.alert(Alert::Check),
+            GameEvent::GameEnded(_) => self.alert(Alert::GameOver),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_settings() {
+        assert_eq!(Notifier::parse("off"), Notifier::Off);
+        assert_eq!(Notifier::parse("bell"), Notifier::Bell);
+        assert_eq!(
+            Notifier::parse("notify-send rChess"),
+            Notifier::Command("notify-send rChess".to_string())
+        );
+        assert_eq!(Alert::YourTurn.to_string(), "Your turn");
+    }
+}
@@ -0,0 +1,113 @@
+use chess::{Board, Color};
+use std::str::FromStr;
+
+/// Handicaps a stronger player can give to make a game more even.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Odds {
+    #[default]
+    None,
+    /// The giver plays without the f-pawn and lets the opponent move first.
+    PawnAndMove,
+    /// The giver plays without the queenside knight.
+    Knight,
+    /// The giver plays without the queen.
+    Queen,
+}
+
+impl Odds {
+    /// Returns the starting position in FEN with the odds given by `giver`.
+    pub fn fen(self, giver: Color) -> &'static str {
+        match (self, giver) {
+            (Odds::None, _) => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            (Odds::PawnAndMove, Color::White) => {
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR b KQkq - 0 1"
+            }
+            (Odds::PawnAndMove, Color::Black) => {
+                "rnbqkbnr/ppppp1pp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            }
+            (Odds::Knight, Color::White) => {
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1"
+            }
+            (Odds::Knight, Color::Black) => {
+                "r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            }
+            (Odds::Queen, Color::White) => {
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1"
+            }
+            (Odds::Queen, Color::Black) => {
+                "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            }
+        }
+    }
+
+    /// Returns the starting board with the odds given by `giver`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let board = Odds::Queen.starting_board(Color::White);
+    /// assert_eq!(board.piece_on(Square::D1), None);
+    /// ```
+    pub fn starting_board(self, giver: Color) -> Board {
+        Board::from_str(self.fen(giver)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess::{ChessMove, Piece, Square};
+
+    #[test]
+    fn odds_remove_pieces_of_the_giver() {
+        let cases = [
+            (Odds::PawnAndMove, Color::White, Square::F2, Piece::Pawn),
+            (Odds::PawnAndMove, Color::Black, Square::F7, Piece::Pawn),
+            (Odds::Knight, Color::White, Square::B1, Piece::Knight),
+            (Odds::Knight, Color::Black, Square::B8, Piece::Knight),
+            (Odds::Queen, Color::White, Square::D1, Piece::Queen),
+            (Odds::Queen, Color::Black, Square::D8, Piece::Queen),
+        ];
+        for (odds, giver, square, piece) in cases {
+            let board = odds.starting_board(giver);
+            assert_eq!(board.piece_on(square), None);
+            assert_eq!(
+                board.pieces(piece).popcnt(),
+                Board::default().pieces(piece).popcnt() - 1
+            );
+        }
+    }
+
+    #[test]
+    fn pawn_and_move_lets_the_receiver_start() {
+        assert_eq!(
+            Odds::PawnAndMove
+                .starting_board(Color::White)
+                .side_to_move(),
+            Color::Black
+        );
+        assert_eq!(
+            Odds::PawnAndMove
+                .starting_board(Color::Black)
+                .side_to_move(),
+            Color::White
+        );
+        assert_eq!(Odds::None.starting_board(Color::White), Board::default());
+    }
+
+    #[test]
+    fn knight_odds_keep_queenside_castling() {
+        let cases = [
+            (Color::White, "d2d4 a7a6 c1f4 a6a5 d1d3 a5a4 e1c1"),
+            (Color::Black, "a2a3 d7d5 a3a4 c8f5 a4a5 d8d6 h2h3 e8c8"),
+        ];
+        for (giver, moves) in cases {
+            let mut board = Odds::Knight.starting_board(giver);
+            for mv in moves.split(' ') {
+                let mv = ChessMove::from_str(mv).unwrap();
+                assert!(board.legal(mv), "{mv} with {giver:?} giving odds");
+                board = board.make_move_new(mv);
+            }
+        }
+    }
+}
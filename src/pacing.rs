@@ -0,0 +1,84 @@
+use crate::rng::random_range;
+use chess::{Board, MoveGen};
+use std::time::Duration;
+
+/// Number of half-moves at the start of the game played quickly, like
+/// a person playing the opening from memory.
+const OPENING_PLIES: usize = 12;
+/// Number of legal moves in a position of average complexity.
+const TYPICAL_MOVES: f64 = 30.0;
+
+/// How long the AI takes over its moves in single-player games: instead
+/// of replying as soon as its search is done, it waits for a time that
+/// depends on the position, like a person would.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pacing {
+    /// Time the AI takes over a move in a position of average
+    /// complexity, `None` to reply instantly.
+    pub think_time: Option<Duration>,
+}
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Self {
+            think_time: Some(Duration::from_millis(1500)),
+        }
+    }
+}
+
+impl Pacing {
+    /// Returns how long the AI takes over its move in `board`, `ply`
+    /// half-moves into the game, with some random variation so moves
+    /// don't all take the same time.
+    ///
+    /// The time spent searching counts towards it, so the AI waits for
+    /// what's left once it has found its move.
+    pub fn delay(&self, board: &Board, ply: usize) -> Duration {
+        self.delay_with(board, ply, random_range(0.6..1.4))
+    }
+
+    /// Returns the time the AI takes over its move, scaled by `variation`.
+    ///
+    /// Forced moves are played almost at once and moves in the opening
+    /// quickly; otherwise the more moves there are to choose from, the
+    /// longer the AI thinks.
+    fn delay_with(&self, board: &Board, ply: usize, variation: f64) -> Duration {
+        let Some(think_time) = self.think_time else {
+            return Duration::ZERO;
+        };
+        let moves = MoveGen::new_legal(board).len();
+        let complexity = match moves {
+            0 | 1 => 0.1,
+            moves => (moves as f64 / TYPICAL_MOVES).clamp(0.5, 1.5),
+        };
+        let phase = if ply < OPENING_PLIES { 0.4 } else { 1.0 };
+        think_time.mul_f64(complexity * phase * variation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn thinks_longer_over_harder_moves() {
+        let pacing = Pacing {
+            think_time: Some(Duration::from_secs(2)),
+        };
+        let middlegame =
+            Board::from_str("r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/P3BPPP/R2QKB1R w KQ - 0 9")
+                .unwrap();
+        let opening = Board::default();
+        // The king has a single move out of check.
+        let forced = Board::from_str("k7/8/8/8/8/8/1r6/K6r w - - 0 1").unwrap();
+        let delay = |board: &Board, ply| pacing.delay_with(board, ply, 1.0);
+        assert!(delay(&middlegame, 16) > delay(&opening, 0));
+        assert!(delay(&middlegame, 16) > delay(&forced, 60));
+        assert_eq!(delay(&forced, 60), Duration::from_millis(200));
+        assert!(pacing.delay(&middlegame, 16) < Duration::from_secs(5));
+
+        let instant = Pacing { think_time: None };
+        assert_eq!(instant.delay(&middlegame, 16), Duration::ZERO);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::error::ChessError;
+use crate::legality::IllegalMoveReason;
+use crate::rng::random_range;
+use chess::{
+    between, get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_rook_moves,
+    BitBoard, Color, Piece, Square, ALL_SQUARES, EMPTY,
+};
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// Pieces the player can take through a puzzle.
+pub const PATH_PIECES: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::King];
+/// Number of enemy pieces guarding squares in a puzzle.
+const ENEMIES: usize = 3;
+/// Pieces the enemies are chosen from.
+const ENEMY_PIECES: [Piece; 4] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook];
+/// Fewest moves a generated puzzle takes to solve.
+const MIN_STEPS: u32 = 3;
+
+/// A piece-path puzzle: take a piece from one square to another, e.g.
+/// the knight from a1 to h8, never stopping on a square an enemy piece
+/// stands on or attacks.
+///
+/// The enemies stay where they are, so the safe squares don't change
+/// during the puzzle. The player's piece can't move through them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathPuzzle {
+    piece: Piece,
+    start: Square,
+    target: Square,
+    enemies: Vec<(Piece, Square)>,
+    /// The square the player's piece is on.
+    current: Square,
+    /// Number of moves played so far.
+    steps: u32,
+    /// Fewest moves solving the puzzle.
+    best: u32,
+}
+
+impl PathPuzzle {
+    /// Generates a random puzzle for `piece`, taking at least
+    /// [`MIN_STEPS`] moves to solve.
+    pub fn new(piece: Piece) -> Self {
+        loop {
+            let start = random_square();
+            let target = random_square();
+            let mut enemies = Vec::new();
+            while enemies.len() < ENEMIES {
+                let square = random_square();
+                if square != start
+                    && square != target
+                    && enemies.iter().all(|(_, taken)| *taken != square)
+                {
+                    let enemy = ENEMY_PIECES[random_range(0..ENEMY_PIECES.len())];
+                    enemies.push((enemy, square));
+                }
+            }
+            if let Some(puzzle) = Self::with_enemies(piece, start, target, enemies) {
+                if puzzle.best >= MIN_STEPS {
+                    return puzzle;
+                }
+            }
+        }
+    }
+
+    /// Creates the puzzle of taking `piece` from `start` to `target`
+    /// past `enemies`.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the puzzle can't be solved, e.g. because the target is
+    /// attacked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let puzzle =
+    ///     PathPuzzle::with_enemies(Piece::Knight, Square::A1, Square::H8, Vec::new()).unwrap();
+    /// assert_eq!(puzzle.best(), 6);
+    /// ```
+    pub fn with_enemies(
+        piece: Piece,
+        start: Square,
+        target: Square,
+        enemies: Vec<(Piece, Square)>,
+    ) -> Option<Self> {
+        let mut puzzle = Self {
+            piece,
+            start,
+            target,
+            enemies,
+            current: start,
+            steps: 0,
+            best: 0,
+        };
+        if start == target || puzzle.unsafe_squares() & BitBoard::from_square(start) != EMPTY {
+            return None;
+        }
+        puzzle.best = puzzle.distance()?;
+        Some(puzzle)
+    }
+
+    pub fn piece(&self) -> Piece {
+        self.piece
+    }
+
+    /// Returns the square the player's piece is on.
+    pub fn current(&self) -> Square {
+        self.current
+    }
+
+    pub fn target(&self) -> Square {
+        self.target
+    }
+
+    /// Returns the number of moves played so far.
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Returns the fewest moves solving the puzzle.
+    pub fn best(&self) -> u32 {
+        self.best
+    }
+
+    /// Returns whether the piece has reached the target.
+    pub fn is_solved(&self) -> bool {
+        self.current == self.target
+    }
+
+    /// Moves the piece to the square written as `input`, e.g. `c2`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `input` is not a square, the piece can't
+    /// move there or the square is not safe.
+    pub fn step(&mut self, input: &str) -> Result<(), ChessError> {
+        let square = Square::from_str(input.trim()).map_err(|_| ChessError::InvalidInput)?;
+        let reach = BitBoard::from_square(square);
+        if self.moves(self.current, EMPTY) & reach == EMPTY {
+            return Err(ChessError::IllegalMove(
+                IllegalMoveReason::CannotMoveThatWay(self.piece),
+            ));
+        }
+        let blocker = between(self.current, square) & self.occupied();
+        if blocker != EMPTY {
+            return Err(ChessError::IllegalMove(IllegalMoveReason::PathBlocked(
+                blocker.to_square(),
+            )));
+        }
+        if self.unsafe_squares() & reach != EMPTY {
+            return Err(ChessError::UnsafeSquare(square));
+        }
+        self.current = square;
+        self.steps += 1;
+        Ok(())
+    }
+
+    /// Starts the puzzle over from the first square.
+    pub fn restart(&mut self) {
+        self.current = self.start;
+        self.steps = 0;
+    }
+
+    /// Draws the puzzle from White's side with the player's piece in
+    /// capitals, the enemies in lowercase, unsafe squares as `x` and the
+    /// target as `*`.
+    pub fn diagram(&self) -> String {
+        let unsafe_squares = self.unsafe_squares();
+        let mut diagram = String::new();
+        for rank in (0..8).rev() {
+            diagram.push_str(&format!("{}  ", rank + 1));
+            for square in &ALL_SQUARES[rank * 8..rank * 8 + 8] {
+                let enemy = self.enemies.iter().find(|(_, on)| on == square);
+                let symbol = if *square == self.current {
+                    self.piece.to_string(Color::White).chars().next().unwrap()
+                } else if let Some((piece, _)) = enemy {
+                    piece.to_string(Color::Black).chars().next().unwrap()
+                } else if *square == self.target {
+                    '*'
+                } else if unsafe_squares & BitBoard::from_square(*square) != EMPTY {
+                    'x'
+                } else {
+                    '.'
+                };
+                diagram.push(symbol);
+                diagram.push(' ');
+            }
+            diagram.push('\n');
+        }
+        diagram.push_str("   a b c d e f g h\n");
+        diagram
+    }
+
+    /// Returns the squares the enemies stand on.
+    fn occupied(&self) -> BitBoard {
+        self.enemies.iter().fold(EMPTY, |occupied, (_, square)| {
+            occupied | BitBoard::from_square(*square)
+        })
+    }
+
+    /// Returns the squares the piece can't stop on: those the enemies
+    /// stand on or attack, moving down the board like Black.
+    fn unsafe_squares(&self) -> BitBoard {
+        let occupied = self.occupied();
+        self.enemies
+            .iter()
+            .fold(occupied, |squares, (piece, square)| {
+                squares
+                    | match piece {
+                        Piece::Pawn => get_pawn_attacks(*square, Color::Black, !EMPTY),
+                        piece => piece_moves(*piece, *square, occupied),
+                    }
+            })
+    }
+
+    /// Returns the squares the player's piece can move to from `from`,
+    /// with pieces on `blockers` standing in its way.
+    fn moves(&self, from: Square, blockers: BitBoard) -> BitBoard {
+        piece_moves(self.piece, from, blockers)
+    }
+
+    /// Returns the fewest moves taking the piece from the start to the
+    /// target over safe squares, `None` if it can't get there.
+    fn distance(&self) -> Option<u32> {
+        let safe = !self.unsafe_squares();
+        let mut steps = [None; 64];
+        steps[self.start.to_index()] = Some(0);
+        let mut queue = VecDeque::from([self.start]);
+        while let Some(square) = queue.pop_front() {
+            let taken = steps[square.to_index()]?;
+            if square == self.target {
+                return Some(taken);
+            }
+            for next in self.moves(square, self.occupied()) & safe {
+                if steps[next.to_index()].is_none() {
+                    steps[next.to_index()] = Some(taken + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Returns the squares `piece` on `from` moves to, with pieces on
+/// `blockers` standing in the way of sliding pieces.
+fn piece_moves(piece: Piece, from: Square, blockers: BitBoard) -> BitBoard {
+    match piece {
+        Piece::Knight => get_knight_moves(from),
+        Piece::Bishop => get_bishop_moves(from, blockers),
+        Piece::Rook => get_rook_moves(from, blockers),
+        Piece::Queen => get_bishop_moves(from, blockers) | get_rook_moves(from, blockers),
+        Piece::King => get_king_moves(from),
+        Piece::Pawn => EMPTY,
+    }
+}
+
+fn random_square() -> Square {
+    ALL_SQUARES[random_range(0..ALL_SQUARES.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_knight_steps() {
+        let mut puzzle =
+            PathPuzzle::with_enemies(Piece::Knight, Square::A1, Square::H8, Vec::new()).unwrap();
+        assert_eq!(puzzle.best(), 6);
+        assert_eq!(
+            puzzle.step("a2"),
+            Err(ChessError::IllegalMove(
+                IllegalMoveReason::CannotMoveThatWay(Piece::Knight)
+            ))
+        );
+        for square in ["b3", "c5", "d7", "f6", "g4", "h6", "g8", "f6", "h7"] {
+            assert!(!puzzle.is_solved());
+            puzzle.step(square).unwrap();
+        }
+        assert_eq!(puzzle.step("z9"), Err(ChessError::InvalidInput));
+        assert_eq!((puzzle.current(), puzzle.steps()), (Square::H7, 9));
+        puzzle.restart();
+        assert_eq!((puzzle.current(), puzzle.steps()), (Square::A1, 0));
+    }
+
+    #[test]
+    fn avoids_attacked_squares() {
+        let enemies = vec![(Piece::Rook, Square::D4), (Piece::Pawn, Square::B5)];
+        let mut puzzle =
+            PathPuzzle::with_enemies(Piece::Rook, Square::A1, Square::H8, enemies).unwrap();
+        // The d-file and the 4th rank are cut off, but not beyond the rook.
+        assert_eq!(puzzle.best(), 2);
+        assert_eq!(puzzle.step("a4"), Err(ChessError::UnsafeSquare(Square::A4)));
+        puzzle.step("b1").unwrap();
+        assert_eq!(
+            puzzle.step("b8"),
+            Err(ChessError::IllegalMove(IllegalMoveReason::PathBlocked(
+                Square::B5
+            )))
+        );
+        assert!(puzzle.diagram().starts_with("8  . . . x . . . * \n"));
+
+        let guarded = vec![(Piece::Bishop, Square::A1)];
+        assert_eq!(
+            PathPuzzle::with_enemies(Piece::King, Square::C3, Square::H8, guarded),
+            None
+        );
+        let puzzle = PathPuzzle::new(Piece::Knight);
+        assert!(puzzle.best() >= MIN_STEPS);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::report::PawnStructure;
+use crate::rng::fixed_keys;
+use chess::{Board, Color, Piece, Square, ALL_COLORS};
+
+/// Centipawns for each passed pawn.
+const PASSED_PAWN: i32 = 30;
+/// Centipawns for each doubled pawn.
+const DOUBLED_PAWN: i32 = -15;
+/// Centipawns for each isolated pawn.
+const ISOLATED_PAWN: i32 = -12;
+/// Centipawns for each pawn island beyond the first.
+const PAWN_ISLAND: i32 = -8;
+/// Slots of a [`PawnTable`], a power of two of them.
+const PAWN_TABLE_SLOTS: usize = 1 << 14;
+
+/// The random numbers pawn structures are hashed with, one for each
+/// square for White's pawns and then for Black's.
+const PAWN_KEYS: [u64; 128] = fixed_keys(0x7243_5061_776e_7321);
+
+/// Returns the number hashed into a pawn key for a pawn of `color` on
+/// `square`.
+pub fn pawn_square_key(color: Color, square: Square) -> u64 {
+    PAWN_KEYS[64 * color.to_index() + square.to_index()]
+}
+
+/// Returns the pawn key of `board`: a Zobrist hash of where the pawns of
+/// each side stand and nothing else, so positions with the same pawns
+/// share it.
+pub fn pawn_key(board: &Board) -> u64 {
+    let mut key = 0;
+    for color in ALL_COLORS {
+        for square in *board.pieces(Piece::Pawn) & board.color_combined(color) {
+            key ^= pawn_square_key(color, square);
+        }
+    }
+    key
+}
+
+/// Scores the pawn structure of `board` in centipawns from White's point
+/// of view: passed pawns count for their side, doubled and isolated ones
+/// and split pawn islands against it.
+///
+/// # Example
+///
+/// ```
+/// // White's passed h-pawn against Black's doubled c-pawns.
+/// let board = Board::from_str("4k3/2p5/2p5/8/8/8/2P4P/4K3 w - - 0 1")?;
+/// assert!(pawn_score(&board) > 0);
+/// ```
+pub fn pawn_score(board: &Board) -> i32 {
+    let side = |color| {
+        let pawns = PawnStructure::new(board, color);
+        PASSED_PAWN * pawns.passed as i32
+            + DOUBLED_PAWN * pawns.doubled as i32
+            + ISOLATED_PAWN * pawns.isolated as i32
+            + PAWN_ISLAND * pawns.islands.saturating_sub(1) as i32
+    };
+    side(Color::White) - side(Color::Black)
+}
+
+/// A fixed-size cache of pawn structure scores, see [`pawn_score`],
+/// by pawn key. The pawns rarely move during a search, so most positions
+/// it evaluates find their pawn structure scored already.
+///
+/// Each pawn key has a single slot, picked by the key and taken over by
+/// the last structure scored there. An empty slot reads as key 0, the key
+/// of a board without pawns, whose score is 0 too.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PawnTable {
+    /// Pawn keys and their scores.
+    slots: Vec<(u64, i32)>,
+    /// Lookups that found their structure scored already.
+    pub hits: u64,
+    /// Lookups that had to score their structure.
+    pub misses: u64,
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self {
+            slots: vec![(0, 0); PAWN_TABLE_SLOTS],
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+
+impl PawnTable {
+    /// Returns [`pawn_score`] of `board`, whose pawn key is `key`, scoring
+    /// its pawns only if their structure isn't kept.
+    pub fn score(&mut self, board: &Board, key: u64) -> i32 {
+        let slot = &mut self.slots[key as usize & (PAWN_TABLE_SLOTS - 1)];
+        if slot.0 == key {
+            self.hits += 1;
+            return slot.1;
+        }
+        self.misses += 1;
+        *slot = (key, pawn_score(board));
+        slot.1
+    }
+
+    /// Returns how many bytes the table takes.
+    pub fn memory(&self) -> usize {
+        self.slots.len() * size_of::<(u64, i32)>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess::ChessMove;
+    use std::str::FromStr;
+
+    #[test]
+    fn keys_only_follow_pawns() {
+        let board = Board::default();
+        let knight = board.make_move_new(ChessMove::from_str("g1f3").unwrap());
+        assert_eq!(pawn_key(&knight), pawn_key(&board));
+        let pawn = board.make_move_new(ChessMove::from_str("e2e4").unwrap());
+        assert_ne!(pawn_key(&pawn), pawn_key(&board));
+        let empty = Board::from_str("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(pawn_key(&empty), 0);
+    }
+
+    #[test]
+    fn scores_and_caches_structures() {
+        assert_eq!(pawn_score(&Board::default()), 0);
+        // Two isolated pawns, one passed, against doubled, isolated ones.
+        let board = Board::from_str("4k3/2p5/2p5/8/8/8/2P4P/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            pawn_score(&board),
+            PASSED_PAWN + 2 * ISOLATED_PAWN + PAWN_ISLAND - (DOUBLED_PAWN + 2 * ISOLATED_PAWN)
+        );
+        let mut table = PawnTable::default();
+        let key = pawn_key(&board);
+        assert_eq!(table.score(&board, key), pawn_score(&board));
+        assert_eq!(table.score(&board, key), pawn_score(&board));
+        assert_eq!((table.hits, table.misses), (1, 1));
+    }
+}
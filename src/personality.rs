@@ -0,0 +1,190 @@
+use crate::ai::EvalWeights;
+use crate::drill::Repertoire;
+use crate::rng::random_range;
+use chess::{Board, ChessMove};
+use std::fmt;
+use std::str::FromStr;
+
+/// Playing style of the AI, changing what it values in a position and
+/// which openings it prefers, so games against it feel different.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Personality {
+    /// Plain material counting, working out its own openings.
+    #[default]
+    Balanced,
+    /// Goes after the enemy king and avoids draws.
+    Aggressive,
+    /// Values central control and piece activity.
+    Positional,
+    /// Gives up material for activity and the initiative.
+    Gambit,
+    /// Plays solid openings and is happy with a draw.
+    Drawish,
+}
+
+impl fmt::Display for Personality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Personality::Balanced => "balanced",
+            Personality::Aggressive => "aggressive",
+            Personality::Positional => "positional",
+            Personality::Gambit => "gambit",
+            Personality::Drawish => "drawish",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Personality {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "balanced" => Ok(Personality::Balanced),
+            "aggressive" => Ok(Personality::Aggressive),
+            "positional" => Ok(Personality::Positional),
+            "gambit" => Ok(Personality::Gambit),
+            "drawish" => Ok(Personality::Drawish),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Personality {
+    /// Returns the evaluation weights the AI plays with.
+    pub fn weights(self) -> EvalWeights {
+        let base = EvalWeights::default();
+        match self {
+            Personality::Balanced | Personality::Drawish => base,
+            Personality::Aggressive => EvalWeights {
+                king_attack: 15,
+                mobility: 2,
+                ..base
+            },
+            Personality::Positional => EvalWeights {
+                center: 10,
+                mobility: 4,
+                placement: 100,
+                pawn_structure: 100,
+                ..base
+            },
+            Personality::Gambit => EvalWeights {
+                material: 85,
+                mobility: 6,
+                king_attack: 10,
+                ..base
+            },
+        }
+    }
+
+    /// Returns the engine's contempt in centipawns: how much the AI
+    /// avoids draws, or if negative, seeks them.
+    pub fn contempt(self) -> i32 {
+        match self {
+            Personality::Balanced | Personality::Positional => 0,
+            Personality::Aggressive => 50,
+            Personality::Gambit => 25,
+            Personality::Drawish => -100,
+        }
+    }
+
+    /// Returns whether the AI likes to play the opening called `name`.
+    fn likes_opening(self, name: &str) -> bool {
+        let liked: &[&str] = match self {
+            Personality::Balanced => &[],
+            Personality::Aggressive => &["Sicilian", "King's Indian", "King's Gambit"],
+            Personality::Positional => &["Declined", "Ruy Lopez", "Caro-Kann", "French"],
+            Personality::Gambit => &["Gambit"],
+            Personality::Drawish => &["Caro-Kann", "Declined", "Giuoco Piano"],
+        };
+        liked.iter().any(|opening| name.contains(opening))
+    }
+
+    /// Picks a move from the built-in opening book for the game that
+    /// started from `start` with `moves`.
+    ///
+    /// Lines the AI likes are preferred; any other line of the book is
+    /// followed if none of them fits the game.
+    ///
+    /// # Returns
+    ///
+    /// The book move, `None` if the game has left the book or the AI
+    /// is [`Personality::Balanced`], which doesn't use the book.
+    pub fn book_move(self, start: &Board, moves: &[ChessMove]) -> Option<ChessMove> {
+        if self == Personality::Balanced {
+            return None;
+        }
+        self.opening_book_move(start, moves)
+    }
+
+    /// Picks a move from the built-in opening book like
+    /// [`Personality::book_move`], also for [`Personality::Balanced`],
+    /// which follows any line of the book.
+    pub fn opening_book_move(self, start: &Board, moves: &[ChessMove]) -> Option<ChessMove> {
+        let book = Repertoire::builtin();
+        let in_book: Vec<_> = book
+            .lines
+            .iter()
+            .filter(|line| {
+                line.start == *start
+                    && line.moves.len() > moves.len()
+                    && line.moves.starts_with(moves)
+            })
+            .collect();
+        let liked: Vec<_> = in_book
+            .iter()
+            .filter(|line| self.likes_opening(&line.name))
+            .collect();
+        let candidates = if liked.is_empty() {
+            in_book.iter().collect()
+        } else {
+            liked
+        };
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(candidates[random_range(0..candidates.len())].moves[moves.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::parse_san;
+
+    #[test]
+    fn follows_liked_openings() {
+        let board = Board::default();
+        let play = |moves: &[&str]| {
+            let mut position = board;
+            let mut played = Vec::new();
+            for text in moves {
+                let mv = parse_san(&position, text).unwrap();
+                position = position.make_move_new(mv);
+                played.push(mv);
+            }
+            played
+        };
+        let after_e4 = play(&["e4"]);
+        let after_e5 = play(&["e4", "e5"]);
+        for _ in 0..10 {
+            let f4 = Personality::Gambit.book_move(&board, &after_e5);
+            assert_eq!(f4, Some(play(&["e4", "e5", "f4"])[2]));
+            let reply = Personality::Drawish.book_move(&board, &after_e4).unwrap();
+            assert!([play(&["e4", "c6"])[1], play(&["e4", "e5"])[1]].contains(&reply));
+        }
+    }
+
+    #[test]
+    fn leaves_the_book() {
+        let board = Board::default();
+        let a3 = parse_san(&board, "a3").unwrap();
+        assert_eq!(Personality::Positional.book_move(&board, &[a3]), None);
+        assert_eq!(Personality::Balanced.book_move(&board, &[]), None);
+        assert!(Personality::Balanced
+            .opening_book_move(&board, &[])
+            .is_some());
+        assert_eq!(Personality::from_str("Drawish"), Ok(Personality::Drawish));
+        assert_eq!(Personality::Gambit.to_string(), "gambit");
+    }
+}
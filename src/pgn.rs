@@ -0,0 +1,728 @@
+use crate::annotation::parse_nag;
+use crate::game::{Game, Status};
+use crate::legality::validate_fen;
+use crate::notation::{parse_san, san_for_move};
+use crate::rng::seed;
+use crate::variant::Variant;
+use crate::variation::MoveTree;
+use chess::{Board, Color};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Event games are exported with unless another is set.
+const DEFAULT_EVENT: &str = "rChess game";
+
+/// A game read from a PGN file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PgnGame {
+    /// Tag pairs in the order they appear, e.g. `("White", "Carlsen")`.
+    pub tags: Vec<(String, String)>,
+    /// Moves of the main line in SAN, without move numbers or annotations.
+    pub moves: Vec<String>,
+    /// Variations branching off the main line.
+    pub variations: Vec<PgnVariation>,
+    /// Comments and NAGs after the given number of moves of the main
+    /// line, `0` for those on the game before the first move.
+    pub notes: BTreeMap<usize, MoveNotes>,
+    /// Game termination marker: `1-0`, `0-1`, `1/2-1/2` or `*`.
+    pub result: Option<String>,
+}
+
+impl PgnGame {
+    /// Returns the value of the first tag called `name`.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the game result, preferring the movetext's termination
+    /// marker over the `Result` tag.
+    pub fn result(&self) -> Option<&str> {
+        self.result.as_deref().or_else(|| self.tag("Result"))
+    }
+}
+
+/// Tags naming a game's event and players, set by the user, see
+/// [`Game::tags`]. Tags left unset are exported with their standard
+/// values, e.g. `?` for an unknown player.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameTags {
+    pub event: Option<String>,
+    pub site: Option<String>,
+    pub round: Option<String>,
+    pub white: Option<String>,
+    pub black: Option<String>,
+}
+
+impl GameTags {
+    /// Reads the tags of a saved game, skipping the values [`write_pgn`]
+    /// exports unset tags with.
+    pub fn read(saved: &PgnGame) -> Self {
+        let tag = |name| {
+            saved
+                .tag(name)
+                .filter(|value| !["?", "-", DEFAULT_EVENT].contains(value))
+                .map(str::to_string)
+        };
+        Self {
+            event: tag("Event"),
+            site: tag("Site"),
+            round: tag("Round"),
+            white: tag("White"),
+            black: tag("Black"),
+        }
+    }
+
+    /// Sets the tag `name`, in lowercase, e.g. `event` or `white`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming a tag that can't be set.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let tag = match name {
+            "event" => &mut self.event,
+            "site" => &mut self.site,
+            "round" => &mut self.round,
+            "white" => &mut self.white,
+            "black" => &mut self.black,
+            _ => return Err(format!("unknown tag `{}`", name)),
+        };
+        *tag = Some(value.to_string());
+        Ok(())
+    }
+
+    /// Returns the name of the player of `color`, if it's set.
+    pub fn name(&self, color: Color) -> Option<&str> {
+        match color {
+            Color::White => self.white.as_deref(),
+            Color::Black => self.black.as_deref(),
+        }
+    }
+
+    /// Sets the name of the player of `color`.
+    pub fn set_name(&mut self, color: Color, name: &str) {
+        match color {
+            Color::White => self.white = Some(name.to_string()),
+            Color::Black => self.black = Some(name.to_string()),
+        }
+    }
+
+    /// Swaps the players' names, for a game they play with colors swapped.
+    pub fn swap_players(&mut self) {
+        std::mem::swap(&mut self.white, &mut self.black);
+    }
+
+    /// Returns the tags that are set as PGN tag pairs.
+    pub fn pairs(&self) -> Vec<(&'static str, String)> {
+        [
+            ("Event", &self.event),
+            ("Site", &self.site),
+            ("Round", &self.round),
+            ("White", &self.white),
+            ("Black", &self.black),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.clone().map(|value| (name, value)))
+        .collect()
+    }
+}
+
+/// A variation of a game read from PGN, played instead of a move of the
+/// line it branches off.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PgnVariation {
+    /// Index in the line it branches off of the move it replaces.
+    pub ply: usize,
+    /// Moves of the variation in SAN.
+    pub moves: Vec<String>,
+    /// Variations branching off this one.
+    pub variations: Vec<PgnVariation>,
+    /// Comments and NAGs after the given number of moves of the
+    /// variation.
+    pub notes: BTreeMap<usize, MoveNotes>,
+}
+
+/// A comment and Numeric Annotation Glyphs written after a move.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MoveNotes {
+    /// The comment's text, without commands like `[%emt 0:00:05]`.
+    pub comment: String,
+    pub nags: Vec<u8>,
+}
+
+pub const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Reads every game of a PGN database.
+///
+/// The moves of the main line and its variations (RAVs) are kept with
+/// their comments and NAGs, move numbers and comment commands like
+/// `[%emt]` are skipped. Games are separated
+/// either by their termination marker or by the tag section of the next
+/// game.
+///
+/// # Example
+///
+/// ```
+/// let games = parse_pgn("[White \"Me\"]\n\n1. e4 e5 (1... c5) 2. Nf3 1-0\n");
+/// assert_eq!(games[0].moves, ["e4", "e5", "Nf3"]);
+/// assert_eq!(games[0].variations[0].moves, ["c5"]);
+/// ```
+pub fn parse_pgn(text: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    // The variations being read, innermost last.
+    let mut open: Vec<PgnVariation> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            '[' => {
+                chars.next();
+                let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                open.clear();
+                if !game.moves.is_empty() || game.result.is_some() {
+                    games.push(std::mem::take(&mut game));
+                }
+                if let Some(pair) = parse_tag(&tag) {
+                    game.tags.push(pair);
+                }
+            }
+            '{' | ';' => {
+                let end = if c == '{' { '}' } else { '\n' };
+                chars.next();
+                let text: String = chars.by_ref().take_while(|c| *c != end).collect();
+                let text = comment_text(&text);
+                if !text.is_empty() {
+                    let comment = &mut current_notes(&mut game, &mut open).comment;
+                    if !comment.is_empty() {
+                        comment.push(' ');
+                    }
+                    comment.push_str(&text);
+                }
+            }
+            '(' => {
+                chars.next();
+                let line = open
+                    .last()
+                    .map_or(&game.moves, |variation| &variation.moves);
+                open.push(PgnVariation {
+                    ply: line.len().saturating_sub(1),
+                    ..PgnVariation::default()
+                });
+            }
+            ')' => {
+                chars.next();
+                if let Some(variation) = open.pop() {
+                    match open.last_mut() {
+                        Some(parent) => parent.variations.push(variation),
+                        None => game.variations.push(variation),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "[{;()".contains(c) {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                if RESULTS.contains(&token.as_str()) {
+                    open.clear();
+                    game.result = Some(token);
+                    games.push(std::mem::take(&mut game));
+                } else if let Some(nag) = parse_nag(&token) {
+                    current_notes(&mut game, &mut open).nags.push(nag);
+                } else if let Some(san) = move_token(&token) {
+                    match open.last_mut() {
+                        Some(variation) => variation.moves.push(san.to_string()),
+                        None => game.moves.push(san.to_string()),
+                    }
+                    let suffix = &token[token.trim_end_matches(['!', '?']).len()..];
+                    if let Some(nag) = parse_nag(suffix) {
+                        current_notes(&mut game, &mut open).nags.push(nag);
+                    }
+                }
+            }
+        }
+    }
+    if !game.moves.is_empty() || !game.tags.is_empty() {
+        games.push(game);
+    }
+    games
+}
+
+/// Returns the notes after the last move read of the innermost line
+/// being read: the open variation, if any, or else the main line.
+fn current_notes<'a>(game: &'a mut PgnGame, open: &'a mut [PgnVariation]) -> &'a mut MoveNotes {
+    let (moves, notes) = match open.last_mut() {
+        Some(variation) => (variation.moves.len(), &mut variation.notes),
+        None => (game.moves.len(), &mut game.notes),
+    };
+    notes.entry(moves).or_default()
+}
+
+/// Returns the text of a comment without its commands, e.g.
+/// `[%emt 0:00:05]`.
+fn comment_text(comment: &str) -> String {
+    let mut text = String::new();
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%") {
+        text.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find(']')
+            .map_or("", |end| &rest[start + end + 1..]);
+    }
+    text.push_str(rest);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Splits the inside of a `[Name "Value"]` tag into its name and value.
+pub fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let (name, rest) = tag.trim().split_once(char::is_whitespace)?;
+    let rest = rest.trim();
+    let value = rest.strip_prefix('"')?.strip_suffix('"')?;
+    Some((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+/// Strips move numbers and annotation glyphs from a movetext token,
+/// returning the SAN move it contains, if any.
+pub fn move_token(token: &str) -> Option<&str> {
+    if token.starts_with('$') {
+        return None;
+    }
+    let san = token
+        .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+        .trim_end_matches(['!', '?']);
+    (!san.is_empty()).then_some(san)
+}
+
+/// Maximum length of a movetext line in exported PGN.
+const LINE_WIDTH: usize = 79;
+
+/// Replays a game read from PGN on `game`, a new game of the wanted mode,
+/// with its variations, see [`Game::branch`].
+///
+/// The variant and starting position are taken from the `Variant` and
+/// `FEN` tags.
+///
+/// # Errors
+///
+/// Returns an error describing an unknown variant, invalid FEN,
+/// illegal move or move played after the game ended.
+pub fn replay(saved: &PgnGame, game: Game) -> Result<Game, String> {
+    let variant = match saved.tag("Variant") {
+        Some(name) => Variant::from_str(name).map_err(|_| format!("unknown variant {}", name))?,
+        None => Variant::Standard,
+    };
+    let start = match saved.tag("FEN") {
+        Some(fen) => validate_fen(fen).map_err(|e| format!("invalid FEN {}: {}", fen, e))?,
+        None => Board::default(),
+    };
+    let mut game = game.with_variant(variant).with_position(start);
+    if let Some(notes) = saved.notes.get(&0) {
+        game.set_comment(&notes.comment);
+        game.set_nags(&notes.nags);
+    }
+    replay_line(&mut game, &saved.moves, &saved.notes, &saved.variations)?;
+    Ok(game)
+}
+
+/// Plays the SAN `moves` of a line on `game` with their `notes`, then
+/// each of its `variations` from the position before the move it
+/// replaces, and goes back to the end of the line.
+fn replay_line(
+    game: &mut Game,
+    moves: &[String],
+    notes: &BTreeMap<usize, MoveNotes>,
+    variations: &[PgnVariation],
+) -> Result<(), String> {
+    let start = game.moves().len();
+    let play = |game: &mut Game, moves: &[String]| {
+        moves.iter().enumerate().try_for_each(|(i, text)| {
+            let mv =
+                parse_san(game.board(), text).ok_or_else(|| format!("illegal move {}", text))?;
+            game.make_move(mv)
+                .map_err(|_| format!("move {} after the end of the game", text))?;
+            if let Some(notes) = notes.get(&(i + 1)) {
+                game.set_comment(&notes.comment);
+                game.set_nags(&notes.nags);
+            }
+            Ok(())
+        })
+    };
+    play(game, moves)?;
+    if variations.is_empty() {
+        return Ok(());
+    }
+    for variation in variations {
+        let _ = game.branch(start);
+        play(game, &moves[..variation.ply.min(moves.len())])?;
+        replay_line(
+            game,
+            &variation.moves,
+            &variation.notes,
+            &variation.variations,
+        )?;
+    }
+    let _ = game.branch(start);
+    play(game, moves)
+}
+
+/// Returns the PGN result token for a game status.
+pub fn result_token(status: Status) -> &'static str {
+    match status {
+        Status::Checkmate(Color::White)
+        | Status::VariantWin(Color::White)
+        | Status::Resignation(Color::White)
+        | Status::Timeout(Color::White) => "1-0",
+        Status::Checkmate(Color::Black)
+        | Status::VariantWin(Color::Black)
+        | Status::Resignation(Color::Black)
+        | Status::Timeout(Color::Black) => "0-1",
+        Status::Stalemate | Status::DrawAgreed | Status::TimeoutDraw(_) => "1/2-1/2",
+        Status::Ongoing => "*",
+    }
+}
+
+/// Formats a move time as `h:mm:ss`, as used by `%emt` and `%clk` comments.
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Exports a game as PGN, with `extra_tags` replacing or following the standard ones.
+/// A `Result` among them also ends the movetext, for games decided
+/// otherwise than on the board. The tags set by the user, see
+/// [`Game::tags`], replace both.
+///
+/// Each move is followed by a `[%emt]` comment holding the time spent
+/// on it, after any comment on the move and its NAGs, and by a `[%clk]`
+/// command with the time left on the mover's clock in games played on
+/// one. Variations are exported as RAVs after the move they replace. Squares marked
+/// and arrows drawn on the board are exported as `[%csl]` and `[%cal]`
+/// commands in the comment of the current move. Games not starting
+/// from the standard position get `SetUp` and `FEN` tags, variant games
+/// a `Variant` tag and games played with `--seed` a `Seed` tag.
+///
+/// # Example
+///
+/// ```
+/// let mut game = Game::new_multi();
+/// game.make_move_from_str("e4", false).unwrap();
+/// assert!(write_pgn(&game, &[]).contains("1. e4 {[%emt 0:00:00]} *"));
+/// ```
+pub fn write_pgn(game: &Game, extra_tags: &[(&str, String)]) -> String {
+    let result = extra_tags
+        .iter()
+        .find(|(name, _)| *name == "Result")
+        .map_or(result_token(game.status()), |(_, result)| result.as_str());
+    let start = *game.starting_position();
+
+    let mut pgn = String::new();
+    let mut tags = vec![
+        ("Event", DEFAULT_EVENT.to_string()),
+        ("Site", "?".to_string()),
+        ("Date", "????.??.??".to_string()),
+        ("Round", "-".to_string()),
+        ("White", "?".to_string()),
+        ("Black", "?".to_string()),
+        ("Result", result.to_string()),
+    ];
+    if start != Board::default() {
+        tags.push(("SetUp", "1".to_string()));
+        tags.push(("FEN", start.to_string()));
+    }
+    if game.variant() != Variant::Standard {
+        tags.push(("Variant", game.variant().to_string()));
+    }
+    if let Some(seed) = seed() {
+        tags.push(("Seed", seed.to_string()));
+    }
+    for (name, value) in extra_tags.iter().cloned().chain(game.tags().pairs()) {
+        match tags.iter_mut().find(|(tag, _)| *tag == name) {
+            Some((_, standard)) => *standard = value,
+            None => tags.push((name, value)),
+        }
+    }
+    for (name, value) in tags {
+        pgn.push_str(&write_tag(name, &value));
+    }
+    pgn.push('\n');
+
+    let mut tokens = Vec::new();
+    let tree = game.move_tree();
+    let current = tree.node(game.current_path());
+    let mut comment = tree.comment.clone();
+    if std::ptr::eq(tree, current) && !game.annotations().is_empty() {
+        if !comment.is_empty() {
+            comment.push(' ');
+        }
+        comment.push_str(&game.annotations().pgn_commands());
+    }
+    if !comment.is_empty() {
+        tokens.push(format!("{{{}}}", comment));
+    }
+    write_line(&mut tokens, start, 1, &tree.children, current, game);
+    tokens.push(result.to_string());
+    pgn.push_str(&write_movetext(&tokens));
+    pgn
+}
+
+/// Appends to `tokens` the movetext of the line starting with the first
+/// of `moves`, played from `board` as move `number`, with the other
+/// `moves` and the variations further on as RAVs. The board annotations
+/// of `game` go in the comment of its `current` move.
+fn write_line(
+    tokens: &mut Vec<String>,
+    mut board: Board,
+    mut number: u32,
+    mut moves: &[MoveTree],
+    current: &MoveTree,
+    game: &Game,
+) {
+    let mut numbered = false;
+    while let Some((main, variations)) = moves.split_first() {
+        let Some(mv) = main.mv else {
+            return;
+        };
+        match board.side_to_move() {
+            Color::White => tokens.push(format!("{}.", number)),
+            Color::Black if !numbered => tokens.push(format!("{}...", number)),
+            Color::Black => (),
+        }
+        tokens.push(san_for_move(&board, mv));
+        tokens.extend(main.nags.iter().map(|nag| format!("${}", nag)));
+        let mut comment = main.comment.clone();
+        if !comment.is_empty() {
+            comment.push(' ');
+        }
+        comment.push_str(&format!("[%emt {}]", format_time(main.time)));
+        if let Some(clock) = main.clock {
+            comment.push_str(&format!("[%clk {}]", format_time(clock)));
+        }
+        if std::ptr::eq(main, current) {
+            comment.push_str(&game.annotations().pgn_commands());
+        }
+        tokens.push(format!("{{{}}}", comment));
+        for variation in variations {
+            let first = tokens.len();
+            write_line(
+                tokens,
+                board,
+                number,
+                std::slice::from_ref(variation),
+                current,
+                game,
+            );
+            tokens[first].insert(0, '(');
+            tokens.last_mut().unwrap().push(')');
+        }
+        // A move after a variation needs its number again.
+        numbered = variations.is_empty();
+        if board.side_to_move() == Color::Black {
+            number += 1;
+        }
+        board = board.make_move_new(mv);
+        moves = &main.children;
+    }
+}
+
+/// Writes a PGN tag pair line, escaping the value.
+pub fn write_tag(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+/// Joins the tokens of a game's movetext with spaces, wrapping lines
+/// so they don't get longer than [`LINE_WIDTH`].
+pub fn write_movetext(tokens: &[String]) -> String {
+    let mut movetext = String::new();
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+            movetext.push_str(&line);
+            movetext.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(token);
+    }
+    movetext.push_str(&line);
+    movetext.push('\n');
+    movetext
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tags_and_moves() {
+        let games = parse_pgn(
+            "[Event \"Casual\"]\n[White \"Anna \\\"The Rook\\\"\"]\n[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n",
+        );
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].tag("Event"), Some("Casual"));
+        assert_eq!(games[0].tag("White"), Some("Anna \"The Rook\""));
+        assert_eq!(games[0].moves, ["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(games[0].result(), Some("0-1"));
+    }
+
+    #[test]
+    fn exported_tags_replace_standard_ones() {
+        let game = Game::new_multi();
+        let pgn = write_pgn(
+            &game,
+            &[
+                ("White", "Anna \"The Rook\"".to_string()),
+                ("Round", "3".to_string()),
+            ],
+        );
+        assert!(pgn.contains("[Round \"3\"]"));
+        assert!(!pgn.contains("[Round \"-\"]"));
+        assert_eq!(parse_pgn(&pgn)[0].tag("White"), Some("Anna \"The Rook\""));
+        assert!(!pgn.contains("[Seed"));
+        crate::rng::set_seed(7);
+        assert!(write_pgn(&game, &[]).contains("[Seed \"7\"]"));
+    }
+
+    #[test]
+    fn skips_comments_variations_and_nags() {
+        let games = parse_pgn(
+            "1. e4 {best by test} e5 (1... c5 2. Nf3 (2. c3)) 2. Nf3!? $1 ; a comment\nNc6 3... a6 *",
+        );
+        assert_eq!(games[0].moves, ["e4", "e5", "Nf3", "Nc6", "a6"]);
+        assert_eq!(games[0].result(), Some("*"));
+    }
+
+    #[test]
+    fn variations_read_back() {
+        let games = parse_pgn("1. e4 e5 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 (2. Bc4) Nc6 *");
+        let variation = &games[0].variations[0];
+        assert_eq!(variation.ply, 1);
+        assert_eq!(variation.moves, ["c5", "Nf3", "d6"]);
+        assert_eq!(variation.variations[0].ply, 1);
+        let game = replay(&games[0], Game::new_multi()).unwrap();
+        assert_eq!(game.moves().len(), 4);
+        let pgn = write_pgn(&game, &[]);
+        let movetext = pgn.split_whitespace().collect::<Vec<_>>().join(" ");
+        let movetext = movetext.replace(" {[%emt 0:00:00]}", "");
+        assert!(movetext
+            .contains("1. e4 e5 (1... c5 2. Nf3 (2. c3) 2... d6) 2. Nf3 (2. Bc4) 2... Nc6 *"));
+        assert_eq!(parse_pgn(&pgn)[0].variations, games[0].variations);
+    }
+
+    #[test]
+    fn comments_and_nags_read_back() {
+        let games = parse_pgn(
+            "{Study} 1. e4!? {Sharp [%emt 0:00:03]} e5 $2 (1... c5 {Sicilian}) 2. Nf3 ; home\n*",
+        );
+        let notes = &games[0].notes;
+        assert_eq!(notes[&0].comment, "Study");
+        assert_eq!(notes[&1].comment, "Sharp");
+        assert_eq!(notes[&1].nags, [5]);
+        assert_eq!(notes[&2].nags, [2]);
+        assert_eq!(notes[&3].comment, "home");
+        assert_eq!(games[0].variations[0].notes[&1].comment, "Sicilian");
+
+        let mut game = replay(&games[0], Game::new_multi()).unwrap();
+        assert_eq!(game.comment(), "home");
+        game.toggle_nag(1);
+        game.set_comment("Development");
+        let pgn = write_pgn(&game, &[]);
+        let read = &parse_pgn(&pgn)[0];
+        assert_eq!(read.notes[&0].comment, "Study");
+        assert_eq!(read.notes[&1], notes[&1]);
+        assert_eq!(read.notes[&3].comment, "Development");
+        assert_eq!(read.notes[&3].nags, [1]);
+        assert_eq!(read.variations, games[0].variations);
+        assert!(pgn.contains("e4 $5 {Sharp [%emt"));
+    }
+
+    #[test]
+    fn splits_multiple_games() {
+        let games = parse_pgn(
+            "[White \"A\"]\n\n1. e4 1-0\n\n[White \"B\"]\n\n1. d4 d5 1/2-1/2\n[White \"C\"]\n1. c4\n",
+        );
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[1].tag("White"), Some("B"));
+        assert_eq!(games[1].moves, ["d4", "d5"]);
+        assert_eq!(games[1].result(), Some("1/2-1/2"));
+        assert_eq!(games[2].moves, ["c4"]);
+        assert_eq!(games[2].result(), None);
+    }
+
+    #[test]
+    fn exported_pgn_reads_back() {
+        let mut game = Game::new_multi();
+        for mv in ["f3", "e5", "g4", "Qh4"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        let pgn = write_pgn(&game, &[]);
+        assert!(pgn.contains("[Result \"0-1\"]"));
+        assert!(pgn.contains("1. f3 {[%emt 0:00:00]} e5"));
+        let games = parse_pgn(&pgn);
+        assert_eq!(games[0].moves, ["f3", "e5", "g4", "Qh4#"]);
+        assert_eq!(games[0].result(), Some("0-1"));
+    }
+
+    #[test]
+    fn exported_pgn_from_position() {
+        let board = Board::from_str("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap();
+        let mut game = Game::new_multi().with_position(board);
+        game.make_move_from_str("Kd7", false).unwrap();
+        let pgn = write_pgn(&game, &[]);
+        assert!(pgn.contains("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]"));
+        assert!(pgn.contains("1... Kd7"));
+        game.annotations_mut().toggle_mark(chess::Square::E2);
+        assert!(write_pgn(&game, &[]).contains("Kd7 {[%emt 0:00:00][%csl Ge2]} *"));
+        assert_eq!(format_time(Duration::from_secs(3725)), "1:02:05");
+    }
+
+    #[test]
+    fn exports_the_remaining_clock_time() {
+        let mut game = Game::new_multi();
+        game.make_move_from_str("e4", false).unwrap();
+        game.set_clock(Duration::from_secs(302));
+        game.make_move_from_str("e5", false).unwrap();
+        let pgn = write_pgn(&game, &[]);
+        assert!(pgn.contains("1. e4 {[%emt 0:00:00][%clk 0:05:02]} e5 {[%emt 0:00:00]}"));
+        assert_eq!(parse_pgn(&pgn)[0].moves, ["e4", "e5"]);
+    }
+
+    #[test]
+    fn exports_the_users_tags() {
+        let mut tags = GameTags::default();
+        tags.set("white", "Anna").unwrap();
+        tags.set("event", "Club championship").unwrap();
+        assert!(tags.set("date", "today").is_err());
+        let mut game = Game::new_multi().with_tags(tags);
+        let pgn = write_pgn(&game, &[("Event", "rChess game".to_string())]);
+        let saved = &parse_pgn(&pgn)[0];
+        assert_eq!(saved.tag("Event"), Some("Club championship"));
+        assert_eq!(saved.tag("White"), Some("Anna"));
+        assert_eq!(saved.tag("Black"), Some("?"));
+        assert_eq!(&GameTags::read(saved), game.tags());
+        game.rematch();
+        assert_eq!(game.tags().name(Color::Black), Some("Anna"));
+        assert_eq!(game.tags().name(Color::White), None);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::ai::{piece_value, see, MATE_SCORE};
+use chess::{Board, ChessMove, Piece, EMPTY};
+
+/// Score in centipawns from which a position counts as easily won, or
+/// below minus which as lost.
+const DECISIVE_SCORE: i32 = 400;
+/// Centipawns below the best move a winning side gives up to simplify.
+const WINNING_MARGIN: i32 = 60;
+/// Centipawns below the best move a losing side gives up to complicate.
+const LOSING_MARGIN: i32 = 40;
+/// Bonus for a move giving check, which the opponent has to answer.
+const CHECK_BONUS: i32 = 20;
+
+/// How a game is going for the side to move, judged from the score of
+/// its search.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outlook {
+    Winning,
+    Balanced,
+    Losing,
+}
+
+impl Outlook {
+    /// Judges a score in centipawns from the point of view of the side
+    /// to move.
+    pub fn from_score(score: i32) -> Self {
+        match score {
+            score if score >= DECISIVE_SCORE => Outlook::Winning,
+            score if score <= -DECISIVE_SCORE => Outlook::Losing,
+            _ => Outlook::Balanced,
+        }
+    }
+
+    /// Returns how much of its usual time the side to move spends: less
+    /// when easily winning, where deep thought rarely changes the result,
+    /// and more when losing, looking for chances to swindle.
+    pub fn time_factor(self) -> f64 {
+        match self {
+            Outlook::Winning => 0.5,
+            Outlook::Balanced => 1.0,
+            Outlook::Losing => 1.25,
+        }
+    }
+}
+
+/// Looks for the move a practical player would prefer to the best one
+/// among `scored` moves in `board`, scored from the point of view of the
+/// side to move and sorted best first, as by
+/// [`score_moves`](crate::ai::score_moves).
+///
+/// In an easily won position it prefers trading pieces off, among the
+/// moves that keep it easily won, and in a lost one it avoids trades and
+/// looks for checks to keep the game complicated, giving up only a
+/// little of the best score.
+///
+/// # Returns
+///
+/// The preferred move and its score, `None` if no move is preferred to
+/// the best one, always so in a balanced position or with a mate on the
+/// board.
+///
+/// # Example
+///
+/// ```
+/// let scored = score_moves(&board, &VariantState::default(), 3, &EngineOptions::default());
+/// let (mv, score) = practical_move(&board, &scored).unwrap_or(scored[0]);
+/// ```
+pub fn practical_move(board: &Board, scored: &[(ChessMove, i32)]) -> Option<(ChessMove, i32)> {
+    let &(best, best_score) = scored.first()?;
+    let outlook = Outlook::from_score(best_score);
+    let margin = match outlook {
+        Outlook::Balanced => return None,
+        _ if best_score.abs() >= MATE_SCORE - 1000 => return None,
+        Outlook::Winning => WINNING_MARGIN,
+        Outlook::Losing => LOSING_MARGIN,
+    };
+    let mut choice = None;
+    let mut choice_value = best_score + practical_bonus(board, best, outlook);
+    for &(mv, score) in &scored[1..] {
+        if best_score - score > margin
+            || (outlook == Outlook::Winning && Outlook::from_score(score) != Outlook::Winning)
+        {
+            continue;
+        }
+        let value = score + practical_bonus(board, mv, outlook);
+        if value > choice_value {
+            choice = Some((mv, score));
+            choice_value = value;
+        }
+    }
+    choice
+}
+
+/// Returns how much `mv` suits a player in `outlook` beyond its score:
+/// an even trade of pieces simplifies the game, welcome when winning and
+/// avoided when losing, where a check also sets the opponent a problem.
+fn practical_bonus(board: &Board, mv: ChessMove, outlook: Outlook) -> i32 {
+    let trade = match board.piece_on(mv.get_dest()) {
+        Some(captured) if captured != Piece::Pawn && see(board, mv) >= 0 => {
+            piece_value(captured) / 10
+        }
+        _ => 0,
+    };
+    let check = *board.make_move_new(mv).checkers() != EMPTY;
+    match outlook {
+        Outlook::Winning => trade,
+        Outlook::Balanced => 0,
+        Outlook::Losing => -trade + if check { CHECK_BONUS } else { 0 },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn mv(text: &str) -> ChessMove {
+        ChessMove::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn trades_when_winning() {
+        // Up a rook, White can trade queens for a little less.
+        let board = Board::from_str("3qk3/8/8/8/8/8/8/R2QK3 w - - 0 1").unwrap();
+        let scored = [(mv("a1a7"), 900), (mv("d1d8"), 870), (mv("e1e2"), 880)];
+        assert_eq!(practical_move(&board, &scored), Some((mv("d1d8"), 870)));
+        // But not for too much.
+        let scored = [(mv("a1a7"), 900), (mv("d1d8"), 800)];
+        assert_eq!(practical_move(&board, &scored), None);
+        // Nor if the position is no longer easily won.
+        let scored = [(mv("a1a7"), 420), (mv("d1d8"), 390)];
+        assert_eq!(practical_move(&board, &scored), None);
+    }
+
+    #[test]
+    fn complicates_when_losing() {
+        // Down a rook, Black keeps the queens on and checks instead.
+        let board = Board::from_str("3qk3/8/8/8/8/8/8/R2QK3 b - - 0 1").unwrap();
+        let scored = [(mv("d8d1"), -880), (mv("d8a5"), -900), (mv("e8e7"), -910)];
+        assert_eq!(practical_move(&board, &scored), Some((mv("d8a5"), -900)));
+        let balanced = [(mv("d8d1"), 0), (mv("d8a5"), -10)];
+        assert_eq!(practical_move(&board, &balanced), None);
+        assert_eq!(Outlook::from_score(-MATE_SCORE + 5).time_factor(), 1.25);
+    }
+}
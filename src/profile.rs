@@ -0,0 +1,478 @@
+use crate::ai::Difficulty;
+use crate::config::config_dir;
+use crate::coords::CoordsMode;
+use crate::game::Status;
+use chess::Color;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Name of the profile store inside the config directory.
+const PROFILES_FILE: &str = "profiles.tsv";
+/// Rating given to new players.
+const INITIAL_RATING: f64 = 1000.0;
+/// How much a single game can change a rating.
+const K_FACTOR: f64 = 32.0;
+/// Difficulties in the order their results are stored. Levels added
+/// later come last, so older profile stores can still be read.
+const DIFFICULTIES: [Difficulty; 7] = [
+    Difficulty::Easy,
+    Difficulty::Medium,
+    Difficulty::Hard,
+    Difficulty::Random,
+    Difficulty::Greedy,
+    Difficulty::Beginner,
+    Difficulty::Adaptive,
+];
+
+/// Returns where player profiles are stored.
+pub fn profiles_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(PROFILES_FILE))
+}
+
+/// Estimated rating of the AI at each difficulty.
+fn ai_rating(difficulty: Difficulty) -> f64 {
+    match difficulty {
+        Difficulty::Random => 200.0,
+        Difficulty::Greedy => 400.0,
+        Difficulty::Beginner => 600.0,
+        Difficulty::Easy => 800.0,
+        Difficulty::Medium => 1200.0,
+        Difficulty::Hard => 1600.0,
+        Difficulty::Elo(elo) => f64::from(elo),
+        // Only meaningful next to the player's rating, see Profile::add_result.
+        Difficulty::Adaptive => INITIAL_RATING,
+    }
+}
+
+fn coords_index(mode: CoordsMode) -> usize {
+    match mode {
+        CoordsMode::Name => 0,
+        CoordsMode::Color => 1,
+    }
+}
+
+/// Returns where the record of games at `difficulty` is kept, games at
+/// an Elo counting for the level rated closest to it.
+fn difficulty_index(difficulty: Difficulty) -> usize {
+    let level = match difficulty {
+        Difficulty::Elo(_) => closest_difficulty(ai_rating(difficulty)),
+        difficulty => difficulty,
+    };
+    DIFFICULTIES.iter().position(|d| *d == level).unwrap()
+}
+
+/// Returns the difficulty, other than adaptive, rated closest to `rating`.
+fn closest_difficulty(rating: f64) -> Difficulty {
+    DIFFICULTIES
+        .iter()
+        .filter(|d| **d != Difficulty::Adaptive)
+        .min_by(|a, b| {
+            let distance = |d: &Difficulty| (ai_rating(*d) - rating).abs();
+            distance(a).total_cmp(&distance(b))
+        })
+        .copied()
+        .unwrap()
+}
+
+/// Result of a game from the player's point of view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    /// Returns the result of a game that ended with `status` for the
+    /// player of `player`, `None` while it goes on.
+    pub fn from_status(status: Status, player: Color) -> Option<Self> {
+        match status {
+            Status::Ongoing => None,
+            Status::Stalemate | Status::DrawAgreed | Status::TimeoutDraw(_) => Some(Outcome::Draw),
+            Status::Checkmate(winner)
+            | Status::VariantWin(winner)
+            | Status::Resignation(winner)
+            | Status::Timeout(winner)
+                if winner == player =>
+            {
+                Some(Outcome::Win)
+            }
+            Status::Checkmate(_)
+            | Status::VariantWin(_)
+            | Status::Resignation(_)
+            | Status::Timeout(_) => Some(Outcome::Loss),
+        }
+    }
+
+    /// Returns the points scored: 1 for a win, ½ for a draw.
+    pub fn score(self) -> f64 {
+        match self {
+            Outcome::Win => 1.0,
+            Outcome::Draw => 0.5,
+            Outcome::Loss => 0.0,
+        }
+    }
+}
+
+/// Wins, draws and losses against the AI at one difficulty.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// A named player's results against the AI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    /// Running Elo estimate.
+    pub rating: f64,
+    /// Results indexed like [`DIFFICULTIES`].
+    records: [Record; 7],
+    /// Average accuracy in percent over the games it was measured in.
+    accuracy: f64,
+    /// Number of games the accuracy was measured in.
+    measured_games: u32,
+    /// Number of sessions the player played games in.
+    sessions: u32,
+    /// Total length of those sessions.
+    time_played: Duration,
+    /// Best coordinate training scores, naming squares and telling
+    /// their colors.
+    coords: [u32; 2],
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rating: INITIAL_RATING,
+            records: [Record::default(); 7],
+            accuracy: 0.0,
+            measured_games: 0,
+            sessions: 0,
+            time_played: Duration::ZERO,
+            coords: [0; 2],
+        }
+    }
+
+    /// Returns the results against the AI at `difficulty`.
+    pub fn record(&self, difficulty: Difficulty) -> Record {
+        self.records[difficulty_index(difficulty)]
+    }
+
+    /// Returns the total number of games played.
+    pub fn games(&self) -> u32 {
+        self.records.iter().map(Record::games).sum()
+    }
+
+    /// Records a game against the AI at `difficulty` and updates the rating.
+    pub fn add_result(&mut self, difficulty: Difficulty, outcome: Outcome) {
+        let record = &mut self.records[difficulty_index(difficulty)];
+        match outcome {
+            Outcome::Win => record.wins += 1,
+            Outcome::Draw => record.draws += 1,
+            Outcome::Loss => record.losses += 1,
+        }
+        // The adaptive AI plays at the player's level, so it is expected
+        // to score half the points.
+        let opponent = match difficulty {
+            Difficulty::Adaptive => self.rating,
+            _ => ai_rating(difficulty),
+        };
+        let expected = 1.0 / (1.0 + 10f64.powf((opponent - self.rating) / 400.0));
+        self.rating += K_FACTOR * (outcome.score() - expected);
+    }
+
+    /// Returns the player's average accuracy in percent, `None` if it
+    /// was never measured.
+    pub fn accuracy(&self) -> Option<f64> {
+        (self.measured_games > 0).then_some(self.accuracy)
+    }
+
+    /// Adds the player's accuracy in a game to their average.
+    pub fn add_accuracy(&mut self, accuracy: f64) {
+        self.measured_games += 1;
+        self.accuracy += (accuracy - self.accuracy) / f64::from(self.measured_games);
+    }
+
+    /// Returns the number of sessions the player played games in.
+    pub fn sessions(&self) -> u32 {
+        self.sessions
+    }
+
+    /// Returns the total length of the player's sessions.
+    pub fn time_played(&self) -> Duration {
+        self.time_played
+    }
+
+    /// Adds a session of the player's, lasting `duration`.
+    pub fn add_session(&mut self, duration: Duration) {
+        self.sessions += 1;
+        self.time_played += duration;
+    }
+
+    /// Returns the best coordinate training score in `mode`.
+    pub fn best_coords(&self, mode: CoordsMode) -> u32 {
+        self.coords[coords_index(mode)]
+    }
+
+    /// Records a coordinate training score in `mode`.
+    ///
+    /// # Returns
+    ///
+    /// Whether it's a new best score.
+    pub fn add_coords_score(&mut self, mode: CoordsMode, score: u32) -> bool {
+        let best = &mut self.coords[coords_index(mode)];
+        let improved = score > *best;
+        *best = (*best).max(score);
+        improved
+    }
+
+    /// Returns the difficulty whose estimated rating is closest to the player's.
+    pub fn suggested_difficulty(&self) -> Difficulty {
+        closest_difficulty(self.rating)
+    }
+
+    /// Formats the profile as a line of the profile store.
+    fn to_line(&self) -> String {
+        let mut line = format!("{}\t{:.1}", self.name, self.rating);
+        for record in &self.records {
+            line.push_str(&format!(
+                "\t{} {} {}",
+                record.wins, record.draws, record.losses
+            ));
+        }
+        line.push_str(&format!("\t{:.1} {}", self.accuracy, self.measured_games));
+        line.push_str(&format!(
+            "\t{} {}",
+            self.sessions,
+            self.time_played.as_secs()
+        ));
+        line.push_str(&format!("\t{} {}", self.coords[0], self.coords[1]));
+        line
+    }
+
+    /// Reads a profile from a line of the profile store. Results against
+    /// levels missing from the line, e.g. written before they were
+    /// added, are left empty, as are the accuracy, sessions and
+    /// coordinate training scores if the line ends before them.
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let mut profile = Profile::new(fields.next()?);
+        profile.rating = fields.next()?.parse().ok()?;
+        for (record, field) in profile.records.iter_mut().zip(fields.by_ref()) {
+            let counts: Vec<u32> = field
+                .split(' ')
+                .map(|count| count.parse().ok())
+                .collect::<Option<_>>()?;
+            let [wins, draws, losses] = counts[..] else {
+                return None;
+            };
+            *record = Record {
+                wins,
+                draws,
+                losses,
+            };
+        }
+        if let Some(field) = fields.next() {
+            let (accuracy, games) = field.split_once(' ')?;
+            profile.accuracy = accuracy.parse().ok()?;
+            profile.measured_games = games.parse().ok()?;
+        }
+        if let Some(field) = fields.next() {
+            let (sessions, seconds) = field.split_once(' ')?;
+            profile.sessions = sessions.parse().ok()?;
+            profile.time_played = Duration::from_secs(seconds.parse().ok()?);
+        }
+        if let Some(field) = fields.next() {
+            let (name, color) = field.split_once(' ')?;
+            profile.coords = [name.parse().ok()?, color.parse().ok()?];
+        }
+        Some(profile)
+    }
+}
+
+/// All player profiles, stored one per line in a tab-separated file.
+#[derive(Debug, Default, PartialEq)]
+pub struct ProfileStore {
+    profiles: Vec<Profile>,
+}
+
+impl ProfileStore {
+    /// Reads the profiles stored at `path`; a missing file holds no profiles.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or a line is malformed.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let profiles = text
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                Profile::from_line(line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("malformed profile: {}", line),
+                    )
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { profiles })
+    }
+
+    /// Writes the profiles to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text: String = self
+            .profiles
+            .iter()
+            .map(|profile| profile.to_line() + "\n")
+            .collect();
+        fs::write(path, text)
+    }
+
+    /// Returns the profiles, best rated first.
+    pub fn profiles(&self) -> Vec<&Profile> {
+        let mut profiles: Vec<_> = self.profiles.iter().collect();
+        profiles.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        profiles
+    }
+
+    /// Returns the profile of the player called `name`.
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// Returns the profile of the player called `name`, creating it if needed.
+    pub fn get_or_create(&mut self, name: &str) -> &mut Profile {
+        match self
+            .profiles
+            .iter()
+            .position(|profile| profile.name == name)
+        {
+            Some(i) => &mut self.profiles[i],
+            None => {
+                self.profiles.push(Profile::new(name));
+                self.profiles.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Removes the profile of the player called `name`, returning whether it existed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.profiles.len();
+        self.profiles.retain(|profile| profile.name != name);
+        self.profiles.len() != count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn rating_follows_results() {
+        let mut profile = Profile::new("Anna");
+        profile.add_result(Difficulty::Medium, Outcome::Win);
+        assert!(profile.rating > INITIAL_RATING);
+        let rating = profile.rating;
+        profile.add_result(Difficulty::Easy, Outcome::Loss);
+        assert!(profile.rating < rating);
+        profile.add_result(Difficulty::Easy, Outcome::Draw);
+        assert_eq!(
+            profile.record(Difficulty::Easy),
+            Record {
+                wins: 0,
+                draws: 1,
+                losses: 1
+            }
+        );
+        assert_eq!(profile.games(), 3);
+        // Games at an Elo count for the level rated closest to it.
+        profile.add_result(Difficulty::Elo(850), Outcome::Win);
+        assert_eq!(profile.record(Difficulty::Easy).wins, 1);
+    }
+
+    #[test]
+    fn suggests_closest_difficulty() {
+        let mut profile = Profile::new("Bob");
+        profile.rating = 1500.0;
+        assert_eq!(profile.suggested_difficulty(), Difficulty::Hard);
+        profile.rating = 700.0;
+        assert_eq!(profile.suggested_difficulty(), Difficulty::Easy);
+    }
+
+    #[test]
+    fn store_round_trip() {
+        let dir = TempDir::new("profiles");
+        let path = dir.join(PROFILES_FILE);
+        assert_eq!(ProfileStore::load(&path).unwrap(), ProfileStore::default());
+
+        let mut store = ProfileStore::default();
+        store
+            .get_or_create("Anna Nowak")
+            .add_result(Difficulty::Hard, Outcome::Win);
+        store.get_or_create("Anna Nowak").add_accuracy(80.0);
+        store.get_or_create("Anna Nowak").add_accuracy(90.0);
+        store
+            .get_or_create("Anna Nowak")
+            .add_session(Duration::from_secs(600));
+        assert!(store
+            .get_or_create("Anna Nowak")
+            .add_coords_score(CoordsMode::Color, 12));
+        assert!(!store
+            .get_or_create("Anna Nowak")
+            .add_coords_score(CoordsMode::Color, 9));
+        store.get_or_create("Bob");
+        store.save(&path).unwrap();
+
+        let loaded = ProfileStore::load(&path).unwrap();
+        assert_eq!(loaded.profiles()[0].name, "Anna Nowak");
+        assert_eq!(
+            loaded
+                .get("Anna Nowak")
+                .unwrap()
+                .record(Difficulty::Hard)
+                .wins,
+            1
+        );
+        assert_eq!(loaded.get("Anna Nowak").unwrap().accuracy(), Some(85.0));
+        assert_eq!(loaded.get("Bob").unwrap().accuracy(), None);
+        assert_eq!(
+            loaded.get("Anna Nowak").unwrap().time_played(),
+            Duration::from_secs(600)
+        );
+        assert_eq!(
+            loaded
+                .get("Anna Nowak")
+                .unwrap()
+                .best_coords(CoordsMode::Color),
+            12
+        );
+        assert!(store.remove("Bob"));
+
+        let old = Profile::from_line("Carol\t900.0\t1 0 0\t0 0 2\t0 1 0").unwrap();
+        assert_eq!(old.record(Difficulty::Medium).losses, 2);
+        assert_eq!(old.record(Difficulty::Random), Record::default());
+        assert_eq!(old.sessions(), 0);
+        assert!(!store.remove("Bob"));
+    }
+}
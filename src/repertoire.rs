@@ -0,0 +1,252 @@
+use crate::legality::validate_fen;
+use crate::notation::{parse_san, san_for_move};
+use crate::pgn::{move_token, parse_tag, RESULTS};
+use chess::{Board, ChessMove, Color};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The moves of a player's repertoire, by position.
+///
+/// Positions are looked up by Zobrist hash, so a line reached by a
+/// transposition still counts as in the repertoire.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpeningTree {
+    moves: HashMap<u64, Vec<ChessMove>>,
+}
+
+/// The move with which a game left the repertoire.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deviation {
+    /// Number of half-moves played before the deviation.
+    pub ply: usize,
+    pub color: Color,
+    /// The move played, in SAN.
+    pub played: String,
+    /// The repertoire moves of the position, in SAN.
+    pub book: Vec<String>,
+}
+
+impl Deviation {
+    /// Describes the deviation, e.g. `White left the repertoire with
+    /// 3. Bc4; the book move was Bb5`.
+    pub fn describe(&self) -> String {
+        let dots = match self.color {
+            Color::White => ".",
+            Color::Black => "...",
+        };
+        let moves = if self.book.len() == 1 {
+            "move was"
+        } else {
+            "moves were"
+        };
+        format!(
+            "{:?} left the repertoire with {}{} {}; the book {} {}",
+            self.color,
+            self.ply / 2 + 1,
+            dots,
+            self.played,
+            moves,
+            self.book.join(", ")
+        )
+    }
+}
+
+impl OpeningTree {
+    /// Builds the tree from a PGN file of repertoire lines, reading both
+    /// the main lines and their variations, nested to any depth.
+    ///
+    /// Each game starts from its `FEN` tag, if it has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming an illegal move or invalid FEN.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let tree = OpeningTree::from_pgn("1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *")?;
+    /// assert_eq!(tree.book_moves(&Board::default()).len(), 1);
+    /// ```
+    pub fn from_pgn(text: &str) -> Result<Self, String> {
+        let mut tree = Self::default();
+        let mut board = Board::default();
+        // The position before the last move, where a variation starts.
+        let mut previous = board;
+        let mut variations = Vec::new();
+        let mut in_movetext = false;
+        let mut chars = text.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            match c {
+                '[' => {
+                    chars.next();
+                    let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                    if in_movetext {
+                        board = Board::default();
+                        variations.clear();
+                        in_movetext = false;
+                    }
+                    if let Some((name, fen)) = parse_tag(&tag).filter(|(name, _)| name == "FEN") {
+                        board = validate_fen(&fen)
+                            .map_err(|e| format!("invalid {} {}: {}", name, fen, e))?;
+                    }
+                    previous = board;
+                }
+                '{' => {
+                    chars.by_ref().find(|c| *c == '}');
+                }
+                ';' => {
+                    chars.by_ref().find(|c| *c == '\n');
+                }
+                '(' => {
+                    chars.next();
+                    variations.push((previous, board));
+                    board = previous;
+                }
+                ')' => {
+                    chars.next();
+                    if let Some((before, after)) = variations.pop() {
+                        previous = before;
+                        board = after;
+                    }
+                }
+                c if c.is_whitespace() => {
+                    chars.next();
+                }
+                _ => {
+                    let mut token = String::new();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || "[{;()".contains(c) {
+                            break;
+                        }
+                        token.push(c);
+                        chars.next();
+                    }
+                    in_movetext = true;
+                    if RESULTS.contains(&token.as_str()) {
+                        board = Board::default();
+                        previous = board;
+                        variations.clear();
+                        in_movetext = false;
+                    } else if let Some(text) = move_token(&token) {
+                        let mv = parse_san(&board, text)
+                            .ok_or_else(|| format!("illegal move {}", text))?;
+                        let book = tree.moves.entry(board.get_hash()).or_default();
+                        if !book.contains(&mv) {
+                            book.push(mv);
+                        }
+                        previous = board;
+                        board = board.make_move_new(mv);
+                    }
+                }
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Reads a repertoire file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or has an illegal move.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_pgn(&text)
+    }
+
+    /// Returns the repertoire moves of `board`, empty if the position
+    /// isn't in the repertoire.
+    pub fn book_moves(&self, board: &Board) -> &[ChessMove] {
+        self.moves
+            .get(&board.get_hash())
+            .map_or(&[], |moves| moves.as_slice())
+    }
+
+    /// Finds the first of `moves`, played from `start`, that leaves the
+    /// repertoire.
+    ///
+    /// A game that reaches the end of a repertoire line, or doesn't
+    /// start in the repertoire, has no deviation.
+    pub fn deviation(&self, start: &Board, moves: &[ChessMove]) -> Option<Deviation> {
+        let mut board = *start;
+        for (ply, mv) in moves.iter().enumerate() {
+            let book = self.book_moves(&board);
+            if book.is_empty() {
+                return None;
+            }
+            if !book.contains(mv) {
+                return Some(Deviation {
+                    ply,
+                    color: board.side_to_move(),
+                    played: san_for_move(&board, *mv),
+                    book: book
+                        .iter()
+                        .map(|book| san_for_move(&board, *book))
+                        .collect(),
+                });
+            }
+            board = board.make_move_new(*mv);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPERTOIRE: &str = "\
+[Event \"White\"]
+
+1. e4 e5 (1... c5 2. Nf3 d6 (2... Nc6 3. d4) 3. d4) 2. Nf3 {main line} Nc6 3. Bb5 *
+
+[FEN \"rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq - 0 1\"]
+
+1... d5 (1... Nf6) *
+";
+
+    fn moves(sans: &[&str]) -> Vec<ChessMove> {
+        let mut board = Board::default();
+        sans.iter()
+            .map(|text| {
+                let mv = parse_san(&board, text).unwrap();
+                board = board.make_move_new(mv);
+                mv
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_variations() {
+        let tree = OpeningTree::from_pgn(REPERTOIRE).unwrap();
+        assert_eq!(tree.book_moves(&Board::default()), moves(&["e4"]));
+        let after_e4 = Board::default().make_move_new(moves(&["e4"])[0]);
+        assert_eq!(tree.book_moves(&after_e4).len(), 2);
+        let sicilian = moves(&["e4", "c5", "Nf3"]);
+        assert_eq!(tree.deviation(&Board::default(), &sicilian), None);
+        // 1. d4 from the FEN start: both replies are in the repertoire.
+        let after_d4 = Board::default().make_move_new(moves(&["d4"])[0]);
+        assert_eq!(tree.book_moves(&after_d4).len(), 2);
+        assert_eq!(
+            OpeningTree::from_pgn("1. e4 e5 2. Ke3 *"),
+            Err("illegal move Ke3".to_string())
+        );
+    }
+
+    #[test]
+    fn finds_deviations() {
+        let tree = OpeningTree::from_pgn(REPERTOIRE).unwrap();
+        let played = moves(&["e4", "c5", "Nf3", "e6"]);
+        let deviation = tree.deviation(&Board::default(), &played).unwrap();
+        assert_eq!(deviation.ply, 3);
+        assert_eq!(deviation.color, Color::Black);
+        assert_eq!(
+            deviation.describe(),
+            "Black left the repertoire with 2... e6; the book moves were d6, Nc6"
+        );
+        // Past the end of a line, moves are no longer checked.
+        let ruy = moves(&["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4"]);
+        assert_eq!(tree.deviation(&Board::default(), &ruy), None);
+    }
+}
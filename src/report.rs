@@ -0,0 +1,241 @@
+use crate::ai::{evaluate, king_attackers, legal_move_count, piece_value};
+use chess::{
+    get_adjacent_files, get_file, BitBoard, Board, Color, Piece, Square, ALL_FILES, ALL_PIECES,
+    ALL_SQUARES, EMPTY,
+};
+use std::fmt;
+
+/// How the pawns of one side stand.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PawnStructure {
+    /// Groups of pawns on adjacent files.
+    pub islands: u32,
+    /// Pawns with another pawn of their side in front of them on their file.
+    pub doubled: u32,
+    /// Pawns with no pawn of their side on the adjacent files.
+    pub isolated: u32,
+    /// Pawns no enemy pawn can stop on their way to promotion, in front
+    /// of them on their file or the adjacent ones.
+    pub passed: u32,
+}
+
+impl PawnStructure {
+    /// Sums up the pawns of `color` on `board`.
+    pub fn new(board: &Board, color: Color) -> Self {
+        let ours = *board.pieces(Piece::Pawn) & board.color_combined(color);
+        let theirs = *board.pieces(Piece::Pawn) & board.color_combined(!color);
+        let mut structure = Self::default();
+        let mut on_previous_file = false;
+        for file in ALL_FILES {
+            let on_file = ours & get_file(file) != EMPTY;
+            if on_file && !on_previous_file {
+                structure.islands += 1;
+            }
+            on_previous_file = on_file;
+        }
+        for pawn in ours {
+            let file = get_file(pawn.get_file());
+            let ahead = ranks_ahead(pawn, color);
+            if ours & file & ahead != EMPTY {
+                structure.doubled += 1;
+            }
+            if ours & get_adjacent_files(pawn.get_file()) == EMPTY {
+                structure.isolated += 1;
+            }
+            if theirs & (file | get_adjacent_files(pawn.get_file())) & ahead == EMPTY {
+                structure.passed += 1;
+            }
+        }
+        structure
+    }
+}
+
+/// Returns the squares on the ranks in front of `square` as seen by
+/// `color`.
+fn ranks_ahead(square: Square, color: Color) -> BitBoard {
+    let rank = square.get_rank().to_index();
+    ALL_SQUARES
+        .into_iter()
+        .filter(|other| match color {
+            Color::White => other.get_rank().to_index() > rank,
+            Color::Black => other.get_rank().to_index() < rank,
+        })
+        .fold(EMPTY, |ahead, other| ahead | BitBoard::from_square(other))
+}
+
+/// Quick facts about one side of a position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SideReport {
+    /// Legal moves the side has, as if it were its turn, `None` when it
+    /// isn't and the side to move is in check.
+    pub legal_moves: Option<usize>,
+    /// Material in centipawns, see [`piece_value`], kings aside.
+    pub material: i32,
+    pub pawns: PawnStructure,
+    /// Pawns of the side on the three files around its king, one or two
+    /// ranks in front of it.
+    pub king_shield: u32,
+    /// Enemy pieces within two squares of the king, see [`king_attackers`].
+    pub king_attackers: u32,
+    pub in_check: bool,
+}
+
+impl SideReport {
+    fn new(board: &Board, color: Color) -> Self {
+        let material = ALL_PIECES[..5]
+            .iter()
+            .map(|piece| {
+                let count = (*board.pieces(*piece) & board.color_combined(color)).popcnt();
+                count as i32 * piece_value(*piece)
+            })
+            .sum();
+        let king = board.king_square(color);
+        let shield = (*board.pieces(Piece::Pawn) & board.color_combined(color))
+            .into_iter()
+            .filter(|pawn| {
+                let files = pawn
+                    .get_file()
+                    .to_index()
+                    .abs_diff(king.get_file().to_index());
+                let ranks = match color {
+                    Color::White => {
+                        pawn.get_rank().to_index() as i32 - king.get_rank().to_index() as i32
+                    }
+                    Color::Black => {
+                        king.get_rank().to_index() as i32 - pawn.get_rank().to_index() as i32
+                    }
+                };
+                files <= 1 && (1..=2).contains(&ranks)
+            })
+            .count() as u32;
+        Self {
+            legal_moves: legal_move_count(board, color),
+            material,
+            pawns: PawnStructure::new(board, color),
+            king_shield: shield,
+            king_attackers: king_attackers(board, color),
+            in_check: board.side_to_move() == color && *board.checkers() != EMPTY,
+        }
+    }
+}
+
+/// Quick facts about a position for both sides, built from the same
+/// components the engine's evaluation uses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionReport {
+    pub white: SideReport,
+    pub black: SideReport,
+    /// The engine's static evaluation in centipawns from White's point
+    /// of view, see [`evaluate`].
+    pub evaluation: i32,
+}
+
+impl PositionReport {
+    /// Reports on `board`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let report = PositionReport::new(&Board::default());
+    /// assert_eq!(report.side(Color::White).legal_moves, Some(20));
+    /// ```
+    pub fn new(board: &Board) -> Self {
+        Self {
+            white: SideReport::new(board, Color::White),
+            black: SideReport::new(board, Color::Black),
+            evaluation: evaluate(board, Color::White),
+        }
+    }
+
+    /// Returns the facts about the side of `color`.
+    pub fn side(&self, color: Color) -> &SideReport {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+}
+
+impl fmt::Display for PositionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<16}{:>7}{:>7}", "", "White", "Black")?;
+        let mut row = |name: &str, fact: &dyn Fn(&SideReport) -> String| {
+            writeln!(
+                f,
+                "{:<16}{:>7}{:>7}",
+                name,
+                fact(self.side(Color::White)),
+                fact(self.side(Color::Black))
+            )
+        };
+        row("Legal moves", &|side| {
+            side.legal_moves
+                .map_or("-".to_string(), |moves| moves.to_string())
+        })?;
+        row("Material", &|side| format!("{}", side.material / 100))?;
+        row("Pawn islands", &|side| side.pawns.islands.to_string())?;
+        row("Doubled pawns", &|side| side.pawns.doubled.to_string())?;
+        row("Isolated pawns", &|side| side.pawns.isolated.to_string())?;
+        row("Passed pawns", &|side| side.pawns.passed.to_string())?;
+        row("King shield", &|side| side.king_shield.to_string())?;
+        row("King attackers", &|side| side.king_attackers.to_string())?;
+        row("In check", &|side| {
+            if side.in_check { "yes" } else { "no" }.to_string()
+        })?;
+        writeln!(
+            f,
+            "Evaluation: {:+.2} for White",
+            f64::from(self.evaluation) / 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn reports_the_starting_position() {
+        let report = PositionReport::new(&Board::default());
+        for color in [Color::White, Color::Black] {
+            let side = report.side(color);
+            assert_eq!(side.legal_moves, Some(20));
+            assert_eq!(side.material, 3900);
+            assert_eq!(
+                side.pawns,
+                PawnStructure {
+                    islands: 1,
+                    ..PawnStructure::default()
+                }
+            );
+            assert_eq!(side.king_shield, 3);
+            assert_eq!(side.king_attackers, 0);
+        }
+        assert_eq!(report.evaluation, 0);
+        assert!(report.to_string().contains("Legal moves"));
+    }
+
+    #[test]
+    fn sums_up_pawn_structure_and_king_safety() {
+        // White's pawns are all isolated, the c pawns doubled and passed.
+        let board = Board::from_str("4k3/p5p1/8/8/2P5/2P4P/8/4K2r w - - 0 1").unwrap();
+        let white = PawnStructure::new(&board, Color::White);
+        assert_eq!(
+            white,
+            PawnStructure {
+                islands: 2,
+                doubled: 1,
+                isolated: 3,
+                passed: 2
+            }
+        );
+        let report = PositionReport::new(&board);
+        assert!(report.white.in_check);
+        assert_eq!(report.white.king_shield, 0);
+        assert_eq!(report.white.king_attackers, 0);
+        // Black can't be asked for its moves while White is in check.
+        assert_eq!(report.black.legal_moves, None);
+        assert_eq!(report.black.pawns.islands, 2);
+    }
+}
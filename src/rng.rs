@@ -0,0 +1,117 @@
+use rand::distr::uniform::{SampleRange, SampleUniform};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+
+thread_local! {
+    /// The seed set with [`set_seed`] and the generator it started,
+    /// `None` if randomness isn't seeded. Games are played on the main
+    /// thread; other threads making random choices for them are seeded
+    /// from it with [`fork_seed`].
+    static SEEDED: RefCell<Option<(u64, StdRng)>> = const { RefCell::new(None) };
+}
+
+/// Makes all later random choices follow from `seed`, so games against
+/// the AI can be replayed exactly.
+pub fn set_seed(seed: u64) {
+    SEEDED.set(Some((seed, StdRng::seed_from_u64(seed))));
+}
+
+/// Returns the seed set with [`set_seed`], if any.
+pub fn seed() -> Option<u64> {
+    SEEDED.with_borrow(|seeded| seeded.as_ref().map(|(seed, _)| *seed))
+}
+
+/// Draws a seed for another thread from the seeded generator, so the
+/// random choices made there still follow from the seed set with
+/// [`set_seed`].
+///
+/// # Returns
+///
+/// The seed to [`set_seed`] the other thread with, `None` if randomness
+/// isn't seeded.
+///
+/// # Example
+///
+/// ```
+/// let seed = fork_seed();
+/// thread::spawn(move || {
+///     if let Some(seed) = seed {
+///         set_seed(seed);
+///     }
+/// });
+/// ```
+pub fn fork_seed() -> Option<u64> {
+    SEEDED.with_borrow_mut(|seeded| seeded.as_mut().map(|(_, rng)| rng.random()))
+}
+
+/// Returns a random value in `range`, like [`rand::random_range`], from
+/// the seeded generator if there is one.
+///
+/// # Example
+///
+/// ```
+/// let index = random_range(0..moves.len());
+/// ```
+pub fn random_range<T, R>(range: R) -> T
+where
+    T: SampleUniform,
+    R: SampleRange<T>,
+{
+    SEEDED.with_borrow_mut(|seeded| match seeded {
+        Some((_, rng)) => rng.random_range(range),
+        None => rand::random_range(range),
+    })
+}
+
+/// Returns `true` with probability `p`, like [`rand::random_bool`], from
+/// the seeded generator if there is one.
+pub fn random_bool(p: f64) -> bool {
+    SEEDED.with_borrow_mut(|seeded| match seeded {
+        Some((_, rng)) => rng.random_bool(p),
+        None => rand::random_bool(p),
+    })
+}
+
+/// Returns a random number from a generator the operating system seeds,
+/// never from the one set with [`set_seed`], for secrets a known seed
+/// must not give away.
+pub fn secret() -> u128 {
+    rand::random()
+}
+
+/// Returns `N` numbers generated with SplitMix64 from `seed`, the same
+/// in every run, for hash keys that have to stay put between sessions.
+pub const fn fixed_keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut state = seed;
+    let mut index = 0;
+    while index < N {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[index] = z ^ (z >> 31);
+        index += 1;
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_repeats_choices() {
+        set_seed(42);
+        assert_eq!(seed(), Some(42));
+        let first: Vec<u32> = (0..10).map(|_| random_range(0..1000)).collect();
+        set_seed(42);
+        let second: Vec<u32> = (0..10).map(|_| random_range(0..1000)).collect();
+        assert_eq!(first, second);
+        set_seed(42);
+        let secret = secret();
+        set_seed(42);
+        assert_ne!(secret, super::secret());
+    }
+}
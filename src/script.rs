@@ -0,0 +1,138 @@
+use crate::error::ChessError;
+use crate::game::Game;
+use crate::legality::validate_fen;
+use crate::variant::Variant;
+use chess::ChessMove;
+use std::fmt;
+use std::str::FromStr;
+
+/// An error in a script, with the line it occurred on.
+#[derive(Debug, PartialEq)]
+pub struct ScriptError {
+    /// Line number, counting from 1.
+    pub line: usize,
+    pub error: ChessError,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// Plays a two-player game from a script and returns it.
+///
+/// Every line holds moves in SAN or UCI notation (move numbers are
+/// skipped) or one of the commands:
+///
+/// * `fen <FEN>` - starts a new game from the given position
+/// * `variant <name>` - starts a new game of the given variant
+/// * `restart` - starts the game over from its starting position
+/// * `undo` - takes back the last move
+///
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// # Errors
+///
+/// Stops at the first invalid command or move, returning it along
+/// with the line it's on.
+///
+/// # Example
+///
+/// ```
+/// let game = run_script("e4 e5\nNf3 Nc6\nundo").unwrap();
+/// assert_eq!(game.moves().len(), 3);
+/// ```
+pub fn run_script(script: &str) -> Result<Game, ScriptError> {
+    let mut game = Game::new_multi();
+    for (number, line) in script.lines().enumerate() {
+        let at_line = |error| ScriptError {
+            line: number + 1,
+            error,
+        };
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        match command {
+            "" => (),
+            _ if command.starts_with('#') => (),
+            "fen" => {
+                let board =
+                    validate_fen(argument).map_err(|e| at_line(ChessError::InvalidPosition(e)))?;
+                game = Game::new_multi()
+                    .with_variant(game.variant())
+                    .with_position(board);
+            }
+            "variant" => {
+                let variant = Variant::from_str(argument.trim())
+                    .map_err(|_| at_line(ChessError::InvalidInput))?;
+                let start = *game.starting_position();
+                game = Game::new_multi().with_variant(variant).with_position(start);
+            }
+            "restart" => game.reset(),
+            "undo" => game.undo().map_err(at_line)?,
+            _ => {
+                for token in line.split_whitespace() {
+                    let mv = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                    if mv.is_empty() {
+                        continue;
+                    }
+                    let uci = ChessMove::from_str(mv).is_ok();
+                    game.make_move_from_str(mv, uci).map_err(at_line)?;
+                }
+            }
+        }
+    }
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Status;
+    use crate::legality::IllegalMoveReason;
+    use chess::{Color, Piece, Square};
+
+    #[test]
+    fn plays_moves_and_commands() {
+        let game = run_script("# Fool's mate\n1. f3 e5\n2. g4 d7d6\nundo\n\nQh4\n").unwrap();
+        assert_eq!(game.status(), Status::Checkmate(Color::Black));
+        assert_eq!(
+            run_script("f3 e5 g4 Qh4\ne4").err(),
+            Some(ScriptError {
+                line: 2,
+                error: ChessError::GameOver,
+            })
+        );
+        let game = run_script("f3 e5 g4 Qh4\nrestart\ne4").unwrap();
+        assert_eq!(game.moves().len(), 1);
+    }
+
+    #[test]
+    fn starts_from_fen_and_variant() {
+        let script = "variant koth\nfen 8/8/8/8/8/3K4/8/4k3 b - - 0 1\nKd1\nKe4";
+        let game = run_script(script).unwrap();
+        assert_eq!(game.variant(), Variant::KingOfTheHill);
+        assert_eq!(game.status(), Status::VariantWin(Color::White));
+    }
+
+    #[test]
+    fn reports_failing_line() {
+        assert_eq!(
+            run_script("e4 e5\nKe3").err(),
+            Some(ScriptError {
+                line: 2,
+                error: ChessError::IllegalMove(IllegalMoveReason::NoPieceCanReach(
+                    Piece::King,
+                    Square::E3
+                )),
+            })
+        );
+        assert_eq!(run_script("undo").err().map(|e| e.line), Some(1));
+        assert_eq!(
+            run_script("e4\nfen nonsense").err().map(|e| e.line),
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,633 @@
+use crate::adjudication::{Adjudicated, Adjudication};
+use crate::ai::{baseline_move, Baseline, EngineKind, EngineOptions, SearchParams, MAX_SKILL};
+use crate::book::book_move;
+use crate::config::parse_string;
+use crate::drill::{Line, Repertoire};
+use crate::external::ExternalEngine;
+use crate::game::{Game, Status};
+use crate::personality::Personality;
+use crate::pgn::result_token;
+use crate::profile::Outcome;
+use crate::sprt::Sprt;
+use crate::strength::{StrengthLimit, MAX_ELO, MIN_ELO};
+use chess::{ChessMove, Color};
+use log::warn;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Deepest search tried when a side plays with a time per move.
+const MAX_TIMED_DEPTH: u32 = 20;
+
+/// Settings of one engine in an engine-vs-engine match.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerConfig {
+    pub engine: EngineKind,
+    /// Command starting an external UCI engine, see [`ExternalEngine`],
+    /// playing instead of the built-in one with the same depth or time
+    /// per move.
+    pub command: Option<String>,
+    /// Search depth, used when there's no time per move.
+    pub depth: u32,
+    /// Skill level the search depth is taken from instead of `depth`,
+    /// see [`EngineOptions::skill`].
+    pub skill: Option<u32>,
+    /// Time per move; the engine searches one ply deeper at a time
+    /// until half of it is used, since the next depth would likely take
+    /// longer than the rest.
+    pub move_time: Option<Duration>,
+    /// Evaluate with the NNUE network, if one is loaded, rather than the
+    /// hand-written evaluation.
+    pub network: bool,
+    /// Play moves from the opening book while the game is in it: the
+    /// configured `book_file` if there is one, then the built-in book.
+    pub book: bool,
+    pub personality: Personality,
+    /// Parameters of the search's pruning and reductions.
+    pub params: SearchParams,
+    /// Half-width in centipawns of the search's aspiration window, see
+    /// [`EngineOptions::aspiration_window`].
+    pub aspiration_window: Option<i32>,
+    /// Extend the search of checks, see
+    /// [`EngineOptions::check_extensions`].
+    pub check_extensions: bool,
+    /// Extend the search of singular moves, see
+    /// [`EngineOptions::singular_extensions`].
+    pub singular_extensions: bool,
+    /// Elo the engine is held back to, see [`StrengthLimit`], replacing
+    /// its depth and time per move.
+    pub elo: Option<u32>,
+    /// Baseline the side plays as instead of searching, to measure the
+    /// engine against.
+    pub baseline: Option<Baseline>,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            engine: EngineKind::default(),
+            command: None,
+            depth: 4,
+            skill: None,
+            move_time: None,
+            network: true,
+            book: true,
+            personality: Personality::default(),
+            params: SearchParams::default(),
+            aspiration_window: EngineOptions::default().aspiration_window,
+            check_extensions: EngineOptions::default().check_extensions,
+            singular_extensions: EngineOptions::default().singular_extensions,
+            elo: None,
+            baseline: None,
+        }
+    }
+}
+
+impl PlayerConfig {
+    /// Changes the setting `key` to `value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming an unknown setting or invalid value.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid {} `{}`", key, value);
+        match key {
+            "engine" => self.engine = EngineKind::from_str(value).map_err(|_| invalid())?,
+            "command" => self.command = Some(value.to_string()),
+            "depth" => self.depth = value.parse().map_err(|_| invalid())?,
+            "skill" => {
+                self.skill = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|skill| *skill <= MAX_SKILL)
+                        .ok_or_else(invalid)?,
+                )
+            }
+            "move_time" => {
+                let millis = value.parse().map_err(|_| invalid())?;
+                self.move_time = Some(Duration::from_millis(millis));
+            }
+            "evaluator" => {
+                self.network = match value {
+                    "nnue" => true,
+                    "classic" => false,
+                    _ => return Err(invalid()),
+                }
+            }
+            "book" => {
+                self.book = match value {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(invalid()),
+                }
+            }
+            "personality" => {
+                self.personality = Personality::from_str(value).map_err(|_| invalid())?
+            }
+            "null_move_reduction" => {
+                self.params.null_move_reduction = value.parse().map_err(|_| invalid())?
+            }
+            "lmr_full_depth_moves" => {
+                self.params.lmr_full_depth_moves = value.parse().map_err(|_| invalid())?
+            }
+            "lmr_min_depth" => self.params.lmr_min_depth = value.parse().map_err(|_| invalid())?,
+            "aspiration_window" => {
+                self.aspiration_window = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| invalid())?),
+                }
+            }
+            "check_extensions" | "singular_extensions" => {
+                let enabled = match value {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(invalid()),
+                };
+                if key == "check_extensions" {
+                    self.check_extensions = enabled;
+                } else {
+                    self.singular_extensions = enabled;
+                }
+            }
+            "elo" => {
+                self.elo = match value {
+                    "none" => None,
+                    _ => Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|elo| (MIN_ELO..=MAX_ELO).contains(elo))
+                            .ok_or_else(invalid)?,
+                    ),
+                }
+            }
+            "baseline" => {
+                self.baseline = match value {
+                    "none" => None,
+                    "random" => Some(Baseline::Random),
+                    "greedy" => Some(Baseline::Greedy),
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("unknown setting `{}`", key)),
+        }
+        Ok(())
+    }
+
+    /// Returns the engine options of this side.
+    fn engine_options(&self) -> EngineOptions {
+        let defaults = EngineOptions::default();
+        EngineOptions {
+            weights: self.personality.weights(),
+            contempt: defaults.contempt + self.personality.contempt(),
+            use_network: self.network,
+            params: self.params,
+            aspiration_window: self.aspiration_window,
+            check_extensions: self.check_extensions,
+            singular_extensions: self.singular_extensions,
+            skill: self.skill.unwrap_or(defaults.skill),
+            ..defaults
+        }
+    }
+
+    /// Returns the search depth of this side, from its skill level if
+    /// it has one.
+    fn search_depth(&self) -> u32 {
+        match self.skill {
+            Some(_) => self.engine_options().skill_depth(),
+            None => self.depth,
+        }
+    }
+
+    /// Picks this side's move in `game`.
+    ///
+    /// # Returns
+    ///
+    /// The move with its score in centipawns for the side to move,
+    /// `None` for a book move, or `None` if there are no legal moves.
+    pub fn choose_move(&self, game: &Game) -> Option<(ChessMove, Option<i32>)> {
+        if let Some(baseline) = self.baseline {
+            return baseline_move(game.board(), baseline).map(|mv| (mv, None));
+        }
+        if let Some(mv) = self.book_move(game) {
+            return Some((mv, None));
+        }
+        let options = EngineOptions {
+            history: game.position_history(),
+            ..self.engine_options()
+        };
+        let state = game.variant_state();
+        if let Some(elo) = self.elo {
+            let chosen = StrengthLimit::for_elo(elo).choose(game.board(), &state, &options);
+            return chosen.map(|(mv, score)| (mv, Some(score)));
+        }
+        let Some(move_time) = self.move_time else {
+            let result = self
+                .engine
+                .engine(self.search_depth(), options)
+                .search(game.board(), &state);
+            return result.best_move.map(|mv| (mv, Some(result.score)));
+        };
+        let started = Instant::now();
+        let mut best = None;
+        for depth in 1..=MAX_TIMED_DEPTH {
+            let result = self
+                .engine
+                .engine(depth, options.clone())
+                .search(game.board(), &state);
+            best = result.best_move.map(|mv| (mv, Some(result.score))).or(best);
+            if started.elapsed() * 2 >= move_time {
+                break;
+            }
+        }
+        best
+    }
+
+    /// Returns the move this side plays from the opening book in `game`,
+    /// if it uses the book and the game is still in it.
+    fn book_move(&self, game: &Game) -> Option<ChessMove> {
+        self.book
+            .then(|| {
+                book_move(game.board()).or_else(|| {
+                    self.personality
+                        .opening_book_move(game.starting_position(), game.moves())
+                })
+            })
+            .flatten()
+    }
+}
+
+/// How a game of a match ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ending {
+    /// The game was played to checkmate or stalemate.
+    Played,
+    /// The game reached [`MatchConfig::max_plies`], a draw.
+    MoveLimit,
+    Adjudicated(Adjudicated),
+    /// The external engine of the side of this color couldn't be
+    /// started, quit or played an illegal move, losing the game.
+    Forfeit(Color),
+}
+
+/// A game played in a match.
+pub struct MatchGame {
+    pub game: Game,
+    pub ending: Ending,
+    /// The result for engine A.
+    pub outcome: Outcome,
+}
+
+impl MatchGame {
+    /// Returns the result of the game, e.g. `1-0` or `1/2-1/2 (adjudicated)`.
+    pub fn result(&self) -> String {
+        match self.ending {
+            Ending::Played => result_token(self.game.status()).to_string(),
+            Ending::MoveLimit => "1/2-1/2 (move limit)".to_string(),
+            Ending::Adjudicated(Adjudicated::Win(Color::White)) => "1-0 (adjudicated)".to_string(),
+            Ending::Adjudicated(Adjudicated::Win(Color::Black)) => "0-1 (adjudicated)".to_string(),
+            Ending::Adjudicated(Adjudicated::Draw) => "1/2-1/2 (adjudicated)".to_string(),
+            Ending::Forfeit(Color::White) => "0-1 (forfeit)".to_string(),
+            Ending::Forfeit(Color::Black) => "1-0 (forfeit)".to_string(),
+        }
+    }
+}
+
+/// Settings of an engine-vs-engine match between two engines, `a` and
+/// `b`, which take turns playing White.
+///
+/// Match files hold `key = value` settings like the configuration file,
+/// with the settings of each engine in an `[a]` or `[b]` section, those
+/// of the SPRT, if the match should stop once it's decided, in a
+/// `[sprt]` section, and the rules ending games early in an
+/// `[adjudication]` section:
+///
+/// ```toml
+/// games = 20
+/// openings = "suites/balanced.epd"
+///
+/// [a]
+/// depth = 3
+///
+/// [b]
+/// engine = mcts
+/// move_time = 500
+///
+/// [sprt]
+/// elo1 = 10
+///
+/// [adjudication]
+/// win_score = 800
+/// win_moves = 4
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchConfig {
+    /// Number of games played, at most if there's a SPRT.
+    pub games: u32,
+    /// Positions the games start from, each played twice with colors
+    /// reversed, in turn; the standard starting position if empty.
+    pub openings: Vec<Line>,
+    /// Number of half-moves after which an unfinished game is scored as
+    /// a draw.
+    pub max_plies: u32,
+    pub a: PlayerConfig,
+    pub b: PlayerConfig,
+    /// Test stopping the match once A is shown to be stronger or not.
+    pub sprt: Option<Sprt>,
+    /// Rules ending games early; dead draws are always adjudicated.
+    pub adjudication: Adjudication,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            games: 2,
+            openings: Vec::new(),
+            max_plies: 200,
+            a: PlayerConfig::default(),
+            b: PlayerConfig::default(),
+            sprt: None,
+            adjudication: Adjudication::default(),
+        }
+    }
+}
+
+impl FromStr for MatchConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut config = MatchConfig::default();
+        let mut section = String::new();
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = format!("{}.", name.trim());
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+            let key = format!("{}{}", section, key.trim());
+            config
+                .set(&key, &parse_string(value.trim()))
+                .map_err(|e| format!("line {}: {}", number + 1, e))?;
+        }
+        Ok(config)
+    }
+}
+
+impl MatchConfig {
+    /// Changes the setting `key`: `games`, `openings`, the opening suite
+    /// read with [`load_openings`], `max_plies`, an engine's
+    /// setting prefixed with its name, e.g. `b.depth`, a setting of the
+    /// SPRT, e.g. `sprt.elo1`, which turns the SPRT on, or an
+    /// adjudication rule, e.g. `adjudication.win_score`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming an unknown setting or invalid value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut config = MatchConfig::default();
+    /// config.set("b.engine", "mcts")?;
+    /// ```
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("invalid {} `{}`", key, value);
+        match key.split_once('.') {
+            Some(("a", key)) => self.a.set(key, value),
+            Some(("b", key)) => self.b.set(key, value),
+            Some(("adjudication", key)) => self.adjudication.set(key, value),
+            Some(("sprt", key)) => self.sprt.get_or_insert_with(Sprt::default).set(key, value),
+            _ => {
+                match key {
+                    "games" => self.games = value.parse().map_err(|_| invalid())?,
+                    "openings" => self.openings = load_openings(Path::new(value))?,
+                    "max_plies" => self.max_plies = value.parse().map_err(|_| invalid())?,
+                    _ => return Err(format!("unknown setting `{}`", key)),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the index in [`MatchConfig::openings`] of the opening
+    /// game `number` (counted from 1) starts from, `None` without an
+    /// opening suite.
+    pub fn opening_index(&self, number: u32) -> Option<usize> {
+        (!self.openings.is_empty()).then(|| (number as usize - 1) / 2 % self.openings.len())
+    }
+
+    /// Plays one game of the match from `opening`, or the standard
+    /// starting position if there's none, with engine `a` playing White
+    /// if `a_white` is set.
+    ///
+    /// Games reaching [`MatchConfig::max_plies`] are draws, and games
+    /// are adjudicated by [`MatchConfig::adjudication`] as soon as they
+    /// can be. External engines are started for the game and lose it by
+    /// forfeit if they fail.
+    pub fn play_game(&self, a_white: bool, opening: Option<&Line>) -> MatchGame {
+        let a_color = if a_white { Color::White } else { Color::Black };
+        let mut game = Game::new_multi();
+        if let Some(opening) = opening {
+            game = game.with_position(opening.start);
+            for mv in &opening.moves {
+                if game.make_move(*mv).is_err() {
+                    break;
+                }
+            }
+        }
+        let mut external = [&self.a, &self.b].map(|side| {
+            side.command
+                .as_deref()
+                .map(|command| ExternalEngine::start(command).map_err(|e| (command, e)))
+        });
+        // The score of every move, from White's point of view.
+        let mut scores = Vec::new();
+        let ending = loop {
+            if game.status() != Status::Ongoing {
+                break Ending::Played;
+            }
+            if game.moves().len() >= self.max_plies as usize {
+                break Ending::MoveLimit;
+            }
+            if let Some(adjudicated) = self.adjudication.check(game.board(), &scores) {
+                break Ending::Adjudicated(adjudicated);
+            }
+            let (side, engine) = if game.turn() == a_color {
+                (&self.a, &mut external[0])
+            } else {
+                (&self.b, &mut external[1])
+            };
+            let chosen = match (side.book_move(&game), engine) {
+                (Some(mv), Some(Ok(_))) => Ok(Some((mv, None))),
+                (_, Some(Ok(engine))) => engine
+                    .search(&game, side.search_depth(), side.move_time)
+                    .map_err(|e| e.to_string()),
+                (_, Some(Err((command, e)))) => Err(format!("can't start `{}`: {}", command, e)),
+                (_, None) => Ok(side.choose_move(&game)),
+            };
+            let chosen = match chosen {
+                Ok(chosen) => chosen,
+                Err(e) => {
+                    warn!("{:?} forfeits: {}", game.turn(), e);
+                    break Ending::Forfeit(game.turn());
+                }
+            };
+            let Some((mv, score)) = chosen else {
+                break Ending::Played;
+            };
+            let sign = if game.turn() == Color::White { 1 } else { -1 };
+            scores.push(score.map(|score| sign * score));
+            if game.make_move(mv).is_err() {
+                break Ending::Played;
+            }
+        };
+        let winner = match (ending, game.status()) {
+            (
+                Ending::Played,
+                Status::Checkmate(winner)
+                | Status::VariantWin(winner)
+                | Status::Resignation(winner)
+                | Status::Timeout(winner),
+            ) => Some(winner),
+            (Ending::Adjudicated(Adjudicated::Win(winner)), _) => Some(winner),
+            (Ending::Forfeit(loser), _) => Some(!loser),
+            _ => None,
+        };
+        let outcome = match winner {
+            Some(winner) if winner == a_color => Outcome::Win,
+            Some(_) => Outcome::Loss,
+            None => Outcome::Draw,
+        };
+        MatchGame {
+            game,
+            ending,
+            outcome,
+        }
+    }
+}
+
+/// Reads an opening suite: an EPD file of start positions if its name
+/// ends in `.epd`, a PGN file of opening lines otherwise.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or has no valid openings.
+pub fn load_openings(path: &Path) -> Result<Vec<Line>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let suite = if path.extension().is_some_and(|extension| extension == "epd") {
+        Repertoire::from_epd(&text)
+    } else {
+        Repertoire::from_pgn(&text)
+    }
+    .map_err(|e| format!("{}: {}", path.display(), e))?;
+    if suite.lines.is_empty() {
+        return Err(format!("{}: no openings", path.display()));
+    }
+    Ok(suite.lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn reads_match_files() {
+        let config = MatchConfig::from_str(
+            "games = 10\n\n[a]\ndepth = 3\nbook = false\n\n\
+             [b]\nengine = mcts\nmove_time = 250\nevaluator = classic\n\
+             personality = \"gambit\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.games, 10);
+        assert_eq!(config.a.depth, 3);
+        assert!(!config.a.book);
+        assert_eq!(config.b.engine, EngineKind::Mcts);
+        assert_eq!(config.b.move_time, Some(Duration::from_millis(250)));
+        assert!(!config.b.network);
+        assert_eq!(config.b.personality, Personality::Gambit);
+        assert_eq!(config.b.depth, PlayerConfig::default().depth);
+        let mut tuned = MatchConfig::default();
+        tuned.set("a.lmr_min_depth", "4").unwrap();
+        tuned.set("a.aspiration_window", "none").unwrap();
+        assert_eq!(tuned.a.engine_options().params.lmr_min_depth, 4);
+        assert_eq!(tuned.a.engine_options().aspiration_window, None);
+        tuned.set("a.singular_extensions", "false").unwrap();
+        assert!(!tuned.a.engine_options().singular_extensions);
+        assert!(tuned.a.engine_options().check_extensions);
+        assert!(tuned.set("b.check_extensions", "maybe").is_err());
+        tuned.set("b.skill", "0").unwrap();
+        assert_eq!(tuned.b.search_depth(), 1);
+        assert!(tuned.set("b.skill", "21").is_err());
+        assert_eq!(
+            MatchConfig::from_str("[a]\ndepth = deep"),
+            Err("line 2: invalid depth `deep`".to_string())
+        );
+        assert!(MatchConfig::default().set("c.depth", "3").is_err());
+        tuned.set("a.elo", "1500").unwrap();
+        tuned.set("b.baseline", "greedy").unwrap();
+        assert_eq!(tuned.a.elo, Some(1500));
+        assert_eq!(tuned.b.baseline, Some(Baseline::Greedy));
+        assert!(tuned.set("a.elo", "5000").is_err());
+        assert_eq!(config.sprt, None);
+        let config = MatchConfig::from_str("[sprt]\nelo1 = 10").unwrap();
+        assert_eq!(config.sprt.map(|sprt| sprt.elo1), Some(10.0));
+    }
+
+    #[test]
+    fn plays_games() {
+        let mut config = MatchConfig::default();
+        config.set("max_plies", "6").unwrap();
+        config.set("a.depth", "1").unwrap();
+        config.set("b.depth", "1").unwrap();
+        let played = config.play_game(false, None);
+        assert_eq!(played.game.moves().len(), 6);
+        assert_eq!(played.ending, Ending::MoveLimit);
+        assert_eq!(played.outcome, Outcome::Draw);
+        assert_eq!(played.result(), "1/2-1/2 (move limit)");
+
+        // Both engines see the material count as level for two moves.
+        config.set("max_plies", "40").unwrap();
+        config.set("adjudication.draw_score", "0").unwrap();
+        config.set("adjudication.draw_moves", "2").unwrap();
+        config.set("a.book", "false").unwrap();
+        config.set("b.book", "false").unwrap();
+        let played = config.play_game(true, None);
+        assert_eq!(played.game.moves().len(), 4);
+        assert_eq!(played.ending, Ending::Adjudicated(Adjudicated::Draw));
+
+        config.set("b.command", "/nonexistent/engine").unwrap();
+        let played = config.play_game(false, None);
+        assert_eq!(played.ending, Ending::Forfeit(Color::White));
+        assert_eq!(played.outcome, Outcome::Win);
+        assert_eq!(played.result(), "0-1 (forfeit)");
+    }
+
+    #[test]
+    fn plays_opening_suites() {
+        let dir = TempDir::new("suite");
+        let path = dir.join("suite.pgn");
+        fs::write(&path, "1. e4 e5 *\n\n1. d4 d5 2. c4 *\n").unwrap();
+        let mut config = MatchConfig::default();
+        config.set("openings", path.to_str().unwrap()).unwrap();
+        assert_eq!(config.openings.len(), 2);
+        let indexes: Vec<_> = (1..=6).map(|number| config.opening_index(number)).collect();
+        assert_eq!(indexes, [0, 0, 1, 1, 0, 0].map(Some));
+
+        config.set("max_plies", "4").unwrap();
+        config.set("a.depth", "1").unwrap();
+        config.set("b.depth", "1").unwrap();
+        let played = config.play_game(true, Some(&config.openings[1]));
+        assert_eq!(played.game.moves().len(), 4);
+        assert_eq!(played.game.moves()[..3], config.openings[1].moves[..]);
+        assert!(config.set("openings", "/nonexistent/suite.epd").is_err());
+    }
+}
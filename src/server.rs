@@ -0,0 +1,602 @@
+use crate::ai::Difficulty;
+use crate::annotate::json_string;
+use crate::config::Config;
+use crate::game::{Game, Status};
+use crate::pgn::result_token;
+use chess::Color;
+use log::{info, warn};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Port the game server listens on unless another one is given.
+pub const DEFAULT_SERVER_PORT: u16 = 8080;
+/// How long a game is kept without requests unless set otherwise.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// Most games hosted at once unless set otherwise.
+pub const DEFAULT_SERVER_GAMES: usize = 64;
+/// Size in megabytes of each game's transposition table unless set
+/// otherwise.
+pub const DEFAULT_SERVER_HASH_MB: usize = 1;
+/// Largest request body read, to keep a client from exhausting memory.
+const MAX_BODY: usize = 64 * 1024;
+/// How long a client may take to send its request or read the answer
+/// before the connection is dropped.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+/// Number of connections served at once; more wait for a free worker.
+const WORKERS: usize = 8;
+/// How often games are checked for having been idle too long.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A request to the server: its method, path, query parameters and body.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    /// Returns the query parameter `name`, if given.
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Reads a request from `stream`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream fails or doesn't hold an HTTP
+    /// request.
+    fn read(stream: &TcpStream) -> io::Result<Self> {
+        let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, what.to_string());
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let mut words = line.split_whitespace();
+        let (Some(method), Some(target)) = (words.next(), words.next()) else {
+            return Err(invalid("not an HTTP request"));
+        };
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().map_err(|_| invalid("bad length"))?;
+                }
+            }
+        }
+        if length > MAX_BODY {
+            return Err(invalid("request too large"));
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        Ok(Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    (key.to_string(), value.to_string())
+                })
+                .collect(),
+            body: String::from_utf8_lossy(&body).into_owned(),
+        })
+    }
+}
+
+/// A response of the server: its HTTP status code and JSON body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn ok(body: String) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: format!("{{\"error\":{}}}", json_string(message)),
+        }
+    }
+
+    /// Writes the response to `stream` as HTTP, closing the connection.
+    fn write(&self, stream: &mut TcpStream) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason_phrase(self.status),
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
+/// Returns the reason phrase of the HTTP status code `status`, e.g.
+/// `Not Found` for 404.
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        408 => "Request Timeout",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => match status / 100 {
+            2 => "Success",
+            4 => "Client Error",
+            _ => "Server Error",
+        },
+    }
+}
+
+/// A game hosted by the server, with its own engine playing against the
+/// client.
+struct ServedGame {
+    game: Game,
+    player_color: Color,
+    last_used: Instant,
+}
+
+/// Hosts many games against the AI at once, each found by its ID, for
+/// clients talking JSON over HTTP:
+///
+/// * `GET /games` lists the games
+/// * `POST /games?color=white&difficulty=medium` starts one
+/// * `GET /games/<id>` shows one
+/// * `POST /games/<id>/moves` plays the move in the body, in UCI or
+///   SAN, and the AI's reply
+/// * `DELETE /games/<id>` ends one
+///
+/// Requests may come from several threads at once. Each game has a lock
+/// of its own, so the AI thinking in one game holds up only the
+/// requests to that game. At most a set number of games are hosted, and
+/// games no request has touched for the idle timeout are dropped.
+pub struct GameServer {
+    games: Mutex<BTreeMap<u64, Arc<Mutex<ServedGame>>>>,
+    next_id: AtomicU64,
+    idle_timeout: Duration,
+    /// Most games hosted at once.
+    max_games: usize,
+    /// Size in megabytes of the transposition table of each game.
+    hash_size: usize,
+}
+
+impl GameServer {
+    pub fn new(idle_timeout: Duration, max_games: usize, hash_size: usize) -> Self {
+        Self {
+            games: Mutex::new(BTreeMap::new()),
+            next_id: AtomicU64::new(1),
+            idle_timeout,
+            max_games,
+            hash_size,
+        }
+    }
+
+    /// Drops the games idle for longer than the timeout at `now`,
+    /// returning how many. Games busy answering a request are kept.
+    pub fn evict_idle(&self, now: Instant) -> usize {
+        let mut games = self.games.lock().unwrap();
+        let before = games.len();
+        let timeout = self.idle_timeout;
+        games.retain(|_, served| match served.try_lock() {
+            Ok(served) => now.duration_since(served.last_used) <= timeout,
+            Err(_) => true,
+        });
+        before - games.len()
+    }
+
+    /// Answers `request`, see [`GameServer`] for the endpoints.
+    pub fn handle(&self, request: &Request) -> Response {
+        let parts: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        match (request.method.as_str(), &parts[..]) {
+            ("GET", ["games"]) => {
+                // Games are locked one by one, after the list is let go.
+                let games: Vec<(u64, Arc<Mutex<ServedGame>>)> = self
+                    .games
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(id, served)| (*id, Arc::clone(served)))
+                    .collect();
+                let games: Vec<String> = games
+                    .iter()
+                    .map(|(id, served)| game_json(*id, &served.lock().unwrap()))
+                    .collect();
+                Response::ok(format!("{{\"games\":[{}]}}", games.join(",")))
+            }
+            ("POST", ["games"]) => self.create(request),
+            ("GET", ["games", id]) => {
+                self.with_game(id, |id, served| Response::ok(game_json(id, served)))
+            }
+            ("POST", ["games", id, "moves"]) => {
+                let input = request.param("move").unwrap_or(request.body.trim());
+                self.with_game(id, |id, served| match play(served, input) {
+                    Ok(()) => Response::ok(game_json(id, served)),
+                    Err(e) => Response::error(400, &e),
+                })
+            }
+            ("DELETE", ["games", id]) => {
+                let removed = id
+                    .parse()
+                    .ok()
+                    .and_then(|id| self.games.lock().unwrap().remove(&id));
+                match removed {
+                    Some(_) => Response::ok("{}".to_string()),
+                    None => Response::error(404, "no such game"),
+                }
+            }
+            _ => Response::error(404, "no such endpoint"),
+        }
+    }
+
+    /// Starts a game with the color and difficulty given in the query,
+    /// white and medium by default, the AI moving first if it's White.
+    fn create(&self, request: &Request) -> Response {
+        let color = match request.param("color").unwrap_or("white") {
+            "white" => Color::White,
+            "black" => Color::Black,
+            _ => return Response::error(400, "color must be white or black"),
+        };
+        let Ok(difficulty) = Difficulty::from_str(request.param("difficulty").unwrap_or("medium"))
+        else {
+            return Response::error(400, "unknown difficulty");
+        };
+        if self.is_full() {
+            return Response::error(503, "too many games");
+        }
+        let mut served = ServedGame {
+            game: Game::new_single(color, difficulty).with_hash(self.hash_size),
+            player_color: color,
+            last_used: Instant::now(),
+        };
+        if color == Color::Black {
+            if let Err(e) = reply(&mut served.game) {
+                return Response::error(400, &e);
+            }
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = game_json(id, &served);
+        let mut games = self.games.lock().unwrap();
+        // Checked again, as other games may have started meanwhile.
+        if games.len() >= self.max_games {
+            return Response::error(503, "too many games");
+        }
+        games.insert(id, Arc::new(Mutex::new(served)));
+        info!("Started game {}", id);
+        Response { status: 201, body }
+    }
+
+    /// Tells whether as many games as allowed are hosted.
+    fn is_full(&self) -> bool {
+        self.games.lock().unwrap().len() >= self.max_games
+    }
+
+    /// Answers with `answer` applied to the game with ID `id`, marking it
+    /// as used, or with an error if there's none. Only the game is locked
+    /// while `answer` runs, so other games can be played meanwhile.
+    fn with_game(
+        &self,
+        id: &str,
+        answer: impl FnOnce(u64, &mut ServedGame) -> Response,
+    ) -> Response {
+        let Some((id, served)) = id.parse().ok().and_then(|id| {
+            let games = self.games.lock().unwrap();
+            games.get(&id).map(|served| (id, Arc::clone(served)))
+        }) else {
+            return Response::error(404, "no such game");
+        };
+        let mut served = served.lock().unwrap();
+        served.last_used = Instant::now();
+        answer(id, &mut served)
+    }
+}
+
+/// Plays the client's move `input`, in UCI or SAN, and the AI's reply.
+fn play(served: &mut ServedGame, input: &str) -> Result<(), String> {
+    if served.game.turn() != served.player_color {
+        return Err("it's not your turn".to_string());
+    }
+    let game = &mut served.game;
+    game.make_move_from_str(input, true)
+        .or_else(|_| game.make_move_from_str(input, false))
+        .map_err(|e| e.to_string())?;
+    if game.status() == Status::Ongoing {
+        reply(game)?;
+    }
+    Ok(())
+}
+
+/// Plays the AI's move in `game`.
+fn reply(game: &mut Game) -> Result<(), String> {
+    let mv = game.get_ai_move().map_err(|e| e.to_string())?;
+    game.make_move(mv).map_err(|e| e.to_string())
+}
+
+/// Writes the game `served` with ID `id` as JSON: the position, the
+/// moves in UCI, the client's color and the result, `*` while ongoing.
+fn game_json(id: u64, served: &ServedGame) -> String {
+    let color = |color: Color| match color {
+        Color::White => "\"white\"",
+        Color::Black => "\"black\"",
+    };
+    let moves: Vec<String> = served
+        .game
+        .moves()
+        .iter()
+        .map(|mv| format!("\"{}\"", mv))
+        .collect();
+    format!(
+        "{{\"id\":{},\"fen\":{},\"turn\":{},\"player\":{},\"result\":\"{}\",\"moves\":[{}]}}",
+        id,
+        json_string(&served.game.fen()),
+        color(served.game.turn()),
+        color(served.player_color),
+        result_token(served.game.status()),
+        moves.join(",")
+    )
+}
+
+/// Runs the game server with the arguments of the `serve` command: the
+/// port and the idle timeout in minutes, both optional. How many games
+/// it hosts and the size of their tables are set in the configuration,
+/// see [`Config::server_games`].
+///
+/// The server speaks plain HTTP only, without WebSocket, and runs in
+/// the foreground; to keep it running in the background, start it from
+/// a service manager such as systemd.
+pub fn run(args: &[String]) {
+    let port = args
+        .first()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(DEFAULT_SERVER_PORT);
+    let idle_timeout = args
+        .get(1)
+        .and_then(|minutes| minutes.parse::<u64>().ok())
+        .map_or(DEFAULT_IDLE_TIMEOUT, |minutes| {
+            Duration::from_secs(minutes * 60)
+        });
+    let listener = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Error listening on port {}: {}", port, e);
+            return;
+        }
+    };
+    println!("Serving games on port {}", port);
+    let config = Config::load();
+    let server = GameServer::new(idle_timeout, config.server_games, config.server_hash_size);
+    serve(listener, Arc::new(server));
+}
+
+/// Answers the connections to `listener` with `server` on
+/// [`WORKERS`] threads, dropping idle games every
+/// [`EVICTION_INTERVAL`].
+fn serve(listener: TcpListener, server: Arc<GameServer>) {
+    thread::spawn({
+        let server = Arc::clone(&server);
+        move || loop {
+            thread::sleep(EVICTION_INTERVAL);
+            let evicted = server.evict_idle(Instant::now());
+            if evicted > 0 {
+                info!("Dropped {} idle games", evicted);
+            }
+        }
+    });
+    let (sender, receiver) = mpsc::channel::<TcpStream>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..WORKERS {
+        let server = Arc::clone(&server);
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || loop {
+            let Ok(stream) = receiver.lock().unwrap().recv() else {
+                break;
+            };
+            answer(&server, stream);
+        });
+    }
+    for stream in listener.incoming().flatten() {
+        if sender.send(stream).is_err() {
+            break;
+        }
+    }
+}
+
+/// Reads a request from `stream` and answers it with `server`, giving up
+/// on clients slower than [`IO_TIMEOUT`].
+fn answer(server: &GameServer, mut stream: TcpStream) {
+    let timeouts = stream
+        .set_read_timeout(Some(IO_TIMEOUT))
+        .and_then(|()| stream.set_write_timeout(Some(IO_TIMEOUT)));
+    if let Err(e) = timeouts {
+        warn!("Error setting up a connection: {}", e);
+        return;
+    }
+    let response = match Request::read(&stream) {
+        Ok(request) => server.handle(&request),
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ) =>
+        {
+            Response::error(408, "request not sent in time")
+        }
+        Err(e) => Response::error(400, &e.to_string()),
+    };
+    if let Err(e) = response.write(&mut stream) {
+        warn!("Error answering a request: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn hosts_several_games() {
+        let server = GameServer::new(Duration::from_secs(60), 4, 1);
+        let created = server.handle(&request("POST", "/games?difficulty=random", ""));
+        assert_eq!(created.status, 201);
+        assert!(created.body.starts_with("{\"id\":1,"));
+        let black = server.handle(&request("POST", "/games?color=black&difficulty=random", ""));
+        // The AI has already played its first move.
+        assert!(black.body.contains("\"turn\":\"black\""));
+        let played = server.handle(&request("POST", "/games/1/moves", "e4\n"));
+        assert_eq!(played.status, 200);
+        assert!(played.body.contains("\"moves\":[\"e2e4\","));
+        assert_eq!(
+            server
+                .handle(&request("POST", "/games/1/moves", "Qh8"))
+                .status,
+            400
+        );
+        let list = server.handle(&request("GET", "/games", ""));
+        assert!(list.body.contains("\"id\":2"));
+        assert_eq!(server.handle(&request("GET", "/games/3", "")).status, 404);
+        assert_eq!(
+            server.evict_idle(Instant::now() + Duration::from_secs(61)),
+            2
+        );
+        assert_eq!(
+            server.handle(&request("DELETE", "/games/1", "")).status,
+            404
+        );
+    }
+
+    #[test]
+    fn deletes_games() {
+        let server = GameServer::new(Duration::from_secs(60), 4, 1);
+        server.handle(&request("POST", "/games?difficulty=random", ""));
+        server.handle(&request("POST", "/games?difficulty=random", ""));
+        let deleted = server.handle(&request("DELETE", "/games/1", ""));
+        assert_eq!(deleted, Response::ok("{}".to_string()));
+        assert_eq!(server.handle(&request("GET", "/games/1", "")).status, 404);
+        assert_eq!(
+            server
+                .handle(&request("POST", "/games/1/moves", "e4"))
+                .status,
+            404
+        );
+        assert_eq!(
+            server.handle(&request("DELETE", "/games/1", "")).status,
+            404
+        );
+        let list = server.handle(&request("GET", "/games", ""));
+        assert!(list.body.starts_with("{\"games\":[{\"id\":2,"));
+    }
+
+    #[test]
+    fn evicts_only_idle_games() {
+        let server = GameServer::new(Duration::from_millis(50), 4, 1);
+        server.handle(&request("POST", "/games?difficulty=random", ""));
+        thread::sleep(Duration::from_millis(100));
+        server.handle(&request("POST", "/games?difficulty=random", ""));
+        assert_eq!(server.evict_idle(Instant::now()), 1);
+        assert_eq!(server.handle(&request("GET", "/games/1", "")).status, 404);
+        assert_eq!(server.handle(&request("GET", "/games/2", "")).status, 200);
+        // A game answering a request isn't dropped, however long it takes.
+        let busy = Arc::clone(&server.games.lock().unwrap()[&2]);
+        let _lock = busy.lock().unwrap();
+        assert_eq!(
+            server.evict_idle(Instant::now() + Duration::from_secs(60)),
+            0
+        );
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        let server = GameServer::new(Duration::from_secs(60), 4, 1);
+        let errors = [
+            ("POST", "/games?color=green", 400),
+            ("POST", "/games?difficulty=godlike", 400),
+            ("PUT", "/games", 404),
+            ("GET", "/games/first", 404),
+            ("GET", "/players", 404),
+        ];
+        for (method, path, status) in errors {
+            let response = server.handle(&request(method, path, ""));
+            assert_eq!(response.status, status, "{} {}", method, path);
+            assert!(response.body.starts_with("{\"error\":"));
+        }
+        server.handle(&request("POST", "/games?difficulty=random", ""));
+        let illegal = server.handle(&request("POST", "/games/1/moves", "e5"));
+        assert_eq!(illegal.status, 400);
+        assert!(server
+            .handle(&request("GET", "/games/1", ""))
+            .body
+            .contains("\"moves\":[]"));
+    }
+
+    #[test]
+    fn refuses_games_past_the_limit() {
+        let server = GameServer::new(Duration::from_secs(60), 1, 1);
+        let create = || server.handle(&request("POST", "/games?difficulty=random", ""));
+        assert_eq!(create().status, 201);
+        assert_eq!(create(), Response::error(503, "too many games"));
+        server.handle(&request("DELETE", "/games/1", ""));
+        assert_eq!(create().status, 201);
+    }
+
+    #[test]
+    fn names_statuses() {
+        assert_eq!(reason_phrase(201), "Created");
+        assert_eq!(reason_phrase(404), "Not Found");
+        assert_eq!(reason_phrase(408), "Request Timeout");
+        assert_eq!(reason_phrase(503), "Service Unavailable");
+        assert_eq!(reason_phrase(418), "Client Error");
+    }
+
+    #[test]
+    fn serves_clients_while_one_is_silent() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Arc::new(GameServer::new(Duration::from_secs(60), 4, 1));
+        thread::spawn(move || serve(listener, server));
+        let _silent = TcpStream::connect(address).unwrap();
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        client.write_all(b"GET /games HTTP/1.1\r\n\r\n").unwrap();
+        let mut answer = String::new();
+        client.read_to_string(&mut answer).unwrap();
+        assert!(answer.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(answer.ends_with("{\"games\":[]}"));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::config::Config;
+use crate::game::Game;
+use crate::notation::san_for_move;
+use crate::pgn::format_time;
+use crate::profile::{profiles_path, Outcome, ProfileStore};
+use chess::{Board, Color};
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// Number of half-moves a game's opening is told apart by.
+const OPENING_PLIES: usize = 4;
+
+thread_local! {
+    /// The session of the running program. Games are played on the main
+    /// thread, so that's the only one keeping one.
+    static CURRENT: RefCell<Session> = RefCell::new(Session::new());
+}
+
+/// A game finished during the session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionGame {
+    /// The player's result, `None` if they played both sides.
+    pub outcome: Option<Outcome>,
+    /// The player's accuracy in percent, if it was measured.
+    pub accuracy: Option<f64>,
+    /// The first moves, e.g. `1. e4 c5 2. Nf3 d6`, `None` if the game
+    /// didn't start from the starting position.
+    pub opening: Option<String>,
+}
+
+impl SessionGame {
+    /// Describes `game`, finished with the player playing `player`, or
+    /// both sides for `None`.
+    pub fn new(game: &Game, player: Option<Color>, accuracy: Option<f64>) -> Self {
+        Self {
+            outcome: player.and_then(|player| Outcome::from_status(game.status(), player)),
+            accuracy,
+            opening: opening_line(game),
+        }
+    }
+}
+
+/// Returns the first [`OPENING_PLIES`] moves of `game` in SAN with move
+/// numbers, if it started from the starting position.
+fn opening_line(game: &Game) -> Option<String> {
+    if *game.starting_position() != Board::default() || game.moves().is_empty() {
+        return None;
+    }
+    let mut board = Board::default();
+    let mut tokens = Vec::new();
+    for (ply, mv) in game.moves().iter().take(OPENING_PLIES).enumerate() {
+        if ply.is_multiple_of(2) {
+            tokens.push(format!("{}.", ply / 2 + 1));
+        }
+        tokens.push(san_for_move(&board, *mv));
+        board = board.make_move_new(*mv);
+    }
+    Some(tokens.join(" "))
+}
+
+/// The games played since the program started.
+#[derive(Clone, Debug)]
+pub struct Session {
+    started: Instant,
+    /// The name of the profile the player used, if any.
+    player: Option<String>,
+    games: Vec<SessionGame>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            player: None,
+            games: Vec::new(),
+        }
+    }
+
+    pub fn games(&self) -> &[SessionGame] {
+        &self.games
+    }
+
+    /// Returns the name of the profile the player used, if any.
+    pub fn player(&self) -> Option<&str> {
+        self.player.as_deref()
+    }
+
+    /// Returns the time since the session started.
+    pub fn duration(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Returns the player's average accuracy over the games it was
+    /// measured in, `None` if it wasn't measured.
+    pub fn accuracy(&self) -> Option<f64> {
+        let measured: Vec<f64> = self.games.iter().filter_map(|game| game.accuracy).collect();
+        (!measured.is_empty()).then(|| measured.iter().sum::<f64>() / measured.len() as f64)
+    }
+
+    /// Returns the opening played most often, the first one played among
+    /// equally frequent ones.
+    pub fn favorite_opening(&self) -> Option<&str> {
+        let openings: Vec<&str> = self
+            .games
+            .iter()
+            .filter_map(|game| game.opening.as_deref())
+            .collect();
+        let count = |opening: &str| openings.iter().filter(|o| **o == opening).count();
+        openings
+            .iter()
+            .copied()
+            .rev()
+            .max_by_key(|opening| count(opening))
+    }
+
+    /// Describes the session: games and results, accuracy, time spent
+    /// and favorite opening.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut session = Session::new();
+    /// session.add(SessionGame::new(&game, Some(Color::White), Some(90.0)));
+    /// print!("{}", session.summary());
+    /// ```
+    pub fn summary(&self) -> String {
+        let count = |outcome| {
+            self.games
+                .iter()
+                .filter(|game| game.outcome == Some(outcome))
+                .count()
+        };
+        let mut summary = format!(
+            "Session summary:\n  Games played: {} ({} won, {} drawn, {} lost)\n",
+            self.games.len(),
+            count(Outcome::Win),
+            count(Outcome::Draw),
+            count(Outcome::Loss)
+        );
+        if let Some(accuracy) = self.accuracy() {
+            summary.push_str(&format!("  Average accuracy: {:.1}%\n", accuracy));
+        }
+        summary.push_str(&format!("  Time spent: {}\n", format_time(self.duration())));
+        if let Some(opening) = self.favorite_opening() {
+            summary.push_str(&format!("  Favorite opening: {}\n", opening));
+        }
+        summary
+    }
+
+    pub fn add(&mut self, game: SessionGame) {
+        self.games.push(game);
+    }
+}
+
+/// Adds a finished game to the current session.
+pub fn record_game(game: SessionGame) {
+    CURRENT.with_borrow_mut(|session| session.add(game));
+}
+
+/// Sets the name of the profile the player uses in the current session.
+pub fn set_player(name: &str) {
+    CURRENT.with_borrow_mut(|session| session.player = Some(name.to_string()));
+}
+
+/// Returns a copy of the current session.
+pub fn current() -> Session {
+    CURRENT.with_borrow(Session::clone)
+}
+
+/// Ends the current session when the program exits: prints its summary
+/// if any games were played and adds it to the profile of the player,
+/// the one they chose in a game or else the `name` setting.
+pub fn finish() {
+    let session = current();
+    if session.games().is_empty() {
+        return;
+    }
+    print!("{}", session.summary());
+    let Some(name) = session.player().map(str::to_string).or(Config::load().name) else {
+        return;
+    };
+    let Some(path) = profiles_path() else {
+        return;
+    };
+    let mut store = match ProfileStore::load(&path) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("Error reading profiles: {}", e);
+            return;
+        }
+    };
+    store.get_or_create(&name).add_session(session.duration());
+    if let Err(e) = store.save(&path) {
+        eprintln!("Error saving profiles: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(moves: &[&str], resign: Option<Color>) -> Game {
+        let mut game = Game::new_multi();
+        for mv in moves {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        if let Some(color) = resign {
+            game.resign(color).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn summarizes_games() {
+        let mut session = Session::new();
+        let sicilian = game(&["e4", "c5", "Nf3", "d6", "d4"], Some(Color::Black));
+        session.add(SessionGame::new(&sicilian, Some(Color::White), Some(80.0)));
+        let french = game(&["e4", "e6"], Some(Color::White));
+        session.add(SessionGame::new(&french, Some(Color::White), None));
+        session.add(SessionGame::new(&sicilian, None, Some(90.0)));
+        assert_eq!(session.games()[1].opening.as_deref(), Some("1. e4 e6"));
+        assert_eq!(session.accuracy(), Some(85.0));
+        assert_eq!(session.favorite_opening(), Some("1. e4 c5 2. Nf3 d6"));
+        let summary = session.summary();
+        assert!(summary.contains("Games played: 3 (1 won, 0 drawn, 1 lost)"));
+        assert!(summary.contains("Average accuracy: 85.0%"));
+        assert!(summary.contains("Time spent: 0:00:00"));
+    }
+
+    #[test]
+    fn prefers_earlier_openings() {
+        let mut session = Session::new();
+        for moves in [&["d4"][..], &["e4"][..]] {
+            session.add(SessionGame::new(&game(moves, None), None, None));
+        }
+        assert_eq!(session.favorite_opening(), Some("1. d4"));
+        assert_eq!(session.summary().lines().count(), 4);
+    }
+}
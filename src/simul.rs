@@ -0,0 +1,200 @@
+use crate::error::ChessError;
+use crate::game::{Game, Status};
+use crate::profile::Outcome;
+use chess::Color;
+
+/// Number of boards of a simul unless another one is given.
+pub const DEFAULT_BOARDS: usize = 3;
+
+/// A simultaneous exhibition: one player against the AI on several
+/// boards at once, playing White on the odd-numbered boards and Black on
+/// the others.
+///
+/// Like in a real simul, the player goes from board to board: after
+/// their move the AI replies on that board and the player moves on to
+/// the next board where a game is still going on.
+pub struct Simul {
+    games: Vec<Game>,
+    /// Index of the board the player is at.
+    current: usize,
+}
+
+impl Simul {
+    /// Creates a simul on `boards` boards, the game on each created by
+    /// `new_game` given the color the player has on it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let simul = Simul::new(2, |color| Game::new_single(color, Difficulty::Easy));
+    /// assert_eq!(simul.player(1), Color::Black);
+    /// ```
+    pub fn new(boards: usize, new_game: impl Fn(Color) -> Game) -> Self {
+        Self {
+            games: (0..boards)
+                .map(|board| new_game(player_color(board)))
+                .collect(),
+            current: 0,
+        }
+    }
+
+    /// Returns the number of boards.
+    pub fn boards(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Returns the index of the board the player is at.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the game on the current board.
+    pub fn game(&self) -> &Game {
+        &self.games[self.current]
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.games[self.current]
+    }
+
+    /// Returns the games on all boards, in order.
+    pub fn games(&self) -> &[Game] {
+        &self.games
+    }
+
+    /// Returns the color the player has on `board`.
+    pub fn player(&self, board: usize) -> Color {
+        player_color(board)
+    }
+
+    /// Goes to board `number`, counted from 1 as shown to the player.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no such board.
+    pub fn switch(&mut self, number: usize) -> Result<(), ChessError> {
+        if number == 0 || number > self.games.len() {
+            return Err(ChessError::NoSuchBoard(number));
+        }
+        self.current = number - 1;
+        Ok(())
+    }
+
+    /// Plays the AI's move on the current board.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it's the player's turn or the game is over.
+    pub fn reply(&mut self) -> Result<(), ChessError> {
+        let game = self.game_mut();
+        let mv = game.get_ai_move()?;
+        game.make_move(mv)
+    }
+
+    /// Moves on to the next board, in order and starting over from the
+    /// first one, where the game is still going on.
+    ///
+    /// # Returns
+    ///
+    /// `false` if every game is over, leaving the player where they are.
+    pub fn next_board(&mut self) -> bool {
+        let boards = self.games.len();
+        let next = (1..=boards)
+            .map(|step| (self.current + step) % boards)
+            .find(|&board| self.games[board].status() == Status::Ongoing);
+        if let Some(board) = next {
+            self.current = board;
+        }
+        next.is_some()
+    }
+
+    /// Returns the player's result on `board`, `None` while the game
+    /// goes on.
+    pub fn outcome(&self, board: usize) -> Option<Outcome> {
+        Outcome::from_status(self.games[board].status(), self.player(board))
+    }
+
+    /// Returns whether the games on all boards are over.
+    pub fn is_over(&self) -> bool {
+        (0..self.boards()).all(|board| self.outcome(board).is_some())
+    }
+
+    /// Returns the player's points from the finished games.
+    pub fn score(&self) -> f64 {
+        (0..self.boards())
+            .filter_map(|board| self.outcome(board))
+            .map(Outcome::score)
+            .sum()
+    }
+
+    /// Describes the state of `board`, e.g. `Board 2 (Black): your move
+    /// (move 5)` or `Board 1 (White): won`.
+    pub fn describe(&self, board: usize) -> String {
+        let game = &self.games[board];
+        let state = match self.outcome(board) {
+            Some(Outcome::Win) => "won".to_string(),
+            Some(Outcome::Draw) => "drawn".to_string(),
+            Some(Outcome::Loss) => "lost".to_string(),
+            None => {
+                let waiting = if game.turn() == self.player(board) {
+                    "your move"
+                } else {
+                    "AI to move"
+                };
+                format!("{} (move {})", waiting, game.moves().len() / 2 + 1)
+            }
+        };
+        format!("Board {} ({:?}): {}", board + 1, self.player(board), state)
+    }
+}
+
+/// Returns the color the player has on `board`: White on the first,
+/// third, ... board as shown to the player.
+fn player_color(board: usize) -> Color {
+    if board.is_multiple_of(2) {
+        Color::White
+    } else {
+        Color::Black
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+
+    fn simul(boards: usize) -> Simul {
+        Simul::new(boards, |color| Game::new_single(color, Difficulty::Random))
+    }
+
+    #[test]
+    fn cycles_through_boards() {
+        let mut simul = simul(3);
+        assert_eq!(simul.player(0), Color::White);
+        assert_eq!(simul.describe(1), "Board 2 (Black): AI to move (move 1)");
+        simul.game_mut().make_move_from_str("e4", false).unwrap();
+        simul.reply().unwrap();
+        assert!(simul.next_board());
+        assert_eq!(simul.current(), 1);
+        simul.reply().unwrap();
+        assert_eq!(simul.reply(), Err(ChessError::NotAiTurn));
+        assert_eq!(simul.switch(4), Err(ChessError::NoSuchBoard(4)));
+        simul.switch(1).unwrap();
+        assert_eq!(simul.describe(0), "Board 1 (White): your move (move 2)");
+    }
+
+    #[test]
+    fn tracks_results() {
+        let mut simul = simul(2);
+        simul.game_mut().resign(Color::White).unwrap();
+        assert_eq!(simul.outcome(0), Some(Outcome::Loss));
+        assert_eq!(simul.describe(0), "Board 1 (White): lost");
+        assert!(!simul.is_over());
+        assert!(simul.next_board());
+        simul.game_mut().resign(Color::White).unwrap();
+        assert!(simul.is_over());
+        assert!(!simul.next_board());
+        assert_eq!(simul.current(), 1);
+        assert_eq!(simul.score(), 1.0);
+    }
+}
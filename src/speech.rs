@@ -0,0 +1,241 @@
+use crate::game::{Game, GameEvent};
+use crate::notation::san_for_move;
+use chess::{Board, ChessMove, Color, File, Rank, Square};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Returns the name of the piece written as `letter` in SAN.
+fn piece_name(letter: char) -> &'static str {
+    match letter {
+        'N' => "knight",
+        'B' => "bishop",
+        'R' => "rook",
+        'Q' => "queen",
+        'K' => "king",
+        _ => "pawn",
+    }
+}
+
+/// Describes a move played on `board` in words, e.g. "knight takes e5,
+/// check", for reading it out loud.
+///
+/// # Example
+///
+/// ```
+/// let mv = ChessMove::from_str("g1f3").unwrap();
+/// assert_eq!(spoken_move(&Board::default(), mv), "knight to f3");
+/// ```
+pub fn spoken_move(board: &Board, mv: ChessMove) -> String {
+    let san = san_for_move(board, mv);
+    let core = san.trim_end_matches(['+', '#']);
+    let mut words = match core {
+        "O-O" => "castles kingside".to_string(),
+        "O-O-O" => "castles queenside".to_string(),
+        _ => {
+            let (core, promotion) = match core.split_once('=') {
+                Some((core, piece)) => (core, piece.chars().next()),
+                None => (core, None),
+            };
+            let (piece, rest) = match core.chars().next() {
+                Some(letter) if letter.is_ascii_uppercase() => (piece_name(letter), &core[1..]),
+                _ => ("pawn", core),
+            };
+            let (from, action, to) = match rest.split_once('x') {
+                Some((from, to)) => (from, "takes", to),
+                None => (&rest[..rest.len() - 2], "to", &rest[rest.len() - 2..]),
+            };
+            let mut words = piece.to_string();
+            if !from.is_empty() {
+                words.push(' ');
+                words.push_str(from);
+            }
+            words.push_str(&format!(" {} {}", action, to));
+            if let Some(letter) = promotion {
+                words.push_str(&format!(" promoting to {}", piece_name(letter)));
+            }
+            words
+        }
+    };
+    if san.ends_with('#') {
+        words.push_str(", checkmate");
+    } else if san.ends_with('+') {
+        words.push_str(", check");
+    }
+    words
+}
+
+/// Runs `command` with `text` written to its standard input.
+///
+/// The command is split on whitespace into the program and its
+/// arguments and runs in the background, so a slow speech synthesizer
+/// doesn't hold up the game.
+pub fn run_hook(command: &str, text: String) {
+    let mut parts = command.split_whitespace();
+    let Some(program) = parts.next() else {
+        return;
+    };
+    let spawned = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn();
+    match spawned {
+        Ok(mut child) => {
+            thread::spawn(move || {
+                if let Some(mut stdin) = child.stdin.take() {
+                    let _ = writeln!(stdin, "{}", text);
+                }
+                let _ = child.wait();
+            });
+        }
+        Err(e) => eprintln!("Error running {}: {}", program, e),
+    }
+}
+
+/// Returns a game listener passing every move of `game`, described by
+/// [`spoken_move`], to `output` along with the color that played it.
+pub fn describe_moves(
+    game: &Game,
+    output: impl FnMut(Color, String) + 'static,
+) -> impl FnMut(&GameEvent) {
+    notate_moves(game, spoken_move, output)
+}
+
+/// Returns a game listener passing every move of `game`, written by
+/// `notation` from the position it was played in, to `output` along
+/// with the color that played it.
+pub fn notate_moves(
+    game: &Game,
+    notation: fn(&Board, ChessMove) -> String,
+    mut output: impl FnMut(Color, String) + 'static,
+) -> impl FnMut(&GameEvent) {
+    // The positions before each move, to describe moves and take them back.
+    let mut boards = vec![*game.starting_position()];
+    for mv in game.moves() {
+        boards.push(boards.last().unwrap().make_move_new(*mv));
+    }
+    move |event| match *event {
+        GameEvent::MoveMade(color, mv) => {
+            let board = *boards.last().unwrap();
+            output(color, notation(&board, mv));
+            boards.push(board.make_move_new(mv));
+        }
+        GameEvent::MoveUndone(_) if boards.len() > 1 => {
+            boards.pop();
+        }
+        GameEvent::Reset => boards.truncate(1),
+        _ => (),
+    }
+}
+
+/// Returns a game listener passing every move of `game` in words to the
+/// external `command`, e.g. a speech synthesizer or a streaming overlay.
+pub fn move_hook(command: String, game: &Game) -> impl FnMut(&GameEvent) {
+    describe_moves(game, move |_, text| run_hook(&command, text))
+}
+
+/// Describes the pieces on `board` rank by rank, from the 8th to the
+/// 1st, e.g. "Rank 8: black rook a8, black king e8".
+pub fn describe_board(board: &Board) -> String {
+    let mut description = String::new();
+    for rank in (0..8).rev() {
+        let pieces: Vec<String> = (0..8)
+            .filter_map(|file| {
+                let square = Square::make_square(Rank::from_index(rank), File::from_index(file));
+                let piece = board.piece_on(square)?;
+                let color = match board.color_on(square)? {
+                    Color::White => "white",
+                    Color::Black => "black",
+                };
+                let name = format!("{:?}", piece).to_lowercase();
+                Some(format!("{} {} {}", color, name, square))
+            })
+            .collect();
+        let pieces = if pieces.is_empty() {
+            "empty".to_string()
+        } else {
+            pieces.join(", ")
+        };
+        description.push_str(&format!("Rank {}: {}\n", rank + 1, pieces));
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    fn spoken(fen: &str, uci: &str) -> String {
+        spoken_move(
+            &Board::from_str(fen).unwrap(),
+            ChessMove::from_str(uci).unwrap(),
+        )
+    }
+
+    #[test]
+    fn describes_moves_in_words() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(spoken(start, "e2e4"), "pawn to e4");
+        assert_eq!(spoken(start, "b1c3"), "knight to c3");
+        assert_eq!(
+            spoken("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5"),
+            "pawn e takes d5"
+        );
+        assert_eq!(
+            spoken("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "a1d1"),
+            "rook a to d1"
+        );
+        assert_eq!(
+            spoken("r3k3/8/8/8/8/8/8/R3K3 w Qq - 0 1", "e1c1"),
+            "castles queenside"
+        );
+        assert_eq!(
+            spoken("7k/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"),
+            "pawn to b8 promoting to queen, check"
+        );
+        assert_eq!(
+            spoken("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"),
+            "rook to a8, checkmate"
+        );
+    }
+
+    #[test]
+    fn notates_moves_as_played() {
+        let mut game = Game::new_multi();
+        let played = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&played);
+        game.subscribe(notate_moves(&game, san_for_move, move |color, text| {
+            log.lock().unwrap().push(format!("{:?} {}", color, text))
+        }));
+        for mv in ["e4", "e5", "Qh5", "Nc6", "Bc4", "d6"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        game.undo().unwrap();
+        game.make_move_from_str("Nf6", false).unwrap();
+        game.make_move_from_str("Qxf7", false).unwrap();
+        game.reset();
+        for mv in ["e4", "e5", "Qh5"] {
+            game.make_move_from_str(mv, false).unwrap();
+        }
+        let played = played.lock().unwrap();
+        assert_eq!(played[5], "Black d6");
+        assert_eq!(played[6], "Black Nf6");
+        assert_eq!(played[7], "White Qxf7#");
+        assert_eq!(played[10], "White Qh5");
+    }
+
+    #[test]
+    fn describes_board_by_rank() {
+        let board = Board::from_str("4k3/8/8/8/8/8/4P3/R3K3 w Q - 0 1").unwrap();
+        let description = describe_board(&board);
+        let ranks: Vec<&str> = description.lines().collect();
+        assert_eq!(ranks.len(), 8);
+        assert_eq!(ranks[0], "Rank 8: black king e8");
+        assert_eq!(ranks[1], "Rank 7: empty");
+        assert_eq!(ranks[6], "Rank 2: white pawn e2");
+        assert_eq!(ranks[7], "Rank 1: white rook a1, white king e1");
+    }
+}
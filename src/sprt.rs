@@ -0,0 +1,147 @@
+/// A sequential probability ratio test of whether engine A is stronger
+/// than engine B, deciding between the hypotheses that A is `elo0`
+/// points stronger (H0) and that it's `elo1` points stronger (H1).
+///
+/// The log-likelihood ratio is the normal approximation of the
+/// trinomial GSPRT used by engine testing frameworks: each game scores
+/// 1, ½ or 0, and the test stops once the ratio leaves the bounds set by
+/// the error rates `alpha` and `beta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// Chance of accepting H1 when H0 holds.
+    pub alpha: f64,
+    /// Chance of accepting H0 when H1 holds.
+    pub beta: f64,
+}
+
+/// The state of a [`Sprt`] after some games.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SprtResult {
+    /// More games are needed.
+    Continue,
+    /// A is more likely `elo0` than `elo1` points stronger than B.
+    AcceptH0,
+    /// A is more likely `elo1` than `elo0` points stronger than B.
+    AcceptH1,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Self {
+            elo0: 0.0,
+            elo1: 5.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+/// Returns the expected score of a player `elo` points stronger than
+/// their opponent.
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+impl Sprt {
+    /// Changes the setting `key`: `elo0`, `elo1`, `alpha` or `beta`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming an unknown setting or invalid value.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let number: f64 = value
+            .parse()
+            .map_err(|_| format!("invalid {} `{}`", key, value))?;
+        match key {
+            "elo0" => self.elo0 = number,
+            "elo1" => self.elo1 = number,
+            "alpha" if number > 0.0 && number < 1.0 => self.alpha = number,
+            "beta" if number > 0.0 && number < 1.0 => self.beta = number,
+            "alpha" | "beta" => return Err(format!("{} must be between 0 and 1", key)),
+            _ => return Err(format!("unknown setting `{}`", key)),
+        }
+        Ok(())
+    }
+
+    /// Returns the lower and upper bounds of the log-likelihood ratio,
+    /// where H0 and H1 are accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Returns the log-likelihood ratio of H1 against H0 after A scored
+    /// `wins`, `draws` and `losses`.
+    ///
+    /// Half a game of each result is added, so a run of identical
+    /// results, which has no variance, still moves the ratio.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let sprt = Sprt::default();
+    /// println!("LLR {:.2}", sprt.llr(60, 30, 40));
+    /// ```
+    pub fn llr(&self, wins: u32, draws: u32, losses: u32) -> f64 {
+        if wins + draws + losses == 0 {
+            return 0.0;
+        }
+        let (wins, draws, losses) = (wins as f64 + 0.5, draws as f64 + 0.5, losses as f64 + 0.5);
+        let games = wins + draws + losses;
+        let score = (wins + draws / 2.0) / games;
+        let variance =
+            (wins * (1.0 - score).powi(2) + draws * (0.5 - score).powi(2) + losses * score.powi(2))
+                / games;
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+        games * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+    }
+
+    /// Decides the test after A scored `wins`, `draws` and `losses`.
+    pub fn result(&self, wins: u32, draws: u32, losses: u32) -> SprtResult {
+        let llr = self.llr(wins, draws, losses);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtResult::AcceptH1
+        } else if llr <= lower {
+            SprtResult::AcceptH0
+        } else {
+            SprtResult::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_llr() {
+        let sprt = Sprt {
+            elo1: 10.0,
+            ..Sprt::default()
+        };
+        let (lower, upper) = sprt.bounds();
+        assert!((lower + 2.944).abs() < 0.001 && (upper - 2.944).abs() < 0.001);
+        assert!((sprt.llr(60, 30, 40) - 0.700).abs() < 0.001);
+        assert!((sprt.llr(40, 30, 60) + 0.846).abs() < 0.001);
+        assert_eq!(sprt.llr(0, 0, 0), 0.0);
+        assert!(sprt.llr(0, 10, 0) < 0.0);
+        assert_eq!(sprt.result(30, 0, 0), SprtResult::AcceptH1);
+        assert_eq!(sprt.result(60, 30, 40), SprtResult::Continue);
+        assert_eq!(sprt.result(600, 300, 400), SprtResult::AcceptH1);
+        assert_eq!(sprt.result(400, 300, 600), SprtResult::AcceptH0);
+    }
+
+    #[test]
+    fn reads_settings() {
+        let mut sprt = Sprt::default();
+        sprt.set("elo1", "2.5").unwrap();
+        assert_eq!(sprt.elo1, 2.5);
+        assert!(sprt.set("alpha", "1.5").is_err());
+        assert!(sprt.set("gamma", "0.1").is_err());
+    }
+}
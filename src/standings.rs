@@ -0,0 +1,223 @@
+use crate::game::Status;
+use chess::Color;
+use std::fmt;
+
+/// Score of two players over a series of games, kept while they swap
+/// colors from one game to the next.
+///
+/// The series is either open-ended, going on as long as the players
+/// want rematches, or a best-of-N match.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standings {
+    names: [String; 2],
+    /// Color of the first player in the current game.
+    first_color: Color,
+    /// Games won by each player.
+    wins: [u32; 2],
+    draws: u32,
+    /// Number of games of a match, `None` for an open-ended series.
+    best_of: Option<u32>,
+}
+
+impl Standings {
+    /// Creates the standings of `first` and `second` before their first
+    /// game, in which `first` plays `first_color`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut standings = Standings::new("Anna", "AI", Color::White);
+    /// standings.record(Status::Checkmate(Color::White));
+    /// assert_eq!(standings.to_string(), "Anna 1 - 0 AI");
+    /// ```
+    pub fn new(first: &str, second: &str, first_color: Color) -> Self {
+        Self {
+            names: [first.to_string(), second.to_string()],
+            first_color,
+            wins: [0; 2],
+            draws: 0,
+            best_of: None,
+        }
+    }
+
+    /// Makes the series a match over at most `games` games, or an
+    /// open-ended one for `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut standings = Standings::new("Anna", "AI", Color::White).with_best_of(Some(3));
+    /// standings.record(Status::Checkmate(Color::White));
+    /// standings.swap_colors();
+    /// standings.record(Status::Checkmate(Color::Black));
+    /// assert!(standings.is_decided());
+    /// assert_eq!(standings.leader(), Some("Anna"));
+    /// ```
+    pub fn with_best_of(mut self, games: Option<u32>) -> Self {
+        self.best_of = games;
+        self
+    }
+
+    /// Returns the number of games of the match, `None` for an
+    /// open-ended series.
+    pub fn best_of(&self) -> Option<u32> {
+        self.best_of
+    }
+
+    /// Counts the result of the current game, if it's over.
+    pub fn record(&mut self, status: Status) {
+        match status {
+            Status::Ongoing => (),
+            Status::Stalemate | Status::DrawAgreed | Status::TimeoutDraw(_) => self.draws += 1,
+            Status::Checkmate(winner)
+            | Status::VariantWin(winner)
+            | Status::Resignation(winner)
+            | Status::Timeout(winner) => self.wins[self.player(winner)] += 1,
+        }
+    }
+
+    /// Swaps the players' colors for the next game.
+    pub fn swap_colors(&mut self) {
+        self.first_color = !self.first_color;
+    }
+
+    /// Returns the color the first (0) or second (1) player has in the
+    /// current game.
+    pub fn color(&self, player: usize) -> Color {
+        if player == 0 {
+            self.first_color
+        } else {
+            !self.first_color
+        }
+    }
+
+    /// Returns the points of the first (0) or second (1) player: 1 per
+    /// win and ½ per draw.
+    pub fn points(&self, player: usize) -> f64 {
+        f64::from(self.wins[player]) + f64::from(self.draws) / 2.0
+    }
+
+    /// Returns the number of games recorded so far.
+    pub fn games(&self) -> u32 {
+        self.wins[0] + self.wins[1] + self.draws
+    }
+
+    /// Returns whether the match is over: all its games were played or
+    /// a player has more than half of the points, which the other can no
+    /// longer catch up with. An open-ended series is never decided.
+    pub fn is_decided(&self) -> bool {
+        let Some(games) = self.best_of else {
+            return false;
+        };
+        let majority = f64::from(games) / 2.0;
+        self.games() >= games || self.points(0) > majority || self.points(1) > majority
+    }
+
+    /// Returns the name of the player ahead on points, `None` while
+    /// they're level.
+    pub fn leader(&self) -> Option<&str> {
+        let (first, second) = (self.points(0), self.points(1));
+        if first > second {
+            Some(&self.names[0])
+        } else if second > first {
+            Some(&self.names[1])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the PGN tags of the current game as a round of the match:
+    /// the event, round number and the players of each color.
+    ///
+    /// The round is the number of games recorded so far, so the tags
+    /// of a finished game are taken after [`Standings::record`] and
+    /// before the colors are swapped.
+    pub fn round_tags(&self) -> Vec<(&'static str, String)> {
+        let name = |color| self.names[self.player(color)].clone();
+        vec![
+            (
+                "Event",
+                format!("{} vs {} match", self.names[0], self.names[1]),
+            ),
+            ("Round", self.games().to_string()),
+            ("White", name(Color::White)),
+            ("Black", name(Color::Black)),
+        ]
+    }
+
+    /// Returns the index of the player playing `color` in the current game.
+    fn player(&self, color: Color) -> usize {
+        if color == self.first_color {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+impl fmt::Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} - {} {}",
+            self.names[0],
+            self.points(0),
+            self.points(1),
+            self.names[1]
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_players_across_colors() {
+        let mut standings = Standings::new("Player 1", "Player 2", Color::White);
+        standings.record(Status::Checkmate(Color::White));
+        standings.swap_colors();
+        standings.record(Status::Resignation(Color::White));
+        standings.swap_colors();
+        standings.record(Status::DrawAgreed);
+        standings.record(Status::Ongoing);
+        assert_eq!(standings.color(1), Color::Black);
+        assert_eq!(standings.points(0), 1.5);
+        assert_eq!(standings.to_string(), "Player 1 1.5 - 1.5 Player 2");
+        assert_eq!(standings.leader(), None);
+        assert!(!standings.is_decided());
+    }
+
+    #[test]
+    fn decides_matches() {
+        let mut standings = Standings::new("Anna", "AI", Color::Black).with_best_of(Some(4));
+        standings.record(Status::Checkmate(Color::Black));
+        standings.swap_colors();
+        standings.record(Status::Stalemate);
+        assert_eq!(
+            standings.round_tags(),
+            vec![
+                ("Event", "Anna vs AI match".to_string()),
+                ("Round", "2".to_string()),
+                ("White", "Anna".to_string()),
+                ("Black", "AI".to_string()),
+            ]
+        );
+        // 1.5 - 0.5 can still be caught up with in two games.
+        assert!(!standings.is_decided());
+        standings.swap_colors();
+        standings.record(Status::Resignation(Color::Black));
+        // The last game can't change the result.
+        assert!(standings.is_decided());
+        assert_eq!(standings.leader(), Some("Anna"));
+
+        // A drawn match ends once all its games are played.
+        let mut standings = Standings::new("Anna", "AI", Color::White).with_best_of(Some(2));
+        standings.record(Status::Checkmate(Color::White));
+        assert!(!standings.is_decided());
+        standings.swap_colors();
+        standings.record(Status::Checkmate(Color::White));
+        assert!(standings.is_decided());
+        assert_eq!(standings.leader(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::pgn::PgnGame;
+use std::collections::HashMap;
+
+/// Score of one player across the games of a database.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerScore {
+    pub name: String,
+    pub games: u32,
+    /// Points scored: 1 per win, 0.5 per draw.
+    pub points: f64,
+}
+
+/// Aggregate statistics of a PGN database.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DatabaseStats {
+    pub games: u32,
+    pub white_wins: u32,
+    pub black_wins: u32,
+    pub draws: u32,
+    /// Games without a decisive result or draw, e.g. marked with `*`.
+    pub unfinished: u32,
+    /// Openings by ECO code with their number of games, most common first.
+    pub openings: Vec<(String, u32)>,
+    /// Average number of full moves per game.
+    pub average_length: f64,
+    /// Players with their scores, by number of games played.
+    pub players: Vec<PlayerScore>,
+}
+
+impl DatabaseStats {
+    /// Computes the statistics of the given games.
+    ///
+    /// Openings are grouped by the `ECO` tag, players by the `White`
+    /// and `Black` tags; games missing these tags are left out of the
+    /// respective table.
+    pub fn from_games(games: &[PgnGame]) -> Self {
+        let mut stats = DatabaseStats {
+            games: games.len() as u32,
+            ..Default::default()
+        };
+        let mut openings: HashMap<&str, u32> = HashMap::new();
+        let mut players: HashMap<&str, PlayerScore> = HashMap::new();
+        let mut plies = 0;
+
+        for game in games {
+            plies += game.moves.len();
+            let (white_points, black_points) = match game.result() {
+                Some("1-0") => {
+                    stats.white_wins += 1;
+                    (1.0, 0.0)
+                }
+                Some("0-1") => {
+                    stats.black_wins += 1;
+                    (0.0, 1.0)
+                }
+                Some("1/2-1/2") => {
+                    stats.draws += 1;
+                    (0.5, 0.5)
+                }
+                _ => {
+                    stats.unfinished += 1;
+                    (0.0, 0.0)
+                }
+            };
+
+            if let Some(eco) = game.tag("ECO") {
+                *openings.entry(eco).or_default() += 1;
+            }
+            for (tag, points) in [("White", white_points), ("Black", black_points)] {
+                if let Some(name) = game.tag(tag) {
+                    let player = players.entry(name).or_insert_with(|| PlayerScore {
+                        name: name.to_string(),
+                        ..Default::default()
+                    });
+                    player.games += 1;
+                    player.points += points;
+                }
+            }
+        }
+
+        if !games.is_empty() {
+            stats.average_length = plies as f64 / 2.0 / games.len() as f64;
+        }
+        stats.openings = openings
+            .into_iter()
+            .map(|(eco, count)| (eco.to_string(), count))
+            .collect();
+        stats
+            .openings
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats.players = players.into_values().collect();
+        stats
+            .players
+            .sort_by(|a, b| b.games.cmp(&a.games).then_with(|| a.name.cmp(&b.name)));
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::parse_pgn;
+
+    const DATABASE: &str = r#"
+[White "Anna"]
+[Black "Bob"]
+[ECO "C20"]
+[Result "1-0"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+
+[White "Bob"]
+[Black "Anna"]
+[ECO "C20"]
+[Result "1/2-1/2"]
+
+1. e4 e5 1/2-1/2
+
+[White "Carl"]
+[Black "Anna"]
+[ECO "A00"]
+[Result "0-1"]
+
+1. g4 e5 2. f3 Qh4# 0-1
+
+[White "Carl"]
+[Black "Bob"]
+
+1. d4 *
+"#;
+
+    #[test]
+    fn counts_results_and_length() {
+        let stats = DatabaseStats::from_games(&parse_pgn(DATABASE));
+        assert_eq!(stats.games, 4);
+        assert_eq!(stats.white_wins, 1);
+        assert_eq!(stats.black_wins, 1);
+        assert_eq!(stats.draws, 1);
+        assert_eq!(stats.unfinished, 1);
+        // 7 + 2 + 4 + 1 plies over 4 games
+        assert_eq!(stats.average_length, 1.75);
+    }
+
+    #[test]
+    fn ranks_openings_and_players() {
+        let stats = DatabaseStats::from_games(&parse_pgn(DATABASE));
+        assert_eq!(
+            stats.openings,
+            [("C20".to_string(), 2), ("A00".to_string(), 1)]
+        );
+        let anna = &stats.players[0];
+        assert_eq!(
+            (anna.name.as_str(), anna.games, anna.points),
+            ("Anna", 3, 2.5)
+        );
+        let bob = &stats.players[1];
+        assert_eq!((bob.name.as_str(), bob.games, bob.points), ("Bob", 3, 0.5));
+        let carl = &stats.players[2];
+        assert_eq!(
+            (carl.name.as_str(), carl.games, carl.points),
+            ("Carl", 2, 0.0)
+        );
+    }
+
+    #[test]
+    fn empty_database() {
+        assert_eq!(DatabaseStats::from_games(&[]), DatabaseStats::default());
+    }
+}
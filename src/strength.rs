@@ -0,0 +1,175 @@
+use crate::ai::{score_moves, BlunderModel, EngineOptions, SearchLimits};
+use crate::variant::VariantState;
+use chess::{Board, ChessMove};
+
+/// Weakest Elo the AI can be limited to.
+pub const MIN_ELO: u32 = 500;
+/// Strongest Elo the AI can be limited to.
+pub const MAX_ELO: u32 = 2000;
+/// Elo of the greedy baseline, which the limiter's calibration starts
+/// from, on the same scale as the players' ratings.
+pub const GREEDY_ELO: f64 = 400.0;
+
+/// How the AI plays at each calibrated Elo, from the weakest: the depth
+/// it searches, the most positions it visits per move, and how many of
+/// its best moves it picks from and how freely, see [`BlunderModel`].
+///
+/// The Elos were measured with `rChess calibrate`, each level playing
+/// the one below it and the weakest the greedy baseline.
+const CALIBRATION: [(u32, u32, u64, usize, f64); 5] = [
+    (500, 1, 300, 10, 400.0),
+    (800, 1, 1_000, 8, 300.0),
+    (1200, 2, 4_000, 5, 120.0),
+    (1600, 3, 20_000, 5, 200.0),
+    (2000, 3, 40_000, 2, 30.0),
+];
+
+/// Settings holding the AI back to play at about a given Elo, like the
+/// `UCI_LimitStrength` and `UCI_Elo` options of other engines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrengthLimit {
+    pub depth: u32,
+    /// Most positions searched per move.
+    pub nodes: u64,
+    /// How the AI picks among its best moves, `None` to always play the
+    /// best one.
+    pub blunder_model: Option<BlunderModel>,
+}
+
+impl StrengthLimit {
+    /// Returns the limits for playing at about `elo`, clamped to
+    /// [`MIN_ELO`]..=[`MAX_ELO`].
+    ///
+    /// Between the calibrated Elos, the depth and number of candidates
+    /// are those of the weaker level, the node limit grows geometrically
+    /// and the blunder temperature falls linearly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let limit = StrengthLimit::for_elo(1500);
+    /// assert_eq!(limit.depth, 2);
+    /// ```
+    pub fn for_elo(elo: u32) -> Self {
+        let elo = elo.clamp(MIN_ELO, MAX_ELO);
+        let upper = CALIBRATION
+            .iter()
+            .position(|(level, ..)| *level >= elo)
+            .unwrap()
+            .max(1);
+        let (low_elo, depth, low_nodes, candidates, low_temperature) = CALIBRATION[upper - 1];
+        let (high_elo, _, high_nodes, _, high_temperature) = CALIBRATION[upper];
+        if elo == high_elo {
+            return Self::level(CALIBRATION[upper]);
+        }
+        let t = f64::from(elo - low_elo) / f64::from(high_elo - low_elo);
+        let nodes = low_nodes as f64 * (high_nodes as f64 / low_nodes as f64).powf(t);
+        let temperature = low_temperature + t * (high_temperature - low_temperature);
+        Self::level((elo, depth, nodes.round() as u64, candidates, temperature))
+    }
+
+    fn level((_, depth, nodes, candidates, temperature): (u32, u32, u64, usize, f64)) -> Self {
+        Self {
+            depth,
+            nodes,
+            blunder_model: (candidates > 1).then_some(BlunderModel {
+                candidates,
+                temperature,
+            }),
+        }
+    }
+
+    /// Returns the Elos the limiter was calibrated at, from the weakest.
+    pub fn calibrated_elos() -> Vec<u32> {
+        CALIBRATION.iter().map(|(elo, ..)| *elo).collect()
+    }
+
+    /// Returns the search limits of `limits` with this node limit on top.
+    pub fn limit(&self, limits: SearchLimits) -> SearchLimits {
+        SearchLimits {
+            nodes: Some(
+                limits
+                    .nodes
+                    .map_or(self.nodes, |nodes| nodes.min(self.nodes)),
+            ),
+            ..limits
+        }
+    }
+
+    /// Picks a move on `board` the way a player of this strength would.
+    ///
+    /// # Returns
+    ///
+    /// The move with its score in centipawns for the side to move, `None`
+    /// if there are no legal moves.
+    pub fn choose(
+        &self,
+        board: &Board,
+        state: &VariantState,
+        options: &EngineOptions,
+    ) -> Option<(ChessMove, i32)> {
+        let options = EngineOptions {
+            limits: self.limit(options.limits),
+            ..options.clone()
+        };
+        let scored = score_moves(board, state, self.depth, &options);
+        match self.blunder_model {
+            Some(model) => model.choose(&scored),
+            None => scored.first().copied(),
+        }
+    }
+}
+
+/// Returns the Elo a player performed at, scoring `score` of the points
+/// of their games against an opponent rated `opponent`.
+///
+/// A perfect or zero score is counted as 1% short of it, so it gives a
+/// finite rating.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(performance(1200.0, 0.5), 1200.0);
+/// ```
+pub fn performance(opponent: f64, score: f64) -> f64 {
+    let score = score.clamp(0.01, 0.99);
+    opponent + 400.0 * (score / (1.0 - score)).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_elos_to_limits() {
+        let weakest = StrengthLimit::for_elo(0);
+        assert_eq!(weakest, StrengthLimit::for_elo(MIN_ELO));
+        assert_eq!(weakest.depth, 1);
+        assert_eq!(weakest.blunder_model.unwrap().candidates, 10);
+        let between = StrengthLimit::for_elo(1000);
+        assert_eq!(between.depth, 1);
+        assert_eq!(between.nodes, 2_000);
+        assert_eq!(between.blunder_model.unwrap().temperature, 210.0);
+        let strongest = StrengthLimit::for_elo(3000);
+        assert_eq!(strongest.depth, 3);
+        assert_eq!(strongest.nodes, 40_000);
+        assert_eq!(strongest.blunder_model.unwrap().candidates, 2);
+        let limits = SearchLimits {
+            nodes: Some(100),
+            ..SearchLimits::default()
+        };
+        assert_eq!(between.limit(limits).nodes, Some(100));
+
+        let board = Board::default();
+        let chosen = weakest.choose(&board, &VariantState::default(), &EngineOptions::default());
+        assert!(board.legal(chosen.unwrap().0));
+    }
+
+    #[test]
+    fn rates_performances() {
+        assert_eq!(performance(1200.0, 0.5), 1200.0);
+        assert!((performance(400.0, 0.75) - 591.0).abs() < 1.0);
+        assert!(performance(400.0, 1.0) < 400.0 + 800.0);
+        assert!(performance(400.0, 0.0) > 400.0 - 800.0);
+    }
+}
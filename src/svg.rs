@@ -0,0 +1,331 @@
+use crate::ai::{search, EngineOptions};
+use crate::annotate::format_score;
+use chess::{Board, ChessMove, Color, File, Piece, Rank, Square};
+use std::str::FromStr;
+
+/// Size of one square of the rendered board, in pixels.
+const SQUARE_SIZE: u32 = 45;
+/// Depth the engine searches each image to for its best move and
+/// evaluation.
+const ANALYSIS_DEPTH: u32 = 4;
+/// Color of the best-move arrow.
+const ARROW_COLOR: &str = "#15781b";
+
+/// Colors a board image is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub light: &'static str,
+    pub dark: &'static str,
+    /// Color of the squares of the last move.
+    pub highlight: &'static str,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            light: "#f0d9b5",
+            dark: "#b58863",
+            highlight: "#cdd26a",
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "brown" => Ok(Theme::default()),
+            "blue" => Ok(Theme {
+                light: "#dee3e6",
+                dark: "#8ca2ad",
+                highlight: "#9bc700",
+            }),
+            "green" => Ok(Theme {
+                light: "#ffffdd",
+                dark: "#86a666",
+                highlight: "#f6f669",
+            }),
+            "gray" | "grey" => Ok(Theme {
+                light: "#e0e0e0",
+                dark: "#a0a0a0",
+                highlight: "#f0c040",
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How a board image is drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgOptions {
+    pub theme: Theme,
+    /// The side shown at the bottom of the board.
+    pub orientation: Color,
+    /// Whether the squares of the last move are highlighted.
+    pub highlight_last_move: bool,
+    /// Whether the engine's best move is drawn as an arrow.
+    pub best_move_arrow: bool,
+    /// Whether the engine's evaluation is shown in a badge in the top
+    /// right corner.
+    pub evaluation_badge: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            orientation: Color::White,
+            highlight_last_move: false,
+            best_move_arrow: false,
+            evaluation_badge: false,
+        }
+    }
+}
+
+/// Returns the Unicode character a piece is drawn with.
+fn piece_glyph(piece: Piece, color: Color) -> char {
+    match (piece, color) {
+        (Piece::Pawn, Color::White) => '♙',
+        (Piece::Pawn, Color::Black) => '♟',
+        (Piece::Knight, Color::White) => '♘',
+        (Piece::Knight, Color::Black) => '♞',
+        (Piece::Bishop, Color::White) => '♗',
+        (Piece::Bishop, Color::Black) => '♝',
+        (Piece::Rook, Color::White) => '♖',
+        (Piece::Rook, Color::Black) => '♜',
+        (Piece::Queen, Color::White) => '♕',
+        (Piece::Queen, Color::Black) => '♛',
+        (Piece::King, Color::White) => '♔',
+        (Piece::King, Color::Black) => '♚',
+    }
+}
+
+/// Returns the pixel coordinates of the center of `square` on a board
+/// drawn with a margin of `margin` from the side of `orientation`.
+fn square_center(square: Square, orientation: Color, margin: u32) -> (u32, u32) {
+    let (rank, file) = (square.get_rank().to_index(), square.get_file().to_index());
+    let (row, column) = match orientation {
+        Color::White => (7 - rank, file),
+        Color::Black => (rank, 7 - file),
+    };
+    (
+        margin + column as u32 * SQUARE_SIZE + SQUARE_SIZE / 2,
+        margin + row as u32 * SQUARE_SIZE + SQUARE_SIZE / 2,
+    )
+}
+
+/// Draws `board` as an SVG image, with the file and rank names along
+/// its edges.
+///
+/// For instructional diagrams, `options` can ask for the engine's best
+/// move to be drawn as an arrow and its evaluation, from White's side,
+/// shown in a badge in the top right corner; the position is then
+/// searched to [`ANALYSIS_DEPTH`].
+///
+/// # Arguments
+///
+/// * `board` - The position to draw.
+/// * `last_move` - The move that led to the position, highlighted if
+///   `options` ask for it.
+/// * `options` - Colors, orientation, highlighting and overlays of the
+///   image.
+///
+/// # Returns
+///
+/// The SVG document as a string.
+pub fn render_board(board: &Board, last_move: Option<ChessMove>, options: &SvgOptions) -> String {
+    let size = SQUARE_SIZE * 8;
+    let margin = SQUARE_SIZE / 2;
+    let full = size + 2 * margin;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+         width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n\
+         <rect width=\"{0}\" height=\"{0}\" fill=\"{1}\"/>\n",
+        full, options.theme.dark
+    );
+    let highlighted: Vec<Square> = match last_move {
+        Some(mv) if options.highlight_last_move => vec![mv.get_source(), mv.get_dest()],
+        _ => Vec::new(),
+    };
+
+    for row in 0..8 {
+        for column in 0..8 {
+            let (rank, file) = match options.orientation {
+                Color::White => (7 - row, column),
+                Color::Black => (row, 7 - column),
+            };
+            let square = Square::make_square(Rank::from_index(rank), File::from_index(file));
+            let (x, y) = (
+                margin + column as u32 * SQUARE_SIZE,
+                margin + row as u32 * SQUARE_SIZE,
+            );
+            let fill = if highlighted.contains(&square) {
+                options.theme.highlight
+            } else if (rank + file) % 2 == 1 {
+                options.theme.light
+            } else {
+                options.theme.dark
+            };
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\"/>\n",
+                x, y, SQUARE_SIZE, fill
+            ));
+            if let (Some(piece), Some(color)) = (board.piece_on(square), board.color_on(square)) {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"{}\" \
+                     text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+                    x + SQUARE_SIZE / 2,
+                    y + SQUARE_SIZE / 2,
+                    SQUARE_SIZE * 4 / 5,
+                    piece_glyph(piece, color)
+                ));
+            }
+        }
+    }
+
+    let label = |x: u32, y: u32, text: char| {
+        format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\" fill=\"{}\" \
+             text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+            x,
+            y,
+            margin * 2 / 3,
+            options.theme.light,
+            text
+        )
+    };
+    for index in 0..8u32 {
+        let (file, rank) = match options.orientation {
+            Color::White => (index, 7 - index),
+            Color::Black => (7 - index, index),
+        };
+        let center = margin + index * SQUARE_SIZE + SQUARE_SIZE / 2;
+        let file_name = (b'a' + file as u8) as char;
+        let rank_name = (b'1' + rank as u8) as char;
+        svg.push_str(&label(center, full - margin / 2, file_name));
+        svg.push_str(&label(margin / 2, center, rank_name));
+    }
+    if options.best_move_arrow || options.evaluation_badge {
+        let result = search(board, ANALYSIS_DEPTH, &EngineOptions::default());
+        if let (Some(mv), true) = (result.best_move, options.best_move_arrow) {
+            svg.push_str(&arrow(mv, options.orientation, margin));
+        }
+        if options.evaluation_badge {
+            let score = match board.side_to_move() {
+                Color::White => result.score,
+                Color::Black => -result.score,
+            };
+            svg.push_str(&badge(score, full, margin));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Draws `mv` as an arrow from the center of its source square to that
+/// of its destination.
+fn arrow(mv: ChessMove, orientation: Color, margin: u32) -> String {
+    let (x1, y1) = square_center(mv.get_source(), orientation, margin);
+    let (x2, y2) = square_center(mv.get_dest(), orientation, margin);
+    format!(
+        "<defs><marker id=\"arrowhead\" markerWidth=\"4\" markerHeight=\"4\" \
+         refX=\"2.5\" refY=\"2\" orient=\"auto\">\
+         <path d=\"M0,0 L4,2 L0,4 z\" fill=\"{0}\"/></marker></defs>\n\
+         <line x1=\"{1}\" y1=\"{2}\" x2=\"{3}\" y2=\"{4}\" stroke=\"{0}\" \
+         stroke-width=\"{5}\" stroke-opacity=\"0.8\" marker-end=\"url(#arrowhead)\"/>\n",
+        ARROW_COLOR,
+        x1,
+        y1,
+        x2,
+        y2,
+        SQUARE_SIZE / 5
+    )
+}
+
+/// Draws `score`, in centipawns from White's side, as a badge in the top
+/// right corner of an image `full` pixels wide, light when White is
+/// better and dark when Black is.
+fn badge(score: i32, full: u32, margin: u32) -> String {
+    let (fill, text) = if score >= 0 {
+        ("#ffffff", "#000000")
+    } else {
+        ("#000000", "#ffffff")
+    };
+    let width = SQUARE_SIZE * 2;
+    format!(
+        "<rect x=\"{}\" y=\"0\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"{}\"/>\n\
+         <text x=\"{}\" y=\"{}\" font-size=\"{}\" fill=\"{}\" \
+         text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+        full - width,
+        width,
+        margin,
+        fill,
+        full - width / 2,
+        margin / 2,
+        margin * 2 / 3,
+        text,
+        format_score(score)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_pieces_and_highlight() {
+        let mut board = Board::default();
+        let mv = ChessMove::from_str("e2e4").unwrap();
+        board = board.make_move_new(mv);
+        let options = SvgOptions {
+            highlight_last_move: true,
+            ..Default::default()
+        };
+        let svg = render_board(&board, Some(mv), &options);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches('♟').count(), 8);
+        assert_eq!(svg.matches("♔").count(), 1);
+        assert_eq!(svg.matches(options.theme.highlight).count(), 2);
+
+        let plain = render_board(&board, Some(mv), &SvgOptions::default());
+        assert!(!plain.contains(options.theme.highlight));
+        assert!(!plain.contains(ARROW_COLOR));
+    }
+
+    #[test]
+    fn overlays_best_move_and_evaluation() {
+        // White mates in one with Ra8.
+        let board = Board::from_str("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let options = SvgOptions {
+            best_move_arrow: true,
+            evaluation_badge: true,
+            ..Default::default()
+        };
+        let svg = render_board(&board, None, &options);
+        // From the center of a1 to that of a8.
+        assert!(svg.contains("x1=\"44\" y1=\"359\" x2=\"44\" y2=\"44\""));
+        assert!(svg.contains(">#1</text>"));
+    }
+
+    #[test]
+    fn orientation_flips_board() {
+        let board = Board::default();
+        let white = render_board(&board, None, &SvgOptions::default());
+        let black = render_board(
+            &board,
+            None,
+            &SvgOptions {
+                orientation: Color::Black,
+                ..Default::default()
+            },
+        );
+        // The first piece drawn is the top-left one: a black rook from
+        // White's side, a white rook from Black's.
+        let first_piece = |svg: &str| svg.chars().find(|c| "♜♖".contains(*c));
+        assert_eq!(first_piece(&white), Some('♜'));
+        assert_eq!(first_piece(&black), Some('♖'));
+        assert_eq!(Theme::from_str("Blue").unwrap().light, "#dee3e6");
+        assert!(Theme::from_str("purple").is_err());
+    }
+}
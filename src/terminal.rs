@@ -0,0 +1,135 @@
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+/// What the terminal rChess runs in can show.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    /// Whether Unicode chess pieces can be drawn; ASCII letters are
+    /// used otherwise.
+    pub unicode: bool,
+    /// Whether ANSI escape codes color the output; it's plain text
+    /// otherwise.
+    pub ansi: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            unicode: true,
+            ansi: true,
+        }
+    }
+}
+
+static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+
+/// Sets what the terminal can show, once at startup.
+pub fn set_capabilities(capabilities: Capabilities) {
+    let _ = CAPABILITIES.set(capabilities);
+}
+
+/// Returns what the terminal can show, everything unless set otherwise.
+pub fn capabilities() -> Capabilities {
+    CAPABILITIES.get().copied().unwrap_or_default()
+}
+
+/// Tells whether the terminal shows Unicode: on Windows, whether its
+/// console could be switched to UTF-8; elsewhere, whether the locale's
+/// character set is UTF-8, assumed if no locale is set.
+pub fn detect_unicode() -> bool {
+    if cfg!(windows) {
+        return windows::enable_utf8();
+    }
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|locale| !locale.is_empty())
+        .is_none_or(|locale| is_utf8_locale(&locale))
+}
+
+/// Tells whether the locale `locale`, e.g. `pl_PL.UTF-8`, uses UTF-8.
+fn is_utf8_locale(locale: &str) -> bool {
+    let charset = locale.split_once('.').map_or("", |(_, charset)| charset);
+    let charset = charset.split('@').next().unwrap_or_default().to_lowercase();
+    charset == "utf-8" || charset == "utf8"
+}
+
+/// Tells whether output can be colored with ANSI escape codes: it must
+/// go to a terminal that isn't `dumb`, `NO_COLOR` mustn't be set, and on
+/// Windows the console must accept virtual terminal sequences, which are
+/// turned on.
+pub fn detect_ansi() -> bool {
+    if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || env::var("TERM").is_ok_and(|term| term == "dumb")
+        || !io::stdout().is_terminal()
+    {
+        return false;
+    }
+    !cfg!(windows) || windows::enable_virtual_terminal()
+}
+
+/// The Windows console calls, to turn on UTF-8 output and ANSI escape
+/// codes, which older consoles don't have on by default.
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+    const CP_UTF8: u32 = 65001;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+        fn SetConsoleOutputCP(code_page: u32) -> i32;
+    }
+
+    /// Turns on virtual terminal processing of the console, returning
+    /// whether it's on.
+    pub fn enable_virtual_terminal() -> bool {
+        // SAFETY: the handle is only passed back to the console API,
+        // which checks it, and `mode` outlives the call writing it.
+        unsafe {
+            let console = GetStdHandle(STD_OUTPUT_HANDLE);
+            let mut mode = 0;
+            GetConsoleMode(console, &mut mode) != 0
+                && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                    || SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+        }
+    }
+
+    /// Switches the console to UTF-8 output, returning whether it worked.
+    pub fn enable_utf8() -> bool {
+        // SAFETY: takes and returns plain integers.
+        unsafe { SetConsoleOutputCP(CP_UTF8) != 0 }
+    }
+}
+
+/// Stand-ins for other systems, whose terminals take UTF-8 and ANSI
+/// escape codes as they are.
+#[cfg(not(windows))]
+mod windows {
+    pub fn enable_virtual_terminal() -> bool {
+        true
+    }
+
+    pub fn enable_utf8() -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_locale_charset() {
+        assert!(is_utf8_locale("pl_PL.UTF-8"));
+        assert!(is_utf8_locale("de_DE.utf8@euro"));
+        assert!(!is_utf8_locale("C"));
+        assert!(!is_utf8_locale("en_US.ISO-8859-1"));
+    }
+}
@@ -0,0 +1,43 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Tells apart the directories of tests running at the same time.
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory of a test's own to write files in, removed with
+/// everything in it once dropped, even if the test fails.
+///
+/// # Example
+///
+/// ```
+/// let dir = TempDir::new("tt");
+/// table.save(&dir.join("table.hash"))?;
+/// ```
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates an empty directory under the system's temporary
+    /// directory, named after `name`.
+    pub fn new(name: &str) -> Self {
+        let number = NEXT_DIR.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("rchess-{}-{}-{}", name, process::id(), number));
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    /// Returns the path of `file` in the directory.
+    pub fn join(&self, file: &str) -> PathBuf {
+        self.path.join(file)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::practical::Outlook;
+use std::time::Duration;
+
+/// Most moves the time left is spread over, at the start of the game.
+const MAX_MOVES_TO_GO: u32 = 40;
+/// Fewest moves the time left is spread over, however long the game.
+const MIN_MOVES_TO_GO: u32 = 15;
+/// Moves a game is expected to last, used to guess how many are left.
+const EXPECTED_MOVES: u32 = 55;
+/// Share of the increment spent on each move, the rest is saved.
+const INCREMENT_SHARE: f64 = 0.75;
+/// How many times the optimum time a move may take at most.
+const MAX_STRETCH: u32 = 4;
+/// Time kept in reserve so a slow move doesn't lose on time.
+const SAFETY_MARGIN: Duration = Duration::from_millis(200);
+
+/// Time allotted to a move searched on a clock.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeBudget {
+    /// Time a move normally takes. The search starts no new iteration
+    /// once it's spent, stretched by [`TimeBudget::soft_limit`] while the
+    /// best move is unstable.
+    pub optimum: Duration,
+    /// Time after which the search stops, whatever it's doing.
+    pub maximum: Duration,
+}
+
+impl TimeBudget {
+    /// Allots time to move `move_number` of the game with `remaining` on
+    /// the clock and `increment` added after every move, or after the
+    /// last of `moves_to_go` moves before the next time control.
+    ///
+    /// Early moves get less of the remaining time, as more moves are
+    /// still to come, and most of the increment is spent on every move.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let budget = TimeBudget::new(Duration::from_secs(60), Duration::from_secs(1), 10, None);
+    /// assert!(budget.optimum < budget.maximum);
+    /// ```
+    pub fn new(
+        remaining: Duration,
+        increment: Duration,
+        move_number: u32,
+        moves_to_go: Option<u32>,
+    ) -> Self {
+        let moves_to_go = moves_to_go.map_or_else(
+            || {
+                EXPECTED_MOVES
+                    .saturating_sub(move_number)
+                    .clamp(MIN_MOVES_TO_GO, MAX_MOVES_TO_GO)
+            },
+            |moves| moves.clamp(1, MAX_MOVES_TO_GO),
+        );
+        let usable = remaining.saturating_sub(SAFETY_MARGIN);
+        // Never more than a third of the clock on a single move.
+        let maximum = usable / 3;
+        let optimum = usable / moves_to_go + increment.mul_f64(INCREMENT_SHARE);
+        let optimum = optimum.min(maximum);
+        Self {
+            optimum,
+            maximum: (optimum * MAX_STRETCH).min(maximum),
+        }
+    }
+
+    /// Returns the time after which the search starts no new iteration,
+    /// given how unstable its results have been, see [`Instability`]: a
+    /// settled best move and score take less than the optimum time, a
+    /// changing one more, up to the maximum. The time is scaled by the
+    /// [`Outlook`] of the position, so easily won ones are played faster.
+    pub fn soft_limit(&self, instability: &Instability, outlook: Outlook) -> Duration {
+        let factor = (0.6
+            + instability.best_move_changes
+            + f64::from(instability.score_swing.min(300)) / 200.0)
+            * outlook.time_factor();
+        self.optimum
+            .mul_f64(factor.min(f64::from(MAX_STRETCH)))
+            .min(self.maximum)
+    }
+}
+
+/// How much the results of a search's iterations still change: a sign
+/// of a complicated position, worth more time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Instability {
+    /// Number of times the best move changed, recent changes weighing
+    /// more than older ones.
+    pub best_move_changes: f64,
+    /// Change in centipawns of the score over the last iteration.
+    pub score_swing: i32,
+}
+
+impl Instability {
+    /// Records an iteration that changed the best move or not and moved
+    /// the score by `score_swing`.
+    pub fn observe(&mut self, best_move_changed: bool, score_swing: i32) {
+        self.best_move_changes =
+            self.best_move_changes / 2.0 + f64::from(u8::from(best_move_changed));
+        self.score_swing = score_swing.abs();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spends_more_time_later_and_with_increments() {
+        let minute = Duration::from_secs(60);
+        let opening = TimeBudget::new(minute, Duration::ZERO, 1, None);
+        let middlegame = TimeBudget::new(minute, Duration::ZERO, 30, None);
+        assert!(opening.optimum < middlegame.optimum);
+        let increment = TimeBudget::new(minute, Duration::from_secs(2), 30, None);
+        assert!(increment.optimum > middlegame.optimum + Duration::from_secs(1));
+        assert!(increment.optimum <= increment.maximum);
+        let last_move = TimeBudget::new(minute, Duration::ZERO, 30, Some(1));
+        assert_eq!(last_move.maximum, (minute - SAFETY_MARGIN) / 3);
+
+        // Almost out of time, every move is played at once.
+        let flagging = TimeBudget::new(SAFETY_MARGIN, Duration::ZERO, 30, None);
+        assert_eq!(flagging.maximum, Duration::ZERO);
+    }
+
+    #[test]
+    fn thinks_longer_when_unstable() {
+        let budget = TimeBudget::new(Duration::from_secs(60), Duration::ZERO, 20, None);
+        let mut instability = Instability::default();
+        let settled = budget.soft_limit(&instability, Outlook::Balanced);
+        assert!(settled < budget.optimum);
+        assert!(budget.soft_limit(&instability, Outlook::Winning) < settled);
+        instability.observe(true, 80);
+        instability.observe(true, -120);
+        assert_eq!(instability.best_move_changes, 1.5);
+        let unstable = budget.soft_limit(&instability, Outlook::Balanced);
+        assert!(unstable > budget.optimum && unstable <= budget.maximum);
+    }
+}
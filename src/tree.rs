@@ -0,0 +1,196 @@
+use chess::ChessMove;
+
+/// A position visited by the search, reached by `mv` from its parent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeNode {
+    /// The move leading to the position, `None` at the root.
+    pub mv: Option<ChessMove>,
+    /// Remaining search depth after the move.
+    pub depth: u32,
+    /// Score in centipawns from the point of view of the side that
+    /// played `mv`; at the root, from the side to move's.
+    pub score: i32,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    fn new(mv: Option<ChessMove>, depth: u32) -> Self {
+        Self {
+            mv,
+            depth,
+            score: 0,
+            children: Vec::new(),
+        }
+    }
+
+    fn label(&self) -> String {
+        self.mv.map_or("root".to_string(), |mv| mv.to_string())
+    }
+}
+
+/// Records the top plies of a search, to see why the engine chose its
+/// move.
+///
+/// Only the last root search is kept: with iterative deepening, that's
+/// the deepest one, which decides the move.
+#[derive(Debug)]
+pub struct SearchTree {
+    /// Number of plies below the root that are recorded.
+    plies: u32,
+    /// The nodes being searched, from the root down.
+    path: Vec<TreeNode>,
+    /// Number of nodes entered below the recorded plies.
+    hidden: u32,
+    /// Number of nodes recorded in the current root search.
+    recorded: usize,
+    root: Option<TreeNode>,
+}
+
+impl SearchTree {
+    /// Creates a recorder keeping the first `plies` plies of the search.
+    pub fn new(plies: u32) -> Self {
+        Self {
+            plies,
+            path: Vec::new(),
+            hidden: 0,
+            recorded: 0,
+            root: None,
+        }
+    }
+
+    /// Starts recording a new root search to `depth`.
+    pub fn start(&mut self, depth: u32) {
+        self.path = vec![TreeNode::new(None, depth)];
+        self.hidden = 0;
+        self.recorded = 1;
+    }
+
+    /// Records that the search enters the position after `mv`.
+    pub fn enter(&mut self, mv: ChessMove, depth: u32) {
+        if self.hidden > 0 || self.path.len() > self.plies as usize {
+            self.hidden += 1;
+        } else {
+            self.path.push(TreeNode::new(Some(mv), depth));
+            self.recorded += 1;
+        }
+    }
+
+    /// Records that the search of the position last entered is over,
+    /// with `score` for the side that moved into it.
+    pub fn leave(&mut self, score: i32) {
+        if self.hidden > 0 {
+            self.hidden -= 1;
+            return;
+        }
+        let mut node = self.path.pop().unwrap();
+        node.score = score;
+        self.path.last_mut().unwrap().children.push(node);
+    }
+
+    /// Returns roughly how many bytes the nodes of the current root
+    /// search take.
+    pub fn memory(&self) -> usize {
+        self.recorded * size_of::<TreeNode>()
+    }
+
+    /// Ends the root search started last with its `score`.
+    pub fn finish(&mut self, score: i32) {
+        if let Some(mut root) = self.path.pop() {
+            root.score = score;
+            self.root = Some(root);
+        }
+    }
+
+    /// Writes the tree as JSON, each node as an object with its `move`,
+    /// `depth`, `score` and `children`.
+    pub fn to_json(&self) -> String {
+        fn write(node: &TreeNode, json: &mut String) {
+            let mv = node
+                .mv
+                .map_or("null".to_string(), |mv| format!("\"{}\"", mv));
+            json.push_str(&format!(
+                "{{\"move\":{},\"depth\":{},\"score\":{},\"children\":[",
+                mv, node.depth, node.score
+            ));
+            for (i, child) in node.children.iter().enumerate() {
+                if i > 0 {
+                    json.push(',');
+                }
+                write(child, json);
+            }
+            json.push_str("]}");
+        }
+        let mut json = String::new();
+        match &self.root {
+            Some(root) => write(root, &mut json),
+            None => json.push_str("null"),
+        }
+        json.push('\n');
+        json
+    }
+
+    /// Writes the tree as a Graphviz DOT graph, each node labelled with
+    /// its move, score and remaining depth.
+    pub fn to_dot(&self) -> String {
+        fn write(node: &TreeNode, id: &mut u32, dot: &mut String) -> u32 {
+            let own = *id;
+            *id += 1;
+            dot.push_str(&format!(
+                "  n{} [label=\"{}\\n{} (d{})\"];\n",
+                own,
+                node.label(),
+                node.score,
+                node.depth
+            ));
+            for child in &node.children {
+                let child_id = write(child, id, dot);
+                dot.push_str(&format!("  n{} -> n{};\n", own, child_id));
+            }
+            own
+        }
+        let mut dot = String::from("digraph search {\n");
+        if let Some(root) = &self.root {
+            write(root, &mut 0, &mut dot);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn records_top_plies() {
+        let e4 = ChessMove::from_str("e2e4").unwrap();
+        let e5 = ChessMove::from_str("e7e5").unwrap();
+        let nf3 = ChessMove::from_str("g1f3").unwrap();
+        let mut tree = SearchTree::new(2);
+        tree.start(3);
+        tree.enter(e4, 2);
+        tree.enter(e5, 1);
+        tree.enter(nf3, 0);
+        tree.leave(30);
+        tree.leave(-30);
+        tree.leave(30);
+        tree.finish(30);
+
+        let root = tree.root.as_ref().unwrap();
+        assert_eq!(root.children.len(), 1);
+        let reply = &root.children[0].children[0];
+        assert_eq!(reply.mv, Some(e5));
+        assert_eq!(reply.score, -30);
+        assert!(reply.children.is_empty());
+        assert_eq!(
+            tree.to_json(),
+            "{\"move\":null,\"depth\":3,\"score\":30,\"children\":[{\"move\":\"e2e4\",\
+             \"depth\":2,\"score\":30,\"children\":[{\"move\":\"e7e5\",\"depth\":1,\
+             \"score\":-30,\"children\":[]}]}]}\n"
+        );
+        let dot = tree.to_dot();
+        assert!(dot.contains("n1 [label=\"e2e4\\n30 (d2)\"];"));
+        assert!(dot.contains("n1 -> n2;"));
+    }
+}
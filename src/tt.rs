@@ -0,0 +1,284 @@
+use chess::{ChessMove, Piece, Square, ALL_SQUARES};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Size of a table in megabytes unless set otherwise.
+pub const DEFAULT_HASH_MB: usize = 16;
+/// Bytes a saved table starts with, telling it from other files.
+const MAGIC: &[u8; 4] = b"RCTT";
+/// Version of the format tables are saved in.
+const VERSION: u8 = 1;
+/// Bytes of a saved entry: the key, depth, score, bound and move.
+const ENTRY_BYTES: usize = 16;
+
+/// What the score of an [`Entry`] tells about the position's true score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The true score is at least the score: the search failed high.
+    Lower,
+    /// The true score is at most the score: no move raised alpha.
+    Upper,
+}
+
+/// The result of searching a position, kept for when it's reached again.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    /// Hash of the position, see [`Board::get_hash`](chess::Board::get_hash).
+    pub key: u64,
+    /// Depth the position was searched to.
+    pub depth: u32,
+    /// Score in centipawns from the point of view of the side to move,
+    /// mates counted from the position rather than the root.
+    pub score: i32,
+    pub bound: Bound,
+    /// Best move found, tried first when the position is searched again.
+    pub best_move: Option<ChessMove>,
+}
+
+/// A fixed-size table of searched positions, so a position reached
+/// again, in the same search or a later one, needn't be searched again
+/// and its best move is tried first.
+///
+/// Each position has a single slot, picked by its hash; a deeper search
+/// of another position replaces a shallower one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TranspositionTable {
+    /// The slots, a power of two of them.
+    entries: Vec<Option<Entry>>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    /// Creates an empty table taking about `megabytes` of memory.
+    pub fn new(megabytes: usize) -> Self {
+        let slots = (megabytes << 20) / size_of::<Option<Entry>>();
+        // Rounded down to a power of two, so a slot is picked by masking.
+        let slots = match slots {
+            0 => 1,
+            slots => 1 << slots.ilog2(),
+        };
+        Self {
+            entries: vec![None; slots],
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    /// Returns the entry of the position with hash `key`, if it's kept.
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    /// Keeps `entry`, unless its slot holds a deeper search of another
+    /// position.
+    pub fn store(&mut self, entry: Entry) {
+        let index = self.index(entry.key);
+        let slot = &mut self.entries[index];
+        if slot.is_none_or(|old| old.key == entry.key || old.depth <= entry.depth) {
+            *slot = Some(entry);
+        }
+    }
+
+    /// Forgets every position, e.g. before a new game.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// Returns the number of positions kept.
+    pub fn filled(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    /// Returns how many bytes the table takes.
+    pub fn memory(&self) -> usize {
+        self.entries.len() * size_of::<Option<Entry>>()
+    }
+
+    /// Writes the positions kept to `path`, to carry on searching from
+    /// them in a later session, see [`TranspositionTable::load`].
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        for entry in self.entries.iter().flatten() {
+            bytes.extend(entry.key.to_le_bytes());
+            bytes.push(entry.depth.min(u8::MAX.into()) as u8);
+            bytes.extend(entry.score.to_le_bytes());
+            bytes.push(match entry.bound {
+                Bound::Exact => 0,
+                Bound::Lower => 1,
+                Bound::Upper => 2,
+            });
+            bytes.extend(encode_move(entry.best_move).to_le_bytes());
+        }
+        fs::write(path, bytes)
+    }
+
+    /// Reads the positions saved at `path` into a new table of about
+    /// `megabytes`, see [`TranspositionTable::save`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't a saved table.
+    pub fn load(path: &Path, megabytes: usize) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a saved hash table");
+        let bytes = fs::read(path)?;
+        let entries = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .and_then(|rest| rest.strip_prefix(&[VERSION]))
+            .filter(|entries| entries.len() % ENTRY_BYTES == 0)
+            .ok_or_else(invalid)?;
+        let mut table = Self::new(megabytes);
+        for entry in entries.chunks(ENTRY_BYTES) {
+            let bound = match entry[13] {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                2 => Bound::Upper,
+                _ => return Err(invalid()),
+            };
+            table.store(Entry {
+                key: u64::from_le_bytes(entry[0..8].try_into().unwrap()),
+                depth: entry[8].into(),
+                score: i32::from_le_bytes(entry[9..13].try_into().unwrap()),
+                bound,
+                best_move: decode_move(u16::from_le_bytes([entry[14], entry[15]])),
+            });
+        }
+        Ok(table)
+    }
+}
+
+/// Packs a move into 16 bits: the source and destination squares in
+/// six bits each and the promotion piece in the top four, zero for none.
+fn encode_move(mv: Option<ChessMove>) -> u16 {
+    let Some(mv) = mv else {
+        return 0;
+    };
+    let promotion = match mv.get_promotion() {
+        None => 0,
+        Some(piece) => piece.to_index() as u16 + 1,
+    };
+    mv.get_source().to_index() as u16 | (mv.get_dest().to_index() as u16) << 6 | promotion << 12
+}
+
+/// Unpacks a move packed by [`encode_move`].
+fn decode_move(bits: u16) -> Option<ChessMove> {
+    if bits == 0 {
+        return None;
+    }
+    let square = |index: u16| -> Square { ALL_SQUARES[usize::from(index & 63)] };
+    let promotion = match bits >> 12 {
+        0 => None,
+        index => [
+            Piece::Pawn,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Rook,
+            Piece::Queen,
+            Piece::King,
+        ]
+        .get(usize::from(index) - 1)
+        .copied(),
+    };
+    Some(ChessMove::new(square(bits), square(bits >> 6), promotion))
+}
+
+/// A transposition table shared between the searches of a game, kept
+/// across its moves.
+#[derive(Clone, Default)]
+pub struct SharedTable(Arc<Mutex<TranspositionTable>>);
+
+impl SharedTable {
+    pub fn new(table: TranspositionTable) -> Self {
+        Self(Arc::new(Mutex::new(table)))
+    }
+
+    /// Takes the table for a search, waiting for one already using it.
+    pub fn lock(&self) -> MutexGuard<'_, TranspositionTable> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Shows the size of the table rather than its millions of slots.
+impl fmt::Debug for SharedTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let table = self.lock();
+        write!(
+            f,
+            "SharedTable({} of {} bytes filled)",
+            table.filled(),
+            table.memory()
+        )
+    }
+}
+
+/// Shared tables are equal if they're the same table.
+impl PartialEq for SharedTable {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::str::FromStr;
+
+    fn entry(key: u64, depth: u32) -> Entry {
+        Entry {
+            key,
+            depth,
+            score: -35,
+            bound: Bound::Lower,
+            best_move: Some(ChessMove::from_str("e7e8n").unwrap()),
+        }
+    }
+
+    #[test]
+    fn keeps_the_deeper_search() {
+        let mut table = TranspositionTable::new(1);
+        let slots = table.entries.len() as u64;
+        table.store(entry(5, 4));
+        // Another position in the same slot doesn't replace a deeper one.
+        table.store(entry(5 + slots, 2));
+        assert_eq!(table.probe(5), Some(entry(5, 4)));
+        assert_eq!(table.probe(5 + slots), None);
+        table.store(entry(5 + slots, 6));
+        assert_eq!(table.probe(5), None);
+        assert_eq!(table.filled(), 1);
+        table.clear();
+        assert_eq!(table.filled(), 0);
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = TempDir::new("tt");
+        let path = dir.join("table.hash");
+        let mut table = TranspositionTable::new(1);
+        table.store(entry(7, 3));
+        table.store(Entry {
+            best_move: None,
+            bound: Bound::Exact,
+            ..entry(8, 1)
+        });
+        table.save(&path).unwrap();
+        let loaded = TranspositionTable::load(&path, 1).unwrap();
+        assert_eq!(loaded, table);
+        fs::write(&path, "not a table").unwrap();
+        assert!(TranspositionTable::load(&path, 1).is_err());
+    }
+}
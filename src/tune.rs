@@ -0,0 +1,239 @@
+use crate::profile::Outcome;
+use crate::rng::random_bool;
+use crate::selfplay::MatchConfig;
+use std::str::FromStr;
+
+/// Exponent of the decay of the step taken towards the better values
+/// over the iterations.
+const ALPHA: f64 = 0.602;
+/// Exponent of the decay of the perturbations over the iterations.
+const GAMMA: f64 = 0.101;
+
+/// A search parameter tuned by [`Spsa`], named like the engine setting
+/// it changes in a match, see [`MatchConfig::set`]. Values are tuned as
+/// real numbers and rounded when played.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuneParam {
+    pub name: String,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    /// How far the value is perturbed in the last iteration; earlier
+    /// iterations perturb it further.
+    pub step: f64,
+}
+
+impl FromStr for TuneParam {
+    type Err = String;
+
+    /// Reads a parameter written `name=value,min,max,step`, e.g.
+    /// `lmr_min_depth=3,2,6,0.5`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid parameter `{}`, expected name=value,min,max,step",
+                s
+            )
+        };
+        let (name, numbers) = s.split_once('=').ok_or_else(invalid)?;
+        let numbers: Vec<f64> = numbers
+            .split(',')
+            .map(|number| number.trim().parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let [value, min, max, step] = numbers[..] else {
+            return Err(invalid());
+        };
+        if !(min <= value && value <= max && step > 0.0) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name: name.trim().to_string(),
+            value,
+            min,
+            max,
+            step,
+        })
+    }
+}
+
+/// Returns the search parameters tuned by default, at their current
+/// values, see [`SearchParams`](crate::ai::SearchParams).
+pub fn default_params() -> Vec<TuneParam> {
+    [
+        "null_move_reduction=2,1,4,0.5",
+        "lmr_full_depth_moves=3,1,8,1",
+        "lmr_min_depth=3,2,6,0.5",
+        "aspiration_window=150,25,400,25",
+    ]
+    .iter()
+    .map(|param| param.parse().unwrap())
+    .collect()
+}
+
+/// Tunes search parameters by self-play with Simultaneous Perturbation
+/// Stochastic Approximation: every iteration, all parameters are nudged
+/// at random at once, one engine playing with them nudged up and another
+/// with them nudged down, and each moves towards the values of the
+/// engine that scored better. Needs no model of the engine, only many
+/// cheap games.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spsa {
+    pub params: Vec<TuneParam>,
+    pub iterations: u32,
+    /// Pairs of games played per iteration, each from the same opening
+    /// with colors reversed.
+    pub pairs: u32,
+    /// How far the values move towards the better engine's in the last
+    /// iteration, relative to the square of their steps.
+    pub rate: f64,
+}
+
+impl Default for Spsa {
+    fn default() -> Self {
+        Self {
+            params: default_params(),
+            iterations: 100,
+            pairs: 2,
+            rate: 0.002,
+        }
+    }
+}
+
+impl Spsa {
+    /// Runs iteration `k` (counted from 1): nudges every parameter up or
+    /// down at random, lets `play` play the values nudged one way
+    /// against those nudged the other, and moves the values towards the
+    /// better side, within their bounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `k` - The number of the iteration, which the size of the
+    ///   nudges and of the moves decreases with.
+    /// * `play` - Plays the values nudged up against those nudged down,
+    ///   returning the points the first side won minus those it lost.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut spsa = Spsa::default();
+    /// for k in 1..=spsa.iterations {
+    ///     let tuner = spsa.clone();
+    ///     spsa.step(k, |plus, minus| tuner.play(&config, plus, minus, k).unwrap());
+    /// }
+    /// ```
+    pub fn step(&mut self, k: u32, play: impl FnOnce(&[f64], &[f64]) -> i32) -> i32 {
+        let iterations = f64::from(self.iterations.max(k));
+        // The stability constant recommended for SPSA: a tenth of the run.
+        let stability = iterations / 10.0;
+        let k = f64::from(k);
+        let deltas: Vec<f64> = self
+            .params
+            .iter()
+            .map(|_| if random_bool(0.5) { 1.0 } else { -1.0 })
+            .collect();
+        let perturbations: Vec<f64> = self
+            .params
+            .iter()
+            .map(|param| param.step * iterations.powf(GAMMA) / k.powf(GAMMA))
+            .collect();
+        let nudged = |sign: f64| -> Vec<f64> {
+            self.params
+                .iter()
+                .zip(&deltas)
+                .zip(&perturbations)
+                .map(|((param, delta), c)| {
+                    (param.value + sign * c * delta).clamp(param.min, param.max)
+                })
+                .collect()
+        };
+        let result = play(&nudged(1.0), &nudged(-1.0));
+        for ((param, delta), c) in self.params.iter_mut().zip(&deltas).zip(&perturbations) {
+            let a = self.rate * param.step.powi(2) * (stability + iterations).powf(ALPHA)
+                / (stability + k).powf(ALPHA);
+            param.value =
+                (param.value + a / c * f64::from(result) * delta).clamp(param.min, param.max);
+        }
+        result
+    }
+
+    /// Plays [`Spsa::pairs`] pairs of games of the match `config` between
+    /// engine A with the parameters set to `plus` and engine B with them
+    /// set to `minus`, the openings of the suite taken in turn from
+    /// iteration `k` on.
+    ///
+    /// # Returns
+    ///
+    /// A's wins minus its losses.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming a parameter that isn't an engine setting.
+    pub fn play(
+        &self,
+        config: &MatchConfig,
+        plus: &[f64],
+        minus: &[f64],
+        k: u32,
+    ) -> Result<i32, String> {
+        let mut config = config.clone();
+        for ((param, plus), minus) in self.params.iter().zip(plus).zip(minus) {
+            config.set(&format!("a.{}", param.name), &plus.round().to_string())?;
+            config.set(&format!("b.{}", param.name), &minus.round().to_string())?;
+        }
+        let mut result = 0;
+        let first = (k - 1) * self.pairs * 2 + 1;
+        for number in first..first + self.pairs * 2 {
+            let opening = config
+                .opening_index(number)
+                .map(|index| &config.openings[index]);
+            result += match config.play_game(number % 2 == 1, opening).outcome {
+                Outcome::Win => 1,
+                Outcome::Draw => 0,
+                Outcome::Loss => -1,
+            };
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_towards_the_better_side() {
+        let mut spsa = Spsa {
+            params: vec!["depth=3,1,10,1".parse().unwrap()],
+            iterations: 20,
+            pairs: 1,
+            rate: 0.05,
+        };
+        // The side with the larger value always wins.
+        for k in 1..=spsa.iterations {
+            spsa.step(k, |plus, minus| if plus[0] > minus[0] { 2 } else { -2 });
+        }
+        assert!(spsa.params[0].value > 3.5);
+        assert!(spsa.params[0].value <= 10.0);
+        assert!("depth=3,4,10,1".parse::<TuneParam>().is_err());
+        assert!("depth=3,1,10".parse::<TuneParam>().is_err());
+    }
+
+    #[test]
+    fn plays_nudged_engines_against_each_other() {
+        let mut config = MatchConfig::default();
+        config.set("max_plies", "4").unwrap();
+        config.set("a.depth", "1").unwrap();
+        config.set("b.depth", "1").unwrap();
+        let spsa = Spsa {
+            pairs: 1,
+            ..Spsa::default()
+        };
+        let values: Vec<f64> = spsa.params.iter().map(|param| param.value).collect();
+        assert_eq!(spsa.play(&config, &values, &values, 1), Ok(0));
+        let unknown = Spsa {
+            params: vec!["depthh=3,1,10,1".parse().unwrap()],
+            ..Spsa::default()
+        };
+        assert!(unknown.play(&config, &[3.0], &[3.0], 1).is_err());
+    }
+}
@@ -0,0 +1,568 @@
+use crate::ai::{
+    score_moves, search_reporting, search_with_tree, EngineOptions, PositionHistory, SearchLimits,
+    SearchResult, MAX_SKILL,
+};
+use crate::info::uci_score;
+use crate::legality::validate_fen;
+use crate::notation::parse_uci;
+use crate::strength::{StrengthLimit, MAX_ELO, MIN_ELO};
+use crate::timing::TimeBudget;
+use crate::tt::{SharedTable, TranspositionTable, DEFAULT_HASH_MB};
+use crate::variant::VariantState;
+use chess::{Board, Color};
+use log::debug;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Range of the `Contempt` option in centipawns.
+const CONTEMPT_RANGE: (i32, i32) = (-100, 100);
+/// Plies of the search tree written by default when `Debug Tree File` is set.
+const DEFAULT_TREE_PLIES: u32 = 2;
+/// Most plies of the search tree that can be written.
+const MAX_TREE_PLIES: u32 = 8;
+/// Largest `Hash` in megabytes.
+const MAX_HASH_MB: usize = 1024;
+/// Search depth of a `go` limited by nodes or time but not by depth.
+const MAX_LIMITED_DEPTH: u32 = 64;
+/// Default of the `UCI_Elo` option.
+const DEFAULT_ELO: u32 = 1500;
+/// Most moves reported with `MultiPV`.
+const MAX_MULTI_PV: usize = 64;
+/// Largest `Move Overhead` in milliseconds.
+const MAX_MOVE_OVERHEAD_MS: u64 = 5000;
+
+/// State of the engine in UCI mode.
+pub struct Uci {
+    board: Board,
+    /// Number of the move to play, counting from the `fen`'s.
+    move_number: u32,
+    options: EngineOptions,
+    /// Elo the engine is held back to with `UCI_LimitStrength`, see
+    /// [`StrengthLimit`].
+    limit_strength: bool,
+    elo: u32,
+    /// File the search tree of every `go` is written to, as DOT if its
+    /// name ends in `.dot` and as JSON otherwise.
+    tree_file: Option<PathBuf>,
+    tree_plies: u32,
+}
+
+impl Uci {
+    pub fn new() -> Self {
+        Self {
+            board: Board::default(),
+            move_number: 1,
+            options: EngineOptions {
+                table: Some(SharedTable::default()),
+                ..EngineOptions::default()
+            },
+            limit_strength: false,
+            elo: DEFAULT_ELO,
+            tree_file: None,
+            tree_plies: DEFAULT_TREE_PLIES,
+        }
+    }
+
+    /// Handles one command from the GUI, returning the lines to answer with,
+    /// or `None` if the engine should quit.
+    ///
+    /// Unknown commands are ignored, as the protocol requires. The
+    /// engine searches on a single thread and doesn't ponder, so
+    /// `Threads` only allows 1 and `Ponder` is accepted but unused.
+    pub fn handle(&mut self, command: &str) -> Option<Vec<String>> {
+        let mut words = command.split_whitespace();
+        let response = match words.next() {
+            Some("uci") => vec![
+                "id name rChess".to_string(),
+                "id author pawik077".to_string(),
+                format!(
+                    "option name Skill Level type spin default {} min 0 max {}",
+                    MAX_SKILL, MAX_SKILL
+                ),
+                "option name Threads type spin default 1 min 1 max 1".to_string(),
+                format!(
+                    "option name MultiPV type spin default 1 min 1 max {}",
+                    MAX_MULTI_PV
+                ),
+                "option name Ponder type check default false".to_string(),
+                format!(
+                    "option name Move Overhead type spin default {} min 0 max {}",
+                    EngineOptions::default().move_overhead.as_millis(),
+                    MAX_MOVE_OVERHEAD_MS
+                ),
+                format!(
+                    "option name Contempt type spin default {} min {} max {}",
+                    EngineOptions::default().contempt,
+                    CONTEMPT_RANGE.0,
+                    CONTEMPT_RANGE.1
+                ),
+                format!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_MB, MAX_HASH_MB
+                ),
+                "option name Clear Hash type button".to_string(),
+                "option name UCI_LimitStrength type check default false".to_string(),
+                format!(
+                    "option name UCI_Elo type spin default {} min {} max {}",
+                    DEFAULT_ELO, MIN_ELO, MAX_ELO
+                ),
+                "option name Debug Tree File type string default <empty>".to_string(),
+                format!(
+                    "option name Debug Tree Plies type spin default {} min 1 max {}",
+                    DEFAULT_TREE_PLIES, MAX_TREE_PLIES
+                ),
+                "uciok".to_string(),
+            ],
+            Some("isready") => vec!["readyok".to_string()],
+            Some("ucinewgame") => {
+                self.board = Board::default();
+                self.clear_hash();
+                Vec::new()
+            }
+            Some("setoption") => self.set_option(&words.collect::<Vec<_>>()),
+            Some("position") => self.set_position(&words.collect::<Vec<_>>()),
+            Some("go") => self.go(&words.collect::<Vec<_>>()),
+            Some("quit") => return None,
+            _ => Vec::new(),
+        };
+        Some(response)
+    }
+
+    /// Handles `setoption name <name> [value <value>]`.
+    fn set_option(&mut self, args: &[&str]) -> Vec<String> {
+        let value_at = args.iter().position(|word| *word == "value");
+        let name_end = value_at.unwrap_or(args.len());
+        let name = match args {
+            ["name", ..] if name_end > 1 => args[1..name_end].join(" "),
+            _ => {
+                return vec![
+                    "info string expected setoption name <name> [value <value>]".to_string()
+                ]
+            }
+        };
+        let value = value_at
+            .map(|i| args[i + 1..].join(" "))
+            .unwrap_or_default();
+
+        let result = match name.to_lowercase().as_str() {
+            "skill level" => value
+                .parse::<u32>()
+                .map(|skill| self.options.skill = skill.min(MAX_SKILL))
+                .map_err(|_| "invalid value"),
+            "threads" => match value.as_str() {
+                "1" => Ok(()),
+                _ => Err("only one thread is supported"),
+            },
+            "multipv" => value
+                .parse::<usize>()
+                .map(|lines| self.options.multi_pv = lines.clamp(1, MAX_MULTI_PV))
+                .map_err(|_| "invalid value"),
+            "ponder" => match value.as_str() {
+                "true" | "false" => Ok(()),
+                _ => Err("invalid value"),
+            },
+            "move overhead" => value
+                .parse::<u64>()
+                .map(|millis| {
+                    self.options.move_overhead =
+                        Duration::from_millis(millis.min(MAX_MOVE_OVERHEAD_MS))
+                })
+                .map_err(|_| "invalid value"),
+            "contempt" => value
+                .parse::<i32>()
+                .map(|contempt| {
+                    self.options.contempt = contempt.clamp(CONTEMPT_RANGE.0, CONTEMPT_RANGE.1)
+                })
+                .map_err(|_| "invalid value"),
+            "hash" => value
+                .parse::<usize>()
+                .map(|megabytes| {
+                    let table = TranspositionTable::new(megabytes.clamp(1, MAX_HASH_MB));
+                    self.options.table = Some(SharedTable::new(table));
+                })
+                .map_err(|_| "invalid value"),
+            "clear hash" => {
+                self.clear_hash();
+                Ok(())
+            }
+            "uci_limitstrength" => match value.as_str() {
+                "true" | "false" => {
+                    self.limit_strength = value == "true";
+                    Ok(())
+                }
+                _ => Err("invalid value"),
+            },
+            "uci_elo" => value
+                .parse::<u32>()
+                .map(|elo| self.elo = elo.clamp(MIN_ELO, MAX_ELO))
+                .map_err(|_| "invalid value"),
+            "debug tree file" => {
+                self.tree_file = match value.as_str() {
+                    "" | "<empty>" => None,
+                    path => Some(PathBuf::from(path)),
+                };
+                Ok(())
+            }
+            "debug tree plies" => value
+                .parse::<u32>()
+                .map(|plies| self.tree_plies = plies.clamp(1, MAX_TREE_PLIES))
+                .map_err(|_| "invalid value"),
+            _ => Err("unknown option"),
+        };
+        match result {
+            Ok(()) => Vec::new(),
+            Err(e) => vec![format!("info string {}: {}", e, name)],
+        }
+    }
+
+    /// Forgets the positions searched so far, so the next game starts
+    /// cold.
+    fn clear_hash(&self) {
+        if let Some(table) = &self.options.table {
+            table.lock().clear();
+        }
+    }
+
+    /// Handles `position [startpos | fen <fen>] [moves <move>...]`.
+    fn set_position(&mut self, args: &[&str]) -> Vec<String> {
+        let moves_at = args.iter().position(|word| *word == "moves");
+        let setup = &args[..moves_at.unwrap_or(args.len())];
+        let board = match setup {
+            ["startpos"] => Ok(Board::default()),
+            ["fen", fen @ ..] => {
+                validate_fen(&fen.join(" ")).map_err(|e| format!("invalid fen: {}", e))
+            }
+            _ => Err("invalid position".to_string()),
+        };
+        let mut board = match board {
+            Ok(board) => board,
+            Err(e) => return vec![format!("info string {}", e)],
+        };
+        let counter = |i: usize| match setup {
+            ["fen", fen @ ..] => fen.get(i).and_then(|number| number.parse().ok()),
+            _ => None,
+        };
+        let mut move_number = counter(5).unwrap_or(1);
+        let mut history = PositionHistory {
+            positions: Vec::new(),
+            halfmove_clock: counter(4).unwrap_or(0),
+        };
+        for text in moves_at.map_or(&[][..], |i| &args[i + 1..]) {
+            match parse_uci(&board, text) {
+                Ok(mv) => {
+                    if board.side_to_move() == Color::Black {
+                        move_number += 1;
+                    }
+                    history.play(&board, mv);
+                    board = board.make_move_new(mv)
+                }
+                Err(e) => return vec![format!("info string {}", e)],
+            }
+        }
+        self.board = board;
+        self.move_number = move_number;
+        self.options.history = history;
+        Vec::new()
+    }
+
+    /// Runs the search for `go` with `options`, writing the search tree
+    /// to the `Debug Tree File` if one is set.
+    ///
+    /// # Returns
+    ///
+    /// The search result and the `info` lines to answer with.
+    fn run_search(&self, depth: u32, options: &EngineOptions) -> (SearchResult, Vec<String>) {
+        let mut lines = Vec::new();
+        let Some(path) = &self.tree_file else {
+            let result = search_reporting(&self.board, depth, options, &mut lines);
+            return (result, lines);
+        };
+        let (result, tree) =
+            search_with_tree(&self.board, depth, options, self.tree_plies, &mut lines);
+        let text = if path.extension().is_some_and(|extension| extension == "dot") {
+            tree.to_dot()
+        } else {
+            tree.to_json()
+        };
+        let info = match fs::write(path, text) {
+            Ok(()) => format!("info string search tree written to {}", path.display()),
+            Err(e) => format!("info string error writing {}: {}", path.display(), e),
+        };
+        lines.push(info);
+        (result, lines)
+    }
+
+    /// Handles `go`, searching to the given `depth` or the skill level's
+    /// depth. The search stops early after `nodes` positions or
+    /// `movetime` milliseconds, or on a clock after the time allotted
+    /// from `wtime`/`btime`, `winc`/`binc` and `movestogo`, see
+    /// [`TimeBudget`]; with any of them and no `depth`, it goes as deep
+    /// as they allow. With `UCI_LimitStrength`, it plays like the
+    /// [`StrengthLimit`] of `UCI_Elo`, to its depth unless one is given.
+    /// The `Move Overhead` is taken off the clock before allotting time,
+    /// and with a `MultiPV` above 1 every move is scored and the best
+    /// ones reported, without a search tree.
+    fn go(&mut self, args: &[&str]) -> Vec<String> {
+        let value = |name: &str| {
+            args.iter()
+                .position(|word| *word == name)
+                .and_then(|i| args.get(i + 1)?.parse::<u64>().ok())
+        };
+        let (time, increment) = match self.board.side_to_move() {
+            Color::White => ("wtime", "winc"),
+            Color::Black => ("btime", "binc"),
+        };
+        let budget = value(time).map(|remaining| {
+            TimeBudget::new(
+                Duration::from_millis(remaining).saturating_sub(self.options.move_overhead),
+                Duration::from_millis(value(increment).unwrap_or(0)),
+                self.move_number,
+                value("movestogo").map(|moves| moves as u32),
+            )
+        });
+        let limits = SearchLimits {
+            nodes: value("nodes"),
+            time: value("movetime")
+                .map(Duration::from_millis)
+                .or(budget.map(|budget| budget.maximum)),
+            optimum_time: budget.map(|budget| budget.optimum),
+            ..self.options.limits
+        };
+        let strength = self
+            .limit_strength
+            .then(|| StrengthLimit::for_elo(self.elo));
+        let depth = match (value("depth"), strength) {
+            (Some(depth), _) => depth as u32,
+            (None, Some(limit)) => limit.depth,
+            (None, None) if limits.nodes.is_some() || limits.time.is_some() => MAX_LIMITED_DEPTH,
+            (None, None) => self.options.skill_depth(),
+        };
+        let options = EngineOptions {
+            limits,
+            ..self.options.clone()
+        };
+        if let Some(limit) = strength {
+            let limit = StrengthLimit { depth, ..limit };
+            return match limit.choose(&self.board, &VariantState::default(), &options) {
+                Some((mv, score)) => vec![
+                    format!("info depth {} score {} pv {}", depth, uci_score(score), mv),
+                    format!("bestmove {}", mv),
+                ],
+                None => vec!["bestmove 0000".to_string()],
+            };
+        }
+        if options.multi_pv > 1 {
+            let scored = score_moves(&self.board, &VariantState::default(), depth, &options);
+            let mut response: Vec<_> = scored
+                .iter()
+                .take(options.multi_pv)
+                .enumerate()
+                .map(|(i, (mv, score))| {
+                    format!(
+                        "info depth {} multipv {} score {} pv {}",
+                        depth,
+                        i + 1,
+                        uci_score(*score),
+                        mv
+                    )
+                })
+                .collect();
+            response.push(match scored.first() {
+                Some((mv, _)) => format!("bestmove {}", mv),
+                None => "bestmove 0000".to_string(),
+            });
+            return response;
+        }
+        let (result, mut response) = self.run_search(depth, &options);
+        match result.best_move {
+            Some(mv) => response.push(format!("bestmove {}", mv)),
+            None => response.push("bestmove 0000".to_string()),
+        }
+        response
+    }
+}
+
+/// Runs the engine in UCI mode, reading commands from standard input
+/// until `quit` or the end of input.
+pub fn run() {
+    let mut uci = Uci::new();
+    let stdout = io::stdout();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        debug!("<< {}", line);
+        let Some(response) = uci.handle(&line) else {
+            break;
+        };
+        let mut out = stdout.lock();
+        for answer in response {
+            debug!(">> {}", answer);
+            writeln!(out, "{}", answer).unwrap();
+        }
+        out.flush().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn handshake() {
+        let mut uci = Uci::new();
+        let response = uci.handle("uci").unwrap();
+        assert_eq!(response.first().unwrap(), "id name rChess");
+        assert_eq!(response.last().unwrap(), "uciok");
+        assert_eq!(uci.handle("isready").unwrap(), ["readyok"]);
+        assert_eq!(uci.handle("quit"), None);
+    }
+
+    #[test]
+    fn sets_options() {
+        let mut uci = Uci::new();
+        assert!(uci
+            .handle("setoption name Contempt value 30")
+            .unwrap()
+            .is_empty());
+        assert_eq!(uci.options.contempt, 30);
+        uci.handle("setoption name Skill Level value 0");
+        assert_eq!(uci.options.skill_depth(), 1);
+        uci.handle("setoption name Skill Level value 99");
+        assert_eq!(uci.options.skill, MAX_SKILL);
+        assert!(uci
+            .handle("setoption name Threads value 1")
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            uci.handle("setoption name Threads value 4").unwrap(),
+            ["info string only one thread is supported: Threads"]
+        );
+        assert!(uci
+            .handle("setoption name Ponder value true")
+            .unwrap()
+            .is_empty());
+        uci.handle("setoption name Move Overhead value 50");
+        assert_eq!(uci.options.move_overhead, Duration::from_millis(50));
+        assert_eq!(
+            uci.handle("setoption name Hashes value 4").unwrap(),
+            ["info string unknown option: Hashes"]
+        );
+        for malformed in [
+            "setoption",
+            "setoption value 5",
+            "setoption name",
+            "setoption name value 5",
+            "setoption Hash value 4",
+        ] {
+            assert_eq!(
+                uci.handle(malformed).unwrap(),
+                ["info string expected setoption name <name> [value <value>]"],
+                "{}",
+                malformed
+            );
+        }
+        uci.handle("setoption name UCI_LimitStrength value true");
+        uci.handle("setoption name UCI_Elo value 100");
+        assert!(uci.limit_strength);
+        assert_eq!(uci.elo, MIN_ELO);
+        let response = uci.handle("go").unwrap();
+        assert!(response[0].starts_with("info depth 1 score cp"));
+        assert!(response[1].starts_with("bestmove"));
+    }
+
+    #[test]
+    fn reports_multiple_lines() {
+        let mut uci = Uci::new();
+        uci.handle("setoption name MultiPV value 3");
+        uci.handle("position startpos moves f2f3 e7e5 g2g4");
+        let response = uci.handle("go depth 2").unwrap();
+        assert_eq!(response.len(), 4);
+        assert_eq!(response[0], "info depth 2 multipv 1 score mate 1 pv d8h4");
+        assert!(response[2].starts_with("info depth 2 multipv 3 score cp"));
+        assert_eq!(response[3], "bestmove d8h4");
+    }
+
+    #[test]
+    fn keeps_the_hash_until_a_new_game() {
+        let mut uci = Uci::new();
+        let filled = |uci: &Uci| uci.options.table.as_ref().unwrap().lock().filled();
+        uci.handle("setoption name Hash value 1");
+        uci.handle("go depth 3");
+        let after_first = filled(&uci);
+        assert!(after_first > 0);
+        uci.handle("position startpos moves e2e4");
+        uci.handle("go depth 3");
+        assert!(filled(&uci) > after_first);
+        uci.handle("ucinewgame");
+        assert_eq!(filled(&uci), 0);
+    }
+
+    #[test]
+    fn plays_mate_from_position() {
+        let mut uci = Uci::new();
+        uci.handle("position startpos moves f2f3 e7e5 g2g4");
+        let response = uci.handle("go depth 2").unwrap();
+        assert_eq!(response.len(), 3);
+        assert!(response[0].starts_with("info depth 1 seldepth 1 score mate 1"));
+        assert!(response[1].ends_with("pv d8h4"));
+        assert_eq!(response[2], "bestmove d8h4");
+
+        uci.handle("position fen 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert_eq!(uci.handle("go").unwrap(), ["bestmove 0000"]);
+    }
+
+    #[test]
+    fn stops_at_search_limits() {
+        let mut uci = Uci::new();
+        uci.handle("position startpos");
+        let response = uci.handle("go nodes 2000").unwrap();
+        let (bestmove, info) = response.split_last().unwrap();
+        let nodes: u64 = info
+            .last()
+            .unwrap()
+            .split_whitespace()
+            .skip_while(|word| *word != "nodes")
+            .nth(1)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(nodes <= 2000, "{:?}", info);
+        assert!(bestmove.starts_with("bestmove ") && bestmove != "bestmove 0000");
+        let response = uci.handle("go movetime 50").unwrap();
+        assert_ne!(response.last().unwrap(), "bestmove 0000");
+
+        // A second on the clock leaves a fraction of it for the move.
+        uci.handle("position fen 4k3/8/8/8/8/8/8/R3K3 b - - 7 30 moves e8d8 a1a8");
+        assert_eq!(uci.move_number, 31);
+        assert_eq!(uci.options.history.halfmove_clock, 9);
+        let started = std::time::Instant::now();
+        let response = uci
+            .handle("go wtime 60000 btime 1000 winc 0 binc 0")
+            .unwrap();
+        let bestmove = response.last().unwrap();
+        assert!(bestmove.starts_with("bestmove d8"), "{}", bestmove);
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn writes_search_tree() {
+        let dir = TempDir::new("tree");
+        let path = dir.join("tree.json");
+        let mut uci = Uci::new();
+        uci.handle(&format!(
+            "setoption name Debug Tree File value {}",
+            path.display()
+        ));
+        uci.handle("setoption name Debug Tree Plies value 1");
+        uci.handle("position fen 6k1/5ppp/8/8/8/8/8/4R1K1 w - - 0 1");
+        let response = uci.handle("go depth 2").unwrap();
+        assert!(response[0].starts_with("info depth 1"));
+        assert!(response[2].starts_with("info string search tree written to"));
+        assert_eq!(response.last().unwrap(), "bestmove e1e8");
+        let json = fs::read_to_string(&path).unwrap();
+        assert!(json.starts_with("{\"move\":null,\"depth\":2,"));
+        assert!(json.contains("{\"move\":\"e1e8\",\"depth\":1,\"score\":99999,\"children\":[]}"));
+    }
+}
@@ -0,0 +1,159 @@
+use chess::{BitBoard, Board, Color, Piece, Square, EMPTY};
+use std::fmt;
+use std::str::FromStr;
+
+/// Squares the king has to reach to win a King of the Hill game.
+pub const HILL: [Square; 4] = [Square::D4, Square::E4, Square::D5, Square::E5];
+
+/// Number of checks needed to win a Three-check game.
+pub const CHECKS_TO_WIN: u32 = 3;
+/// Every variant rChess plays.
+pub const VARIANTS: [Variant; 3] = [
+    Variant::Standard,
+    Variant::KingOfTheHill,
+    Variant::ThreeCheck,
+];
+
+/// Represents the rule set a game is played with.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// A player also wins by bringing their king to one of the four center squares.
+    KingOfTheHill,
+    /// A player also wins by giving check for the third time.
+    ThreeCheck,
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variant::Standard => write!(f, "Standard"),
+            Variant::KingOfTheHill => write!(f, "King of the Hill"),
+            Variant::ThreeCheck => write!(f, "Three-check"),
+        }
+    }
+}
+
+impl FromStr for Variant {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace([' ', '-', '_'], "").as_str() {
+            "standard" => Ok(Variant::Standard),
+            "kingofthehill" | "koth" => Ok(Variant::KingOfTheHill),
+            "threecheck" | "3check" => Ok(Variant::ThreeCheck),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Variant rule state that the board itself doesn't record.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VariantState {
+    pub variant: Variant,
+    /// Checks given so far, indexed by the color giving them.
+    pub checks_given: [u32; 2],
+}
+
+impl VariantState {
+    /// Creates the state for a game of `variant` that hasn't started yet.
+    pub fn new(variant: Variant) -> Self {
+        Self {
+            variant,
+            checks_given: [0, 0],
+        }
+    }
+
+    /// Returns the state after a move resulting in `board` was played.
+    pub fn after_move(&self, board: &Board) -> Self {
+        let mut state = *self;
+        if *board.checkers() != EMPTY {
+            state.checks_given[(!board.side_to_move()).to_index()] += 1;
+        }
+        state
+    }
+
+    /// Returns the checks given by `color` so far.
+    pub fn checks(&self, color: Color) -> u32 {
+        self.checks_given[color.to_index()]
+    }
+
+    /// Returns the winner by the variant's extra win condition, if any.
+    ///
+    /// Only the player who just moved can have met the condition, and
+    /// checkmate and stalemate are left to the board status.
+    pub fn winner(&self, board: &Board) -> Option<Color> {
+        let mover = !board.side_to_move();
+        let won = match self.variant {
+            Variant::Standard => false,
+            Variant::KingOfTheHill => on_hill(board, mover),
+            Variant::ThreeCheck => self.checks(mover) >= CHECKS_TO_WIN,
+        };
+        won.then_some(mover)
+    }
+}
+
+/// Returns whether the king of `color` stands on one of the [`HILL`] squares.
+pub fn on_hill(board: &Board, color: Color) -> bool {
+    let kings = *board.pieces(Piece::King) & *board.color_combined(color);
+    HILL.iter()
+        .any(|square| kings & BitBoard::from_square(*square) != EMPTY)
+}
+
+/// Returns how many king moves the king of `color` is away from the nearest [`HILL`] square.
+pub fn hill_distance(board: &Board, color: Color) -> u32 {
+    let king = board.king_square(color);
+    HILL.iter()
+        .map(|square| {
+            let files = king
+                .get_file()
+                .to_index()
+                .abs_diff(square.get_file().to_index());
+            let ranks = king
+                .get_rank()
+                .to_index()
+                .abs_diff(square.get_rank().to_index());
+            files.max(ranks) as u32
+        })
+        .min()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn king_of_the_hill_win() {
+        let board = Board::from_str("4k3/8/8/8/4K3/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(
+            VariantState::new(Variant::KingOfTheHill).winner(&board),
+            Some(Color::White)
+        );
+        assert_eq!(VariantState::new(Variant::Standard).winner(&board), None);
+    }
+
+    #[test]
+    fn three_check_counts_checks() {
+        let check = Board::from_str("4k3/8/8/8/8/8/8/4RK2 b - - 0 1").unwrap();
+        let mut state = VariantState::new(Variant::ThreeCheck);
+        for given in 1..=CHECKS_TO_WIN {
+            assert_eq!(state.winner(&check), None);
+            state = state.after_move(&check);
+            assert_eq!(state.checks(Color::White), given);
+        }
+        assert_eq!(state.winner(&check), Some(Color::White));
+    }
+
+    #[test]
+    fn parse_variant_names() {
+        assert_eq!(Variant::from_str("koth"), Ok(Variant::KingOfTheHill));
+        assert_eq!(
+            Variant::from_str("King of the Hill"),
+            Ok(Variant::KingOfTheHill)
+        );
+        assert_eq!(Variant::from_str("three-check"), Ok(Variant::ThreeCheck));
+        assert!(Variant::from_str("crazyhouse").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use chess::ChessMove;
+use std::time::Duration;
+
+/// The moves of a game as a tree: each move played from a position is a
+/// child of the move leading to it, the main continuation first and the
+/// variations after it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MoveTree {
+    /// The move leading to the position, `None` at the root.
+    pub mv: Option<ChessMove>,
+    /// Wall-clock time spent on the move.
+    pub time: Duration,
+    /// Time left on the mover's clock after the move, if the game was
+    /// played on a clock.
+    pub clock: Option<Duration>,
+    /// Text commenting on the move, or at the root on the game.
+    pub comment: String,
+    /// Numeric Annotation Glyphs judging the move or the position after
+    /// it, e.g. `1` for `!`.
+    pub nags: Vec<u8>,
+    pub children: Vec<MoveTree>,
+}
+
+impl MoveTree {
+    /// Returns the node reached by following `path`, the index of the
+    /// child taken at each ply, from this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` leaves the tree.
+    pub fn node(&self, path: &[usize]) -> &MoveTree {
+        path.iter().fold(self, |node, &index| &node.children[index])
+    }
+
+    /// Returns the node at `path` for changing it, see [`MoveTree::node`].
+    pub fn node_mut(&mut self, path: &[usize]) -> &mut MoveTree {
+        path.iter()
+            .fold(self, |node, &index| &mut node.children[index])
+    }
+
+    /// Returns the nodes of the moves along `path`, not counting this one.
+    pub fn line(&self, path: &[usize]) -> Vec<&MoveTree> {
+        let mut node = self;
+        path.iter()
+            .map(|&index| {
+                node = &node.children[index];
+                node
+            })
+            .collect()
+    }
+
+    /// Plays `mv` after the node at `path`: follows the child already
+    /// holding it, or else adds it as a new variation.
+    ///
+    /// # Returns
+    ///
+    /// The index of the child played.
+    pub fn play(&mut self, path: &[usize], mv: ChessMove, time: Duration) -> usize {
+        let node = self.node_mut(path);
+        match node.children.iter().position(|child| child.mv == Some(mv)) {
+            Some(index) => index,
+            None => {
+                node.children.push(MoveTree {
+                    mv: Some(mv),
+                    time,
+                    ..MoveTree::default()
+                });
+                node.children.len() - 1
+            }
+        }
+    }
+
+    /// Removes the node at `path` with all the moves after it.
+    pub fn remove(&mut self, path: &[usize]) {
+        if let Some((&index, parent)) = path.split_last() {
+            self.node_mut(parent).children.remove(index);
+        }
+    }
+
+    /// Returns the moves of the main line: the first child of every node
+    /// from this one on.
+    pub fn main_line(&self) -> Vec<ChessMove> {
+        let mut moves = Vec::new();
+        let mut node = self;
+        while let Some(child) = node.children.first() {
+            moves.extend(child.mv);
+            node = child;
+        }
+        moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn plays_variations_beside_the_main_line() {
+        let mv = |text| ChessMove::from_str(text).unwrap();
+        let mut tree = MoveTree::default();
+        assert_eq!(tree.play(&[], mv("e2e4"), Duration::ZERO), 0);
+        assert_eq!(tree.play(&[0], mv("e7e5"), Duration::ZERO), 0);
+        assert_eq!(tree.play(&[0], mv("c7c5"), Duration::ZERO), 1);
+        // Playing a move again follows it.
+        assert_eq!(tree.play(&[0], mv("c7c5"), Duration::ZERO), 1);
+        assert_eq!(tree.node(&[0, 1]).mv, Some(mv("c7c5")));
+        assert_eq!(tree.main_line(), [mv("e2e4"), mv("e7e5")]);
+        tree.remove(&[0, 0]);
+        assert_eq!(tree.main_line(), [mv("e2e4"), mv("c7c5")]);
+    }
+}